@@ -0,0 +1,36 @@
+#!/usr/bin/env bash
+# publishes the version in the package.json of each pipe of registry.json: tags the last
+# commit that changed the pipe as pipes/<name>/v<version> and records the tag and commit, so
+# `screenpipe pipe install <name>@<version>` can pin and verify it. push the tags with
+# `git push origin --tags` before committing the registry.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+registry=pipes/registry.json
+
+for name in $(jq -r '.pipes | keys[]' "$registry"); do
+  path=$(jq -r --arg name "$name" '.pipes[$name].path // ("pipes/" + $name)' "$registry")
+  if [ ! -f "$path/package.json" ]; then
+    echo "skipping $name, $path has no package.json" >&2
+    continue
+  fi
+  version=$(jq -r '.version' "$path/package.json")
+  if jq -e --arg name "$name" --arg version "$version" \
+    '.pipes[$name].versions[$version].commit' "$registry" >/dev/null; then
+    continue
+  fi
+  if [ -n "$(git status --porcelain -- "$path")" ]; then
+    echo "skipping $name, $path has uncommitted changes" >&2
+    continue
+  fi
+
+  commit=$(git log -1 --format=%H -- "$path")
+  tag="pipes/$name/v$version"
+  git tag "$tag" "$commit"
+  jq --arg name "$name" --arg version "$version" --arg tag "$tag" --arg commit "$commit" \
+    '.pipes[$name].version = $version
+     | .pipes[$name].versions[$version] = {"ref": $tag, "commit": $commit}' \
+    "$registry" >"$registry.tmp"
+  mv "$registry.tmp" "$registry"
+  echo "published $name@$version at $commit"
+done
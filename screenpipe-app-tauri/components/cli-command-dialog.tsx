@@ -95,6 +95,9 @@ export function CliCommandDialog({ settings }: CliCommandDialogProps) {
     if (settings.ocrEngine !== "default") {
       args.push(`--ocr-engine ${settings.ocrEngine}`);
     }
+    if (settings.captureBackend !== "default") {
+      args.push(`--capture-backend ${settings.captureBackend}`);
+    }
     if (
       settings.monitorIds.length > 0 &&
       settings.monitorIds[0] !== "default"
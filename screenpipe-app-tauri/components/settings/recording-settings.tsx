@@ -1088,6 +1088,18 @@ export function RecordingSettings() {
                     <SelectItem value="whisper-tiny-quantized">
                       whisper-tiny-quantized
                     </SelectItem>
+                    <SelectItem value="whisper-base">whisper-base</SelectItem>
+                    <SelectItem value="whisper-base-quantized">
+                      whisper-base-quantized
+                    </SelectItem>
+                    <SelectItem value="whisper-small">whisper-small</SelectItem>
+                    <SelectItem value="whisper-small-quantized">
+                      whisper-small-quantized
+                    </SelectItem>
+                    <SelectItem value="whisper-medium">whisper-medium</SelectItem>
+                    <SelectItem value="whisper-medium-quantized">
+                      whisper-medium-quantized
+                    </SelectItem>
                     <SelectItem value="whisper-large">whisper-large</SelectItem>
                     <SelectItem value="whisper-large-quantized">
                       whisper-large-quantized
@@ -93,6 +93,7 @@ export type Settings = {
 	devMode: boolean;
 	audioTranscriptionEngine: string;
 	ocrEngine: string;
+	captureBackend: string;
 	monitorIds: string[];
 	audioDevices: string[];
 	usePiiRemoval: boolean;
@@ -159,6 +160,7 @@ const DEFAULT_SETTINGS: Settings = {
 	devMode: false,
 	audioTranscriptionEngine: "whisper-large-v3-turbo",
 	ocrEngine: "default",
+	captureBackend: "default",
 	monitorIds: ["default"],
 	audioDevices: ["default"],
 	usePiiRemoval: false,
@@ -171,6 +171,11 @@ fn spawn_sidecar(app: &tauri::AppHandle, override_args: Option<Vec<String>>) ->
         .and_then(|v| v.as_str().map(String::from))
         .unwrap_or(String::from("default"));
 
+    let capture_backend = store
+        .get("captureBackend")
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or(String::from("default"));
+
     let monitor_ids = store
         .get("monitorIds")
         .and_then(|v| v.as_array().cloned())
@@ -304,6 +309,11 @@ fn spawn_sidecar(app: &tauri::AppHandle, override_args: Option<Vec<String>>) ->
         args.push(model);
     }
 
+    if capture_backend != "default" {
+        args.push("--capture-backend");
+        args.push(capture_backend.as_str());
+    }
+
     if !monitor_ids.is_empty() && monitor_ids[0] != Value::String("default".to_string()) {
         for monitor in &monitor_ids {
             args.push("--monitor-id");
@@ -1,15 +1,23 @@
 use anyhow::Result;
-use std::{collections::HashSet, env, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
-use screenpipe_core::Language;
+use screenpipe_core::{Language, WasmPlugins};
 use screenpipe_db::DatabaseManager;
 
 use crate::{
     core::{
         device::{default_input_device, default_output_device},
-        engine::AudioTranscriptionEngine,
+        engine::{AudioTranscriptionEngine, RealtimeTranscriptionEngine},
     },
-    transcription::deepgram::CUSTOM_DEEPGRAM_API_TOKEN,
+    preprocessing::AudioPreprocessing,
+    storage::AudioStorage,
+    transcription::{deepgram::CUSTOM_DEEPGRAM_API_TOKEN, vocabulary::Vocabulary},
     vad::{VadEngineEnum, VadSensitivity},
 };
 
@@ -18,13 +26,28 @@ use crate::audio_manager::AudioManager;
 #[derive(Clone)]
 pub struct AudioManagerOptions {
     pub transcription_engine: Arc<AudioTranscriptionEngine>,
+    /// Engines of devices not transcribed with `transcription_engine`, by device name
+    pub device_transcription_engines: HashMap<String, Arc<AudioTranscriptionEngine>>,
     pub vad_engine: VadEngineEnum,
     pub languages: Vec<Language>,
+    /// Languages of devices not transcribed in `languages`, by device name
+    pub device_languages: HashMap<String, Vec<Language>>,
+    /// Terms transcriptions are biased toward
+    pub vocabulary: Vocabulary,
     pub deepgram_api_key: Option<String>,
     pub enable_diarization: bool,
     pub enable_realtime: bool,
+    pub realtime_transcription_engine: RealtimeTranscriptionEngine,
     pub audio_chunk_duration: Duration,
+    /// Chunk durations of devices not chunked every `audio_chunk_duration`, by device name
+    pub device_audio_chunk_durations: HashMap<String, Duration>,
     pub vad_sensitivity: VadSensitivity,
+    /// Clean up of the audio before it is transcribed
+    pub preprocessing: AudioPreprocessing,
+    /// Format transcribed chunks are kept in
+    pub storage: AudioStorage,
+    /// Filter transcriptions before they are stored
+    pub plugins: Option<Arc<WasmPlugins>>,
     pub health_check_grace_period: u64,
     pub enabled_devices: HashSet<String>,
     pub use_all_devices: bool,
@@ -43,13 +66,21 @@ impl Default for AudioManagerOptions {
         Self {
             output_path: None,
             transcription_engine: Arc::new(AudioTranscriptionEngine::default()),
+            device_transcription_engines: HashMap::new(),
             vad_engine: VadEngineEnum::Silero,
             languages: vec![],
+            device_languages: HashMap::new(),
+            vocabulary: Vocabulary::default(),
             deepgram_api_key,
             enable_diarization: true,
             enable_realtime: false,
+            realtime_transcription_engine: RealtimeTranscriptionEngine::default(),
             audio_chunk_duration: Duration::from_secs(30),
+            device_audio_chunk_durations: HashMap::new(),
             vad_sensitivity: VadSensitivity::High,
+            preprocessing: AudioPreprocessing::default(),
+            storage: AudioStorage::default(),
+            plugins: None,
             health_check_grace_period: 15,
             enabled_devices,
             use_all_devices: false,
@@ -77,6 +108,18 @@ impl AudioManagerBuilder {
         self
     }
 
+    /// Transcribes each device, named like `MacBook Pro Microphone (input)`, with its engine.
+    pub fn device_transcription_engines(
+        mut self,
+        device_transcription_engines: HashMap<String, AudioTranscriptionEngine>,
+    ) -> Self {
+        self.options.device_transcription_engines = device_transcription_engines
+            .into_iter()
+            .map(|(device, engine)| (device, Arc::new(engine)))
+            .collect();
+        self
+    }
+
     pub fn vad_engine(mut self, vad_engine: VadEngineEnum) -> Self {
         self.options.vad_engine = vad_engine;
         self
@@ -87,6 +130,23 @@ impl AudioManagerBuilder {
         self
     }
 
+    /// Transcribes each device, named like `MacBook Pro Microphone (input)`, in its languages.
+    pub fn device_languages(mut self, device_languages: HashMap<String, Vec<Language>>) -> Self {
+        self.options.device_languages = device_languages;
+        self
+    }
+
+    /// Biases transcriptions toward `terms`, and toward the terms of an app while it is
+    /// focused, by app name.
+    pub fn vocabulary(
+        mut self,
+        terms: Vec<String>,
+        app_terms: HashMap<String, Vec<String>>,
+    ) -> Self {
+        self.options.vocabulary = Vocabulary::new(terms, app_terms);
+        self
+    }
+
     pub fn deepgram_api_key(mut self, deepgram_api_key: Option<String>) -> Self {
         self.options.deepgram_api_key = deepgram_api_key;
         self
@@ -102,16 +162,50 @@ impl AudioManagerBuilder {
         self
     }
 
+    /// Streams realtime transcripts from Deepgram, or from the engine of each device.
+    pub fn realtime_transcription_engine(
+        mut self,
+        realtime_transcription_engine: RealtimeTranscriptionEngine,
+    ) -> Self {
+        self.options.realtime_transcription_engine = realtime_transcription_engine;
+        self
+    }
+
     pub fn audio_chunk_duration(mut self, audio_chunk_duration: Duration) -> Self {
         self.options.audio_chunk_duration = audio_chunk_duration;
         self
     }
 
+    /// Cuts the audio of each device, named like `MacBook Pro Microphone (input)`, into
+    /// chunks of its duration.
+    pub fn device_audio_chunk_durations(
+        mut self,
+        device_audio_chunk_durations: HashMap<String, Duration>,
+    ) -> Self {
+        self.options.device_audio_chunk_durations = device_audio_chunk_durations;
+        self
+    }
+
     pub fn vad_sensitivity(mut self, vad_sensitivity: VadSensitivity) -> Self {
         self.options.vad_sensitivity = vad_sensitivity;
         self
     }
 
+    pub fn preprocessing(mut self, preprocessing: AudioPreprocessing) -> Self {
+        self.options.preprocessing = preprocessing;
+        self
+    }
+
+    pub fn storage(mut self, storage: AudioStorage) -> Self {
+        self.options.storage = storage;
+        self
+    }
+
+    pub fn plugins(mut self, plugins: Option<Arc<WasmPlugins>>) -> Self {
+        self.options.plugins = plugins;
+        self
+    }
+
     pub fn health_check_grace_period(mut self, health_check_grace_period: u64) -> Self {
         self.options.health_check_grace_period = health_check_grace_period;
         self
@@ -158,7 +252,10 @@ impl AudioManagerBuilder {
 
     // TODO: Make sure the custom urls work
     pub fn validate_options(&self) -> Result<()> {
-        if self.options.transcription_engine == Arc::new(AudioTranscriptionEngine::Deepgram)
+        let uses_deepgram = std::iter::once(&self.options.transcription_engine)
+            .chain(self.options.device_transcription_engines.values())
+            .any(|engine| **engine == AudioTranscriptionEngine::Deepgram);
+        if uses_deepgram
             && (self.options.deepgram_api_key.is_none() && CUSTOM_DEEPGRAM_API_TOKEN.is_empty())
         {
             return Err(anyhow::anyhow!(
@@ -166,11 +263,17 @@ impl AudioManagerBuilder {
             ));
         }
 
+        let mut device_chunk_durations = self.options.device_audio_chunk_durations.values();
+        if device_chunk_durations.any(|duration| duration.is_zero()) {
+            return Err(anyhow::anyhow!("Audio chunk duration must be positive"));
+        }
+
         if self.options.output_path.is_none() {
             return Err(anyhow::anyhow!("Output path is required for audio manager"));
         }
 
         if self.options.enable_realtime
+            && self.options.realtime_transcription_engine == RealtimeTranscriptionEngine::Deepgram
             && (self.options.deepgram_api_key.is_none() && CUSTOM_DEEPGRAM_API_TOKEN.is_empty())
         {
             return Err(anyhow::anyhow!(
@@ -1,10 +1,19 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Result;
 use tokio::{sync::Mutex, task::JoinHandle, time::sleep};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::{core::device::parse_audio_device, device::device_manager::DeviceManager};
+use crate::{
+    core::device::{
+        default_input_device, default_output_device, parse_audio_device, AudioDevice, DeviceType,
+    },
+    device::device_manager::DeviceManager,
+};
 
 use super::{AudioManager, AudioManagerStatus};
 
@@ -12,6 +21,19 @@ lazy_static::lazy_static! {
   pub static ref DEVICE_MONITOR: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
 }
 
+const DISCONNECTED: &str = "disconnected";
+const RECONNECTED: &str = "reconnected";
+
+/// The default device of the type of `device` when it can stand in for it.
+async fn fallback_device(device: &AudioDevice, available: &[AudioDevice]) -> Option<AudioDevice> {
+    let default = match device.device_type {
+        DeviceType::Input => default_input_device(),
+        DeviceType::Output => default_output_device().await,
+    }
+    .ok()?;
+    (default != *device && available.contains(&default)).then_some(default)
+}
+
 pub async fn start_device_monitor(
     audio_manager: Arc<AudioManager>,
     device_manager: Arc<DeviceManager>,
@@ -20,6 +42,8 @@ pub async fn start_device_monitor(
 
     *DEVICE_MONITOR.lock().await = Some(tokio::spawn(async move {
         let mut disconnected_devices: HashSet<String> = HashSet::new();
+        // devices started while the device they stand in for is gone, by that device
+        let mut fallbacks: HashMap<String, AudioDevice> = HashMap::new();
         loop {
             if audio_manager.status().await == AudioManagerStatus::Running {
                 let currently_available_devices = device_manager.devices().await;
@@ -35,6 +59,19 @@ pub async fn start_device_monitor(
 
                     if audio_manager.start_device(&device).await.is_ok() {
                         disconnected_devices.remove(&device_name);
+                        info!("Device {device_name} reconnected");
+                        let fallback = fallbacks.remove(&device_name);
+                        // unless another device that is still gone records through it
+                        if let Some(fallback) = fallback
+                            .as_ref()
+                            .filter(|fallback| !fallbacks.values().any(|other| other == *fallback))
+                        {
+                            info!("stopping {fallback}, recorded while {device_name} was gone");
+                            let _ = audio_manager.stop_device(&fallback.to_string()).await;
+                        }
+                        audio_manager
+                            .record_device_event(RECONNECTED, &device, fallback.as_ref())
+                            .await;
                     }
                 }
 
@@ -54,6 +91,31 @@ pub async fn start_device_monitor(
 
                         let _ = audio_manager.stop_device(device_name).await;
                         disconnected_devices.insert(device_name.clone());
+
+                        let mut fallback =
+                            fallback_device(&device, &currently_available_devices).await;
+                        if let Some(default) = fallback.clone() {
+                            // the default may be recorded already
+                            let enabled = audio_manager.enabled_devices().await;
+                            if !enabled.contains(&default.to_string()) {
+                                match audio_manager.start_device(&default).await {
+                                    Ok(()) => {
+                                        fallbacks.insert(device_name.clone(), default);
+                                    }
+                                    Err(e) => {
+                                        error!("failed to start {default}: {e}");
+                                        fallback = None;
+                                    }
+                                }
+                            }
+                        }
+                        match &fallback {
+                            Some(fallback) => info!("recording {fallback} instead"),
+                            None => warn!("no device to record instead of {device_name}"),
+                        }
+                        audio_manager
+                            .record_device_event(DISCONNECTED, &device, fallback.as_ref())
+                            .await;
                     } else {
                         if audio_manager.status().await != AudioManagerStatus::Running {
                             break;
@@ -1,8 +1,9 @@
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use dashmap::DashMap;
 use std::{
-    collections::HashSet,
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
     sync::{atomic::Ordering, Arc},
 };
 use tokio::{
@@ -13,21 +14,28 @@ use tokio::{
 use tracing::{error, info, warn};
 use whisper_rs::WhisperContext;
 
+use screenpipe_core::Language;
 use screenpipe_db::DatabaseManager;
 
 use super::{start_device_monitor, stop_device_monitor, AudioManagerOptions};
 use crate::{
     core::{
         device::{parse_audio_device, AudioDevice},
+        engine::{AudioTranscriptionEngine, RealtimeTranscriptionEngine},
         record_and_transcribe,
     },
     device::device_manager::DeviceManager,
     segmentation::segmentation_manager::SegmentationManager,
+    speaker::enrollment::voice_embeddings,
     transcription::{
         deepgram::streaming::stream_transcription_deepgram,
+        deepgram::CUSTOM_DEEPGRAM_API_TOKEN,
         handle_new_transcript,
+        streaming::stream_transcription_local,
         stt::process_audio_input,
-        whisper::model::{create_whisper_context_parameters, download_whisper_model},
+        stt_engine::{create_stt_engine, SttEngine, SttEngines},
+        vocabulary::Vocabulary,
+        whisper::model::{create_whisper_context_parameters, ensure_whisper_model},
     },
     vad::{silero::SileroVad, webrtc::WebRtcVad, VadEngine, VadEngineEnum},
     AudioInput, TranscriptionResult,
@@ -40,6 +48,19 @@ pub enum AudioManagerStatus {
     Stopped,
 }
 
+/// A switch of the transcription engine started with
+/// [`AudioManager::switch_transcription_engine`] that isn't done.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineSwitch {
+    /// Its model is being downloaded or loaded
+    Loading(AudioTranscriptionEngine),
+    /// The engine transcribing audio was kept
+    Failed {
+        engine: AudioTranscriptionEngine,
+        error: String,
+    },
+}
+
 type RecordingHandlesMap = DashMap<AudioDevice, Arc<Mutex<JoinHandle<Result<()>>>>>;
 
 #[derive(Clone)]
@@ -57,7 +78,71 @@ pub struct AudioManager {
     transcription_sender: Arc<crossbeam::channel::Sender<TranscriptionResult>>,
     transcription_receiver_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
     recording_receiver_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
-    stt_model_path: PathBuf,
+    // Downloaded whisper models, by the engine running them
+    stt_model_paths: Arc<RwLock<HashMap<AudioTranscriptionEngine, PathBuf>>>,
+    // Set while audio is transcribed, swapped when the engine changes
+    stt_engines: Arc<RwLock<Option<SttEngines>>>,
+    engine_switch: Arc<RwLock<Option<EngineSwitch>>>,
+}
+
+/// The whisper model `engine` runs, deepgram runs one when a request fails: one of the
+/// `loaded` models if there is one, so a second model isn't kept in memory.
+fn whisper_model_of<'a>(
+    engine: &AudioTranscriptionEngine,
+    mut loaded: impl Iterator<Item = &'a AudioTranscriptionEngine>,
+) -> Option<AudioTranscriptionEngine> {
+    match engine {
+        engine if engine.is_whisper() => Some(engine.clone()),
+        AudioTranscriptionEngine::Deepgram => Some(
+            loaded
+                .find(|model| model.is_whisper())
+                .cloned()
+                .unwrap_or(AudioTranscriptionEngine::WhisperLargeV3TurboQuantized),
+        ),
+        _ => None,
+    }
+}
+
+fn load_whisper_context(
+    model: &AudioTranscriptionEngine,
+    path: &Path,
+) -> Result<Arc<WhisperContext>> {
+    let context_param = create_whisper_context_parameters(Arc::new(model.clone()))?;
+    let whisper_context = WhisperContext::new_with_params(&path.to_string_lossy(), context_param)
+        .map_err(|e| anyhow!("failed to load model {}: {}", model, e))?;
+    Ok(Arc::new(whisper_context))
+}
+
+fn check_deepgram_api_key(
+    engine: &AudioTranscriptionEngine,
+    deepgram_api_key: &Option<String>,
+) -> Result<()> {
+    if *engine == AudioTranscriptionEngine::Deepgram
+        && deepgram_api_key.is_none()
+        && CUSTOM_DEEPGRAM_API_TOKEN.is_empty()
+    {
+        return Err(anyhow!(
+            "Deepgram API key is required for Deepgram transcription engine"
+        ));
+    }
+    Ok(())
+}
+
+/// A backend running `engine` without an audio manager, e.g. to transcribe files. Its
+/// whisper model is downloaded first if needed.
+pub async fn standalone_stt_engine(
+    engine: &AudioTranscriptionEngine,
+    deepgram_api_key: Option<String>,
+) -> Result<Arc<dyn SttEngine>> {
+    check_deepgram_api_key(engine, &deepgram_api_key)?;
+    let whisper_context = match whisper_model_of(engine, std::iter::empty()) {
+        Some(model) => {
+            let path = ensure_whisper_model(&model).await?;
+            Some(load_whisper_context(&model, &path)?)
+        }
+        None => None,
+    };
+    create_stt_engine(engine, whisper_context, deepgram_api_key)
 }
 
 impl AudioManager {
@@ -65,18 +150,35 @@ impl AudioManager {
         let device_manager = DeviceManager::new().await?;
         let segmentation_manager = Arc::new(SegmentationManager::new().await?);
         let status = RwLock::new(AudioManagerStatus::Stopped);
-        let vad_engine: Arc<Mutex<Box<dyn VadEngine + Send>>> = match options.vad_engine {
-            VadEngineEnum::Silero => Arc::new(Mutex::new(Box::new(SileroVad::new().await?))),
-            VadEngineEnum::WebRtc => Arc::new(Mutex::new(Box::new(WebRtcVad::new()))),
+        let mut vad_engine: Box<dyn VadEngine + Send> = match options.vad_engine {
+            VadEngineEnum::Silero => Box::new(SileroVad::new().await?),
+            VadEngineEnum::WebRtc => Box::new(WebRtcVad::new()),
         };
+        vad_engine.set_sensitivity(options.vad_sensitivity);
+        let vad_engine = Arc::new(Mutex::new(vad_engine));
 
         let (recording_sender, recording_receiver) = crossbeam::channel::bounded(1000);
         let (transcription_sender, transcription_receiver) = crossbeam::channel::bounded(1000);
 
         let recording_handles = DashMap::new();
-        let stt_model_path = download_whisper_model(options.transcription_engine.clone())?;
+        let mut stt_model_paths = HashMap::new();
+        let device_engines = options.device_transcription_engines.values();
+        let engines: Vec<&AudioTranscriptionEngine> =
+            std::iter::once(options.transcription_engine.as_ref())
+                .chain(device_engines.map(|engine| engine.as_ref()))
+                .collect();
+        let models = engines
+            .iter()
+            .filter_map(|engine| whisper_model_of(engine, engines.iter().copied()));
+        for model in models {
+            if !stt_model_paths.contains_key(&model) {
+                let path = ensure_whisper_model(&model).await?;
+                stt_model_paths.insert(model, path);
+            }
+        }
 
         whisper_rs::install_logging_hooks();
+        options.vocabulary.track_focused_app();
 
         let manager = Self {
             options: Arc::new(RwLock::new(options)),
@@ -92,7 +194,9 @@ impl AudioManager {
             recording_handles: Arc::new(recording_handles),
             recording_receiver_handle: Arc::new(RwLock::new(None)),
             transcription_receiver_handle: Arc::new(RwLock::new(None)),
-            stt_model_path,
+            stt_model_paths: Arc::new(RwLock::new(stt_model_paths)),
+            stt_engines: Arc::new(RwLock::new(None)),
+            engine_switch: Arc::new(RwLock::new(None)),
         };
 
         Ok(manager)
@@ -157,14 +261,39 @@ impl AudioManager {
     }
 
     pub async fn stop(&self) -> Result<()> {
-        if self.status().await == AudioManagerStatus::Stopped {
-            return Ok(());
+        match self.status().await {
+            AudioManagerStatus::Stopped => return Ok(()),
+            // nothing is recording, [`AudioManager::resume`] just won't start it again
+            AudioManagerStatus::Paused => {
+                *self.status.write().await = AudioManagerStatus::Stopped;
+                return Ok(());
+            }
+            AudioManagerStatus::Running => {}
         }
         *self.status.write().await = AudioManagerStatus::Stopped;
         stop_device_monitor().await?;
         self.stop_internal().await
     }
 
+    /// Stops recording until [`AudioManager::resume`], recording stopped or started in
+    /// between is left as it is.
+    pub async fn pause(&self) -> Result<()> {
+        if self.status().await != AudioManagerStatus::Running {
+            return Ok(());
+        }
+        self.stop_internal().await?;
+        *self.status.write().await = AudioManagerStatus::Paused;
+        Ok(())
+    }
+
+    /// Starts recording again if it's still paused by [`AudioManager::pause`].
+    pub async fn resume(&self) -> Result<()> {
+        if self.status().await != AudioManagerStatus::Paused {
+            return Ok(());
+        }
+        self.start_internal().await
+    }
+
     pub async fn devices(&self) -> Result<Vec<AudioDevice>> {
         let devices = self.device_manager.devices().await;
         Ok(devices)
@@ -237,12 +366,23 @@ impl AudioManager {
     async fn record_device(&self, device: &AudioDevice) -> Result<JoinHandle<Result<()>>> {
         let options = self.options.read().await;
         let stream = self.device_manager.stream(device).unwrap();
-        let audio_chunk_duration = options.audio_chunk_duration;
+        let audio_chunk_duration = options
+            .device_audio_chunk_durations
+            .get(&device.to_string())
+            .copied()
+            .unwrap_or(options.audio_chunk_duration);
         let recording_sender = self.recording_sender.clone();
         let is_running = self.device_manager.is_running_mut(device).unwrap();
-        let languages = options.languages.clone();
+        let languages = options
+            .device_languages
+            .get(&device.to_string())
+            .unwrap_or(&options.languages)
+            .clone();
         let deepgram_api_key = options.deepgram_api_key.clone();
         let realtime_enabled = options.enable_realtime;
+        let realtime_transcription_engine = options.realtime_transcription_engine.clone();
+        let stt_engines = self.stt_engines.clone();
+        let vocabulary = options.vocabulary.clone();
         let device_clone = device.clone();
 
         let recording_handle = tokio::spawn(async move {
@@ -253,15 +393,20 @@ impl AudioManager {
                 is_running.clone(),
             ));
 
-            let realtime_handle = if realtime_enabled {
-                Some(tokio::spawn(stream_transcription_deepgram(
-                    stream,
-                    languages,
-                    is_running,
-                    deepgram_api_key,
-                )))
-            } else {
-                None
+            let realtime_handle = match (realtime_enabled, realtime_transcription_engine) {
+                (false, _) => None,
+                (true, RealtimeTranscriptionEngine::Deepgram) => Some(tokio::spawn(
+                    stream_transcription_deepgram(stream, languages, is_running, deepgram_api_key),
+                )),
+                (true, RealtimeTranscriptionEngine::Local) => {
+                    Some(tokio::spawn(stream_transcription_local(
+                        stream,
+                        languages,
+                        vocabulary,
+                        is_running,
+                        stt_engines,
+                    )))
+                }
             };
 
             let (record_result, realtime_result) = if let Some(handle) = realtime_handle {
@@ -308,21 +453,41 @@ impl AudioManager {
         let options = self.options.read().await;
         let output_path = options.output_path.clone();
         let languages = options.languages.clone();
+        let device_languages = options.device_languages.clone();
+        let vocabulary = options.vocabulary.clone();
+        let preprocessing = options.preprocessing;
+        let storage = options.storage;
         let deepgram_api_key = options.deepgram_api_key.clone();
-        let audio_transcription_engine = options.transcription_engine.clone();
         let vad_engine = self.vad_engine.clone();
         let whisper_receiver = self.recording_receiver.clone();
-        let context_param = create_whisper_context_parameters(audio_transcription_engine.clone())?;
 
-        let quantized_path = self.stt_model_path.clone();
-        let whisper_context = Arc::new(
-            WhisperContext::new_with_params(&quantized_path.to_string_lossy(), context_param)
-                .expect("failed to load model"),
-        );
+        let mut whisper_contexts = HashMap::new();
+        for (model, path) in self.stt_model_paths.read().await.iter() {
+            whisper_contexts.insert(model.clone(), load_whisper_context(model, path)?);
+        }
+        let backend_of = |engine: &Arc<AudioTranscriptionEngine>| {
+            let whisper_context = whisper_model_of(engine, whisper_contexts.keys())
+                .and_then(|model| whisper_contexts.get(&model).cloned());
+            create_stt_engine(engine, whisper_context, deepgram_api_key.clone())
+        };
+        let default_engine = options.transcription_engine.clone();
+        let mut stt_engines = SttEngines::new(default_engine.clone(), backend_of(&default_engine)?);
+        for (device, engine) in &options.device_transcription_engines {
+            stt_engines = stt_engines.with_device(device, engine.clone(), backend_of(engine)?);
+        }
+        *self.stt_engines.write().await = Some(stt_engines);
+        let stt_engines = self.stt_engines.clone();
 
         Ok(tokio::spawn(async move {
             while let Ok(audio) = whisper_receiver.recv() {
                 info!("Received audio from device: {:?}", audio.device.name);
+                let Some(engines) = stt_engines.read().await.clone() else {
+                    continue;
+                };
+                let languages = device_languages
+                    .get(&audio.device.to_string())
+                    .unwrap_or(&languages)
+                    .clone();
                 if let Err(e) = process_audio_input(
                     audio.clone(),
                     vad_engine.clone(),
@@ -330,11 +495,12 @@ impl AudioManager {
                     embedding_manager.clone(),
                     embedding_extractor.clone(),
                     &output_path.clone().unwrap(),
-                    audio_transcription_engine.clone(),
-                    deepgram_api_key.clone(),
-                    languages.clone(),
+                    &engines,
+                    languages,
+                    vocabulary.terms(),
+                    preprocessing,
+                    storage,
                     &transcription_sender.clone(),
-                    whisper_context.clone(),
                 )
                 .await
                 {
@@ -344,14 +510,129 @@ impl AudioManager {
         }))
     }
 
+    /// Voice embeddings of `samples` only one speaker talks in, to enroll that speaker with.
+    pub async fn voice_embeddings(
+        &self,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> Result<Vec<Vec<f32>>> {
+        let segmentation_model_path = self.segmentation_manager.segmentation_model_path.clone();
+        let embedding_extractor = self.segmentation_manager.embedding_extractor.clone();
+        tokio::task::spawn_blocking(move || {
+            voice_embeddings(
+                &samples,
+                sample_rate,
+                segmentation_model_path,
+                embedding_extractor,
+            )
+        })
+        .await?
+    }
+
+    pub async fn transcription_engine(&self) -> Arc<AudioTranscriptionEngine> {
+        self.options.read().await.transcription_engine.clone()
+    }
+
+    /// Languages audio of `device`, named like `MacBook Pro Microphone (input)`, is
+    /// transcribed in.
+    pub async fn languages_of(&self, device: &str) -> Vec<Language> {
+        let options = self.options.read().await;
+        options
+            .device_languages
+            .get(device)
+            .unwrap_or(&options.languages)
+            .clone()
+    }
+
+    pub async fn vocabulary(&self) -> Vocabulary {
+        self.options.read().await.vocabulary.clone()
+    }
+
+    /// A backend running `engine`, its whisper model is downloaded first if needed. Audio
+    /// being recorded keeps being transcribed by the engines it was.
+    pub async fn load_stt_engine(
+        &self,
+        engine: &AudioTranscriptionEngine,
+    ) -> Result<Arc<dyn SttEngine>> {
+        let deepgram_api_key = self.options.read().await.deepgram_api_key.clone();
+        check_deepgram_api_key(engine, &deepgram_api_key)?;
+
+        let model = whisper_model_of(engine, self.stt_model_paths.read().await.keys());
+        let whisper_context = match model {
+            Some(model) => {
+                let known = self.stt_model_paths.read().await.get(&model).cloned();
+                let path = match known {
+                    Some(path) => path,
+                    None => ensure_whisper_model(&model).await?,
+                };
+                let whisper_context = {
+                    let (model, path) = (model.clone(), path.clone());
+                    tokio::task::spawn_blocking(move || load_whisper_context(&model, &path))
+                        .await??
+                };
+                self.stt_model_paths.write().await.insert(model, path);
+                Some(whisper_context)
+            }
+            None => None,
+        };
+        create_stt_engine(engine, whisper_context, deepgram_api_key)
+    }
+
+    /// Transcribes devices without an engine of their own with `engine` from the next chunk
+    /// on, recording goes on meanwhile. Its whisper model is downloaded first if needed.
+    pub async fn set_transcription_engine(&self, engine: AudioTranscriptionEngine) -> Result<()> {
+        let backend = self.load_stt_engine(&engine).await?;
+        let engine = Arc::new(engine);
+
+        self.options.write().await.transcription_engine = engine.clone();
+        let mut stt_engines = self.stt_engines.write().await;
+        if let Some(engines) = stt_engines.take() {
+            *stt_engines = Some(engines.with_default(engine.clone(), backend));
+        }
+        info!("transcribing audio with {}", engine);
+        Ok(())
+    }
+
+    /// Calls [`AudioManager::set_transcription_engine`] in the background, downloading a
+    /// model takes minutes. False when a switch is already loading.
+    pub async fn switch_transcription_engine(&self, engine: AudioTranscriptionEngine) -> bool {
+        let mut switch = self.engine_switch.write().await;
+        if matches!(*switch, Some(EngineSwitch::Loading(_))) {
+            return false;
+        }
+        *switch = Some(EngineSwitch::Loading(engine.clone()));
+        drop(switch);
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let result = manager.set_transcription_engine(engine.clone()).await;
+            *manager.engine_switch.write().await = match result {
+                Ok(()) => None,
+                Err(e) => {
+                    error!("failed to switch transcription engine to {}: {}", engine, e);
+                    Some(EngineSwitch::Failed {
+                        engine,
+                        error: e.to_string(),
+                    })
+                }
+            };
+        });
+        true
+    }
+
+    /// The last switch of the transcription engine, none once it is done.
+    pub async fn engine_switch(&self) -> Option<EngineSwitch> {
+        self.engine_switch.read().await.clone()
+    }
+
     async fn start_transcription_receiver_handler(&self) -> Result<JoinHandle<()>> {
         let transcription_receiver = self.transcription_receiver.clone();
         let db = self.db.clone();
-        let transcription_engine = self.options.read().await.transcription_engine.clone();
+        let plugins = self.options.read().await.plugins.clone();
         Ok(tokio::spawn(handle_new_transcript(
             db,
             transcription_receiver,
-            transcription_engine,
+            plugins,
         )))
     }
 
@@ -386,6 +667,29 @@ impl AudioManager {
     pub async fn enabled_devices(&self) -> HashSet<String> {
         self.options.read().await.enabled_devices.clone()
     }
+
+    /// Puts `device` disconnecting or coming back on the timeline, along with the device
+    /// recorded while it was gone.
+    pub(crate) async fn record_device_event(
+        &self,
+        event: &str,
+        device: &AudioDevice,
+        fallback_device: Option<&AudioDevice>,
+    ) {
+        let fallback_device = fallback_device.map(|device| device.to_string());
+        if let Err(e) = self
+            .db
+            .insert_audio_device_event(
+                event,
+                &device.to_string(),
+                fallback_device.as_deref(),
+                Utc::now(),
+            )
+            .await
+        {
+            warn!("failed to record that {} {}: {}", device, event, e);
+        }
+    }
 }
 
 impl Drop for AudioManager {
@@ -251,6 +251,17 @@ pub async fn list_audio_devices() -> Result<Vec<AudioDevice>> {
         }
     }
 
+    // alsa can't record output devices, pulseaudio and pipewire can through their monitors
+    #[cfg(target_os = "linux")]
+    match super::pulse::list_monitor_sources() {
+        Ok(monitors) => {
+            for name in monitors {
+                devices.push(AudioDevice::new(name, DeviceType::Output));
+            }
+        }
+        Err(e) => tracing::debug!("no pulseaudio monitor sources: {}", e),
+    }
+
     // last, add devices that are listed in .devices() which are not already in the devices vector
     let other_devices = host.devices().unwrap();
     for device in other_devices {
@@ -294,6 +305,11 @@ pub async fn default_output_device() -> Result<AudioDevice> {
         return Ok(AudioDevice::new(device.name()?, DeviceType::Output));
     }
 
+    #[cfg(target_os = "linux")]
+    if let Ok(name) = super::pulse::default_monitor_source() {
+        return Ok(AudioDevice::new(name, DeviceType::Output));
+    }
+
     #[cfg(not(target_os = "macos"))]
     {
         let host = cpal::default_host();
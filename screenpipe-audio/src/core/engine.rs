@@ -1,15 +1,46 @@
 use std::fmt;
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub enum AudioTranscriptionEngine {
     Deepgram,
     WhisperTiny,
     WhisperTinyQuantized,
+    WhisperBase,
+    WhisperBaseQuantized,
+    WhisperSmall,
+    WhisperSmallQuantized,
+    WhisperMedium,
+    WhisperMediumQuantized,
     #[default]
     WhisperLargeV3Turbo,
     WhisperLargeV3TurboQuantized,
     WhisperLargeV3,
     WhisperLargeV3Quantized,
+    /// On-device dictation of the Speech framework, macOS only
+    AppleNative,
+    /// An engine registered at runtime through `stt_engine::register_stt_engine`
+    Provider(String),
+}
+
+impl AudioTranscriptionEngine {
+    /// Whether it runs a local whisper model, which has to be downloaded first.
+    pub fn is_whisper(&self) -> bool {
+        matches!(
+            self,
+            AudioTranscriptionEngine::WhisperTiny
+                | AudioTranscriptionEngine::WhisperTinyQuantized
+                | AudioTranscriptionEngine::WhisperBase
+                | AudioTranscriptionEngine::WhisperBaseQuantized
+                | AudioTranscriptionEngine::WhisperSmall
+                | AudioTranscriptionEngine::WhisperSmallQuantized
+                | AudioTranscriptionEngine::WhisperMedium
+                | AudioTranscriptionEngine::WhisperMediumQuantized
+                | AudioTranscriptionEngine::WhisperLargeV3Turbo
+                | AudioTranscriptionEngine::WhisperLargeV3TurboQuantized
+                | AudioTranscriptionEngine::WhisperLargeV3
+                | AudioTranscriptionEngine::WhisperLargeV3Quantized
+        )
+    }
 }
 
 impl fmt::Display for AudioTranscriptionEngine {
@@ -18,6 +49,14 @@ impl fmt::Display for AudioTranscriptionEngine {
             AudioTranscriptionEngine::Deepgram => write!(f, "Deepgram"),
             AudioTranscriptionEngine::WhisperTiny => write!(f, "WhisperTiny"),
             AudioTranscriptionEngine::WhisperTinyQuantized => write!(f, "WhisperTinyQuantized"),
+            AudioTranscriptionEngine::WhisperBase => write!(f, "WhisperBase"),
+            AudioTranscriptionEngine::WhisperBaseQuantized => write!(f, "WhisperBaseQuantized"),
+            AudioTranscriptionEngine::WhisperSmall => write!(f, "WhisperSmall"),
+            AudioTranscriptionEngine::WhisperSmallQuantized => write!(f, "WhisperSmallQuantized"),
+            AudioTranscriptionEngine::WhisperMedium => write!(f, "WhisperMedium"),
+            AudioTranscriptionEngine::WhisperMediumQuantized => {
+                write!(f, "WhisperMediumQuantized")
+            }
             AudioTranscriptionEngine::WhisperLargeV3 => write!(f, "WhisperLargeV3"),
             AudioTranscriptionEngine::WhisperLargeV3Quantized => {
                 write!(f, "WhisperLargeV3Quantized")
@@ -26,6 +65,27 @@ impl fmt::Display for AudioTranscriptionEngine {
             AudioTranscriptionEngine::WhisperLargeV3TurboQuantized => {
                 write!(f, "WhisperLargeV3TurboQuantized")
             }
+            AudioTranscriptionEngine::AppleNative => write!(f, "AppleNative"),
+            AudioTranscriptionEngine::Provider(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Where realtime transcripts come from.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum RealtimeTranscriptionEngine {
+    /// The Deepgram streaming api
+    #[default]
+    Deepgram,
+    /// The engine transcribing the device, run again on the utterance every second
+    Local,
+}
+
+impl fmt::Display for RealtimeTranscriptionEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RealtimeTranscriptionEngine::Deepgram => write!(f, "Deepgram"),
+            RealtimeTranscriptionEngine::Local => write!(f, "Local"),
         }
     }
 }
@@ -1,5 +1,7 @@
 pub mod device;
 pub mod engine;
+#[cfg(target_os = "linux")]
+pub mod pulse;
 mod run_record_and_transcribe;
 pub mod stream;
 use crate::transcription::deepgram::streaming::stream_transcription_deepgram;
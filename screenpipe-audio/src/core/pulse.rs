@@ -0,0 +1,114 @@
+//! System audio on linux. alsa can't record what plays through an output device, pulseaudio
+//! and pipewire expose it as a `<sink>.monitor` source that `parec` records.
+use anyhow::{anyhow, Result};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::transcription::stt::SAMPLE_RATE;
+
+const MONITOR_SUFFIX: &str = ".monitor";
+// 100ms of mono f32 samples per read
+const CHUNK_BYTES: usize = SAMPLE_RATE as usize / 10 * 4;
+
+pub fn is_monitor_source(name: &str) -> bool {
+    name.ends_with(MONITOR_SUFFIX)
+}
+
+/// The monitor sources in the output of `pactl list short sources`, one
+/// `<index>\t<name>\t<driver>\t<sample spec>\t<state>` line per source.
+pub fn parse_monitor_sources(sources: &str) -> Vec<String> {
+    sources
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .map(str::trim)
+        .filter(|name| is_monitor_source(name))
+        .map(str::to_string)
+        .collect()
+}
+
+fn pactl(args: &[&str]) -> Result<String> {
+    let output = Command::new("pactl").args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "pactl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn list_monitor_sources() -> Result<Vec<String>> {
+    let sources = pactl(&["list", "short", "sources"])?;
+    Ok(parse_monitor_sources(&sources))
+}
+
+/// The monitor of the sink audio currently plays through.
+pub fn default_monitor_source() -> Result<String> {
+    let sink = match pactl(&["get-default-sink"]) {
+        Ok(sink) => sink.trim().to_string(),
+        // pulseaudio before 15.0 only reports it in `pactl info`
+        Err(_) => pactl(&["info"])?
+            .lines()
+            .find_map(|line| line.strip_prefix("Default Sink:"))
+            .map(|sink| sink.trim().to_string())
+            .unwrap_or_default(),
+    };
+    if sink.is_empty() {
+        return Err(anyhow!("no default sink"));
+    }
+    Ok(format!("{}{}", sink, MONITOR_SUFFIX))
+}
+
+/// Records `source` as mono f32 at [`SAMPLE_RATE`] until the returned process is killed.
+/// `is_disconnected` is set when the recording ends on its own.
+pub fn spawn_monitor_capture(
+    source: &str,
+    tx: broadcast::Sender<Vec<f32>>,
+    is_disconnected: Arc<AtomicBool>,
+) -> Result<Arc<Mutex<Child>>> {
+    let mut child = Command::new("parec")
+        .arg(format!("--device={}", source))
+        .arg("--format=float32le")
+        .arg(format!("--rate={}", SAMPLE_RATE))
+        .arg("--channels=1")
+        .arg("--latency-msec=100")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("failed to start parec: {}", e))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("parec has no stdout"))?;
+    let child = Arc::new(Mutex::new(child));
+
+    let source = source.to_string();
+    std::thread::spawn(move || {
+        let mut buffer = vec![0u8; CHUNK_BYTES];
+        loop {
+            match stdout.read_exact(&mut buffer) {
+                Ok(()) => {
+                    let samples = buffer
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect();
+                    let _ = tx.send(samples);
+                }
+                Err(e) => {
+                    if !is_disconnected.swap(true, Ordering::Relaxed) {
+                        warn!("recording of {} ended: {}", source, e);
+                    }
+                    break;
+                }
+            }
+        }
+        debug!("stopped reading {}", source);
+    });
+
+    Ok(child)
+}
@@ -10,6 +10,8 @@ use tracing::{error, warn};
 
 use crate::utils::audio::audio_to_mono;
 
+#[cfg(target_os = "linux")]
+use super::device::DeviceType;
 use super::device::{get_cpal_device_and_config, AudioDevice};
 
 #[derive(Clone)]
@@ -33,6 +35,12 @@ impl AudioStream {
     ) -> Result<Self> {
         let (tx, _) = broadcast::channel::<Vec<f32>>(1000);
         let tx_clone = tx.clone();
+        #[cfg(target_os = "linux")]
+        if device.device_type == DeviceType::Output && super::pulse::is_monitor_source(&device.name)
+        {
+            return Self::from_monitor_source(device, tx);
+        }
+        // on windows cpal records output devices through wasapi loopback
         let (cpal_audio_device, config) = get_cpal_device_and_config(&device).await?;
         let channels = config.channels();
 
@@ -62,6 +70,41 @@ impl AudioStream {
         })
     }
 
+    /// Records what plays through a pulseaudio or pipewire sink from its monitor source.
+    #[cfg(target_os = "linux")]
+    fn from_monitor_source(
+        device: Arc<AudioDevice>,
+        tx: broadcast::Sender<Vec<f32>>,
+    ) -> Result<Self> {
+        let is_disconnected = Arc::new(AtomicBool::new(false));
+        let child =
+            super::pulse::spawn_monitor_capture(&device.name, tx.clone(), is_disconnected.clone())?;
+        let (stream_control_tx, stream_control_rx) = mpsc::channel();
+
+        let stream_thread = tokio::task::spawn_blocking(move || {
+            if let Ok(StreamControl::Stop(response)) = stream_control_rx.recv() {
+                let mut child = child.lock().unwrap_or_else(|e| e.into_inner());
+                child.kill().ok();
+                child.wait().ok();
+                response.send(()).ok();
+            }
+        });
+
+        Ok(AudioStream {
+            device,
+            device_config: cpal::SupportedStreamConfig::new(
+                1,
+                cpal::SampleRate(crate::transcription::stt::SAMPLE_RATE),
+                cpal::SupportedBufferSize::Unknown,
+                cpal::SampleFormat::F32,
+            ),
+            transmitter: Arc::new(tx),
+            stream_control: stream_control_tx,
+            stream_thread: Some(Arc::new(tokio::sync::Mutex::new(Some(stream_thread)))),
+            is_disconnected,
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn spawn_audio_thread(
         device: cpal::Device,
@@ -1,6 +1,8 @@
 pub mod core;
 mod utils;
 pub mod vad;
+pub mod preprocessing;
+pub mod storage;
 pub use transcription::stt::stt;
 pub use transcription::{AudioInput, TranscriptionResult};
 pub mod speaker;
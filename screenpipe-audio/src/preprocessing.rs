@@ -0,0 +1,82 @@
+//! Audio clean up before speech detection and transcription, each stage can be turned off.
+use anyhow::Result;
+use nnnoiseless::DenoiseState;
+use tracing::warn;
+
+use crate::utils::audio::{normalize_v2, resample};
+
+// RNNoise works on 10ms frames of 48kHz audio in the range of i16 samples
+const RNNOISE_SAMPLE_RATE: u32 = 48000;
+const RNNOISE_SCALE: f32 = i16::MAX as f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioPreprocessing {
+    /// Removes steady noise like fans and keyboard clatter with RNNoise
+    pub noise_suppression: bool,
+    /// Brings quiet and loud recordings to the same loudness
+    pub normalization: bool,
+}
+
+impl Default for AudioPreprocessing {
+    fn default() -> Self {
+        Self {
+            noise_suppression: false,
+            normalization: true,
+        }
+    }
+}
+
+impl AudioPreprocessing {
+    /// Runs the enabled stages on mono `audio`, noise suppression first. Audio that noise
+    /// suppression fails on is passed on as it is.
+    pub fn apply(&self, audio: &[f32], sample_rate: u32) -> Vec<f32> {
+        let mut audio = audio.to_vec();
+        if self.noise_suppression {
+            match suppress_noise(&audio, sample_rate) {
+                Ok(denoised) => audio = denoised,
+                Err(e) => warn!("noise suppression failed, transcribing as is: {}", e),
+            }
+        }
+        if self.normalization {
+            audio = normalize_v2(&audio);
+        }
+        audio
+    }
+}
+
+/// `audio` with RNNoise run over it, as long as it was.
+pub fn suppress_noise(audio: &[f32], sample_rate: u32) -> Result<Vec<f32>> {
+    if audio.is_empty() {
+        return Ok(Vec::new());
+    }
+    let input = if sample_rate != RNNOISE_SAMPLE_RATE {
+        resample(audio, sample_rate, RNNOISE_SAMPLE_RATE)?
+    } else {
+        audio.to_vec()
+    };
+
+    let mut state = DenoiseState::new();
+    let mut frame = [0.0f32; DenoiseState::FRAME_SIZE];
+    let mut denoised_frame = [0.0f32; DenoiseState::FRAME_SIZE];
+    let mut denoised = Vec::with_capacity(input.len() + DenoiseState::FRAME_SIZE);
+    for chunk in input.chunks(DenoiseState::FRAME_SIZE) {
+        frame.fill(0.0);
+        for (sample, input) in frame.iter_mut().zip(chunk) {
+            *sample = input * RNNOISE_SCALE;
+        }
+        state.process_frame(&mut denoised_frame, &frame);
+        denoised.extend(
+            denoised_frame[..chunk.len()]
+                .iter()
+                .map(|sample| sample / RNNOISE_SCALE),
+        );
+    }
+
+    let mut denoised = if sample_rate != RNNOISE_SAMPLE_RATE {
+        resample(&denoised, RNNOISE_SAMPLE_RATE, sample_rate)?
+    } else {
+        denoised
+    };
+    denoised.resize(audio.len(), 0.0);
+    Ok(denoised)
+}
@@ -0,0 +1,42 @@
+use super::{
+    embedding::EmbeddingExtractor, embedding_manager::EmbeddingManager, segment::get_segments,
+};
+use crate::{transcription::stt::SAMPLE_RATE, utils::audio::resample};
+use anyhow::{anyhow, Result};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex as StdMutex},
+};
+
+/// Voice embeddings of a sample only one speaker talks in, one per stretch of speech, to
+/// enroll that speaker with.
+pub fn voice_embeddings<P: AsRef<Path>>(
+    samples: &[f32],
+    sample_rate: u32,
+    segmentation_model_path: P,
+    embedding_extractor: Arc<StdMutex<EmbeddingExtractor>>,
+) -> Result<Vec<Vec<f32>>> {
+    let samples = if sample_rate != SAMPLE_RATE {
+        resample(samples, sample_rate, SAMPLE_RATE)?
+    } else {
+        samples.to_vec()
+    };
+    let segments = get_segments(
+        &samples,
+        SAMPLE_RATE,
+        segmentation_model_path,
+        embedding_extractor,
+        EmbeddingManager::new(usize::MAX),
+    )?
+    .collect::<Result<Vec<_>>>()?;
+
+    let embeddings: Vec<Vec<f32>> = segments
+        .into_iter()
+        .map(|segment| segment.embedding)
+        .filter(|embedding| !embedding.is_empty())
+        .collect();
+    if embeddings.is_empty() {
+        return Err(anyhow!("no speech found in the sample"));
+    }
+    Ok(embeddings)
+}
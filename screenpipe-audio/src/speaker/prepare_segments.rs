@@ -1,12 +1,13 @@
 use super::segment::get_segments;
 use crate::{
-    utils::audio::{average_noise_spectrum, normalize_v2, spectral_subtraction},
-    vad::VadEngine,
+    preprocessing::AudioPreprocessing,
+    utils::audio::{average_noise_spectrum, spectral_subtraction},
+    vad::{is_silent, VadEngine},
 };
 use anyhow::Result;
 use std::{path::PathBuf, sync::Arc, sync::Mutex as StdMutex};
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 use vad_rs::VadStatus;
 
 use super::{
@@ -20,8 +21,15 @@ pub async fn prepare_segments(
     embedding_manager: EmbeddingManager,
     embedding_extractor: Arc<StdMutex<EmbeddingExtractor>>,
     device: &str,
+    preprocessing: AudioPreprocessing,
 ) -> Result<(tokio::sync::mpsc::Receiver<SpeechSegment>, bool)> {
-    let audio_data = normalize_v2(audio_data);
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    // checked before normalizing, which would bring the noise floor up to speech level
+    if is_silent(audio_data) {
+        debug!("device: {}, silent audio, skipping vad", device);
+        return Ok((rx, false));
+    }
+    let audio_data = preprocessing.apply(audio_data, 16000);
 
     let frame_size = 1600;
     let vad_engine = vad_engine.clone();
@@ -65,7 +73,6 @@ pub async fn prepare_segments(
 
     let threshold_met = speech_ratio > min_speech_ratio;
 
-    let (tx, rx) = tokio::sync::mpsc::channel(100);
     if !audio_frames.is_empty() && threshold_met {
         let segments = get_segments(
             &audio_data,
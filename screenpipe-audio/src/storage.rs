@@ -0,0 +1,96 @@
+//! How audio is kept once it is transcribed, or that only its transcript is.
+
+// Bitrates ffmpeg's aac and opus encoders take
+const MIN_BITRATE_KBPS: u32 = 8;
+const MAX_BITRATE_KBPS: u32 = 320;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AudioFormat {
+    /// AAC in mp4
+    #[default]
+    Aac,
+    /// Opus in ogg, smallest at the same quality
+    Opus,
+    /// Lossless, about half the size of wav
+    Flac,
+    /// Uncompressed
+    Wav,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Aac => "mp4",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    /// Whether the format takes a bitrate, lossless ones keep every sample.
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, AudioFormat::Aac | AudioFormat::Opus)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioStorage {
+    pub format: AudioFormat,
+    /// Of lossy formats
+    pub bitrate_kbps: u32,
+    /// Writes no audio files, chunks are only transcribed
+    pub transcript_only: bool,
+}
+
+impl Default for AudioStorage {
+    fn default() -> Self {
+        Self {
+            format: AudioFormat::Aac,
+            bitrate_kbps: 64,
+            transcript_only: false,
+        }
+    }
+}
+
+impl AudioStorage {
+    pub fn new(
+        format: AudioFormat,
+        bitrate_kbps: u32,
+        transcript_only: bool,
+    ) -> Result<Self, String> {
+        if !(MIN_BITRATE_KBPS..=MAX_BITRATE_KBPS).contains(&bitrate_kbps) {
+            return Err(format!(
+                "audio bitrate must be between {} and {} kbps, got {}",
+                MIN_BITRATE_KBPS, MAX_BITRATE_KBPS, bitrate_kbps
+            ));
+        }
+        Ok(Self {
+            format,
+            bitrate_kbps,
+            transcript_only,
+        })
+    }
+
+    /// The ffmpeg output options encoding to the format, before the output path.
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let bitrate = format!("{}k", self.bitrate_kbps);
+        let args: Vec<&str> = match self.format {
+            AudioFormat::Aac => vec![
+                "-c:a",
+                "aac",
+                "-b:a",
+                &bitrate,
+                "-profile:a",
+                "aac_low", // Use AAC-LC profile for better compatibility
+                "-movflags",
+                "+faststart", // Optimize for web streaming
+                "-f",
+                "mp4",
+            ],
+            AudioFormat::Opus => vec!["-c:a", "libopus", "-b:a", &bitrate, "-f", "ogg"],
+            AudioFormat::Flac => vec!["-c:a", "flac", "-f", "flac"],
+            AudioFormat::Wav => vec!["-c:a", "pcm_s16le", "-f", "wav"],
+        };
+        args.into_iter().map(str::to_string).collect()
+    }
+}
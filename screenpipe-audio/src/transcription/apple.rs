@@ -0,0 +1,240 @@
+use crate::storage::{AudioFormat, AudioStorage};
+use crate::transcription::stt_engine::{SttEngine, SttFuture};
+use crate::utils::ffmpeg::write_audio_to_file;
+use anyhow::{anyhow, Result};
+use block::ConcreteBlock;
+use objc::runtime::{Object, BOOL, NO, YES};
+use objc::{class, msg_send, sel, sel_impl};
+use screenpipe_core::Language;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+#[link(name = "Speech", kind = "framework")]
+extern "C" {}
+
+// Longer than whisper takes on a 30 second chunk
+const RECOGNITION_TIMEOUT: Duration = Duration::from_secs(120);
+// SFSpeechRecognizerAuthorizationStatus
+const NOT_DETERMINED: i64 = 0;
+const AUTHORIZED: i64 = 3;
+const UTF8_ENCODING: u64 = 4;
+
+/// On-device dictation of the Speech framework. Nothing leaves the computer, recognition
+/// fails for languages without an on-device model.
+pub struct AppleSttEngine;
+
+impl SttEngine for AppleSttEngine {
+    fn name(&self) -> &str {
+        "apple-native"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        _device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttFuture<'a> {
+        Box::pin(transcribe_with_apple(
+            audio,
+            sample_rate,
+            languages,
+            vocabulary,
+        ))
+    }
+}
+
+/// Objects released once recognition is over. They are only messaged, which is thread safe.
+struct Retained(*mut Object);
+
+unsafe impl Send for Retained {}
+
+impl Drop for Retained {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                let _: () = msg_send![self.0, release];
+            }
+        }
+    }
+}
+
+async fn transcribe_with_apple(
+    audio: &[f32],
+    sample_rate: u32,
+    languages: &[Language],
+    vocabulary: &[String],
+) -> Result<String> {
+    // requests read from a file, the buffer api needs an audio engine running
+    let path = std::env::temp_dir().join(format!("screenpipe-stt-{}.wav", rand::random::<u64>()));
+    let wav = AudioStorage {
+        format: AudioFormat::Wav,
+        ..AudioStorage::default()
+    };
+    write_audio_to_file(audio, sample_rate, &path, &wav, false)?;
+    let locale = languages.first().map(|language| language.as_lang_code());
+
+    let (tx, rx) = oneshot::channel();
+    let started = unsafe { start_recognition(&path, locale, vocabulary, tx) };
+    let transcription = match started {
+        Ok(recognition) => {
+            let result = tokio::time::timeout(RECOGNITION_TIMEOUT, rx).await;
+            if result.is_err() {
+                // the handler would still be called on a released request otherwise
+                unsafe {
+                    let _: () = msg_send![recognition.task.0, cancel];
+                }
+            }
+            drop(recognition);
+            match result {
+                Ok(Ok(transcription)) => transcription,
+                Ok(Err(_)) => Err(anyhow!("speech recognition ended without a result")),
+                Err(_) => Err(anyhow!("speech recognition timed out")),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&path);
+    transcription
+}
+
+/// Kept until recognition is over, the task is cancelled if it outlives the timeout.
+struct Recognition {
+    task: Retained,
+    _request: Retained,
+    _recognizer: Retained,
+}
+
+/// Runs `body` in an autorelease pool, the objects the framework autoreleases on a thread
+/// without a run loop would leak otherwise.
+unsafe fn autoreleased<T>(body: impl FnOnce() -> T) -> T {
+    let pool: *mut Object = msg_send![class!(NSAutoreleasePool), new];
+    let result = body();
+    let _: () = msg_send![pool, drain];
+    result
+}
+
+unsafe fn start_recognition(
+    path: &Path,
+    locale: Option<&str>,
+    vocabulary: &[String],
+    tx: oneshot::Sender<Result<String>>,
+) -> Result<Recognition> {
+    autoreleased(|| start_recognition_in_pool(path, locale, vocabulary, tx))
+}
+
+unsafe fn start_recognition_in_pool(
+    path: &Path,
+    locale: Option<&str>,
+    vocabulary: &[String],
+    tx: oneshot::Sender<Result<String>>,
+) -> Result<Recognition> {
+    let status: i64 = msg_send![class!(SFSpeechRecognizer), authorizationStatus];
+    if status == NOT_DETERMINED {
+        // asks once, the next chunks are transcribed once the user allowed it
+        let handler = ConcreteBlock::new(|_status: i64| {}).copy();
+        let _: () = msg_send![class!(SFSpeechRecognizer), requestAuthorization: &*handler];
+        return Err(anyhow!("speech recognition is not authorized yet"));
+    }
+    if status != AUTHORIZED {
+        return Err(anyhow!(
+            "speech recognition is not allowed, enable it in system settings"
+        ));
+    }
+
+    let mut recognizer: *mut Object = std::ptr::null_mut();
+    if let Some(locale) = locale {
+        let identifier = ns_string(locale);
+        let locale: *mut Object =
+            msg_send![class!(NSLocale), localeWithLocaleIdentifier: identifier.0];
+        let allocated: *mut Object = msg_send![class!(SFSpeechRecognizer), alloc];
+        recognizer = msg_send![allocated, initWithLocale: locale];
+    }
+    if recognizer.is_null() {
+        // bare language codes aren't always supported, the system locale is
+        let allocated: *mut Object = msg_send![class!(SFSpeechRecognizer), alloc];
+        recognizer = msg_send![allocated, init];
+    }
+    let recognizer = Retained(recognizer);
+    if recognizer.0.is_null() {
+        return Err(anyhow!("speech recognition does not support {:?}", locale));
+    }
+    let available: BOOL = msg_send![recognizer.0, isAvailable];
+    if available == NO {
+        return Err(anyhow!("speech recognition is not available"));
+    }
+
+    let path = ns_string(&path.to_string_lossy());
+    let url: *mut Object = msg_send![class!(NSURL), fileURLWithPath: path.0];
+    let request: *mut Object = msg_send![class!(SFSpeechURLRecognitionRequest), alloc];
+    let request = Retained(msg_send![request, initWithURL: url]);
+    let _: () = msg_send![request.0, setShouldReportPartialResults: NO];
+    let _: () = msg_send![request.0, setRequiresOnDeviceRecognition: YES];
+    if !vocabulary.is_empty() {
+        let terms: *mut Object = msg_send![class!(NSMutableArray), array];
+        for term in vocabulary {
+            let term = ns_string(term);
+            let _: () = msg_send![terms, addObject: term.0];
+        }
+        let _: () = msg_send![request.0, setContextualStrings: terms];
+    }
+
+    let tx = Mutex::new(Some(tx));
+    let handler = ConcreteBlock::new(move |result: *mut Object, error: *mut Object| {
+        let transcription = if !error.is_null() {
+            let description: *mut Object = msg_send![error, localizedDescription];
+            Err(anyhow!(
+                "speech recognition failed: {}",
+                rust_string(description)
+            ))
+        } else if result.is_null() {
+            return;
+        } else {
+            let is_final: BOOL = msg_send![result, isFinal];
+            if is_final == NO {
+                return;
+            }
+            let best: *mut Object = msg_send![result, bestTranscription];
+            let text: *mut Object = msg_send![best, formattedString];
+            Ok(rust_string(text))
+        };
+        if let Some(tx) = tx.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = tx.send(transcription);
+        }
+    })
+    .copy();
+    let task: *mut Object =
+        msg_send![recognizer.0, recognitionTaskWithRequest: request.0 resultHandler: &*handler];
+    let _: *mut Object = msg_send![task, retain];
+    Ok(Recognition {
+        task: Retained(task),
+        _request: request,
+        _recognizer: recognizer,
+    })
+}
+
+unsafe fn ns_string(text: &str) -> Retained {
+    let string: *mut Object = msg_send![class!(NSString), alloc];
+    Retained(msg_send![
+        string,
+        initWithBytes: text.as_ptr()
+        length: text.len()
+        encoding: UTF8_ENCODING
+    ])
+}
+
+unsafe fn rust_string(string: *mut Object) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+    let utf8: *const c_char = msg_send![string, UTF8String];
+    if utf8.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(utf8).to_string_lossy().into_owned()
+}
@@ -2,11 +2,14 @@ use anyhow::Result;
 use hound::{WavSpec, WavWriter};
 use reqwest::{Client, Response};
 use screenpipe_core::Language;
+use screenpipe_db::TranscriptWord;
 use serde_json::Value;
 use std::io::Cursor;
 use tracing::{debug, error, info};
 
 use crate::transcription::deepgram::{CUSTOM_DEEPGRAM_API_TOKEN, DEEPGRAM_API_URL};
+use crate::transcription::stt_engine::SttTranscript;
+use crate::transcription::vocabulary::deepgram_keywords;
 
 pub async fn transcribe_with_deepgram(
     api_key: &str,
@@ -14,7 +17,30 @@ pub async fn transcribe_with_deepgram(
     device: &str,
     sample_rate: u32,
     languages: Vec<Language>,
+    vocabulary: &[String],
 ) -> Result<String> {
+    let transcript = transcribe_with_deepgram_words(
+        api_key,
+        audio_data,
+        device,
+        sample_rate,
+        languages,
+        vocabulary,
+    )
+    .await?;
+    Ok(transcript.text)
+}
+
+/// Like [`transcribe_with_deepgram`], along with when each word was said. The terms of
+/// `vocabulary` are boosted.
+pub async fn transcribe_with_deepgram_words(
+    api_key: &str,
+    audio_data: &[f32],
+    device: &str,
+    sample_rate: u32,
+    languages: Vec<Language>,
+    vocabulary: &[String],
+) -> Result<SttTranscript> {
     debug!("starting deepgram transcription");
 
     // Use token from env var
@@ -24,7 +50,7 @@ pub async fn transcribe_with_deepgram(
     // Create a WAV file in memory
     let wav_data = create_wav_file(audio_data, sample_rate)?;
 
-    let query_params = create_query_params(languages);
+    let query_params = create_query_params(languages) + &deepgram_keywords(vocabulary);
 
     // rationale: custom api key = custom AI proxy to use deepgram
     // no custom api key = use deepgram api key for real deepgram endpoint
@@ -110,10 +136,32 @@ async fn get_deepgram_response(
         .await
 }
 
+/// The words of the best alternative of a deepgram response, punctuated when it was asked to.
+pub fn deepgram_words(alternative: &Value) -> Vec<TranscriptWord> {
+    alternative["words"]
+        .as_array()
+        .map(|words| {
+            words
+                .iter()
+                .filter_map(|word| {
+                    let text = word["punctuated_word"]
+                        .as_str()
+                        .or_else(|| word["word"].as_str())?;
+                    Some(TranscriptWord {
+                        word: text.to_string(),
+                        start: word["start"].as_f64()?,
+                        end: word["end"].as_f64()?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 async fn handle_deepgram_response(
     response: Result<Response, reqwest::Error>,
     device: &str,
-) -> Result<String> {
+) -> Result<SttTranscript> {
     match response {
         Ok(resp) => {
             debug!("received response from deepgram api");
@@ -127,10 +175,8 @@ async fn handle_deepgram_response(
                         );
                         return Err(anyhow::anyhow!("Deepgram API error: {:?}", result));
                     }
-                    let transcription = result["results"]["channels"][0]["alternatives"][0]
-                        ["transcript"]
-                        .as_str()
-                        .unwrap_or("");
+                    let alternative = &result["results"]["channels"][0]["alternatives"][0];
+                    let transcription = alternative["transcript"].as_str().unwrap_or("");
 
                     if transcription.is_empty() {
                         info!("device: {}, transcription is empty.", device);
@@ -142,7 +188,10 @@ async fn handle_deepgram_response(
                         );
                     }
 
-                    Ok(transcription.to_string())
+                    Ok(SttTranscript {
+                        text: transcription.to_string(),
+                        words: deepgram_words(alternative),
+                    })
                 }
                 Err(e) => {
                     error!("Failed to parse JSON response: {:?}", e);
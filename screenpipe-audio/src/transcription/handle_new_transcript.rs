@@ -1,16 +1,20 @@
 use std::sync::Arc;
 
-use crate::{core::engine::AudioTranscriptionEngine, transcription::process_transcription_result};
+use crate::core::device::DeviceType;
+use crate::transcription::process_transcription_result;
+use screenpipe_core::wasm_plugins::Transcript;
+use screenpipe_core::{PluginCapability, WasmPlugins};
 use screenpipe_db::DatabaseManager;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use super::TranscriptionResult;
 
 pub async fn handle_new_transcript(
     db: Arc<DatabaseManager>,
     transcription_receiver: Arc<crossbeam::channel::Receiver<TranscriptionResult>>,
-    transcription_engine: Arc<AudioTranscriptionEngine>,
+    plugins: Option<Arc<WasmPlugins>>,
 ) {
+    let plugins = plugins.filter(|plugins| plugins.wants(PluginCapability::Transcripts));
     let mut previous_transcript = "".to_string();
     let mut previous_transcript_id: Option<i64> = None;
     while let Ok(mut transcription) = transcription_receiver.recv() {
@@ -27,6 +31,32 @@ pub async fn handle_new_transcript(
             transcription.input.device, transcription.transcription
         );
 
+        if let (Some(plugins), Some(text)) = (&plugins, &transcription.transcription) {
+            let filtered = plugins.filter_transcript(Transcript {
+                device: transcription.input.device.name.clone(),
+                input: transcription.input.device.device_type == DeviceType::Input,
+                text: text.clone(),
+                start: transcription.start_time,
+                end: transcription.end_time,
+            });
+            match filtered {
+                Some(filtered) if filtered.is_empty() => continue,
+                Some(filtered) if &filtered != text => {
+                    // the timed words were of the text the plugin replaced
+                    transcription.words.clear();
+                    transcription.transcription = Some(filtered);
+                }
+                Some(_) => {}
+                None => {
+                    debug!(
+                        "transcription of {} dropped by a plugin",
+                        transcription.input.device
+                    );
+                    continue;
+                }
+            }
+        }
+
         // Insert the new transcript after fetching
         let mut current_transcript: Option<String> = transcription.transcription.clone();
         let mut processed_previous: Option<String> = None;
@@ -55,7 +85,6 @@ pub async fn handle_new_transcript(
         match process_transcription_result(
             &db,
             transcription,
-            transcription_engine.clone(),
             processed_previous,
             previous_transcript_id,
         )
@@ -2,8 +2,14 @@ use std::sync::Arc;
 
 use crate::core::device::AudioDevice;
 
+#[cfg(target_os = "macos")]
+pub mod apple;
 pub mod deepgram;
+pub mod retranscribe;
+pub mod streaming;
 pub mod stt;
+pub mod stt_engine;
+pub mod vocabulary;
 pub mod whisper;
 
 #[derive(Debug, Clone)]
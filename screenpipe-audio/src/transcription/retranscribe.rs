@@ -0,0 +1,65 @@
+//! Transcribing recorded audio again, e.g. with a better model than the one it was
+//! transcribed with while recording.
+use anyhow::Result;
+use screenpipe_core::Language;
+use screenpipe_db::TranscriptWord;
+use std::path::PathBuf;
+
+use crate::transcription::stt::SAMPLE_RATE;
+use crate::transcription::stt_engine::{SttEngine, SttTranscript};
+use crate::utils::audio::{pcm_decode, resample};
+use crate::utils::ffmpeg::decode_audio_file;
+
+/// Mono audio of the recording at `path`, at the sample rate speech is transcribed at.
+/// Formats symphonia can't read are decoded with ffmpeg.
+pub async fn load_recording(path: PathBuf) -> Result<Vec<f32>> {
+    tokio::task::spawn_blocking(move || match pcm_decode(&path) {
+        Ok((audio, sample_rate)) if sample_rate == SAMPLE_RATE => Ok(audio),
+        Ok((audio, sample_rate)) => resample(&audio, sample_rate, SAMPLE_RATE),
+        Err(_) => decode_audio_file(&path, SAMPLE_RATE),
+    })
+    .await?
+}
+
+/// The samples of `audio` from `start` to `end` seconds in, the rest of it when a bound is
+/// missing. Bounds past the audio are clamped to it.
+pub fn segment(audio: &[f32], sample_rate: u32, start: Option<f64>, end: Option<f64>) -> &[f32] {
+    let sample = |seconds: f64| ((seconds.max(0.0) * sample_rate as f64) as usize).min(audio.len());
+    let start = start.map_or(0, sample);
+    let end = end.map_or(audio.len(), sample).max(start);
+    &audio[start..end]
+}
+
+/// Transcribes the segment of `recording` from `start` to `end` seconds with `backend`,
+/// its words are timed from the start of the recording as stored.
+#[allow(clippy::too_many_arguments)]
+pub async fn retranscribe_segment(
+    backend: &dyn SttEngine,
+    recording: &[f32],
+    start: Option<f64>,
+    end: Option<f64>,
+    device: &str,
+    languages: &[Language],
+    vocabulary: &[String],
+) -> Result<SttTranscript> {
+    let audio = segment(recording, SAMPLE_RATE, start, end);
+    let transcript = backend
+        .transcribe_words(audio, SAMPLE_RATE, device, languages, vocabulary)
+        .await?;
+    Ok(SttTranscript {
+        text: transcript.text.trim().to_string(),
+        words: shift_words(transcript.words, start.unwrap_or(0.0).max(0.0)),
+    })
+}
+
+/// `words` timed from the start of a segment, timed from `offset` seconds earlier.
+pub fn shift_words(words: Vec<TranscriptWord>, offset: f64) -> Vec<TranscriptWord> {
+    words
+        .into_iter()
+        .map(|word| TranscriptWord {
+            start: word.start + offset,
+            end: word.end + offset,
+            ..word
+        })
+        .collect()
+}
@@ -0,0 +1,200 @@
+//! Realtime transcription without a streaming api. The end of the utterance being spoken is
+//! transcribed again every second of new audio, its transcript is final once the speaker
+//! pauses.
+use anyhow::{anyhow, Result};
+use screenpipe_core::Language;
+use screenpipe_events::send_event;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, warn};
+
+use crate::core::device::DeviceType;
+use crate::core::stream::AudioStream;
+use crate::transcription::deepgram::streaming::RealtimeTranscriptionEvent;
+use crate::transcription::stt::SAMPLE_RATE;
+use crate::transcription::stt_engine::SttEngines;
+use crate::transcription::vocabulary::Vocabulary;
+use crate::utils::audio::resample;
+
+pub const PARTIAL_INTERVAL: Duration = Duration::from_secs(1);
+pub const END_OF_UTTERANCE_SILENCE: Duration = Duration::from_millis(800);
+pub const MAX_UTTERANCE_DURATION: Duration = Duration::from_secs(15);
+// partials transcribe this much of the utterance at most, so they keep up with the speaker
+pub const PARTIAL_WINDOW: Duration = Duration::from_secs(5);
+// chunks quieter than this are silence
+const SPEECH_RMS: f32 = 0.01;
+// how often the stream checks it should stop when no audio comes in
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Audio of the utterance to transcribe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UtteranceStep {
+    /// The last `PARTIAL_WINDOW` of the utterance so far, still being spoken
+    Partial(Vec<f32>),
+    /// The whole utterance
+    Final(Vec<f32>),
+}
+
+/// Collects the audio of the utterance being spoken, leading silence is left out.
+pub struct UtteranceBuffer {
+    audio: Vec<f32>,
+    since_partial: usize,
+    trailing_silence: usize,
+    partial_samples: usize,
+    window_samples: usize,
+    silence_samples: usize,
+    max_samples: usize,
+}
+
+impl UtteranceBuffer {
+    pub fn new(sample_rate: u32) -> Self {
+        let samples = |duration: Duration| (duration.as_secs_f64() * sample_rate as f64) as usize;
+        Self {
+            audio: Vec::new(),
+            since_partial: 0,
+            trailing_silence: 0,
+            partial_samples: samples(PARTIAL_INTERVAL),
+            window_samples: samples(PARTIAL_WINDOW),
+            silence_samples: samples(END_OF_UTTERANCE_SILENCE),
+            max_samples: samples(MAX_UTTERANCE_DURATION),
+        }
+    }
+
+    /// Adds mono `chunk`, returns the audio to transcribe when a partial or the final
+    /// transcript is due.
+    pub fn push(&mut self, chunk: &[f32]) -> Option<UtteranceStep> {
+        let is_speech = rms(chunk) > SPEECH_RMS;
+        if self.audio.is_empty() && !is_speech {
+            return None;
+        }
+
+        self.audio.extend_from_slice(chunk);
+        self.since_partial += chunk.len();
+        if is_speech {
+            self.trailing_silence = 0;
+        } else {
+            self.trailing_silence += chunk.len();
+        }
+
+        if self.trailing_silence >= self.silence_samples || self.audio.len() >= self.max_samples {
+            self.since_partial = 0;
+            self.trailing_silence = 0;
+            return Some(UtteranceStep::Final(std::mem::take(&mut self.audio)));
+        }
+        if self.since_partial >= self.partial_samples {
+            self.since_partial = 0;
+            let start = self.audio.len().saturating_sub(self.window_samples);
+            return Some(UtteranceStep::Partial(self.audio[start..].to_vec()));
+        }
+        None
+    }
+}
+
+fn rms(chunk: &[f32]) -> f32 {
+    if chunk.is_empty() {
+        return 0.0;
+    }
+    (chunk.iter().map(|sample| sample * sample).sum::<f32>() / chunk.len() as f32).sqrt()
+}
+
+/// Sends `transcription` events of what is said on `stream`, transcribed by the engine of
+/// its device in `stt_engines`.
+pub async fn stream_transcription_local(
+    stream: Arc<AudioStream>,
+    languages: Vec<Language>,
+    vocabulary: Vocabulary,
+    is_running: Arc<AtomicBool>,
+    stt_engines: Arc<RwLock<Option<SttEngines>>>,
+) -> Result<()> {
+    let mut receiver = stream.subscribe().await;
+    let sample_rate = stream.device_config.sample_rate().0;
+    let device = stream.device.to_string();
+    let is_input = stream.device.device_type == DeviceType::Input;
+
+    let (step_tx, mut step_rx) = mpsc::channel::<UtteranceStep>(1);
+    let transcriber = tokio::spawn(async move {
+        while let Some(step) = step_rx.recv().await {
+            let (audio, is_final) = match step {
+                UtteranceStep::Partial(audio) => (audio, false),
+                UtteranceStep::Final(audio) => (audio, true),
+            };
+            let Some(engines) = stt_engines.read().await.clone() else {
+                continue;
+            };
+            let (_, backend) = engines.for_device(&device);
+            // local models keep a core busy for the whole transcription
+            let runtime = tokio::runtime::Handle::current();
+            let (languages, terms, name) = (languages.clone(), vocabulary.terms(), device.clone());
+            let transcription = tokio::task::spawn_blocking(move || {
+                let audio = if sample_rate != SAMPLE_RATE {
+                    resample(&audio, sample_rate, SAMPLE_RATE)
+                        .map_err(|e| anyhow!("failed to resample realtime audio: {}", e))?
+                } else {
+                    audio
+                };
+                runtime.block_on(backend.transcribe(&audio, SAMPLE_RATE, &name, &languages, &terms))
+            })
+            .await;
+            let transcription = match transcription.map_err(anyhow::Error::from).and_then(|r| r) {
+                Ok(transcription) => transcription,
+                Err(e) => {
+                    warn!("device: {}, realtime transcription failed: {}", device, e);
+                    continue;
+                }
+            };
+            let transcription = transcription.trim();
+            if transcription.is_empty() {
+                continue;
+            }
+            let _ = send_event(
+                "transcription",
+                RealtimeTranscriptionEvent {
+                    timestamp: chrono::Utc::now(),
+                    device: device.clone(),
+                    transcription: transcription.to_string(),
+                    is_final,
+                    is_input,
+                    speaker: None,
+                },
+            );
+        }
+    });
+
+    let mut buffer = UtteranceBuffer::new(sample_rate);
+    let result = loop {
+        if !is_running.load(Ordering::Relaxed) || stream.is_disconnected.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+        let chunk = match tokio::time::timeout(STOP_CHECK_INTERVAL, receiver.recv()).await {
+            Ok(Ok(chunk)) => chunk,
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                debug!(
+                    "realtime transcription of {} skipped {} chunks",
+                    stream.device, skipped
+                );
+                continue;
+            }
+            Ok(Err(RecvError::Closed)) => break Err(anyhow!("audio stream closed")),
+            Err(_) => continue,
+        };
+        match buffer.push(&chunk) {
+            // partials are skipped while the previous ones are still being transcribed
+            Some(step @ UtteranceStep::Partial(_)) => {
+                let _ = step_tx.try_send(step);
+            }
+            Some(step @ UtteranceStep::Final(_)) => {
+                if step_tx.send(step).await.is_err() {
+                    break Err(anyhow!("realtime transcriber stopped"));
+                }
+            }
+            None => {}
+        }
+    };
+
+    drop(step_tx);
+    let _ = transcriber.await;
+    result
+}
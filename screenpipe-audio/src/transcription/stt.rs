@@ -1,23 +1,25 @@
 use crate::core::device::AudioDevice;
 use crate::core::engine::AudioTranscriptionEngine;
+use crate::preprocessing::AudioPreprocessing;
 use crate::speaker::embedding::EmbeddingExtractor;
 use crate::speaker::embedding_manager::EmbeddingManager;
 use crate::speaker::prepare_segments;
 use crate::speaker::segment::SpeechSegment;
-use crate::transcription::deepgram::batch::transcribe_with_deepgram;
-use crate::transcription::whisper::batch::process_with_whisper;
+use crate::storage::AudioStorage;
+use crate::transcription::stt_engine::{create_stt_engine, SttEngine, SttEngines};
 use crate::utils::audio::resample;
 use crate::utils::ffmpeg::{get_new_file_path, write_audio_to_file};
-use crate::vad::VadEngine;
+use crate::vad::{record_chunk, VadEngine};
 use anyhow::Result;
 #[cfg(target_os = "macos")]
 use objc::rc::autoreleasepool;
 use screenpipe_core::Language;
+use screenpipe_db::TranscriptWord;
 use std::path::PathBuf;
 use std::{
     sync::Arc,
     sync::Mutex as StdMutex,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::Mutex;
 use tracing::error;
@@ -63,30 +65,13 @@ pub async fn stt(
     languages: Vec<Language>,
     whisper_context: Arc<WhisperContext>,
 ) -> Result<String> {
-    let transcription: Result<String> =
-        if audio_transcription_engine == AudioTranscriptionEngine::Deepgram.into() {
-            // Deepgram implementation
-            let api_key = deepgram_api_key.unwrap_or_default();
-
-            match transcribe_with_deepgram(&api_key, audio, device, sample_rate, languages.clone())
-                .await
-            {
-                Ok(transcription) => Ok(transcription),
-                Err(e) => {
-                    error!(
-                        "device: {}, deepgram transcription failed, falling back to Whisper: {:?}",
-                        device, e
-                    );
-                    // Fallback to Whisper
-                    process_with_whisper(audio, languages.clone(), whisper_context).await
-                }
-            }
-        } else {
-            // Existing Whisper implementation
-            process_with_whisper(audio, languages, whisper_context).await
-        };
-
-    transcription
+    create_stt_engine(
+        &audio_transcription_engine,
+        Some(whisper_context),
+        deepgram_api_key,
+    )?
+    .transcribe(audio, sample_rate, device, &languages, &[])
+    .await
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -97,11 +82,12 @@ pub async fn process_audio_input(
     embedding_manager: EmbeddingManager,
     embedding_extractor: Arc<StdMutex<EmbeddingExtractor>>,
     output_path: &PathBuf,
-    audio_transcription_engine: Arc<AudioTranscriptionEngine>,
-    deepgram_api_key: Option<String>,
+    stt_engines: &SttEngines,
     languages: Vec<Language>,
+    vocabulary: Vec<String>,
+    preprocessing: AudioPreprocessing,
+    storage: AudioStorage,
     output_sender: &crossbeam::channel::Sender<TranscriptionResult>,
-    whisper_context: Arc<WhisperContext>,
 ) -> Result<()> {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -127,23 +113,41 @@ pub async fn process_audio_input(
         embedding_manager,
         embedding_extractor,
         &audio.device.to_string(),
+        preprocessing,
     )
     .await?;
 
+    record_chunk(
+        speech_ratio_ok,
+        Duration::from_secs_f64(audio_data.len() as f64 / SAMPLE_RATE as f64),
+    );
     if !speech_ratio_ok {
         return Ok(());
     }
 
-    let new_file_path = get_new_file_path(&audio.device.to_string(), output_path);
+    let (audio_transcription_engine, stt_engine) =
+        stt_engines.for_device(&audio.device.to_string());
 
-    if let Err(e) = write_audio_to_file(
-        &audio.data.to_vec(),
-        audio.sample_rate,
-        &PathBuf::from(&new_file_path),
-        false,
-    ) {
-        error!("Error writing audio to file: {:?}", e);
-    }
+    // no file is written in transcript only mode, the chunk is stored without a path
+    let new_file_path = if storage.transcript_only {
+        String::new()
+    } else {
+        let new_file_path = get_new_file_path(
+            &audio.device.to_string(),
+            output_path,
+            storage.format.extension(),
+        );
+        if let Err(e) = write_audio_to_file(
+            &audio.data.to_vec(),
+            audio.sample_rate,
+            &PathBuf::from(&new_file_path),
+            &storage,
+            false,
+        ) {
+            error!("Error writing audio to file: {:?}", e);
+        }
+        new_file_path
+    };
 
     while let Some(segment) = segments.recv().await {
         let path = new_file_path.clone();
@@ -156,11 +160,11 @@ pub async fn process_audio_input(
                         segment,
                         audio.device.clone(),
                         audio_transcription_engine.clone(),
-                        deepgram_api_key.clone(),
+                        stt_engine.clone(),
                         languages.clone(),
+                        vocabulary.clone(),
                         path,
                         timestamp,
-                        whisper_context.clone(),
                     )
                 })
                 .await?
@@ -174,11 +178,11 @@ pub async fn process_audio_input(
                 segment,
                 audio.device.clone(),
                 audio_transcription_engine.clone(),
-                deepgram_api_key.clone(),
+                stt_engine.clone(),
                 languages.clone(),
+                vocabulary.clone(),
                 path,
                 timestamp,
-                whisper_context.clone(),
             )
             .await?
         };
@@ -196,33 +200,43 @@ pub async fn run_stt(
     segment: SpeechSegment,
     device: Arc<AudioDevice>,
     audio_transcription_engine: Arc<AudioTranscriptionEngine>,
-    deepgram_api_key: Option<String>,
+    stt_engine: Arc<dyn SttEngine>,
     languages: Vec<Language>,
+    vocabulary: Vec<String>,
     path: String,
     timestamp: u64,
-    whisper_context: Arc<WhisperContext>,
 ) -> Result<TranscriptionResult> {
     let audio = segment.samples.clone();
     let sample_rate = segment.sample_rate;
-    match stt_sync(
-        &audio,
-        sample_rate,
-        &device.to_string(),
-        audio_transcription_engine.clone(),
-        deepgram_api_key.clone(),
-        languages.clone(),
-        whisper_context,
-    )
-    .await
+    match stt_engine
+        .transcribe_words(
+            &audio,
+            sample_rate,
+            &device.to_string(),
+            &languages,
+            &vocabulary,
+        )
+        .await
     {
-        Ok(transcription) => Ok(TranscriptionResult {
+        Ok(transcript) => Ok(TranscriptionResult {
             input: AudioInput {
                 data: Arc::new(audio),
                 sample_rate,
                 channels: 1,
                 device: device.clone(),
             },
-            transcription: Some(transcription),
+            transcription: Some(transcript.text),
+            // words were timed from the start of the segment, stored from the start of the chunk
+            words: transcript
+                .words
+                .into_iter()
+                .map(|word| TranscriptWord {
+                    start: word.start + segment.start,
+                    end: word.end + segment.start,
+                    ..word
+                })
+                .collect(),
+            engine: audio_transcription_engine,
             path,
             timestamp,
             error: None,
@@ -240,6 +254,8 @@ pub async fn run_stt(
                     device: device.clone(),
                 },
                 transcription: None,
+                words: Vec::new(),
+                engine: audio_transcription_engine,
                 path,
                 timestamp,
                 error: Some(e.to_string()),
@@ -0,0 +1,290 @@
+use crate::core::engine::AudioTranscriptionEngine;
+use crate::transcription::deepgram::batch::transcribe_with_deepgram_words;
+use crate::transcription::whisper::batch::{process_with_whisper, process_with_whisper_words};
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use screenpipe_core::Language;
+use screenpipe_db::TranscriptWord;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use tracing::error;
+use whisper_rs::WhisperContext;
+
+pub type SttFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+pub type SttWordsFuture<'a> = Pin<Box<dyn Future<Output = Result<SttTranscript>> + Send + 'a>>;
+
+/// A transcription and when each of its words was said.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SttTranscript {
+    pub text: String,
+    /// In seconds into the transcribed audio, empty when the engine can't tell
+    pub words: Vec<TranscriptWord>,
+}
+
+/// A backend able to turn speech into text.
+///
+/// Implement this and call [`register_stt_engine`] to plug a custom backend into the audio
+/// pipeline, then select it with `AudioTranscriptionEngine::Provider(name)`.
+pub trait SttEngine: Send + Sync {
+    fn name(&self) -> &str;
+    /// Transcribes mono `audio` recorded on `device` at `sample_rate`, recognizing the terms
+    /// of `vocabulary` where the engine supports it.
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttFuture<'a>;
+
+    /// Transcribes like [`SttEngine::transcribe`], along with when each word was said.
+    /// Engines that can't tell leave the words out.
+    fn transcribe_words<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttWordsFuture<'a> {
+        Box::pin(async move {
+            let text = self
+                .transcribe(audio, sample_rate, device, languages, vocabulary)
+                .await?;
+            Ok(SttTranscript {
+                text,
+                words: Vec::new(),
+            })
+        })
+    }
+}
+
+lazy_static! {
+    static ref STT_ENGINES: RwLock<HashMap<String, Arc<dyn SttEngine>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Registers an engine under its `name()`, replacing any engine previously registered
+/// under the same name.
+pub fn register_stt_engine(engine: Arc<dyn SttEngine>) {
+    let name = engine.name().to_string();
+    STT_ENGINES
+        .write()
+        .expect("stt engine registry poisoned")
+        .insert(name, engine);
+}
+
+pub fn unregister_stt_engine(name: &str) -> Option<Arc<dyn SttEngine>> {
+    STT_ENGINES
+        .write()
+        .expect("stt engine registry poisoned")
+        .remove(name)
+}
+
+pub fn get_stt_engine(name: &str) -> Option<Arc<dyn SttEngine>> {
+    STT_ENGINES
+        .read()
+        .expect("stt engine registry poisoned")
+        .get(name)
+        .cloned()
+}
+
+pub fn list_stt_engines() -> Vec<String> {
+    STT_ENGINES
+        .read()
+        .expect("stt engine registry poisoned")
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Creates the backend of `engine`. Whisper models need their loaded `whisper_context`,
+/// deepgram falls back to it when a request fails.
+pub fn create_stt_engine(
+    engine: &AudioTranscriptionEngine,
+    whisper_context: Option<Arc<WhisperContext>>,
+    deepgram_api_key: Option<String>,
+) -> Result<Arc<dyn SttEngine>> {
+    let whisper = whisper_context.map(|context| Arc::new(WhisperSttEngine::new(context)));
+    match engine {
+        AudioTranscriptionEngine::Deepgram => Ok(Arc::new(DeepgramSttEngine {
+            api_key: deepgram_api_key.unwrap_or_default(),
+            fallback: whisper.map(|whisper| whisper as Arc<dyn SttEngine>),
+        })),
+        #[cfg(target_os = "macos")]
+        AudioTranscriptionEngine::AppleNative => {
+            Ok(Arc::new(crate::transcription::apple::AppleSttEngine))
+        }
+        AudioTranscriptionEngine::Provider(name) => {
+            get_stt_engine(name).ok_or_else(|| anyhow!("no stt engine registered under '{}'", name))
+        }
+        engine if engine.is_whisper() => match whisper {
+            Some(whisper) => Ok(whisper),
+            None => Err(anyhow!("whisper model for {} is not loaded", engine)),
+        },
+        #[allow(unreachable_patterns)]
+        _ => Err(anyhow!(
+            "stt engine {} is not supported on this platform",
+            engine
+        )),
+    }
+}
+
+/// Runs a local whisper.cpp model.
+pub struct WhisperSttEngine {
+    context: Arc<WhisperContext>,
+}
+
+impl WhisperSttEngine {
+    pub fn new(context: Arc<WhisperContext>) -> Self {
+        Self { context }
+    }
+}
+
+impl SttEngine for WhisperSttEngine {
+    fn name(&self) -> &str {
+        "whisper"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        _sample_rate: u32,
+        _device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttFuture<'a> {
+        Box::pin(process_with_whisper(
+            audio,
+            languages.to_vec(),
+            vocabulary,
+            self.context.clone(),
+        ))
+    }
+
+    fn transcribe_words<'a>(
+        &'a self,
+        audio: &'a [f32],
+        _sample_rate: u32,
+        _device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttWordsFuture<'a> {
+        Box::pin(process_with_whisper_words(
+            audio,
+            languages.to_vec(),
+            vocabulary,
+            self.context.clone(),
+        ))
+    }
+}
+
+/// Sends audio to the Deepgram API, or the proxy set with `CUSTOM_DEEPGRAM_API_TOKEN`.
+pub struct DeepgramSttEngine {
+    api_key: String,
+    /// Transcribes the audio when the request fails
+    fallback: Option<Arc<dyn SttEngine>>,
+}
+
+impl SttEngine for DeepgramSttEngine {
+    fn name(&self) -> &str {
+        "deepgram"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttFuture<'a> {
+        Box::pin(async move {
+            let transcript = self
+                .transcribe_words(audio, sample_rate, device, languages, vocabulary)
+                .await?;
+            Ok(transcript.text)
+        })
+    }
+
+    fn transcribe_words<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+        vocabulary: &'a [String],
+    ) -> SttWordsFuture<'a> {
+        Box::pin(async move {
+            let transcribed = transcribe_with_deepgram_words(
+                &self.api_key,
+                audio,
+                device,
+                sample_rate,
+                languages.to_vec(),
+                vocabulary,
+            )
+            .await;
+            match (transcribed, &self.fallback) {
+                (Err(e), Some(fallback)) => {
+                    error!(
+                        "device: {}, deepgram transcription failed, falling back to {}: {:?}",
+                        device,
+                        fallback.name(),
+                        e
+                    );
+                    fallback
+                        .transcribe_words(audio, sample_rate, device, languages, vocabulary)
+                        .await
+                }
+                (transcribed, _) => transcribed,
+            }
+        })
+    }
+}
+
+/// The engine transcribing each device, devices without one of their own use the default.
+#[derive(Clone)]
+pub struct SttEngines {
+    default: (Arc<AudioTranscriptionEngine>, Arc<dyn SttEngine>),
+    devices: HashMap<String, (Arc<AudioTranscriptionEngine>, Arc<dyn SttEngine>)>,
+}
+
+impl SttEngines {
+    pub fn new(engine: Arc<AudioTranscriptionEngine>, backend: Arc<dyn SttEngine>) -> Self {
+        Self {
+            default: (engine, backend),
+            devices: HashMap::new(),
+        }
+    }
+
+    /// Transcribes devices without an engine of their own with `backend`.
+    pub fn with_default(
+        mut self,
+        engine: Arc<AudioTranscriptionEngine>,
+        backend: Arc<dyn SttEngine>,
+    ) -> Self {
+        self.default = (engine, backend);
+        self
+    }
+
+    /// Transcribes `device`, named like `MacBook Pro Microphone (input)`, with `backend`.
+    pub fn with_device(
+        mut self,
+        device: &str,
+        engine: Arc<AudioTranscriptionEngine>,
+        backend: Arc<dyn SttEngine>,
+    ) -> Self {
+        self.devices.insert(device.to_string(), (engine, backend));
+        self
+    }
+
+    /// The engine of `device` and its backend.
+    pub fn for_device(&self, device: &str) -> (Arc<AudioTranscriptionEngine>, Arc<dyn SttEngine>) {
+        let (engine, backend) = self.devices.get(device).unwrap_or(&self.default);
+        (engine.clone(), backend.clone())
+    }
+}
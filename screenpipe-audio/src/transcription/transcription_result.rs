@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use screenpipe_db::{DatabaseManager, Speaker};
+use screenpipe_db::{DatabaseManager, Speaker, TranscriptWord};
+use screenpipe_events::{send_capture_event, CaptureEvent, TranscriptCapture};
 use tracing::{debug, error, info};
 
 use crate::core::engine::AudioTranscriptionEngine;
@@ -13,6 +14,10 @@ pub struct TranscriptionResult {
     pub input: AudioInput,
     pub speaker_embedding: Vec<f32>,
     pub transcription: Option<String>,
+    /// When each word of `transcription` was said, in seconds into the chunk at `path`
+    pub words: Vec<TranscriptWord>,
+    /// Engine the transcription was made with
+    pub engine: Arc<AudioTranscriptionEngine>,
     pub timestamp: u64,
     pub error: Option<String>,
     pub start_time: f64,
@@ -35,6 +40,10 @@ impl TranscriptionResult {
                 // strip new transcript before cur_idx word pos
                 let new_cur =
                     transcription.split_whitespace().collect::<Vec<&str>>()[cur_idx..].join(" ");
+                // the timed words only line up with the text when the engine split it alike
+                if self.words.len() == transcription.split_whitespace().count() {
+                    self.words.drain(..cur_idx);
+                }
 
                 return Some((new_prev, new_cur));
             }
@@ -47,7 +56,6 @@ impl TranscriptionResult {
 pub async fn process_transcription_result(
     db: &DatabaseManager,
     result: TranscriptionResult,
-    audio_transcription_engine: Arc<AudioTranscriptionEngine>,
     previous_transcript: Option<String>,
     previous_transcript_id: Option<i64>,
 ) -> Result<Option<i64>, anyhow::Error> {
@@ -64,7 +72,7 @@ pub async fn process_transcription_result(
     info!("Detected speaker: {:?}", speaker);
 
     let transcription = result.transcription.unwrap();
-    let transcription_engine = audio_transcription_engine.to_string();
+    let transcription_engine = result.engine.to_string();
     let mut chunk_id: Option<i64> = None;
 
     info!(
@@ -85,13 +93,19 @@ pub async fn process_transcription_result(
             }
         }
     }
-    match db.get_or_insert_audio_chunk(&result.path).await {
+    // chunks without a file share no path, each transcript gets one of its own
+    let audio_chunk = if result.path.is_empty() {
+        db.insert_audio_chunk("").await
+    } else {
+        db.get_or_insert_audio_chunk(&result.path).await
+    };
+    match audio_chunk {
         Ok(audio_chunk_id) => {
             if transcription.is_empty() {
                 return Ok(Some(audio_chunk_id));
             }
 
-            if let Err(e) = db
+            match db
                 .insert_audio_transcription(
                     audio_chunk_id,
                     &transcription,
@@ -114,17 +128,40 @@ pub async fn process_transcription_result(
                 )
                 .await
             {
-                error!(
-                    "Failed to insert audio transcription for device {}: {}",
-                    result.input.device, e
-                );
-                return Ok(Some(audio_chunk_id));
-            } else {
-                debug!(
-                    "Inserted audio transcription for chunk {} from device {} using {}",
-                    audio_chunk_id, result.input.device, transcription_engine
-                );
-                chunk_id = Some(audio_chunk_id);
+                Err(e) => {
+                    error!(
+                        "Failed to insert audio transcription for device {}: {}",
+                        result.input.device, e
+                    );
+                    return Ok(Some(audio_chunk_id));
+                }
+                Ok(transcription_id) => {
+                    debug!(
+                        "Inserted audio transcription for chunk {} from device {} using {}",
+                        audio_chunk_id, result.input.device, transcription_engine
+                    );
+                    if !result.words.is_empty() {
+                        if let Err(e) = db
+                            .set_audio_transcription_words(transcription_id, &result.words)
+                            .await
+                        {
+                            error!(
+                                "Failed to store word timestamps of transcription {}: {}",
+                                transcription_id, e
+                            );
+                        }
+                    }
+                    send_capture_event(CaptureEvent::Transcript(TranscriptCapture {
+                        audio_chunk_id,
+                        timestamp: chrono::Utc::now(),
+                        device_name: result.input.device.name.clone(),
+                        is_input_device: result.input.device.device_type
+                            == crate::core::device::DeviceType::Input,
+                        speaker_id: Some(speaker.id),
+                        transcription: transcription.clone(),
+                    }));
+                    chunk_id = Some(audio_chunk_id);
+                }
             }
         }
         Err(e) => error!(
@@ -0,0 +1,122 @@
+//! Terms speech to text should recognize, like names and product codenames it would
+//! otherwise mishear. Whisper is prompted with them, Deepgram boosts them.
+use futures::StreamExt;
+use screenpipe_events::{subscribe_to_event, CaptureEvent, CAPTURE_EVENT};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+// Whisper only reads the last 224 tokens of its prompt
+const MAX_PROMPT_CHARS: usize = 600;
+// Deepgram keyword boost, higher makes misrecognitions more likely
+const KEYWORD_BOOST: u32 = 2;
+
+/// Terms to bias transcriptions toward, along with the terms of each app that are only
+/// used while the app is focused.
+#[derive(Clone, Debug, Default)]
+pub struct Vocabulary {
+    terms: Vec<String>,
+    /// By lowercase app name, matching apps whose name contains it
+    app_terms: HashMap<String, Vec<String>>,
+    focused_app: Arc<RwLock<Option<String>>>,
+}
+
+impl Vocabulary {
+    pub fn new(terms: Vec<String>, app_terms: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            terms,
+            app_terms: app_terms
+                .into_iter()
+                .map(|(app, terms)| (app.to_lowercase(), terms))
+                .collect(),
+            focused_app: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.app_terms.is_empty()
+    }
+
+    pub fn set_focused_app(&self, app_name: Option<&str>) {
+        *self.focused_app.write().unwrap_or_else(|e| e.into_inner()) =
+            app_name.map(str::to_lowercase);
+    }
+
+    /// The terms of the focused app followed by the global ones, without duplicates.
+    pub fn terms(&self) -> Vec<String> {
+        let focused_app = self
+            .focused_app
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let app_terms = focused_app.iter().flat_map(|focused_app| {
+            self.app_terms
+                .iter()
+                .filter(|(app, _)| focused_app.contains(app.as_str()))
+                .flat_map(|(_, terms)| terms)
+        });
+        let mut terms: Vec<String> = Vec::new();
+        for term in app_terms.chain(&self.terms) {
+            let term = term.trim();
+            if !term.is_empty() && !terms.iter().any(|known| known.eq_ignore_ascii_case(term)) {
+                terms.push(term.to_string());
+            }
+        }
+        terms
+    }
+
+    /// Follows the app focused on screen, for the terms of apps.
+    pub fn track_focused_app(&self) {
+        if self.app_terms.is_empty() {
+            return;
+        }
+        let vocabulary = self.clone();
+        tokio::spawn(async move {
+            let mut events = subscribe_to_event::<CaptureEvent>(CAPTURE_EVENT);
+            while let Some(event) = events.next().await {
+                if let CaptureEvent::AppFocus(focus) = event.data {
+                    debug!("vocabulary of {} in use", focus.app_name);
+                    vocabulary.set_focused_app(Some(&focus.app_name));
+                }
+            }
+        });
+    }
+}
+
+/// The initial prompt of whisper listing `terms`, none without terms.
+pub fn whisper_prompt(terms: &[String]) -> Option<String> {
+    let mut prompt = String::new();
+    for term in terms {
+        if prompt.len() + term.len() + 2 > MAX_PROMPT_CHARS {
+            break;
+        }
+        if !prompt.is_empty() {
+            prompt.push_str(", ");
+        }
+        prompt.push_str(term);
+    }
+    if prompt.is_empty() {
+        None
+    } else {
+        Some(format!("Glossary: {}.", prompt))
+    }
+}
+
+/// The query parameters boosting `terms` in a Deepgram request.
+pub fn deepgram_keywords(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("&keywords={}:{}", percent_encode(term), KEYWORD_BOOST))
+        .collect()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -1,6 +1,9 @@
 use super::detect_language;
+use crate::transcription::stt_engine::SttTranscript;
+use crate::transcription::vocabulary::whisper_prompt;
 use anyhow::Result;
 use screenpipe_core::Language;
+use screenpipe_db::TranscriptWord;
 use std::sync::Arc;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
 /// Processes audio data using the Whisper model to generate transcriptions.
@@ -10,8 +13,43 @@ use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
 pub async fn process_with_whisper(
     audio: &[f32],
     languages: Vec<Language>,
+    vocabulary: &[String],
     whisper_context: Arc<WhisperContext>,
 ) -> Result<String> {
+    let transcript =
+        process_with_whisper_words(audio, languages, vocabulary, whisper_context).await?;
+    Ok(transcript.text)
+}
+
+/// Appends a text token to `words`, tokens starting with a space start a new word.
+/// `start` and `end` are in seconds.
+pub fn push_token(words: &mut Vec<TranscriptWord>, token: &str, start: f64, end: f64) {
+    match words.last_mut() {
+        Some(word) if !token.starts_with(' ') => {
+            word.word.push_str(token);
+            word.end = end;
+        }
+        _ => {
+            let token = token.trim();
+            if !token.is_empty() {
+                words.push(TranscriptWord {
+                    word: token.to_string(),
+                    start,
+                    end,
+                });
+            }
+        }
+    }
+}
+
+/// Like [`process_with_whisper`], along with when each word was said from its token
+/// timestamps. The terms of `vocabulary` are listed in the initial prompt.
+pub async fn process_with_whisper_words(
+    audio: &[f32],
+    languages: Vec<Language>,
+    vocabulary: &[String],
+    whisper_context: Arc<WhisperContext>,
+) -> Result<SttTranscript> {
     let mut whisper_state = whisper_context
         .create_state()
         .expect("failed to create key");
@@ -41,6 +79,10 @@ pub async fn process_with_whisper(
     params.set_debug_mode(false);
     params.set_logprob_thold(-2.0);
     params.set_translate(false);
+    let prompt = whisper_prompt(vocabulary);
+    if let Some(prompt) = &prompt {
+        params.set_initial_prompt(prompt);
+    }
 
     whisper_state
         .full(params, &audio)
@@ -51,6 +93,9 @@ pub async fn process_with_whisper(
         .expect("failed to get number of segments");
 
     let mut transcript = String::new();
+    let mut words = Vec::new();
+    // ids from the end of text token on are special tokens, e.g. timestamps
+    let token_eot = whisper_context.token_eot();
 
     for i in 0..num_segments {
         // Get the transcribed text and timestamps for the current segment.
@@ -59,7 +104,27 @@ pub async fn process_with_whisper(
             .expect("failed to get segment");
 
         transcript.push_str(&segment);
+
+        for j in 0..whisper_state.full_n_tokens(i)? {
+            if whisper_state.full_get_token_id(i, j)? >= token_eot {
+                continue;
+            }
+            let Ok(text) = whisper_state.full_get_token_text(i, j) else {
+                continue;
+            };
+            let data = whisper_state.full_get_token_data(i, j)?;
+            // token timestamps are in hundredths of a second
+            push_token(
+                &mut words,
+                &text,
+                data.t0 as f64 / 100.0,
+                data.t1 as f64 / 100.0,
+            );
+        }
     }
 
-    Ok(transcript)
+    Ok(SttTranscript {
+        text: transcript,
+        words,
+    })
 }
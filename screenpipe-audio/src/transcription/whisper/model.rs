@@ -1,28 +1,66 @@
 use crate::core::engine::AudioTranscriptionEngine;
-use anyhow::Result;
-use hf_hub::{api::sync::Api, Repo, RepoType};
-use std::{path::PathBuf, sync::Arc};
-use tracing::info;
+use anyhow::{anyhow, Result};
+use hf_hub::{api::sync::Api, Cache, Repo, RepoType};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
 use whisper_rs::WhisperContextParameters;
 
-pub fn download_whisper_model(engine: Arc<AudioTranscriptionEngine>) -> Result<PathBuf> {
-    let api = Api::new()?;
-    let repo = Repo::with_revision(
-        "ggerganov/whisper.cpp".to_string(),
-        RepoType::Model,
-        "main".to_string(),
-    );
-    let api_repo = api.repo(repo);
+const WHISPER_REPO_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp";
+// Lists the files of the repo with the sha256 of their content
+const WHISPER_TREE_URL: &str = "https://huggingface.co/api/models/ggerganov/whisper.cpp/tree/main";
 
-    info!("downloading model {:?}", engine);
-    let model_name = match *engine {
-        AudioTranscriptionEngine::WhisperLargeV3Turbo => "ggml-large-v3-turbo.bin",
+static MODELS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the directory whisper models are downloaded to, usually `<data dir>/models`.
+/// Only the first call has an effect.
+pub fn set_models_dir(dir: PathBuf) {
+    let _ = MODELS_DIR.set(dir);
+}
+
+pub fn models_dir() -> Result<PathBuf> {
+    if let Some(dir) = MODELS_DIR.get() {
+        return Ok(dir.clone());
+    }
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("failed to get home dir"))?;
+    Ok(home.join(".screenpipe").join("models"))
+}
+
+/// The file of the whisper.cpp model `engine` runs, deepgram falls back to the default one.
+pub fn whisper_model_file(engine: &AudioTranscriptionEngine) -> &'static str {
+    match engine {
         AudioTranscriptionEngine::WhisperTiny => "ggml-tiny.bin",
         AudioTranscriptionEngine::WhisperTinyQuantized => "ggml-tiny-q8_0.bin",
+        AudioTranscriptionEngine::WhisperBase => "ggml-base.bin",
+        AudioTranscriptionEngine::WhisperBaseQuantized => "ggml-base-q8_0.bin",
+        AudioTranscriptionEngine::WhisperSmall => "ggml-small.bin",
+        AudioTranscriptionEngine::WhisperSmallQuantized => "ggml-small-q8_0.bin",
+        AudioTranscriptionEngine::WhisperMedium => "ggml-medium.bin",
+        AudioTranscriptionEngine::WhisperMediumQuantized => "ggml-medium-q8_0.bin",
+        AudioTranscriptionEngine::WhisperLargeV3Turbo => "ggml-large-v3-turbo.bin",
         AudioTranscriptionEngine::WhisperLargeV3 => "ggml-large-v3.bin",
         AudioTranscriptionEngine::WhisperLargeV3Quantized => "ggml-large-v3-q5_0.bin",
         _ => "ggml-large-v3-turbo-q8_0.bin",
-    };
+    }
+}
+
+fn whisper_repo() -> Repo {
+    Repo::with_revision(
+        "ggerganov/whisper.cpp".to_string(),
+        RepoType::Model,
+        "main".to_string(),
+    )
+}
+
+pub fn download_whisper_model(engine: Arc<AudioTranscriptionEngine>) -> Result<PathBuf> {
+    let api = Api::new()?;
+    let api_repo = api.repo(whisper_repo());
+
+    info!("downloading model {:?}", engine);
+    let model_name = whisper_model_file(&engine);
 
     let model = api_repo.get(model_name)?;
 
@@ -31,6 +69,149 @@ pub fn download_whisper_model(engine: Arc<AudioTranscriptionEngine>) -> Result<P
     Ok(model)
 }
 
+#[derive(Deserialize)]
+struct RepoFile {
+    path: String,
+    lfs: Option<LfsPointer>,
+}
+
+#[derive(Deserialize)]
+struct LfsPointer {
+    oid: String,
+}
+
+/// The sha256 the repo lists for `file`.
+async fn expected_checksum(file: &str) -> Result<String> {
+    let files: Vec<RepoFile> = reqwest::get(WHISPER_TREE_URL)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    files
+        .into_iter()
+        .find(|repo_file| repo_file.path == file)
+        .and_then(|repo_file| repo_file.lfs)
+        .map(|lfs| lfs.oid.to_lowercase())
+        .ok_or_else(|| anyhow!("no checksum listed for {}", file))
+}
+
+pub fn sha256_of_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Puts the model `file` downloaded to the hf-hub cache by earlier versions in `dir`, so it
+/// isn't downloaded again.
+async fn link_cached_model(file: &str, dir: &Path, path: &Path) -> Result<bool> {
+    let Some(cached) = Cache::default().repo(whisper_repo()).get(file) else {
+        return Ok(false);
+    };
+    // cache snapshots are symlinks to the blobs
+    let cached = tokio::fs::canonicalize(&cached).await?;
+    tokio::fs::create_dir_all(dir).await?;
+    if let Err(e) = tokio::fs::hard_link(&cached, path).await {
+        debug!("failed to link {:?}, copying it instead: {}", cached, e);
+        tokio::fs::copy(&cached, path).await?;
+    }
+    info!("reusing whisper model {:?} of the hf-hub cache", cached);
+    Ok(true)
+}
+
+/// Returns the path of the model of `engine` inside `<models dir>/whisper`, see
+/// [`ensure_whisper_model_in`].
+pub async fn ensure_whisper_model(engine: &AudioTranscriptionEngine) -> Result<PathBuf> {
+    ensure_whisper_model_in(&models_dir()?.join("whisper"), engine).await
+}
+
+/// Returns the path of the model of `engine` inside `dir`, taking it from the hf-hub cache
+/// or downloading it first if it is not there yet. Models are checked against the sha256
+/// the repo lists, a model whose checksum doesn't match is downloaded again. Models
+/// verified before are used as they are without asking the repo, and unverified ones too
+/// when the checksum can't be fetched.
+pub async fn ensure_whisper_model_in(
+    dir: &Path,
+    engine: &AudioTranscriptionEngine,
+) -> Result<PathBuf> {
+    let file = whisper_model_file(engine);
+    let path = dir.join(file);
+    // the checksum of a verified model, hashing a large model takes a while
+    let checksum_path = dir.join(format!("{}.sha256", file));
+    if path.exists() && tokio::fs::metadata(&checksum_path).await.is_ok() {
+        debug!("found verified whisper model at: {:?}", path);
+        return Ok(path);
+    }
+    if !path.exists() {
+        if let Err(e) = link_cached_model(file, dir, &path).await {
+            warn!("failed to reuse the cached whisper model {}: {}", file, e);
+        }
+    }
+
+    let expected = match expected_checksum(file).await {
+        Ok(expected) => Some(expected),
+        Err(e) => {
+            warn!("failed to fetch the checksum of {}: {}", file, e);
+            None
+        }
+    };
+
+    if path.exists() {
+        match &expected {
+            None => {
+                warn!("using whisper model {:?} without verifying it", path);
+                return Ok(path);
+            }
+            Some(expected) => {
+                let model = path.clone();
+                let actual = tokio::task::spawn_blocking(move || sha256_of_file(&model)).await??;
+                if actual == *expected {
+                    tokio::fs::write(&checksum_path, &actual).await?;
+                    return Ok(path);
+                }
+                warn!(
+                    "whisper model {:?} is corrupted, downloading it again",
+                    path
+                );
+                tokio::fs::remove_file(&path).await?;
+            }
+        }
+    }
+    let expected = expected.ok_or_else(|| anyhow!("can't download {} unverified", file))?;
+
+    tokio::fs::create_dir_all(dir).await?;
+    let url = format!("{}/resolve/main/{}", WHISPER_REPO_URL, file);
+    info!("downloading whisper model {} from {}", file, url);
+    let mut response = reqwest::get(&url).await?.error_for_status()?;
+    let partial_path = dir.join(format!("{}.part", file));
+    let mut partial = tokio::fs::File::create(&partial_path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        partial.write_all(&chunk).await?;
+        size += chunk.len();
+    }
+    partial.flush().await?;
+    drop(partial);
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(anyhow!(
+            "checksum of {} is {}, expected {}",
+            file,
+            actual,
+            expected
+        ));
+    }
+    tokio::fs::rename(&partial_path, &path).await?;
+    tokio::fs::write(&checksum_path, &actual).await?;
+    info!("saved {} ({} bytes) to {:?}", file, size, path);
+
+    Ok(path)
+}
+
 pub fn create_whisper_context_parameters<'a>(
     engine: Arc<AudioTranscriptionEngine>,
 ) -> Result<WhisperContextParameters<'a>> {
@@ -40,6 +221,13 @@ pub fn create_whisper_context_parameters<'a>(
         AudioTranscriptionEngine::WhisperTiny | AudioTranscriptionEngine::WhisperTinyQuantized => {
             whisper_rs::DtwModelPreset::Tiny
         }
+        AudioTranscriptionEngine::WhisperBase | AudioTranscriptionEngine::WhisperBaseQuantized => {
+            whisper_rs::DtwModelPreset::Base
+        }
+        AudioTranscriptionEngine::WhisperSmall
+        | AudioTranscriptionEngine::WhisperSmallQuantized => whisper_rs::DtwModelPreset::Small,
+        AudioTranscriptionEngine::WhisperMedium
+        | AudioTranscriptionEngine::WhisperMediumQuantized => whisper_rs::DtwModelPreset::Medium,
         _ => whisper_rs::DtwModelPreset::LargeV3Turbo,
     };
 
@@ -1,3 +1,4 @@
+use crate::storage::AudioStorage;
 use anyhow::Result;
 use chrono::Utc;
 use tracing::debug;
@@ -14,6 +15,7 @@ fn encode_single_audio(
     data: &[u8],
     sample_rate: u32,
     channels: u16,
+    storage: &AudioStorage,
     output_path: &Path,
 ) -> anyhow::Result<()> {
     debug!("Starting FFmpeg process");
@@ -29,18 +31,9 @@ fn encode_single_audio(
             &channels.to_string(),
             "-i",
             "pipe:0",
-            "-c:a",
-            "aac",
-            "-b:a",
-            "64k", // Reduced bitrate for higher compression
-            "-profile:a",
-            "aac_low", // Use AAC-LC profile for better compatibility
-            "-movflags",
-            "+faststart", // Optimize for web streaming
-            "-f",
-            "mp4",
-            output_path.to_str().unwrap(),
         ])
+        .args(storage.ffmpeg_args())
+        .arg(output_path.to_str().unwrap())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -78,11 +71,14 @@ fn encode_single_audio(
     Ok(())
 }
 
-pub fn get_new_file_path(device: &str, output_path: &PathBuf) -> String {
+pub fn get_new_file_path(device: &str, output_path: &PathBuf, extension: &str) -> String {
     let new_file_name = Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string();
     let sanitized_device_name = device.replace(['/', '\\'], "_");
     PathBuf::from(output_path)
-        .join(format!("{}_{}.mp4", sanitized_device_name, new_file_name))
+        .join(format!(
+            "{}_{}.{}",
+            sanitized_device_name, new_file_name, extension
+        ))
         .to_str()
         .expect("Failed to create valid path")
         .to_string()
@@ -92,6 +88,7 @@ pub fn write_audio_to_file(
     audio: &[f32],
     sample_rate: u32,
     path: &PathBuf,
+    storage: &AudioStorage,
     skip_encoding: bool,
 ) -> Result<()> {
     // Run FFmpeg in a separate task
@@ -100,8 +97,41 @@ pub fn write_audio_to_file(
             bytemuck::cast_slice(audio),
             sample_rate,
             1,
+            storage,
             &PathBuf::from(path),
         )?;
     }
     Ok(())
 }
+
+/// Mono samples of the audio file at `path` at `sample_rate`, decoded by ffmpeg. For
+/// formats symphonia can't read, like opus.
+pub fn decode_audio_file(path: &Path, sample_rate: u32) -> Result<Vec<f32>> {
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
+    let output = Command::new(ffmpeg_path)
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args([
+            "-f",
+            "f32le",
+            "-ac",
+            "1",
+            "-ar",
+            &sample_rate.to_string(),
+            "pipe:1",
+        ])
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffmpeg failed to decode {:?}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect())
+}
@@ -3,9 +3,13 @@ pub mod webrtc;
 
 use anyhow;
 use lazy_static::lazy_static;
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
 use silero::SileroVad;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Once;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use vad_rs::VadStatus;
 use webrtc::WebRtcVad;
@@ -46,6 +50,44 @@ const SPEECH_THRESHOLD: f32 = 0.5;
 const SILENCE_THRESHOLD: f32 = 0.35;
 const SPEECH_FRAME_THRESHOLD: usize = 3; // Minimum number of frames above SPEECH_THRESHOLD to consider as speech
 
+// -60 dBFS, quieter chunks are muted or unplugged microphones and skip the vad model
+const SILENCE_PEAK: f32 = 0.001;
+
+/// Whether no sample of `samples` is loud enough to hold speech.
+pub fn is_silent(samples: &[f32]) -> bool {
+    samples.iter().all(|sample| sample.abs() < SILENCE_PEAK)
+}
+
+static SPEECH_CHUNKS: AtomicU64 = AtomicU64::new(0);
+static SILENT_CHUNKS: AtomicU64 = AtomicU64::new(0);
+static SILENT_MILLIS: AtomicU64 = AtomicU64::new(0);
+
+/// Audio chunks transcribed and chunks left out, neither transcribed nor stored, because
+/// no one spoke in them. Counted since the start.
+#[derive(OaSchema, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SilenceStats {
+    pub speech_chunks: u64,
+    pub silent_chunks: u64,
+    pub silent_seconds: f64,
+}
+
+pub fn record_chunk(speech: bool, duration: Duration) {
+    if speech {
+        SPEECH_CHUNKS.fetch_add(1, Ordering::Relaxed);
+    } else {
+        SILENT_CHUNKS.fetch_add(1, Ordering::Relaxed);
+        SILENT_MILLIS.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+pub fn silence_stats() -> SilenceStats {
+    SilenceStats {
+        speech_chunks: SPEECH_CHUNKS.load(Ordering::Relaxed),
+        silent_chunks: SILENT_CHUNKS.load(Ordering::Relaxed),
+        silent_seconds: SILENT_MILLIS.load(Ordering::Relaxed) as f64 / 1000.0,
+    }
+}
+
 lazy_static! {
     static ref MODEL_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
 }
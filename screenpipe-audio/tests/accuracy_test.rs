@@ -1,6 +1,7 @@
 use futures::future::join_all;
 use screenpipe_audio::core::device::default_input_device;
 use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+use screenpipe_audio::preprocessing::AudioPreprocessing;
 use screenpipe_audio::speaker::embedding::EmbeddingExtractor;
 use screenpipe_audio::speaker::embedding_manager::EmbeddingManager;
 use screenpipe_audio::speaker::prepare_segments;
@@ -126,6 +127,7 @@ async fn test_transcription_accuracy() {
                 embedding_manager,
                 embedding_extractor,
                 &audio_input.device.name,
+                AudioPreprocessing::default(),
             )
             .await
             .unwrap();
@@ -9,6 +9,7 @@ mod tests {
     use screenpipe_audio::core::engine::AudioTranscriptionEngine;
     use screenpipe_audio::core::record_and_transcribe;
     use screenpipe_audio::core::stream::AudioStream;
+    use screenpipe_audio::preprocessing::AudioPreprocessing;
     use screenpipe_audio::speaker::embedding::EmbeddingExtractor;
     use screenpipe_audio::speaker::embedding_manager::EmbeddingManager;
     use screenpipe_audio::speaker::prepare_segments;
@@ -265,6 +266,7 @@ mod tests {
             embedding_manager,
             embedding_extractor,
             &audio_input.device.to_string(),
+            AudioPreprocessing::default(),
         )
         .await
         .unwrap();
@@ -366,6 +368,7 @@ mod tests {
             embedding_manager,
             embedding_extractor,
             &audio_input.device.to_string(),
+            AudioPreprocessing::default(),
         )
         .await
         .unwrap();
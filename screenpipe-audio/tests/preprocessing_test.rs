@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::preprocessing::{suppress_noise, AudioPreprocessing};
+
+    fn rms(audio: &[f32]) -> f32 {
+        (audio.iter().map(|sample| sample * sample).sum::<f32>() / audio.len() as f32).sqrt()
+    }
+
+    // a steady hiss, like a fan
+    fn noise(len: usize) -> Vec<f32> {
+        let mut seed = 1u32;
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as f32 / 32768.0 - 1.0
+            })
+            .map(|sample| sample * 0.05)
+            .collect()
+    }
+
+    #[test]
+    fn test_stages_can_be_turned_off() {
+        let audio = noise(16000);
+        let untouched = AudioPreprocessing {
+            noise_suppression: false,
+            normalization: false,
+        };
+        assert_eq!(untouched.apply(&audio, 16000), audio);
+
+        let normalized = AudioPreprocessing::default().apply(&audio, 16000);
+        assert!(rms(&normalized) > rms(&audio) * 2.0);
+    }
+
+    #[test]
+    fn test_noise_is_suppressed() {
+        let audio = noise(16000 * 2);
+        let denoised = suppress_noise(&audio, 16000).unwrap();
+        assert_eq!(denoised.len(), audio.len());
+        assert!(rms(&denoised) < rms(&audio) / 2.0);
+        assert!(suppress_noise(&[], 16000).unwrap().is_empty());
+    }
+}
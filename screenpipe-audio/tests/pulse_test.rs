@@ -0,0 +1,31 @@
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use screenpipe_audio::core::device::{AudioDevice, DeviceType};
+    use screenpipe_audio::core::pulse::{is_monitor_source, parse_monitor_sources};
+
+    #[test]
+    fn test_only_monitor_sources_are_output_devices() {
+        let sources = "\
+47\talsa_output.pci-0000_00_1f.3.analog-stereo.monitor\tPipeWire\ts32le 2ch 48000Hz\tIDLE
+48\talsa_input.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tRUNNING
+52\tbluez_output.AC_80_0A_2E_1F_6D.1.monitor\tPipeWire\ts16le 2ch 48000Hz\tSUSPENDED
+";
+        assert_eq!(
+            parse_monitor_sources(sources),
+            vec![
+                "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor",
+                "bluez_output.AC_80_0A_2E_1F_6D.1.monitor",
+            ]
+        );
+        assert!(parse_monitor_sources("").is_empty());
+        assert!(!is_monitor_source(
+            "alsa_input.pci-0000_00_1f.3.analog-stereo"
+        ));
+
+        let device =
+            AudioDevice::from_name("alsa_output.pci-0000_00_1f.3.analog-stereo.monitor (output)")
+                .unwrap();
+        assert_eq!(device.device_type, DeviceType::Output);
+        assert!(is_monitor_source(&device.name));
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::transcription::retranscribe::{
+        retranscribe_segment, segment, shift_words,
+    };
+    use screenpipe_audio::transcription::stt_engine::{
+        SttEngine, SttFuture, SttTranscript, SttWordsFuture,
+    };
+    use screenpipe_core::Language;
+    use screenpipe_db::TranscriptWord;
+
+    fn word(word: &str, start: f64, end: f64) -> TranscriptWord {
+        TranscriptWord {
+            word: word.to_string(),
+            start,
+            end,
+        }
+    }
+
+    /// Hears one word per second of audio.
+    struct SecondsEngine;
+
+    impl SttEngine for SecondsEngine {
+        fn name(&self) -> &str {
+            "seconds"
+        }
+
+        fn transcribe<'a>(
+            &'a self,
+            audio: &'a [f32],
+            sample_rate: u32,
+            _device: &'a str,
+            _languages: &'a [Language],
+            _vocabulary: &'a [String],
+        ) -> SttFuture<'a> {
+            let seconds = audio.len() / sample_rate as usize;
+            Box::pin(async move { Ok(format!(" {} seconds ", seconds)) })
+        }
+
+        fn transcribe_words<'a>(
+            &'a self,
+            audio: &'a [f32],
+            sample_rate: u32,
+            _device: &'a str,
+            _languages: &'a [Language],
+            _vocabulary: &'a [String],
+        ) -> SttWordsFuture<'a> {
+            let seconds = audio.len() / sample_rate as usize;
+            Box::pin(async move {
+                Ok(SttTranscript {
+                    text: format!(" {} seconds ", seconds),
+                    words: (0..seconds)
+                        .map(|second| word("word", second as f64, second as f64 + 0.5))
+                        .collect(),
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_segment_is_clamped_to_the_audio() {
+        let audio: Vec<f32> = (0..100).map(|sample| sample as f32).collect();
+        assert_eq!(segment(&audio, 10, None, None).len(), 100);
+        assert_eq!(segment(&audio, 10, Some(2.0), Some(3.0)), &audio[20..30]);
+        assert_eq!(segment(&audio, 10, Some(8.0), None), &audio[80..]);
+        assert_eq!(segment(&audio, 10, Some(-1.0), Some(20.0)).len(), 100);
+        assert!(segment(&audio, 10, Some(5.0), Some(4.0)).is_empty());
+    }
+
+    #[test]
+    fn test_words_are_timed_from_the_recording() {
+        let shifted = shift_words(vec![word("hello", 0.0, 0.4), word("there", 0.5, 1.0)], 2.5);
+        assert_eq!(
+            shifted,
+            vec![word("hello", 2.5, 2.9), word("there", 3.0, 3.5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_segment_of_the_recording_is_transcribed() {
+        let recording = vec![0.0; 16000 * 10];
+        let transcript = retranscribe_segment(
+            &SecondsEngine,
+            &recording,
+            Some(4.0),
+            Some(6.0),
+            "mic (input)",
+            &[Language::English],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(transcript.text, "2 seconds");
+        assert_eq!(
+            transcript.words,
+            vec![word("word", 4.0, 4.5), word("word", 5.0, 5.5)]
+        );
+
+        let whole = retranscribe_segment(&SecondsEngine, &recording, None, None, "", &[], &[])
+            .await
+            .unwrap();
+        assert_eq!(whole.text, "10 seconds");
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::storage::{AudioFormat, AudioStorage};
+
+    #[test]
+    fn test_bitrate_is_checked() {
+        assert!(AudioStorage::new(AudioFormat::Opus, 24, false).is_ok());
+        assert!(AudioStorage::new(AudioFormat::Opus, 0, false).is_err());
+        assert!(AudioStorage::new(AudioFormat::Aac, 1000, false).is_err());
+    }
+
+    #[test]
+    fn test_lossy_formats_are_encoded_at_the_bitrate() {
+        let opus = AudioStorage::new(AudioFormat::Opus, 24, false).unwrap();
+        let args = opus.ffmpeg_args();
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "libopus"]));
+        assert!(args.windows(2).any(|pair| pair == ["-b:a", "24k"]));
+        assert_eq!(opus.format.extension(), "opus");
+
+        let flac = AudioStorage::new(AudioFormat::Flac, 24, false).unwrap();
+        assert!(!flac.format.is_lossy());
+        assert!(!flac.ffmpeg_args().contains(&"-b:a".to_string()));
+        assert_eq!(flac.format.extension(), "flac");
+    }
+
+    #[test]
+    fn test_audio_is_kept_as_before_by_default() {
+        let storage = AudioStorage::default();
+        assert_eq!(storage.format, AudioFormat::Aac);
+        assert_eq!(storage.format.extension(), "mp4");
+        assert!(!storage.transcript_only);
+        assert!(storage
+            .ffmpeg_args()
+            .windows(2)
+            .any(|pair| pair == ["-b:a", "64k"]));
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::transcription::streaming::{UtteranceBuffer, UtteranceStep};
+
+    const SAMPLE_RATE: u32 = 16000;
+    // 100ms chunks, like the audio streams send
+    const CHUNK: usize = SAMPLE_RATE as usize / 10;
+
+    fn speech() -> Vec<f32> {
+        (0..CHUNK).map(|i| (i as f32 * 0.1).sin() * 0.5).collect()
+    }
+
+    fn silence() -> Vec<f32> {
+        vec![0.0; CHUNK]
+    }
+
+    #[test]
+    fn test_leading_silence_is_left_out() {
+        let mut buffer = UtteranceBuffer::new(SAMPLE_RATE);
+        for _ in 0..50 {
+            assert_eq!(buffer.push(&silence()), None);
+        }
+    }
+
+    #[test]
+    fn test_partial_every_second_then_final_after_a_pause() {
+        let mut buffer = UtteranceBuffer::new(SAMPLE_RATE);
+        let steps: Vec<_> = (0..25).filter_map(|_| buffer.push(&speech())).collect();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(&steps[0], UtteranceStep::Partial(audio) if audio.len() == 10 * CHUNK));
+        assert!(matches!(&steps[1], UtteranceStep::Partial(audio) if audio.len() == 20 * CHUNK));
+
+        let steps: Vec<_> = (0..8).filter_map(|_| buffer.push(&silence())).collect();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(&steps[0], UtteranceStep::Partial(audio) if audio.len() == 30 * CHUNK));
+        assert!(matches!(&steps[1], UtteranceStep::Final(audio) if audio.len() == 33 * CHUNK));
+
+        // the next utterance starts from scratch
+        assert_eq!(buffer.push(&silence()), None);
+        assert_eq!(buffer.push(&speech()), None);
+    }
+
+    #[test]
+    fn test_partials_are_the_end_of_long_utterances() {
+        let mut buffer = UtteranceBuffer::new(SAMPLE_RATE);
+        let partials: Vec<_> = (0..100).filter_map(|_| buffer.push(&speech())).collect();
+        assert_eq!(partials.len(), 10);
+        assert!(matches!(&partials[3], UtteranceStep::Partial(audio) if audio.len() == 40 * CHUNK));
+        assert!(matches!(&partials[9], UtteranceStep::Partial(audio) if audio.len() == 50 * CHUNK));
+    }
+
+    #[test]
+    fn test_long_utterance_is_cut() {
+        let mut buffer = UtteranceBuffer::new(SAMPLE_RATE);
+        let finals: Vec<_> = (0..300)
+            .filter_map(|_| buffer.push(&speech()))
+            .filter(|step| matches!(step, UtteranceStep::Final(_)))
+            .collect();
+        assert_eq!(finals.len(), 2);
+        assert!(matches!(&finals[0], UtteranceStep::Final(audio) if audio.len() == 150 * CHUNK));
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::audio_manager::AudioManagerBuilder;
+    use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+    use screenpipe_audio::transcription::deepgram::batch::deepgram_words;
+    use screenpipe_audio::transcription::stt_engine::{
+        create_stt_engine, get_stt_engine, list_stt_engines, register_stt_engine,
+        unregister_stt_engine, SttEngine, SttEngines, SttFuture,
+    };
+    use screenpipe_audio::transcription::whisper::batch::push_token;
+    use screenpipe_core::Language;
+    use screenpipe_db::TranscriptWord;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    /// Transcribes any audio as how many samples it got, and from where.
+    struct CountingEngine(&'static str);
+
+    impl SttEngine for CountingEngine {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn transcribe<'a>(
+            &'a self,
+            audio: &'a [f32],
+            _sample_rate: u32,
+            device: &'a str,
+            _languages: &'a [Language],
+            _vocabulary: &'a [String],
+        ) -> SttFuture<'a> {
+            Box::pin(async move { Ok(format!("{} samples from {}", audio.len(), device)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_engine_is_selected_by_name() {
+        register_stt_engine(Arc::new(CountingEngine("counting")));
+        assert!(list_stt_engines().contains(&"counting".to_string()));
+
+        let engine = AudioTranscriptionEngine::Provider("counting".to_string());
+        let backend = create_stt_engine(&engine, None, None).unwrap();
+        let transcription = backend
+            .transcribe(&[0.0; 160], 16000, "mic (input)", &[Language::English], &[])
+            .await
+            .unwrap();
+        assert_eq!(transcription, "160 samples from mic (input)");
+
+        assert!(unregister_stt_engine("counting").is_some());
+        assert!(get_stt_engine("counting").is_none());
+        assert!(create_stt_engine(&engine, None, None).is_err());
+    }
+
+    #[test]
+    fn test_whisper_needs_its_model_loaded() {
+        let engine = AudioTranscriptionEngine::WhisperTinyQuantized;
+        assert!(engine.is_whisper());
+        assert!(create_stt_engine(&engine, None, None).is_err());
+        assert!(!AudioTranscriptionEngine::Deepgram.is_whisper());
+    }
+
+    #[tokio::test]
+    async fn test_devices_fall_back_to_the_default_engine() {
+        let default = Arc::new(AudioTranscriptionEngine::Provider("default".to_string()));
+        let meeting = Arc::new(AudioTranscriptionEngine::Provider("meeting".to_string()));
+        let engines = SttEngines::new(default.clone(), Arc::new(CountingEngine("default")))
+            .with_device(
+                "Zoom (output)",
+                meeting.clone(),
+                Arc::new(CountingEngine("meeting")),
+            );
+
+        let (engine, backend) = engines.for_device("Zoom (output)");
+        assert_eq!(engine, meeting);
+        assert_eq!(backend.name(), "meeting");
+        let (engine, backend) = engines.for_device("MacBook Pro Microphone (input)");
+        assert_eq!(engine, default);
+        assert_eq!(backend.name(), "default");
+    }
+
+    #[test]
+    fn test_deepgram_device_needs_an_api_key() {
+        if std::env::var("CUSTOM_DEEPGRAM_API_TOKEN").is_ok() {
+            return;
+        }
+        let builder = AudioManagerBuilder::new()
+            .transcription_engine(AudioTranscriptionEngine::WhisperTinyQuantized)
+            .deepgram_api_key(None)
+            .output_path(PathBuf::from("/tmp"));
+        assert!(builder.validate_options().is_ok());
+
+        let builder = builder.device_transcription_engines(HashMap::from([(
+            "Zoom (output)".to_string(),
+            AudioTranscriptionEngine::Deepgram,
+        )]));
+        assert!(builder.validate_options().is_err());
+        let builder = builder.deepgram_api_key(Some("key".to_string()));
+        assert!(builder.validate_options().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_engines_without_word_timestamps_leave_them_out() {
+        let engine = CountingEngine("untimed");
+        let transcript = engine
+            .transcribe_words(&[0.0; 10], 16000, "mic (input)", &[], &[])
+            .await
+            .unwrap();
+        assert_eq!(transcript.text, "10 samples from mic (input)");
+        assert!(transcript.words.is_empty());
+    }
+
+    #[test]
+    fn test_word_timestamps() {
+        let word = |word: &str, start, end| TranscriptWord {
+            word: word.to_string(),
+            start,
+            end,
+        };
+
+        // whisper splits words into tokens, the ones after the first have no leading space
+        let mut words = Vec::new();
+        for (token, start, end) in [(" Hel", 0.5, 0.7), ("lo", 0.7, 0.9), (" world", 1.0, 1.4)] {
+            push_token(&mut words, token, start, end);
+        }
+        push_token(&mut words, " ", 1.4, 1.5);
+        assert_eq!(
+            words,
+            vec![word("Hello", 0.5, 0.9), word("world", 1.0, 1.4)]
+        );
+
+        let alternative = json!({
+            "transcript": "hello world",
+            "words": [
+                {"word": "hello", "start": 0.5, "end": 0.9, "punctuated_word": "Hello"},
+                {"word": "world", "start": 1.0, "end": 1.4},
+                {"word": "untimed"}
+            ]
+        });
+        assert_eq!(
+            deepgram_words(&alternative),
+            vec![word("Hello", 0.5, 0.9), word("world", 1.0, 1.4)]
+        );
+        assert!(deepgram_words(&json!({"transcript": ""})).is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::vad::{is_silent, record_chunk, silence_stats};
+    use std::time::Duration;
+
+    #[test]
+    fn test_muted_audio_is_silent() {
+        assert!(is_silent(&[0.0; 16000]));
+        assert!(is_silent(&[0.0005, -0.0009, 0.0]));
+        assert!(!is_silent(&[0.0, 0.2, -0.1]));
+        assert!(is_silent(&[]));
+    }
+
+    #[test]
+    fn test_silent_chunks_are_counted() {
+        let before = silence_stats();
+        record_chunk(false, Duration::from_secs(30));
+        record_chunk(true, Duration::from_secs(30));
+        let after = silence_stats();
+        assert!(after.silent_chunks > before.silent_chunks);
+        assert!(after.speech_chunks > before.speech_chunks);
+        assert!(after.silent_seconds - before.silent_seconds >= 30.0);
+    }
+}
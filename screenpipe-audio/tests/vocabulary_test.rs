@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::transcription::vocabulary::{
+        deepgram_keywords, whisper_prompt, Vocabulary,
+    };
+    use std::collections::HashMap;
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    #[test]
+    fn test_app_terms_are_used_while_the_app_is_focused() {
+        let vocabulary = Vocabulary::new(
+            terms(&["screenpipe", "Kubernetes"]),
+            HashMap::from([("Figma".to_string(), terms(&["autolayout", "kubernetes"]))]),
+        );
+        assert_eq!(vocabulary.terms(), terms(&["screenpipe", "Kubernetes"]));
+
+        vocabulary.set_focused_app(Some("Figma Desktop"));
+        assert_eq!(
+            vocabulary.terms(),
+            terms(&["autolayout", "kubernetes", "screenpipe"])
+        );
+
+        vocabulary.set_focused_app(Some("Slack"));
+        assert_eq!(vocabulary.terms(), terms(&["screenpipe", "Kubernetes"]));
+    }
+
+    #[test]
+    fn test_engine_parameters() {
+        assert_eq!(whisper_prompt(&[]), None);
+        assert_eq!(
+            whisper_prompt(&terms(&["screenpipe", "Louis"])).unwrap(),
+            "Glossary: screenpipe, Louis."
+        );
+        let long: Vec<String> = (0..500).map(|i| format!("term{}", i)).collect();
+        assert!(whisper_prompt(&long).unwrap().len() < 620);
+
+        assert_eq!(
+            deepgram_keywords(&terms(&["screenpipe", "Project Atlas"])),
+            "&keywords=screenpipe:2&keywords=Project%20Atlas:2"
+        );
+    }
+}
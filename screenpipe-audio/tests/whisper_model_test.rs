@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+    use screenpipe_audio::transcription::whisper::model::{
+        create_whisper_context_parameters, ensure_whisper_model_in, sha256_of_file,
+        whisper_model_file,
+    };
+    use std::collections::HashSet;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    const WHISPER_ENGINES: [AudioTranscriptionEngine; 12] = [
+        AudioTranscriptionEngine::WhisperTiny,
+        AudioTranscriptionEngine::WhisperTinyQuantized,
+        AudioTranscriptionEngine::WhisperBase,
+        AudioTranscriptionEngine::WhisperBaseQuantized,
+        AudioTranscriptionEngine::WhisperSmall,
+        AudioTranscriptionEngine::WhisperSmallQuantized,
+        AudioTranscriptionEngine::WhisperMedium,
+        AudioTranscriptionEngine::WhisperMediumQuantized,
+        AudioTranscriptionEngine::WhisperLargeV3,
+        AudioTranscriptionEngine::WhisperLargeV3Quantized,
+        AudioTranscriptionEngine::WhisperLargeV3Turbo,
+        AudioTranscriptionEngine::WhisperLargeV3TurboQuantized,
+    ];
+
+    #[test]
+    fn test_every_model_size_has_its_own_file() {
+        let files: HashSet<&str> = WHISPER_ENGINES.iter().map(whisper_model_file).collect();
+        assert_eq!(files.len(), WHISPER_ENGINES.len());
+        assert!(WHISPER_ENGINES.iter().all(|engine| engine.is_whisper()));
+        assert_eq!(
+            whisper_model_file(&AudioTranscriptionEngine::WhisperSmallQuantized),
+            "ggml-small-q8_0.bin"
+        );
+        // deepgram falls back to the default model
+        assert_eq!(
+            whisper_model_file(&AudioTranscriptionEngine::Deepgram),
+            whisper_model_file(&AudioTranscriptionEngine::WhisperLargeV3TurboQuantized)
+        );
+        for engine in WHISPER_ENGINES {
+            assert!(create_whisper_context_parameters(Arc::new(engine)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_model_checksum() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"abc").unwrap();
+        assert_eq!(
+            sha256_of_file(file.path()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verified_models_are_used_without_asking_the_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = AudioTranscriptionEngine::WhisperTinyQuantized;
+        let file = whisper_model_file(&engine);
+        std::fs::write(dir.path().join(file), b"abc").unwrap();
+        std::fs::write(
+            dir.path().join(format!("{}.sha256", file)),
+            "checked before",
+        )
+        .unwrap();
+        assert_eq!(
+            ensure_whisper_model_in(dir.path(), &engine).await.unwrap(),
+            dir.path().join(file)
+        );
+    }
+}
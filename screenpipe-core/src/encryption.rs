@@ -0,0 +1,255 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::OnceCell;
+use rand::RngCore;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tempfile::{TempDir, TempPath};
+use tokio::io::AsyncReadExt;
+
+/// Start of every encrypted file, followed by the nonce and the AES-256-GCM ciphertext.
+pub const ENCRYPTED_MAGIC: &[u8; 8] = b"SPENC01\0";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+const KEYCHAIN_SERVICE: &str = "screenpipe";
+const KEYCHAIN_USER: &str = "encryption-key";
+/// Hex key used instead of the keychain, for machines without one
+pub const ENCRYPTION_KEY_ENV: &str = "SCREENPIPE_ENCRYPTION_KEY";
+
+static MEDIA_KEY: OnceCell<EncryptionKey> = OnceCell::new();
+
+/// AES-256 key media files and the database are encrypted with.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl EncryptionKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn from_hex(hex_key: &str) -> Result<Self> {
+        let bytes = hex::decode(hex_key.trim()).context("encryption key is not hex")?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("encryption key must be {} bytes", KEY_LEN))?;
+        Ok(Self(key))
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// The key from `SCREENPIPE_ENCRYPTION_KEY`, or from the OS keychain. A new key is
+    /// stored in the keychain the first time.
+    pub fn load() -> Result<Self> {
+        if let Ok(hex_key) = std::env::var(ENCRYPTION_KEY_ENV) {
+            return Self::from_hex(&hex_key);
+        }
+
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)?;
+        match entry.get_password() {
+            Ok(hex_key) => Self::from_hex(&hex_key),
+            Err(keyring::Error::NoEntry) => {
+                let key = Self::generate();
+                entry
+                    .set_password(&key.to_hex())
+                    .context("failed to store the encryption key in the keychain")?;
+                Ok(key)
+            }
+            Err(e) => Err(anyhow!(
+                "failed to read the encryption key from the keychain: {}",
+                e
+            )),
+        }
+    }
+
+    /// `data` encrypted with a random nonce, in the format of encrypted files.
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), data)
+            .map_err(|_| anyhow!("failed to encrypt"))?;
+
+        let mut encrypted =
+            Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(ENCRYPTED_MAGIC);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        Ok(encrypted)
+    }
+
+    /// Decrypts what [`Self::encrypt`] returned. Fails when the data was changed or
+    /// encrypted with another key.
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>> {
+        if !is_encrypted(encrypted) || encrypted.len() < ENCRYPTED_MAGIC.len() + NONCE_LEN {
+            return Err(anyhow!("data is not encrypted"));
+        }
+        let (nonce, ciphertext) = encrypted[ENCRYPTED_MAGIC.len()..].split_at(NONCE_LEN);
+        self.cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("failed to decrypt, wrong key or corrupted data"))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+/// Sets the key media files are encrypted with, for [`encrypt_file`] and the read path.
+pub fn set_media_key(key: EncryptionKey) {
+    let _ = MEDIA_KEY.set(key);
+}
+
+pub fn media_key() -> Option<&'static EncryptionKey> {
+    MEDIA_KEY.get()
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MAGIC)
+}
+
+pub async fn is_encrypted_file(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut magic = [0u8; ENCRYPTED_MAGIC.len()];
+    file.read_exact(&mut magic).await.is_ok() && &magic == ENCRYPTED_MAGIC
+}
+
+/// Encrypts a file with the media key. The file is rewritten in place, so frame blobs
+/// hard linked into image chunks are encrypted for every chunk at once. Returns false
+/// when it already was encrypted.
+pub async fn encrypt_file(path: &Path) -> Result<bool> {
+    let key = media_key().ok_or_else(|| anyhow!("no media encryption key set"))?;
+    let data = tokio::fs::read(path).await?;
+    if is_encrypted(&data) {
+        return Ok(false);
+    }
+    let encrypted = key.encrypt(&data)?;
+    tokio::fs::write(path, encrypted).await?;
+    Ok(true)
+}
+
+/// Encrypts a media file, or every file of an image chunk directory. Returns how many
+/// files were encrypted.
+pub async fn encrypt_media(path: &Path) -> Result<usize> {
+    if !tokio::fs::metadata(path).await?.is_dir() {
+        return Ok(encrypt_file(path).await? as usize);
+    }
+
+    let mut encrypted = 0;
+    let mut entries = tokio::fs::read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() && encrypt_file(&entry.path()).await? {
+            encrypted += 1;
+        }
+    }
+    Ok(encrypted)
+}
+
+/// `data` encrypted with the media key when one is set, unchanged otherwise. For files
+/// derived from media, like cached frames, that [`read_media`] reads back.
+pub fn encrypt_if_enabled(data: &[u8]) -> Result<Vec<u8>> {
+    match media_key() {
+        Some(key) => key.encrypt(data),
+        None => Ok(data.to_vec()),
+    }
+}
+
+/// Reads a file, decrypting it when it is encrypted.
+pub async fn read_media(path: &Path) -> Result<Vec<u8>> {
+    let data = tokio::fs::read(path).await?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+    media_key()
+        .ok_or_else(|| anyhow!("{} is encrypted, start with --encrypt", path.display()))?
+        .decrypt(&data)
+}
+
+/// A media path ffmpeg can read. Encrypted files are decrypted to a private temporary
+/// file that is deleted when this is dropped.
+pub struct MediaPath {
+    path: PathBuf,
+    _decrypted_file: Option<TempPath>,
+    _decrypted_dir: Option<TempDir>,
+}
+
+impl MediaPath {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn to_str(&self) -> &str {
+        self.path.to_str().unwrap_or_default()
+    }
+}
+
+/// `path` itself when it isn't encrypted, a decrypted copy otherwise. Directories are
+/// image chunks, their files are decrypted to a temporary directory with the same names.
+pub async fn readable_media(path: &Path) -> Result<MediaPath> {
+    let plain = MediaPath {
+        path: path.to_path_buf(),
+        _decrypted_file: None,
+        _decrypted_dir: None,
+    };
+
+    if tokio::fs::metadata(path).await.is_ok_and(|m| m.is_dir()) {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            files.push(entry.path());
+        }
+        let mut any_encrypted = false;
+        for file in &files {
+            any_encrypted |= is_encrypted_file(file).await;
+        }
+        if !any_encrypted {
+            return Ok(plain);
+        }
+
+        let dir = tempfile::tempdir()?;
+        for file in &files {
+            let name = file.file_name().expect("dir entries have a name");
+            tokio::fs::write(dir.path().join(name), read_media(file).await?).await?;
+        }
+        return Ok(MediaPath {
+            path: dir.path().to_path_buf(),
+            _decrypted_file: None,
+            _decrypted_dir: Some(dir),
+        });
+    }
+
+    if !is_encrypted_file(path).await {
+        return Ok(plain);
+    }
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let file = tempfile::Builder::new()
+        .prefix("screenpipe-")
+        .suffix(&extension)
+        .tempfile()?
+        .into_temp_path();
+    tokio::fs::write(&file, read_media(path).await?).await?;
+    Ok(MediaPath {
+        path: file.to_path_buf(),
+        _decrypted_file: Some(file),
+        _decrypted_dir: None,
+    })
+}
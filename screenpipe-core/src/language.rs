@@ -238,6 +238,36 @@ impl Language {
             Language::Javanese => "jw",
         }
     }
+
+    /// Tesseract traineddata name for this language, e.g. `deu` for German.
+    pub fn as_tesseract_code(&self) -> Option<&'static str> {
+        TESSERACT_LANGUAGES
+            .iter()
+            .find(|(_, name)| self == name)
+            .map(|(code, _)| *code)
+    }
+
+    /// Tesseract traineddata for text set vertically in this language, e.g. `jpn_vert`.
+    pub fn as_vertical_tesseract_code(&self) -> Option<&'static str> {
+        match self {
+            Language::Chinese => Some("chi_sim_vert"),
+            Language::Japanese => Some("jpn_vert"),
+            Language::Korean => Some("kor_vert"),
+            _ => None,
+        }
+    }
+
+    /// Looks up a language by its Tesseract traineddata name (`eng`, `chi_sim`, ...).
+    /// Plain language names such as `german` are accepted as well.
+    pub fn from_tesseract_code(code: &str) -> Option<Language> {
+        let code = code.trim();
+        let name = TESSERACT_LANGUAGES
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(code))
+            .map(|(_, name)| *name)
+            .unwrap_or(code);
+        Language::from_str(name, true).ok()
+    }
 }
 
 impl fmt::Display for Language {
@@ -332,3 +362,26 @@ pub const TESSERACT_LANGUAGES: [(&str, &str); 76] = [
     ("hau", "hausa"),
     ("jav", "javanese"),
 ];
+
+/// Parses an OCR language list in Tesseract notation, e.g. `eng+deu+jpn`.
+/// Commas are accepted as separators too. Duplicates are dropped, order is kept.
+pub fn parse_ocr_languages(spec: &str) -> Result<Vec<Language>, String> {
+    let mut languages = Vec::new();
+    let mut unknown = Vec::new();
+    for code in spec
+        .split(|c| c == '+' || c == ',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        match Language::from_tesseract_code(code) {
+            Some(language) if !languages.contains(&language) => languages.push(language),
+            Some(_) => {}
+            None => unknown.push(code.to_string()),
+        }
+    }
+
+    if !unknown.is_empty() {
+        return Err(format!("unknown ocr language(s): {}", unknown.join(", ")));
+    }
+    Ok(languages)
+}
@@ -1,3 +1,4 @@
+pub mod encryption;
 pub mod ffmpeg;
 pub use ffmpeg::find_ffmpeg_path;
 #[cfg(feature = "llm")]
@@ -22,20 +23,35 @@ pub mod llama;
 pub use llama::*;
 pub mod pipes;
 pub use pipes::*;
+pub mod pipe_install;
+pub use pipe_install::{
+    check_updates, install_pipe, InstallRecord, InstalledPipe, PipeSource, PipeUpdate, Registry,
+};
+pub mod pipe_runtime;
+pub use pipe_runtime::{
+    start_script_pipe, PipeEvent, PipeHost, PipeLimits, PipePermissions, ReadRequest, ScriptPipe,
+    ScriptPipeHandle, TRIGGER_PREFIX,
+};
+pub mod wasm_plugins;
+pub use wasm_plugins::{PluginCapability, WasmPlugins};
 mod language;
 #[cfg(feature = "security")]
 pub mod pii_removal;
+#[cfg(feature = "security")]
+pub mod redaction;
 
 #[cfg(feature = "mkl")]
 extern crate intel_mkl_src;
 
 #[cfg(feature = "security")]
 pub use pii_removal::*;
+#[cfg(feature = "security")]
+pub use redaction::{RedactionCategory, RedactionMatch, RedactionPolicy};
 
 pub mod network;
 pub use network::*;
 
-pub use language::{Language, TESSERACT_LANGUAGES};
+pub use language::{parse_ocr_languages, Language, TESSERACT_LANGUAGES};
 pub mod embedding;
 pub use embedding::*;
 
@@ -1,21 +1,12 @@
+use crate::redaction::RedactionPolicy;
 use lazy_static::lazy_static;
-use regex::Regex;
 
 lazy_static! {
-    static ref PII_PATTERNS: Vec<(Regex, &'static str)> = vec![
-        (Regex::new(r"\b(?:\d{4}[-\s]?){3}\d{4}\b").unwrap(), "[CREDIT_CARD]"),
-        (Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap(), "[SSN]"),
-        (Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b").unwrap(), "[EMAIL]"),
-        // add more patterns as needed
-    ];
+    static ref PII_POLICY: RedactionPolicy = RedactionPolicy::builtin();
 }
 
 pub fn remove_pii(text: &str) -> String {
-    let mut sanitized = text.to_string();
-    for (pattern, replacement) in PII_PATTERNS.iter() {
-        sanitized = pattern.replace_all(&sanitized, *replacement).to_string();
-    }
-    sanitized
+    PII_POLICY.redact(text)
 }
 
 #[cfg(test)]
@@ -0,0 +1,83 @@
+// The `pipe` API of pipes run in the screenpipe sandbox ("runtime": "sandbox" in pipe.json).
+// Written next to each such pipe as screenpipe.d.ts, start pipe.js with
+// `/// <reference path="./screenpipe.d.ts" />` and `// @ts-check` to check it in an editor.
+
+type ContentType = "all" | "ocr" | "audio" | "ui" | "audio+ui" | "ocr+ui" | "audio+ocr";
+
+interface QueryParams {
+  q?: string;
+  content_type?: ContentType;
+  /** 20 by default, at most 100 */
+  limit?: number;
+  offset?: number;
+  /** RFC 3339 */
+  start_time?: string;
+  /** RFC 3339 */
+  end_time?: string;
+  app_name?: string;
+  window_name?: string;
+}
+
+interface QueryResult {
+  /** Same items as GET /search returns */
+  data: Array<{ type: "OCR" | "Audio" | "UI"; content: Record<string, unknown> }>;
+  pagination: { limit: number; offset: number; total: number };
+}
+
+interface FetchOptions {
+  /** GET by default */
+  method?: string;
+  headers?: Record<string, string>;
+  /** Sent as it is when a string, as JSON otherwise */
+  body?: unknown;
+}
+
+interface FetchResponse {
+  status: number;
+  ok: boolean;
+  headers: Record<string, string>;
+  body: string;
+  text(): string;
+  json(): any;
+}
+
+interface PipeEvent {
+  [key: string]: unknown;
+}
+
+interface Pipe {
+  /** Name of the pipe */
+  readonly id: string;
+  /** Its pipe.json */
+  readonly config: Record<string, any>;
+  /** Searches what was recorded, needs /search in the endpoints permission */
+  query(params?: QueryParams): Promise<QueryResult>;
+  /**
+   * GET of an endpoint of the local API, e.g. `read("/meetings/12/summary")`, needs it in the
+   * endpoints permission. Screenshots, recordings and frames need the images permission and
+   * come as `{ content_type, base64 }`
+   */
+  read(path: string, params?: Record<string, string | number | boolean>): Promise<any>;
+  /** Shows a desktop notification, needs the notify permission */
+  notify(title: string, body?: string): Promise<void>;
+  /**
+   * HTTP request to a public host of the http permissions, redirects aren't followed and
+   * bodies over 10 MB fail
+   */
+  fetch(url: string, options?: FetchOptions): Promise<FetchResponse>;
+  /**
+   * Runs `handler` on each `event` screenpipe sends, needs the event in the events permission.
+   * Screenshots are left out of events without the images permission
+   */
+  on(event: string, handler: (data: PipeEvent) => void | Promise<void>): void;
+  /**
+   * Runs `handler` when the trigger `name` of the pipe.json fires, with what fired it.
+   * Throwing counts as a failed run and delays the next ones.
+   */
+  onTrigger(name: string, handler: (data: PipeEvent) => void | Promise<void>): void;
+  /** Runs `handler` every `seconds`, at least 1 */
+  every(seconds: number, handler: () => void | Promise<void>): void;
+  log(...args: unknown[]): void;
+}
+
+declare const pipe: Pipe;
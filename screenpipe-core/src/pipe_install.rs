@@ -0,0 +1,615 @@
+//! Installing pipes from git repositories and the pipe registry, pinned to a version or
+//! following the latest one, and checking the installed ones for updates.
+use crate::pipe_runtime::PipePermissions;
+use crate::pipes::download_pipe;
+use anyhow::{anyhow, Result};
+use reqwest_middleware::reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Registry `pipe install <name>` looks pipes up in
+pub const DEFAULT_REGISTRY: &str =
+    "https://raw.githubusercontent.com/mediar-ai/screenpipe/main/pipes/registry.json";
+/// Key of the pipe.json recording where the pipe was installed from
+pub const INSTALL_KEY: &str = "install";
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where a pipe is installed from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipeSource {
+    /// A git repository at `reference`, e.g. `https://github.com/me/pipe.git#v1.2.0`. Its
+    /// default branch when none
+    Git {
+        url: String,
+        reference: Option<String>,
+    },
+    /// A pipe of the registry at `version`, e.g. `obsidian@0.1.28`. Its latest when none
+    Registry {
+        name: String,
+        version: Option<String>,
+    },
+    /// A GitHub folder or a local directory, downloaded as it is
+    Download(String),
+}
+
+fn is_pipe_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_version(version: &str) -> bool {
+    !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+impl PipeSource {
+    pub fn parse(source: &str) -> Self {
+        let source = source.trim();
+        let (location, reference) = match source.rsplit_once('#') {
+            Some((location, reference)) if !reference.is_empty() => {
+                (location, Some(reference.to_string()))
+            }
+            _ => (source, None),
+        };
+        if let Some(url) = location.strip_prefix("git+") {
+            return PipeSource::Git {
+                url: url.to_string(),
+                reference,
+            };
+        }
+        if location.starts_with("git@")
+            || location.starts_with("ssh://")
+            || location.ends_with(".git")
+        {
+            return PipeSource::Git {
+                url: location.to_string(),
+                reference,
+            };
+        }
+        if Url::parse(source).is_ok() || Path::new(source).exists() {
+            return PipeSource::Download(source.to_string());
+        }
+        let (name, version) = match source.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (source, None),
+        };
+        if is_pipe_name(name) && version.is_none_or(is_version) {
+            return PipeSource::Registry {
+                name: name.to_string(),
+                version: version.map(str::to_string),
+            };
+        }
+        PipeSource::Download(source.to_string())
+    }
+}
+
+/// The id a pipe installed from the git repository `url` gets, its name.
+pub fn pipe_id_from_repository(url: &str) -> Option<String> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git");
+    is_pipe_name(name).then(|| name.to_string())
+}
+
+/// A version of a pipe of the registry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistryVersion {
+    /// Git ref the version is at, the default branch when none
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Commit the ref must be at, checked on install
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// A pipe of the registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryPipe {
+    #[serde(default)]
+    pub description: String,
+    /// Git repository of the pipe
+    pub repository: String,
+    /// Directory of the pipe in the repository, its root when none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Latest version
+    pub version: String,
+    /// Versions pipes can be pinned to, the ones with a commit. The latest one is at the
+    /// default branch when it isn't listed
+    #[serde(default)]
+    pub versions: BTreeMap<String, RegistryVersion>,
+}
+
+/// The pipes `pipe install <name>` installs, by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Registry {
+    pub pipes: BTreeMap<String, RegistryPipe>,
+}
+
+impl Registry {
+    /// The registry at `location`, a URL or a file.
+    pub async fn load(location: &str) -> Result<Self> {
+        let content = if location.starts_with("http://") || location.starts_with("https://") {
+            Client::builder()
+                .timeout(REGISTRY_TIMEOUT)
+                .build()?
+                .get(location)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?
+        } else {
+            tokio::fs::read_to_string(location).await?
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("invalid pipe registry {}: {}", location, e))
+    }
+
+    /// What installing version `version` of `name`, its latest when none, checks out. Only
+    /// versions published with a commit can be pinned, as nothing else could be verified.
+    pub fn resolve(&self, name: &str, version: Option<&str>) -> Result<InstallPlan> {
+        let pipe = self
+            .pipes
+            .get(name)
+            .ok_or_else(|| anyhow!("there is no pipe {} in the registry", name))?;
+        let (version, release) = match version {
+            // the latest, verified when it was published
+            None => (
+                pipe.version.as_str(),
+                pipe.versions
+                    .get(&pipe.version)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            Some(version) => match pipe.versions.get(version) {
+                Some(release) if release.commit.is_some() => (version, release.clone()),
+                _ => {
+                    let pinnable: Vec<&str> = pipe
+                        .versions
+                        .iter()
+                        .filter(|(_, release)| release.commit.is_some())
+                        .map(|(version, _)| version.as_str())
+                        .collect();
+                    if pinnable.is_empty() {
+                        return Err(anyhow!(
+                            "pipe {} has no published versions to pin, install {} for its \
+                             latest",
+                            name,
+                            name
+                        ));
+                    }
+                    return Err(anyhow!(
+                        "pipe {} has no published version {}, only {}",
+                        name,
+                        version,
+                        pinnable.join(", ")
+                    ));
+                }
+            },
+        };
+        Ok(InstallPlan {
+            id: name.to_string(),
+            repository: pipe.repository.clone(),
+            path: pipe.path.clone(),
+            reference: release.reference,
+            commit: release.commit,
+            version: Some(version.to_string()),
+            registry_name: Some(name.to_string()),
+        })
+    }
+}
+
+/// What installing a pipe from git checks out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallPlan {
+    pub id: String,
+    pub repository: String,
+    pub path: Option<String>,
+    pub reference: Option<String>,
+    /// Commit the checkout must be at
+    pub commit: Option<String>,
+    pub version: Option<String>,
+    pub registry_name: Option<String>,
+}
+
+/// Where an installed pipe comes from, the `install` of its pipe.json.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstallRecord {
+    /// What it was installed with, e.g. `obsidian@0.1.28`
+    pub source: String,
+    pub repository: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Name in the registry, none for pipes installed from git
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Version or git ref the pipe is pinned to, none when it follows the latest. A git ref
+    /// pins the commit it was at when installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<String>,
+    /// Commit installed
+    pub commit: String,
+    /// RFC 3339
+    pub installed_at: String,
+}
+
+impl InstallRecord {
+    /// The record of the pipe.json `pipe_config`, none for pipes not installed from git or
+    /// the registry.
+    pub fn from_config(pipe_config: &Value) -> Option<Self> {
+        serde_json::from_value(pipe_config.get(INSTALL_KEY)?.clone()).ok()
+    }
+
+    /// The source installing the latest version again, or the pinned one: the version of
+    /// the registry or the commit a git ref was at.
+    pub fn source(&self) -> PipeSource {
+        match &self.registry_name {
+            Some(name) => PipeSource::Registry {
+                name: name.clone(),
+                version: self.pinned.clone(),
+            },
+            None => PipeSource::Git {
+                url: self.repository.clone(),
+                reference: self.pinned.as_ref().map(|_| self.commit.clone()),
+            },
+        }
+    }
+}
+
+/// A pipe once installed.
+#[derive(Debug, Clone)]
+pub struct InstalledPipe {
+    pub id: String,
+    pub dir: PathBuf,
+    /// None for pipes downloaded rather than installed from git or the registry
+    pub record: Option<InstallRecord>,
+    /// What a sandboxed pipe is allowed to do, none for the other pipes
+    pub permissions: Option<PipePermissions>,
+    /// Permissions the update asked for on top of the ones the pipe had, held back as they
+    /// weren't accepted. The pipe keeps its old ones and is disabled
+    pub held_permissions: Vec<String>,
+}
+
+async fn git(args: &[&str], dir: Option<&Path>) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command
+        .args(args)
+        // a missing repository fails instead of asking for credentials
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run git, is it installed? {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// `path` of a registry entry, which must stay in the repository
+fn relative_path(path: &str) -> Result<&Path> {
+    let path = Path::new(path);
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path)
+    } else {
+        Err(anyhow!(
+            "path {} isn't inside the repository",
+            path.display()
+        ))
+    }
+}
+
+/// Checks out `plan` and verifies it is at the commit expected and is a pipe, returning
+/// the directory of the pipe and the commit.
+async fn checkout(plan: &InstallPlan, dir: &Path) -> Result<(PathBuf, String)> {
+    let checkout = dir.join("checkout");
+    let checkout_arg = checkout.to_string_lossy().into_owned();
+    info!("cloning {}", plan.repository);
+    // the latest version only needs the last commit, a pinned one may be further back
+    let target = plan.reference.as_ref().or(plan.commit.as_ref());
+    let mut args = vec!["clone", "--quiet"];
+    if target.is_none() {
+        args.extend(["--depth", "1"]);
+    }
+    args.extend(["--", plan.repository.as_str(), checkout_arg.as_str()]);
+    git(&args, None).await?;
+    if let Some(reference) = target {
+        if reference.starts_with('-') {
+            return Err(anyhow!("invalid git ref {}", reference));
+        }
+        git(&["checkout", "--quiet", reference, "--"], Some(&checkout)).await?;
+    }
+    let commit = git(&["rev-parse", "HEAD"], Some(&checkout)).await?;
+    if let Some(expected) = &plan.commit {
+        if !commit.eq_ignore_ascii_case(expected) {
+            return Err(anyhow!(
+                "{} is at commit {} rather than {}, refusing to install it",
+                plan.repository,
+                commit,
+                expected
+            ));
+        }
+    }
+
+    let pipe_dir = match &plan.path {
+        Some(path) => checkout.join(relative_path(path)?),
+        None => checkout.clone(),
+    };
+    if !pipe_dir.join("pipe.json").exists() && !pipe_dir.join("package.json").exists() {
+        return Err(anyhow!(
+            "{} has no pipe.json nor package.json, it isn't a pipe",
+            plan.repository
+        ));
+    }
+    // the pipe gets the id it is installed as, whatever its directory is called
+    let staged = dir.join("staged").join(&plan.id);
+    tokio::fs::create_dir_all(dir.join("staged")).await?;
+    tokio::fs::rename(&pipe_dir, &staged).await?;
+    Ok((staged, commit))
+}
+
+fn read_permissions(pipe_config: &Value) -> Result<Option<PipePermissions>> {
+    if !crate::pipe_runtime::is_script_pipe(pipe_config) {
+        return Ok(None);
+    }
+    match pipe_config.get("permissions") {
+        Some(permissions) => serde_json::from_value(permissions.clone())
+            .map(Some)
+            .map_err(|e| anyhow!("invalid permissions in pipe.json: {}", e)),
+        None => Ok(Some(PipePermissions::default())),
+    }
+}
+
+async fn read_pipe_config(pipe_dir: &Path) -> Result<Value> {
+    let path = pipe_dir.join("pipe.json");
+    if !path.exists() {
+        return Ok(json!({}));
+    }
+    serde_json::from_str(&tokio::fs::read_to_string(&path).await?)
+        .map_err(|e| anyhow!("invalid pipe.json: {}", e))
+}
+
+/// Installs the pipe of `source` in `screenpipe_dir`, looking names up in the registry at
+/// `registry`. Pipes installed before are updated, keeping their settings, and only get the
+/// permissions they ask for on top of their old ones with `accept_permissions`. New pipes
+/// are left disabled.
+pub async fn install_pipe(
+    source: &PipeSource,
+    screenpipe_dir: &Path,
+    registry: &str,
+    accept_permissions: bool,
+) -> Result<InstalledPipe> {
+    let (plan, label, pinned) = match source {
+        PipeSource::Download(source) => {
+            let dir = download_pipe(source, screenpipe_dir.to_path_buf()).await?;
+            let config = read_pipe_config(&dir).await?;
+            return Ok(InstalledPipe {
+                id: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                permissions: read_permissions(&config)?,
+                dir,
+                record: None,
+                held_permissions: Vec::new(),
+            });
+        }
+        PipeSource::Git { url, reference } => {
+            let id = pipe_id_from_repository(url)
+                .ok_or_else(|| anyhow!("can't name a pipe after {}", url))?;
+            let plan = InstallPlan {
+                id,
+                repository: url.clone(),
+                path: None,
+                reference: reference.clone(),
+                commit: None,
+                version: None,
+                registry_name: None,
+            };
+            let label = match reference {
+                Some(reference) => format!("{}#{}", url, reference),
+                None => url.clone(),
+            };
+            (plan, label, reference.clone())
+        }
+        PipeSource::Registry { name, version } => {
+            let plan = Registry::load(registry)
+                .await?
+                .resolve(name, version.as_deref())?;
+            let label = format!("{}@{}", name, plan.version.as_deref().unwrap_or_default());
+            (plan, label, version.clone())
+        }
+    };
+
+    let work_dir = tempfile::tempdir()?;
+    let (staged, commit) = checkout(&plan, work_dir.path()).await?;
+    let manifest = read_pipe_config(&staged).await?;
+    let mut permissions = read_permissions(&manifest)?;
+
+    // what the user agreed to when the pipe was installed before
+    let previous_dir = screenpipe_dir.join("pipes").join(&plan.id);
+    let previous = if previous_dir.join("pipe.json").exists() {
+        Some(read_pipe_config(&previous_dir).await?)
+    } else {
+        None
+    };
+    let (previous_permissions, held_permissions) = match (&previous, &permissions) {
+        (Some(previous), Some(permissions)) => {
+            let previous_permissions = read_permissions(previous)
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            let added = previous_permissions.added_by(permissions);
+            (Some(previous_permissions), added)
+        }
+        _ => (None, Vec::new()),
+    };
+    let hold = !held_permissions.is_empty() && !accept_permissions;
+    if hold {
+        warn!(
+            "pipe {} asks for more permissions, keeping its old ones and disabling it: {}",
+            plan.id,
+            held_permissions.join(", ")
+        );
+        permissions = previous_permissions;
+    }
+
+    let dir = download_pipe(&staged.to_string_lossy(), screenpipe_dir.to_path_buf()).await?;
+
+    let record = InstallRecord {
+        source: label,
+        repository: plan.repository,
+        path: plan.path,
+        registry_name: plan.registry_name,
+        version: plan.version,
+        pinned,
+        commit,
+        installed_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut config = read_pipe_config(&dir).await?;
+    if let Some(config) = config.as_object_mut() {
+        config.insert(INSTALL_KEY.to_string(), serde_json::to_value(&record)?);
+        config.insert("source".to_string(), json!(record.source));
+        // updates keep the settings of the pipe but get the permissions of the new version,
+        // unless they grew without the user agreeing
+        let granted = match (hold, &previous) {
+            (true, Some(previous)) => previous.get("permissions"),
+            _ => manifest.get("permissions"),
+        };
+        match granted {
+            Some(granted) => config.insert("permissions".to_string(), granted.clone()),
+            None => config.remove("permissions"),
+        };
+        if hold {
+            config.insert("enabled".to_string(), json!(false));
+        }
+    }
+    tokio::fs::write(
+        dir.join("pipe.json"),
+        serde_json::to_string_pretty(&config)?,
+    )
+    .await?;
+    info!("installed pipe {} at commit {}", plan.id, record.commit);
+
+    Ok(InstalledPipe {
+        id: plan.id,
+        dir,
+        record: Some(record),
+        permissions,
+        held_permissions: if hold { held_permissions } else { Vec::new() },
+    })
+}
+
+/// Whether an installed pipe has a newer version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipeUpdate {
+    pub id: String,
+    /// Version installed, its commit for pipes installed from git
+    pub installed: String,
+    /// Newer version or commit, none when up to date or pinned
+    pub latest: Option<String>,
+    pub pinned: Option<String>,
+    /// Why it couldn't be checked
+    pub error: Option<String>,
+}
+
+/// The newer version of `record`, none when it is up to date or pinned.
+pub async fn check_update(
+    record: &InstallRecord,
+    registry: Option<&Registry>,
+) -> Result<Option<String>> {
+    if record.pinned.is_some() {
+        return Ok(None);
+    }
+    if let Some(name) = &record.registry_name {
+        let registry = registry.ok_or_else(|| anyhow!("the registry is unavailable"))?;
+        let latest = registry.resolve(name, None)?.version;
+        return Ok(latest.filter(|latest| Some(latest) != record.version.as_ref()));
+    }
+    let head = git(&["ls-remote", "--", &record.repository, "HEAD"], None).await?;
+    let latest = head
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow!("{} has no HEAD", record.repository))?;
+    Ok((!latest.eq_ignore_ascii_case(&record.commit)).then(|| latest.to_string()))
+}
+
+/// Checks the pipes of `screenpipe_dir` installed from git or the registry for updates.
+pub async fn check_updates(screenpipe_dir: &Path, registry: &str) -> Result<Vec<PipeUpdate>> {
+    let mut records = Vec::new();
+    let mut entries = match tokio::fs::read_dir(screenpipe_dir.join("pipes")).await {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let id = entry.file_name().to_string_lossy().into_owned();
+        if id.starts_with('.') || !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let Ok(config) = read_pipe_config(&entry.path()).await else {
+            continue;
+        };
+        if let Some(record) = InstallRecord::from_config(&config) {
+            records.push((id, record));
+        }
+    }
+    records.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let registry = if records
+        .iter()
+        .any(|(_, record)| record.registry_name.is_some() && record.pinned.is_none())
+    {
+        Registry::load(registry)
+            .await
+            .map_err(|e| warn!("failed to load the pipe registry: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let mut updates = Vec::new();
+    for (id, record) in records {
+        debug!("checking pipe {} for updates", id);
+        let (latest, error) = match check_update(&record, registry.as_ref()).await {
+            Ok(latest) => (latest, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        updates.push(PipeUpdate {
+            id,
+            installed: record
+                .version
+                .clone()
+                .unwrap_or_else(|| record.commit.chars().take(12).collect()),
+            latest,
+            pinned: record.pinned,
+            error,
+        });
+    }
+    Ok(updates)
+}
@@ -0,0 +1,936 @@
+//! Pipes run inside screenpipe by a sandboxed QuickJS engine rather than as bun processes.
+//! Scripts reach nothing but the `pipe` API: searching what was recorded, events,
+//! notifications, and the local endpoints and HTTP hosts their pipe.json allows. Each pipe
+//! has its own thread, memory limit and time limit per handler, so a broken pipe can't
+//! stall the others or screenpipe.
+use anyhow::{anyhow, Result};
+use reqwest_middleware::reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest_middleware::reqwest::{redirect, Client, Method};
+use rquickjs::prelude::Async;
+use rquickjs::{async_with, AsyncContext, AsyncRuntime, CatchResultExt, Function, Promise};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+use url::{Host, Url};
+
+/// `runtime` of the pipe.json of pipes run in the sandbox
+pub const SANDBOX_RUNTIME: &str = "sandbox";
+/// Script run when the pipe.json names no `main`
+pub const DEFAULT_SCRIPT: &str = "pipe.js";
+/// Type declarations of the `pipe` API, for editors checking pipes with `// @ts-check`
+pub const PIPE_API_TYPES: &str = include_str!("pipe_api.d.ts");
+/// Prefix of the events of `pipe.onTrigger` handlers, `trigger:<name>`
+pub const TRIGGER_PREFIX: &str = "trigger:";
+// Events waiting for a busy pipe, newer ones are dropped
+const EVENT_QUEUE: usize = 256;
+const MAX_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+// keys events and answers carry screenshots and frames in, as base64
+const IMAGE_KEYS: [&str; 3] = ["image", "frame", "screenshot"];
+/// Endpoint `pipe.query` searches, which the endpoints permission must allow
+pub const QUERY_ENDPOINT: &str = "/search";
+
+// wraps the native functions into the `pipe` API, native calls pass JSON strings and
+// reply with `{"ok": value}` or `{"error": message}`
+const PRELUDE: &str = r#"
+(() => {
+  const handlers = {};
+  const intervals = [];
+  const call = async (native, ...args) => {
+    const reply = JSON.parse(await native(...args.map((arg) => JSON.stringify(arg ?? null))));
+    if ("error" in reply) throw new Error(reply.error);
+    return reply.ok;
+  };
+  const format = (args) =>
+    args.map((arg) => (typeof arg === "string" ? arg : JSON.stringify(arg))).join(" ");
+  const log = (level) => (...args) => __sp_log(level, format(args));
+  globalThis.console = Object.freeze({
+    log: log("info"),
+    info: log("info"),
+    debug: log("debug"),
+    warn: log("warn"),
+    error: log("error"),
+  });
+  globalThis.pipe = Object.freeze({
+    id: __sp_id,
+    config: Object.freeze(JSON.parse(__sp_config)),
+    query: (params) => call(__sp_query, params ?? {}),
+    read: (path, params) => call(__sp_read, String(path), params ?? {}),
+    notify: (title, body) => call(__sp_notify, String(title), String(body ?? "")),
+    fetch: async (url, options) => {
+      const response = await call(__sp_fetch, { ...(options ?? {}), url: String(url) });
+      return { ...response, text: () => response.body, json: () => JSON.parse(response.body) };
+    },
+    on: (event, handler) => {
+      (handlers[event] ??= []).push(handler);
+    },
+    onTrigger: (name, handler) => {
+      (handlers["trigger:" + name] ??= []).push(handler);
+    },
+    every: (seconds, handler) => {
+      intervals.push({ seconds: Number(seconds), handler });
+    },
+    log: log("info"),
+  });
+  globalThis.__sp_events = () => JSON.stringify(Object.keys(handlers));
+  globalThis.__sp_intervals = () => JSON.stringify(intervals.map(({ seconds }) => seconds));
+  globalThis.__sp_dispatch = async (event, data) => {
+    const payload = JSON.parse(data);
+    for (const handler of handlers[event] ?? []) await handler(payload);
+  };
+  globalThis.__sp_tick = async (index) => {
+    await intervals[Number(index)].handler();
+  };
+})();
+"#;
+
+pub type HostFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// What screenpipe does for pipes: their searches and notifications.
+pub trait PipeHost: Send + Sync {
+    /// Searches what was recorded, `params` being the query parameters of `GET /search`.
+    fn query(&self, params: Value) -> HostFuture<'_, Value>;
+    /// Shows a desktop notification from `pipe`.
+    fn notify(&self, pipe: String, title: String, body: String) -> HostFuture<'_, ()>;
+    /// Answers a GET of an endpoint of the local API, the JSON or text it answers, images
+    /// as `{"content_type", "base64"}`.
+    fn read(&self, request: ReadRequest) -> HostFuture<'_, Value>;
+    /// Port of the local API, which `pipe.fetch` never reaches.
+    fn api_port(&self) -> Option<u16> {
+        None
+    }
+}
+
+/// A read of the local API by a pipe, once its permissions allow the endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadRequest {
+    pub pipe: String,
+    /// e.g. `/meetings/12/summary`
+    pub path: String,
+    /// Query parameters
+    pub params: Value,
+    /// Whether screenshots, recordings and frames may be answered, text only otherwise
+    pub images: bool,
+}
+
+/// What a pipe is allowed to do, the `permissions` of its pipe.json. Pipes without any may
+/// only run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipePermissions {
+    /// Show desktop notifications
+    pub notify: bool,
+    /// Events the pipe can subscribe to, `*` for all of them
+    pub events: Vec<String>,
+    /// Hosts the pipe can send HTTP requests to, `*.example.com` for its subdomains
+    pub http: Vec<String>,
+    /// Endpoints of the local API the pipe can read with `pipe.read`, `/meetings/*` for
+    /// the ones under it. `pipe.query` needs `/search`
+    pub endpoints: Vec<String>,
+    /// Read screenshots, recordings and frames, rather than only text
+    pub images: bool,
+}
+
+impl Default for PipePermissions {
+    fn default() -> Self {
+        Self {
+            notify: false,
+            events: Vec::new(),
+            http: Vec::new(),
+            endpoints: Vec::new(),
+            images: false,
+        }
+    }
+}
+
+impl PipePermissions {
+    pub fn allows_event(&self, event: &str) -> bool {
+        self.events
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == event)
+    }
+
+    /// Whether the pipe can read `path` of the local API.
+    pub fn allows_endpoint(&self, path: &str) -> bool {
+        if !path.starts_with('/')
+            || path.contains(['?', '#', '\\'])
+            || path.split('/').any(|segment| segment == "..")
+        {
+            return false;
+        }
+        let path = path.trim_end_matches('/');
+        self.endpoints.iter().any(|allowed| {
+            let allowed = allowed.trim();
+            if allowed == "*" {
+                return true;
+            }
+            match allowed.strip_suffix("/*") {
+                Some(prefix) => path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/')),
+                None => path == allowed.trim_end_matches('/'),
+            }
+        })
+    }
+
+    /// What `newer` allows that these permissions don't, e.g. `http api.example.com`.
+    pub fn added_by(&self, newer: &PipePermissions) -> Vec<String> {
+        let mut added = Vec::new();
+        if newer.notify && !self.notify {
+            added.push("notify".to_string());
+        }
+        if newer.images && !self.images {
+            added.push("images".to_string());
+        }
+        for event in &newer.events {
+            if !self.allows_event(event) {
+                added.push(format!("event {}", event));
+            }
+        }
+        for endpoint in &newer.endpoints {
+            if !self.endpoints.contains(endpoint) && !self.allows_endpoint(endpoint) {
+                added.push(format!("endpoint {}", endpoint));
+            }
+        }
+        for host in &newer.http {
+            let covered = self.http.contains(host)
+                || (!host.contains('*')
+                    && Url::parse(&format!("https://{}", host.trim()))
+                        .is_ok_and(|url| self.allows_url(&url)));
+            if !covered {
+                added.push(format!("http {}", host));
+            }
+        }
+        added
+    }
+
+    /// Whether the pipe can send a request to `url`, over http or https only. Hosts of the
+    /// machine and its network are out of reach whatever the permissions, names are checked
+    /// again once resolved.
+    pub fn allows_url(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let host = match url.host() {
+            Some(Host::Domain(host)) => host.trim_end_matches('.').to_lowercase(),
+            Some(Host::Ipv4(ip)) if is_public_ip(&IpAddr::V4(ip)) => ip.to_string(),
+            Some(Host::Ipv6(ip)) if is_public_ip(&IpAddr::V6(ip)) => ip.to_string(),
+            _ => return false,
+        };
+        if host == "localhost" || host.ends_with(".localhost") {
+            return false;
+        }
+        self.http.iter().any(|allowed| {
+            let allowed = allowed.trim().to_lowercase();
+            if allowed == "*" {
+                return true;
+            }
+            match allowed.strip_prefix("*.") {
+                Some(domain) => host.ends_with(&format!(".{}", domain)),
+                None => host == allowed,
+            }
+        })
+    }
+}
+
+/// Removes the screenshots and frames of `value`, at any depth, for pipes without the
+/// images permission.
+pub fn strip_images(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|key, _| !IMAGE_KEYS.contains(&key.as_str()));
+            object.values_mut().for_each(strip_images);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_images),
+        _ => {}
+    }
+}
+
+/// Whether `ip` is on the internet rather than the machine or a private network.
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || a >= 240
+                // shared address space of carrier-grade NAT
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(&IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local and link-local
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+// resolves the hosts of `pipe.fetch` to their public addresses only, so a name pointing at
+// 127.0.0.1 or the local network leads nowhere
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve_public(name))
+    }
+}
+
+async fn resolve_public(name: Name) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+        .await?
+        .filter(|addr| is_public_ip(&addr.ip()))
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{} has no public address pipes can reach", name.as_str()).into());
+    }
+    Ok(Box::new(addrs.into_iter()))
+}
+
+/// How much a pipe may use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipeLimits {
+    pub memory_bytes: usize,
+    pub stack_bytes: usize,
+    /// Longest an event handler, interval or the script itself may run
+    pub handler_timeout: Duration,
+}
+
+impl Default for PipeLimits {
+    fn default() -> Self {
+        Self {
+            memory_bytes: 64 * 1024 * 1024,
+            stack_bytes: 1024 * 1024,
+            handler_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Something that happened in screenpipe, passed to the handlers the pipe registered with
+/// `pipe.on(name, handler)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipeEvent {
+    pub name: String,
+    pub data: Value,
+}
+
+/// A pipe run in the sandbox.
+#[derive(Debug, Clone)]
+pub struct ScriptPipe {
+    pub id: String,
+    pub source: String,
+    /// Its pipe.json, `pipe.config` to the script
+    pub config: Value,
+    pub permissions: PipePermissions,
+    pub limits: PipeLimits,
+}
+
+/// Whether the pipe of `pipe_config`, its pipe.json, runs in the sandbox.
+pub fn is_script_pipe(pipe_config: &Value) -> bool {
+    pipe_config.get("runtime").and_then(Value::as_str) == Some(SANDBOX_RUNTIME)
+}
+
+impl ScriptPipe {
+    /// The pipe in `pipe_dir` when its pipe.json runs it in the sandbox.
+    pub async fn load(id: &str, pipe_dir: &Path) -> Result<Option<Self>> {
+        let pipe_json_path = pipe_dir.join("pipe.json");
+        if !pipe_json_path.exists() {
+            return Ok(None);
+        }
+        let config: Value =
+            serde_json::from_str(&tokio::fs::read_to_string(&pipe_json_path).await?)?;
+        if !is_script_pipe(&config) {
+            return Ok(None);
+        }
+        let permissions = match config.get("permissions") {
+            Some(permissions) => serde_json::from_value(permissions.clone())
+                .map_err(|e| anyhow!("invalid permissions in pipe.json: {}", e))?,
+            None => PipePermissions::default(),
+        };
+        let main = config
+            .get("main")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_SCRIPT);
+        let script_path = pipe_dir.join(main);
+        // the script is read from the pipe's own directory only
+        if !script_path.starts_with(pipe_dir) || main.contains("..") {
+            return Err(anyhow!("main must be a file in the pipe directory"));
+        }
+        let source = tokio::fs::read_to_string(&script_path)
+            .await
+            .map_err(|e| anyhow!("failed to read {}: {}", script_path.display(), e))?;
+        Ok(Some(Self {
+            id: id.to_string(),
+            source,
+            config,
+            permissions,
+            limits: PipeLimits::default(),
+        }))
+    }
+}
+
+// an event and where to reply once its handlers ran, when someone waits for them
+type QueuedEvent = (PipeEvent, Option<oneshot::Sender<Result<()>>>);
+
+/// A pipe running in the sandbox.
+pub struct ScriptPipeHandle {
+    events: Vec<String>,
+    // whether events may carry screenshots to the pipe
+    images: bool,
+    events_tx: mpsc::Sender<QueuedEvent>,
+    shutdown: watch::Sender<bool>,
+    done: watch::Receiver<bool>,
+}
+
+impl ScriptPipeHandle {
+    /// Events the pipe has handlers for.
+    pub fn events(&self) -> &[String] {
+        &self.events
+    }
+
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.iter().any(|name| name == event)
+    }
+
+    fn allowed(&self, mut event: PipeEvent) -> PipeEvent {
+        if !self.images {
+            strip_images(&mut event.data);
+        }
+        event
+    }
+
+    /// Queues `event` for the pipe, dropped when the pipe doesn't handle it or is behind.
+    /// Its screenshots are left out unless the pipe may see images.
+    pub fn send(&self, event: PipeEvent) {
+        if !self.wants(&event.name) {
+            return;
+        }
+        let event = self.allowed(event);
+        if let Err(mpsc::error::TrySendError::Full((event, _))) =
+            self.events_tx.try_send((event, None))
+        {
+            warn!("pipe is behind, dropped its {} event", event.name);
+        }
+    }
+
+    /// Runs the handlers of `event` and waits for them, failing when one of them throws or
+    /// the pipe has none.
+    pub async fn run(&self, event: PipeEvent) -> Result<()> {
+        if !self.wants(&event.name) {
+            return Err(anyhow!("pipe has no {} handler", event.name));
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.events_tx
+            .send((self.allowed(event), Some(reply_tx)))
+            .await
+            .map_err(|_| anyhow!("pipe stopped"))?;
+        reply_rx.await.map_err(|_| anyhow!("pipe stopped"))?
+    }
+
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    /// Waits for the pipe to stop.
+    pub async fn wait(&self) {
+        let mut done = self.done.clone();
+        while !*done.borrow_and_update() {
+            if done.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Starts `pipe` on a thread of its own. Fails when its script throws or runs out of time.
+pub async fn start_script_pipe(
+    pipe: ScriptPipe,
+    host: Arc<dyn PipeHost>,
+) -> Result<ScriptPipeHandle> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (events_tx, events_rx) = mpsc::channel(EVENT_QUEUE);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (done_tx, done_rx) = watch::channel(false);
+    let host_runtime = tokio::runtime::Handle::current();
+    let id = pipe.id.clone();
+    let images = pipe.permissions.images;
+
+    std::thread::Builder::new()
+        .name(format!("pipe-{}", id))
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(anyhow!(e)));
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                let id = pipe.id.clone();
+                let sandbox = match Sandbox::new(pipe, host, host_runtime).await {
+                    Ok(sandbox) => sandbox,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(sandbox.events.clone()));
+                sandbox.run(events_rx, shutdown_rx).await;
+                info!("[{}] stopped", id);
+            });
+            let _ = done_tx.send(true);
+        })?;
+
+    let events = ready_rx
+        .await
+        .map_err(|_| anyhow!("pipe {} stopped while starting", id))??;
+    info!("[{}] started in the sandbox, handling {:?}", id, events);
+    Ok(ScriptPipeHandle {
+        events,
+        images,
+        events_tx,
+        shutdown: shutdown_tx,
+        done: done_rx,
+    })
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn reply(result: Result<Value>) -> String {
+    match result {
+        Ok(value) => json!({ "ok": value }),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+    .to_string()
+}
+
+/// Parses an argument of a native call, which scripts pass as JSON.
+fn argument<T: for<'de> Deserialize<'de>>(json: &str) -> Result<T> {
+    serde_json::from_str(json).map_err(|e| anyhow!("invalid argument: {}", e))
+}
+
+#[derive(Debug, Deserialize)]
+struct FetchRequest {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    /// Sent as it is when a string, as JSON otherwise
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+struct Interval {
+    period: Duration,
+    due: Instant,
+}
+
+struct Sandbox {
+    id: String,
+    runtime: AsyncRuntime,
+    context: AsyncContext,
+    // milliseconds since the epoch the running handler is interrupted at, 0 when idle
+    deadline: Arc<AtomicU64>,
+    limits: PipeLimits,
+    events: Vec<String>,
+    intervals: Vec<Interval>,
+}
+
+impl Sandbox {
+    async fn new(
+        pipe: ScriptPipe,
+        host: Arc<dyn PipeHost>,
+        host_runtime: tokio::runtime::Handle,
+    ) -> Result<Self> {
+        let runtime = AsyncRuntime::new()?;
+        runtime.set_memory_limit(pipe.limits.memory_bytes).await;
+        runtime.set_max_stack_size(pipe.limits.stack_bytes).await;
+        let deadline = Arc::new(AtomicU64::new(0));
+        let interrupt_at = deadline.clone();
+        runtime
+            .set_interrupt_handler(Some(Box::new(move || {
+                let deadline = interrupt_at.load(Ordering::Relaxed);
+                deadline != 0 && now_millis() > deadline
+            })))
+            .await;
+        let context = AsyncContext::full(&runtime).await?;
+
+        let id = pipe.id.clone();
+        let config = pipe.config.to_string();
+        let permissions = Arc::new(pipe.permissions.clone());
+        let api_port = host.api_port();
+        let http = Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            // a redirect could lead anywhere, scripts follow them themselves
+            .redirect(redirect::Policy::none())
+            // a proxy would resolve the hosts itself
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()?;
+        async_with!(context => |ctx| {
+            let globals = ctx.globals();
+            globals.set("__sp_id", id.clone())?;
+            globals.set("__sp_config", config)?;
+
+            let log_id = id.clone();
+            globals.set(
+                "__sp_log",
+                Function::new(ctx.clone(), move |level: String, message: String| {
+                    match level.as_str() {
+                        "error" => error!("[{}] {}", log_id, message),
+                        "warn" => warn!("[{}] {}", log_id, message),
+                        "debug" => debug!("[{}] {}", log_id, message),
+                        _ => info!("[{}] {}", log_id, message),
+                    }
+                })?,
+            )?;
+
+            let (query_host, query_runtime) = (host.clone(), host_runtime.clone());
+            let query_permissions = permissions.clone();
+            globals.set(
+                "__sp_query",
+                Function::new(
+                    ctx.clone(),
+                    Async(move |params: String| {
+                        let host = query_host.clone();
+                        let runtime = query_runtime.clone();
+                        let allowed = query_permissions.allows_endpoint(QUERY_ENDPOINT);
+                        async move {
+                            reply(async {
+                                if !allowed {
+                                    return Err(anyhow!(
+                                        "the pipe is not allowed to query, add {} to the \
+                                         endpoints permission of its pipe.json",
+                                        QUERY_ENDPOINT
+                                    ));
+                                }
+                                let params: Value = argument(&params)?;
+                                runtime.spawn(async move { host.query(params).await }).await?
+                            }
+                            .await)
+                        }
+                    }),
+                )?,
+            )?;
+
+            let (notify_host, notify_runtime) = (host.clone(), host_runtime.clone());
+            let notify_permissions = permissions.clone();
+            let notify_id = id.clone();
+            globals.set(
+                "__sp_notify",
+                Function::new(
+                    ctx.clone(),
+                    Async(move |title: String, body: String| {
+                        let host = notify_host.clone();
+                        let runtime = notify_runtime.clone();
+                        let allowed = notify_permissions.notify;
+                        let id = notify_id.clone();
+                        async move {
+                            reply(async {
+                                if !allowed {
+                                    return Err(anyhow!("the pipe is not allowed to notify"));
+                                }
+                                let (title, body): (String, String) =
+                                    (argument(&title)?, argument(&body)?);
+                                runtime
+                                    .spawn(async move { host.notify(id, title, body).await })
+                                    .await??;
+                                Ok(Value::Null)
+                            }
+                            .await)
+                        }
+                    }),
+                )?,
+            )?;
+
+            let (read_host, read_runtime) = (host.clone(), host_runtime.clone());
+            let read_permissions = permissions.clone();
+            let read_id = id.clone();
+            globals.set(
+                "__sp_read",
+                Function::new(
+                    ctx.clone(),
+                    Async(move |path: String, params: String| {
+                        let host = read_host.clone();
+                        let runtime = read_runtime.clone();
+                        let permissions = read_permissions.clone();
+                        let id = read_id.clone();
+                        async move {
+                            reply(async {
+                                let path: String = argument(&path)?;
+                                if !permissions.allows_endpoint(&path) {
+                                    return Err(anyhow!("the pipe is not allowed to read {}", path));
+                                }
+                                let request = ReadRequest {
+                                    pipe: id,
+                                    path,
+                                    params: argument(&params)?,
+                                    images: permissions.images,
+                                };
+                                runtime.spawn(async move { host.read(request).await }).await?
+                            }
+                            .await)
+                        }
+                    }),
+                )?,
+            )?;
+
+            let fetch_permissions = permissions.clone();
+            globals.set(
+                "__sp_fetch",
+                Function::new(
+                    ctx.clone(),
+                    Async(move |request: String| {
+                        let http = http.clone();
+                        let permissions = fetch_permissions.clone();
+                        async move {
+                            reply(async {
+                                fetch(&http, &permissions, api_port, argument(&request)?).await
+                            }
+                            .await)
+                        }
+                    }),
+                )?,
+            )?;
+
+            ctx.eval::<(), _>(PRELUDE)?;
+            Ok::<_, rquickjs::Error>(())
+        })
+        .await
+        .map_err(|e| anyhow!("failed to set up the sandbox: {}", e))?;
+
+        let mut sandbox = Self {
+            id: pipe.id,
+            runtime,
+            context,
+            deadline,
+            limits: pipe.limits,
+            events: Vec::new(),
+            intervals: Vec::new(),
+        };
+        sandbox.eval(pipe.source).await?;
+
+        let events: Vec<String> = serde_json::from_str(&sandbox.call_json("__sp_events").await?)?;
+        // triggers are checked against the permissions by the schedule firing them
+        let (events, denied): (Vec<String>, Vec<String>) = events.into_iter().partition(|event| {
+            event.starts_with(TRIGGER_PREFIX) || pipe.permissions.allows_event(event)
+        });
+        if !denied.is_empty() {
+            warn!(
+                "[{}] is not allowed to subscribe to {:?}, its handlers won't run",
+                sandbox.id, denied
+            );
+        }
+        sandbox.events = events;
+        let intervals: Vec<f64> =
+            serde_json::from_str(&sandbox.call_json("__sp_intervals").await?)?;
+        let now = Instant::now();
+        sandbox.intervals = intervals
+            .into_iter()
+            .map(|seconds| {
+                let period = Duration::from_secs_f64(seconds.max(0.0)).max(MIN_INTERVAL);
+                Interval {
+                    period,
+                    due: now + period,
+                }
+            })
+            .collect();
+        Ok(sandbox)
+    }
+
+    /// Runs `f` under the handler time limit, then the promises it left behind.
+    async fn limited<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        let timeout = self.limits.handler_timeout;
+        self.deadline
+            .store(now_millis() + timeout.as_millis() as u64, Ordering::Relaxed);
+        let result = match tokio::time::timeout(timeout, async {
+            let result = f.await;
+            self.runtime.idle().await;
+            result
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("timed out after {:?}", timeout)),
+        };
+        self.deadline.store(0, Ordering::Relaxed);
+        result
+    }
+
+    async fn eval(&self, source: String) -> Result<()> {
+        self.limited(async_with!(self.context => |ctx| {
+            ctx.eval::<(), _>(source)
+                .catch(&ctx)
+                .map_err(|e| anyhow!("{}", e))
+        }))
+        .await
+    }
+
+    async fn call_json(&self, function: &'static str) -> Result<String> {
+        self.limited(async_with!(self.context => |ctx| {
+            let run = || -> rquickjs::Result<String> {
+                let function: Function = ctx.globals().get(function)?;
+                function.call(())
+            };
+            run().catch(&ctx).map_err(|e| anyhow!("{}", e))
+        }))
+        .await
+    }
+
+    /// Runs the async JS `function` with `args` and waits for it.
+    async fn call_async(&self, function: &'static str, args: (String, String)) -> Result<()> {
+        self.limited(async_with!(self.context => |ctx| {
+            let run = async {
+                let function: Function = ctx.globals().get(function)?;
+                let promise: Promise = function.call(args)?;
+                promise.into_future::<()>().await
+            };
+            run.await.catch(&ctx).map_err(|e| anyhow!("{}", e))
+        }))
+        .await
+    }
+
+    async fn run(
+        mut self,
+        mut events: mpsc::Receiver<QueuedEvent>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        loop {
+            let next = self
+                .intervals
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, interval)| interval.due)
+                .map(|(index, interval)| (index, interval.due));
+            tokio::select! {
+                _ = shutdown.changed() => return,
+                event = events.recv() => {
+                    let Some((event, reply)) = event else { return };
+                    let args = (event.name.clone(), event.data.to_string());
+                    let result = self.call_async("__sp_dispatch", args).await;
+                    if let Err(e) = &result {
+                        error!("[{}] {} handler failed: {}", self.id, event.name, e);
+                    }
+                    if let Some(reply) = reply {
+                        let _ = reply.send(result);
+                    }
+                }
+                _ = tokio::time::sleep_until(next.map_or_else(Instant::now, |(_, due)| due)),
+                    if next.is_some() =>
+                {
+                    let Some((index, _)) = next else { continue };
+                    let interval = &mut self.intervals[index];
+                    interval.due = Instant::now() + interval.period;
+                    if let Err(e) = self
+                        .call_async("__sp_tick", (index.to_string(), String::new()))
+                        .await
+                    {
+                        error!("[{}] interval handler failed: {}", self.id, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn fetch(
+    http: &Client,
+    permissions: &PipePermissions,
+    api_port: Option<u16>,
+    request: FetchRequest,
+) -> Result<Value> {
+    let url = Url::parse(&request.url)?;
+    if !permissions.allows_url(&url) {
+        return Err(anyhow!(
+            "the pipe is not allowed to reach {}, add the host to the http permissions of \
+             its pipe.json",
+            url.host_str().unwrap_or_default()
+        ));
+    }
+    if api_port.is_some() && url.port_or_known_default() == api_port {
+        return Err(anyhow!(
+            "the pipe is not allowed to reach the screenpipe API, read it with pipe.read"
+        ));
+    }
+    let method = match request.method {
+        Some(method) => Method::from_bytes(method.to_uppercase().as_bytes())?,
+        None => Method::GET,
+    };
+    let mut builder = http.request(method, url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    builder = match request.body {
+        None | Some(Value::Null) => builder,
+        Some(Value::String(body)) => builder.body(body),
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(body.to_string()),
+    };
+
+    let mut response = builder.send().await?;
+    let status = response.status();
+    let headers: HashMap<String, String> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let too_large = || {
+        anyhow!(
+            "response is over the {} bytes pipes can read",
+            MAX_RESPONSE_BYTES
+        )
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_RESPONSE_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+    // read as it comes so a huge body is cut off rather than buffered
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_RESPONSE_BYTES {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(json!({
+        "status": status.as_u16(),
+        "ok": status.is_success(),
+        "headers": headers,
+        "body": String::from_utf8_lossy(&body),
+    }))
+}
@@ -0,0 +1,135 @@
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::ops::Range;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum RedactionCategory {
+    #[clap(name = "credit-card")]
+    CreditCard,
+    #[clap(name = "ssn")]
+    Ssn,
+    #[clap(name = "email")]
+    Email,
+}
+
+impl RedactionCategory {
+    pub const ALL: [RedactionCategory; 3] = [
+        RedactionCategory::CreditCard,
+        RedactionCategory::Ssn,
+        RedactionCategory::Email,
+    ];
+
+    fn rule(&self) -> RedactionRule {
+        let (pattern, replacement) = match self {
+            RedactionCategory::CreditCard => (&*CREDIT_CARD, "[CREDIT_CARD]"),
+            RedactionCategory::Ssn => (&*SSN, "[SSN]"),
+            RedactionCategory::Email => (&*EMAIL, "[EMAIL]"),
+        };
+        RedactionRule {
+            pattern: pattern.clone(),
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref CREDIT_CARD: Regex = Regex::new(r"\b(?:\d{4}[-\s]?){3}\d{4}\b").unwrap();
+    static ref SSN: Regex = Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap();
+    static ref EMAIL: Regex =
+        Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b").unwrap();
+}
+
+#[derive(Debug, Clone)]
+struct RedactionRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+/// A span of text matched by a redaction rule, as a byte range into the scanned text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedactionMatch {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// What to scrub from captured text before it is stored, and whether the matching
+/// regions should also be blurred in stored screenshots.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPolicy {
+    rules: Vec<RedactionRule>,
+    blur_images: bool,
+}
+
+impl RedactionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy redacting every built in category.
+    pub fn builtin() -> Self {
+        RedactionCategory::ALL
+            .iter()
+            .fold(Self::new(), |policy, category| policy.with_category(*category))
+    }
+
+    pub fn with_category(mut self, category: RedactionCategory) -> Self {
+        self.rules.push(category.rule());
+        self
+    }
+
+    /// Adds a user supplied regex, matches are replaced with `[REDACTED]`.
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.rules.push(RedactionRule {
+            pattern: Regex::new(pattern)?,
+            replacement: "[REDACTED]".to_string(),
+        });
+        Ok(self)
+    }
+
+    pub fn with_image_blur(mut self, blur_images: bool) -> Self {
+        self.blur_images = blur_images;
+        self
+    }
+
+    pub fn blur_images(&self) -> bool {
+        self.blur_images
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Non overlapping matches of every rule, in text order. Where rules overlap the
+    /// earliest rule wins.
+    pub fn find_matches(&self, text: &str) -> Vec<RedactionMatch> {
+        let mut matches: Vec<RedactionMatch> = Vec::new();
+        for rule in &self.rules {
+            for found in rule.pattern.find_iter(text) {
+                let overlaps = matches
+                    .iter()
+                    .any(|m| found.start() < m.range.end && m.range.start < found.end());
+                if !overlaps && !found.range().is_empty() {
+                    matches.push(RedactionMatch {
+                        range: found.range(),
+                        replacement: rule.replacement.clone(),
+                    });
+                }
+            }
+        }
+        matches.sort_by_key(|m| m.range.start);
+        matches
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for found in self.find_matches(text) {
+            redacted.push_str(&text[last..found.range.start]);
+            redacted.push_str(&found.replacement);
+            last = found.range.end;
+        }
+        redacted.push_str(&text[last..]);
+        redacted
+    }
+}
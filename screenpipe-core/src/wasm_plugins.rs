@@ -0,0 +1,452 @@
+//! WASM components extending the capture at native speed, implementing the `plugin` world
+//! of `wit/plugin.wit`: post-processors of frames and filters of OCR text and
+//! transcriptions. A plugin is a directory of the plugins directory holding its
+//! `plugin.json` and component. It is only called with the capabilities listed in its
+//! manifest, and gets WASI without files, environment or network.
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, info, warn};
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
+
+mod bindings {
+    wasmtime::component::bindgen!({ path: "wit", world: "plugin" });
+}
+
+use bindings::screenpipe::plugin::host::{self as host_api, Level};
+pub use bindings::screenpipe::plugin::types::{Frame, FrameAction, TextAction, Transcript, Window};
+use bindings::{Plugin, PluginPre};
+
+pub const PLUGIN_MANIFEST: &str = "plugin.json";
+/// Component loaded when the manifest names no `wasm`
+pub const DEFAULT_COMPONENT: &str = "plugin.wasm";
+// About a second of work, frames keep coming while a plugin runs
+const FUEL_PER_CALL: u64 = 1_000_000_000;
+const MEMORY_LIMIT_BYTES: usize = 256 * 1024 * 1024;
+
+/// What a plugin is called with, granted in its manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginCapability {
+    /// `process-frame` is called on every frame
+    Frames,
+    /// `frame-pixels` returns the pixels of the frame being processed, `set-frame-pixels`
+    /// replaces them
+    FramePixels,
+    /// `filter-ocr` is called on the text of every window
+    Ocr,
+    /// `filter-transcript` is called on every transcription
+    Transcripts,
+}
+
+/// The `plugin.json` of a plugin.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PluginManifest {
+    /// Defaults to the name of the plugin directory
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Path of the component in the plugin directory
+    #[serde(default = "default_component")]
+    pub wasm: String,
+    #[serde(default)]
+    pub capabilities: HashSet<PluginCapability>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_component() -> String {
+    DEFAULT_COMPONENT.to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PluginManifest {
+    pub fn read(plugin_dir: &Path) -> Result<Self> {
+        let path = plugin_dir.join(PLUGIN_MANIFEST);
+        let manifest = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&manifest).map_err(|e| anyhow!("invalid {}: {}", path.display(), e))
+    }
+
+    /// Path of the component, which must be in `plugin_dir`.
+    pub fn component_path(&self, plugin_dir: &Path) -> Result<PathBuf> {
+        if self.wasm.contains("..") || Path::new(&self.wasm).is_absolute() {
+            return Err(anyhow!("wasm must be a file in the plugin directory"));
+        }
+        Ok(plugin_dir.join(&self.wasm))
+    }
+}
+
+struct PluginState {
+    name: String,
+    wasi: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+    reads_pixels: bool,
+    // RGBA pixels of the frame being processed, until the plugin reads them
+    pixels: Option<Arc<Vec<u8>>>,
+    pixels_len: usize,
+    new_pixels: Option<Vec<u8>>,
+}
+
+impl WasiView for PluginState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl bindings::screenpipe::plugin::types::Host for PluginState {}
+
+impl host_api::Host for PluginState {
+    fn log(&mut self, level: Level, message: String) {
+        match level {
+            Level::Debug => debug!("[plugin {}] {}", self.name, message),
+            Level::Info => info!("[plugin {}] {}", self.name, message),
+            Level::Warn => warn!("[plugin {}] {}", self.name, message),
+            Level::Error => error!("[plugin {}] {}", self.name, message),
+        }
+    }
+
+    fn frame_pixels(&mut self) -> Result<Vec<u8>, String> {
+        if !self.reads_pixels {
+            return Err("the plugin lacks the frame-pixels capability".to_string());
+        }
+        let pixels = self
+            .pixels
+            .take()
+            .ok_or_else(|| "the pixels of the frame were already read".to_string())?;
+        // only copied when a plugin after this one reads them too
+        Ok(Arc::try_unwrap(pixels).unwrap_or_else(|pixels| pixels.to_vec()))
+    }
+
+    fn set_frame_pixels(&mut self, pixels: Vec<u8>) -> Result<(), String> {
+        if !self.reads_pixels {
+            return Err("the plugin lacks the frame-pixels capability".to_string());
+        }
+        if pixels.len() != self.pixels_len {
+            return Err(format!(
+                "expected {} bytes of pixels, got {}",
+                self.pixels_len,
+                pixels.len()
+            ));
+        }
+        self.new_pixels = Some(pixels);
+        Ok(())
+    }
+}
+
+struct LoadedPlugin {
+    name: String,
+    capabilities: HashSet<PluginCapability>,
+    engine: Engine,
+    pre: PluginPre<PluginState>,
+    // dropped when a call fails, a trapped instance can't be called again
+    instance: Option<(Store<PluginState>, Plugin)>,
+}
+
+impl LoadedPlugin {
+    fn instantiate(&self) -> Result<(Store<PluginState>, Plugin)> {
+        let state = PluginState {
+            name: self.name.clone(),
+            wasi: WasiCtxBuilder::new().build(),
+            table: ResourceTable::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MEMORY_LIMIT_BYTES)
+                .build(),
+            reads_pixels: self.capabilities.contains(&PluginCapability::FramePixels),
+            pixels: None,
+            pixels_len: 0,
+            new_pixels: None,
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+        let plugin = self.pre.instantiate(&mut store)?;
+        Ok((store, plugin))
+    }
+
+    fn call<T>(
+        &mut self,
+        pixels: Option<Arc<Vec<u8>>>,
+        f: impl FnOnce(&Plugin, &mut Store<PluginState>) -> wasmtime::Result<T>,
+    ) -> Result<T> {
+        if self.instance.is_none() {
+            self.instance = Some(self.instantiate()?);
+        }
+        let Some((store, plugin)) = self.instance.as_mut() else {
+            return Err(anyhow!("plugin {} is not instantiated", self.name));
+        };
+        store.set_fuel(FUEL_PER_CALL)?;
+        let state = store.data_mut();
+        state.pixels_len = pixels.as_ref().map_or(0, |pixels| pixels.len());
+        state.pixels = pixels;
+        state.new_pixels = None;
+        let result = f(plugin, store);
+        store.data_mut().pixels = None;
+        if result.is_err() {
+            self.instance = None;
+        }
+        result
+    }
+
+    /// The pixels the last call replaced the frame with.
+    fn take_new_pixels(&mut self) -> Option<Vec<u8>> {
+        let (store, _) = self.instance.as_mut()?;
+        store.data_mut().new_pixels.take()
+    }
+}
+
+/// What the frame post-processors left of a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedFrame {
+    /// The text of each window, none for windows dropped
+    pub texts: Vec<Option<String>>,
+    /// The RGBA pixels the frame was replaced with
+    pub pixels: Option<Vec<u8>>,
+}
+
+/// The plugins loaded from a plugins directory, called in name order, each on what the
+/// one before left. A plugin failing or running out of fuel leaves what it was called with
+/// as it is.
+pub struct WasmPlugins {
+    plugins: Vec<Mutex<LoadedPlugin>>,
+}
+
+impl WasmPlugins {
+    /// Loads the enabled plugins in `plugins_dir`, plugins failing to load are skipped.
+    pub fn load(plugins_dir: &Path) -> Result<Self> {
+        let mut config = Config::new();
+        config.wasm_component_model(true).consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+        Plugin::add_to_linker(&mut linker, |state: &mut PluginState| state)?;
+
+        let mut dirs: Vec<PathBuf> = match std::fs::read_dir(plugins_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.join(PLUGIN_MANIFEST).is_file())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        dirs.sort();
+
+        let mut plugins = Vec::new();
+        for dir in dirs {
+            match Self::load_plugin(&engine, &linker, &dir) {
+                Ok(Some(plugin)) => {
+                    info!(
+                        "loaded plugin {} with {:?}",
+                        plugin.name, plugin.capabilities
+                    );
+                    plugins.push(Mutex::new(plugin));
+                }
+                Ok(None) => debug!("plugin {} is disabled", dir.display()),
+                Err(e) => error!("failed to load plugin {}: {}", dir.display(), e),
+            }
+        }
+        Ok(Self { plugins })
+    }
+
+    fn load_plugin(
+        engine: &Engine,
+        linker: &Linker<PluginState>,
+        dir: &Path,
+    ) -> Result<Option<LoadedPlugin>> {
+        let manifest = PluginManifest::read(dir)?;
+        if !manifest.enabled {
+            return Ok(None);
+        }
+        let name = manifest.name.clone().unwrap_or_else(|| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        let component = Component::from_file(engine, manifest.component_path(dir)?)?;
+        let pre = PluginPre::new(linker.instantiate_pre(&component)?)?;
+        let mut plugin = LoadedPlugin {
+            name,
+            capabilities: manifest.capabilities,
+            engine: engine.clone(),
+            pre,
+            instance: None,
+        };
+        // fails now rather than on the first frame
+        plugin.instance = Some(plugin.instantiate()?);
+        Ok(Some(plugin))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.each(|plugin| plugin.name.clone())
+    }
+
+    /// Whether a plugin is called with `capability`.
+    pub fn wants(&self, capability: PluginCapability) -> bool {
+        self.each(|plugin| plugin.capabilities.contains(&capability))
+            .into_iter()
+            .any(|wanted| wanted)
+    }
+
+    fn each<T>(&self, f: impl Fn(&LoadedPlugin) -> T) -> Vec<T> {
+        self.plugins
+            .iter()
+            .map(|plugin| f(&plugin.lock().unwrap_or_else(|e| e.into_inner())))
+            .collect()
+    }
+
+    /// Runs the frame post-processors on `frame`, `pixels` being its RGBA pixels. None when
+    /// the frame is dropped. Plugins run at native speed but may take their whole fuel,
+    /// this is called off the async runtime.
+    pub fn process_frame(
+        &self,
+        mut frame: Frame,
+        pixels: impl FnOnce() -> Vec<u8>,
+    ) -> Option<ProcessedFrame> {
+        let mut texts: Vec<Option<String>> = frame
+            .windows
+            .iter()
+            .map(|window| Some(window.text.clone()))
+            .collect();
+        // windows of `frame` still kept, by their index in `texts`
+        let mut kept: Vec<usize> = (0..texts.len()).collect();
+        let last_reader = self
+            .each(|plugin| {
+                plugin.capabilities.contains(&PluginCapability::Frames)
+                    && plugin.capabilities.contains(&PluginCapability::FramePixels)
+            })
+            .into_iter()
+            .rposition(|reads| reads);
+        let mut pixels = last_reader.map(|_| Arc::new(pixels()));
+        let mut replaced = false;
+
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            let mut plugin = plugin.lock().unwrap_or_else(|e| e.into_inner());
+            if !plugin.capabilities.contains(&PluginCapability::Frames) {
+                continue;
+            }
+            let given = if !plugin.capabilities.contains(&PluginCapability::FramePixels) {
+                None
+            } else if Some(index) == last_reader && !replaced {
+                // nothing reads them after, the plugin gets them without a copy
+                pixels.take()
+            } else {
+                pixels.clone()
+            };
+            let action = plugin.call(given, |instance, store| {
+                instance.call_process_frame(store, &frame)
+            });
+            if let Some(new_pixels) = plugin.take_new_pixels() {
+                pixels = Some(Arc::new(new_pixels));
+                replaced = true;
+            }
+            let action = match action {
+                Ok(action) => action,
+                Err(e) => {
+                    warn!("plugin {} failed to process a frame: {}", plugin.name, e);
+                    continue;
+                }
+            };
+            let actions = match action {
+                FrameAction::Keep => continue,
+                FrameAction::Drop => {
+                    debug!("plugin {} dropped a frame", plugin.name);
+                    return None;
+                }
+                FrameAction::Windows(actions) if actions.len() == frame.windows.len() => actions,
+                FrameAction::Windows(actions) => {
+                    warn!(
+                        "plugin {} returned {} actions for {} windows, ignored",
+                        plugin.name,
+                        actions.len(),
+                        frame.windows.len()
+                    );
+                    continue;
+                }
+            };
+
+            let mut windows = Vec::new();
+            let mut still_kept = Vec::new();
+            for ((mut window, index), action) in
+                frame.windows.drain(..).zip(kept.drain(..)).zip(actions)
+            {
+                match action {
+                    TextAction::Keep => {}
+                    TextAction::Replace(text) => {
+                        texts[index] = Some(text.clone());
+                        window.text = text;
+                    }
+                    TextAction::Drop => {
+                        texts[index] = None;
+                        continue;
+                    }
+                }
+                windows.push(window);
+                still_kept.push(index);
+            }
+            frame.windows = windows;
+            kept = still_kept;
+        }
+        Some(ProcessedFrame {
+            texts,
+            pixels: pixels
+                .filter(|_| replaced)
+                .map(|pixels| Arc::try_unwrap(pixels).unwrap_or_else(|pixels| pixels.to_vec())),
+        })
+    }
+
+    /// Runs the OCR filters on the text of `window`, None when it is dropped.
+    pub fn filter_ocr(&self, mut window: Window) -> Option<String> {
+        for plugin in &self.plugins {
+            let mut plugin = plugin.lock().unwrap_or_else(|e| e.into_inner());
+            if !plugin.capabilities.contains(&PluginCapability::Ocr) {
+                continue;
+            }
+            match plugin.call(None, |instance, store| {
+                instance.call_filter_ocr(store, &window)
+            }) {
+                Ok(TextAction::Keep) => {}
+                Ok(TextAction::Replace(text)) => window.text = text,
+                Ok(TextAction::Drop) => return None,
+                Err(e) => warn!("plugin {} failed to filter ocr text: {}", plugin.name, e),
+            }
+        }
+        Some(window.text)
+    }
+
+    /// Runs the transcription filters on `transcript`, None when it is dropped.
+    pub fn filter_transcript(&self, mut transcript: Transcript) -> Option<String> {
+        for plugin in &self.plugins {
+            let mut plugin = plugin.lock().unwrap_or_else(|e| e.into_inner());
+            if !plugin.capabilities.contains(&PluginCapability::Transcripts) {
+                continue;
+            }
+            match plugin.call(None, |instance, store| {
+                instance.call_filter_transcript(store, &transcript)
+            }) {
+                Ok(TextAction::Keep) => {}
+                Ok(TextAction::Replace(text)) => transcript.text = text,
+                Ok(TextAction::Drop) => return None,
+                Err(e) => warn!(
+                    "plugin {} failed to filter a transcription: {}",
+                    plugin.name, e
+                ),
+            }
+        }
+        Some(transcript.text)
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::encryption::{
+        encrypt_media, is_encrypted, read_media, readable_media, set_media_key, EncryptionKey,
+    };
+
+    #[test]
+    fn test_encryption_round_trip() {
+        let key = EncryptionKey::generate();
+        let encrypted = key.encrypt(b"frame bytes").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(b"frame bytes"));
+        assert_eq!(key.decrypt(&encrypted).unwrap(), b"frame bytes");
+
+        // a fresh nonce every time
+        assert_ne!(key.encrypt(b"frame bytes").unwrap(), encrypted);
+
+        assert!(EncryptionKey::generate().decrypt(&encrypted).is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.decrypt(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_key_from_hex() {
+        let key = EncryptionKey::generate();
+        assert_eq!(EncryptionKey::from_hex(&key.to_hex()).unwrap(), key);
+        assert!(EncryptionKey::from_hex("not hex").is_err());
+        assert!(EncryptionKey::from_hex("abcd").is_err());
+        assert_eq!(format!("{:?}", key), "EncryptionKey(..)");
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_media_reads_transparently() {
+        set_media_key(EncryptionKey::from_hex(&"11".repeat(32)).unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("monitor_1.mp4");
+        std::fs::write(&video, b"video bytes").unwrap();
+
+        let plain = readable_media(&video).await.unwrap();
+        assert_eq!(plain.path(), video);
+        drop(plain);
+
+        assert_eq!(encrypt_media(&video).await.unwrap(), 1);
+        // already encrypted
+        assert_eq!(encrypt_media(&video).await.unwrap(), 0);
+        assert!(is_encrypted(&std::fs::read(&video).unwrap()));
+        assert_eq!(read_media(&video).await.unwrap(), b"video bytes");
+
+        let readable = readable_media(&video).await.unwrap();
+        assert_ne!(readable.path(), video);
+        assert!(readable.to_str().ends_with(".mp4"));
+        assert_eq!(std::fs::read(readable.path()).unwrap(), b"video bytes");
+        let decrypted = readable.path().to_path_buf();
+        drop(readable);
+        assert!(!decrypted.exists());
+    }
+}
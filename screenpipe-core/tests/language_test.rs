@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::{parse_ocr_languages, Language};
+
+    #[test]
+    fn test_parse_ocr_languages_tesseract_codes() {
+        let languages = parse_ocr_languages("eng+deu+jpn").unwrap();
+        assert_eq!(
+            languages,
+            vec![Language::English, Language::German, Language::Japanese]
+        );
+    }
+
+    #[test]
+    fn test_parse_ocr_languages_accepts_names_and_drops_duplicates() {
+        let languages = parse_ocr_languages("eng, english+chi_sim").unwrap();
+        assert_eq!(languages, vec![Language::English, Language::Chinese]);
+    }
+
+    #[test]
+    fn test_parse_ocr_languages_reports_unknown_codes() {
+        let err = parse_ocr_languages("eng+xyz").unwrap_err();
+        assert!(err.contains("xyz"));
+    }
+
+    #[test]
+    fn test_tesseract_code_round_trip() {
+        assert_eq!(Language::German.as_tesseract_code(), Some("deu"));
+        assert_eq!(Language::from_tesseract_code("DEU"), Some(Language::German));
+    }
+
+    #[test]
+    fn test_vertical_tesseract_code() {
+        assert_eq!(
+            Language::Japanese.as_vertical_tesseract_code(),
+            Some("jpn_vert")
+        );
+        assert_eq!(Language::German.as_vertical_tesseract_code(), None);
+    }
+}
@@ -0,0 +1,283 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::pipe_install::{pipe_id_from_repository, InstallPlan};
+    use screenpipe_core::{install_pipe, InstallRecord, PipeSource, Registry};
+    use serde_json::{json, Value};
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn write_pipe(dir: &Path, permissions: Value) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            dir.join("pipe.json"),
+            json!({"runtime": "sandbox", "permissions": permissions}).to_string(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("pipe.js"), "pipe.log('standup');").unwrap();
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn registry() -> Registry {
+        serde_json::from_value(json!({
+            "pipes": {
+                "obsidian": {
+                    "description": "writes notes of the day",
+                    "repository": "https://github.com/mediar-ai/screenpipe.git",
+                    "path": "pipes/obsidian",
+                    "version": "0.2.0",
+                    "versions": {
+                        "0.1.0": {"ref": "v0.1.0", "commit": "abc123"}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sources_are_told_apart() {
+        assert_eq!(
+            PipeSource::parse("https://github.com/me/standup.git#v1.2.0"),
+            PipeSource::Git {
+                url: "https://github.com/me/standup.git".to_string(),
+                reference: Some("v1.2.0".to_string()),
+            }
+        );
+        assert_eq!(
+            PipeSource::parse("git+https://example.com/me/standup"),
+            PipeSource::Git {
+                url: "https://example.com/me/standup".to_string(),
+                reference: None,
+            }
+        );
+        assert!(matches!(
+            PipeSource::parse("git@github.com:me/standup"),
+            PipeSource::Git { .. }
+        ));
+        assert_eq!(
+            PipeSource::parse("obsidian@0.1.0"),
+            PipeSource::Registry {
+                name: "obsidian".to_string(),
+                version: Some("0.1.0".to_string()),
+            }
+        );
+        assert_eq!(
+            PipeSource::parse("obsidian"),
+            PipeSource::Registry {
+                name: "obsidian".to_string(),
+                version: None,
+            }
+        );
+        assert!(matches!(
+            PipeSource::parse("https://github.com/mediar-ai/screenpipe/tree/main/pipes/obsidian"),
+            PipeSource::Download(_)
+        ));
+        assert!(matches!(
+            PipeSource::parse("../my pipe"),
+            PipeSource::Download(_)
+        ));
+    }
+
+    #[test]
+    fn test_pipes_are_named_after_their_repository() {
+        assert_eq!(
+            pipe_id_from_repository("https://github.com/me/standup.git").as_deref(),
+            Some("standup")
+        );
+        assert_eq!(
+            pipe_id_from_repository("git@github.com:me/standup").as_deref(),
+            Some("standup")
+        );
+        assert_eq!(pipe_id_from_repository("https://github.com/me/.git"), None);
+    }
+
+    #[test]
+    fn test_registry_versions_are_pinned_or_latest() {
+        let registry = registry();
+        let pinned = registry.resolve("obsidian", Some("0.1.0")).unwrap();
+        assert_eq!(pinned.reference.as_deref(), Some("v0.1.0"));
+        assert_eq!(pinned.commit.as_deref(), Some("abc123"));
+
+        let latest = registry.resolve("obsidian", None).unwrap();
+        assert_eq!(
+            latest,
+            InstallPlan {
+                id: "obsidian".to_string(),
+                repository: "https://github.com/mediar-ai/screenpipe.git".to_string(),
+                path: Some("pipes/obsidian".to_string()),
+                reference: None,
+                commit: None,
+                version: Some("0.2.0".to_string()),
+                registry_name: Some("obsidian".to_string()),
+            }
+        );
+
+        let missing = registry.resolve("obsidian", Some("9.9.9")).unwrap_err();
+        assert!(missing.to_string().contains("only 0.1.0"), "{}", missing);
+        // pins are verified against their commit, a version without one can't be pinned
+        assert!(registry.resolve("obsidian", Some("0.2.0")).is_err());
+        assert!(registry.resolve("calendar", None).is_err());
+    }
+
+    #[test]
+    fn test_install_records_reinstall_the_same_source() {
+        let config = json!({
+            "enabled": true,
+            "install": {
+                "source": "obsidian@0.1.0",
+                "repository": "https://github.com/mediar-ai/screenpipe.git",
+                "registry_name": "obsidian",
+                "version": "0.1.0",
+                "pinned": "0.1.0",
+                "commit": "abc123",
+                "installed_at": "2024-10-01T09:00:00+00:00"
+            }
+        });
+        let record = InstallRecord::from_config(&config).unwrap();
+        assert_eq!(
+            record.source(),
+            PipeSource::Registry {
+                name: "obsidian".to_string(),
+                version: Some("0.1.0".to_string()),
+            }
+        );
+        assert!(InstallRecord::from_config(&json!({"enabled": true})).is_none());
+
+        // a pinned branch moves, reinstalling it goes back to the commit it was at
+        let branch = InstallRecord {
+            source: "https://github.com/me/standup.git#main".to_string(),
+            repository: "https://github.com/me/standup.git".to_string(),
+            registry_name: None,
+            version: None,
+            pinned: Some("main".to_string()),
+            ..record
+        };
+        assert_eq!(
+            branch.source(),
+            PipeSource::Git {
+                url: "https://github.com/me/standup.git".to_string(),
+                reference: Some("abc123".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_pipes_install_from_git_at_their_commit() {
+        let repo = TempDir::new().unwrap();
+        write_pipe(
+            &repo.path().join("pipes/standup"),
+            json!({"endpoints": ["/search"]}),
+        );
+        git(repo.path(), &["init", "--quiet"]);
+        git(repo.path(), &["add", "-A"]);
+        git(repo.path(), &["commit", "--quiet", "-m", "standup"]);
+        let commit = git(repo.path(), &["rev-parse", "HEAD"]);
+
+        let registry_file = repo.path().join("registry.json");
+        let registry = |commit: &str| {
+            json!({
+                "pipes": {
+                    "standup": {
+                        "repository": repo.path().to_string_lossy(),
+                        "path": "pipes/standup",
+                        "version": "1.0.0",
+                        "versions": {"1.0.0": {"commit": commit}}
+                    }
+                }
+            })
+            .to_string()
+        };
+        std::fs::write(&registry_file, registry(&commit)).unwrap();
+
+        let screenpipe_dir = TempDir::new().unwrap();
+        let installed = install_pipe(
+            &PipeSource::parse("standup@1.0.0"),
+            screenpipe_dir.path(),
+            &registry_file.to_string_lossy(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(installed.id, "standup");
+        assert!(installed.dir.join("pipe.js").exists());
+        assert!(installed.permissions.unwrap().allows_endpoint("/search"));
+        let record = installed.record.unwrap();
+        assert_eq!(record.commit, commit);
+        assert_eq!(record.pinned.as_deref(), Some("1.0.0"));
+
+        let config = read_json(&installed.dir.join("pipe.json"));
+        assert_eq!(InstallRecord::from_config(&config), Some(record));
+        assert_ne!(config["enabled"], json!(true));
+
+        // a registry entry whose commit doesn't match what the repository serves is refused
+        std::fs::write(&registry_file, registry("0000000000")).unwrap();
+        assert!(install_pipe(
+            &PipeSource::parse("standup@1.0.0"),
+            screenpipe_dir.path(),
+            &registry_file.to_string_lossy(),
+            false,
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_updates_asking_for_more_permissions_are_held_back() {
+        let repo = TempDir::new().unwrap();
+        let pipe_repo = repo.path().join("standup");
+        write_pipe(&pipe_repo, json!({"endpoints": ["/search"]}));
+        git(&pipe_repo, &["init", "--quiet"]);
+        git(&pipe_repo, &["add", "-A"]);
+        git(&pipe_repo, &["commit", "--quiet", "-m", "standup"]);
+
+        let source = PipeSource::Git {
+            url: pipe_repo.to_string_lossy().into_owned(),
+            reference: None,
+        };
+        let screenpipe_dir = TempDir::new().unwrap();
+        let installed = install_pipe(&source, screenpipe_dir.path(), "", false)
+            .await
+            .unwrap();
+        assert!(installed.held_permissions.is_empty());
+        let pipe_json = installed.dir.join("pipe.json");
+        let mut config = read_json(&pipe_json);
+        config["enabled"] = json!(true);
+        std::fs::write(&pipe_json, config.to_string()).unwrap();
+
+        write_pipe(
+            &pipe_repo,
+            json!({"endpoints": ["/search"], "http": ["*"], "images": true}),
+        );
+        git(&pipe_repo, &["commit", "--quiet", "-am", "everything"]);
+        let held = install_pipe(&source, screenpipe_dir.path(), "", false)
+            .await
+            .unwrap();
+        assert_eq!(held.held_permissions, ["images", "http *"]);
+        assert!(!held.permissions.unwrap().images);
+        let config = read_json(&pipe_json);
+        assert_eq!(config["enabled"], json!(false));
+        assert_eq!(config["permissions"], json!({"endpoints": ["/search"]}));
+
+        let granted = install_pipe(&source, screenpipe_dir.path(), "", true)
+            .await
+            .unwrap();
+        assert!(granted.held_permissions.is_empty());
+        assert!(granted.permissions.unwrap().images);
+        assert_eq!(read_json(&pipe_json)["permissions"]["http"], json!(["*"]));
+    }
+}
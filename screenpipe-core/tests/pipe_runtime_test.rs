@@ -0,0 +1,369 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::pipe_runtime::{strip_images, HostFuture};
+    use screenpipe_core::{
+        start_script_pipe, PipeEvent, PipeHost, PipeLimits, PipePermissions, ReadRequest,
+        ScriptPipe,
+    };
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use url::Url;
+
+    struct MockHost {
+        notifications: mpsc::UnboundedSender<(String, String)>,
+    }
+
+    impl PipeHost for MockHost {
+        fn query(&self, params: Value) -> HostFuture<'_, Value> {
+            Box::pin(async move {
+                Ok(json!({
+                    "data": [{"type": "OCR", "content": {"text": params["q"]}}],
+                    "pagination": {"limit": 20, "offset": 0, "total": 1},
+                }))
+            })
+        }
+
+        fn notify(&self, _pipe: String, title: String, body: String) -> HostFuture<'_, ()> {
+            Box::pin(async move {
+                self.notifications.send((title, body))?;
+                Ok(())
+            })
+        }
+
+        fn read(&self, request: ReadRequest) -> HostFuture<'_, Value> {
+            Box::pin(async move { Ok(json!({"path": request.path, "images": request.images})) })
+        }
+    }
+
+    fn script_pipe(source: &str, permissions: PipePermissions) -> ScriptPipe {
+        ScriptPipe {
+            id: "test-pipe".to_string(),
+            source: source.to_string(),
+            config: json!({"runtime": "sandbox", "sheet": "figma"}),
+            permissions,
+            limits: PipeLimits {
+                handler_timeout: Duration::from_secs(1),
+                ..PipeLimits::default()
+            },
+        }
+    }
+
+    // lets the pipe notify and handle `events`
+    fn notifying(events: &[&str]) -> PipePermissions {
+        PipePermissions {
+            notify: true,
+            events: events.iter().map(|event| event.to_string()).collect(),
+            ..PipePermissions::default()
+        }
+    }
+
+    fn mock_host() -> (Arc<dyn PipeHost>, mpsc::UnboundedReceiver<(String, String)>) {
+        let (notifications, received) = mpsc::unbounded_channel();
+        (Arc::new(MockHost { notifications }), received)
+    }
+
+    async fn next_notification(
+        received: &mut mpsc::UnboundedReceiver<(String, String)>,
+    ) -> (String, String) {
+        tokio::time::timeout(Duration::from_secs(10), received.recv())
+            .await
+            .expect("no notification")
+            .expect("host dropped")
+    }
+
+    #[test]
+    fn test_pipes_reach_only_allowed_hosts() {
+        let permissions = PipePermissions {
+            http: vec![
+                "sheets.googleapis.com".to_string(),
+                "*.example.com".to_string(),
+            ],
+            ..PipePermissions::default()
+        };
+        let allows = |url: &str| permissions.allows_url(&Url::parse(url).unwrap());
+        assert!(allows("https://sheets.googleapis.com/v4/spreadsheets"));
+        assert!(allows("http://api.example.com/hook"));
+        assert!(!allows("https://example.com"));
+        assert!(!allows("https://evil-sheets.googleapis.com"));
+        assert!(!allows("https://localhost:3030/search"));
+        assert!(!allows("file:///etc/passwd"));
+        assert!(!PipePermissions::default().allows_url(&Url::parse("https://a.com").unwrap()));
+
+        let any = PipePermissions {
+            http: vec!["*".to_string()],
+            ..PipePermissions::default()
+        };
+        let allows = |url: &str| any.allows_url(&Url::parse(url).unwrap());
+        assert!(allows("https://a.com"));
+        assert!(allows("https://8.8.8.8/dns"));
+        assert!(!allows("ftp://a.com"));
+        // the machine and its network stay out of reach, whatever the permissions
+        for local in [
+            "http://localhost:3030/raw_sql",
+            "http://api.localhost/",
+            "http://127.0.0.1:3030/pipes/download",
+            "http://0.0.0.0:3030/",
+            "http://10.0.0.2/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]:3030/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[fd00::1]/",
+        ] {
+            assert!(!allows(local), "{}", local);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_never_reaches_the_api_port() {
+        struct ApiHost(MockHost);
+
+        impl PipeHost for ApiHost {
+            fn query(&self, params: Value) -> HostFuture<'_, Value> {
+                self.0.query(params)
+            }
+            fn notify(&self, pipe: String, title: String, body: String) -> HostFuture<'_, ()> {
+                self.0.notify(pipe, title, body)
+            }
+            fn read(&self, request: ReadRequest) -> HostFuture<'_, Value> {
+                self.0.read(request)
+            }
+            fn api_port(&self) -> Option<u16> {
+                Some(3030)
+            }
+        }
+
+        let (notifications, mut received) = mpsc::unbounded_channel();
+        let host = Arc::new(ApiHost(MockHost { notifications }));
+        let source = r#"
+            pipe.on("ping", async () => {
+                try {
+                    await pipe.fetch("http://example.com:3030/raw_sql", { method: "POST" });
+                } catch (e) {
+                    await pipe.notify("denied", e.message);
+                }
+            });
+        "#;
+        let permissions = PipePermissions {
+            http: vec!["*".to_string()],
+            ..notifying(&["ping"])
+        };
+        let handle = start_script_pipe(script_pipe(source, permissions), host)
+            .await
+            .unwrap();
+        handle.send(PipeEvent {
+            name: "ping".to_string(),
+            data: Value::Null,
+        });
+        let (title, body) = next_notification(&mut received).await;
+        assert_eq!(title, "denied");
+        assert!(body.contains("screenpipe API"), "{}", body);
+        handle.stop();
+    }
+
+    #[test]
+    fn test_pipes_subscribe_to_allowed_events() {
+        // pipes are allowed nothing their pipe.json doesn't ask for
+        let nothing = PipePermissions::default();
+        assert!(!nothing.allows_event("meeting_started"));
+        assert!(!nothing.allows_endpoint("/search"));
+        assert!(!nothing.notify);
+        let permissions: PipePermissions =
+            serde_json::from_value(json!({"events": ["window_focused"], "notify": true})).unwrap();
+        assert!(permissions.allows_event("window_focused"));
+        assert!(!permissions.allows_event("meeting_started"));
+        assert!(permissions.notify);
+    }
+
+    #[test]
+    fn test_screenshots_are_stripped_at_any_depth() {
+        let mut event = json!({
+            "image": "iVBORw0K",
+            "text": "standup",
+            "data": [{"content": {"frame": "iVBORw0K", "frame_id": 12}}],
+        });
+        strip_images(&mut event);
+        assert_eq!(
+            event,
+            json!({"text": "standup", "data": [{"content": {"frame_id": 12}}]})
+        );
+    }
+
+    #[test]
+    fn test_pipes_read_only_allowed_endpoints() {
+        let permissions = PipePermissions {
+            endpoints: vec!["/search".to_string(), "/meetings/*".to_string()],
+            ..PipePermissions::default()
+        };
+        assert!(permissions.allows_endpoint("/search"));
+        assert!(permissions.allows_endpoint("/search/"));
+        assert!(permissions.allows_endpoint("/meetings/12/summary"));
+        assert!(!permissions.allows_endpoint("/meetings"));
+        assert!(!permissions.allows_endpoint("/meetingsx/12"));
+        assert!(!permissions.allows_endpoint("/meetings/../raw_sql"));
+        assert!(!permissions.allows_endpoint("/search?q=a"));
+        assert!(!permissions.allows_endpoint("search"));
+        assert!(!permissions.allows_endpoint("/frames/1"));
+        assert!(!PipePermissions::default().allows_endpoint("/search"));
+        assert!(!PipePermissions::default().images);
+    }
+
+    #[tokio::test]
+    async fn test_script_handles_events_with_the_pipe_api() {
+        let (host, mut received) = mock_host();
+        let source = r#"
+            pipe.on("window_focused", async (event) => {
+                const result = await pipe.query({ q: event.app });
+                await pipe.notify(pipe.config.sheet, result.data[0].content.text);
+            });
+        "#;
+        let permissions = PipePermissions {
+            endpoints: vec!["/search".to_string()],
+            ..notifying(&["window_focused"])
+        };
+        let handle = start_script_pipe(script_pipe(source, permissions), host)
+            .await
+            .unwrap();
+        assert_eq!(handle.events(), ["window_focused".to_string()]);
+
+        handle.send(PipeEvent {
+            name: "window_focused".to_string(),
+            data: json!({"app": "Figma"}),
+        });
+        assert_eq!(
+            next_notification(&mut received).await,
+            ("figma".to_string(), "Figma".to_string())
+        );
+
+        handle.stop();
+        tokio::time::timeout(Duration::from_secs(10), handle.wait())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_script_is_denied_what_its_permissions_leave_out() {
+        let (host, mut received) = mock_host();
+        let source = r#"
+            pipe.every(1, async () => {
+                const errors = [];
+                const calls = [
+                    () => pipe.query({}),
+                    () => pipe.fetch("https://example.com"),
+                    () => pipe.read("/frames/1"),
+                ];
+                for (const call of calls) {
+                    try {
+                        await call();
+                    } catch (e) {
+                        errors.push(e.message);
+                    }
+                }
+                await pipe.notify("denied", errors.join("|"));
+            });
+            pipe.on("meeting_started", () => {});
+        "#;
+        let permissions = notifying(&["window_focused"]);
+        let handle = start_script_pipe(script_pipe(source, permissions), host)
+            .await
+            .unwrap();
+        assert!(handle.events().is_empty());
+
+        let (title, body) = next_notification(&mut received).await;
+        assert_eq!(title, "denied");
+        assert!(body.contains("not allowed to query"), "{}", body);
+        assert!(
+            body.contains("not allowed to reach example.com"),
+            "{}",
+            body
+        );
+        assert!(body.contains("not allowed to read /frames/1"), "{}", body);
+        handle.stop();
+    }
+
+    #[tokio::test]
+    async fn test_triggers_run_their_handler_and_report_failures() {
+        let (host, mut received) = mock_host();
+        let source = r#"
+            pipe.onTrigger("standup", async ({ trigger }) => {
+                await pipe.notify(trigger);
+            });
+            pipe.onTrigger("broken", () => {
+                throw new Error("sheet is gone");
+            });
+        "#;
+        let handle = start_script_pipe(script_pipe(source, notifying(&[])), host)
+            .await
+            .unwrap();
+        assert!(handle.wants("trigger:standup"));
+
+        let trigger = |name: &str| PipeEvent {
+            name: format!("trigger:{}", name),
+            data: json!({ "trigger": name }),
+        };
+        handle.run(trigger("standup")).await.unwrap();
+        assert_eq!(next_notification(&mut received).await.0, "standup");
+        let error = handle.run(trigger("broken")).await.unwrap_err();
+        assert!(error.to_string().contains("sheet is gone"), "{}", error);
+        assert!(handle.run(trigger("missing")).await.is_err());
+        handle.stop();
+    }
+
+    #[tokio::test]
+    async fn test_script_reads_allowed_endpoints_without_images() {
+        let (host, mut received) = mock_host();
+        let source = r#"
+            pipe.on("ping", async (event) => {
+                const meeting = await pipe.read("/meetings/12", { summary: true });
+                await pipe.notify(meeting.path, `${meeting.images} ${"image" in event}`);
+            });
+        "#;
+        let permissions = PipePermissions {
+            endpoints: vec!["/meetings/*".to_string()],
+            ..notifying(&["ping"])
+        };
+        let handle = start_script_pipe(script_pipe(source, permissions), host)
+            .await
+            .unwrap();
+        // screenshots of events are left out too
+        handle.send(PipeEvent {
+            name: "ping".to_string(),
+            data: json!({"image": "iVBORw0K", "text": "standup"}),
+        });
+        assert_eq!(
+            next_notification(&mut received).await,
+            ("/meetings/12".to_string(), "false false".to_string())
+        );
+        handle.stop();
+    }
+
+    #[tokio::test]
+    async fn test_runaway_scripts_are_interrupted() {
+        let (host, mut received) = mock_host();
+        assert!(start_script_pipe(
+            script_pipe("while (true) {}", PipePermissions::default()),
+            host.clone()
+        )
+        .await
+        .is_err());
+
+        // a handler stuck in a loop doesn't stop the next ones
+        let source = r#"
+            pipe.on("loop", () => { while (true) {} });
+            pipe.on("ping", () => pipe.notify("pong"));
+        "#;
+        let handle = start_script_pipe(script_pipe(source, notifying(&["loop", "ping"])), host)
+            .await
+            .unwrap();
+        for name in ["loop", "ping"] {
+            handle.send(PipeEvent {
+                name: name.to_string(),
+                data: Value::Null,
+            });
+        }
+        assert_eq!(next_notification(&mut received).await.0, "pong");
+        handle.stop();
+    }
+}
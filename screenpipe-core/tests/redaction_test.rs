@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::{RedactionCategory, RedactionPolicy};
+
+    #[test]
+    fn test_builtin_policy_redacts_all_categories() {
+        let policy = RedactionPolicy::builtin();
+        let redacted =
+            policy.redact("card 4111 1111 1111 1111, ssn 123-45-6789, mail a.b@example.org");
+        assert_eq!(redacted, "card [CREDIT_CARD], ssn [SSN], mail [EMAIL]");
+    }
+
+    #[test]
+    fn test_policy_only_applies_selected_categories() {
+        let policy = RedactionPolicy::new().with_category(RedactionCategory::Email);
+        assert_eq!(
+            policy.redact("ssn 123-45-6789 mail a@example.com"),
+            "ssn 123-45-6789 mail [EMAIL]"
+        );
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let policy = RedactionPolicy::new()
+            .with_pattern(r"sk-[A-Za-z0-9]{8,}")
+            .unwrap();
+        assert_eq!(
+            policy.redact("export KEY=sk-abcdef123456"),
+            "export KEY=[REDACTED]"
+        );
+        assert!(RedactionPolicy::new().with_pattern("(").is_err());
+    }
+
+    #[test]
+    fn test_find_matches_skips_overlaps_and_keeps_order() {
+        let policy = RedactionPolicy::new()
+            .with_category(RedactionCategory::Ssn)
+            .with_pattern(r"\d{3}-\d{2}")
+            .unwrap()
+            .with_category(RedactionCategory::Email);
+
+        let text = "x@y.io then 123-45-6789";
+        let matches = policy.find_matches(text);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(&text[matches[0].range.clone()], "x@y.io");
+        assert_eq!(matches[1].replacement, "[SSN]");
+        assert_eq!(&text[matches[1].range.clone()], "123-45-6789");
+    }
+
+    #[test]
+    fn test_empty_policy_is_noop() {
+        let policy = RedactionPolicy::new();
+        assert!(policy.is_empty());
+        assert_eq!(policy.redact("123-45-6789"), "123-45-6789");
+    }
+}
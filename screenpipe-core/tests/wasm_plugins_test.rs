@@ -0,0 +1,220 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::wasm_plugins::{
+        Frame, PluginManifest, Transcript, Window, DEFAULT_COMPONENT, PLUGIN_MANIFEST,
+    };
+    use screenpipe_core::{PluginCapability, WasmPlugins};
+    use std::path::Path;
+
+    // A component dropping frames and transcriptions and replacing ocr text with "redacted"
+    const REDACTING_PLUGIN: &str = r#"
+(component
+  (core module $plugin
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 4096))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    (func (export "process-frame") (param i64 i32 i32 i32 i32 i32) (result i32)
+      (i32.store8 (i32.const 0) (i32.const 1))
+      (i32.const 0))
+    (func (export "filter-ocr")
+      (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+      (i32.store8 (i32.const 16) (i32.const 1))
+      (i32.store (i32.const 20) (i32.const 64))
+      (i32.store (i32.const 24) (i32.const 8))
+      (i32.const 16))
+    (func (export "filter-transcript") (param i32 i32 i32 i32 i32 f64 f64) (result i32)
+      (i32.store8 (i32.const 32) (i32.const 2))
+      (i32.const 32))
+    (data (i32.const 64) "redacted"))
+  (core instance $instance (instantiate $plugin))
+
+  (type $text-action-type (variant (case "keep") (case "replace" string) (case "drop")))
+  (export $text-action "text-action" (type $text-action-type))
+  (type $frame-action-type
+    (variant (case "keep") (case "drop") (case "windows" (list $text-action))))
+  (export $frame-action "frame-action" (type $frame-action-type))
+  (type $window-type
+    (record
+      (field "app-name" string)
+      (field "window-name" string)
+      (field "browser-url" (option string))
+      (field "focused" bool)
+      (field "text" string)))
+  (export $window "window" (type $window-type))
+  (type $frame-type
+    (record
+      (field "timestamp" s64)
+      (field "monitor-id" u32)
+      (field "width" u32)
+      (field "height" u32)
+      (field "windows" (list $window))))
+  (export $frame "frame" (type $frame-type))
+  (type $transcript-type
+    (record
+      (field "device" string)
+      (field "input" bool)
+      (field "text" string)
+      (field "start" f64)
+      (field "end" f64)))
+  (export $transcript "transcript" (type $transcript-type))
+
+  (func $process-frame (param "frame" $frame) (result $frame-action)
+    (canon lift (core func $instance "process-frame")
+      (memory $instance "memory") (realloc (func $instance "realloc"))))
+  (export "process-frame" (func $process-frame))
+  (func $filter-ocr (param "window" $window) (result $text-action)
+    (canon lift (core func $instance "filter-ocr")
+      (memory $instance "memory") (realloc (func $instance "realloc"))))
+  (export "filter-ocr" (func $filter-ocr))
+  (func $filter-transcript (param "transcript" $transcript) (result $text-action)
+    (canon lift (core func $instance "filter-transcript")
+      (memory $instance "memory") (realloc (func $instance "realloc"))))
+  (export "filter-transcript" (func $filter-transcript)))
+"#;
+
+    fn write_plugin(dir: &Path, name: &str, manifest: &str, wasm: &[u8]) {
+        let plugin_dir = dir.join(name);
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join(PLUGIN_MANIFEST), manifest).unwrap();
+        std::fs::write(plugin_dir.join(DEFAULT_COMPONENT), wasm).unwrap();
+    }
+
+    #[test]
+    fn test_manifests_list_the_capabilities_of_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plugin(
+            dir.path(),
+            "redact",
+            r#"{"capabilities": ["ocr", "frame-pixels"]}"#,
+            b"",
+        );
+        let manifest = PluginManifest::read(&dir.path().join("redact")).unwrap();
+        assert!(manifest.enabled);
+        assert_eq!(manifest.wasm, DEFAULT_COMPONENT);
+        assert!(manifest.capabilities.contains(&PluginCapability::Ocr));
+        assert!(manifest
+            .capabilities
+            .contains(&PluginCapability::FramePixels));
+        assert!(!manifest.capabilities.contains(&PluginCapability::Frames));
+        assert!(!manifest
+            .capabilities
+            .contains(&PluginCapability::Transcripts));
+
+        write_plugin(
+            dir.path(),
+            "unknown",
+            r#"{"capabilities": ["network"]}"#,
+            b"",
+        );
+        assert!(PluginManifest::read(&dir.path().join("unknown")).is_err());
+    }
+
+    #[test]
+    fn test_plugins_are_only_called_with_the_capabilities_of_their_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = REDACTING_PLUGIN.as_bytes();
+        write_plugin(dir.path(), "a-ocr", r#"{"capabilities": ["ocr"]}"#, plugin);
+        write_plugin(
+            dir.path(),
+            "b-frames",
+            r#"{"capabilities": ["frames", "transcripts"]}"#,
+            plugin,
+        );
+        let plugins = WasmPlugins::load(dir.path()).unwrap();
+        assert_eq!(plugins.names(), ["a-ocr", "b-frames"]);
+
+        let window = Window {
+            app_name: "Terminal".to_string(),
+            window_name: "ssh".to_string(),
+            browser_url: None,
+            focused: true,
+            text: "hunter2".to_string(),
+        };
+        assert_eq!(
+            plugins.filter_ocr(window.clone()).as_deref(),
+            Some("redacted")
+        );
+        let frame = Frame {
+            timestamp: 0,
+            monitor_id: 1,
+            width: 1,
+            height: 1,
+            windows: vec![window],
+        };
+        assert_eq!(plugins.process_frame(frame.clone(), || vec![0; 4]), None);
+        let transcript = Transcript {
+            device: "MacBook Pro Microphone".to_string(),
+            input: true,
+            text: "hello".to_string(),
+            start: 0.0,
+            end: 1.0,
+        };
+        assert_eq!(plugins.filter_transcript(transcript), None);
+
+        // a-ocr exports every function, only the one of its capability is called
+        std::fs::remove_dir_all(dir.path().join("b-frames")).unwrap();
+        let plugins = WasmPlugins::load(dir.path()).unwrap();
+        let transcript = Transcript {
+            device: "MacBook Pro Microphone".to_string(),
+            input: true,
+            text: "hello".to_string(),
+            start: 0.0,
+            end: 1.0,
+        };
+        assert_eq!(
+            plugins.filter_transcript(transcript).as_deref(),
+            Some("hello")
+        );
+        assert!(!plugins.wants(PluginCapability::Frames));
+        let processed = plugins.process_frame(frame, || vec![0; 4]).unwrap();
+        assert_eq!(processed.texts, [Some("hunter2".to_string())]);
+        assert_eq!(processed.pixels, None);
+    }
+
+    #[test]
+    fn test_plugin_components_stay_in_their_directory() {
+        let dir = Path::new("/plugins/redact");
+        let manifest = |wasm: &str| -> PluginManifest {
+            serde_json::from_str(&format!(r#"{{"wasm": "{}"}}"#, wasm)).unwrap()
+        };
+        assert_eq!(
+            manifest("build/redact.wasm").component_path(dir).unwrap(),
+            dir.join("build/redact.wasm")
+        );
+        assert!(manifest("../other/plugin.wasm")
+            .component_path(dir)
+            .is_err());
+        assert!(manifest("/tmp/plugin.wasm").component_path(dir).is_err());
+    }
+
+    #[test]
+    fn test_broken_and_disabled_plugins_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plugin(
+            dir.path(),
+            "broken",
+            r#"{"capabilities": ["frames"]}"#,
+            b"not a component",
+        );
+        write_plugin(
+            dir.path(),
+            "disabled",
+            r#"{"enabled": false, "capabilities": ["transcripts"]}"#,
+            b"",
+        );
+
+        let plugins = WasmPlugins::load(dir.path()).unwrap();
+        assert!(plugins.is_empty());
+        assert!(!plugins.wants(PluginCapability::Frames));
+        assert!(WasmPlugins::load(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -2,7 +2,7 @@
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use rand::Rng;
-use screenpipe_db::{AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine};
+use screenpipe_db::{AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine, SearchExclusions, SearchSort};
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
@@ -16,7 +16,7 @@ async fn setup_large_db(size: usize) -> DatabaseManager {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, None, None, false, Some(0.0))
+            .insert_frame("test_device", None, None, None, None, None, None, None, false, Some(0.0), None)
             .await
             .unwrap();
         let ocr_text = format!("OCR text {}", rng.gen::<u32>());
@@ -26,6 +26,7 @@ async fn setup_large_db(size: usize) -> DatabaseManager {
             &ocr_text,
             &text_json,
             Arc::new(OcrEngine::default()), // Assuming a default implementation
+            false,
         )
         .await
         .unwrap();
@@ -88,7 +89,12 @@ fn bench_search(c: &mut Criterion) {
                                 None,
                                 None,
                                 None
-                            )
+                            ,
+                                None,
+                                false,
+                                &SearchExclusions::default(),
+                                None,
+                                SearchSort::Time)
                             .await
                             .unwrap()
                         });
@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Result};
+use libsqlite3_sys as ffi;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection as _};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_int;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+// How long the backup waits for the recorder's write lock
+const BUSY_TIMEOUT_MS: c_int = 10_000;
+const BUSY_RETRY: Duration = Duration::from_millis(100);
+
+/// Copies the database at `database_path` to `destination` with the SQLite online backup
+/// API, while screenpipe keeps recording. `key` is the hex SQLCipher key of an encrypted
+/// database, the copy is encrypted with it too.
+pub async fn backup_database(
+    database_path: &str,
+    destination: &Path,
+    key: Option<&str>,
+) -> Result<()> {
+    let key = key.map(check_key).transpose()?;
+    let source = database_path.to_string();
+    let destination = destination.to_path_buf();
+    tokio::task::spawn_blocking(move || copy_database(&source, &destination, key.as_deref()))
+        .await?
+}
+
+/// Fails unless SQLite finds the database at `database_path` intact.
+pub async fn verify_database(database_path: &str, key: Option<&str>) -> Result<()> {
+    let mut options =
+        SqliteConnectOptions::from_str(&format!("sqlite:{}", database_path))?.read_only(true);
+    if let Some(key) = key {
+        options = options.pragma("key", format!("\"x'{}'\"", check_key(key)?));
+    }
+    let mut conn = options.connect().await?;
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut conn)
+        .await?;
+    conn.close().await?;
+
+    match problems.as_slice() {
+        [ok] if ok == "ok" => Ok(()),
+        problems => Err(anyhow!(
+            "database {} is corrupted: {}",
+            database_path,
+            problems.join(", ")
+        )),
+    }
+}
+
+fn check_key(key: &str) -> Result<String> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("database key must be hex"));
+    }
+    Ok(key.to_string())
+}
+
+fn copy_database(source: &str, destination: &Path, key: Option<&str>) -> Result<()> {
+    let source = RawConnection::open(source, ffi::SQLITE_OPEN_READWRITE, key)?;
+    let destination = RawConnection::open(
+        &destination.to_string_lossy(),
+        ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+        key,
+    )?;
+
+    let main = CString::new("main").expect("no nul in a literal");
+    let backup =
+        unsafe { ffi::sqlite3_backup_init(destination.0, main.as_ptr(), source.0, main.as_ptr()) };
+    if backup.is_null() {
+        return Err(destination.error("failed to start the backup"));
+    }
+
+    // a single step copies every page in one read transaction, so the copy is consistent.
+    // The database is in WAL mode, this doesn't block the recorder's writes
+    let rc = loop {
+        match unsafe { ffi::sqlite3_backup_step(backup, -1) } {
+            ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => std::thread::sleep(BUSY_RETRY),
+            rc => break rc,
+        }
+    };
+    unsafe { ffi::sqlite3_backup_finish(backup) };
+    if rc != ffi::SQLITE_DONE {
+        return Err(destination.error("failed to back up the database"));
+    }
+    // a single file, without a write-ahead log to keep next to it
+    destination.execute("PRAGMA journal_mode = DELETE;")
+}
+
+/// A connection outside the pool, the backup API needs the raw handles.
+struct RawConnection(*mut ffi::sqlite3);
+
+impl RawConnection {
+    fn open(path: &str, flags: c_int, key: Option<&str>) -> Result<Self> {
+        let c_path = CString::new(path)?;
+        let mut handle = std::ptr::null_mut();
+        let rc =
+            unsafe { ffi::sqlite3_open_v2(c_path.as_ptr(), &mut handle, flags, std::ptr::null()) };
+        // sqlite hands out a handle to close even when opening fails
+        let conn = RawConnection(handle);
+        if rc != ffi::SQLITE_OK {
+            return Err(conn.error(&format!("failed to open {}", path)));
+        }
+        unsafe { ffi::sqlite3_busy_timeout(handle, BUSY_TIMEOUT_MS) };
+        if let Some(key) = key {
+            conn.execute(&format!("PRAGMA key = \"x'{}'\";", key))?;
+        }
+        Ok(conn)
+    }
+
+    fn execute(&self, sql: &str) -> Result<()> {
+        let sql = CString::new(sql)?;
+        let rc = unsafe {
+            ffi::sqlite3_exec(
+                self.0,
+                sql.as_ptr(),
+                None,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if rc != ffi::SQLITE_OK {
+            return Err(self.error("failed to configure the connection"));
+        }
+        Ok(())
+    }
+
+    fn error(&self, context: &str) -> anyhow::Error {
+        let message = unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(self.0)) };
+        anyhow!("{}: {}", context, message.to_string_lossy())
+    }
+}
+
+impl Drop for RawConnection {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_close(self.0) };
+    }
+}
@@ -1,37 +1,101 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use image::DynamicImage;
 use libsqlite3_sys::sqlite3_auto_extension;
 use sqlite_vec::sqlite3_vec_init;
 use sqlx::migrate::MigrateDatabase;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::Column;
+use sqlx::ConnectOptions;
 use sqlx::Error as SqlxError;
 use sqlx::Row;
 use sqlx::TypeInfo;
 use sqlx::ValueRef;
+use std::io::Read;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use zerocopy::AsBytes;
 
 use futures::future::try_join_all;
 
+use crate::vector_store::{
+    match_distances, match_ids, SqliteVecStore, VectorBackend, VectorCollection, VectorMatch,
+    VectorStore,
+};
 use crate::{
-    AudioChunksResponse, AudioDevice, AudioEntry, AudioResult, AudioResultRaw, ContentType,
-    DeviceType, FrameData, FrameRow, OCREntry, OCRResult, OCRResultRaw, OcrEngine, OcrTextBlock,
-    Order, SearchMatch, SearchResult, Speaker, TagContentType, TextBounds, TextPosition,
-    TimeSeriesChunk, UiContent, VideoMetadata,
+    ActionItem, AlertEvent, AlertRule, Annotation, ApiToken, AppUsage, AudioChunksResponse,
+    AudioDevice, AudioDeviceEvent, AudioEntry, AudioResult, AudioResultRaw,
+    AudioTranscriptionVersion, Bookmark, CalendarEvent, CapturePause, CapturedText,
+    CapturedTranscription, ClipboardEntry, ColdMedia, ContentType, DataDeletion, DataFilter,
+    DeviceType, ExportFrame, ExportTranscription, FrameCode, FrameData, FrameRow, FrameSimilarity,
+    FrameTable, ImportedFrame, ImportedTranscription, InputActivity, MediaFile, MediaKind,
+    MeetingNotes, OCREntry, OCRResult, OCRResultRaw, OcrEngine, OcrTextLayout, OcrWord, Order,
+    SearchExclusions, SearchMatch, SearchResult, SearchSort, Speaker, StitchedDocument,
+    TagContentType, TextPosition, TimeSeriesChunk, TimelineFrame, TimelineTranscription,
+    TranscriptWord, UiContent, VideoMetadata, VideoSegment, Webhook, WebhookDelivery,
+    WindowGeometry, WindowSession,
 };
 
+// Nearest neighbours fetched per result, so filtering them by time or app still fills the
+// results. More are fetched, this many times as many, while the filters leave too few.
+const VECTOR_QUERY_OVERFETCH: u32 = 4;
+// The most nearest neighbours fetched, sqlite-vec doesn't take a larger k
+const MAX_VECTOR_NEIGHBOURS: u32 = 4096;
+// Failed translations after which a transcription is left untranslated
+const MAX_TRANSLATION_ATTEMPTS: i64 = 5;
+// Failed embeddings after which a text is left without one
+const MAX_EMBEDDING_ATTEMPTS: i64 = 5;
+/// Deliveries kept per webhook, older ones are deleted as new ones are logged
+pub const MAX_WEBHOOK_DELIVERIES: i64 = 1000;
+// Frames whose OCR text is past a cutoff `?1`, pinned frames keep theirs
+const FRAMES_BEFORE_SQL: &str = "SELECT id FROM frames WHERE timestamp < ?1 \
+    AND id NOT IN (SELECT frame_id FROM bookmarks WHERE frame_id IS NOT NULL)";
+
 pub struct DatabaseManager {
     pub pool: SqlitePool,
+    vector_store: VectorStore,
+}
+
+/// Whether the database at `database_path` exists unencrypted. An encrypted database
+/// doesn't start with the SQLite header.
+pub fn database_is_plaintext(database_path: &str) -> bool {
+    let mut header = [0u8; 16];
+    std::fs::File::open(database_path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && &header == b"SQLite format 3\0"
+}
+
+/// Whether the database at `database_path` exists and is encrypted.
+pub fn database_is_encrypted(database_path: &str) -> bool {
+    std::fs::metadata(database_path).is_ok_and(|metadata| metadata.len() > 0)
+        && !database_is_plaintext(database_path)
+}
+
+fn media_table(kind: MediaKind) -> &'static str {
+    match kind {
+        MediaKind::VideoChunk => "video_chunks",
+        MediaKind::VideoSegment => "video_segments",
+        MediaKind::AudioChunk => "audio_chunks",
+    }
 }
 
 impl DatabaseManager {
     pub async fn new(database_path: &str) -> Result<Self, sqlx::Error> {
+        Self::open(database_path, None).await
+    }
+
+    /// Opens the database encrypted with SQLCipher under `key`, a hex AES-256 key. A
+    /// plaintext database is encrypted first. Needs a build with the sqlcipher feature.
+    pub async fn new_encrypted(database_path: &str, key: &str) -> Result<Self, sqlx::Error> {
+        Self::open(database_path, Some(key)).await
+    }
+
+    async fn open(database_path: &str, key: Option<&str>) -> Result<Self, sqlx::Error> {
         debug!(
             "Initializing DatabaseManager with database path: {}",
             database_path
@@ -46,6 +110,19 @@ impl DatabaseManager {
             ));
         }
 
+        let mut options = SqliteConnectOptions::from_str(&connection_string)?;
+        if let Some(key) = key {
+            if !key.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(SqlxError::Configuration("database key must be hex".into()));
+            }
+            Self::check_sqlcipher().await?;
+            if database_is_plaintext(database_path) {
+                info!("encrypting database {}", database_path);
+                Self::encrypt_plaintext(database_path, key).await?;
+            }
+            options = options.pragma("key", format!("\"x'{}'\"", key));
+        }
+
         // Create the database if it doesn't exist
         if !sqlx::Sqlite::database_exists(&connection_string).await? {
             sqlx::Sqlite::create_database(&connection_string).await?;
@@ -55,7 +132,7 @@ impl DatabaseManager {
             .max_connections(50)
             .min_connections(3) // Minimum number of idle connections
             .acquire_timeout(Duration::from_secs(10))
-            .connect(&connection_string)
+            .connect_with(options)
             .await?;
 
         // Enable WAL mode
@@ -73,7 +150,10 @@ impl DatabaseManager {
             .execute(&pool)
             .await?;
 
-        let db_manager = DatabaseManager { pool };
+        let db_manager = DatabaseManager {
+            vector_store: VectorStore::SqliteVec(SqliteVecStore::new(pool.clone())),
+            pool,
+        };
 
         // Run migrations after establishing the connection
         Self::run_migrations(&db_manager.pool).await?;
@@ -81,6 +161,68 @@ impl DatabaseManager {
         Ok(db_manager)
     }
 
+    async fn check_sqlcipher() -> Result<(), sqlx::Error> {
+        let mut conn = SqliteConnectOptions::from_str("sqlite::memory:")?
+            .connect()
+            .await?;
+        let version: Option<String> = sqlx::query_scalar("PRAGMA cipher_version")
+            .fetch_optional(&mut conn)
+            .await?;
+        conn.close().await?;
+        match version {
+            Some(version) => {
+                debug!("using sqlcipher {}", version);
+                Ok(())
+            }
+            None => Err(SqlxError::Configuration(
+                "database encryption needs a build with the sqlcipher feature".into(),
+            )),
+        }
+    }
+
+    /// Copies a plaintext database into an encrypted one that replaces it.
+    async fn encrypt_plaintext(database_path: &str, key: &str) -> Result<(), sqlx::Error> {
+        let encrypted_path = format!("{}.encrypting", database_path);
+        let _ = std::fs::remove_file(&encrypted_path);
+
+        let mut conn = SqliteConnectOptions::from_str(&format!("sqlite:{}", database_path))?
+            .connect()
+            .await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&mut conn)
+            .await?;
+        sqlx::query(&format!(
+            "ATTACH DATABASE '{}' AS encrypted KEY \"x'{}'\"",
+            encrypted_path.replace('\'', "''"),
+            key
+        ))
+        .execute(&mut conn)
+        .await?;
+        sqlx::query("SELECT sqlcipher_export('encrypted')")
+            .execute(&mut conn)
+            .await?;
+        sqlx::query("DETACH DATABASE encrypted")
+            .execute(&mut conn)
+            .await?;
+        conn.close().await?;
+
+        std::fs::rename(&encrypted_path, database_path)?;
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", database_path, suffix));
+        }
+        Ok(())
+    }
+
+    /// Runs nearest neighbour queries against `backend` instead of the sqlite-vec tables.
+    /// Embeddings stored so far only reach it through [`Self::build_vector_indexes`].
+    pub async fn with_vector_backend(
+        mut self,
+        backend: &VectorBackend,
+    ) -> Result<Self, anyhow::Error> {
+        self.vector_store = VectorStore::open(self.pool.clone(), backend).await?;
+        Ok(self)
+    }
+
     async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         let mut migrator = sqlx::migrate!("./src/migrations");
         migrator.set_ignore_missing(true);
@@ -91,10 +233,19 @@ impl DatabaseManager {
     }
 
     pub async fn insert_audio_chunk(&self, file_path: &str) -> Result<i64, sqlx::Error> {
+        self.insert_audio_chunk_at(file_path, Utc::now()).await
+    }
+
+    /// Inserts a chunk recorded at `timestamp`, e.g. of an imported recording.
+    pub async fn insert_audio_chunk_at(
+        &self,
+        file_path: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
         let mut tx = self.pool.begin().await?;
         let id = sqlx::query("INSERT INTO audio_chunks (file_path, timestamp) VALUES (?1, ?2)")
             .bind(file_path)
-            .bind(Utc::now())
+            .bind(timestamp)
             .execute(&mut *tx)
             .await?
             .last_insert_rowid();
@@ -110,6 +261,10 @@ impl DatabaseManager {
         Ok(id.unwrap_or(0))
     }
 
+    pub async fn has_audio_chunk(&self, file_path: &str) -> Result<bool, sqlx::Error> {
+        Ok(self.get_audio_chunk_id(file_path).await? != 0)
+    }
+
     pub async fn get_or_insert_audio_chunk(&self, file_path: &str) -> Result<i64, sqlx::Error> {
         let mut id = self.get_audio_chunk_id(file_path).await?;
         if id == 0 {
@@ -142,6 +297,34 @@ impl DatabaseManager {
         speaker_id: Option<i64>,
         start_time: Option<f64>,
         end_time: Option<f64>,
+    ) -> Result<i64, sqlx::Error> {
+        self.insert_audio_transcription_at(
+            audio_chunk_id,
+            transcription,
+            offset_index,
+            transcription_engine,
+            device,
+            speaker_id,
+            start_time,
+            end_time,
+            Utc::now(),
+        )
+        .await
+    }
+
+    /// Inserts a transcription of speech said at `timestamp`, e.g. in an imported recording.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_audio_transcription_at(
+        &self,
+        audio_chunk_id: i64,
+        transcription: &str,
+        offset_index: i64,
+        transcription_engine: &str,
+        device: &AudioDevice,
+        speaker_id: Option<i64>,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        timestamp: DateTime<Utc>,
     ) -> Result<i64, sqlx::Error> {
         let text_length = transcription.len() as i64;
         let mut tx = self.pool.begin().await?;
@@ -153,7 +336,7 @@ impl DatabaseManager {
         .bind(audio_chunk_id)
         .bind(transcription)
         .bind(offset_index)
-        .bind(Utc::now())
+        .bind(timestamp)
         .bind(transcription_engine)
         .bind(&device.name)
         .bind(device.device_type == DeviceType::Input)
@@ -171,6 +354,48 @@ impl DatabaseManager {
         Ok(id)
     }
 
+    /// Inserts the chunk of a recording imported from `file_path`, recorded at `timestamp`,
+    /// with its transcriptions. All or nothing, an import failing halfway is imported again.
+    pub async fn insert_imported_audio(
+        &self,
+        file_path: &str,
+        timestamp: DateTime<Utc>,
+        transcription_engine: &str,
+        device: &AudioDevice,
+        transcriptions: &[ImportedTranscription],
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let audio_chunk_id =
+            sqlx::query("INSERT INTO audio_chunks (file_path, timestamp) VALUES (?1, ?2)")
+                .bind(file_path)
+                .bind(timestamp)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+        for transcription in transcriptions {
+            let words =
+                serde_json::to_string(&transcription.words).unwrap_or_else(|_| "[]".to_string());
+            sqlx::query(
+                "INSERT INTO audio_transcriptions (audio_chunk_id, transcription, offset_index, timestamp, transcription_engine, device, is_input_device, start_time, end_time, text_length, words) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )
+            .bind(audio_chunk_id)
+            .bind(&transcription.transcription)
+            .bind(transcription.offset_index)
+            .bind(transcription.timestamp)
+            .bind(transcription_engine)
+            .bind(&device.name)
+            .bind(device.device_type == DeviceType::Input)
+            .bind(transcription.start_time)
+            .bind(transcription.end_time)
+            .bind(transcription.transcription.len() as i64)
+            .bind(words)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(audio_chunk_id)
+    }
+
     pub async fn update_audio_transcription(
         &self,
         audio_chunk_id: i64,
@@ -181,7 +406,7 @@ impl DatabaseManager {
 
         // Insert the full transcription
         let affected = sqlx::query(
-            "UPDATE audio_transcriptions SET transcription = ?1, text_length = ?2 WHERE audio_chunk_id = ?3",
+            "UPDATE audio_transcriptions SET transcription = ?1, text_length = ?2, translation = NULL, translation_language = NULL, translation_attempts = 0 WHERE audio_chunk_id = ?3",
         )
         .bind(transcription)
         .bind(text_length)
@@ -190,11 +415,161 @@ impl DatabaseManager {
         .await?
         .rows_affected();
 
+        // the text changed, the embedder picks the transcription up again
+        sqlx::query(
+            "DELETE FROM audio_transcription_embeddings WHERE audio_transcription_id IN (SELECT id FROM audio_transcriptions WHERE audio_chunk_id = ?1)",
+        )
+        .bind(audio_chunk_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "DELETE FROM embedding_failures WHERE collection = 'transcriptions' AND content_id IN (SELECT id FROM audio_transcriptions WHERE audio_chunk_id = ?1)",
+        )
+        .bind(audio_chunk_id)
+        .execute(&mut *tx)
+        .await?;
+
         // Commit the transaction for the full transcription
         tx.commit().await?;
         Ok(affected as i64)
     }
 
+    /// Stores when each word of transcription `id` was said, in seconds into its chunk.
+    pub async fn set_audio_transcription_words(
+        &self,
+        id: i64,
+        words: &[TranscriptWord],
+    ) -> Result<(), sqlx::Error> {
+        let words = serde_json::to_string(words).unwrap_or_else(|_| "[]".to_string());
+        sqlx::query("UPDATE audio_transcriptions SET words = ?1 WHERE id = ?2")
+            .bind(words)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Replaces transcription `id` by a new transcript of its audio, the transcript it had
+    /// is kept as a version. Its translation is dropped and it is embedded again.
+    pub async fn replace_audio_transcription(
+        &self,
+        id: i64,
+        transcription: &str,
+        transcription_engine: &str,
+        words: &[TranscriptWord],
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO audio_transcription_versions \
+             (audio_transcription_id, transcription, transcription_engine, words) \
+             SELECT id, transcription, transcription_engine, COALESCE(words, '[]') \
+             FROM audio_transcriptions WHERE id = ?1",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let words = serde_json::to_string(words).unwrap_or_else(|_| "[]".to_string());
+        let affected = sqlx::query(
+            "UPDATE audio_transcriptions SET transcription = ?1, transcription_engine = ?2, \
+             text_length = ?3, words = ?4, translation = NULL, translation_language = NULL, \
+             translation_attempts = 0 WHERE id = ?5",
+        )
+        .bind(transcription)
+        .bind(transcription_engine)
+        .bind(transcription.len() as i64)
+        .bind(words)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM audio_transcription_embeddings WHERE audio_transcription_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "DELETE FROM embedding_failures WHERE collection = 'transcriptions' AND content_id = ?1",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(affected > 0)
+    }
+
+    /// The transcripts transcription `id` had before it was re-transcribed, oldest first.
+    pub async fn get_audio_transcription_versions(
+        &self,
+        id: i64,
+    ) -> Result<Vec<AudioTranscriptionVersion>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, audio_transcription_id, transcription, transcription_engine, words, \
+             replaced_at FROM audio_transcription_versions \
+             WHERE audio_transcription_id = ?1 ORDER BY id ASC",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Transcriptions not yet translated to `language`, newest first. The ones translation
+    /// failed on come after the others, and not at all after a few failures.
+    pub async fn get_transcriptions_without_translation(
+        &self,
+        language: &str,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        // < and > rather than != so the translation language index is used
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, transcription
+            FROM audio_transcriptions
+            WHERE TRIM(transcription) != ''
+                AND (translation_language IS NULL
+                    OR translation_language < ?1
+                    OR translation_language > ?1)
+                AND translation_attempts < ?2
+            ORDER BY translation_attempts ASC, id DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(language)
+        .bind(MAX_TRANSLATION_ATTEMPTS)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records that translating transcription `id` failed.
+    pub async fn record_translation_failure(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE audio_transcriptions SET translation_attempts = translation_attempts + 1 \
+             WHERE id = ?1",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores the translation of transcription `id` to `language`, an empty one when it
+    /// already is in that language.
+    pub async fn set_audio_transcription_translation(
+        &self,
+        id: i64,
+        translation: &str,
+        language: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE audio_transcriptions SET translation = ?1, translation_language = ?2, translation_attempts = 0 WHERE id = ?3",
+        )
+        .bind(translation)
+        .bind(language)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn insert_speaker(&self, embedding: &[f32]) -> Result<Speaker, SqlxError> {
         let mut tx = self.pool.begin().await?;
 
@@ -243,11 +618,14 @@ impl DatabaseManager {
         Ok(speaker)
     }
 
+    /// The speaker whose voice is nearest to `embedding`, if close enough. Enrolled voices
+    /// must be closer, a name is only given to a voice that surely is theirs.
     pub async fn get_speaker_from_embedding(
         &self,
         embedding: &[f32],
     ) -> Result<Option<Speaker>, SqlxError> {
         let speaker_threshold = 0.5;
+        let enrolled_speaker_threshold = 0.3;
         let bytes: &[u8] = embedding.as_bytes();
 
         // Using subquery with LIMIT 1 instead of JOIN
@@ -255,21 +633,93 @@ impl DatabaseManager {
             "SELECT id, name, metadata
              FROM speakers
              WHERE id = (
-                 SELECT speaker_id
-                 FROM speaker_embeddings
-                 WHERE vec_distance_cosine(embedding, vec_f32(?1)) < ?2
-                 ORDER BY vec_distance_cosine(embedding, vec_f32(?1))
+                 SELECT se.speaker_id
+                 FROM speaker_embeddings se
+                 JOIN speakers s ON s.id = se.speaker_id
+                 WHERE vec_distance_cosine(se.embedding, vec_f32(?1))
+                     < CASE WHEN s.enrolled THEN ?3 ELSE ?2 END
+                 ORDER BY vec_distance_cosine(se.embedding, vec_f32(?1))
                  LIMIT 1
              )",
         )
         .bind(bytes)
         .bind(speaker_threshold)
+        .bind(enrolled_speaker_threshold)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(speaker)
     }
 
+    /// Enrolls the voice of `name` from `embeddings` of samples of it. Enrolling a name again
+    /// adds the embeddings to the speaker enrolled under it.
+    pub async fn enroll_speaker(
+        &self,
+        name: &str,
+        embeddings: &[Vec<f32>],
+    ) -> Result<Speaker, SqlxError> {
+        let mut tx = self.pool.begin().await?;
+        let enrolled: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM speakers WHERE name = ?1 AND enrolled = 1")
+                .bind(name)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let id = match enrolled {
+            Some(id) => id,
+            None => sqlx::query("INSERT INTO speakers (name, enrolled) VALUES (?1, 1)")
+                .bind(name)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid(),
+        };
+        for embedding in embeddings {
+            let bytes: &[u8] = embedding.as_bytes();
+            sqlx::query(
+                "INSERT INTO speaker_embeddings (embedding, speaker_id) VALUES (vec_f32(?1), ?2)",
+            )
+            .bind(bytes)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.get_speaker_by_id(id).await
+    }
+
+    /// Enrolls a speaker recognized so far as `name`, its voice is the one already stored.
+    pub async fn enroll_existing_speaker(
+        &self,
+        speaker_id: i64,
+        name: &str,
+    ) -> Result<Speaker, SqlxError> {
+        let updated = sqlx::query("UPDATE speakers SET name = ?1, enrolled = 1 WHERE id = ?2")
+            .bind(name)
+            .bind(speaker_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if updated == 0 {
+            return Err(SqlxError::RowNotFound);
+        }
+        self.get_speaker_by_id(speaker_id).await
+    }
+
+    /// The speaker goes back to being recognized like any other, its name is kept.
+    pub async fn unenroll_speaker(&self, speaker_id: i64) -> Result<(), SqlxError> {
+        sqlx::query("UPDATE speakers SET enrolled = 0 WHERE id = ?1")
+            .bind(speaker_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_enrolled_speakers(&self) -> Result<Vec<Speaker>, SqlxError> {
+        sqlx::query_as("SELECT id, name, metadata FROM speakers WHERE enrolled = 1 ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+    }
+
     pub async fn update_speaker_name(&self, speaker_id: i64, name: &str) -> Result<i64, SqlxError> {
         let mut tx = self.pool.begin().await?;
         sqlx::query("UPDATE speakers SET name = ?1 WHERE id = ?2")
@@ -277,24 +727,2338 @@ impl DatabaseManager {
             .bind(speaker_id)
             .execute(&mut *tx)
             .await?;
-        tx.commit().await?;
-        Ok(speaker_id)
+        tx.commit().await?;
+        Ok(speaker_id)
+    }
+
+    pub async fn insert_video_chunk(
+        &self,
+        file_path: &str,
+        device_name: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
+            .bind(file_path)
+            .bind(device_name)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Inserts the image chunk of a screenshot or video imported to `file_path` with its
+    /// frames and their text. All or nothing, an import failing halfway is imported again.
+    pub async fn insert_imported_frames(
+        &self,
+        file_path: &str,
+        device_name: &str,
+        window_name: Option<&str>,
+        ocr_engine: &OcrEngine,
+        frames: &[ImportedFrame],
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let video_chunk_id =
+            sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
+                .bind(file_path)
+                .bind(device_name)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+        for (offset_index, frame) in frames.iter().enumerate() {
+            let frame_id = sqlx::query(
+                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, window_name, focused, visible_percentage, phash) VALUES (?1, ?2, ?3, ?4, ?5, 0, 0.0, ?6)",
+            )
+            .bind(video_chunk_id)
+            .bind(offset_index as i64)
+            .bind(frame.timestamp)
+            .bind(file_path)
+            .bind(window_name)
+            .bind(frame.phash)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+            let Some((text, text_json)) = &frame.ocr else {
+                continue;
+            };
+            sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length, low_quality) VALUES (?1, ?2, ?3, ?4, ?5, 0)")
+                .bind(frame_id)
+                .bind(text)
+                .bind(text_json)
+                .bind(format!("{:?}", ocr_engine))
+                .bind(text.len() as i64)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(video_chunk_id)
+    }
+
+    pub async fn has_video_chunk(&self, file_path: &str) -> Result<bool, sqlx::Error> {
+        let id = sqlx::query_scalar::<_, i64>("SELECT id FROM video_chunks WHERE file_path = ?1")
+            .bind(file_path)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(id.is_some())
+    }
+
+    /// Records that image `offset_index` of the image chunk `chunk_path` holds the frame
+    /// blob `content_key`, stored at `file_path`. The blob is added on its first reference.
+    /// Returns how many images point at the blob.
+    pub async fn add_frame_blob_ref(
+        &self,
+        content_key: &str,
+        file_path: &str,
+        chunk_path: &str,
+        offset_index: i64,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO frame_blobs (content_key, file_path) VALUES (?1, ?2) ON CONFLICT(content_key) DO NOTHING",
+        )
+        .bind(content_key)
+        .bind(file_path)
+        .execute(&mut *tx)
+        .await?;
+        let blob_id: i64 = sqlx::query_scalar("SELECT id FROM frame_blobs WHERE content_key = ?1")
+            .bind(content_key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let added = sqlx::query(
+            "INSERT INTO frame_blob_refs (chunk_path, offset_index, blob_id) VALUES (?1, ?2, ?3) ON CONFLICT(chunk_path, offset_index) DO NOTHING",
+        )
+        .bind(chunk_path)
+        .bind(offset_index)
+        .bind(blob_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if added > 0 {
+            sqlx::query("UPDATE frame_blobs SET ref_count = ref_count + 1 WHERE id = ?1")
+                .bind(blob_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let ref_count: i64 = sqlx::query_scalar("SELECT ref_count FROM frame_blobs WHERE id = ?1")
+            .bind(blob_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(ref_count)
+    }
+
+    /// Drops the blob references of every image of `chunk_path` and deletes the blobs
+    /// nothing points at anymore. Returns the paths of the deleted blobs, their files are
+    /// left to the caller.
+    pub async fn release_frame_blobs(&self, chunk_path: &str) -> Result<Vec<String>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE frame_blobs
+            SET ref_count = ref_count - (
+                SELECT COUNT(*) FROM frame_blob_refs
+                WHERE frame_blob_refs.blob_id = frame_blobs.id AND frame_blob_refs.chunk_path = ?1
+            )
+            WHERE id IN (SELECT blob_id FROM frame_blob_refs WHERE chunk_path = ?1)
+            "#,
+        )
+        .bind(chunk_path)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM frame_blob_refs WHERE chunk_path = ?1")
+            .bind(chunk_path)
+            .execute(&mut *tx)
+            .await?;
+        let released: Vec<String> =
+            sqlx::query_scalar("DELETE FROM frame_blobs WHERE ref_count <= 0 RETURNING file_path")
+                .fetch_all(&mut *tx)
+                .await?;
+        tx.commit().await?;
+        Ok(released)
+    }
+
+    /// Media files of `kind` whose data was all recorded before `before` and that weren't
+    /// purged yet, oldest first. Files of pinned frames are left out, see `insert_bookmark`.
+    pub async fn get_media_to_purge(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        self.get_media_before(kind, &["purged_at"], true, before, limit)
+            .await
+    }
+
+    /// Media files of `kind` recorded before `before` that are still on disk unencrypted,
+    /// oldest first.
+    pub async fn get_media_to_encrypt(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        self.get_media_before(kind, &["purged_at", "encrypted_at"], false, before, limit)
+            .await
+    }
+
+    /// Media files of `kind` recorded before `before` that are only on disk, oldest first.
+    pub async fn get_media_to_tier(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        self.get_media_before(kind, &["purged_at", "cold_key"], false, before, limit)
+            .await
+    }
+
+    /// Like `get_media_to_tier`, without the files of pinned frames.
+    pub async fn get_media_to_evict(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        self.get_media_before(kind, &["purged_at", "cold_key"], true, before, limit)
+            .await
+    }
+
+    /// Media files of `kind` recorded before `before` whose `unset` columns are all NULL,
+    /// without the ones holding a pinned frame when `keep_pinned` is set.
+    async fn get_media_before(
+        &self,
+        kind: MediaKind,
+        unset: &[&str],
+        keep_pinned: bool,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        let table = media_table(kind);
+        let mut filters: Vec<String> = unset
+            .iter()
+            .map(|column| format!("{}.{} IS NULL", table, column))
+            .collect();
+        let pinned = match kind {
+            MediaKind::VideoChunk => Some(
+                "NOT EXISTS (SELECT 1 FROM bookmarks \
+                 JOIN frames AS pinned ON pinned.id = bookmarks.frame_id \
+                 WHERE pinned.video_chunk_id = video_chunks.id)",
+            ),
+            // recordings have no frames, the pinned time is in them
+            MediaKind::VideoSegment => Some(
+                "NOT EXISTS (SELECT 1 FROM bookmarks \
+                 WHERE bookmarks.timestamp >= video_segments.start_time \
+                 AND bookmarks.timestamp <= video_segments.end_time)",
+            ),
+            // only frames are pinned
+            MediaKind::AudioChunk => None,
+        };
+        if keep_pinned {
+            filters.extend(pinned.map(String::from));
+        }
+        let filter = filters.join(" AND ");
+        let query = match kind {
+            MediaKind::VideoChunk => format!(
+                r#"
+                SELECT video_chunks.id, video_chunks.file_path, MAX(frames.timestamp) AS timestamp
+                FROM video_chunks
+                JOIN frames ON frames.video_chunk_id = video_chunks.id
+                WHERE {}
+                GROUP BY video_chunks.id
+                HAVING MAX(frames.timestamp) < ?1
+                ORDER BY timestamp ASC
+                LIMIT ?2
+                "#,
+                filter
+            ),
+            MediaKind::VideoSegment => format!(
+                r#"
+                SELECT id, file_path, end_time AS timestamp
+                FROM video_segments
+                WHERE {} AND end_time IS NOT NULL AND end_time < ?1
+                ORDER BY end_time ASC
+                LIMIT ?2
+                "#,
+                filter
+            ),
+            MediaKind::AudioChunk => format!(
+                r#"
+                SELECT id, file_path, timestamp
+                FROM audio_chunks
+                WHERE {} AND timestamp IS NOT NULL AND timestamp < ?1
+                ORDER BY timestamp ASC
+                LIMIT ?2
+                "#,
+                filter
+            ),
+        };
+
+        sqlx::query_as::<_, MediaFile>(&query)
+            .bind(before)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Marks the files of `ids` as deleted from disk. The rows stay, the text recorded
+    /// from them still points at them.
+    pub async fn mark_media_purged(&self, kind: MediaKind, ids: &[i64]) -> Result<(), sqlx::Error> {
+        self.mark_media(kind, "purged_at", ids).await
+    }
+
+    /// Marks the files of `ids` as encrypted at rest.
+    pub async fn mark_media_encrypted(
+        &self,
+        kind: MediaKind,
+        ids: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        self.mark_media(kind, "encrypted_at", ids).await
+    }
+
+    /// Records that a file was moved to cold storage under `cold_key`.
+    pub async fn mark_media_tiered(
+        &self,
+        kind: MediaKind,
+        id: i64,
+        cold_key: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET cold_key = ?1, fetched_at = NULL WHERE id = ?2",
+            media_table(kind)
+        ))
+        .bind(cold_key)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The cold storage object of the media file at `file_path`, if it was moved there
+    /// and not purged since.
+    pub async fn get_cold_media(&self, file_path: &str) -> Result<Option<ColdMedia>, sqlx::Error> {
+        for kind in [
+            MediaKind::VideoChunk,
+            MediaKind::VideoSegment,
+            MediaKind::AudioChunk,
+        ] {
+            let row: Option<(i64, String)> = sqlx::query_as(&format!(
+                "SELECT id, cold_key FROM {} \
+                 WHERE file_path = ?1 AND cold_key IS NOT NULL AND purged_at IS NULL",
+                media_table(kind)
+            ))
+            .bind(file_path)
+            .fetch_optional(&self.pool)
+            .await?;
+            if let Some((id, cold_key)) = row {
+                return Ok(Some(ColdMedia { kind, id, cold_key }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Marks the local copy of a file in cold storage as fetched now.
+    pub async fn mark_media_fetched(&self, kind: MediaKind, id: i64) -> Result<(), sqlx::Error> {
+        self.mark_media(kind, "fetched_at", &[id]).await
+    }
+
+    /// Files in cold storage whose local copy was fetched before `before`, the timestamp
+    /// is when it was fetched.
+    pub async fn get_media_fetched_before(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<MediaFile>, sqlx::Error> {
+        sqlx::query_as::<_, MediaFile>(&format!(
+            r#"
+            SELECT id, file_path, fetched_at AS timestamp
+            FROM {}
+            WHERE cold_key IS NOT NULL AND purged_at IS NULL
+                AND datetime(fetched_at) < datetime(?1)
+            ORDER BY fetched_at ASC
+            LIMIT ?2
+            "#,
+            media_table(kind)
+        ))
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Forgets the local copies of `ids`, after they were deleted again.
+    pub async fn clear_media_fetched(
+        &self,
+        kind: MediaKind,
+        ids: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(&format!(
+            "UPDATE {} SET fetched_at = NULL WHERE id IN (SELECT value FROM json_each(?1))",
+            media_table(kind)
+        ))
+        .bind(serde_json::to_string(ids).unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn mark_media(
+        &self,
+        kind: MediaKind,
+        column: &str,
+        ids: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(&format!(
+            "UPDATE {} SET {} = CURRENT_TIMESTAMP WHERE id IN (SELECT value FROM json_each(?1))",
+            media_table(kind),
+            column
+        ))
+        .bind(serde_json::to_string(ids).unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Points the recorded file paths under `from` at `to`, for a database restored to
+    /// another data dir. Returns the number of paths changed.
+    pub async fn rebase_media_paths(&self, from: &str, to: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut rebased = 0;
+        for (table, column) in [
+            ("video_chunks", "file_path"),
+            ("video_segments", "file_path"),
+            ("audio_chunks", "file_path"),
+            ("frame_blobs", "file_path"),
+            ("frame_blob_refs", "chunk_path"),
+            ("scroll_documents", "image_path"),
+        ] {
+            rebased += sqlx::query(&format!(
+                "UPDATE {table} SET {column} = ?2 || substr({column}, length(?1) + 1) \
+                 WHERE substr({column}, 1, length(?1)) = ?1",
+                table = table,
+                column = column
+            ))
+            .bind(from)
+            .bind(to)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(rebased)
+    }
+
+    /// Number of OCR text rows of frames captured before `before`, except pinned ones.
+    pub async fn count_ocr_text_before(&self, before: DateTime<Utc>) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM ocr_text WHERE frame_id IN ({})",
+            FRAMES_BEFORE_SQL
+        ))
+        .bind(before)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Deletes the OCR text and its embeddings of frames captured before `before`, except
+    /// pinned ones. The frames stay. Returns the number of deleted OCR text rows.
+    pub async fn delete_ocr_text_before(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&format!(
+            "DELETE FROM ocr_text_embeddings WHERE frame_id IN ({})",
+            FRAMES_BEFORE_SQL
+        ))
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query(&format!(
+            "DELETE FROM ocr_text WHERE frame_id IN ({})",
+            FRAMES_BEFORE_SQL
+        ))
+        .bind(before)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Number of transcriptions recorded before `before`.
+    pub async fn count_transcriptions_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM audio_transcriptions WHERE timestamp < ?1")
+            .bind(before)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Deletes the transcriptions recorded before `before`, their embeddings go with them.
+    /// Returns the number of deleted transcriptions.
+    pub async fn delete_transcriptions_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM audio_transcriptions WHERE timestamp < ?1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted)
+    }
+
+    /// Number of clipboard entries copied before `before`.
+    pub async fn count_clipboard_entries_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM clipboard_entries WHERE timestamp < ?1")
+            .bind(before)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Deletes the clipboard entries copied before `before`. Returns their number.
+    pub async fn delete_clipboard_entries_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM clipboard_entries WHERE timestamp < ?1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted)
+    }
+
+    /// Deletes the frames, OCR, accessibility and copied text, transcriptions and
+    /// embeddings matching `filter` in one transaction, or only counts them on a `dry_run`.
+    /// Returns the counts and the files left without data, for the caller to delete from
+    /// disk and mark purged: files still holding frames that didn't match stay. Chunks still
+    /// being recorded to are queued, see [`Self::closed_chunks_to_delete`].
+    pub async fn delete_data(
+        &self,
+        filter: &DataFilter,
+        dry_run: bool,
+    ) -> Result<(DataDeletion, Vec<(MediaKind, MediaFile)>), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut deletion = DataDeletion::default();
+        let mut media = Vec::new();
+        let mut transcription_ids: Vec<i64> = Vec::new();
+
+        let frame_ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT frames.id FROM frames \
+             JOIN video_chunks ON video_chunks.id = frames.video_chunk_id \
+             WHERE (?1 IS NULL OR frames.timestamp >= ?1) \
+             AND (?2 IS NULL OR frames.timestamp < ?2) \
+             AND (?3 IS NULL OR frames.app_name LIKE '%' || ?3 || '%') \
+             AND (?4 IS NULL OR frames.window_name LIKE '%' || ?4 || '%') \
+             AND (?5 IS NULL OR video_chunks.device_name = ?5)",
+        )
+        .bind(filter.start_time)
+        .bind(filter.end_time)
+        .bind(filter.app_name.as_deref())
+        .bind(filter.window_name.as_deref())
+        .bind(filter.device_name.as_deref())
+        .fetch_all(&mut *tx)
+        .await?;
+        let deleted_frames = frame_ids.clone();
+        let frame_ids = serde_json::to_string(&frame_ids).unwrap_or_default();
+        let with_frames = |sql: &'static str| sqlx::query(sql).bind(frame_ids.clone());
+
+        // chunks all of whose frames are deleted
+        let emptied = "SELECT video_chunks.id FROM video_chunks \
+             JOIN frames ON frames.video_chunk_id = video_chunks.id \
+             WHERE frames.id IN (SELECT value FROM json_each(?1)) \
+             AND video_chunks.purged_at IS NULL \
+             AND NOT EXISTS (SELECT 1 FROM frames AS kept \
+                 WHERE kept.video_chunk_id = video_chunks.id \
+                 AND kept.id NOT IN (SELECT value FROM json_each(?1))) \
+             GROUP BY video_chunks.id";
+        let chunks: Vec<MediaFile> = sqlx::query_as(&format!(
+            "SELECT video_chunks.id, video_chunks.file_path, MAX(frames.timestamp) AS timestamp \
+             FROM video_chunks JOIN frames ON frames.video_chunk_id = video_chunks.id \
+             WHERE video_chunks.id IN ({}) \
+             AND video_chunks.id NOT IN (SELECT MAX(id) FROM video_chunks GROUP BY device_name) \
+             GROUP BY video_chunks.id",
+            emptied
+        ))
+        .bind(&frame_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        media.extend(chunks.into_iter().map(|file| (MediaKind::VideoChunk, file)));
+        sqlx::query(&format!(
+            "UPDATE video_chunks SET delete_requested_at = ?2 \
+             WHERE id IN ({}) \
+             AND id IN (SELECT MAX(id) FROM video_chunks GROUP BY device_name)",
+            emptied
+        ))
+        .bind(&frame_ids)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        let segments: Vec<MediaFile> = sqlx::query_as(
+            "SELECT id, file_path, end_time AS timestamp FROM video_segments \
+             WHERE purged_at IS NULL AND end_time IS NOT NULL AND EXISTS ( \
+                 SELECT 1 FROM frames \
+                 JOIN video_chunks ON video_chunks.id = frames.video_chunk_id \
+                 WHERE frames.id IN (SELECT value FROM json_each(?1)) \
+                 AND video_chunks.device_name = video_segments.device_name \
+                 AND frames.timestamp >= video_segments.start_time \
+                 AND frames.timestamp <= video_segments.end_time) \
+             AND NOT EXISTS ( \
+                 SELECT 1 FROM frames \
+                 JOIN video_chunks ON video_chunks.id = frames.video_chunk_id \
+                 WHERE frames.id NOT IN (SELECT value FROM json_each(?1)) \
+                 AND video_chunks.device_name = video_segments.device_name \
+                 AND frames.timestamp >= video_segments.start_time \
+                 AND frames.timestamp <= video_segments.end_time)",
+        )
+        .bind(&frame_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        media.extend(
+            segments
+                .into_iter()
+                .map(|file| (MediaKind::VideoSegment, file)),
+        );
+
+        for sql in [
+            "DELETE FROM ocr_text_embeddings WHERE frame_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM frame_embeddings WHERE frame_id IN (SELECT value FROM json_each(?1))",
+        ] {
+            deletion.embeddings += with_frames(sql).execute(&mut *tx).await?.rows_affected();
+        }
+        deletion.ocr_text =
+            with_frames("DELETE FROM ocr_text WHERE frame_id IN (SELECT value FROM json_each(?1))")
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        for sql in [
+            "DELETE FROM chunked_text_entries WHERE frame_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM annotation_tags WHERE annotation_id IN (SELECT id FROM annotations \
+             WHERE content_type = 'vision' AND content_id IN (SELECT value FROM json_each(?1)))",
+            "DELETE FROM annotations WHERE content_type = 'vision' \
+             AND content_id IN (SELECT value FROM json_each(?1))",
+        ] {
+            with_frames(sql).execute(&mut *tx).await?;
+        }
+        // tags, codes, tables and document pages go with the frames
+        deletion.frames =
+            with_frames("DELETE FROM frames WHERE id IN (SELECT value FROM json_each(?1))")
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        if filter.device_name.is_none() {
+            deletion.ui_text = sqlx::query(
+                "DELETE FROM ui_monitoring \
+                 WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2) \
+                 AND (?3 IS NULL OR app LIKE '%' || ?3 || '%') \
+                 AND (?4 IS NULL OR window LIKE '%' || ?4 || '%')",
+            )
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(filter.app_name.as_deref())
+            .bind(filter.window_name.as_deref())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            deletion.clipboard = sqlx::query(
+                "DELETE FROM clipboard_entries \
+                 WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2) \
+                 AND (?3 IS NULL OR app_name LIKE '%' || ?3 || '%') \
+                 AND (?4 IS NULL OR window_name LIKE '%' || ?4 || '%')",
+            )
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(filter.app_name.as_deref())
+            .bind(filter.window_name.as_deref())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        if filter.matches_audio() {
+            let audio_chunks: Vec<(i64, String, DateTime<Utc>, bool)> = sqlx::query_as(
+                "SELECT id, file_path, timestamp, purged_at IS NULL AS on_disk \
+                 FROM audio_chunks WHERE timestamp IS NOT NULL \
+                 AND (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2) \
+                 AND (?3 IS NULL OR EXISTS (SELECT 1 FROM audio_transcriptions \
+                     WHERE audio_chunk_id = audio_chunks.id AND device = ?3))",
+            )
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(filter.device_name.as_deref())
+            .fetch_all(&mut *tx)
+            .await?;
+            let chunk_ids = serde_json::to_string(
+                &audio_chunks.iter().map(|chunk| chunk.0).collect::<Vec<_>>(),
+            )
+            .unwrap_or_default();
+            let with_chunks = |sql: &'static str| sqlx::query(sql).bind(chunk_ids.clone());
+            transcription_ids = sqlx::query_scalar(
+                "SELECT id FROM audio_transcriptions \
+                 WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))",
+            )
+            .bind(&chunk_ids)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            deletion.embeddings += with_chunks(
+                "DELETE FROM audio_transcription_embeddings WHERE audio_transcription_id IN \
+                 (SELECT id FROM audio_transcriptions \
+                 WHERE audio_chunk_id IN (SELECT value FROM json_each(?1)))",
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            with_chunks(
+                "DELETE FROM chunked_text_entries \
+                 WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))",
+            )
+            .execute(&mut *tx)
+            .await?;
+            deletion.transcriptions = with_chunks(
+                "DELETE FROM audio_transcriptions \
+                 WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))",
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            media.extend(audio_chunks.into_iter().filter(|chunk| chunk.3).map(
+                |(id, file_path, timestamp, _)| {
+                    let file = MediaFile {
+                        id,
+                        file_path,
+                        timestamp,
+                    };
+                    (MediaKind::AudioChunk, file)
+                },
+            ));
+        }
+
+        if dry_run {
+            tx.rollback().await?;
+            return Ok((deletion, media));
+        }
+        tx.commit().await?;
+
+        // the sqlite tables went with the transaction, another index keeps copies
+        for (collection, ids) in [
+            (VectorCollection::Frames, &deleted_frames),
+            (VectorCollection::OcrText, &deleted_frames),
+            (VectorCollection::Transcriptions, &transcription_ids),
+        ] {
+            if let Err(e) = self.vector_store.delete(collection, ids).await {
+                warn!(
+                    "failed to delete {} embeddings from the vector index: {}",
+                    collection.name(),
+                    e
+                );
+            }
+        }
+        Ok((deletion, media))
+    }
+
+    /// Closed chunks queued by [`Self::delete_data`] while recorded to that are still left
+    /// without frames, for the caller to delete from disk and mark purged. Chunks that got
+    /// frames after the deletion keep their file and leave the queue.
+    pub async fn closed_chunks_to_delete(&self) -> Result<Vec<MediaFile>, sqlx::Error> {
+        let closed = "delete_requested_at IS NOT NULL AND purged_at IS NULL \
+             AND id NOT IN (SELECT MAX(id) FROM video_chunks GROUP BY device_name)";
+        sqlx::query(&format!(
+            "UPDATE video_chunks SET delete_requested_at = NULL WHERE {} \
+             AND EXISTS (SELECT 1 FROM frames WHERE frames.video_chunk_id = video_chunks.id)",
+            closed
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query_as(&format!(
+            "SELECT id, file_path, delete_requested_at AS timestamp FROM video_chunks WHERE {}",
+            closed
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// OCR text with an id above `after_id`, oldest first, to send to a central database.
+    pub async fn get_texts_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<CapturedText>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                ocr_text.rowid AS id,
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.device_name,
+                COALESCE(frames.app_name, '') AS app_name,
+                COALESCE(frames.window_name, '') AS window_name,
+                frames.browser_url,
+                ocr_text.text
+            FROM ocr_text
+            JOIN frames ON frames.id = ocr_text.frame_id
+            JOIN video_chunks ON video_chunks.id = frames.video_chunk_id
+            WHERE ocr_text.rowid > ?1
+            ORDER BY ocr_text.rowid ASC
+            LIMIT ?2
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Transcriptions with an id above `after_id`, oldest first, to send to a central
+    /// database.
+    pub async fn get_transcriptions_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<CapturedTranscription>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, timestamp, device, is_input_device, speaker_id, transcription
+            FROM audio_transcriptions
+            WHERE id > ?1
+            ORDER BY id ASC
+            LIMIT ?2
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert_video_segment(
+        &self,
+        device_name: &str,
+        file_path: &str,
+        start_time: DateTime<Utc>,
+        fps: f64,
+        codec: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT INTO video_segments (device_name, file_path, start_time, fps, codec) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(device_name)
+        .bind(file_path)
+        .bind(start_time)
+        .bind(fps)
+        .bind(codec)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub async fn finish_video_segment(
+        &self,
+        id: i64,
+        end_time: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE video_segments SET end_time = ?1 WHERE id = ?2")
+            .bind(end_time)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Segment of `device_name` recorded at `timestamp`, if the recording was running then.
+    pub async fn get_video_segment(
+        &self,
+        device_name: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<Option<VideoSegment>, sqlx::Error> {
+        sqlx::query_as::<_, VideoSegment>(
+            r#"
+            SELECT id, device_name, file_path, start_time, end_time, fps, codec
+            FROM video_segments
+            WHERE device_name = ?1
+                AND start_time <= ?2
+                AND (end_time IS NULL OR end_time >= ?2)
+            ORDER BY start_time DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(device_name)
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Segment recorded by the same monitor while the frame was captured.
+    pub async fn get_video_segment_for_frame(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<(VideoSegment, DateTime<Utc>)>, sqlx::Error> {
+        let frame: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            SELECT video_chunks.device_name, frames.timestamp
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE frames.id = ?1
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((device_name, timestamp)) = frame else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_video_segment(&device_name, timestamp)
+            .await?
+            .map(|segment| (segment, timestamp)))
+    }
+
+    /// Segments of `device_name` overlapping `start..end`, oldest first.
+    pub async fn get_video_segments_in_range(
+        &self,
+        device_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<VideoSegment>, sqlx::Error> {
+        sqlx::query_as::<_, VideoSegment>(
+            r#"
+            SELECT id, device_name, file_path, start_time, end_time, fps, codec
+            FROM video_segments
+            WHERE device_name = ?1
+                AND start_time <= ?3
+                AND (end_time IS NULL OR end_time >= ?2)
+            ORDER BY start_time ASC
+            "#,
+        )
+        .bind(device_name)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Video file, offset and timestamp of every frame `device_name` captured in
+    /// `start..=end`, oldest first.
+    pub async fn get_frames_in_range(
+        &self,
+        device_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64, DateTime<Utc>)>, sqlx::Error> {
+        sqlx::query_as::<_, (String, i64, DateTime<Utc>)>(
+            r#"
+            SELECT video_chunks.file_path, frames.offset_index, frames.timestamp
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE video_chunks.device_name = ?1
+                AND frames.timestamp >= ?2
+                AND frames.timestamp <= ?3
+            ORDER BY frames.timestamp ASC
+            "#,
+        )
+        .bind(device_name)
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Frames of every monitor captured in `start..=end`, oldest first, with the first
+    /// `snippet_chars` characters of their OCR text. Of those captured at exactly `start`,
+    /// the ones from the frame `from_id` on.
+    pub async fn get_timeline_frames(
+        &self,
+        start: DateTime<Utc>,
+        from_id: i64,
+        end: DateTime<Utc>,
+        limit: u32,
+        snippet_chars: u32,
+    ) -> Result<Vec<TimelineFrame>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                frames.id AS frame_id,
+                frames.timestamp,
+                video_chunks.device_name,
+                COALESCE(frames.app_name, '') AS app_name,
+                COALESCE(frames.window_name, '') AS window_name,
+                frames.browser_url,
+                frames.focused,
+                (
+                    SELECT substr(ocr_text.text, 1, ?4) FROM ocr_text
+                    WHERE ocr_text.frame_id = frames.id
+                    LIMIT 1
+                ) AS snippet
+            FROM frames
+            JOIN video_chunks ON video_chunks.id = frames.video_chunk_id
+            WHERE frames.timestamp >= ?1 AND frames.timestamp <= ?2
+                AND (frames.timestamp > ?1 OR frames.id >= ?5)
+            ORDER BY frames.timestamp ASC, frames.id ASC
+            LIMIT ?3
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .bind(snippet_chars)
+        .bind(from_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Transcriptions of every device in `start..=end`, oldest first. Of those at exactly
+    /// `start`, the ones from the transcription `from_id` on.
+    pub async fn get_timeline_transcriptions(
+        &self,
+        start: DateTime<Utc>,
+        from_id: i64,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TimelineTranscription>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, timestamp, device, is_input_device, speaker_id, transcription, start_time, end_time
+            FROM audio_transcriptions
+            WHERE timestamp >= ?1 AND timestamp <= ?2 AND (timestamp > ?1 OR id >= ?4)
+            ORDER BY timestamp ASC, id ASC
+            LIMIT ?3
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .bind(from_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Frames and transcriptions recorded in `start..=end`.
+    pub async fn count_export_items(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(i64, i64), sqlx::Error> {
+        sqlx::query_as(
+            "SELECT \
+             (SELECT COUNT(*) FROM frames WHERE timestamp >= ?1 AND timestamp <= ?2), \
+             (SELECT COUNT(*) FROM audio_transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2)",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Frames in `start..=end` with ids after `after_id`, by id, so an export can page
+    /// through them while new ones are recorded.
+    pub async fn get_export_frames(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        after_id: i64,
+        limit: u32,
+    ) -> Result<Vec<ExportFrame>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                frames.id,
+                frames.timestamp,
+                video_chunks.device_name,
+                frames.app_name,
+                frames.window_name,
+                frames.browser_url,
+                frames.focused,
+                video_chunks.file_path,
+                frames.offset_index,
+                ocr_text.text,
+                ocr_text.text_json,
+                ocr_text.ocr_engine
+            FROM frames
+            JOIN video_chunks ON video_chunks.id = frames.video_chunk_id
+            LEFT JOIN ocr_text ON ocr_text.frame_id = frames.id
+            WHERE frames.timestamp >= ?1 AND frames.timestamp <= ?2 AND frames.id > ?3
+            GROUP BY frames.id
+            ORDER BY frames.id ASC
+            LIMIT ?4
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Transcriptions in `start..=end` with ids after `after_id`, by id.
+    pub async fn get_export_transcriptions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        after_id: i64,
+        limit: u32,
+    ) -> Result<Vec<ExportTranscription>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                audio_transcriptions.id,
+                audio_transcriptions.timestamp,
+                audio_transcriptions.device,
+                audio_transcriptions.is_input_device,
+                audio_transcriptions.speaker_id,
+                audio_transcriptions.transcription,
+                audio_transcriptions.transcription_engine,
+                audio_transcriptions.start_time,
+                audio_transcriptions.end_time,
+                audio_chunks.file_path
+            FROM audio_transcriptions
+            JOIN audio_chunks ON audio_chunks.id = audio_transcriptions.audio_chunk_id
+            WHERE audio_transcriptions.timestamp >= ?1 AND audio_transcriptions.timestamp <= ?2
+                AND audio_transcriptions.id > ?3
+            ORDER BY audio_transcriptions.id ASC
+            LIMIT ?4
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Seconds each app and window was focused in `start..end`, per hour. A focused frame
+    /// counts until the next one, gaps longer than `max_gap_secs` only count that long so
+    /// time away from the computer is left out.
+    pub async fn get_app_usage(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_secs: f64,
+    ) -> Result<Vec<AppUsage>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            WITH focused AS (
+                SELECT
+                    timestamp,
+                    COALESCE(app_name, '') AS app_name,
+                    COALESCE(window_name, '') AS window_name,
+                    LEAD(timestamp) OVER (ORDER BY timestamp, id) AS next_timestamp
+                FROM frames
+                WHERE timestamp >= ?1 AND timestamp < ?2 AND focused = 1
+            )
+            SELECT
+                strftime('%Y-%m-%dT%H:00:00Z', timestamp) AS hour,
+                app_name,
+                window_name,
+                SUM(MIN(
+                    (julianday(COALESCE(next_timestamp, timestamp)) - julianday(timestamp)) * 86400.0,
+                    ?3
+                )) AS seconds
+            FROM focused
+            GROUP BY hour, app_name, window_name
+            ORDER BY hour ASC, seconds DESC
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(max_gap_secs)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Stretches of focused frames of the same app and window in `start..end`, oldest
+    /// first. A gap longer than `max_gap_secs` starts a new one.
+    pub async fn get_window_sessions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_secs: f64,
+    ) -> Result<Vec<WindowSession>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            WITH focused AS (
+                SELECT
+                    id,
+                    timestamp,
+                    COALESCE(app_name, '') AS app_name,
+                    COALESCE(window_name, '') AS window_name,
+                    browser_url,
+                    LAG(timestamp) OVER w AS previous_timestamp,
+                    LAG(COALESCE(app_name, '')) OVER w AS previous_app_name,
+                    LAG(COALESCE(window_name, '')) OVER w AS previous_window_name
+                FROM frames
+                WHERE timestamp >= ?1 AND timestamp < ?2 AND focused = 1
+                WINDOW w AS (ORDER BY timestamp, id)
+            ),
+            numbered AS (
+                SELECT
+                    *,
+                    SUM(
+                        CASE
+                            WHEN previous_timestamp IS NULL
+                                OR app_name != previous_app_name
+                                OR window_name != previous_window_name
+                                OR (julianday(timestamp) - julianday(previous_timestamp)) * 86400.0 > ?3
+                            THEN 1
+                            ELSE 0
+                        END
+                    ) OVER (ORDER BY timestamp, id) AS session
+                FROM focused
+            )
+            SELECT
+                app_name,
+                window_name,
+                MAX(browser_url) AS browser_url,
+                MIN(timestamp) AS start_time,
+                MAX(timestamp) AS end_time,
+                COUNT(*) AS frame_count
+            FROM numbered
+            GROUP BY session
+            ORDER BY start_time ASC
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(max_gap_secs)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records that capture paused for `reason` at `start_time`, until
+    /// `end_capture_pause` is called with the returned id.
+    pub async fn start_capture_pause(
+        &self,
+        reason: &str,
+        start_time: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        Ok(
+            sqlx::query("INSERT INTO capture_pauses (reason, start_time) VALUES (?1, ?2)")
+                .bind(reason)
+                .bind(start_time)
+                .execute(&self.pool)
+                .await?
+                .last_insert_rowid(),
+        )
+    }
+
+    pub async fn end_capture_pause(
+        &self,
+        id: i64,
+        end_time: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE capture_pauses SET end_time = ?2 WHERE id = ?1 AND end_time IS NULL")
+            .bind(id)
+            .bind(end_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Ends the pauses left open, e.g. by a crash, at `end_time`. Returns how many were.
+    pub async fn end_open_capture_pauses(
+        &self,
+        end_time: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query(
+            "UPDATE capture_pauses SET end_time = MAX(start_time, ?1) WHERE end_time IS NULL",
+        )
+        .bind(end_time)
+        .execute(&self.pool)
+        .await?
+        .rows_affected())
+    }
+
+    /// Pauses overlapping `start..end`, oldest first, at most `limit` of them.
+    pub async fn list_capture_pauses(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<CapturePause>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, reason, start_time, end_time FROM capture_pauses \
+             WHERE start_time < ?2 AND (end_time IS NULL OR end_time >= ?1) \
+             ORDER BY start_time ASC, id ASC LIMIT ?3",
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert_audio_device_event(
+        &self,
+        event: &str,
+        device: &str,
+        fallback_device: Option<&str>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        Ok(sqlx::query(
+            "INSERT INTO audio_device_events (event, device, fallback_device, timestamp) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(event)
+        .bind(device)
+        .bind(fallback_device)
+        .bind(timestamp)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// Audio device events in `start..end`, oldest first, at most `limit` of them.
+    pub async fn list_audio_device_events(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<AudioDeviceEvent>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, event, device, fallback_device, timestamp FROM audio_device_events \
+             WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp ASC, id ASC LIMIT ?3",
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Adds `activity` to the counts of its minute.
+    pub async fn add_input_activity(&self, activity: &InputActivity) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO input_activity (minute, key_presses, mouse_clicks, mouse_moves, scrolls) \
+             VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT(minute) DO UPDATE SET \
+             key_presses = key_presses + excluded.key_presses, \
+             mouse_clicks = mouse_clicks + excluded.mouse_clicks, \
+             mouse_moves = mouse_moves + excluded.mouse_moves, \
+             scrolls = scrolls + excluded.scrolls",
+        )
+        .bind(activity.minute)
+        .bind(activity.key_presses)
+        .bind(activity.mouse_clicks)
+        .bind(activity.mouse_moves)
+        .bind(activity.scrolls)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Minutes with input in `start..end`, oldest first.
+    pub async fn get_input_activity(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<InputActivity>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT minute, key_presses, mouse_clicks, mouse_moves, scrolls FROM input_activity \
+             WHERE minute >= ?1 AND minute < ?2 ORDER BY minute ASC",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Distinct OCR texts of frames captured in `start..end`, at most `limit` of them.
+    pub async fn get_texts_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT ocr_text.text
+            FROM ocr_text
+            JOIN frames ON frames.id = ocr_text.frame_id
+            WHERE frames.timestamp >= ?1 AND frames.timestamp < ?2 AND ocr_text.text != ''
+            LIMIT ?3
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn upsert_daily_digest(
+        &self,
+        date: NaiveDate,
+        digest: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO daily_digests (date, digest, created_at) VALUES (?1, ?2, ?3) \
+             ON CONFLICT(date) DO UPDATE SET digest = excluded.digest, created_at = excluded.created_at",
+        )
+        .bind(date)
+        .bind(digest)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The digest stored for `date`, as JSON.
+    pub async fn get_daily_digest(&self, date: NaiveDate) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT digest FROM daily_digests WHERE date = ?1")
+            .bind(date)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    pub async fn insert_webhook(
+        &self,
+        url: &str,
+        secret: Option<&str>,
+        types: &str,
+        apps: &str,
+        keywords: &str,
+    ) -> Result<Webhook, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO webhooks (url, secret, types, apps, keywords, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING *",
+        )
+        .bind(url)
+        .bind(secret)
+        .bind(types)
+        .bind(apps)
+        .bind(keywords)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM webhooks ORDER BY id")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn get_webhook(&self, id: i64) -> Result<Option<Webhook>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM webhooks WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Deletes the webhook and its deliveries. Returns whether it existed.
+    pub async fn delete_webhook(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM webhooks WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    /// Logs a delivery, dropping the oldest of the webhook past `MAX_WEBHOOK_DELIVERIES`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_webhook_delivery(
+        &self,
+        webhook_id: i64,
+        event_id: i64,
+        event_type: &str,
+        attempt: i64,
+        success: bool,
+        status_code: Option<i64>,
+        error: Option<&str>,
+        duration_ms: i64,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT INTO webhook_deliveries \
+             (webhook_id, event_id, event_type, attempt, success, status_code, error, \
+             duration_ms, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )
+        .bind(webhook_id)
+        .bind(event_id)
+        .bind(event_type)
+        .bind(attempt)
+        .bind(success)
+        .bind(status_code)
+        .bind(error)
+        .bind(duration_ms)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        sqlx::query(
+            "DELETE FROM webhook_deliveries WHERE webhook_id = ?1 AND id <= \
+             (SELECT id FROM webhook_deliveries WHERE webhook_id = ?1 \
+             ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+        )
+        .bind(webhook_id)
+        .bind(MAX_WEBHOOK_DELIVERIES)
+        .execute(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Deliveries of the webhook, newest first, the ones before `before_id` when set.
+    pub async fn list_webhook_deliveries(
+        &self,
+        webhook_id: i64,
+        before_id: Option<i64>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT * FROM webhook_deliveries WHERE webhook_id = ?1 \
+             AND (?4 IS NULL OR id < ?4) ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(before_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert_api_token(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scopes: &str,
+    ) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO api_tokens (name, token_hash, scopes, created_at) \
+             VALUES (?1, ?2, ?3, ?4) RETURNING *",
+        )
+        .bind(name)
+        .bind(token_hash)
+        .bind(scopes)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM api_tokens ORDER BY id")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn get_api_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<ApiToken>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM api_tokens WHERE token_hash = ?1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Records that the token was used, unless it already was in the last minute.
+    pub async fn touch_api_token(&self, id: i64) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query(
+            "UPDATE api_tokens SET last_used_at = ?2 \
+             WHERE id = ?1 AND (last_used_at IS NULL OR last_used_at < ?3)",
+        )
+        .bind(id)
+        .bind(now)
+        .bind(now - chrono::Duration::minutes(1))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns whether the token existed.
+    pub async fn delete_api_token(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM api_tokens WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    pub async fn insert_alert_rule(
+        &self,
+        name: &str,
+        pattern: &str,
+        types: &str,
+        apps: &str,
+        cooldown_secs: i64,
+        notify: bool,
+    ) -> Result<AlertRule, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO alert_rules \
+             (name, pattern, types, apps, cooldown_secs, notify, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) RETURNING *",
+        )
+        .bind(name)
+        .bind(pattern)
+        .bind(types)
+        .bind(apps)
+        .bind(cooldown_secs)
+        .bind(notify)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_alert_rules(&self) -> Result<Vec<AlertRule>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM alert_rules ORDER BY id")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Deletes the rule and its alerts. Returns whether it existed.
+    pub async fn delete_alert_rule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM alert_events WHERE rule_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM alert_rules WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_alert_event(
+        &self,
+        rule_id: i64,
+        event_type: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        matched_text: &str,
+        frame_id: Option<i64>,
+        audio_chunk_id: Option<i64>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT INTO alert_events \
+             (rule_id, event_type, app_name, window_name, matched_text, frame_id, \
+             audio_chunk_id, timestamp) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(rule_id)
+        .bind(event_type)
+        .bind(app_name)
+        .bind(window_name)
+        .bind(matched_text)
+        .bind(frame_id)
+        .bind(audio_chunk_id)
+        .bind(timestamp)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Alerts raised by `rule_id`, or by every rule, newest first.
+    /// Newest first, the ones before `before_id` when set.
+    pub async fn list_alert_events(
+        &self,
+        rule_id: Option<i64>,
+        before_id: Option<i64>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AlertEvent>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT * FROM alert_events WHERE (?1 IS NULL OR rule_id = ?1) \
+             AND (?4 IS NULL OR id < ?4) ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+        )
+        .bind(rule_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(before_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Stores `note` and `tags` on a frame (`vision`) or an audio chunk (`audio`) with
+    /// `content_id`, or on the range from `start_time` to `end_time` (`range`). Tags of
+    /// frames and audio chunks are added to them too, as `add_tags` does, and stay when the
+    /// annotation is deleted. `RowNotFound` when the frame or audio chunk doesn't exist.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_annotation(
+        &self,
+        content_type: &str,
+        content_id: Option<i64>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        note: Option<&str>,
+        tags: &[String],
+    ) -> Result<Annotation, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let (start_time, end_time) = match (content_type, content_id) {
+            ("vision" | "audio", Some(content_id)) => {
+                let sql = if content_type == "vision" {
+                    "SELECT timestamp FROM frames WHERE id = ?1"
+                } else {
+                    "SELECT timestamp FROM audio_chunks WHERE id = ?1"
+                };
+                let timestamp: DateTime<Utc> = sqlx::query_scalar(sql)
+                    .bind(content_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or(sqlx::Error::RowNotFound)?;
+                (timestamp, timestamp)
+            }
+            (_, _) => match (start_time, end_time) {
+                (Some(start_time), Some(end_time)) => (start_time, end_time),
+                _ => return Err(sqlx::Error::RowNotFound),
+            },
+        };
+
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO annotations (content_type, content_id, start_time, end_time, note) \
+             VALUES (?1, ?2, ?3, ?4, ?5) RETURNING id",
+        )
+        .bind(content_type)
+        .bind(content_id)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(note)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let content_tags = match content_type {
+            "vision" => Some(
+                "INSERT INTO vision_tags (vision_id, tag_id) VALUES (?1, ?2) \
+                 ON CONFLICT DO NOTHING",
+            ),
+            "audio" => Some(
+                "INSERT INTO audio_tags (audio_chunk_id, tag_id) VALUES (?1, ?2) \
+                 ON CONFLICT DO NOTHING",
+            ),
+            _ => None,
+        };
+        for tag in tags {
+            let tag_id: i64 = sqlx::query_scalar(
+                "INSERT INTO tags (name) VALUES (?) \
+                 ON CONFLICT(name) DO UPDATE SET name=name RETURNING id",
+            )
+            .bind(tag)
+            .fetch_one(&mut *tx)
+            .await?;
+            sqlx::query(
+                "INSERT INTO annotation_tags (annotation_id, tag_id) VALUES (?1, ?2) \
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(id)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+            if let (Some(sql), Some(content_id)) = (content_tags, content_id) {
+                sqlx::query(sql)
+                    .bind(content_id)
+                    .bind(tag_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+
+        self.get_annotation(id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_annotation(&self, id: i64) -> Result<Option<Annotation>, sqlx::Error> {
+        let sql = format!(
+            "{} WHERE annotations.id = ?1 GROUP BY annotations.id",
+            ANNOTATIONS_SQL
+        );
+        let row: Option<AnnotationRow> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(annotation_from_row))
+    }
+
+    /// The annotations overlapping `start_time` to `end_time`, of `content_type` and
+    /// `content_id` and tagged `tag` when set. Oldest first, the ones after the annotation
+    /// `after_id` when set.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_annotations(
+        &self,
+        content_type: Option<&str>,
+        content_id: Option<i64>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        tag: Option<&str>,
+        after_id: Option<i64>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Annotation>, sqlx::Error> {
+        let sql = format!(
+            "{} WHERE (?1 IS NULL OR annotations.content_type = ?1) \
+             AND (?2 IS NULL OR annotations.content_id = ?2) \
+             AND (?3 IS NULL OR annotations.end_time >= ?3) \
+             AND (?4 IS NULL OR annotations.start_time <= ?4) \
+             AND (?5 IS NULL OR annotations.id IN (SELECT annotation_tags.annotation_id \
+                 FROM annotation_tags JOIN tags ON tags.id = annotation_tags.tag_id \
+                 WHERE tags.name = ?5 COLLATE NOCASE)) \
+             AND (?8 IS NULL OR (annotations.start_time, annotations.id) > \
+                 (SELECT start_time, id FROM annotations WHERE id = ?8)) \
+             GROUP BY annotations.id ORDER BY annotations.start_time, annotations.id \
+             LIMIT ?6 OFFSET ?7",
+            ANNOTATIONS_SQL
+        );
+        let rows: Vec<AnnotationRow> = sqlx::query_as(&sql)
+            .bind(content_type)
+            .bind(content_id)
+            .bind(start_time)
+            .bind(end_time)
+            .bind(tag)
+            .bind(limit)
+            .bind(offset)
+            .bind(after_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(annotation_from_row).collect())
+    }
+
+    /// Whether the annotation existed.
+    pub async fn delete_annotation(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM annotation_tags WHERE annotation_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM annotations WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Pins the frame `frame_id`, or the moment `timestamp` and the last frame captured at
+    /// or before it. `RowNotFound` when the frame doesn't exist or neither is set.
+    pub async fn insert_bookmark(
+        &self,
+        frame_id: Option<i64>,
+        timestamp: Option<DateTime<Utc>>,
+        title: Option<&str>,
+    ) -> Result<Bookmark, sqlx::Error> {
+        let (frame_id, timestamp) = match (frame_id, timestamp) {
+            (Some(frame_id), _) => {
+                let timestamp: DateTime<Utc> =
+                    sqlx::query_scalar("SELECT timestamp FROM frames WHERE id = ?1")
+                        .bind(frame_id)
+                        .fetch_optional(&self.pool)
+                        .await?
+                        .ok_or(sqlx::Error::RowNotFound)?;
+                (Some(frame_id), timestamp)
+            }
+            (None, Some(timestamp)) => {
+                let frame_id: Option<i64> = sqlx::query_scalar(
+                    "SELECT id FROM frames WHERE timestamp <= ?1 \
+                     ORDER BY timestamp DESC, id DESC LIMIT 1",
+                )
+                .bind(timestamp)
+                .fetch_optional(&self.pool)
+                .await?;
+                (frame_id, timestamp)
+            }
+            (None, None) => return Err(sqlx::Error::RowNotFound),
+        };
+
+        sqlx::query_as(
+            "INSERT INTO bookmarks (frame_id, timestamp, title) VALUES (?1, ?2, ?3) \
+             RETURNING id, frame_id, timestamp, title, created_at",
+        )
+        .bind(frame_id)
+        .bind(timestamp)
+        .bind(title)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// The bookmarks from `start_time` to `end_time`, newest first, the ones before the
+    /// bookmark `before_id` when set.
+    pub async fn list_bookmarks(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        before_id: Option<i64>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Bookmark>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, frame_id, timestamp, title, created_at FROM bookmarks \
+             WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2) \
+             AND (?5 IS NULL OR (timestamp, id) < (SELECT timestamp, id FROM bookmarks \
+                 WHERE id = ?5)) \
+             ORDER BY timestamp DESC, id DESC LIMIT ?3 OFFSET ?4",
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(offset)
+        .bind(before_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Whether the bookmark existed. Its media is left to retention again.
+    pub async fn delete_bookmark(&self, id: i64) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query("DELETE FROM bookmarks WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected()
+            > 0)
+    }
+
+    /// Stores a meeting read from the calendar and tags what is captured from `start_time`
+    /// to `end_time` with `tags`, through a range annotation with `note`. The annotation
+    /// is replaced when the meeting changed. Returns whether it did, or was new.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn upsert_calendar_event(
+        &self,
+        uid: &str,
+        title: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        attendees: &[String],
+        location: Option<&str>,
+        note: Option<&str>,
+        tags: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        self.upsert_meeting(
+            uid, "calendar", None, title, start_time, end_time, attendees, location, note, tags,
+        )
+        .await
+    }
+
+    /// Stores a meeting seen in `app_name` from `start_time` to `end_time`, tagging what is
+    /// captured during it like `upsert_calendar_event`. A detected meeting of the app it
+    /// overlaps is extended instead, so an ongoing meeting stays one: it keeps the title and
+    /// start it was first seen with and only ends later. Returns whether the meeting
+    /// changed, or was new.
+    pub async fn upsert_detected_meeting(
+        &self,
+        app_name: &str,
+        title: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        note: Option<&str>,
+        tags: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        let existing: Option<(String, String, DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT uid, title, start_time, end_time FROM calendar_events \
+             WHERE source = 'detected' AND app_name = ?1 AND start_time <= ?3 AND end_time >= ?2 \
+             ORDER BY start_time LIMIT 1",
+        )
+        .bind(app_name)
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_optional(&self.pool)
+        .await?;
+        let (uid, title, start_time, end_time) = match existing {
+            Some((uid, stored_title, stored_start, stored_end)) => {
+                (uid, stored_title, stored_start, stored_end.max(end_time))
+            }
+            None => (
+                format!("detected:{}:{}", app_name, start_time.timestamp()),
+                title.to_string(),
+                start_time,
+                end_time,
+            ),
+        };
+        self.upsert_meeting(
+            &uid,
+            "detected",
+            Some(app_name),
+            &title,
+            start_time,
+            end_time,
+            &[],
+            None,
+            note,
+            tags,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn upsert_meeting(
+        &self,
+        uid: &str,
+        source: &str,
+        app_name: Option<&str>,
+        title: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        attendees: &[String],
+        location: Option<&str>,
+        note: Option<&str>,
+        tags: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        let attendees = json_strings(attendees);
+        let existing: Option<(i64, Option<i64>, DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT id, annotation_id, start_time, end_time FROM calendar_events WHERE uid = ?1",
+        )
+        .bind(uid)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some((id, annotation_id, stored_start, stored_end)) = existing {
+            let unchanged: bool = sqlx::query_scalar(
+                "SELECT title = ?2 AND start_time = ?3 AND end_time = ?4 AND attendees = ?5 \
+                 AND location IS ?6 AND app_name IS ?7 FROM calendar_events WHERE id = ?1",
+            )
+            .bind(id)
+            .bind(title)
+            .bind(start_time)
+            .bind(end_time)
+            .bind(&attendees)
+            .bind(location)
+            .bind(app_name)
+            .fetch_one(&self.pool)
+            .await?;
+            if unchanged && annotation_id.is_some() {
+                return Ok(false);
+            }
+            if let Some(annotation_id) = annotation_id {
+                self.delete_annotation(annotation_id).await?;
+            }
+            // notes are written again when the meeting went on, not when it was renamed
+            if start_time < stored_start || end_time > stored_end {
+                sqlx::query("DELETE FROM meeting_notes WHERE meeting_id = ?1")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        let annotation = self
+            .insert_annotation("range", None, Some(start_time), Some(end_time), note, tags)
+            .await?;
+        sqlx::query(
+            "INSERT INTO calendar_events \
+             (uid, title, start_time, end_time, attendees, location, annotation_id, source, \
+             app_name) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) \
+             ON CONFLICT(uid) DO UPDATE SET title = ?2, start_time = ?3, end_time = ?4, \
+             attendees = ?5, location = ?6, annotation_id = ?7, app_name = ?9, \
+             updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(uid)
+        .bind(title)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(&attendees)
+        .bind(location)
+        .bind(annotation.id)
+        .bind(source)
+        .bind(app_name)
+        .execute(&self.pool)
+        .await?;
+        Ok(true)
+    }
+
+    /// Deletes the calendar meetings starting from `start_time` to `end_time` that aren't in
+    /// `uids`, e.g. cancelled since, with their annotations. Returns how many were deleted.
+    pub async fn delete_calendar_events_except(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        uids: &[String],
+    ) -> Result<u64, sqlx::Error> {
+        let stale: Vec<(i64, Option<i64>)> = sqlx::query_as(
+            "SELECT id, annotation_id FROM calendar_events \
+             WHERE source = 'calendar' AND start_time >= ?1 AND start_time <= ?2 \
+             AND uid NOT IN (SELECT value FROM json_each(?3))",
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(json_strings(uids))
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, annotation_id) in &stale {
+            if let Some(annotation_id) = annotation_id {
+                self.delete_annotation(*annotation_id).await?;
+            }
+            sqlx::query("DELETE FROM meeting_notes WHERE meeting_id = ?1")
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM calendar_events WHERE id = ?1")
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(stale.len() as u64)
+    }
+
+    /// The meetings overlapping `start_time` to `end_time` whose title or attendees
+    /// contain `query` and of `source` when set, newest first, with what was captured
+    /// during them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_calendar_events(
+        &self,
+        query: Option<&str>,
+        source: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<CalendarEvent>, sqlx::Error> {
+        let sql = format!(
+            "{} WHERE (?1 IS NULL OR calendar_events.title LIKE '%' || ?1 || '%' \
+             OR calendar_events.attendees LIKE '%' || ?1 || '%') \
+             AND (?2 IS NULL OR calendar_events.end_time >= ?2) \
+             AND (?3 IS NULL OR calendar_events.start_time <= ?3) \
+             AND (?6 IS NULL OR calendar_events.source = ?6) \
+             ORDER BY calendar_events.start_time DESC, calendar_events.id DESC \
+             LIMIT ?4 OFFSET ?5",
+            CALENDAR_EVENTS_SQL
+        );
+        let rows: Vec<CalendarEventRow> = sqlx::query_as(&sql)
+            .bind(query.map(str::trim).filter(|query| !query.is_empty()))
+            .bind(start_time)
+            .bind(end_time)
+            .bind(limit)
+            .bind(offset)
+            .bind(source)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(calendar_event_from_row).collect())
+    }
+
+    pub async fn get_calendar_event(&self, id: i64) -> Result<Option<CalendarEvent>, sqlx::Error> {
+        let sql = format!("{} WHERE calendar_events.id = ?1", CALENDAR_EVENTS_SQL);
+        let row: Option<CalendarEventRow> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(calendar_event_from_row))
+    }
+
+    /// The meetings of `source` that ended by `ended_before` without notes yet, the last
+    /// ended first. Those in `skip` are left out, e.g. the LLM failed on them.
+    pub async fn get_meetings_without_notes(
+        &self,
+        source: &str,
+        ended_before: DateTime<Utc>,
+        skip: &[i64],
+        limit: u32,
+    ) -> Result<Vec<CalendarEvent>, sqlx::Error> {
+        let sql = format!(
+            "{} WHERE calendar_events.source = ?1 AND calendar_events.end_time <= ?2 \
+             AND calendar_events.id NOT IN (SELECT meeting_id FROM meeting_notes) \
+             AND calendar_events.id NOT IN (SELECT value FROM json_each(?4)) \
+             ORDER BY calendar_events.end_time DESC, calendar_events.id DESC LIMIT ?3",
+            CALENDAR_EVENTS_SQL
+        );
+        let rows: Vec<CalendarEventRow> = sqlx::query_as(&sql)
+            .bind(source)
+            .bind(ended_before)
+            .bind(limit)
+            .bind(serde_json::to_string(skip).unwrap_or_else(|_| "[]".to_string()))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(calendar_event_from_row).collect())
+    }
+
+    /// Stores the notes of meeting `meeting_id`, replacing those written before.
+    pub async fn upsert_meeting_notes(
+        &self,
+        meeting_id: i64,
+        summary: &str,
+        decisions: &[String],
+        action_items: &[ActionItem],
+        provider: &str,
+        model: &str,
+    ) -> Result<MeetingNotes, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO meeting_notes \
+             (meeting_id, summary, decisions, action_items, provider, model) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+             ON CONFLICT(meeting_id) DO UPDATE SET summary = ?2, decisions = ?3, \
+             action_items = ?4, provider = ?5, model = ?6, created_at = CURRENT_TIMESTAMP \
+             RETURNING meeting_id, summary, decisions, action_items, provider, model, created_at",
+        )
+        .bind(meeting_id)
+        .bind(summary)
+        .bind(json_strings(decisions))
+        .bind(serde_json::to_string(action_items).unwrap_or_else(|_| "[]".to_string()))
+        .bind(provider)
+        .bind(model)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn get_meeting_notes(
+        &self,
+        meeting_id: i64,
+    ) -> Result<Option<MeetingNotes>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT meeting_id, summary, decisions, action_items, provider, model, created_at \
+             FROM meeting_notes WHERE meeting_id = ?1",
+        )
+        .bind(meeting_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn insert_clipboard_entry(
+        &self,
+        timestamp: DateTime<Utc>,
+        text: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        Ok(sqlx::query(
+            "INSERT INTO clipboard_entries (timestamp, text, app_name, window_name) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(timestamp)
+        .bind(text)
+        .bind(app_name)
+        .bind(window_name)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid())
+    }
+
+    /// App and window focused last in `since..=before`, from the frames captured.
+    pub async fn latest_focused_window(
+        &self,
+        since: DateTime<Utc>,
+        before: DateTime<Utc>,
+    ) -> Result<Option<(Option<String>, Option<String>)>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT app_name, window_name FROM frames \
+             WHERE timestamp >= ?1 AND timestamp <= ?2 AND focused = 1 \
+             ORDER BY timestamp DESC, id DESC LIMIT 1",
+        )
+        .bind(since)
+        .bind(before)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Text copied to the clipboard. App and window names match names that contain them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_clipboard(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        exclude: &SearchExclusions,
+        sort: SearchSort,
+    ) -> Result<Vec<ClipboardEntry>, sqlx::Error> {
+        let query = query.trim();
+        let sql = format!(
+            "SELECT clipboard_entries.id, clipboard_entries.timestamp, clipboard_entries.text, \
+             clipboard_entries.app_name, clipboard_entries.window_name, {} \
+             FROM clipboard_entries {} WHERE {} ORDER BY {} LIMIT ?10 OFFSET ?11",
+            if query.is_empty() {
+                "NULL AS snippet, NULL AS score"
+            } else {
+                "NULL AS snippet, clipboard_match.score"
+            },
+            clipboard_match_join(query),
+            CLIPBOARD_CONDITIONS,
+            match sort {
+                SearchSort::Relevance if !query.is_empty() => {
+                    "score DESC, clipboard_entries.timestamp DESC, clipboard_entries.id DESC"
+                }
+                _ => "clipboard_entries.timestamp DESC, clipboard_entries.id DESC",
+            },
+        );
+        let mut entries: Vec<ClipboardEntry> = sqlx::query_as(&sql)
+            .bind((!query.is_empty()).then_some(query))
+            .bind(start_time)
+            .bind(end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .bind(min_length.map(|l| l as i64))
+            .bind(max_length.map(|l| l as i64))
+            .bind(like_patterns(&exclude.app_names))
+            .bind(like_patterns(&exclude.window_names))
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+        let mut snippets = self
+            .fts_snippets(
+                "clipboard_entries_fts",
+                "rowid",
+                query,
+                entries.iter().map(|entry| entry.id),
+            )
+            .await?;
+        for entry in &mut entries {
+            entry.snippet = snippets.remove(&entry.id);
+        }
+        Ok(entries)
     }
 
-    pub async fn insert_video_chunk(
+    #[allow(clippy::too_many_arguments)]
+    pub async fn count_clipboard(
         &self,
-        file_path: &str,
-        device_name: &str,
-    ) -> Result<i64, sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
-        let id = sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
-            .bind(file_path)
-            .bind(device_name)
-            .execute(&mut *tx)
-            .await?
-            .last_insert_rowid();
-        tx.commit().await?;
-        Ok(id)
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        exclude: &SearchExclusions,
+    ) -> Result<usize, sqlx::Error> {
+        let query = query.trim();
+        let sql = format!(
+            "SELECT COUNT(*) FROM clipboard_entries {} WHERE {}",
+            clipboard_match_join(query),
+            CLIPBOARD_CONDITIONS
+        );
+        let count: i64 = sqlx::query_scalar(&sql)
+            .bind((!query.is_empty()).then_some(query))
+            .bind(start_time)
+            .bind(end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .bind(min_length.map(|l| l as i64))
+            .bind(max_length.map(|l| l as i64))
+            .bind(like_patterns(&exclude.app_names))
+            .bind(like_patterns(&exclude.window_names))
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count as usize)
     }
 
     pub async fn insert_frame(
@@ -302,10 +3066,14 @@ impl DatabaseManager {
         device_name: &str,
         timestamp: Option<DateTime<Utc>>,
         browser_url: Option<&str>,
+        browser_title: Option<&str>,
         app_name: Option<&str>,
+        app_id: Option<&str>,
         window_name: Option<&str>,
+        window_geometry: Option<&WindowGeometry>,
         focused: bool,
         visible_percentage: Option<f32>,
+        phash: Option<i64>,
     ) -> Result<i64, sqlx::Error> {
         let mut tx = self.pool.begin().await?;
         debug!("insert_frame Transaction started");
@@ -345,17 +3113,24 @@ impl DatabaseManager {
 
         // Insert the new frame with file_path as name and app/window metadata
         let id = sqlx::query(
-            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, visible_percentage) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, browser_title, app_name, app_id, window_name, window_x, window_y, window_width, window_height, focused, visible_percentage, phash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
         )
         .bind(video_chunk_id)
         .bind(offset_index)
         .bind(timestamp)
         .bind(file_path)
         .bind(browser_url)
+        .bind(browser_title)
         .bind(app_name)
+        .bind(app_id)
         .bind(window_name)
+        .bind(window_geometry.map(|geometry| geometry.x))
+        .bind(window_geometry.map(|geometry| geometry.y))
+        .bind(window_geometry.map(|geometry| geometry.width))
+        .bind(window_geometry.map(|geometry| geometry.height))
         .bind(focused)
         .bind(visible_percentage)
+        .bind(phash)
         .execute(&mut *tx)
         .await?
         .last_insert_rowid();
@@ -367,21 +3142,330 @@ impl DatabaseManager {
         Ok(id)
     }
 
+    /// Most recent frame of the same app and window whose perceptual hash is within
+    /// `max_distance` bits of `phash`, looking at the last `lookback` frames of that window.
+    pub async fn find_near_duplicate_frame(
+        &self,
+        app_name: &str,
+        window_name: &str,
+        phash: i64,
+        max_distance: u32,
+        lookback: i64,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let candidates: Vec<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT id, phash
+            FROM frames
+            WHERE app_name = ?1 AND window_name = ?2 AND phash IS NOT NULL
+            ORDER BY id DESC
+            LIMIT ?3
+            "#,
+        )
+        .bind(app_name)
+        .bind(window_name)
+        .bind(lookback)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // sqlite has no popcount, compare the hashes here
+        Ok(candidates
+            .into_iter()
+            .find(|(_, candidate)| ((*candidate ^ phash) as u64).count_ones() <= max_distance)
+            .map(|(id, _)| id))
+    }
+
+    /// Stores a stitched document, its time range is taken from the frames it covers.
+    pub async fn insert_scroll_document(
+        &self,
+        app_name: &str,
+        window_name: &str,
+        text: &str,
+        image_path: &str,
+        frame_ids: &[i64],
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+        let id = sqlx::query(
+            "INSERT INTO scroll_documents (app_name, window_name, text, image_path, start_time, end_time, frame_count) VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6)",
+        )
+        .bind(app_name)
+        .bind(window_name)
+        .bind(text)
+        .bind(image_path)
+        .bind(now)
+        .bind(frame_ids.len() as i64)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        for frame_id in frame_ids {
+            sqlx::query(
+                "INSERT OR IGNORE INTO scroll_document_frames (document_id, frame_id) VALUES (?1, ?2)",
+            )
+            .bind(id)
+            .bind(frame_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE scroll_documents
+            SET start_time = COALESCE((
+                    SELECT MIN(frames.timestamp) FROM frames
+                    JOIN scroll_document_frames ON scroll_document_frames.frame_id = frames.id
+                    WHERE scroll_document_frames.document_id = ?1
+                ), start_time),
+                end_time = COALESCE((
+                    SELECT MAX(frames.timestamp) FROM frames
+                    JOIN scroll_document_frames ON scroll_document_frames.frame_id = frames.id
+                    WHERE scroll_document_frames.document_id = ?1
+                ), end_time)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    pub async fn get_scroll_document(
+        &self,
+        id: i64,
+    ) -> Result<Option<StitchedDocument>, sqlx::Error> {
+        sqlx::query_as::<_, StitchedDocument>("SELECT * FROM scroll_documents WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Document the frame was stitched into, if any.
+    pub async fn get_scroll_document_for_frame(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<StitchedDocument>, sqlx::Error> {
+        sqlx::query_as::<_, StitchedDocument>(
+            r#"
+            SELECT scroll_documents.*
+            FROM scroll_documents
+            JOIN scroll_document_frames ON scroll_document_frames.document_id = scroll_documents.id
+            WHERE scroll_document_frames.frame_id = ?1
+            LIMIT 1
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Documents whose text contains `query`, newest first. An empty query lists them all.
+    pub async fn search_scroll_documents(
+        &self,
+        query: &str,
+        app_name: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<StitchedDocument>, sqlx::Error> {
+        sqlx::query_as::<_, StitchedDocument>(
+            r#"
+            SELECT *
+            FROM scroll_documents
+            WHERE (?1 = '' OR text LIKE '%' || ?1 || '%')
+                AND (?2 IS NULL OR app_name = ?2)
+            ORDER BY start_time DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(query)
+        .bind(app_name)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records a code decoded from a frame, unless the same code was already recorded for a
+    /// frame taken at or after `seen_since`, so a code left on screen is stored once rather
+    /// than for every frame. Returns the id of the new row.
+    pub async fn insert_frame_code(
+        &self,
+        frame_id: i64,
+        format: &str,
+        payload: &str,
+        seen_since: DateTime<Utc>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO frame_codes (frame_id, format, payload)
+            SELECT ?1, ?2, ?3
+            WHERE NOT EXISTS (
+                SELECT 1 FROM frame_codes
+                JOIN frames ON frames.id = frame_codes.frame_id
+                WHERE frame_codes.format = ?2
+                    AND frame_codes.payload = ?3
+                    AND frames.timestamp >= ?4
+            )
+            "#,
+        )
+        .bind(frame_id)
+        .bind(format)
+        .bind(payload)
+        .bind(seen_since)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+    }
+
+    pub async fn search_frame_codes(
+        &self,
+        query: &str,
+        format: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<FrameCode>, sqlx::Error> {
+        sqlx::query_as::<_, FrameCode>(
+            r#"
+            SELECT
+                frame_codes.id,
+                frame_codes.frame_id,
+                frames.timestamp,
+                COALESCE(frames.app_name, '') as app_name,
+                COALESCE(frames.window_name, '') as window_name,
+                frame_codes.format,
+                frame_codes.payload
+            FROM frame_codes
+            JOIN frames ON frames.id = frame_codes.frame_id
+            WHERE (?1 = '' OR frame_codes.payload LIKE '%' || ?1 || '%')
+                AND (?2 IS NULL OR frame_codes.format = ?2)
+                AND (?3 IS NULL OR frames.timestamp >= ?3)
+                AND (?4 IS NULL OR frames.timestamp <= ?4)
+            ORDER BY frames.timestamp DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+        )
+        .bind(query)
+        .bind(format)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records a table detected in a frame. `cells` is row major with rows of equal length.
+    pub async fn insert_table(
+        &self,
+        frame_id: i64,
+        cells: &[Vec<String>],
+        csv: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let cells_json = serde_json::to_string(cells).unwrap_or_else(|_| "[]".to_string());
+        let id = sqlx::query(
+            "INSERT INTO tables (frame_id, row_count, column_count, cells, csv) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(frame_id)
+        .bind(cells.len() as i64)
+        .bind(cells.first().map_or(0, Vec::len) as i64)
+        .bind(cells_json)
+        .bind(csv)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub async fn get_tables_for_frame(
+        &self,
+        frame_id: i64,
+    ) -> Result<Vec<FrameTable>, sqlx::Error> {
+        sqlx::query_as::<_, FrameTable>(
+            r#"
+            SELECT
+                tables.id,
+                tables.frame_id,
+                frames.timestamp,
+                COALESCE(frames.app_name, '') as app_name,
+                COALESCE(frames.window_name, '') as window_name,
+                tables.row_count,
+                tables.column_count,
+                tables.cells,
+                tables.csv
+            FROM tables
+            JOIN frames ON frames.id = tables.frame_id
+            WHERE tables.frame_id = ?1
+            ORDER BY tables.id
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn search_tables(
+        &self,
+        query: &str,
+        app_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<FrameTable>, sqlx::Error> {
+        sqlx::query_as::<_, FrameTable>(
+            r#"
+            SELECT
+                tables.id,
+                tables.frame_id,
+                frames.timestamp,
+                COALESCE(frames.app_name, '') as app_name,
+                COALESCE(frames.window_name, '') as window_name,
+                tables.row_count,
+                tables.column_count,
+                tables.cells,
+                tables.csv
+            FROM tables
+            JOIN frames ON frames.id = tables.frame_id
+            WHERE (?1 = '' OR tables.csv LIKE '%' || ?1 || '%')
+                AND (?2 IS NULL OR frames.app_name = ?2)
+                AND (?3 IS NULL OR frames.timestamp >= ?3)
+                AND (?4 IS NULL OR frames.timestamp <= ?4)
+            ORDER BY frames.timestamp DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+        )
+        .bind(query)
+        .bind(app_name)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     pub async fn insert_ocr_text(
         &self,
         frame_id: i64,
         text: &str,
         text_json: &str,
         ocr_engine: Arc<OcrEngine>,
+        low_quality: bool,
     ) -> Result<(), sqlx::Error> {
         let text_length = text.len() as i64;
         let mut tx = self.pool.begin().await?;
-        sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length) VALUES (?1, ?2, ?3, ?4, ?5)")
+        sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length, low_quality) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
             .bind(frame_id)
             .bind(text)
             .bind(text_json)
             .bind(format!("{:?}", *ocr_engine))
             .bind(text_length)
+            .bind(low_quality)
             .execute(&mut *tx)
             .await?;
 
@@ -408,16 +3492,52 @@ impl DatabaseManager {
         browser_url: Option<&str>,
         focused: Option<bool>,
         min_visible_percentage: Option<f32>,
-        max_visible_percentage: Option<f32>
+        max_visible_percentage: Option<f32>,
+        app_id: Option<&str>,
+        exclude_low_quality: bool,
+        exclude: &SearchExclusions,
+        tag: Option<&str>,
+        sort: SearchSort,
     ) -> Result<Vec<SearchResult>, sqlx::Error> {
         let mut results = Vec::new();
+        // each kind is fetched from the start, the page is cut once they are merged
+        let fetch = limit + offset;
 
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url or app_id is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || app_id.is_some() {
             content_type = ContentType::OCR;
         }
+        if tag.is_some() {
+            match tagged_content_type(content_type, app_name, window_name, frame_name) {
+                Some(tagged) => content_type = tagged,
+                None => return Ok(results),
+            }
+        }
+
+        // copied text has no frame name or speaker, filtering by them leaves it out
+        if content_type == ContentType::Clipboard
+            || (content_type == ContentType::All && frame_name.is_none() && speaker_ids.is_none())
+        {
+            let entries = self
+                .search_clipboard(
+                    query,
+                    fetch,
+                    0,
+                    start_time,
+                    end_time,
+                    app_name,
+                    window_name,
+                    min_length,
+                    max_length,
+                    exclude,
+                    sort,
+                )
+                .await?;
+            results.extend(entries.into_iter().map(SearchResult::Clipboard));
+        }
 
         match content_type {
+            ContentType::Clipboard => {}
             ContentType::All => {
                 let (ocr_results, audio_results, ui_results) =
                     if app_name.is_none() && window_name.is_none() && frame_name.is_none() {
@@ -425,8 +3545,8 @@ impl DatabaseManager {
                         let (ocr, audio, ui) = tokio::try_join!(
                             self.search_ocr(
                                 query,
-                                limit,
-                                offset,
+                                fetch,
+                                0,
                                 start_time,
                                 end_time,
                                 app_name,
@@ -438,16 +3558,23 @@ impl DatabaseManager {
                                 focused,
                                 min_visible_percentage,
                                 max_visible_percentage,
+                                app_id,
+                                exclude_low_quality,
+                                exclude,
+                                tag,
+                                sort,
                             ),
                             self.search_audio(
                                 query,
-                                limit,
-                                offset,
+                                fetch,
+                                0,
                                 start_time,
                                 end_time,
                                 min_length,
                                 max_length,
-                                speaker_ids
+                                speaker_ids,
+                                tag,
+                                sort,
                             ),
                             self.search_ui_monitoring(
                                 query,
@@ -455,8 +3582,9 @@ impl DatabaseManager {
                                 window_name,
                                 start_time,
                                 end_time,
-                                limit,
-                                offset,
+                                exclude,
+                                fetch,
+                                0,
                             )
                         )?;
                         (ocr, Some(audio), ui)
@@ -465,8 +3593,8 @@ impl DatabaseManager {
                         let (ocr, ui) = tokio::try_join!(
                             self.search_ocr(
                                 query,
-                                limit,
-                                offset,
+                                fetch,
+                                0,
                                 start_time,
                                 end_time,
                                 app_name,
@@ -478,6 +3606,11 @@ impl DatabaseManager {
                                 focused,
                                 min_visible_percentage,
                                 max_visible_percentage,
+                                app_id,
+                                exclude_low_quality,
+                                exclude,
+                                tag,
+                                sort,
                             ),
                             self.search_ui_monitoring(
                                 query,
@@ -485,8 +3618,9 @@ impl DatabaseManager {
                                 window_name,
                                 start_time,
                                 end_time,
-                                limit,
-                                offset,
+                                exclude,
+                                fetch,
+                                0,
                             )
                         )?;
                         (ocr, None, ui)
@@ -502,8 +3636,8 @@ impl DatabaseManager {
                 let ocr_results = self
                     .search_ocr(
                         query,
-                        limit,
-                        offset,
+                        fetch,
+                        0,
                         start_time,
                         end_time,
                         app_name,
@@ -515,6 +3649,11 @@ impl DatabaseManager {
                         focused,
                         min_visible_percentage,
                         max_visible_percentage,
+                        app_id,
+                        exclude_low_quality,
+                        exclude,
+                        tag,
+                        sort,
                     )
                     .await?;
                 results.extend(ocr_results.into_iter().map(SearchResult::OCR));
@@ -524,13 +3663,15 @@ impl DatabaseManager {
                     let audio_results = self
                         .search_audio(
                             query,
-                            limit,
-                            offset,
+                            fetch,
+                            0,
                             start_time,
                             end_time,
                             min_length,
                             max_length,
                             speaker_ids,
+                            tag,
+                            sort,
                         )
                         .await?;
                     results.extend(audio_results.into_iter().map(SearchResult::Audio));
@@ -544,8 +3685,9 @@ impl DatabaseManager {
                         window_name,
                         start_time,
                         end_time,
-                        limit,
-                        offset,
+                        exclude,
+                        fetch,
+                        0,
                     )
                     .await?;
                 results.extend(ui_results.into_iter().map(SearchResult::UI));
@@ -554,13 +3696,15 @@ impl DatabaseManager {
                 let audio_results = self
                     .search_audio(
                         query,
-                        limit / 2,
-                        offset,
+                        fetch,
+                        0,
                         start_time,
                         end_time,
                         min_length,
                         max_length,
                         speaker_ids,
+                        tag,
+                        sort,
                     )
                     .await?;
                 let ui_results = self
@@ -570,8 +3714,9 @@ impl DatabaseManager {
                         window_name,
                         start_time,
                         end_time,
-                        limit / 2,
-                        offset,
+                        exclude,
+                        fetch,
+                        0,
                     )
                     .await?;
 
@@ -582,8 +3727,8 @@ impl DatabaseManager {
                 let ocr_results = self
                     .search_ocr(
                         query,
-                        limit / 2,
-                        offset,
+                        fetch,
+                        0,
                         start_time,
                         end_time,
                         app_name,
@@ -595,6 +3740,11 @@ impl DatabaseManager {
                         focused,
                         min_visible_percentage,
                         max_visible_percentage,
+                        app_id,
+                        exclude_low_quality,
+                        exclude,
+                        tag,
+                        sort,
                     )
                     .await?;
                 let ui_results = self
@@ -604,8 +3754,9 @@ impl DatabaseManager {
                         window_name,
                         start_time,
                         end_time,
-                        limit / 2,
-                        offset,
+                        exclude,
+                        fetch,
+                        0,
                     )
                     .await?;
 
@@ -616,20 +3767,22 @@ impl DatabaseManager {
                 let audio_results = self
                     .search_audio(
                         query,
-                        limit / 2,
-                        offset,
+                        fetch,
+                        0,
                         start_time,
                         end_time,
                         min_length,
                         max_length,
                         speaker_ids,
+                        tag,
+                        sort,
                     )
                     .await?;
                 let ocr_results = self
                     .search_ocr(
                         query,
-                        limit / 2,
-                        offset,
+                        fetch,
+                        0,
                         start_time,
                         end_time,
                         app_name,
@@ -641,6 +3794,11 @@ impl DatabaseManager {
                         focused,
                         min_visible_percentage,
                         max_visible_percentage,
+                        app_id,
+                        exclude_low_quality,
+                        exclude,
+                        tag,
+                        sort,
                     )
                     .await?;
 
@@ -649,19 +3807,31 @@ impl DatabaseManager {
             }
         }
 
-        // Sort results by timestamp in descending order
+        // Sort results by timestamp in descending order, or by relevance when asked for. Ties
+        // are broken by kind and id the way each kind is ordered, so pages cut the same list
+        let key = |result: &SearchResult| match result {
+            SearchResult::OCR(ocr) => (ocr.score, ocr.timestamp, (0, ocr.frame_id, 0)),
+            SearchResult::Audio(audio) => (
+                audio.score,
+                audio.timestamp,
+                (1, audio.audio_chunk_id, audio.offset_index),
+            ),
+            SearchResult::UI(ui) => (None, ui.timestamp, (2, ui.id, 0)),
+            SearchResult::Clipboard(entry) => (entry.score, entry.timestamp, (3, entry.id, 0)),
+        };
         results.sort_by(|a, b| {
-            let timestamp_a = match a {
-                SearchResult::OCR(ocr) => ocr.timestamp,
-                SearchResult::Audio(audio) => audio.timestamp,
-                SearchResult::UI(ui) => ui.timestamp,
+            let (score_a, timestamp_a, id_a) = key(a);
+            let (score_b, timestamp_b, id_b) = key(b);
+            let by_score = match sort {
+                SearchSort::Relevance => score_b
+                    .unwrap_or(f64::MIN)
+                    .total_cmp(&score_a.unwrap_or(f64::MIN)),
+                SearchSort::Time => std::cmp::Ordering::Equal,
             };
-            let timestamp_b = match b {
-                SearchResult::OCR(ocr) => ocr.timestamp,
-                SearchResult::Audio(audio) => audio.timestamp,
-                SearchResult::UI(ui) => ui.timestamp,
-            };
-            timestamp_b.cmp(&timestamp_a)
+            by_score
+                .then(timestamp_b.cmp(&timestamp_a))
+                .then(id_a.0.cmp(&id_b.0))
+                .then((id_b.1, id_b.2).cmp(&(id_a.1, id_a.2)))
         });
 
         // Apply offset and limit after sorting
@@ -690,23 +3860,28 @@ impl DatabaseManager {
         browser_url: Option<&str>,
         focused: Option<bool>,
         min_visible_percentage: Option<f32>,
-        max_visible_percentage: Option<f32>
+        max_visible_percentage: Option<f32>,
+        app_id: Option<&str>,
+        exclude_low_quality: bool,
+        exclude: &SearchExclusions,
+        tag: Option<&str>,
+        sort: SearchSort,
     ) -> Result<Vec<OCRResult>, sqlx::Error> {
         let mut frame_fts_parts = Vec::new();
 
         if let Some(app) = app_name {
             if !app.is_empty() {
-                frame_fts_parts.push(format!("app_name:{}", app));
+                frame_fts_parts.push(fts_column_filter("app_name", app));
             }
         }
         if let Some(window) = window_name {
             if !window.is_empty() {
-                frame_fts_parts.push(format!("window_name:{}", window));
+                frame_fts_parts.push(fts_column_filter("window_name", window));
             }
         }
         if let Some(browser) = browser_url {
             if !browser.is_empty() {
-                frame_fts_parts.push(format!("browser_url:{}", browser));
+                frame_fts_parts.push(fts_column_phrase("browser_url", browser));
             }
         }
         if let Some(is_focused) = focused {
@@ -732,11 +3907,19 @@ impl DatabaseManager {
             frames.offset_index,
             frames.app_name,
             ocr_text.ocr_engine,
+            ocr_text.low_quality,
             frames.window_name,
             GROUP_CONCAT(tags.name, ',') as tags,
             frames.browser_url,
+            frames.browser_title,
+            frames.app_id,
+            frames.window_x,
+            frames.window_y,
+            frames.window_width,
+            frames.window_height,
             frames.focused,
-            frames.visible_percentage
+            frames.visible_percentage,
+            {match_columns}
         FROM frames
         JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
         JOIN ocr_text ON frames.id = ocr_text.frame_id
@@ -746,37 +3929,55 @@ impl DatabaseManager {
         {ocr_fts_join}
         WHERE 1=1
             {frame_fts_condition}
-            {ocr_fts_condition}
             AND (?2 IS NULL OR frames.timestamp >= ?2)
             AND (?3 IS NULL OR frames.timestamp <= ?3)
             AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
             AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
             AND (?9 IS NULL OR frames.visible_percentage >= ?9)
             AND (?10 IS NULL OR frames.visible_percentage <= ?10)
+            AND (?11 IS NULL OR frames.app_id = ?11 COLLATE NOCASE)
+            AND (?12 = 0 OR ocr_text.low_quality = 0)
+            AND NOT EXISTS (SELECT 1 FROM json_each(?13) WHERE frames.app_name LIKE '%' || json_each.value || '%' ESCAPE '\')
+            AND NOT EXISTS (SELECT 1 FROM json_each(?14) WHERE frames.window_name LIKE '%' || json_each.value || '%' ESCAPE '\')
+            AND (?15 IS NULL OR {tag_condition})
         GROUP BY frames.id
-        ORDER BY frames.timestamp DESC
+        ORDER BY {order}
         LIMIT ?7 OFFSET ?8
         "#,
+            match_columns = if query.trim().is_empty() {
+                "NULL AS snippet, NULL AS score"
+            } else {
+                "NULL AS snippet, MAX(ocr_match.score) AS score"
+            },
             frame_fts_join = if frame_query.trim().is_empty() {
                 ""
             } else {
                 "JOIN frames_fts ON frames.id = frames_fts.id"
             },
             ocr_fts_join = if query.trim().is_empty() {
-                ""
+                String::new()
             } else {
-                "JOIN ocr_text_fts ON ocr_text.frame_id = ocr_text_fts.frame_id"
+                fts_match_join("ocr_text_fts", "frame_id", "?6", "ocr_match")
+                    + " ON ocr_match.id = ocr_text.frame_id"
             },
             frame_fts_condition = if frame_query.trim().is_empty() {
                 ""
             } else {
                 "AND frames_fts MATCH ?1"
             },
-            ocr_fts_condition = if query.trim().is_empty() {
-                ""
-            } else {
-                "AND ocr_text_fts MATCH ?6"
-            }
+            order = match sort {
+                SearchSort::Relevance if !query.trim().is_empty() => {
+                    "score DESC, frames.timestamp DESC, frames.id DESC"
+                }
+                _ => "frames.timestamp DESC, frames.id DESC",
+            },
+            tag_condition = tag_condition(
+                "vision_tags",
+                "vision_id",
+                "frames.id",
+                "frames.timestamp",
+                "?15"
+            ),
         );
 
         let query_builder = sqlx::query_as(&sql);
@@ -800,8 +4001,21 @@ impl DatabaseManager {
             .bind(offset)
             .bind(min_visible_percentage)
             .bind(max_visible_percentage)
+            .bind(app_id)
+            .bind(exclude_low_quality)
+            .bind(like_patterns(&exclude.app_names))
+            .bind(like_patterns(&exclude.window_names))
+            .bind(tag)
             .fetch_all(&self.pool)
             .await?;
+        let mut snippets = self
+            .fts_snippets(
+                "ocr_text_fts",
+                "frame_id",
+                query.trim(),
+                raw_results.iter().map(|raw| raw.frame_id),
+            )
+            .await?;
 
         Ok(raw_results
             .into_iter()
@@ -815,14 +4029,25 @@ impl DatabaseManager {
                 offset_index: raw.offset_index,
                 app_name: raw.app_name,
                 ocr_engine: raw.ocr_engine,
+                low_quality: raw.low_quality,
                 window_name: raw.window_name,
                 tags: raw
                     .tags
                     .map(|t| t.split(',').map(String::from).collect())
                     .unwrap_or_default(),
                 browser_url: raw.browser_url,
+                browser_title: raw.browser_title,
+                app_id: raw.app_id,
+                window_geometry: WindowGeometry::from_columns(
+                    raw.window_x,
+                    raw.window_y,
+                    raw.window_width,
+                    raw.window_height,
+                ),
                 focused: raw.focused,
-                visible_percentage: raw.visible_percentage
+                visible_percentage: raw.visible_percentage,
+                snippet: snippets.remove(&raw.frame_id),
+                score: raw.score,
             })
             .collect())
     }
@@ -838,9 +4063,11 @@ impl DatabaseManager {
         min_length: Option<usize>,
         max_length: Option<usize>,
         speaker_ids: Option<Vec<i64>>,
+        tag: Option<&str>,
+        sort: SearchSort,
     ) -> Result<Vec<AudioResult>, sqlx::Error> {
         // base query for audio search
-        let mut base_sql = String::from(
+        let mut base_sql = format!(
             "SELECT
                 audio_transcriptions.audio_chunk_id,
                 audio_transcriptions.transcription,
@@ -853,23 +4080,36 @@ impl DatabaseManager {
                 audio_transcriptions.is_input_device,
                 audio_transcriptions.speaker_id,
                 audio_transcriptions.start_time,
-                audio_transcriptions.end_time
+                audio_transcriptions.end_time,
+                audio_transcriptions.words,
+                audio_transcriptions.translation,
+                {}
              FROM audio_transcriptions
              JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
              LEFT JOIN speakers ON audio_transcriptions.speaker_id = speakers.id
              LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
              LEFT JOIN tags ON audio_tags.tag_id = tags.id",
+            if query.is_empty() {
+                "NULL AS snippet, NULL AS score"
+            } else {
+                // the bare id is taken from the best scoring row of the group
+                "NULL AS snippet, MAX(audio_match.score) AS score, audio_match.id AS match_id"
+            }
         );
-        // if query is provided, join the corresponding fts table
+        // if query is provided, join the transcriptions matching it
         if !query.is_empty() {
-            base_sql.push_str(" JOIN audio_transcriptions_fts ON audio_transcriptions_fts.audio_chunk_id = audio_transcriptions.audio_chunk_id");
+            base_sql.push(' ');
+            base_sql.push_str(&fts_match_join(
+                "audio_transcriptions_fts",
+                "rowid",
+                "?",
+                "audio_match",
+            ));
+            base_sql.push_str(" ON audio_match.id = audio_transcriptions.id");
         }
 
         // build where clause conditions in order
         let mut conditions = Vec::new();
-        if !query.is_empty() {
-            conditions.push("audio_transcriptions_fts MATCH ?");
-        }
         if start_time.is_some() {
             conditions.push("audio_transcriptions.timestamp >= ?");
         }
@@ -886,6 +4126,16 @@ impl DatabaseManager {
         if speaker_ids.is_some() {
             conditions.push("(json_array_length(?) = 0 OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?)))");
         }
+        let tagged = tag_condition(
+            "audio_tags",
+            "audio_chunk_id",
+            "audio_chunks.id",
+            "audio_transcriptions.timestamp",
+            "?",
+        );
+        if tag.is_some() {
+            conditions.push(&tagged);
+        }
 
         let where_clause = if conditions.is_empty() {
             "WHERE 1=1".to_owned()
@@ -894,9 +4144,19 @@ impl DatabaseManager {
         };
 
         // complete sql with group, order, limit and offset
+        let order = match sort {
+            SearchSort::Relevance if !query.is_empty() => {
+                "score DESC, audio_transcriptions.timestamp DESC, \
+                 audio_transcriptions.audio_chunk_id DESC, audio_transcriptions.offset_index DESC"
+            }
+            _ => {
+                "audio_transcriptions.timestamp DESC, audio_transcriptions.audio_chunk_id DESC, \
+                 audio_transcriptions.offset_index DESC"
+            }
+        };
         let sql = format!(
-            "{} {} GROUP BY audio_transcriptions.audio_chunk_id, audio_transcriptions.offset_index ORDER BY audio_transcriptions.timestamp DESC LIMIT ? OFFSET ?",
-            base_sql, where_clause
+            "{} {} GROUP BY audio_transcriptions.audio_chunk_id, audio_transcriptions.offset_index ORDER BY {} LIMIT ? OFFSET ?",
+            base_sql, where_clause, order
         );
 
         // prepare binding for speaker_ids (if any)
@@ -928,9 +4188,22 @@ impl DatabaseManager {
                 .bind(&speaker_ids_json)
                 .bind(&speaker_ids_json);
         }
+        if let Some(tag) = tag {
+            // once in the tags and once in the ranges
+            query_builder = query_builder.bind(tag).bind(tag);
+        }
         query_builder = query_builder.bind(limit as i64).bind(offset as i64);
 
         let results_raw: Vec<AudioResultRaw> = query_builder.fetch_all(&self.pool).await?;
+        let snippets = self
+            .fts_snippets(
+                "audio_transcriptions_fts",
+                "rowid",
+                query,
+                results_raw.iter().filter_map(|raw| raw.match_id),
+            )
+            .await?;
+        let snippets = &snippets;
 
         // map raw results into audio result type
         let futures: Vec<_> = results_raw
@@ -964,6 +4237,10 @@ impl DatabaseManager {
                     speaker,
                     start_time: raw.start_time,
                     end_time: raw.end_time,
+                    snippet: raw.match_id.and_then(|id| snippets.get(&id).cloned()),
+                    score: raw.score,
+                    words: transcript_words(raw.words.as_deref()),
+                    translation: translation(raw.translation),
                 })
             })
             .collect();
@@ -971,6 +4248,36 @@ impl DatabaseManager {
         Ok(try_join_all(futures).await?.into_iter().collect())
     }
 
+    /// Snippets marking the terms of `query` in the rows `ids` of the FTS `table`, by id.
+    /// snippet() goes through the whole text of a row, it is only run for a page of results.
+    async fn fts_snippets(
+        &self,
+        table: &str,
+        id_column: &str,
+        query: &str,
+        ids: impl Iterator<Item = i64>,
+    ) -> Result<HashMap<i64, String>, sqlx::Error> {
+        let ids: Vec<i64> = ids.collect();
+        if query.is_empty() || ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let rows: Vec<(i64, String)> = sqlx::query_as(&format!(
+            "SELECT {id_column}, snippet({table}, 0, '<mark>', '</mark>', '…', 24) \
+             FROM {table} WHERE {table} MATCH ?1 \
+             AND {id_column} IN (SELECT value FROM json_each(?2))"
+        ))
+        .bind(query)
+        .bind(serde_json::to_string(&ids).unwrap_or_default())
+        .fetch_all(&self.pool)
+        .await?;
+        // a frame can have several OCR rows, the first one matching wins
+        let mut snippets = HashMap::new();
+        for (id, snippet) in rows {
+            snippets.entry(id).or_insert(snippet);
+        }
+        Ok(snippets)
+    }
+
     pub async fn get_frame(&self, frame_id: i64) -> Result<Option<(String, i64)>, sqlx::Error> {
         sqlx::query_as::<_, (String, i64)>(
             r#"
@@ -1006,14 +4313,53 @@ impl DatabaseManager {
         browser_url: Option<&str>,
         focused: Option<bool>,
         min_visible_percentage: Option<f32>,
-        max_visible_percentage: Option<f32>
+        max_visible_percentage: Option<f32>,
+        app_id: Option<&str>,
+        exclude_low_quality: bool,
+        exclude: &SearchExclusions,
+        tag: Option<&str>,
     ) -> Result<usize, sqlx::Error> {
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url or app_id is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || app_id.is_some() {
             content_type = ContentType::OCR;
         }
+        // tags are on frames, audio chunks and time ranges
+        if tag.is_some() && matches!(content_type, ContentType::UI | ContentType::Clipboard) {
+            return Ok(0);
+        }
+        if content_type == ContentType::Clipboard {
+            return self
+                .count_clipboard(
+                    query,
+                    start_time,
+                    end_time,
+                    app_name,
+                    window_name,
+                    min_length,
+                    max_length,
+                    exclude,
+                )
+                .await;
+        }
 
         if content_type == ContentType::All {
+            // copied text has no frame name, speaker or tag
+            let clipboard_count = if frame_name.is_none() && speaker_ids.is_none() && tag.is_none()
+            {
+                self.count_clipboard(
+                    query,
+                    start_time,
+                    end_time,
+                    app_name,
+                    window_name,
+                    min_length,
+                    max_length,
+                    exclude,
+                )
+                .await?
+            } else {
+                0
+            };
             // Create boxed futures to avoid infinite size issues with recursion
             let ocr_future = Box::pin(self.count_search_results(
                 query,
@@ -1029,7 +4375,11 @@ impl DatabaseManager {
                 browser_url,
                 focused,
                 min_visible_percentage,
-                max_visible_percentage
+                max_visible_percentage,
+                app_id,
+                exclude_low_quality,
+                exclude,
+                tag,
             ));
 
             let ui_future = Box::pin(self.count_search_results(
@@ -1046,7 +4396,11 @@ impl DatabaseManager {
                 None,
                 None,
                 None,
-                None
+                None,
+                None,
+                false,
+                exclude,
+                tag,
             ));
 
             if app_name.is_none() && window_name.is_none() {
@@ -1064,15 +4418,19 @@ impl DatabaseManager {
                     None,
                     None,
                     None,
-                    None
+                    None,
+                    None,
+                    false,
+                    exclude,
+                    tag,
                 ));
 
                 let (ocr_count, audio_count, ui_count) =
                     tokio::try_join!(ocr_future, audio_future, ui_future)?;
-                return Ok(ocr_count + audio_count + ui_count);
+                return Ok(ocr_count + audio_count + ui_count + clipboard_count);
             } else {
                 let (ocr_count, ui_count) = tokio::try_join!(ocr_future, ui_future)?;
-                return Ok(ocr_count + ui_count);
+                return Ok(ocr_count + ui_count + clipboard_count);
             }
         }
 
@@ -1093,23 +4451,23 @@ impl DatabaseManager {
         // Split query parts between frame metadata and OCR content
         if !query.is_empty() {
             ocr_fts_parts.push(query.to_owned()); // Just use the query directly
-            ui_fts_parts.push(query.to_owned());
+            ui_fts_parts.push(format!("({})", query)); // so filters constrain all of `a OR b`
         }
         if let Some(app) = app_name {
             if !app.is_empty() {
-                frame_fts_parts.push(format!("app_name:{}", app));
-                ui_fts_parts.push(format!("app:\"{}\"", app));
+                frame_fts_parts.push(fts_column_filter("app_name", app));
+                ui_fts_parts.push(fts_column_filter("app", app));
             }
         }
         if let Some(window) = window_name {
             if !window.is_empty() {
-                frame_fts_parts.push(format!("window_name:{}", window));
-                ui_fts_parts.push(format!("window:\"{}\"", window));
+                frame_fts_parts.push(fts_column_filter("window_name", window));
+                ui_fts_parts.push(fts_column_filter("window", window));
             }
         }
         if let Some(browser) = browser_url {
             if !browser.is_empty() {
-                frame_fts_parts.push(format!("browser_url:{}", browser));
+                frame_fts_parts.push(fts_column_phrase("browser_url", browser));
             }
         }
         if let Some(is_focused) = focused {
@@ -1131,7 +4489,12 @@ impl DatabaseManager {
                        AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
                        AND (?6 IS NULL OR frames.name LIKE '%' || ?6 || '%')
                        AND (?7 IS NULL OR frames.visible_percentage >= ?7)
-                       AND (?8 IS NULL OR frames.visible_percentage <= ?8)"#,
+                       AND (?8 IS NULL OR frames.visible_percentage <= ?8)
+                       AND (?9 IS NULL OR frames.app_id = ?9 COLLATE NOCASE)
+                       AND (?10 = 0 OR ocr_text.low_quality = 0)
+                       AND NOT EXISTS (SELECT 1 FROM json_each(?11) WHERE frames.app_name LIKE '%' || json_each.value || '%' ESCAPE '\')
+                       AND NOT EXISTS (SELECT 1 FROM json_each(?12) WHERE frames.window_name LIKE '%' || json_each.value || '%' ESCAPE '\')
+                       AND (?13 IS NULL OR {tag_condition})"#,
                 base_table = if ocr_query.is_empty() {
                     "frames
                      JOIN ocr_text ON frames.id = ocr_text.frame_id"
@@ -1144,7 +4507,14 @@ impl DatabaseManager {
                     "1=1"
                 } else {
                     "ocr_text_fts MATCH ?1"
-                }
+                },
+                tag_condition = tag_condition(
+                    "vision_tags",
+                    "vision_id",
+                    "frames.id",
+                    "frames.timestamp",
+                    "?13"
+                ),
             ),
             ContentType::UI => format!(
                 r#"SELECT COUNT(DISTINCT ui_monitoring.id)
@@ -1153,7 +4523,9 @@ impl DatabaseManager {
                        AND (?2 IS NULL OR timestamp >= ?2)
                        AND (?3 IS NULL OR timestamp <= ?3)
                        AND (?4 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) >= ?4)
-                       AND (?5 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) <= ?5)"#,
+                       AND (?5 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) <= ?5)
+                       AND NOT EXISTS (SELECT 1 FROM json_each(?6) WHERE ui_monitoring.app LIKE '%' || json_each.value || '%' ESCAPE '\')
+                       AND NOT EXISTS (SELECT 1 FROM json_each(?7) WHERE ui_monitoring.window LIKE '%' || json_each.value || '%' ESCAPE '\')"#,
                 table = if ui_query.is_empty() {
                     "ui_monitoring"
                 } else {
@@ -1174,17 +4546,25 @@ impl DatabaseManager {
                        AND (?4 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) >= ?4)
                        AND (?5 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) <= ?5)
                        AND (json_array_length(?6) = 0 OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?6)))
+                       AND (?7 IS NULL OR {tag_condition})
                 "#,
                 table = if query.is_empty() {
                     "audio_transcriptions"
                 } else {
-                    "audio_transcriptions_fts JOIN audio_transcriptions ON audio_transcriptions_fts.audio_chunk_id = audio_transcriptions.audio_chunk_id"
+                    "audio_transcriptions_fts JOIN audio_transcriptions ON audio_transcriptions_fts.rowid = audio_transcriptions.id"
                 },
                 match_condition = if query.is_empty() {
                     "1=1"
                 } else {
                     "audio_transcriptions_fts MATCH ?1"
-                }
+                },
+                tag_condition = tag_condition(
+                    "audio_tags",
+                    "audio_chunk_id",
+                    "audio_transcriptions.audio_chunk_id",
+                    "audio_transcriptions.timestamp",
+                    "?7"
+                ),
             ),
             _ => return Ok(0),
         };
@@ -1206,6 +4586,11 @@ impl DatabaseManager {
                     .bind(frame_name)
                     .bind(min_visible_percentage)
                     .bind(max_visible_percentage)
+                    .bind(app_id)
+                    .bind(exclude_low_quality)
+                    .bind(like_patterns(&exclude.app_names))
+                    .bind(like_patterns(&exclude.window_names))
+                    .bind(tag)
                     .fetch_one(&self.pool)
                     .await?
             }
@@ -1216,6 +4601,8 @@ impl DatabaseManager {
                     .bind(end_time)
                     .bind(min_length.map(|l| l as i64))
                     .bind(max_length.map(|l| l as i64))
+                    .bind(like_patterns(&exclude.app_names))
+                    .bind(like_patterns(&exclude.window_names))
                     .fetch_one(&self.pool)
                     .await?
             }
@@ -1227,6 +4614,7 @@ impl DatabaseManager {
                     .bind(min_length.map(|l| l as i64))
                     .bind(max_length.map(|l| l as i64))
                     .bind(json_array)
+                    .bind(tag)
                     .fetch_one(&self.pool)
                     .await?
             }
@@ -1614,19 +5002,21 @@ impl DatabaseManager {
         window_name: Option<&str>,
         start_time: Option<DateTime<Utc>>,
         end_time: Option<DateTime<Utc>>,
+        exclude: &SearchExclusions,
         limit: u32,
         offset: u32,
     ) -> Result<Vec<UiContent>, sqlx::Error> {
-        // combine search aspects into single fts query
+        // combine search aspects into single fts query, the query in parentheses so the
+        // filters constrain all of `a OR b`
         let mut fts_parts = Vec::new();
         if !query.is_empty() {
-            fts_parts.push(query.to_owned());
+            fts_parts.push(format!("({})", query));
         }
         if let Some(app) = app_name {
-            fts_parts.push(format!("app:{}", app));
+            fts_parts.push(fts_column_filter("app", app));
         }
         if let Some(window) = window_name {
-            fts_parts.push(format!("window:{}", window));
+            fts_parts.push(fts_column_filter("window", window));
         }
         let combined_query = fts_parts.join(" ");
 
@@ -1664,8 +5054,10 @@ impl DatabaseManager {
             {}
                 AND (?2 IS NULL OR ui_monitoring.timestamp >= ?2)
                 AND (?3 IS NULL OR ui_monitoring.timestamp <= ?3)
+                AND NOT EXISTS (SELECT 1 FROM json_each(?6) WHERE ui_monitoring.app LIKE '%' || json_each.value || '%' ESCAPE '\')
+                AND NOT EXISTS (SELECT 1 FROM json_each(?7) WHERE ui_monitoring.window LIKE '%' || json_each.value || '%' ESCAPE '\')
             GROUP BY ui_monitoring.id
-            ORDER BY ui_monitoring.timestamp DESC
+            ORDER BY ui_monitoring.timestamp DESC, ui_monitoring.id DESC
             LIMIT ?4 OFFSET ?5
             "#,
             base_sql, where_clause
@@ -1681,6 +5073,8 @@ impl DatabaseManager {
             .bind(end_time)
             .bind(limit)
             .bind(offset)
+            .bind(like_patterns(&exclude.app_names))
+            .bind(like_patterns(&exclude.window_names))
             .fetch_all(&self.pool)
             .await
     }
@@ -1742,12 +5136,13 @@ impl DatabaseManager {
         .await
     }
 
-    // get unnamed speakers
+    // get unnamed speakers, most heard first, the ones after the speaker `after_id` when set
     pub async fn get_unnamed_speakers(
         &self,
         limit: u32,
         offset: u32,
         speaker_ids: Option<Vec<i64>>,
+        after_id: Option<i64>,
     ) -> Result<Vec<Speaker>, sqlx::Error> {
         let base_query = r#"
             WITH RecentAudioPaths AS (
@@ -1782,7 +5177,8 @@ impl DatabaseManager {
                     ORDER BY timestamp DESC
                     LIMIT 3
                 )
-            )
+            ),
+            Ranked AS (
             SELECT
                 s.id,
                 s.name,
@@ -1813,7 +5209,11 @@ impl DatabaseManager {
             JOIN RecentAudioPaths rap ON s.id = rap.speaker_id
             JOIN audio_transcriptions at ON s.id = at.speaker_id
             GROUP BY s.id
-            ORDER BY transcription_count DESC
+            )
+            SELECT id, name, metadata FROM Ranked
+            WHERE ? IS NULL OR (transcription_count, id) <
+                (SELECT transcription_count, id FROM Ranked WHERE id = ?)
+            ORDER BY transcription_count DESC, id DESC
             LIMIT ? OFFSET ?
             "#,
             base_query, speaker_filter
@@ -1828,8 +5228,12 @@ impl DatabaseManager {
             }
         }
 
-        // Add limit and offset last
-        db_query = db_query.bind(limit).bind(offset);
+        // Add the speaker to continue after, limit and offset last
+        db_query = db_query
+            .bind(after_id)
+            .bind(after_id)
+            .bind(limit)
+            .bind(offset);
 
         let res = db_query.fetch_all(&self.pool).await?;
         Ok(res)
@@ -2053,37 +5457,209 @@ impl DatabaseManager {
         Ok(frame_ids)
     }
 
+    /// Stores `items` in `collection`, replacing the embeddings stored for the same ids.
+    pub async fn upsert_embeddings(
+        &self,
+        collection: VectorCollection,
+        items: &[(i64, Vec<f32>)],
+    ) -> Result<(), anyhow::Error> {
+        self.vector_store.upsert(collection, items).await
+    }
+
+    /// Stores the image embedding of a frame. `embedding` has to be L2 normalized and 512
+    /// long, see `screenpipe_vision::image_embedding`.
+    pub async fn insert_frame_embedding(
+        &self,
+        frame_id: i64,
+        embedding: &[f32],
+    ) -> Result<(), anyhow::Error> {
+        self.upsert_embeddings(VectorCollection::Frames, &[(frame_id, embedding.to_vec())])
+            .await
+    }
+
+    /// Stores the text embedding of the OCR text of a frame, `embedding` is a JSON array.
     pub async fn insert_embeddings(
         &self,
         frame_id: i64,
         embedding: String,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query("INSERT INTO ocr_text_embeddings (frame_id, embedding) VALUES (?1, ?2)")
-            .bind(frame_id)
-            .bind(embedding)
-            .execute(&self.pool)
-            .await?;
+    ) -> Result<(), anyhow::Error> {
+        let embedding: Vec<f32> = serde_json::from_str(&embedding)?;
+        self.upsert_embeddings(VectorCollection::OcrText, &[(frame_id, embedding)])
+            .await
+    }
+
+    pub async fn insert_transcription_embedding(
+        &self,
+        audio_transcription_id: i64,
+        embedding: &[f32],
+    ) -> Result<(), anyhow::Error> {
+        self.upsert_embeddings(
+            VectorCollection::Transcriptions,
+            &[(audio_transcription_id, embedding.to_vec())],
+        )
+        .await
+    }
+
+    /// Copies embeddings stored before the vector backend was set up into it and builds its
+    /// ANN index. Slow after switching to LanceDB with a long history, run it in the
+    /// background.
+    pub async fn build_vector_indexes(&self) -> Result<(), anyhow::Error> {
+        for collection in VectorCollection::ALL {
+            let copied = self.vector_store.build_index(collection).await?;
+            if copied > 0 {
+                info!(
+                    "copied {} {} embeddings into the vector index",
+                    copied,
+                    collection.name()
+                );
+            }
+        }
         Ok(())
     }
 
+    /// The `limit` frames closest to `embedding` matching the filters. Fewer come back when
+    /// the filters exclude most of the `MAX_VECTOR_NEIGHBOURS` nearest frames.
+    pub async fn search_frames_by_embedding(
+        &self,
+        embedding: &[f32],
+        limit: u32,
+        app_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<Vec<FrameSimilarity>, anyhow::Error> {
+        let mut k = first_vector_query(limit);
+        loop {
+            let (matches, exhausted) = self
+                .nearest_vectors(VectorCollection::Frames, embedding, k, f32::INFINITY)
+                .await?;
+            let frames = self
+                .frames_of_matches(&matches, limit, app_name, start_time, end_time)
+                .await?;
+            if exhausted || frames.len() as u32 >= limit {
+                return Ok(frames);
+            }
+            k = k.saturating_mul(VECTOR_QUERY_OVERFETCH);
+        }
+    }
+
+    /// Nearest neighbours of `embedding` in `collection` closer than `threshold`, and
+    /// whether there are no more of them to fetch with a larger `k`.
+    async fn nearest_vectors(
+        &self,
+        collection: VectorCollection,
+        embedding: &[f32],
+        k: u32,
+        threshold: f32,
+    ) -> Result<(Vec<VectorMatch>, bool), anyhow::Error> {
+        let k = k.min(MAX_VECTOR_NEIGHBOURS);
+        let matches = self.vector_store.query(collection, embedding, k).await?;
+        let exhausted = (matches.len() as u32) < k
+            || k == MAX_VECTOR_NEIGHBOURS
+            || matches.last().is_some_and(|m| m.distance >= threshold);
+        Ok((
+            matches
+                .into_iter()
+                .filter(|m| m.distance < threshold)
+                .collect(),
+            exhausted,
+        ))
+    }
+
+    async fn frames_of_matches(
+        &self,
+        matches: &[VectorMatch],
+        limit: u32,
+        app_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<Vec<FrameSimilarity>, anyhow::Error> {
+        let mut frames = sqlx::query_as::<_, FrameSimilarity>(
+            r#"
+            SELECT
+                frames.id as frame_id,
+                frames.timestamp,
+                COALESCE(frames.app_name, '') as app_name,
+                COALESCE(frames.window_name, '') as window_name,
+                frames.browser_url,
+                0.0 as similarity
+            FROM json_each(?1) AS matches
+            JOIN frames ON frames.id = matches.value
+            WHERE (?2 IS NULL OR frames.app_name = ?2)
+                AND (?3 IS NULL OR frames.timestamp >= ?3)
+                AND (?4 IS NULL OR frames.timestamp <= ?4)
+            ORDER BY matches.key
+            LIMIT ?5
+            "#,
+        )
+        .bind(match_ids(matches))
+        .bind(app_name)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let distances = match_distances(matches);
+        for frame in &mut frames {
+            frame.similarity = 1.0 - distances.get(&frame.frame_id).copied().unwrap_or(1.0) as f64;
+        }
+        Ok(frames)
+    }
+
+    /// OCR text closest in meaning to `embedding`, at most `threshold` cosine distance away,
+    /// nearest first. Window names and urls match the ones that contain them.
+    #[allow(clippy::too_many_arguments)]
     pub async fn search_similar_embeddings(
         &self,
         embedding: Vec<f32>,
         limit: u32,
         threshold: f32,
-    ) -> Result<Vec<OCRResult>, sqlx::Error> {
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        browser_url: Option<&str>,
+        focused: Option<bool>,
+    ) -> Result<Vec<OCRResult>, anyhow::Error> {
         debug!("searching similar embeddings with threshold {}", threshold);
 
+        let mut k = first_vector_query(limit);
+        loop {
+            let (matches, exhausted) = self
+                .nearest_vectors(VectorCollection::OcrText, &embedding, k, threshold)
+                .await?;
+            let results = self
+                .ocr_results_of_matches(
+                    &matches,
+                    limit,
+                    start_time,
+                    end_time,
+                    app_name,
+                    window_name,
+                    browser_url,
+                    focused,
+                )
+                .await?;
+            if exhausted || results.len() as u32 >= limit {
+                return Ok(results);
+            }
+            k = k.saturating_mul(VECTOR_QUERY_OVERFETCH);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn ocr_results_of_matches(
+        &self,
+        matches: &[VectorMatch],
+        limit: u32,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        browser_url: Option<&str>,
+        focused: Option<bool>,
+    ) -> Result<Vec<OCRResult>, anyhow::Error> {
         let sql = r#"
-            WITH embedding_matches AS (
-                SELECT
-                    frame_id,
-                    vec_distance_cosine(embedding, vec_f32(?1)) as similarity
-                FROM ocr_text_embeddings
-                WHERE vec_distance_cosine(embedding, vec_f32(?1)) < ?2
-                ORDER BY similarity ASC
-                LIMIT ?3
-            )
             SELECT
                 ocr_text.frame_id,
                 ocr_text.text as ocr_text,
@@ -2094,25 +5670,44 @@ impl DatabaseManager {
                 frames.name as frame_name,
                 frames.app_name,
                 ocr_text.ocr_engine,
+                ocr_text.low_quality,
                 frames.window_name,
                 GROUP_CONCAT(tags.name, ',') as tags,
-                frames.browser_url
-            FROM embedding_matches
-            JOIN ocr_text ON embedding_matches.frame_id = ocr_text.frame_id
+                frames.browser_url,
+                frames.browser_title,
+                frames.app_id,
+                frames.window_x,
+                frames.window_y,
+                frames.window_width,
+                frames.window_height,
+                frames.focused,
+                frames.visible_percentage
+            FROM json_each(?1) AS matches
+            JOIN ocr_text ON ocr_text.frame_id = matches.value
             JOIN frames ON ocr_text.frame_id = frames.id
             JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
             LEFT JOIN vision_tags ON frames.id = vision_tags.vision_id
             LEFT JOIN tags ON vision_tags.tag_id = tags.id
+            WHERE (?2 IS NULL OR frames.timestamp >= ?2)
+                AND (?3 IS NULL OR frames.timestamp <= ?3)
+                AND (?4 IS NULL OR frames.app_name = ?4)
+                AND (?6 IS NULL OR frames.window_name LIKE '%' || ?6 || '%')
+                AND (?7 IS NULL OR frames.browser_url LIKE '%' || ?7 || '%')
+                AND (?8 IS NULL OR frames.focused = ?8)
             GROUP BY ocr_text.frame_id
-            ORDER BY embedding_matches.similarity ASC
+            ORDER BY MIN(matches.key)
+            LIMIT ?5
         "#;
 
-        let bytes = embedding.as_bytes();
-
         let raw_results: Vec<OCRResultRaw> = sqlx::query_as(sql)
-            .bind(bytes)
-            .bind(threshold)
+            .bind(match_ids(matches))
+            .bind(start_time)
+            .bind(end_time)
+            .bind(app_name)
             .bind(limit)
+            .bind(window_name)
+            .bind(browser_url)
+            .bind(focused)
             .fetch_all(&self.pool)
             .await?;
 
@@ -2127,6 +5722,7 @@ impl DatabaseManager {
                 offset_index: raw.offset_index,
                 app_name: raw.app_name,
                 ocr_engine: raw.ocr_engine,
+                low_quality: raw.low_quality,
                 window_name: raw.window_name,
                 frame_name: raw.frame_name,
                 tags: raw
@@ -2134,12 +5730,211 @@ impl DatabaseManager {
                     .map(|t| t.split(',').map(String::from).collect())
                     .unwrap_or_default(),
                 browser_url: raw.browser_url,
+                browser_title: raw.browser_title,
+                app_id: raw.app_id,
+                window_geometry: WindowGeometry::from_columns(
+                    raw.window_x,
+                    raw.window_y,
+                    raw.window_width,
+                    raw.window_height,
+                ),
                 focused: raw.focused,
-                visible_percentage: raw.visible_percentage
+                visible_percentage: raw.visible_percentage,
+                snippet: raw.snippet,
+                score: raw.score,
             })
             .collect())
     }
 
+    pub async fn get_ocr_text_without_embeddings(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT ocr_text.frame_id, ocr_text.text
+            FROM ocr_text
+            WHERE TRIM(ocr_text.text) != ''
+                AND NOT EXISTS (
+                    SELECT 1 FROM ocr_text_embeddings
+                    WHERE ocr_text_embeddings.frame_id = ocr_text.frame_id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM embedding_failures
+                    WHERE embedding_failures.collection = 'ocr_text'
+                        AND embedding_failures.content_id = ocr_text.frame_id
+                        AND embedding_failures.attempts >= ?2
+                )
+            ORDER BY ocr_text.frame_id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .bind(MAX_EMBEDDING_ATTEMPTS)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Newest transcriptions that have no embedding yet, as `(id, transcription)`.
+    pub async fn get_transcriptions_without_embeddings(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT audio_transcriptions.id, audio_transcriptions.transcription
+            FROM audio_transcriptions
+            WHERE TRIM(audio_transcriptions.transcription) != ''
+                AND NOT EXISTS (
+                    SELECT 1 FROM audio_transcription_embeddings
+                    WHERE audio_transcription_embeddings.audio_transcription_id = audio_transcriptions.id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM embedding_failures
+                    WHERE embedding_failures.collection = 'transcriptions'
+                        AND embedding_failures.content_id = audio_transcriptions.id
+                        AND embedding_failures.attempts >= ?2
+                )
+            ORDER BY audio_transcriptions.id DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .bind(MAX_EMBEDDING_ATTEMPTS)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records that embedding text `id` of `collection` failed, the text is left without an
+    /// embedding after a few failures.
+    pub async fn record_embedding_failure(
+        &self,
+        collection: VectorCollection,
+        id: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO embedding_failures (collection, content_id) VALUES (?1, ?2) \
+             ON CONFLICT (collection, content_id) DO UPDATE SET attempts = attempts + 1",
+        )
+        .bind(collection.name())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Transcriptions closest in meaning to `embedding`, like
+    /// [`Self::search_similar_embeddings`] for OCR text. Only the ones of `speaker_ids` when
+    /// there are any.
+    pub async fn search_similar_transcriptions(
+        &self,
+        embedding: &[f32],
+        limit: u32,
+        threshold: f32,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        speaker_ids: Option<&[i64]>,
+    ) -> Result<Vec<AudioResult>, anyhow::Error> {
+        let speaker_ids = serde_json::to_string(speaker_ids.unwrap_or_default())
+            .unwrap_or_else(|_| "[]".to_string());
+        let mut k = first_vector_query(limit);
+        loop {
+            let (matches, exhausted) = self
+                .nearest_vectors(VectorCollection::Transcriptions, embedding, k, threshold)
+                .await?;
+            let results = self
+                .audio_results_of_matches(&matches, limit, start_time, end_time, &speaker_ids)
+                .await?;
+            if exhausted || results.len() as u32 >= limit {
+                return Ok(results);
+            }
+            k = k.saturating_mul(VECTOR_QUERY_OVERFETCH);
+        }
+    }
+
+    async fn audio_results_of_matches(
+        &self,
+        matches: &[VectorMatch],
+        limit: u32,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        speaker_ids: &str,
+    ) -> Result<Vec<AudioResult>, anyhow::Error> {
+        let results_raw: Vec<AudioResultRaw> = sqlx::query_as(
+            r#"
+            SELECT
+                audio_transcriptions.audio_chunk_id,
+                audio_transcriptions.transcription,
+                audio_transcriptions.timestamp,
+                audio_chunks.file_path,
+                audio_transcriptions.offset_index,
+                audio_transcriptions.transcription_engine,
+                GROUP_CONCAT(tags.name, ',') as tags,
+                audio_transcriptions.device as device_name,
+                audio_transcriptions.is_input_device,
+                audio_transcriptions.speaker_id,
+                audio_transcriptions.start_time,
+                audio_transcriptions.end_time,
+                audio_transcriptions.words,
+                audio_transcriptions.translation
+            FROM json_each(?1) AS matches
+            JOIN audio_transcriptions ON audio_transcriptions.id = matches.value
+            JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
+            LEFT JOIN speakers ON audio_transcriptions.speaker_id = speakers.id
+            LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
+            LEFT JOIN tags ON audio_tags.tag_id = tags.id
+            WHERE (speakers.id IS NULL OR speakers.hallucination = 0)
+                AND (?2 IS NULL OR audio_transcriptions.timestamp >= ?2)
+                AND (?3 IS NULL OR audio_transcriptions.timestamp <= ?3)
+                AND (json_array_length(?5) = 0 OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?5)))
+            GROUP BY audio_transcriptions.id
+            ORDER BY MIN(matches.key)
+            LIMIT ?4
+            "#,
+        )
+        .bind(match_ids(matches))
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(speaker_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut results = Vec::with_capacity(results_raw.len());
+        for raw in results_raw {
+            let speaker = match raw.speaker_id {
+                Some(id) => self.get_speaker_by_id(id).await.ok(),
+                None => None,
+            };
+            results.push(AudioResult {
+                audio_chunk_id: raw.audio_chunk_id,
+                transcription: raw.transcription,
+                timestamp: raw.timestamp,
+                file_path: raw.file_path,
+                offset_index: raw.offset_index,
+                transcription_engine: raw.transcription_engine,
+                tags: raw
+                    .tags
+                    .map(|s| s.split(',').map(|s| s.to_owned()).collect())
+                    .unwrap_or_default(),
+                device_name: raw.device_name,
+                device_type: if raw.is_input_device {
+                    DeviceType::Input
+                } else {
+                    DeviceType::Output
+                },
+                speaker,
+                start_time: raw.start_time,
+                end_time: raw.end_time,
+                snippet: raw.snippet,
+                score: raw.score,
+                words: transcript_words(raw.words.as_deref()),
+                translation: translation(raw.translation),
+            });
+        }
+        Ok(results)
+    }
+
     // Add method to update frame names
     pub async fn update_frame_name(&self, frame_id: i64, name: &str) -> Result<(), sqlx::Error> {
         sqlx::query("UPDATE frames SET name = ?1 WHERE id = ?2")
@@ -2262,6 +6057,7 @@ impl DatabaseManager {
         fuzzy_match: bool,
         order: Order,
         app_names: Option<Vec<String>>,
+        after_frame_id: Option<i64>,
     ) -> Result<Vec<SearchMatch>, sqlx::Error> {
         let mut conditions = Vec::new();
         let mut owned_conditions = Vec::new();
@@ -2302,6 +6098,18 @@ impl DatabaseManager {
             String::new()
         };
 
+        // continues after the frame of the last match, in the order of the matches
+        if after_frame_id.is_some() {
+            conditions.push(match order {
+                Order::Ascending => {
+                    "(f.timestamp, f.id) > (SELECT timestamp, id FROM frames WHERE id = ?)"
+                }
+                Order::Descending => {
+                    "(f.timestamp, f.id) < (SELECT timestamp, id FROM frames WHERE id = ?)"
+                }
+            });
+        }
+
         let sql = format!(
             r#"
 SELECT
@@ -2315,7 +6123,7 @@ SELECT
 FROM frames f
 INNER JOIN ocr_text o ON f.id = o.frame_id
 WHERE {}
-ORDER BY f.timestamp {}
+ORDER BY f.timestamp {order}, f.id {order}
 LIMIT ? OFFSET ?
 "#,
             if conditions.is_empty() {
@@ -2323,7 +6131,7 @@ LIMIT ? OFFSET ?
             } else {
                 conditions.join(" AND ")
             },
-            match order {
+            order = match order {
                 Order::Ascending => "ASC",
                 Order::Descending => "DESC",
             }
@@ -2352,6 +6160,9 @@ LIMIT ? OFFSET ?
         if !query.is_empty() {
             query_builder = query_builder.bind(&search_condition);
         }
+        if let Some(after_frame_id) = after_frame_id {
+            query_builder = query_builder.bind(after_frame_id);
+        }
 
         // Bind limit and offset
         query_builder = query_builder.bind(limit as i64).bind(offset as i64);
@@ -2362,9 +6173,8 @@ LIMIT ? OFFSET ?
             .iter()
             .map(|row| {
                 let positions = if !query.is_empty() {
-                    let ocr_blocks: Vec<OcrTextBlock> =
-                        serde_json::from_str(&row.text_json).unwrap_or_default();
-                    find_matching_positions(&ocr_blocks, query)
+                    let layout = OcrTextLayout::from_text_json(&row.text_json);
+                    find_matching_positions(&layout.words, query)
                 } else {
                     Vec::new()
                 };
@@ -2384,29 +6194,24 @@ LIMIT ? OFFSET ?
     }
 }
 
-pub fn find_matching_positions(blocks: &[OcrTextBlock], query: &str) -> Vec<TextPosition> {
+pub fn find_matching_positions(words: &[OcrWord], query: &str) -> Vec<TextPosition> {
     let query_lower = query.to_lowercase();
     let query_words: Vec<&str> = query_lower.split_whitespace().collect();
 
-    blocks
+    words
         .iter()
-        .filter_map(|block| {
-            let text_lower = block.text.to_lowercase();
+        .filter_map(|word| {
+            let text_lower = word.text.to_lowercase();
 
             // Check for exact match or any word match
             let matches = text_lower.contains(&query_lower)
-                || query_words.iter().any(|&word| text_lower.contains(word));
+                || query_words.iter().any(|&w| text_lower.contains(w));
 
             if matches {
                 Some(TextPosition {
-                    text: block.text.clone(),
-                    confidence: block.conf.parse::<f32>().unwrap_or(0.0),
-                    bounds: TextBounds {
-                        left: block.left.parse::<f32>().unwrap_or(0.0),
-                        top: block.top.parse::<f32>().unwrap_or(0.0),
-                        width: block.width.parse::<f32>().unwrap_or(0.0),
-                        height: block.height.parse::<f32>().unwrap_or(0.0),
-                    },
+                    text: word.text.clone(),
+                    confidence: word.conf,
+                    bounds: word.bbox.clone(),
                 })
             } else {
                 None
@@ -2415,6 +6220,221 @@ pub fn find_matching_positions(blocks: &[OcrTextBlock], query: &str) -> Vec<Text
         .collect()
 }
 
+/// What a search for a tag runs on, tags are on frames, audio chunks and time ranges but
+/// not on UI captures or copied text. None when that's nothing.
+fn tagged_content_type(
+    content_type: ContentType,
+    app_name: Option<&str>,
+    window_name: Option<&str>,
+    frame_name: Option<&str>,
+) -> Option<ContentType> {
+    match content_type {
+        ContentType::UI | ContentType::Clipboard => None,
+        // as without a tag, audio isn't searched by app or window
+        ContentType::All if app_name.is_some() || window_name.is_some() || frame_name.is_some() => {
+            Some(ContentType::OCR)
+        }
+        ContentType::All => Some(ContentType::AudioAndOcr),
+        ContentType::OcrAndUi => Some(ContentType::OCR),
+        ContentType::AudioAndUi => Some(ContentType::Audio),
+        other => Some(other),
+    }
+}
+
+// Filters of `search_clipboard` and `count_clipboard`, the query is bound to ?1
+const CLIPBOARD_CONDITIONS: &str = "(?2 IS NULL OR clipboard_entries.timestamp >= ?2) \
+     AND (?3 IS NULL OR clipboard_entries.timestamp <= ?3) \
+     AND (?4 IS NULL OR clipboard_entries.app_name LIKE '%' || ?4 || '%') \
+     AND (?5 IS NULL OR clipboard_entries.window_name LIKE '%' || ?5 || '%') \
+     AND (?6 IS NULL OR LENGTH(clipboard_entries.text) >= ?6) \
+     AND (?7 IS NULL OR LENGTH(clipboard_entries.text) <= ?7) \
+     AND NOT EXISTS (SELECT 1 FROM json_each(?8) \
+     WHERE clipboard_entries.app_name LIKE '%' || json_each.value || '%' ESCAPE '\\') \
+     AND NOT EXISTS (SELECT 1 FROM json_each(?9) \
+     WHERE clipboard_entries.window_name LIKE '%' || json_each.value || '%' ESCAPE '\\')";
+
+/// The clipboard entries matching `query`, none joined when it is empty.
+fn clipboard_match_join(query: &str) -> String {
+    if query.is_empty() {
+        String::new()
+    } else {
+        fts_match_join("clipboard_entries_fts", "rowid", "?1", "clipboard_match")
+            + " ON clipboard_match.id = clipboard_entries.id"
+    }
+}
+
+const ANNOTATIONS_SQL: &str = "SELECT annotations.id, annotations.content_type, \
+     annotations.content_id, annotations.start_time, annotations.end_time, annotations.note, \
+     GROUP_CONCAT(tags.name, ',') AS tags, annotations.created_at FROM annotations \
+     LEFT JOIN annotation_tags ON annotation_tags.annotation_id = annotations.id \
+     LEFT JOIN tags ON tags.id = annotation_tags.tag_id";
+
+type AnnotationRow = (
+    i64,
+    String,
+    Option<i64>,
+    DateTime<Utc>,
+    DateTime<Utc>,
+    Option<String>,
+    Option<String>,
+    DateTime<Utc>,
+);
+
+fn annotation_from_row(row: AnnotationRow) -> Annotation {
+    let (id, content_type, content_id, start_time, end_time, note, tags, created_at) = row;
+    Annotation {
+        id,
+        content_type,
+        content_id,
+        start_time,
+        end_time,
+        note,
+        tags: tags
+            .map(|tags| tags.split(',').map(String::from).collect())
+            .unwrap_or_default(),
+        created_at,
+    }
+}
+
+const CALENDAR_EVENTS_SQL: &str = "SELECT calendar_events.id, calendar_events.uid, \
+     calendar_events.source, calendar_events.app_name, calendar_events.title, \
+     calendar_events.start_time, calendar_events.end_time, \
+     calendar_events.attendees, calendar_events.location, calendar_events.annotation_id, \
+     (SELECT COUNT(*) FROM frames WHERE frames.timestamp \
+         BETWEEN calendar_events.start_time AND calendar_events.end_time), \
+     (SELECT COUNT(*) FROM audio_transcriptions WHERE audio_transcriptions.timestamp \
+         BETWEEN calendar_events.start_time AND calendar_events.end_time) \
+     FROM calendar_events";
+
+type CalendarEventRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    String,
+    DateTime<Utc>,
+    DateTime<Utc>,
+    String,
+    Option<String>,
+    Option<i64>,
+    i64,
+    i64,
+);
+
+fn calendar_event_from_row(row: CalendarEventRow) -> CalendarEvent {
+    let (
+        id,
+        uid,
+        source,
+        app_name,
+        title,
+        start_time,
+        end_time,
+        attendees,
+        location,
+        annotation_id,
+        frame_count,
+        transcription_count,
+    ) = row;
+    CalendarEvent {
+        id,
+        uid,
+        source,
+        app_name,
+        title,
+        start_time,
+        end_time,
+        attendees: serde_json::from_str(&attendees).unwrap_or_default(),
+        location,
+        annotation_id,
+        frame_count,
+        transcription_count,
+    }
+}
+
+/// SQL true for the rows tagged `param`: `id_column` is in the tags junction `junction`
+/// (through its `junction_id`), or `timestamp_column` is in a range annotation with it.
+fn tag_condition(
+    junction: &str,
+    junction_id: &str,
+    id_column: &str,
+    timestamp_column: &str,
+    param: &str,
+) -> String {
+    format!(
+        "({id_column} IN (SELECT {junction}.{junction_id} FROM {junction} \
+         JOIN tags ON tags.id = {junction}.tag_id WHERE tags.name = {param} COLLATE NOCASE) \
+         OR EXISTS (SELECT 1 FROM annotations \
+         JOIN annotation_tags ON annotation_tags.annotation_id = annotations.id \
+         JOIN tags ON tags.id = annotation_tags.tag_id \
+         WHERE annotations.content_type = 'range' AND tags.name = {param} COLLATE NOCASE \
+         AND {timestamp_column} BETWEEN annotations.start_time AND annotations.end_time))"
+    )
+}
+
+/// The words stored along with a transcription, none when it has none.
+fn transcript_words(words: Option<&str>) -> Vec<TranscriptWord> {
+    words
+        .and_then(|words| serde_json::from_str(words).ok())
+        .unwrap_or_default()
+}
+
+// transcriptions already in the language they're translated to are stored with an empty one
+fn translation(translation: Option<String>) -> Option<String> {
+    translation.filter(|translation| !translation.is_empty())
+}
+
+/// Nearest neighbours fetched first for `limit` results.
+fn first_vector_query(limit: u32) -> u32 {
+    limit.max(1).saturating_mul(VECTOR_QUERY_OVERFETCH)
+}
+
+/// `values` as a JSON array, for `json_each()`.
+fn json_strings(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// `values` as a json array of LIKE patterns escaped with `\`, so `_` and `%` match
+/// themselves.
+fn like_patterns(values: &[String]) -> String {
+    let escaped: Vec<String> = values
+        .iter()
+        .map(|value| {
+            value
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        })
+        .collect();
+    json_strings(&escaped)
+}
+
+/// Subquery of the rows of `table` matching the query bound to `param`, with their BM25
+/// score (higher is better). `LIMIT -1` keeps SQLite from flattening it into the outer
+/// query, bm25() only works directly on the FTS table. Snippets are made by
+/// `fts_snippets` once the page of results is known.
+fn fts_match_join(table: &str, id_column: &str, param: &str, alias: &str) -> String {
+    format!(
+        "JOIN (SELECT {id_column} AS id, -bm25({table}) AS score \
+         FROM {table} WHERE {table} MATCH {param} LIMIT -1) AS {alias}"
+    )
+}
+
+/// `column:value` for an FTS5 MATCH, values of several words are matched as a phrase.
+fn fts_column_filter(column: &str, value: &str) -> String {
+    if value.split_whitespace().nth(1).is_some() {
+        fts_column_phrase(column, value)
+    } else {
+        format!("{}:{}", column, value)
+    }
+}
+
+/// `column:"value"` for an FTS5 MATCH, for values like urls whose punctuation would be
+/// read as query syntax.
+fn fts_column_phrase(column: &str, value: &str) -> String {
+    format!("{}:\"{}\"", column, value.replace('"', "\"\""))
+}
+
 fn calculate_confidence(positions: &[TextPosition]) -> f32 {
     if positions.is_empty() {
         return 0.0;
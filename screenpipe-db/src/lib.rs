@@ -1,11 +1,21 @@
+mod backup;
+mod central_database;
 mod db;
 mod migration_worker;
 mod types;
+mod vector_store;
 mod video_db;
 
-pub use db::DatabaseManager;
+pub use backup::{backup_database, verify_database};
+#[cfg(feature = "postgres")]
+pub use central_database::PostgresDatabase;
+pub use central_database::{CapturedText, CapturedTranscription, CentralDatabase, SyncPosition};
+pub use db::{
+    database_is_encrypted, database_is_plaintext, DatabaseManager, MAX_WEBHOOK_DELIVERIES,
+};
 pub use migration_worker::{
     create_migration_worker, MigrationCommand, MigrationConfig, MigrationResponse, MigrationStatus,
     MigrationWorker,
 };
 pub use types::*;
+pub use vector_store::{SqliteVecStore, VectorBackend, VectorCollection, VectorMatch, VectorStore};
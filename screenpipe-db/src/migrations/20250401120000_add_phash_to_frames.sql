@@ -0,0 +1,4 @@
+-- Perceptual hash of the captured window image, lets near-identical frames be deduplicated
+ALTER TABLE frames ADD COLUMN phash INTEGER DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_frames_phash ON frames(phash);
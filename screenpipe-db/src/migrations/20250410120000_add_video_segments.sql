@@ -0,0 +1,12 @@
+-- Continuous screen recordings, one row per rolling segment so a timestamp maps to a file and offset
+CREATE TABLE IF NOT EXISTS video_segments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_name TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP DEFAULT NULL,
+    fps REAL NOT NULL,
+    codec TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_video_segments_device_start ON video_segments(device_name, start_time);
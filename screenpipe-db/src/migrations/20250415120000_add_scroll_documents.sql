@@ -0,0 +1,22 @@
+-- Captures of a scrolled window stitched into one tall image with merged OCR text
+CREATE TABLE IF NOT EXISTS scroll_documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    app_name TEXT NOT NULL,
+    window_name TEXT NOT NULL,
+    text TEXT NOT NULL,
+    image_path TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    frame_count INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS scroll_document_frames (
+    document_id INTEGER NOT NULL,
+    frame_id INTEGER NOT NULL,
+    PRIMARY KEY (document_id, frame_id),
+    FOREIGN KEY (document_id) REFERENCES scroll_documents(id) ON DELETE CASCADE,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_scroll_document_frames_frame_id ON scroll_document_frames(frame_id);
+CREATE INDEX IF NOT EXISTS idx_scroll_documents_start_time ON scroll_documents(start_time);
@@ -0,0 +1,2 @@
+-- Title of the active browser tab, shown next to browser_url so results can link back to the page
+ALTER TABLE frames ADD COLUMN browser_title TEXT DEFAULT NULL;
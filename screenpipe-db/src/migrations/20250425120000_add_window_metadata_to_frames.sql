@@ -0,0 +1,9 @@
+-- Bundle id (macOS) or executable name of the captured app, stable across UI languages unlike app_name
+ALTER TABLE frames ADD COLUMN app_id TEXT DEFAULT NULL;
+-- Where the window sat in the monitor frame, in frame pixels
+ALTER TABLE frames ADD COLUMN window_x INTEGER DEFAULT NULL;
+ALTER TABLE frames ADD COLUMN window_y INTEGER DEFAULT NULL;
+ALTER TABLE frames ADD COLUMN window_width INTEGER DEFAULT NULL;
+ALTER TABLE frames ADD COLUMN window_height INTEGER DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_frames_app_id ON frames(app_id);
@@ -0,0 +1,2 @@
+-- OCR whose overall confidence fell below the configured floor, search can exclude it
+ALTER TABLE ocr_text ADD COLUMN low_quality BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,11 @@
+-- QR codes and barcodes decoded from frames
+CREATE TABLE IF NOT EXISTS frame_codes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    format TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_frame_codes_frame_id ON frame_codes(frame_id);
+CREATE INDEX IF NOT EXISTS idx_frame_codes_payload ON frame_codes(payload);
@@ -0,0 +1,12 @@
+-- Tables detected in the OCR layout of a frame, cells stored as a JSON array of rows
+CREATE TABLE IF NOT EXISTS tables (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    row_count INTEGER NOT NULL,
+    column_count INTEGER NOT NULL,
+    cells TEXT NOT NULL,
+    csv TEXT NOT NULL,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_tables_frame_id ON tables(frame_id);
@@ -0,0 +1,6 @@
+-- CLIP embeddings of frames for visual search. Embeddings are L2 normalized, so the
+-- default euclidean distance of vec0 ranks like cosine similarity.
+CREATE VIRTUAL TABLE IF NOT EXISTS frame_embeddings USING vec0(
+    frame_id INTEGER PRIMARY KEY,
+    embedding float[512]
+);
@@ -0,0 +1,11 @@
+-- Text embeddings of transcriptions for semantic search, next to ocr_text_embeddings
+CREATE TABLE IF NOT EXISTS audio_transcription_embeddings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    audio_transcription_id INTEGER NOT NULL UNIQUE,
+    embedding BLOB NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audio_transcription_id) REFERENCES audio_transcriptions(id) ON DELETE CASCADE
+);
+
+-- the background embedder looks up text without embeddings by these
+CREATE INDEX IF NOT EXISTS idx_ocr_text_embeddings_frame_id ON ocr_text_embeddings(frame_id);
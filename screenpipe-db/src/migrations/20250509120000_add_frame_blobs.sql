@@ -0,0 +1,19 @@
+-- Content addressed frame images, identical frames of image chunks are stored once
+CREATE TABLE IF NOT EXISTS frame_blobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    content_key TEXT NOT NULL UNIQUE,
+    file_path TEXT NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- the blob each image of a chunk points at, released when the chunk is deleted
+CREATE TABLE IF NOT EXISTS frame_blob_refs (
+    chunk_path TEXT NOT NULL,
+    offset_index INTEGER NOT NULL,
+    blob_id INTEGER NOT NULL,
+    PRIMARY KEY (chunk_path, offset_index),
+    FOREIGN KEY (blob_id) REFERENCES frame_blobs(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_frame_blob_refs_blob_id ON frame_blob_refs(blob_id);
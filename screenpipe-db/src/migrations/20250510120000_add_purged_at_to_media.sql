@@ -0,0 +1,4 @@
+-- Set once retention or the disk quota deleted the file, the rows stay for the data pointing at them
+ALTER TABLE video_chunks ADD COLUMN purged_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE video_segments ADD COLUMN purged_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN purged_at TIMESTAMP DEFAULT NULL;
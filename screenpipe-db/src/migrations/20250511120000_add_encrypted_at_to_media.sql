@@ -0,0 +1,4 @@
+-- Set once the file was encrypted at rest, files recorded before encryption was enabled are encrypted later
+ALTER TABLE video_chunks ADD COLUMN encrypted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE video_segments ADD COLUMN encrypted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN encrypted_at TIMESTAMP DEFAULT NULL;
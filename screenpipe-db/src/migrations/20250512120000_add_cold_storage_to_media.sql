@@ -0,0 +1,7 @@
+-- Object key of files moved to cold storage, and when a local copy was last fetched back
+ALTER TABLE video_chunks ADD COLUMN cold_key TEXT DEFAULT NULL;
+ALTER TABLE video_chunks ADD COLUMN fetched_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE video_segments ADD COLUMN cold_key TEXT DEFAULT NULL;
+ALTER TABLE video_segments ADD COLUMN fetched_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN cold_key TEXT DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN fetched_at TIMESTAMP DEFAULT NULL;
@@ -0,0 +1,124 @@
+-- Prefix indexes for `term*` queries, and transcriptions indexed under their own id so a
+-- match ranks and highlights the transcription it came from instead of its whole chunk
+PRAGMA foreign_keys = OFF;
+
+DROP TRIGGER IF EXISTS ocr_text_ai;
+DROP TRIGGER IF EXISTS ocr_text_update;
+DROP TRIGGER IF EXISTS ocr_text_delete;
+DROP TABLE IF EXISTS ocr_text_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS ocr_text_fts USING fts5(
+    text,
+    app_name,
+    window_name,
+    frame_id UNINDEXED,
+    tokenize='unicode61',
+    prefix='2 3 4'
+);
+
+INSERT INTO ocr_text_fts(frame_id, text, app_name, window_name)
+SELECT
+    frame_id,
+    text,
+    COALESCE(app_name, ''),
+    COALESCE(window_name, '')
+FROM ocr_text
+WHERE text IS NOT NULL
+  AND text != ''
+  AND frame_id IS NOT NULL;
+
+CREATE TRIGGER IF NOT EXISTS ocr_text_ai AFTER INSERT ON ocr_text
+WHEN NEW.text IS NOT NULL AND NEW.text != '' AND NEW.frame_id IS NOT NULL
+BEGIN
+    INSERT INTO ocr_text_fts(frame_id, text, app_name, window_name)
+    VALUES (
+        NEW.frame_id,
+        NEW.text,
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, '')
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS ocr_text_update AFTER UPDATE ON ocr_text
+WHEN NEW.text IS NOT NULL AND NEW.text != '' AND OLD.frame_id IS NOT NULL
+BEGIN
+    UPDATE ocr_text_fts
+    SET text = NEW.text,
+        app_name = COALESCE(NEW.app_name, ''),
+        window_name = COALESCE(NEW.window_name, '')
+    WHERE frame_id = OLD.frame_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS ocr_text_delete AFTER DELETE ON ocr_text
+BEGIN
+    DELETE FROM ocr_text_fts
+    WHERE frame_id = OLD.frame_id;
+END;
+
+DROP TRIGGER IF EXISTS audio_transcriptions_ai;
+DROP TRIGGER IF EXISTS audio_transcriptions_update;
+DROP TRIGGER IF EXISTS audio_transcriptions_delete;
+DROP TABLE IF EXISTS audio_transcriptions_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS audio_transcriptions_fts USING fts5(
+    transcription,
+    device,
+    audio_chunk_id UNINDEXED,
+    speaker_id,
+    start_time UNINDEXED,
+    end_time UNINDEXED,
+    tokenize='unicode61',
+    prefix='2 3 4'
+);
+
+INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+SELECT
+    id,
+    transcription,
+    COALESCE(device, ''),
+    audio_chunk_id,
+    speaker_id,
+    start_time,
+    end_time
+FROM audio_transcriptions
+WHERE transcription IS NOT NULL
+  AND transcription != ''
+  AND audio_chunk_id IS NOT NULL;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_ai AFTER INSERT ON audio_transcriptions
+WHEN NEW.transcription IS NOT NULL AND NEW.transcription != '' AND NEW.audio_chunk_id IS NOT NULL
+BEGIN
+    INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES (
+        NEW.id,
+        NEW.transcription,
+        COALESCE(NEW.device, ''),
+        NEW.audio_chunk_id,
+        NEW.speaker_id,
+        NEW.start_time,
+        NEW.end_time
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_update AFTER UPDATE ON audio_transcriptions
+WHEN NEW.transcription IS NOT NULL AND NEW.transcription != '' AND OLD.audio_chunk_id IS NOT NULL
+BEGIN
+    INSERT OR REPLACE INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES (
+        NEW.id,
+        NEW.transcription,
+        COALESCE(NEW.device, ''),
+        NEW.audio_chunk_id,
+        NEW.speaker_id,
+        NEW.start_time,
+        NEW.end_time
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_delete AFTER DELETE ON audio_transcriptions
+BEGIN
+    DELETE FROM audio_transcriptions_fts
+    WHERE rowid = OLD.id;
+END;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,6 @@
+-- Summaries of a day of captures, the digest is stored as JSON
+CREATE TABLE IF NOT EXISTS daily_digests (
+    date TEXT PRIMARY KEY,
+    digest TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
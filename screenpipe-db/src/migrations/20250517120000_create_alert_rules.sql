@@ -0,0 +1,29 @@
+-- Rules raising alerts when captured text matches a regex, types and apps are comma
+-- separated filters
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    types TEXT NOT NULL DEFAULT '',
+    apps TEXT NOT NULL DEFAULT '',
+    cooldown_secs INTEGER NOT NULL DEFAULT 300,
+    notify BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One row per time a rule matched
+CREATE TABLE IF NOT EXISTS alert_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    app_name TEXT,
+    window_name TEXT,
+    matched_text TEXT NOT NULL,
+    frame_id INTEGER,
+    audio_chunk_id INTEGER,
+    timestamp TIMESTAMP NOT NULL,
+    FOREIGN KEY (rule_id) REFERENCES alert_rules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_alert_events_rule_id ON alert_events(rule_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_alert_events_timestamp ON alert_events(timestamp);
@@ -0,0 +1,23 @@
+-- Notes and tags attached to a frame (vision), an audio chunk (audio) or a time range
+-- (range), content_id is the frame or audio chunk
+CREATE TABLE IF NOT EXISTS annotations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    content_type TEXT NOT NULL,
+    content_id INTEGER,
+    start_time TIMESTAMP,
+    end_time TIMESTAMP,
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS annotation_tags (
+    annotation_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (annotation_id, tag_id),
+    FOREIGN KEY (annotation_id) REFERENCES annotations(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_annotations_content ON annotations(content_type, content_id);
+CREATE INDEX IF NOT EXISTS idx_annotations_range ON annotations(start_time, end_time);
+CREATE INDEX IF NOT EXISTS idx_annotation_tags_tag_id ON annotation_tags(tag_id);
@@ -0,0 +1,13 @@
+-- Moments pinned by the user. The media of a pinned frame is kept by retention and the
+-- storage quota, the frame is the one shown at the pinned time when none was given.
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER,
+    timestamp TIMESTAMP NOT NULL,
+    title TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_bookmarks_frame_id ON bookmarks(frame_id);
+CREATE INDEX IF NOT EXISTS idx_bookmarks_timestamp ON bookmarks(timestamp);
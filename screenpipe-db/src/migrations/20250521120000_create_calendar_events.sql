@@ -0,0 +1,17 @@
+-- Meetings read from a calendar. `uid` is the event's UID, with the start of the
+-- occurrence for recurring events. What is captured during a meeting is tagged with its
+-- title and attendees through the range annotation `annotation_id`.
+CREATE TABLE IF NOT EXISTS calendar_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    uid TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    attendees TEXT NOT NULL DEFAULT '[]',
+    location TEXT,
+    annotation_id INTEGER,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (annotation_id) REFERENCES annotations(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_calendar_events_range ON calendar_events(start_time, end_time);
@@ -0,0 +1,35 @@
+-- Text copied to the clipboard, with the app focused when it was copied
+CREATE TABLE IF NOT EXISTS clipboard_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP NOT NULL,
+    text TEXT NOT NULL,
+    app_name TEXT,
+    window_name TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_entries_timestamp ON clipboard_entries(timestamp);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_entries_fts USING fts5(
+    text,
+    app_name,
+    window_name,
+    tokenize='unicode61',
+    prefix='2 3 4'
+);
+
+CREATE TRIGGER IF NOT EXISTS clipboard_entries_ai AFTER INSERT ON clipboard_entries
+BEGIN
+    INSERT INTO clipboard_entries_fts(rowid, text, app_name, window_name)
+    VALUES (
+        NEW.id,
+        NEW.text,
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, '')
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS clipboard_entries_delete AFTER DELETE ON clipboard_entries
+BEGIN
+    DELETE FROM clipboard_entries_fts
+    WHERE rowid = OLD.id;
+END;
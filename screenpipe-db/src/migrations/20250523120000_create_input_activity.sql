@@ -0,0 +1,9 @@
+-- How many keys were pressed, buttons clicked, mouse moves and scrolls seen in every
+-- minute with input. Counts only, which keys or where the mouse was is never stored.
+CREATE TABLE IF NOT EXISTS input_activity (
+    minute TIMESTAMP PRIMARY KEY,
+    key_presses INTEGER NOT NULL DEFAULT 0,
+    mouse_clicks INTEGER NOT NULL DEFAULT 0,
+    mouse_moves INTEGER NOT NULL DEFAULT 0,
+    scrolls INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,10 @@
+-- Stretches capture was paused for automatically, e.g. while the computer was idle or
+-- locked. `end_time` is NULL while still paused.
+CREATE TABLE IF NOT EXISTS capture_pauses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    reason TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_capture_pauses_start_time ON capture_pauses(start_time);
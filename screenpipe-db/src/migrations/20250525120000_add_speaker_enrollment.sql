@@ -0,0 +1,3 @@
+-- Speakers enrolled with a known voice, e.g. "me". Transcripts are labeled with an
+-- enrolled speaker before any other speaker the voice is close to.
+ALTER TABLE speakers ADD COLUMN enrolled BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,11 @@
+-- Audio devices that disconnected or came back while recording. `fallback_device` is the
+-- device recording moved to, or back from, NULL when there was none.
+CREATE TABLE IF NOT EXISTS audio_device_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT NOT NULL,
+    device TEXT NOT NULL,
+    fallback_device TEXT,
+    timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audio_device_events_timestamp ON audio_device_events(timestamp);
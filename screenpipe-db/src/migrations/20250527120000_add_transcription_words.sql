@@ -0,0 +1,3 @@
+-- When each word of a transcription was said, a JSON array of {"word", "start", "end"} in
+-- seconds into the audio chunk. NULL for transcriptions made without word timestamps.
+ALTER TABLE audio_transcriptions ADD COLUMN words TEXT;
@@ -0,0 +1,75 @@
+-- Translation of each transcription, indexed with it so searching in either language finds
+-- it. NULL until it is translated, empty when the transcription already is in that language.
+ALTER TABLE audio_transcriptions ADD COLUMN translation TEXT;
+-- Language code the transcription was translated to, e.g. 'en'
+ALTER TABLE audio_transcriptions ADD COLUMN translation_language TEXT;
+
+DROP TRIGGER IF EXISTS audio_transcriptions_ai;
+DROP TRIGGER IF EXISTS audio_transcriptions_update;
+DROP TRIGGER IF EXISTS audio_transcriptions_delete;
+DROP TABLE IF EXISTS audio_transcriptions_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS audio_transcriptions_fts USING fts5(
+    transcription,
+    device,
+    audio_chunk_id UNINDEXED,
+    speaker_id,
+    start_time UNINDEXED,
+    end_time UNINDEXED,
+    translation,
+    tokenize='unicode61',
+    prefix='2 3 4'
+);
+
+INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time, translation)
+SELECT
+    id,
+    transcription,
+    COALESCE(device, ''),
+    audio_chunk_id,
+    speaker_id,
+    start_time,
+    end_time,
+    COALESCE(translation, '')
+FROM audio_transcriptions
+WHERE transcription IS NOT NULL
+  AND transcription != ''
+  AND audio_chunk_id IS NOT NULL;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_ai AFTER INSERT ON audio_transcriptions
+WHEN NEW.transcription IS NOT NULL AND NEW.transcription != '' AND NEW.audio_chunk_id IS NOT NULL
+BEGIN
+    INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time, translation)
+    VALUES (
+        NEW.id,
+        NEW.transcription,
+        COALESCE(NEW.device, ''),
+        NEW.audio_chunk_id,
+        NEW.speaker_id,
+        NEW.start_time,
+        NEW.end_time,
+        COALESCE(NEW.translation, '')
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_update AFTER UPDATE ON audio_transcriptions
+WHEN NEW.transcription IS NOT NULL AND NEW.transcription != '' AND OLD.audio_chunk_id IS NOT NULL
+BEGIN
+    INSERT OR REPLACE INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time, translation)
+    VALUES (
+        NEW.id,
+        NEW.transcription,
+        COALESCE(NEW.device, ''),
+        NEW.audio_chunk_id,
+        NEW.speaker_id,
+        NEW.start_time,
+        NEW.end_time,
+        COALESCE(NEW.translation, '')
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_delete AFTER DELETE ON audio_transcriptions
+BEGIN
+    DELETE FROM audio_transcriptions_fts
+    WHERE rowid = OLD.id;
+END;
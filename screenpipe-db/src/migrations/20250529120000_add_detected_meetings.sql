@@ -0,0 +1,9 @@
+-- Meetings detected from a meeting app window open while audio is transcribed, stored
+-- along with those read from a calendar. `source` is `calendar` or `detected`,
+-- `app_name` the meeting app of detected ones. `summary` holds the JSON of the summary
+-- written once the meeting is over.
+ALTER TABLE calendar_events ADD COLUMN source TEXT NOT NULL DEFAULT 'calendar';
+ALTER TABLE calendar_events ADD COLUMN app_name TEXT;
+ALTER TABLE calendar_events ADD COLUMN summary TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_calendar_events_source ON calendar_events(source, end_time);
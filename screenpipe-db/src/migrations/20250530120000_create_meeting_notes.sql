@@ -0,0 +1,14 @@
+-- Summary, decisions and action items the LLM wrote for a detected meeting once it was
+-- over. Deleted when the meeting changes, so they are written again.
+CREATE TABLE IF NOT EXISTS meeting_notes (
+    meeting_id INTEGER PRIMARY KEY,
+    summary TEXT NOT NULL,
+    -- JSON array of strings
+    decisions TEXT NOT NULL DEFAULT '[]',
+    -- JSON array of {"task", "owner", "due"}
+    action_items TEXT NOT NULL DEFAULT '[]',
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (meeting_id) REFERENCES calendar_events(id) ON DELETE CASCADE
+);
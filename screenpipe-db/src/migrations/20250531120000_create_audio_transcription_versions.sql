@@ -0,0 +1,15 @@
+-- Transcripts replaced by a re-transcription, the current one stays in
+-- audio_transcriptions.
+CREATE TABLE IF NOT EXISTS audio_transcription_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    audio_transcription_id INTEGER NOT NULL,
+    transcription TEXT NOT NULL,
+    transcription_engine TEXT NOT NULL,
+    -- JSON array of {"word", "start", "end"}, empty when the engine had no word timings
+    words TEXT NOT NULL DEFAULT '[]',
+    replaced_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (audio_transcription_id) REFERENCES audio_transcriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audio_transcription_versions_transcription
+    ON audio_transcription_versions(audio_transcription_id);
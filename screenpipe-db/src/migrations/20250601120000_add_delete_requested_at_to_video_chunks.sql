@@ -0,0 +1,3 @@
+-- Set when data deletion matched every frame of a chunk still recorded to, its file is
+-- deleted once the chunk is closed
+ALTER TABLE video_chunks ADD COLUMN delete_requested_at TIMESTAMP DEFAULT NULL;
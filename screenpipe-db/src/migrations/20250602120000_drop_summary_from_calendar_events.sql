@@ -0,0 +1,2 @@
+-- Summaries of meetings over are their notes in meeting_notes
+ALTER TABLE calendar_events DROP COLUMN summary;
@@ -0,0 +1,8 @@
+-- Times the LLM failed to translate a transcription, the ones it keeps failing on are tried
+-- after the others and given up on after a few attempts.
+ALTER TABLE audio_transcriptions ADD COLUMN translation_attempts INTEGER NOT NULL DEFAULT 0;
+
+-- The translator looks for transcriptions to translate every 30 seconds
+CREATE INDEX IF NOT EXISTS idx_audio_transcriptions_translation_language
+    ON audio_transcriptions(translation_language)
+    WHERE TRIM(transcription) != '';
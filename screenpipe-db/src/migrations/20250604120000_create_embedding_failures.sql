@@ -0,0 +1,10 @@
+-- Texts the embedding model failed on, left without an embedding after a few attempts
+-- rather than tried again every round.
+CREATE TABLE IF NOT EXISTS embedding_failures (
+    -- `VectorCollection::name` of the text, e.g. 'ocr_text'
+    collection TEXT NOT NULL,
+    -- frame id of OCR text, transcription id
+    content_id INTEGER NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (collection, content_id)
+);
@@ -0,0 +1,2 @@
+-- Near duplicate frames are looked up among the latest frames of a window
+CREATE INDEX IF NOT EXISTS idx_frames_app_window_id ON frames(app_name, window_name, id);
@@ -0,0 +1,29 @@
+-- Captures collected from every machine of a team, keyed by the id on that machine
+CREATE TABLE IF NOT EXISTS captured_texts (
+    machine TEXT NOT NULL,
+    id BIGINT NOT NULL,
+    frame_id BIGINT NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    device_name TEXT NOT NULL,
+    app_name TEXT NOT NULL,
+    window_name TEXT NOT NULL,
+    browser_url TEXT,
+    text TEXT NOT NULL,
+    PRIMARY KEY (machine, id)
+);
+
+CREATE TABLE IF NOT EXISTS captured_transcriptions (
+    machine TEXT NOT NULL,
+    id BIGINT NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    device TEXT NOT NULL,
+    is_input_device BOOLEAN NOT NULL,
+    speaker_id BIGINT,
+    transcription TEXT NOT NULL,
+    PRIMARY KEY (machine, id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_captured_texts_timestamp ON captured_texts(timestamp);
+CREATE INDEX IF NOT EXISTS idx_captured_transcriptions_timestamp ON captured_transcriptions(timestamp);
+CREATE INDEX IF NOT EXISTS idx_captured_texts_search ON captured_texts USING GIN (to_tsvector('simple', text));
+CREATE INDEX IF NOT EXISTS idx_captured_transcriptions_search ON captured_transcriptions USING GIN (to_tsvector('simple', transcription));
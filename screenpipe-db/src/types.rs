@@ -21,6 +21,7 @@ pub enum SearchResult {
     OCR(OCRResult),
     Audio(AudioResult),
     UI(UiContent),
+    Clipboard(ClipboardEntry),
 }
 
 #[derive(FromRow, Debug)]
@@ -43,11 +44,29 @@ pub struct OCRResultRaw {
     pub offset_index: i64,
     pub app_name: String,
     pub ocr_engine: String,
+    #[sqlx(default)]
+    pub low_quality: bool,
     pub window_name: String,
     pub tags: Option<String>,
     pub browser_url: Option<String>,
+    #[sqlx(default)]
+    pub browser_title: Option<String>,
+    #[sqlx(default)]
+    pub app_id: Option<String>,
+    #[sqlx(default)]
+    pub window_x: Option<i64>,
+    #[sqlx(default)]
+    pub window_y: Option<i64>,
+    #[sqlx(default)]
+    pub window_width: Option<i64>,
+    #[sqlx(default)]
+    pub window_height: Option<i64>,
     pub focused: Option<bool>,
     pub visible_percentage: f32,
+    #[sqlx(default)]
+    pub snippet: Option<String>,
+    #[sqlx(default)]
+    pub score: Option<f64>,
 }
 
 #[derive(OaSchema, Debug, Serialize, Deserialize)]
@@ -61,11 +80,46 @@ pub struct OCRResult {
     pub offset_index: i64,
     pub app_name: String,
     pub ocr_engine: String,
+    /// Overall OCR confidence fell below the configured floor
+    pub low_quality: bool,
     pub window_name: String,
     pub tags: Vec<String>,
     pub browser_url: Option<String>,
+    pub browser_title: Option<String>,
+    pub app_id: Option<String>,
+    pub window_geometry: Option<WindowGeometry>,
     pub focused: Option<bool>,
     pub visible_percentage: f32,
+    /// Text around the matched terms, which are wrapped in `<mark>` tags
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    pub score: Option<f64>,
+}
+
+/// Where a captured window sat in the monitor frame, in frame pixels.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowGeometry {
+    /// Geometry stored in the frames table, `None` for frames captured before it was recorded.
+    pub fn from_columns(
+        x: Option<i64>,
+        y: Option<i64>,
+        width: Option<i64>,
+        height: Option<i64>,
+    ) -> Option<Self> {
+        Some(Self {
+            x: x? as i32,
+            y: y? as i32,
+            width: width? as u32,
+            height: height? as u32,
+        })
+    }
 }
 
 #[derive(OaSchema, Debug, Deserialize, PartialEq, Default, Clone)]
@@ -85,6 +139,8 @@ pub enum ContentType {
     #[serde(rename = "audio+ocr")]
     #[serde(alias = "audio ocr")]
     AudioAndOcr,
+    /// Text copied to the clipboard, also searched with `all`
+    Clipboard,
 }
 
 #[derive(FromRow)]
@@ -101,6 +157,17 @@ pub struct AudioResultRaw {
     pub speaker_id: Option<i64>,
     pub start_time: Option<f64>,
     pub end_time: Option<f64>,
+    #[sqlx(default)]
+    pub snippet: Option<String>,
+    #[sqlx(default)]
+    pub score: Option<f64>,
+    #[sqlx(default)]
+    pub words: Option<String>,
+    #[sqlx(default)]
+    pub translation: Option<String>,
+    /// Transcription that matched the query, its snippet is made once the page is known
+    #[sqlx(default)]
+    pub match_id: Option<i64>,
 }
 
 #[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
@@ -130,6 +197,116 @@ pub struct AudioResult {
     pub speaker: Option<Speaker>,
     pub start_time: Option<f64>,
     pub end_time: Option<f64>,
+    /// Text around the matched terms, which are wrapped in `<mark>` tags
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    pub score: Option<f64>,
+    /// When each word was said, empty when the engine didn't tell
+    pub words: Vec<TranscriptWord>,
+    /// The transcription translated, None until it is or when it needn't be
+    pub translation: Option<String>,
+}
+
+/// A transcribed word and when it was said, in seconds into the audio chunk.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptWord {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// A transcription of an imported recording, see `DatabaseManager::insert_imported_audio`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedTranscription {
+    pub transcription: String,
+    pub offset_index: i64,
+    /// Seconds into the recording
+    pub start_time: f64,
+    pub end_time: f64,
+    /// When it was said
+    pub timestamp: DateTime<Utc>,
+    pub words: Vec<TranscriptWord>,
+}
+
+/// A frame of an imported screenshot or video, see `DatabaseManager::insert_imported_frames`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedFrame {
+    pub timestamp: DateTime<Utc>,
+    pub phash: Option<i64>,
+    /// Text OCR found and its layout json, none when OCR failed
+    pub ocr: Option<(String, String)>,
+}
+
+/// A transcript replaced by a re-transcription, see
+/// `DatabaseManager::replace_audio_transcription`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct AudioTranscriptionVersion {
+    pub id: i64,
+    pub audio_transcription_id: i64,
+    pub transcription: String,
+    pub transcription_engine: String,
+    #[sqlx(json)]
+    pub words: Vec<TranscriptWord>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// App and window names left out of OCR, accessibility and clipboard search results, each
+/// matching names that contain it, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchExclusions {
+    pub app_names: Vec<String>,
+    pub window_names: Vec<String>,
+}
+
+/// What `DatabaseManager::delete_data` deletes, data matching every field set. App and
+/// window names match names that contain them, ignoring case. Audio has no app or window,
+/// it only matches without them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataFilter {
+    /// Recorded at or after it
+    pub start_time: Option<DateTime<Utc>>,
+    /// Recorded before it
+    pub end_time: Option<DateTime<Utc>>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// A monitor or an audio device
+    pub device_name: Option<String>,
+}
+
+impl DataFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &DataFilter::default()
+    }
+
+    pub fn matches_audio(&self) -> bool {
+        self.app_name.is_none() && self.window_name.is_none()
+    }
+}
+
+/// Rows `DatabaseManager::delete_data` deleted, or would delete on a dry run.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct DataDeletion {
+    pub frames: u64,
+    pub ocr_text: u64,
+    /// Accessibility text
+    pub ui_text: u64,
+    pub transcriptions: u64,
+    /// Text and image embeddings of the deleted frames and transcriptions
+    pub embeddings: u64,
+    /// Copied text
+    #[serde(default)]
+    pub clipboard: u64,
+}
+
+/// How text search results are ordered.
+#[derive(OaSchema, Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// Newest first
+    #[default]
+    Time,
+    /// Best BM25 match first, newest first without a text query
+    Relevance,
 }
 
 #[derive(OaSchema, Debug, Deserialize, PartialEq)]
@@ -156,6 +333,23 @@ pub struct UiContent {
     pub browser_url: Option<String>,
 }
 
+/// Text copied to the clipboard, see `DatabaseManager::insert_clipboard_entry`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct ClipboardEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    /// App focused when the text was copied, none when nothing was captured just before
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// Text around the matched terms, which are wrapped in `<mark>` tags
+    #[sqlx(default)]
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    #[sqlx(default)]
+    pub score: Option<f64>,
+}
+
 #[derive(OaSchema, Debug, Clone)]
 pub struct FrameData {
     pub frame_id: i64,
@@ -244,7 +438,7 @@ pub struct TextPosition {
     pub bounds: TextBounds,
 }
 
-#[derive(OaSchema, Debug, Serialize, Clone)]
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct TextBounds {
     pub left: f32,
     pub top: f32,
@@ -252,6 +446,82 @@ pub struct TextBounds {
     pub height: f32,
 }
 
+impl TextBounds {
+    /// Smallest box containing both `self` and `other`.
+    pub fn union(&self, other: &TextBounds) -> TextBounds {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        let right = (self.left + self.width).max(other.left + other.width);
+        let bottom = (self.top + self.height).max(other.top + other.height);
+        TextBounds {
+            left,
+            top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+}
+
+/// A recognized word. Bounds are in pixels relative to the captured window image,
+/// with the origin at the top-left corner.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub conf: f32,
+    pub bbox: TextBounds,
+}
+
+/// A recognized line of text, using the same coordinate space as [`OcrWord`].
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OcrLine {
+    pub text: String,
+    pub conf: f32,
+    pub bbox: TextBounds,
+}
+
+/// Layout persisted in `ocr_text.text_json`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct OcrTextLayout {
+    #[serde(default)]
+    pub lines: Vec<OcrLine>,
+    #[serde(default)]
+    pub words: Vec<OcrWord>,
+}
+
+impl OcrTextLayout {
+    /// Parses a stored `text_json` value. Rows written before the structured layout
+    /// existed hold a per-engine array; word blocks from those are still recovered.
+    pub fn from_text_json(text_json: &str) -> Self {
+        if text_json.trim_start().starts_with('[') {
+            let blocks: Vec<OcrTextBlock> = serde_json::from_str(text_json).unwrap_or_default();
+            return OcrTextLayout {
+                lines: Vec::new(),
+                words: blocks.into_iter().map(OcrWord::from).collect(),
+            };
+        }
+        serde_json::from_str(text_json).unwrap_or_default()
+    }
+
+    pub fn to_text_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+impl From<OcrTextBlock> for OcrWord {
+    fn from(block: OcrTextBlock) -> Self {
+        OcrWord {
+            text: block.text,
+            conf: block.conf.parse::<f32>().unwrap_or(0.0),
+            bbox: TextBounds {
+                left: block.left.parse::<f32>().unwrap_or(0.0),
+                top: block.top.parse::<f32>().unwrap_or(0.0),
+                width: block.width.parse::<f32>().unwrap_or(0.0),
+                height: block.height.parse::<f32>().unwrap_or(0.0),
+            },
+        }
+    }
+}
+
 #[derive(OaSchema, Serialize)]
 pub struct SearchMatch {
     pub frame_id: i64,
@@ -294,6 +564,395 @@ pub struct VideoMetadata {
     pub name: Option<String>,
 }
 
+/// One rolling file of a continuous screen recording.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct VideoSegment {
+    pub id: i64,
+    pub device_name: String,
+    pub file_path: String,
+    pub start_time: DateTime<Utc>,
+    /// `None` while the segment is still being written
+    pub end_time: Option<DateTime<Utc>>,
+    pub fps: f64,
+    pub codec: String,
+}
+
+impl VideoSegment {
+    /// Seconds into the file at which `timestamp` was recorded.
+    pub fn offset_secs(&self, timestamp: DateTime<Utc>) -> f64 {
+        let offset = (timestamp - self.start_time).num_milliseconds().max(0) as f64 / 1000.0;
+        match self.end_time {
+            Some(end_time) => {
+                offset.min((end_time - self.start_time).num_milliseconds() as f64 / 1000.0)
+            }
+            None => offset,
+        }
+    }
+}
+
+/// Kind of media file retention deletes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    /// Frames of a monitor, a video or a directory of images
+    VideoChunk,
+    /// A file of a continuous screen recording
+    VideoSegment,
+    AudioChunk,
+}
+
+/// A media file still on disk, see `DatabaseManager::get_media_to_purge`.
+#[derive(Debug, Clone, FromRow)]
+pub struct MediaFile {
+    pub id: i64,
+    pub file_path: String,
+    /// When the last data in the file was recorded
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A media file moved to cold storage, see `DatabaseManager::get_cold_media`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColdMedia {
+    pub kind: MediaKind,
+    pub id: i64,
+    /// Object key in cold storage, ending with `/` for an image chunk directory
+    pub cold_key: String,
+}
+
+/// Captures of a scrolled window stitched into one document.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct StitchedDocument {
+    pub id: i64,
+    pub app_name: String,
+    pub window_name: String,
+    pub text: String,
+    pub image_path: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub frame_count: i64,
+}
+
+/// A QR code or barcode decoded from a frame.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct FrameCode {
+    pub id: i64,
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub format: String,
+    pub payload: String,
+}
+
+/// A table detected in the OCR layout of a frame.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct FrameTable {
+    pub id: i64,
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub row_count: i64,
+    pub column_count: i64,
+    /// Cell text, row major
+    #[sqlx(json)]
+    pub cells: Vec<Vec<String>>,
+    pub csv: String,
+}
+
+/// A frame matching a visual search, see `DatabaseManager::search_frames_by_embedding`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct FrameSimilarity {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    /// Cosine similarity with the query, higher is closer
+    pub similarity: f64,
+}
+
+/// A frame on the timeline, see `DatabaseManager::get_timeline_frames`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TimelineFrame {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+    /// Start of the OCR text, `None` until the frame has been read
+    pub snippet: Option<String>,
+}
+
+/// A transcription on the timeline.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TimelineTranscription {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device: String,
+    pub is_input_device: bool,
+    pub speaker_id: Option<i64>,
+    pub transcription: String,
+    /// Seconds into the audio chunk
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+/// A frame with its full OCR output, see `DatabaseManager::get_export_frames`.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct ExportFrame {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+    /// The video chunk, or image chunk directory, the frame is in
+    pub file_path: String,
+    pub offset_index: i64,
+    pub text: Option<String>,
+    /// Words and their positions as the OCR engine returned them
+    pub text_json: Option<String>,
+    pub ocr_engine: Option<String>,
+}
+
+/// A transcription with the audio chunk it was transcribed from.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct ExportTranscription {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device: String,
+    pub is_input_device: bool,
+    pub speaker_id: Option<i64>,
+    pub transcription: String,
+    pub transcription_engine: String,
+    /// Seconds into the audio chunk
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub file_path: String,
+}
+
+/// Time an app and window were focused within an hour, see
+/// `DatabaseManager::get_app_usage`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct AppUsage {
+    /// Start of the hour, UTC
+    pub hour: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub seconds: f64,
+}
+
+/// A stretch of time one window stayed focused, see
+/// `DatabaseManager::get_window_sessions`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct WindowSession {
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub start_time: DateTime<Utc>,
+    /// Timestamp of the last frame
+    pub end_time: DateTime<Utc>,
+    pub frame_count: i64,
+}
+
+/// Keyboard and mouse input seen within a minute, see
+/// `DatabaseManager::add_input_activity`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, Default, PartialEq, Eq)]
+pub struct InputActivity {
+    /// Start of the minute, UTC
+    pub minute: DateTime<Utc>,
+    pub key_presses: i64,
+    pub mouse_clicks: i64,
+    pub mouse_moves: i64,
+    pub scrolls: i64,
+}
+
+/// A URL capture events are posted to, see `DatabaseManager::insert_webhook`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// Key the bodies are signed with, never sent back
+    #[serde(skip_serializing, default)]
+    pub secret: Option<String>,
+    /// Comma separated filters, empty to send everything
+    pub types: String,
+    pub apps: String,
+    pub keywords: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One attempt to post an event to a webhook.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    /// 1 for the first try
+    pub attempt: i64,
+    pub success: bool,
+    /// Status code of the response, none when the request failed
+    pub status_code: Option<i64>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A token of the HTTP API, see `DatabaseManager::insert_api_token`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    /// Hex SHA-256 of the token, never sent back
+    #[serde(skip_serializing, default)]
+    pub token_hash: String,
+    /// Comma separated, e.g. "read-search,read-media"
+    pub scopes: String,
+    pub created_at: DateTime<Utc>,
+    /// Updated at most once a minute
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Raises an alert when captured text matches `pattern`, see
+/// `DatabaseManager::insert_alert_rule`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct AlertRule {
+    pub id: i64,
+    pub name: String,
+    /// Regex matched against the captured text, ignoring case
+    pub pattern: String,
+    /// Comma separated filters, empty to check everything
+    pub types: String,
+    pub apps: String,
+    /// Seconds after an alert the rule stays quiet
+    pub cooldown_secs: i64,
+    /// Whether alerts are shown as desktop notifications
+    pub notify: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A match of an alert rule.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub id: i64,
+    pub rule_id: i64,
+    pub event_type: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// The part of the text the pattern matched
+    pub matched_text: String,
+    pub frame_id: Option<i64>,
+    pub audio_chunk_id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A note or tags attached to a frame, an audio chunk or a time range, see
+/// `DatabaseManager::insert_annotation`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Annotation {
+    pub id: i64,
+    /// `vision`, `audio` or `range`
+    pub content_type: String,
+    /// The frame or audio chunk, none for ranges
+    pub content_id: Option<i64>,
+    /// The range, or the frame's or audio chunk's timestamp twice
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A stretch capture was paused for, see `DatabaseManager::start_capture_pause`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CapturePause {
+    pub id: i64,
+    /// e.g. `idle` or `locked`
+    pub reason: String,
+    pub start_time: DateTime<Utc>,
+    /// When capture resumed, none while it is still paused
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+/// An audio device lost or back while recording, see
+/// `DatabaseManager::insert_audio_device_event`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct AudioDeviceEvent {
+    pub id: i64,
+    /// `disconnected` or `reconnected`
+    pub event: String,
+    /// e.g. `MacBook Pro Microphone (input)`
+    pub device: String,
+    /// Recorded while `device` was gone, none when nothing took over
+    pub fallback_device: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A moment pinned by the user, see `DatabaseManager::insert_bookmark`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct Bookmark {
+    pub id: i64,
+    /// The frame pinned, none when nothing was captured before `timestamp`
+    pub frame_id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A meeting read from the calendar or detected from a meeting app, see
+/// `DatabaseManager::upsert_calendar_event` and `DatabaseManager::upsert_detected_meeting`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub id: i64,
+    /// UID of the calendar event, with the start of the occurrence for recurring ones
+    pub uid: String,
+    /// `calendar` or `detected`
+    pub source: String,
+    /// Meeting app of a detected meeting, e.g. "zoom.us"
+    pub app_name: Option<String>,
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// Names, or emails when the calendar has none
+    pub attendees: Vec<String>,
+    pub location: Option<String>,
+    /// The range annotation tagging what was captured during the meeting
+    pub annotation_id: Option<i64>,
+    pub frame_count: i64,
+    pub transcription_count: i64,
+}
+
+/// A task agreed on in a meeting.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ActionItem {
+    pub task: String,
+    /// Who took it on, when it was said
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// When it is due as it was said, e.g. "next Friday"
+    #[serde(default)]
+    pub due: Option<String>,
+}
+
+/// What the LLM wrote about a meeting once it was over, see
+/// `DatabaseManager::upsert_meeting_notes`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct MeetingNotes {
+    pub meeting_id: i64,
+    pub summary: String,
+    #[sqlx(json)]
+    pub decisions: Vec<String>,
+    #[sqlx(json)]
+    pub action_items: Vec<ActionItem>,
+    pub provider: String,
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(OaSchema, Clone, Eq, PartialEq, Hash, Serialize, Debug, Deserialize)]
 pub struct AudioDevice {
     pub name: String,
@@ -308,6 +967,13 @@ pub enum OcrEngine {
     WindowsNative,
     AppleNative,
     Custom(CustomOcrConfig),
+    Paddle,
+    Embedded,
+    Provider(String),
+    /// Text read from the macOS accessibility tree instead of OCR
+    AppleAccessibility,
+    /// Text read from Windows UI Automation elements, merged with OCR
+    WindowsUiAutomation,
 }
 
 #[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,514 @@
+use anyhow::Result;
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashMap;
+use zerocopy::AsBytes;
+
+#[cfg(feature = "lancedb")]
+pub use lance::LanceDbStore;
+
+// Rows copied per batch when building an index from the sqlite tables
+#[cfg(feature = "lancedb")]
+const INDEX_BATCH: u32 = 1000;
+
+/// A set of embeddings, each keyed by the id of the row it embeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VectorCollection {
+    /// CLIP embeddings of frames, keyed by frame id
+    Frames,
+    /// Text embeddings of OCR text, keyed by frame id
+    OcrText,
+    /// Text embeddings of transcriptions, keyed by transcription id
+    Transcriptions,
+}
+
+impl VectorCollection {
+    pub const ALL: [VectorCollection; 3] = [
+        VectorCollection::Frames,
+        VectorCollection::OcrText,
+        VectorCollection::Transcriptions,
+    ];
+
+    pub fn dimensions(&self) -> usize {
+        match self {
+            VectorCollection::Frames => 512,
+            VectorCollection::OcrText | VectorCollection::Transcriptions => 768,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VectorCollection::Frames => "frames",
+            VectorCollection::OcrText => "ocr_text",
+            VectorCollection::Transcriptions => "transcriptions",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            VectorCollection::Frames => "frame_embeddings",
+            VectorCollection::OcrText => "ocr_text_embeddings",
+            VectorCollection::Transcriptions => "audio_transcription_embeddings",
+        }
+    }
+
+    fn key_column(&self) -> &'static str {
+        match self {
+            VectorCollection::Frames | VectorCollection::OcrText => "frame_id",
+            VectorCollection::Transcriptions => "audio_transcription_id",
+        }
+    }
+}
+
+/// A nearest neighbour of a query vector.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorMatch {
+    pub id: i64,
+    /// Cosine distance, 0 for the same direction
+    pub distance: f32,
+}
+
+/// Which index nearest neighbour queries run against.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum VectorBackend {
+    /// The sqlite-vec tables the embeddings are stored in
+    #[default]
+    SqliteVec,
+    /// A LanceDB database at `path`, needs the `lancedb` feature
+    LanceDb { path: String },
+}
+
+/// Stores embeddings and finds the nearest ones. The sqlite tables always hold every
+/// embedding, so what is embedded is known whatever the backend and another index can be
+/// built from them; LanceDB keeps a copy with an IVF-PQ index for large histories.
+pub enum VectorStore {
+    SqliteVec(SqliteVecStore),
+    #[cfg(feature = "lancedb")]
+    LanceDb {
+        sqlite: SqliteVecStore,
+        lance: LanceDbStore,
+    },
+}
+
+impl VectorStore {
+    pub async fn open(pool: SqlitePool, backend: &VectorBackend) -> Result<Self> {
+        let sqlite = SqliteVecStore::new(pool);
+        match backend {
+            VectorBackend::SqliteVec => Ok(VectorStore::SqliteVec(sqlite)),
+            #[cfg(feature = "lancedb")]
+            VectorBackend::LanceDb { path } => Ok(VectorStore::LanceDb {
+                sqlite,
+                lance: LanceDbStore::open(path).await?,
+            }),
+            #[cfg(not(feature = "lancedb"))]
+            VectorBackend::LanceDb { .. } => Err(anyhow::anyhow!(
+                "screenpipe was built without the lancedb feature"
+            )),
+        }
+    }
+
+    fn sqlite(&self) -> &SqliteVecStore {
+        match self {
+            VectorStore::SqliteVec(sqlite) => sqlite,
+            #[cfg(feature = "lancedb")]
+            VectorStore::LanceDb { sqlite, .. } => sqlite,
+        }
+    }
+
+    /// Inserts `items`, replacing the embeddings stored for their ids.
+    pub async fn upsert(
+        &self,
+        collection: VectorCollection,
+        items: &[(i64, Vec<f32>)],
+    ) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        self.sqlite().upsert(collection, items).await?;
+        #[cfg(feature = "lancedb")]
+        if let VectorStore::LanceDb { lance, .. } = self {
+            lance.upsert(collection, items).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the embeddings of `ids`.
+    pub async fn delete(&self, collection: VectorCollection, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.sqlite().delete(collection, ids).await?;
+        #[cfg(feature = "lancedb")]
+        if let VectorStore::LanceDb { lance, .. } = self {
+            lance.delete(collection, ids).await?;
+        }
+        Ok(())
+    }
+
+    /// The `k` stored embeddings closest to `embedding`, nearest first.
+    pub async fn query(
+        &self,
+        collection: VectorCollection,
+        embedding: &[f32],
+        k: u32,
+    ) -> Result<Vec<VectorMatch>> {
+        match self {
+            VectorStore::SqliteVec(sqlite) => sqlite.query(collection, embedding, k).await,
+            #[cfg(feature = "lancedb")]
+            VectorStore::LanceDb { lance, .. } => lance.query(collection, embedding, k).await,
+        }
+    }
+
+    /// Copies embeddings stored before the index was set up into it and (re)builds its ANN
+    /// index. Returns the number of embeddings copied, nothing to do for sqlite-vec.
+    pub async fn build_index(&self, collection: VectorCollection) -> Result<usize> {
+        match self {
+            VectorStore::SqliteVec(_) => Ok(0),
+            #[cfg(feature = "lancedb")]
+            VectorStore::LanceDb { sqlite, lance } => {
+                let mut copied = 0;
+                if lance.count(collection).await? < sqlite.count(collection).await? {
+                    let mut after = 0;
+                    loop {
+                        let batch = sqlite.page(collection, after, INDEX_BATCH).await?;
+                        let Some((last, _)) = batch.last() else {
+                            break;
+                        };
+                        after = *last;
+                        lance.upsert(collection, &batch).await?;
+                        copied += batch.len();
+                    }
+                }
+                lance.build_index(collection).await?;
+                Ok(copied)
+            }
+        }
+    }
+}
+
+/// Embeddings in the sqlite database, searched by sqlite-vec. Frames are a `vec0` table,
+/// text embeddings plain tables scanned with `vec_distance_cosine`.
+pub struct SqliteVecStore {
+    pool: SqlitePool,
+}
+
+impl SqliteVecStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn upsert(
+        &self,
+        collection: VectorCollection,
+        items: &[(i64, Vec<f32>)],
+    ) -> Result<()> {
+        // vec0 tables don't support upserts, replace by hand
+        let delete = format!(
+            "DELETE FROM {} WHERE {} = ?1",
+            collection.table(),
+            collection.key_column()
+        );
+        let insert = format!(
+            "INSERT INTO {} ({}, embedding) VALUES (?1, vec_f32(?2))",
+            collection.table(),
+            collection.key_column()
+        );
+        let mut tx = self.pool.begin().await?;
+        for (id, embedding) in items {
+            sqlx::query(&delete).bind(id).execute(&mut *tx).await?;
+            sqlx::query(&insert)
+                .bind(id)
+                .bind(embedding.as_bytes())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, collection: VectorCollection, ids: &[i64]) -> Result<()> {
+        let sql = format!(
+            "DELETE FROM {} WHERE {} IN (SELECT value FROM json_each(?1))",
+            collection.table(),
+            collection.key_column()
+        );
+        sqlx::query(&sql)
+            .bind(serde_json::to_string(ids)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn query(
+        &self,
+        collection: VectorCollection,
+        embedding: &[f32],
+        k: u32,
+    ) -> Result<Vec<VectorMatch>> {
+        let sql = match collection {
+            // unit vectors: |a - b|^2 = 2 - 2 cos
+            VectorCollection::Frames => r#"
+                SELECT frame_id, distance * distance / 2.0
+                FROM frame_embeddings
+                WHERE embedding MATCH vec_f32(?1) AND k = ?2
+                ORDER BY distance
+                "#
+            .to_string(),
+            // older text embeddings were stored as JSON, possibly more than once per frame
+            _ => format!(
+                "SELECT {key}, MIN(vec_distance_cosine(embedding, vec_f32(?1))) as distance
+                FROM {table}
+                GROUP BY {key}
+                ORDER BY distance
+                LIMIT ?2",
+                key = collection.key_column(),
+                table = collection.table()
+            ),
+        };
+        let matches = sqlx::query_as::<_, (i64, f64)>(&sql)
+            .bind(embedding.as_bytes())
+            .bind(k)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(matches
+            .into_iter()
+            .map(|(id, distance)| VectorMatch {
+                id,
+                distance: distance as f32,
+            })
+            .collect())
+    }
+
+    /// Number of ids with an embedding.
+    pub async fn count(&self, collection: VectorCollection) -> Result<usize> {
+        let sql = format!(
+            "SELECT COUNT(DISTINCT {}) FROM {}",
+            collection.key_column(),
+            collection.table()
+        );
+        let count: i64 = sqlx::query_scalar(&sql).fetch_one(&self.pool).await?;
+        Ok(count as usize)
+    }
+
+    /// Up to `limit` embeddings with an id greater than `after`, by id.
+    pub async fn page(
+        &self,
+        collection: VectorCollection,
+        after: i64,
+        limit: u32,
+    ) -> Result<Vec<(i64, Vec<f32>)>> {
+        let sql = format!(
+            "SELECT {key}, vec_f32(embedding) FROM {table} WHERE {key} > ?1 ORDER BY {key} LIMIT ?2",
+            key = collection.key_column(),
+            table = collection.table()
+        );
+        let rows = sqlx::query_as::<_, (i64, Vec<u8>)>(&sql)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        // an id embedded more than once keeps its latest embedding
+        let mut embeddings: Vec<(i64, Vec<f32>)> = Vec::with_capacity(rows.len());
+        for (id, bytes) in rows {
+            let embedding = bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect();
+            match embeddings.last_mut() {
+                Some(last) if last.0 == id => last.1 = embedding,
+                _ => embeddings.push((id, embedding)),
+            }
+        }
+        Ok(embeddings)
+    }
+}
+
+/// JSON array of the ids of `matches`, to bind to `json_each`, which also yields each id's
+/// position as `key` to order rows by.
+pub(crate) fn match_ids(matches: &[VectorMatch]) -> String {
+    serde_json::to_string(&matches.iter().map(|m| m.id).collect::<Vec<_>>())
+        .unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Distance of every match, by id.
+pub(crate) fn match_distances(matches: &[VectorMatch]) -> HashMap<i64, f32> {
+    matches.iter().map(|m| (m.id, m.distance)).collect()
+}
+
+#[cfg(feature = "lancedb")]
+mod lance {
+    use super::{VectorCollection, VectorMatch};
+    use anyhow::{anyhow, Result};
+    use arrow_array::{
+        types::Float32Type, Array, FixedSizeListArray, Float32Array, Int64Array, RecordBatch,
+        RecordBatchIterator,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+    use futures::TryStreamExt;
+    use lancedb::{
+        index::{vector::IvfPqIndexBuilder, Index},
+        query::{ExecutableQuery, QueryBase},
+        table::OptimizeAction,
+        Connection, DistanceType, Table,
+    };
+    use std::sync::Arc;
+    use tokio::sync::OnceCell;
+
+    // IVF-PQ trains on the stored vectors, below this the flat scan is fast enough anyway
+    const MIN_INDEX_ROWS: usize = 10_000;
+    // Ids per delete predicate
+    const DELETE_BATCH: usize = 1000;
+
+    /// Embeddings in a LanceDB database, one table per collection with an `id` and a
+    /// `vector` column.
+    pub struct LanceDbStore {
+        connection: Connection,
+        tables: [OnceCell<Table>; 3],
+    }
+
+    impl LanceDbStore {
+        pub async fn open(path: &str) -> Result<Self> {
+            Ok(Self {
+                connection: lancedb::connect(path).execute().await?,
+                tables: Default::default(),
+            })
+        }
+
+        fn schema(collection: VectorCollection) -> Arc<Schema> {
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new(
+                    "vector",
+                    DataType::FixedSizeList(
+                        Arc::new(Field::new("item", DataType::Float32, true)),
+                        collection.dimensions() as i32,
+                    ),
+                    false,
+                ),
+            ]))
+        }
+
+        async fn table(&self, collection: VectorCollection) -> Result<&Table> {
+            self.tables[collection as usize]
+                .get_or_try_init(|| self.open_or_create_table(collection))
+                .await
+        }
+
+        async fn open_or_create_table(&self, collection: VectorCollection) -> Result<Table> {
+            let names = self.connection.table_names().execute().await?;
+            let table = if names.iter().any(|name| name == collection.name()) {
+                self.connection
+                    .open_table(collection.name())
+                    .execute()
+                    .await?
+            } else {
+                self.connection
+                    .create_empty_table(collection.name(), Self::schema(collection))
+                    .execute()
+                    .await?
+            };
+            Ok(table)
+        }
+
+        pub async fn upsert(
+            &self,
+            collection: VectorCollection,
+            items: &[(i64, Vec<f32>)],
+        ) -> Result<()> {
+            let schema = Self::schema(collection);
+            let ids = Int64Array::from_iter_values(items.iter().map(|(id, _)| *id));
+            let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                items
+                    .iter()
+                    .map(|(_, embedding)| Some(embedding.iter().copied().map(Some))),
+                collection.dimensions() as i32,
+            );
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(ids), Arc::new(vectors)])?;
+
+            let table = self.table(collection).await?;
+            let mut merge = table.merge_insert(&["id"]);
+            merge
+                .when_matched_update_all(None)
+                .when_not_matched_insert_all();
+            merge
+                .execute(Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema)))
+                .await?;
+            Ok(())
+        }
+
+        pub async fn query(
+            &self,
+            collection: VectorCollection,
+            embedding: &[f32],
+            k: u32,
+        ) -> Result<Vec<VectorMatch>> {
+            let batches: Vec<RecordBatch> = self
+                .table(collection)
+                .await?
+                .query()
+                .nearest_to(embedding)?
+                .distance_type(DistanceType::Cosine)
+                .limit(k as usize)
+                .execute()
+                .await?
+                .try_collect()
+                .await?;
+
+            let mut matches = Vec::new();
+            for batch in batches {
+                let ids = batch
+                    .column_by_name("id")
+                    .and_then(|column| column.as_any().downcast_ref::<Int64Array>())
+                    .ok_or_else(|| anyhow!("lancedb result has no id column"))?;
+                let distances = batch
+                    .column_by_name("_distance")
+                    .and_then(|column| column.as_any().downcast_ref::<Float32Array>())
+                    .ok_or_else(|| anyhow!("lancedb result has no distance column"))?;
+                for row in 0..batch.num_rows() {
+                    matches.push(VectorMatch {
+                        id: ids.value(row),
+                        distance: distances.value(row),
+                    });
+                }
+            }
+            matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            Ok(matches)
+        }
+
+        pub async fn count(&self, collection: VectorCollection) -> Result<usize> {
+            Ok(self.table(collection).await?.count_rows(None).await?)
+        }
+
+        pub async fn delete(&self, collection: VectorCollection, ids: &[i64]) -> Result<()> {
+            let table = self.table(collection).await?;
+            for batch in ids.chunks(DELETE_BATCH) {
+                let ids: Vec<String> = batch.iter().map(i64::to_string).collect();
+                table.delete(&format!("id IN ({})", ids.join(", "))).await?;
+            }
+            Ok(())
+        }
+
+        /// Creates the IVF-PQ index once there are enough vectors, afterwards adds the
+        /// vectors stored since to it.
+        pub async fn build_index(&self, collection: VectorCollection) -> Result<()> {
+            let table = self.table(collection).await?;
+            if table.count_rows(None).await? < MIN_INDEX_ROWS {
+                return Ok(());
+            }
+            if table.list_indices().await?.is_empty() {
+                table
+                    .create_index(
+                        &["vector"],
+                        Index::IvfPq(
+                            IvfPqIndexBuilder::default().distance_type(DistanceType::Cosine),
+                        ),
+                    )
+                    .execute()
+                    .await?;
+            } else {
+                table.optimize(OptimizeAction::All).await?;
+            }
+            Ok(())
+        }
+    }
+}
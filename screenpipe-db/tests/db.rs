@@ -2,9 +2,12 @@
 mod tests {
     use std::sync::Arc;
 
-    use chrono::Utc;
+    use chrono::{DurationRound, Utc};
     use screenpipe_db::{
-        AudioDevice, ContentType, DatabaseManager, DeviceType, Frame, OcrEngine, SearchResult,
+        ActionItem, AudioDevice, ContentType, DataFilter, DatabaseManager, DeviceType, Frame,
+        ImportedFrame, ImportedTranscription, MediaKind, OcrEngine, OcrTextLayout, Order,
+        SearchExclusions, SearchResult, SearchSort, TagContentType, TranscriptWord,
+        VectorCollection, WindowGeometry, MAX_WEBHOOK_DELIVERIES,
     };
 
     async fn setup_test_db() -> DatabaseManager {
@@ -37,7 +40,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -45,6 +48,7 @@ mod tests {
             "Hello, world!",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -67,7 +71,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -117,7 +126,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(em_results.len(), 0);
@@ -140,7 +154,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -197,7 +216,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(em_results.len(), 0);
@@ -220,7 +244,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -242,7 +271,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
 
@@ -259,6 +288,7 @@ mod tests {
             "Hello from OCR",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -309,7 +339,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(one_result.len(), 1);
@@ -332,7 +367,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 2);
@@ -362,7 +402,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -370,6 +410,7 @@ mod tests {
             "Hello from OCR 1",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -402,7 +443,7 @@ mod tests {
 
         // Insert remaining data
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -410,6 +451,7 @@ mod tests {
             "Hello from OCR 2",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -472,14 +514,36 @@ mod tests {
 
         // After inserting both audio transcriptions, let's check all audio entries
         let all_audio = db
-            .search_audio("", 100, 0, None, None, None, None, None)
+            .search_audio(
+                "",
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                SearchSort::Time,
+            )
             .await
             .unwrap();
         println!("All audio entries: {:?}", all_audio);
 
         // Then try specific search
         let audio_results = db
-            .search_audio("2", 100, 0, None, None, None, None, None)
+            .search_audio(
+                "2",
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                SearchSort::Time,
+            )
             .await
             .unwrap();
         println!("Audio results for '2': {:?}", audio_results);
@@ -505,7 +569,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         println!("OCR time range results: {:?}", ocr_results);
@@ -531,7 +600,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         println!("Full time range results: {:?}", results);
@@ -556,7 +630,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         println!("Limited time range results: {:?}", results);
@@ -585,7 +664,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 2);
@@ -609,7 +693,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 2);
@@ -627,7 +716,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -635,6 +724,7 @@ mod tests {
             "Hello from OCR 1",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -665,7 +755,7 @@ mod tests {
 
         // Insert remaining data
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -673,6 +763,7 @@ mod tests {
             "Hello from OCR 2",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -716,7 +807,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -744,7 +840,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -800,6 +900,67 @@ mod tests {
         assert_eq!(speaker.id, 1);
     }
 
+    #[tokio::test]
+    async fn test_voices_go_to_the_nearest_speaker() {
+        let db = setup_test_db().await;
+        let voice = |x: f32| {
+            let mut embedding = vec![0.0; 512];
+            embedding[0] = 1.0;
+            embedding[1] = x;
+            embedding
+        };
+
+        let unnamed = db.insert_speaker(&voice(0.0)).await.unwrap();
+        let me = db.enroll_speaker("me", &[voice(0.3)]).await.unwrap();
+        assert_eq!(me.name, "me");
+        // close enough to the enrolled speaker, closer to the unnamed one
+        let speaker = db.get_speaker_from_embedding(&voice(0.1)).await.unwrap();
+        assert_eq!(speaker.unwrap().id, unnamed.id);
+        let speaker = db.get_speaker_from_embedding(&voice(0.35)).await.unwrap();
+        assert_eq!(speaker.unwrap().id, me.id);
+
+        let again = db.enroll_speaker("me", &[voice(0.4)]).await.unwrap();
+        assert_eq!(again.id, me.id);
+
+        let boss = db
+            .enroll_existing_speaker(unnamed.id, "boss")
+            .await
+            .unwrap();
+        assert_eq!(boss.name, "boss");
+        let enrolled = db.list_enrolled_speakers().await.unwrap();
+        let names: Vec<&str> = enrolled.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["boss", "me"]);
+
+        db.unenroll_speaker(boss.id).await.unwrap();
+        assert_eq!(db.list_enrolled_speakers().await.unwrap().len(), 1);
+        assert!(matches!(
+            db.enroll_existing_speaker(999, "nobody").await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_enrolled_voices_must_be_closer() {
+        let db = setup_test_db().await;
+        let voice = |x: f32| {
+            let mut embedding = vec![0.0; 512];
+            embedding[0] = 1.0;
+            embedding[1] = x;
+            embedding
+        };
+
+        let me = db.enroll_speaker("me", &[voice(0.0)]).await.unwrap();
+        // a cosine distance of about 0.36, close enough to an unnamed voice
+        let speaker = db.get_speaker_from_embedding(&voice(1.2)).await.unwrap();
+        assert!(speaker.is_none());
+        let speaker = db.get_speaker_from_embedding(&voice(0.5)).await.unwrap();
+        assert_eq!(speaker.unwrap().id, me.id);
+
+        let unnamed = db.insert_speaker(&voice(0.0)).await.unwrap();
+        let speaker = db.get_speaker_from_embedding(&voice(1.2)).await.unwrap();
+        assert_eq!(speaker.unwrap().id, unnamed.id);
+    }
+
     #[tokio::test]
     async fn test_update_speaker_name() {
         let db = setup_test_db().await;
@@ -855,7 +1016,7 @@ mod tests {
             .unwrap();
 
         // Get unnamed speakers
-        let unnamed_speakers = db.get_unnamed_speakers(10, 0, None).await.unwrap();
+        let unnamed_speakers = db.get_unnamed_speakers(10, 0, None, None).await.unwrap();
 
         assert_eq!(unnamed_speakers.len(), 3, "Should find 3 unnamed speakers");
 
@@ -923,7 +1084,7 @@ mod tests {
 
         // Get unnamed speakers
         let unnamed_speakers = db
-            .get_unnamed_speakers(10, 0, Some(vec![speaker.id, 1, 2, 3]))
+            .get_unnamed_speakers(10, 0, Some(vec![speaker.id, 1, 2, 3]), None)
             .await
             .unwrap();
 
@@ -932,6 +1093,14 @@ mod tests {
         assert_eq!(unnamed_speakers[0].id, 3);
         assert_eq!(unnamed_speakers[1].id, 2);
         assert_eq!(unnamed_speakers[2].id, 1);
+
+        // the next page continues after the last speaker of the previous one
+        let next_page = db
+            .get_unnamed_speakers(2, 0, Some(vec![speaker.id, 1, 2, 3]), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(next_page.len(), 1);
+        assert_eq!(next_page[0].id, 1);
     }
 
     #[tokio::test]
@@ -1102,7 +1271,7 @@ mod tests {
 
         // Insert first frame with OCR
         let frame_id1 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1110,13 +1279,14 @@ mod tests {
             "Hello from frame 1",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
 
         // Insert second frame with OCR
         let frame_id2 = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1124,6 +1294,7 @@ mod tests {
             "Hello from frame 2",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -1147,7 +1318,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -1176,7 +1352,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -1205,7 +1386,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -1232,7 +1418,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
 
@@ -1283,7 +1473,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -1314,7 +1509,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -1338,7 +1538,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 0);
@@ -1362,7 +1567,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -1378,7 +1588,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id = db
-            .insert_frame("test_device", None, None, Some("test"), Some(""), false, Some(1.0))
+            .insert_frame("test_device", None, None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -1386,6 +1596,7 @@ mod tests {
             "Hello from OCR",
             "",
             Arc::new(OcrEngine::Tesseract),
+            false,
         )
         .await
         .unwrap();
@@ -1446,7 +1657,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 3, "Should count OCR, Audio, and UI results");
@@ -1468,7 +1683,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 1, "Should only count UI result with app filter");
@@ -1490,9 +1709,3073 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 0, "Should count zero results for non-matching query");
     }
+
+    #[test]
+    fn test_ocr_text_layout_parses_legacy_and_structured_json() {
+        let legacy = r#"[{"block_num":"1","conf":"91.5","page_num":"1","left":"10","height":"12","level":"5","text":"hello","par_num":"1","top":"20","word_num":"1","width":"40","line_num":"1"}]"#;
+        let layout = OcrTextLayout::from_text_json(legacy);
+        assert!(layout.lines.is_empty());
+        assert_eq!(layout.words.len(), 1);
+        assert_eq!(layout.words[0].text, "hello");
+        assert_eq!(layout.words[0].bbox.left, 10.0);
+        assert_eq!(layout.words[0].bbox.width, 40.0);
+
+        let round_trip = OcrTextLayout::from_text_json(&layout.to_text_json());
+        assert_eq!(round_trip, layout);
+
+        assert_eq!(OcrTextLayout::from_text_json(""), OcrTextLayout::default());
+    }
+
+    #[tokio::test]
+    async fn test_find_near_duplicate_frame() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let phash: i64 = 0b1010_1100;
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                None,
+                None,
+                None,
+                Some("app"),
+                None,
+                Some("window"),
+                None,
+                true,
+                Some(1.0),
+                Some(phash),
+            )
+            .await
+            .unwrap();
+
+        let found = db
+            .find_near_duplicate_frame("app", "window", phash ^ 0b1, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(found, Some(frame_id));
+
+        let too_far = db
+            .find_near_duplicate_frame("app", "window", phash ^ 0b111, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(too_far, None);
+
+        let other_window = db
+            .find_near_duplicate_frame("app", "other", phash, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(other_window, None);
+    }
+
+    #[tokio::test]
+    async fn test_video_segment_lookup_by_timestamp_and_frame() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::seconds(600);
+        let split = start + chrono::Duration::seconds(300);
+
+        let first = db
+            .insert_video_segment("monitor_1", "first.mp4", start, 5.0, "h264")
+            .await
+            .unwrap();
+        db.finish_video_segment(first, split).await.unwrap();
+        db.insert_video_segment("monitor_1", "second.mp4", split, 5.0, "h264")
+            .await
+            .unwrap();
+
+        let segment = db
+            .get_video_segment("monitor_1", start + chrono::Duration::seconds(90))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(segment.file_path, "first.mp4");
+        assert_eq!(
+            segment.offset_secs(start + chrono::Duration::seconds(90)),
+            90.0
+        );
+
+        // the open segment covers everything after it started
+        let latest = db
+            .get_video_segment("monitor_1", Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.file_path, "second.mp4");
+        assert!(latest.end_time.is_none());
+
+        assert!(db
+            .get_video_segment("monitor_1", start - chrono::Duration::seconds(1))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_video_segment("monitor_2", Utc::now())
+            .await
+            .unwrap()
+            .is_none());
+
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let timestamp = start + chrono::Duration::seconds(30);
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(timestamp),
+                None,
+                None,
+                Some("app"),
+                None,
+                Some("window"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        let (segment, frame_time) = db
+            .get_video_segment_for_frame(frame_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(segment.file_path, "first.mp4");
+        assert_eq!(segment.offset_secs(frame_time), 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_video_segments_and_frames_in_range() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::seconds(600);
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        let first = db
+            .insert_video_segment("monitor_1", "first.mp4", at(0), 5.0, "h264")
+            .await
+            .unwrap();
+        db.finish_video_segment(first, at(300)).await.unwrap();
+        db.insert_video_segment("monitor_1", "second.mp4", at(300), 5.0, "h264")
+            .await
+            .unwrap();
+
+        let segments = db
+            .get_video_segments_in_range("monitor_1", at(200), at(400))
+            .await
+            .unwrap();
+        let paths: Vec<&str> = segments.iter().map(|s| s.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["first.mp4", "second.mp4"]);
+
+        let segments = db
+            .get_video_segments_in_range("monitor_1", at(10), at(20))
+            .await
+            .unwrap();
+        assert_eq!(segments.len(), 1);
+
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for secs in [10, 20, 30] {
+            db.insert_frame(
+                "monitor_1",
+                Some(at(secs)),
+                None,
+                None,
+                Some("app"),
+                None,
+                Some("window"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let frames = db
+            .get_frames_in_range("monitor_1", at(15), at(30))
+            .await
+            .unwrap();
+        let offsets: Vec<i64> = frames.iter().map(|(_, offset, _)| *offset).collect();
+        assert_eq!(offsets, vec![1, 2]);
+        assert_eq!(frames[0].0, "chunk.mp4");
+        assert!(db
+            .get_frames_in_range("monitor_2", at(0), at(30))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scroll_document_round_trip() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let start = Utc::now() - chrono::Duration::seconds(60);
+        let mut frame_ids = Vec::new();
+        for secs in [0, 5, 10] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(start + chrono::Duration::seconds(secs)),
+                    None,
+                    None,
+                    Some("Safari"),
+                    None,
+                    Some("Article"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let id = db
+            .insert_scroll_document(
+                "Safari",
+                "Article",
+                "first paragraph\nsecond paragraph",
+                "document.png",
+                &frame_ids,
+            )
+            .await
+            .unwrap();
+
+        let document = db.get_scroll_document(id).await.unwrap().unwrap();
+        assert_eq!(document.frame_count, 3);
+        assert_eq!(document.start_time.timestamp(), start.timestamp());
+        assert_eq!((document.end_time - document.start_time).num_seconds(), 10);
+
+        let by_frame = db
+            .get_scroll_document_for_frame(frame_ids[1])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_frame.id, id);
+
+        let found = db
+            .search_scroll_documents("second", Some("Safari"), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(db
+            .search_scroll_documents("missing", None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .search_scroll_documents("", Some("Slack"), 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_frame_codes_are_deduplicated_and_searchable() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let start = Utc::now() - chrono::Duration::seconds(120);
+        let mut frame_ids = Vec::new();
+        for secs in [0, 5, 100] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(start + chrono::Duration::seconds(secs)),
+                    None,
+                    None,
+                    Some("Chrome"),
+                    None,
+                    Some("Security settings"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let payload = "otpauth://totp/example:me?secret=JBSWY3DPEHPK3PXP";
+        let window = chrono::Duration::seconds(60);
+        let first = db
+            .insert_frame_code(frame_ids[0], "qrcode", payload, start - window)
+            .await
+            .unwrap();
+        assert!(first.is_some());
+        // still on screen five seconds later
+        let repeated = db
+            .insert_frame_code(
+                frame_ids[1],
+                "qrcode",
+                payload,
+                start + chrono::Duration::seconds(5) - window,
+            )
+            .await
+            .unwrap();
+        assert!(repeated.is_none());
+        // shown again well after
+        let later = db
+            .insert_frame_code(
+                frame_ids[2],
+                "qrcode",
+                payload,
+                start + chrono::Duration::seconds(100) - window,
+            )
+            .await
+            .unwrap();
+        assert!(later.is_some());
+        db.insert_frame_code(frame_ids[1], "ean 13", "4006381333931", start - window)
+            .await
+            .unwrap();
+
+        let found = db
+            .search_frame_codes("otpauth", None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].frame_id, frame_ids[2]);
+        assert_eq!(found[0].app_name, "Chrome");
+        assert_eq!(found[0].window_name, "Security settings");
+
+        let barcodes = db
+            .search_frame_codes("", Some("ean 13"), None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(barcodes.len(), 1);
+        assert_eq!(barcodes[0].payload, "4006381333931");
+
+        let recent = db
+            .search_frame_codes(
+                "",
+                None,
+                Some(start + chrono::Duration::seconds(50)),
+                None,
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_table_round_trip() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                None,
+                Some("Excel"),
+                None,
+                Some("Budget.xlsx"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let cells = vec![
+            vec!["Item".to_string(), "Price".to_string()],
+            vec!["Rent".to_string(), "1200".to_string()],
+            vec!["Food".to_string(), String::new()],
+        ];
+        let id = db
+            .insert_table(frame_id, &cells, "Item,Price\nRent,1200\nFood,")
+            .await
+            .unwrap();
+
+        let tables = db.get_tables_for_frame(frame_id).await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].id, id);
+        assert_eq!(tables[0].row_count, 3);
+        assert_eq!(tables[0].column_count, 2);
+        assert_eq!(tables[0].cells, cells);
+        assert_eq!(tables[0].app_name, "Excel");
+
+        let found = db
+            .search_tables("Rent", Some("Excel"), None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(db
+            .search_tables("Rent", Some("Numbers"), None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_frames_by_embedding() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        // unit vectors along the first three axes
+        let axis = |index: usize| {
+            let mut embedding = vec![0.0f32; 512];
+            embedding[index] = 1.0;
+            embedding
+        };
+        let mut frame_ids = Vec::new();
+        for (index, app) in ["Terminal", "Figma", "Terminal"].iter().enumerate() {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    None,
+                    None,
+                    None,
+                    Some(app),
+                    None,
+                    Some("window"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_frame_embedding(frame_id, &axis(index))
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let mut query = axis(1);
+        query[0] = 0.1;
+        let matches = db
+            .search_frames_by_embedding(&query, 2, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].frame_id, frame_ids[1]);
+        assert_eq!(matches[0].app_name, "Figma");
+        assert!(matches[0].similarity > matches[1].similarity);
+
+        let terminal = db
+            .search_frames_by_embedding(&query, 3, Some("Terminal"), None, None)
+            .await
+            .unwrap();
+        assert_eq!(terminal.len(), 2);
+        assert_eq!(terminal[0].frame_id, frame_ids[0]);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_embedding_searches_look_past_the_nearest_neighbours() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let axis = |index: usize| {
+            let mut embedding = vec![0.0f32; 512];
+            embedding[index] = 1.0;
+            embedding
+        };
+        let mut terminal_id = 0;
+        for (index, app) in std::iter::repeat("Figma")
+            .take(10)
+            .chain(["Terminal"])
+            .enumerate()
+        {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    None,
+                    None,
+                    None,
+                    Some(app),
+                    None,
+                    Some("window"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            // the figma frames are the nearest
+            let mut embedding = axis(if app == "Figma" { 1 } else { 2 });
+            embedding[3] = index as f32 * 0.01;
+            db.insert_frame_embedding(frame_id, &embedding)
+                .await
+                .unwrap();
+            terminal_id = frame_id;
+        }
+
+        let terminal = db
+            .search_frames_by_embedding(&axis(1), 1, Some("Terminal"), None, None)
+            .await
+            .unwrap();
+        assert_eq!(terminal.len(), 1);
+        assert_eq!(terminal[0].frame_id, terminal_id);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_embeddings_replaces_earlier_ones() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                None,
+                Some("Terminal"),
+                None,
+                Some("window"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        let axis = |index: usize| {
+            let mut embedding = vec![0.0f32; 512];
+            embedding[index] = 1.0;
+            embedding
+        };
+
+        db.upsert_embeddings(VectorCollection::Frames, &[(frame_id, axis(0))])
+            .await
+            .unwrap();
+        db.upsert_embeddings(VectorCollection::Frames, &[(frame_id, axis(1))])
+            .await
+            .unwrap();
+        let matches = db
+            .search_frames_by_embedding(&axis(1), 10, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!((matches[0].similarity - 1.0).abs() < 1e-4);
+
+        // nothing to copy with sqlite-vec
+        db.build_vector_indexes().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transcription_embeddings() {
+        let db = setup_test_db().await;
+        let first_chunk_id = db.insert_audio_chunk("first.mp4").await.unwrap();
+        let second_chunk_id = db.insert_audio_chunk("second.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "test".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let transcriptions = [
+            (first_chunk_id, "deploy the release"),
+            (second_chunk_id, "lunch at noon"),
+            (second_chunk_id, " "),
+        ];
+        let mut transcription_ids = Vec::new();
+        for (offset, (audio_chunk_id, text)) in transcriptions.iter().enumerate() {
+            let id = db
+                .insert_audio_transcription(
+                    *audio_chunk_id,
+                    text,
+                    offset as i64,
+                    "",
+                    &device,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            transcription_ids.push(id);
+        }
+
+        // blank transcriptions are never embedded, newest first
+        let pending = db.get_transcriptions_without_embeddings(10).await.unwrap();
+        assert_eq!(
+            pending.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![transcription_ids[1], transcription_ids[0]]
+        );
+        // one the embedding model keeps failing on is given up on
+        for _ in 0..5 {
+            db.record_embedding_failure(VectorCollection::Transcriptions, transcription_ids[1])
+                .await
+                .unwrap();
+        }
+        let pending = db.get_transcriptions_without_embeddings(10).await.unwrap();
+        assert_eq!(
+            pending.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![transcription_ids[0]]
+        );
+
+        let axis = |index: usize| {
+            let mut embedding = vec![0.0f32; 8];
+            embedding[index] = 1.0;
+            embedding
+        };
+        db.upsert_embeddings(
+            VectorCollection::Transcriptions,
+            &[
+                (transcription_ids[0], axis(0)),
+                (transcription_ids[1], axis(1)),
+            ],
+        )
+        .await
+        .unwrap();
+        assert!(db
+            .get_transcriptions_without_embeddings(10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut query = axis(0);
+        query[1] = 0.2;
+        let matches = db
+            .search_similar_transcriptions(&query, 10, 0.5, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].transcription, "deploy the release");
+        assert_eq!(matches[0].device_type, DeviceType::Input);
+        let matches = db
+            .search_similar_transcriptions(&query, 10, 0.5, None, None, Some(&[999]))
+            .await
+            .unwrap();
+        assert!(matches.is_empty());
+
+        // rewriting the text drops its embeddings
+        db.update_audio_transcription(first_chunk_id, "deploy the release today")
+            .await
+            .unwrap();
+        let pending = db.get_transcriptions_without_embeddings(10).await.unwrap();
+        assert_eq!(
+            pending,
+            vec![(transcription_ids[0], "deploy the release today".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_returns_browser_tab() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                None,
+                Some("https://github.com/mediar-ai/screenpipe/pull/1"),
+                Some("Fix capture on resume"),
+                Some("Google Chrome"),
+                None,
+                Some("Fix capture on resume - Google Chrome"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "Fix capture on resume",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let results = db
+            .search(
+                "capture",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let SearchResult::OCR(ocr_result) = &results[0] else {
+            panic!("Expected OCR result");
+        };
+        assert_eq!(
+            ocr_result.browser_url.as_deref(),
+            Some("https://github.com/mediar-ai/screenpipe/pull/1")
+        );
+        assert_eq!(
+            ocr_result.browser_title.as_deref(),
+            Some("Fix capture on resume")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_by_window_metadata() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let geometry = WindowGeometry {
+            x: 120,
+            y: 40,
+            width: 1280,
+            height: 800,
+        };
+        for (app_name, app_id, window_name) in [
+            (
+                "Google Chrome",
+                "com.google.Chrome",
+                "Review pull request - Google Chrome",
+            ),
+            ("Safari", "com.apple.Safari", "pull the request"),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    None,
+                    Some(app_name),
+                    Some(app_id),
+                    Some(window_name),
+                    Some(&geometry),
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "diff", "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+        }
+
+        let search = |app_id: Option<&'static str>, window_name: Option<&'static str>| {
+            let db = &db;
+            async move {
+                db.search(
+                    "diff",
+                    ContentType::All,
+                    100,
+                    0,
+                    None,
+                    None,
+                    None,
+                    window_name,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    app_id,
+                    false,
+                    &SearchExclusions::default(),
+                    None,
+                    SearchSort::Time,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let results = search(Some("COM.GOOGLE.CHROME"), None).await;
+        assert_eq!(results.len(), 1);
+        let SearchResult::OCR(ocr_result) = &results[0] else {
+            panic!("Expected OCR result");
+        };
+        assert_eq!(ocr_result.app_id.as_deref(), Some("com.google.Chrome"));
+        assert_eq!(ocr_result.window_geometry, Some(geometry));
+        assert_eq!(ocr_result.focused, Some(true));
+
+        // several words are matched as a phrase
+        assert_eq!(search(None, Some("pull request")).await.len(), 1);
+        assert_eq!(search(None, Some("pull")).await.len(), 2);
+
+        // the total paginated over matches the results
+        for window_name in ["pull request", "pull"] {
+            let count = db
+                .count_search_results(
+                    "diff",
+                    ContentType::All,
+                    None,
+                    None,
+                    None,
+                    Some(window_name),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &SearchExclusions::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+            assert_eq!(count, search(None, Some(window_name)).await.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_can_exclude_low_quality_ocr() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for (text, low_quality) in [("invoice total", false), ("inv0ice t#tal", true)] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    None,
+                    Some("Preview"),
+                    None,
+                    Some("scan.pdf"),
+                    None,
+                    false,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                text,
+                "",
+                Arc::new(OcrEngine::Tesseract),
+                low_quality,
+            )
+            .await
+            .unwrap();
+        }
+
+        for (exclude_low_quality, expected) in [(false, 2), (true, 1)] {
+            let results = db
+                .search(
+                    "",
+                    ContentType::OCR,
+                    100,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    exclude_low_quality,
+                    &SearchExclusions::default(),
+                    None,
+                    SearchSort::Time,
+                )
+                .await
+                .unwrap();
+            assert_eq!(results.len(), expected);
+            if exclude_low_quality {
+                let SearchResult::OCR(ocr_result) = &results[0] else {
+                    panic!("Expected OCR result");
+                };
+                assert_eq!(ocr_result.ocr_text, "invoice total");
+                assert!(!ocr_result.low_quality);
+            }
+            let count = db
+                .count_search_results(
+                    "",
+                    ContentType::OCR,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    exclude_low_quality,
+                    &SearchExclusions::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+            assert_eq!(count, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frame_blobs_are_deleted_with_their_last_chunk() {
+        let db = setup_test_db().await;
+        let refs = [
+            ("a.webp", "chunk_1", 0, 1),
+            ("a.webp", "chunk_1", 1, 2),
+            ("a.webp", "chunk_2", 0, 3),
+            ("b.webp", "chunk_1", 2, 1),
+            // writing the same image again doesn't count twice
+            ("a.webp", "chunk_2", 0, 3),
+        ];
+        for (key, chunk, offset_index, expected) in refs {
+            let blob = format!("frame_blobs/{}", key);
+            let ref_count = db
+                .add_frame_blob_ref(key, &blob, chunk, offset_index)
+                .await
+                .unwrap();
+            assert_eq!(ref_count, expected);
+        }
+
+        let released = db.release_frame_blobs("chunk_1").await.unwrap();
+        assert_eq!(released, vec!["frame_blobs/b.webp".to_string()]);
+
+        let released = db.release_frame_blobs("chunk_2").await.unwrap();
+        assert_eq!(released, vec!["frame_blobs/a.webp".to_string()]);
+        assert!(db.release_frame_blobs("chunk_2").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purging_old_media_keeps_the_text() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::days(30);
+
+        for (chunk, age) in [("old.mp4", 40), ("new.mp4", 0)] {
+            db.insert_video_chunk(chunk, "test_device").await.unwrap();
+            let frame_id = db
+                .insert_frame("test_device", Some(now - chrono::Duration::days(age)), None, None, Some("test"), None, Some(""), None, false, Some(1.0), None)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, chunk, "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+        }
+
+        let purge = db
+            .get_media_to_purge(MediaKind::VideoChunk, cutoff, 10)
+            .await
+            .unwrap();
+        assert_eq!(purge.len(), 1);
+        assert_eq!(purge[0].file_path, "old.mp4");
+
+        db.mark_media_purged(MediaKind::VideoChunk, &[purge[0].id])
+            .await
+            .unwrap();
+        assert!(db
+            .get_media_to_purge(MediaKind::VideoChunk, cutoff, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.count_ocr_text_before(cutoff).await.unwrap(), 1);
+
+        // deleting the text keeps the frames
+        assert_eq!(db.delete_ocr_text_before(cutoff).await.unwrap(), 1);
+        assert_eq!(db.count_ocr_text_before(now).await.unwrap(), 0);
+        let frames: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM frames")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(frames, 2);
+    }
+
+    #[tokio::test]
+    async fn test_purging_old_audio_and_transcriptions() {
+        let db = setup_test_db().await;
+        let cutoff = Utc::now() - chrono::Duration::days(7);
+        let device = AudioDevice {
+            name: "test_device".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        let audio_chunk_id = db.insert_audio_chunk("old.mp4").await.unwrap();
+        db.insert_audio_transcription(audio_chunk_id, "old", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+        for table in ["audio_chunks", "audio_transcriptions"] {
+            sqlx::query(&format!("UPDATE {} SET timestamp = ?1", table))
+                .bind(Utc::now() - chrono::Duration::days(10))
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        db.insert_audio_chunk("new.mp4").await.unwrap();
+
+        let purge = db
+            .get_media_to_purge(MediaKind::AudioChunk, cutoff, 10)
+            .await
+            .unwrap();
+        assert_eq!(purge.len(), 1);
+        assert_eq!(purge[0].file_path, "old.mp4");
+
+        assert_eq!(db.count_transcriptions_before(cutoff).await.unwrap(), 1);
+        assert_eq!(db.delete_transcriptions_before(cutoff).await.unwrap(), 1);
+        assert_eq!(db.count_transcriptions_before(cutoff).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_media_to_encrypt_skips_encrypted_and_purged() {
+        let db = setup_test_db().await;
+        let before = Utc::now() + chrono::Duration::minutes(1);
+        for file in ["a.mp4", "b.mp4", "c.mp4"] {
+            db.insert_audio_chunk(file).await.unwrap();
+        }
+
+        let files = db
+            .get_media_to_encrypt(MediaKind::AudioChunk, before, 10)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 3);
+
+        db.mark_media_encrypted(MediaKind::AudioChunk, &[files[0].id])
+            .await
+            .unwrap();
+        db.mark_media_purged(MediaKind::AudioChunk, &[files[1].id])
+            .await
+            .unwrap();
+        let files = db
+            .get_media_to_encrypt(MediaKind::AudioChunk, before, 10)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_path, "c.mp4");
+    }
+
+    #[tokio::test]
+    async fn test_captures_after_sync_position() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for text in ["first", "second", "third"] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    None,
+                    Some("app"),
+                    None,
+                    Some("window"),
+                    None,
+                    false,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+        }
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        let transcription_id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "Hello from audio",
+                0,
+                "",
+                &AudioDevice {
+                    name: "test".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let texts = db.get_texts_after(0, 2).await.unwrap();
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts[0].text, "first");
+        assert_eq!(texts[0].device_name, "test_device");
+        assert_eq!(texts[0].app_name, "app");
+        let texts = db.get_texts_after(texts[1].id, 10).await.unwrap();
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].text, "third");
+
+        let transcriptions = db.get_transcriptions_after(0, 10).await.unwrap();
+        assert_eq!(transcriptions.len(), 1);
+        assert_eq!(transcriptions[0].id, transcription_id);
+        assert!(transcriptions[0].is_input_device);
+        assert!(db
+            .get_transcriptions_after(transcription_id, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_and_highlights_matches() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for text in [
+            "deployment failed, deploy again",
+            "lunch menu",
+            "the deploy went fine",
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    None,
+                    Some("app"),
+                    None,
+                    Some("window"),
+                    None,
+                    false,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+        }
+
+        let search = |sort| {
+            db.search(
+                "deploy*",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                sort,
+            )
+        };
+        let texts = |results: Vec<SearchResult>| {
+            results
+                .into_iter()
+                .map(|result| match result {
+                    SearchResult::OCR(ocr) => ocr.ocr_text,
+                    _ => panic!("expected OCR result"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // newest first by default, the prefix query matches both deploy and deployment
+        let results = search(SearchSort::Time).await.unwrap();
+        assert_eq!(
+            texts(results),
+            vec!["the deploy went fine", "deployment failed, deploy again"]
+        );
+
+        let results = search(SearchSort::Relevance).await.unwrap();
+        let SearchResult::OCR(best) = &results[0] else {
+            panic!("expected OCR result");
+        };
+        assert_eq!(
+            best.snippet.as_deref(),
+            Some("<mark>deployment</mark> failed, <mark>deploy</mark> again")
+        );
+        assert!(best.score.unwrap() > 0.0);
+        assert_eq!(texts(results)[0], "deployment failed, deploy again");
+    }
+
+    #[tokio::test]
+    async fn test_search_leaves_out_excluded_apps_and_windows() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for (app_name, window_name) in [
+            ("Slack", "#deploys"),
+            ("Slack", "#random"),
+            ("Discord", "#deploys"),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    None,
+                    None,
+                    None,
+                    Some(app_name),
+                    None,
+                    Some(window_name),
+                    None,
+                    false,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                "deploy failed",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+                false,
+            )
+            .await
+            .unwrap();
+        }
+
+        let exclude = SearchExclusions {
+            app_names: vec!["discord".to_string()],
+            window_names: vec!["random".to_string()],
+        };
+        let results = db
+            .search(
+                "deploy",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &exclude,
+                None,
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let SearchResult::OCR(ocr) = &results[0] else {
+            panic!("expected OCR result");
+        };
+        assert_eq!(ocr.app_name, "Slack");
+        assert_eq!(ocr.window_name, "#deploys");
+
+        let count = db
+            .count_search_results(
+                "deploy",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &exclude,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ui_search_filters_all_of_the_query_and_leaves_out_exclusions() {
+        let db = setup_test_db().await;
+        for (text, app, window) in [
+            ("deploy failed", "Slack", "#deploys"),
+            ("rollback done", "Slack", "#random"),
+            ("deploy failed", "Discord", "my_server"),
+            ("rollback done", "Discord", "myXserver"),
+        ] {
+            sqlx::query(
+                "INSERT INTO ui_monitoring (text_output, timestamp, app, window, initial_traversal_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(text)
+            .bind(Utc::now())
+            .bind(app)
+            .bind(window)
+            .bind(Utc::now())
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+        let search = |app_name: Option<&'static str>, exclude: SearchExclusions| {
+            let db = &db;
+            async move {
+                let results = db
+                    .search(
+                        "deploy OR rollback",
+                        ContentType::UI,
+                        10,
+                        0,
+                        None,
+                        None,
+                        app_name,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &exclude,
+                        None,
+                        SearchSort::Time,
+                    )
+                    .await
+                    .unwrap();
+                let count = db
+                    .count_search_results(
+                        "deploy OR rollback",
+                        ContentType::UI,
+                        None,
+                        None,
+                        app_name,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &exclude,
+                        None,
+                    )
+                    .await
+                    .unwrap();
+                let mut windows: Vec<String> = results
+                    .into_iter()
+                    .map(|result| match result {
+                        SearchResult::UI(ui) => ui.window_name,
+                        _ => panic!("expected UI result"),
+                    })
+                    .collect();
+                windows.sort();
+                assert_eq!(count, windows.len());
+                windows
+            }
+        };
+
+        assert_eq!(
+            search(Some("Slack"), SearchExclusions::default()).await,
+            ["#deploys", "#random"]
+        );
+        // `_` matches itself, not any character
+        let exclude = SearchExclusions {
+            app_names: vec!["slack".to_string()],
+            window_names: vec!["my_".to_string()],
+        };
+        assert_eq!(search(None, exclude).await, ["myXserver"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_frames_and_transcriptions_in_range() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        db.insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for (offset, text) in [
+            (1, "a".repeat(300)),
+            (2, String::new()),
+            (60, "later".into()),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(start + chrono::Duration::seconds(offset)),
+                    None,
+                    None,
+                    Some("Slack"),
+                    None,
+                    Some("general"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            if !text.is_empty() {
+                db.insert_ocr_text(frame_id, &text, "", Arc::new(OcrEngine::Tesseract), false)
+                    .await
+                    .unwrap();
+            }
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "hello",
+            0,
+            "",
+            &AudioDevice {
+                name: "microphone".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            Some(1.5),
+            Some(3.0),
+        )
+        .await
+        .unwrap();
+
+        let end = start + chrono::Duration::seconds(30);
+        let frames = db
+            .get_timeline_frames(start, 0, end, 10, 200)
+            .await
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].app_name, "Slack");
+        assert_eq!(frames[0].focused, Some(true));
+        assert_eq!(frames[0].snippet.as_deref().map(str::len), Some(200));
+        assert_eq!(frames[1].snippet, None);
+        let first = db.get_timeline_frames(start, 0, end, 1, 200).await.unwrap();
+        assert_eq!(first, frames[..1]);
+        // the frames at exactly `start` before `from_id` were on the page before
+        let second = frames[1].timestamp;
+        assert!(db
+            .get_timeline_frames(second, frames[1].frame_id + 1, end, 10, 200)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            db.get_timeline_frames(second, frames[1].frame_id, end, 10, 200)
+                .await
+                .unwrap(),
+            frames[1..]
+        );
+
+        let transcriptions = db
+            .get_timeline_transcriptions(start - chrono::Duration::seconds(5), 0, end, 10)
+            .await
+            .unwrap();
+        assert_eq!(transcriptions.len(), 1);
+        assert_eq!(transcriptions[0].transcription, "hello");
+        assert_eq!(transcriptions[0].start_time, Some(1.5));
+    }
+
+    #[tokio::test]
+    async fn test_app_usage_per_hour() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let start = Utc::now()
+            .duration_trunc(chrono::Duration::hours(1))
+            .unwrap();
+        for (offset, app_name, focused) in [
+            (0, "Slack", true),
+            (30, "Code", true),
+            // another monitor
+            (40, "Zoom", false),
+            (90, "Code", true),
+            // back after a long break
+            (3000, "Slack", true),
+        ] {
+            db.insert_frame(
+                "monitor_1",
+                Some(start + chrono::Duration::seconds(offset)),
+                None,
+                None,
+                Some(app_name),
+                None,
+                Some("window"),
+                None,
+                focused,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let usage = db
+            .get_app_usage(start, start + chrono::Duration::hours(1), 120.0)
+            .await
+            .unwrap();
+        let seconds: Vec<(&str, i64)> = usage
+            .iter()
+            .map(|row| (row.app_name.as_str(), row.seconds.round() as i64))
+            .collect();
+        assert_eq!(seconds, vec![("Code", 180), ("Slack", 30)]);
+        assert!(usage.iter().all(|row| row.hour == start));
+    }
+
+    #[tokio::test]
+    async fn test_window_sessions_and_daily_digests() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let start = Utc::now()
+            .duration_trunc(chrono::Duration::hours(1))
+            .unwrap();
+        for (offset, window_name) in [
+            (0, "report.pdf - Preview"),
+            (60, "report.pdf - Preview"),
+            (90, "notes.txt - TextEdit"),
+            // back after a long break
+            (1000, "notes.txt - TextEdit"),
+        ] {
+            db.insert_frame(
+                "monitor_1",
+                Some(start + chrono::Duration::seconds(offset)),
+                None,
+                None,
+                Some("Preview"),
+                None,
+                Some(window_name),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let sessions = db
+            .get_window_sessions(start, start + chrono::Duration::hours(1), 120.0)
+            .await
+            .unwrap();
+        let sessions: Vec<(&str, i64, i64)> = sessions
+            .iter()
+            .map(|session| {
+                (
+                    session.window_name.as_str(),
+                    (session.start_time - start).num_seconds(),
+                    session.frame_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![
+                ("report.pdf - Preview", 0, 2),
+                ("notes.txt - TextEdit", 90, 1),
+                ("notes.txt - TextEdit", 1000, 1),
+            ]
+        );
+
+        let date = start.date_naive();
+        assert_eq!(db.get_daily_digest(date).await.unwrap(), None);
+        db.upsert_daily_digest(date, "{}").await.unwrap();
+        db.upsert_daily_digest(date, r#"{"topics":[]}"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_daily_digest(date).await.unwrap().as_deref(),
+            Some(r#"{"topics":[]}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_and_deliveries() {
+        let db = setup_test_db().await;
+        let webhook = db
+            .insert_webhook(
+                "https://n8n.local/webhook/1",
+                Some("secret"),
+                "ocr",
+                "",
+                "invoice",
+            )
+            .await
+            .unwrap();
+        assert_eq!(webhook.secret.as_deref(), Some("secret"));
+        assert_eq!(db.list_webhooks().await.unwrap(), vec![webhook.clone()]);
+
+        db.insert_webhook_delivery(webhook.id, 7, "ocr", 1, false, Some(503), None, 12)
+            .await
+            .unwrap();
+        db.insert_webhook_delivery(webhook.id, 7, "ocr", 2, true, Some(200), None, 8)
+            .await
+            .unwrap();
+        let deliveries = db
+            .list_webhook_deliveries(webhook.id, None, 10, 0)
+            .await
+            .unwrap();
+        let attempts: Vec<(i64, bool)> = deliveries
+            .iter()
+            .map(|delivery| (delivery.attempt, delivery.success))
+            .collect();
+        assert_eq!(attempts, vec![(2, true), (1, false)]);
+        let older = db
+            .list_webhook_deliveries(webhook.id, Some(deliveries[0].id), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(older, deliveries[1..].to_vec());
+
+        assert!(db.delete_webhook(webhook.id).await.unwrap());
+        assert!(!db.delete_webhook(webhook.id).await.unwrap());
+        assert_eq!(db.get_webhook(webhook.id).await.unwrap(), None);
+        assert!(db
+            .list_webhook_deliveries(webhook.id, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_deliveries_are_capped() {
+        let db = setup_test_db().await;
+        let webhook = db
+            .insert_webhook("https://n8n.local/webhook/1", None, "", "", "")
+            .await
+            .unwrap();
+        for event_id in 0..MAX_WEBHOOK_DELIVERIES + 3 {
+            db.insert_webhook_delivery(webhook.id, event_id, "ocr", 1, true, Some(200), None, 1)
+                .await
+                .unwrap();
+        }
+        let deliveries = db
+            .list_webhook_deliveries(webhook.id, None, u32::MAX, 0)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len() as i64, MAX_WEBHOOK_DELIVERIES);
+        // the oldest are dropped
+        assert_eq!(deliveries.last().unwrap().event_id, 3);
+    }
+
+    #[tokio::test]
+    async fn test_alert_rules_and_events() {
+        let db = setup_test_db().await;
+        let errors = db
+            .insert_alert_rule(
+                "prod errors",
+                "production.*error",
+                "ocr",
+                "terminal",
+                300,
+                true,
+            )
+            .await
+            .unwrap();
+        let invoices = db
+            .insert_alert_rule("invoices", "invoice", "", "", 0, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.list_alert_rules().await.unwrap(),
+            vec![errors.clone(), invoices.clone()]
+        );
+
+        let now = Utc::now();
+        for (rule_id, offset) in [(errors.id, 0), (invoices.id, 1), (errors.id, 2)] {
+            db.insert_alert_event(
+                rule_id,
+                "ocr",
+                Some("Terminal"),
+                Some("zsh"),
+                "production error",
+                Some(1),
+                None,
+                now + chrono::Duration::seconds(offset),
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            db.list_alert_events(None, None, 10, 0).await.unwrap().len(),
+            3
+        );
+        let events = db
+            .list_alert_events(Some(errors.id), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].timestamp > events[1].timestamp);
+        let older = db
+            .list_alert_events(Some(errors.id), Some(events[0].id), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(older, events[1..].to_vec());
+
+        assert!(db.delete_alert_rule(errors.id).await.unwrap());
+        let events = db.list_alert_events(None, None, 10, 0).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].rule_id, invoices.id);
+    }
+
+    #[tokio::test]
+    async fn test_api_tokens() {
+        let db = setup_test_db().await;
+        let token = db
+            .insert_api_token("laptop", "abc123", "read-search,read-media")
+            .await
+            .unwrap();
+        assert!(db
+            .insert_api_token("copy", "abc123", "admin")
+            .await
+            .is_err());
+        assert_eq!(token.last_used_at, None);
+
+        db.touch_api_token(token.id).await.unwrap();
+        let used = db.get_api_token_by_hash("abc123").await.unwrap().unwrap();
+        let last_used_at = used.last_used_at.unwrap();
+        // at most once a minute
+        db.touch_api_token(token.id).await.unwrap();
+        let used = db.get_api_token_by_hash("abc123").await.unwrap().unwrap();
+        assert_eq!(used.last_used_at, Some(last_used_at));
+
+        assert_eq!(db.list_api_tokens().await.unwrap().len(), 1);
+        assert!(db.delete_api_token(token.id).await.unwrap());
+        assert!(!db.delete_api_token(token.id).await.unwrap());
+        assert_eq!(db.get_api_token_by_hash("abc123").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_annotations() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        db.insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let mut frame_ids = Vec::new();
+        for offset in [1, 2] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(start + chrono::Duration::seconds(offset)),
+                    None,
+                    None,
+                    Some("Zoom"),
+                    None,
+                    Some("standup"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                "roadmap",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+                false,
+            )
+            .await
+            .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let tags = vec!["meeting".to_string(), "acme".to_string()];
+        let annotation = db
+            .insert_annotation(
+                "vision",
+                Some(frame_ids[0]),
+                None,
+                None,
+                Some("kickoff"),
+                &tags,
+            )
+            .await
+            .unwrap();
+        assert_eq!(annotation.start_time, annotation.end_time);
+        assert_eq!(annotation.note.as_deref(), Some("kickoff"));
+        assert_eq!(annotation.tags.len(), 2);
+        // the frame's own tags have them too
+        let frame_tags = db
+            .get_tags(frame_ids[0], TagContentType::Vision)
+            .await
+            .unwrap();
+        assert!(frame_tags.contains(&"meeting".to_string()));
+
+        let range = db
+            .insert_annotation(
+                "range",
+                None,
+                Some(start),
+                Some(start + chrono::Duration::minutes(30)),
+                None,
+                &["meeting".to_string()],
+            )
+            .await
+            .unwrap();
+
+        let results = db
+            .search(
+                "roadmap",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                Some("Meeting"),
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(&results[0], SearchResult::OCR(ocr) if ocr.frame_id == frame_ids[0]));
+
+        let listed = db
+            .list_annotations(None, None, None, None, Some("meeting"), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+        let later = db
+            .list_annotations(
+                None,
+                None,
+                Some(start + chrono::Duration::minutes(10)),
+                None,
+                None,
+                None,
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(later, vec![range.clone()]);
+        // pages continue after the last annotation of the previous one
+        let after = db
+            .list_annotations(
+                None,
+                None,
+                None,
+                None,
+                Some("meeting"),
+                Some(listed[0].id),
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(after, listed[1..]);
+
+        assert!(db.delete_annotation(range.id).await.unwrap());
+        assert!(!db.delete_annotation(range.id).await.unwrap());
+        assert_eq!(db.get_annotation(range.id).await.unwrap(), None);
+
+        let missing = db
+            .insert_annotation("audio", Some(42), None, None, None, &tags)
+            .await;
+        assert!(matches!(missing, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_keyword_search_pages_after_the_last_match() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("keyword.mp4", "test_device")
+            .await
+            .unwrap();
+        // frames of several monitors are captured at the same time
+        let timestamp = Utc::now();
+        let mut frame_ids = Vec::new();
+        for _ in 0..3 {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(timestamp),
+                    Some(""),
+                    None,
+                    Some("test"),
+                    None,
+                    Some(""),
+                    None,
+                    false,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                "quarterly report",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+                false,
+            )
+            .await
+            .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let page = |after_frame_id| {
+            db.search_with_text_positions(
+                "report",
+                2,
+                0,
+                None,
+                None,
+                false,
+                Order::Descending,
+                None,
+                after_frame_id,
+            )
+        };
+        let first: Vec<i64> = page(None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|m| m.frame_id)
+            .collect();
+        assert_eq!(first, [frame_ids[2], frame_ids[1]]);
+        let second: Vec<i64> = page(Some(frame_ids[1]))
+            .await
+            .unwrap()
+            .iter()
+            .map(|m| m.frame_id)
+            .collect();
+        assert_eq!(second, [frame_ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_frames_are_kept() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::days(30);
+
+        let mut frame_ids = Vec::new();
+        for (chunk, age) in [("pinned.mp4", 50), ("old.mp4", 40)] {
+            db.insert_video_chunk(chunk, "test_device").await.unwrap();
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(now - chrono::Duration::days(age)),
+                    None,
+                    None,
+                    Some("test"),
+                    None,
+                    Some(""),
+                    None,
+                    false,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, chunk, "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let bookmark = db
+            .insert_bookmark(Some(frame_ids[0]), None, Some("contract"))
+            .await
+            .unwrap();
+        assert_eq!(bookmark.title.as_deref(), Some("contract"));
+        // a moment pins the frame shown then
+        let moment = db
+            .insert_bookmark(None, Some(now - chrono::Duration::days(45)), None)
+            .await
+            .unwrap();
+        assert_eq!(moment.frame_id, Some(frame_ids[0]));
+        assert!(matches!(
+            db.insert_bookmark(Some(42), None, None).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert_eq!(
+            db.list_bookmarks(None, None, None, 10, 0).await.unwrap(),
+            vec![moment.clone(), bookmark.clone()]
+        );
+        assert_eq!(
+            db.list_bookmarks(None, None, Some(moment.id), 10, 0)
+                .await
+                .unwrap(),
+            vec![bookmark.clone()]
+        );
+
+        let purge = db
+            .get_media_to_purge(MediaKind::VideoChunk, cutoff, 10)
+            .await
+            .unwrap();
+        assert_eq!(purge.len(), 1);
+        assert_eq!(purge[0].file_path, "old.mp4");
+        let evict = db
+            .get_media_to_evict(MediaKind::VideoChunk, now, 10)
+            .await
+            .unwrap();
+        assert_eq!(evict.len(), 1);
+        assert_eq!(evict[0].file_path, "old.mp4");
+        // pinned media can still move to cold storage
+        let tier = db
+            .get_media_to_tier(MediaKind::VideoChunk, now, 10)
+            .await
+            .unwrap();
+        assert_eq!(tier.len(), 2);
+        assert_eq!(db.count_ocr_text_before(cutoff).await.unwrap(), 1);
+
+        assert!(db.delete_bookmark(bookmark.id).await.unwrap());
+        assert!(db.delete_bookmark(moment.id).await.unwrap());
+        assert!(!db.delete_bookmark(moment.id).await.unwrap());
+        assert_eq!(
+            db.get_media_to_purge(MediaKind::VideoChunk, cutoff, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(db.delete_ocr_text_before(cutoff).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_data_matching_a_filter() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        for (chunk, app, age) in [
+            ("old.mp4", "zoom.us", 3),
+            ("old.mp4", "Slack", 3),
+            ("live.mp4", "zoom.us", 0),
+        ] {
+            if app != "Slack" {
+                db.insert_video_chunk(chunk, "monitor_1").await.unwrap();
+            }
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(now - chrono::Duration::days(age)),
+                    None,
+                    None,
+                    Some(app),
+                    None,
+                    Some(""),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, app, "", Arc::new(OcrEngine::Tesseract), false)
+                .await
+                .unwrap();
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(audio_chunk_id, "hello", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE audio_chunks SET timestamp = ?1")
+            .bind(now - chrono::Duration::days(3))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for app in ["zoom.us", "Slack"] {
+            db.insert_clipboard_entry(now - chrono::Duration::days(3), "copied", Some(app), None)
+                .await
+                .unwrap();
+        }
+        let count_frames = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM frames")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap()
+        };
+
+        let zoom = DataFilter {
+            app_name: Some("ZOOM".to_string()),
+            ..Default::default()
+        };
+        let (dry_run, media) = db.delete_data(&zoom, true).await.unwrap();
+        assert_eq!(dry_run.frames, 2);
+        assert_eq!(dry_run.ocr_text, 2);
+        assert_eq!(dry_run.transcriptions, 0);
+        assert_eq!(dry_run.clipboard, 1);
+        // old.mp4 still holds a frame of slack, the chunk still recorded to is queued
+        assert!(media.is_empty());
+        assert_eq!(count_frames().await, 3);
+
+        let (deleted, _) = db.delete_data(&zoom, false).await.unwrap();
+        assert_eq!(deleted, dry_run);
+        assert_eq!(count_frames().await, 1);
+        assert_eq!(
+            db.count_ocr_text_before(now + chrono::Duration::days(1))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let before = DataFilter {
+            end_time: Some(now - chrono::Duration::days(1)),
+            ..Default::default()
+        };
+        let (deleted, media) = db.delete_data(&before, false).await.unwrap();
+        assert_eq!(deleted.frames, 1);
+        assert_eq!(deleted.transcriptions, 1);
+        assert_eq!(deleted.clipboard, 1);
+        assert!(media
+            .iter()
+            .any(|(kind, file)| *kind == MediaKind::AudioChunk && file.file_path == "audio.mp4"));
+        assert!(media
+            .iter()
+            .any(|(kind, file)| *kind == MediaKind::VideoChunk && file.file_path == "old.mp4"));
+        assert_eq!(count_frames().await, 0);
+
+        // the queued chunk is deleted once it's closed
+        assert!(db.closed_chunks_to_delete().await.unwrap().is_empty());
+        db.insert_video_chunk("next.mp4", "monitor_1").await.unwrap();
+        let queued = db.closed_chunks_to_delete().await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].file_path, "live.mp4");
+    }
+
+    #[tokio::test]
+    async fn test_export_items_page_through_a_range() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for (app, age) in [("old", 3), ("zoom.us", 0), ("Slack", 0)] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(now - chrono::Duration::days(age)),
+                    None,
+                    None,
+                    Some(app),
+                    None,
+                    Some(""),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            if app != "Slack" {
+                db.insert_ocr_text(frame_id, app, "[]", Arc::new(OcrEngine::Tesseract), false)
+                    .await
+                    .unwrap();
+            }
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(audio_chunk_id, "hello", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let start = now - chrono::Duration::days(1);
+        let end = now + chrono::Duration::days(1);
+        assert_eq!(db.count_export_items(start, end).await.unwrap(), (2, 1));
+
+        let first = db.get_export_frames(start, end, 0, 1).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].app_name.as_deref(), Some("zoom.us"));
+        assert_eq!(first[0].text.as_deref(), Some("zoom.us"));
+        assert_eq!(first[0].text_json.as_deref(), Some("[]"));
+        assert_eq!(first[0].file_path, "chunk.mp4");
+        let rest = db
+            .get_export_frames(start, end, first[0].id, 10)
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        // frames not read yet are exported without text
+        assert_eq!(rest[0].text, None);
+        assert!(db
+            .get_export_frames(start, end, rest[0].id, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let transcriptions = db
+            .get_export_transcriptions(start, end, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(transcriptions.len(), 1);
+        assert_eq!(transcriptions[0].transcription, "hello");
+        assert_eq!(transcriptions[0].file_path, "audio.mp4");
+    }
+
+    #[tokio::test]
+    async fn test_calendar_events_tag_what_was_captured() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(audio_chunk_id, "hello", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let start = now - chrono::Duration::minutes(10);
+        let end = now + chrono::Duration::minutes(10);
+        let attendees = vec!["Alice".to_string()];
+        let tags = vec![
+            "meeting".to_string(),
+            "Standup".to_string(),
+            "Alice".to_string(),
+        ];
+        let upsert = |title: &'static str| {
+            db.upsert_calendar_event(
+                "standup-1",
+                title,
+                start,
+                end,
+                &attendees,
+                None,
+                Some(title),
+                &tags,
+            )
+        };
+        assert!(upsert("Standup").await.unwrap());
+        assert!(!upsert("Standup").await.unwrap());
+
+        let search = |tag: &'static str| {
+            db.search(
+                "hello",
+                ContentType::Audio,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                Some(tag),
+                SearchSort::Time,
+            )
+        };
+        assert_eq!(search("alice").await.unwrap().len(), 1);
+
+        let meetings = db
+            .list_calendar_events(Some("stand"), None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(meetings.len(), 1);
+        assert_eq!(meetings[0].attendees, attendees);
+        assert_eq!(meetings[0].transcription_count, 1);
+        assert_eq!(meetings[0].frame_count, 0);
+        assert_eq!(
+            db.get_calendar_event(meetings[0].id).await.unwrap(),
+            Some(meetings[0].clone())
+        );
+        assert!(db
+            .list_calendar_events(Some("retro"), None, None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // a changed meeting replaces its annotation
+        assert!(upsert("Daily standup").await.unwrap());
+        let annotations = db
+            .list_annotations(Some("range"), None, None, None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].note.as_deref(), Some("Daily standup"));
+
+        let deleted = db
+            .delete_calendar_events_except(start, end, &["other".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert!(db
+            .list_calendar_events(None, None, None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(search("alice").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_is_searched_alongside_ocr() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                Some(now - chrono::Duration::seconds(5)),
+                None,
+                None,
+                Some("Terminal"),
+                None,
+                Some("zsh"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "cargo build",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.latest_focused_window(now - chrono::Duration::seconds(10), now)
+                .await
+                .unwrap(),
+            Some((Some("Terminal".to_string()), Some("zsh".to_string())))
+        );
+        db.insert_clipboard_entry(now, "cargo build --release", Some("Terminal"), Some("zsh"))
+            .await
+            .unwrap();
+        db.insert_clipboard_entry(now, "lunch at noon", Some("Slack"), None)
+            .await
+            .unwrap();
+
+        let search = |query: &'static str, content_type: ContentType| {
+            db.search(
+                query,
+                content_type,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time,
+            )
+        };
+        let results = search("cargo", ContentType::All).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let clipboard = search("cargo", ContentType::Clipboard).await.unwrap();
+        assert_eq!(clipboard.len(), 1);
+        match &clipboard[0] {
+            SearchResult::Clipboard(entry) => {
+                assert_eq!(entry.text, "cargo build --release");
+                assert_eq!(entry.app_name.as_deref(), Some("Terminal"));
+                assert!(entry
+                    .snippet
+                    .as_deref()
+                    .unwrap()
+                    .contains("<mark>cargo</mark>"));
+            }
+            result => panic!("expected copied text, got {:?}", result),
+        }
+        assert!(search("cargo", ContentType::OCR)
+            .await
+            .unwrap()
+            .iter()
+            .all(|result| matches!(result, SearchResult::OCR(_))));
+
+        let exclude = SearchExclusions {
+            app_names: vec!["slack".to_string()],
+            window_names: Vec::new(),
+        };
+        let entries = db
+            .search_clipboard(
+                "",
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &exclude,
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        let count = db
+            .count_search_results(
+                "cargo",
+                ContentType::All,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_audio_device_events_in_range() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        let at = |seconds| start + chrono::Duration::seconds(seconds);
+
+        db.insert_audio_device_event(
+            "disconnected",
+            "AirPods (input)",
+            Some("MacBook Pro Microphone (input)"),
+            at(20),
+        )
+        .await
+        .unwrap();
+        db.insert_audio_device_event("disconnected", "USB Mic (input)", None, at(10))
+            .await
+            .unwrap();
+        db.insert_audio_device_event(
+            "reconnected",
+            "AirPods (input)",
+            Some("MacBook Pro Microphone (input)"),
+            at(60),
+        )
+        .await
+        .unwrap();
+
+        let events = db
+            .list_audio_device_events(at(0), at(60), 10)
+            .await
+            .unwrap();
+        let devices: Vec<&str> = events.iter().map(|e| e.device.as_str()).collect();
+        assert_eq!(devices, vec!["USB Mic (input)", "AirPods (input)"]);
+        assert_eq!(events[0].fallback_device, None);
+        assert_eq!(
+            events[1].fallback_device.as_deref(),
+            Some("MacBook Pro Microphone (input)")
+        );
+
+        let events = db
+            .list_audio_device_events(at(0), at(61), 10)
+            .await
+            .unwrap();
+        assert_eq!(events.last().unwrap().event, "reconnected");
+        assert_eq!(
+            db.list_audio_device_events(at(0), at(61), 1)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transcription_words_come_with_search_results() {
+        let db = setup_test_db().await;
+        let device = AudioDevice {
+            name: "test".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let audio_chunk_id = db.insert_audio_chunk("meeting.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "ship it",
+                0,
+                "",
+                &device,
+                None,
+                Some(12.0),
+                Some(13.0),
+            )
+            .await
+            .unwrap();
+        let untimed_chunk_id = db.insert_audio_chunk("call.mp4").await.unwrap();
+        db.insert_audio_transcription(untimed_chunk_id, "ship", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let words = vec![
+            TranscriptWord {
+                word: "ship".to_string(),
+                start: 12.1,
+                end: 12.4,
+            },
+            TranscriptWord {
+                word: "it".to_string(),
+                start: 12.5,
+                end: 12.7,
+            },
+        ];
+        db.set_audio_transcription_words(id, &words).await.unwrap();
+
+        let results = db
+            .search_audio(
+                "ship",
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            if result.audio_chunk_id == audio_chunk_id {
+                assert_eq!(result.words, words);
+            } else {
+                assert!(result.words.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translations_are_searched_with_transcriptions() {
+        let db = setup_test_db().await;
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let audio_chunk_id = db.insert_audio_chunk("reunion.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "on livre vendredi",
+                0,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let english_chunk_id = db.insert_audio_chunk("standup.mp4").await.unwrap();
+        let english_id = db
+            .insert_audio_transcription(
+                english_chunk_id,
+                "we ship on friday",
+                0,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let pending = db
+            .get_transcriptions_without_translation("en", 10)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 2);
+        db.set_audio_transcription_translation(id, "we ship on friday", "en")
+            .await
+            .unwrap();
+        db.set_audio_transcription_translation(english_id, "", "en")
+            .await
+            .unwrap();
+        assert!(db
+            .get_transcriptions_without_translation("en", 10)
+            .await
+            .unwrap()
+            .is_empty());
+        // translated again to another language
+        assert_eq!(
+            db.get_transcriptions_without_translation("de", 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // one the llm fails on comes after the others, then not at all
+        db.record_translation_failure(english_id).await.unwrap();
+        let pending = db
+            .get_transcriptions_without_translation("de", 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            pending.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [id, english_id]
+        );
+        for _ in 0..4 {
+            db.record_translation_failure(english_id).await.unwrap();
+        }
+        let pending = db
+            .get_transcriptions_without_translation("de", 10)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let results = db
+            .search_audio(
+                "friday",
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                SearchSort::Time,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            if result.audio_chunk_id == audio_chunk_id {
+                assert_eq!(result.transcription, "on livre vendredi");
+                assert_eq!(result.translation.as_deref(), Some("we ship on friday"));
+            } else {
+                assert_eq!(result.translation, None);
+            }
+        }
+
+        // an edited transcription is translated again
+        db.update_audio_transcription(english_chunk_id, "we ship on monday")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_transcriptions_without_translation("en", 10)
+                .await
+                .unwrap(),
+            vec![(english_id, "we ship on monday".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detected_meetings_extend_while_ongoing() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::minutes(30);
+        let tags = vec!["meeting".to_string(), "zoom.us".to_string()];
+        let detect = |end_minutes: i64, app_name: &'static str| {
+            db.upsert_detected_meeting(
+                app_name,
+                "Zoom Meeting",
+                start + chrono::Duration::minutes(5),
+                start + chrono::Duration::minutes(end_minutes),
+                Some("Zoom Meeting"),
+                &tags,
+            )
+        };
+        assert!(detect(10, "zoom.us").await.unwrap());
+        assert!(!detect(10, "zoom.us").await.unwrap());
+        assert!(detect(20, "zoom.us").await.unwrap());
+        assert!(detect(20, "Microsoft Teams").await.unwrap());
+
+        let detected = db
+            .list_calendar_events(None, Some("detected"), None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(detected.len(), 2);
+        let zoom = detected
+            .iter()
+            .find(|meeting| meeting.app_name.as_deref() == Some("zoom.us"))
+            .unwrap();
+        assert_eq!(zoom.end_time, start + chrono::Duration::minutes(20));
+        assert!(db
+            .list_calendar_events(None, Some("calendar"), None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(detect(25, "zoom.us").await.unwrap());
+
+        // the window renamed or seen a bit earlier is the same meeting as first seen
+        assert!(db
+            .upsert_detected_meeting(
+                "zoom.us",
+                "Zoom",
+                start,
+                start + chrono::Duration::minutes(28),
+                Some("Zoom"),
+                &tags,
+            )
+            .await
+            .unwrap());
+        let zoom = db.get_calendar_event(zoom.id).await.unwrap().unwrap();
+        assert_eq!(zoom.title, "Zoom Meeting");
+        assert_eq!(zoom.start_time, start + chrono::Duration::minutes(5));
+        assert_eq!(zoom.end_time, start + chrono::Duration::minutes(28));
+
+        // a calendar sync leaves detected meetings alone
+        let removed = db
+            .delete_calendar_events_except(start, Utc::now(), &[])
+            .await
+            .unwrap();
+        assert_eq!(removed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_meeting_notes_of_meetings_over() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let detect = |end_minutes: i64| {
+            db.upsert_detected_meeting(
+                "zoom.us",
+                "Zoom Meeting",
+                now - chrono::Duration::minutes(60),
+                now - chrono::Duration::minutes(end_minutes),
+                None,
+                &[],
+            )
+        };
+        detect(30).await.unwrap();
+        assert!(db
+            .get_meetings_without_notes("detected", now - chrono::Duration::minutes(40), &[], 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let pending = db
+            .get_meetings_without_notes("detected", now - chrono::Duration::minutes(20), &[], 10)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let action_items = vec![ActionItem {
+            task: "Write the release notes".to_string(),
+            owner: Some("Alice".to_string()),
+            due: None,
+        }];
+        let notes = db
+            .upsert_meeting_notes(
+                pending[0].id,
+                "Planned the launch.",
+                &["Ship on Monday".to_string()],
+                &action_items,
+                "ollama",
+                "llama3.2",
+            )
+            .await
+            .unwrap();
+        assert_eq!(notes.action_items, action_items);
+        assert_eq!(
+            db.get_meeting_notes(pending[0].id).await.unwrap(),
+            Some(notes)
+        );
+        assert!(db
+            .get_meetings_without_notes("detected", now, &[], 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // notes are kept when the meeting is renamed or ends sooner
+        db.upsert_detected_meeting(
+            "zoom.us",
+            "Launch planning",
+            now - chrono::Duration::minutes(50),
+            now - chrono::Duration::minutes(35),
+            None,
+            &[],
+        )
+        .await
+        .unwrap();
+        assert!(db.get_meeting_notes(pending[0].id).await.unwrap().is_some());
+
+        // a meeting that went on gets new notes
+        detect(10).await.unwrap();
+        assert_eq!(db.get_meeting_notes(pending[0].id).await.unwrap(), None);
+        assert!(db
+            .get_meetings_without_notes("detected", now, &[pending[0].id], 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_audio_transcription_keeps_versions() {
+        let db = setup_test_db().await;
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "helo wrld",
+                0,
+                "WhisperTiny",
+                &AudioDevice {
+                    name: "test".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                None,
+                Some(0.0),
+                Some(2.0),
+            )
+            .await
+            .unwrap();
+        db.set_audio_transcription_translation(id, "hallo welt", "de")
+            .await
+            .unwrap();
+        let words = vec![
+            TranscriptWord {
+                word: "hello".to_string(),
+                start: 0.0,
+                end: 0.8,
+            },
+            TranscriptWord {
+                word: "world".to_string(),
+                start: 1.0,
+                end: 1.6,
+            },
+        ];
+
+        assert!(db
+            .replace_audio_transcription(id, "hello world", "WhisperLargeV3", &words)
+            .await
+            .unwrap());
+        assert!(!db
+            .replace_audio_transcription(id + 1, "nothing", "WhisperLargeV3", &[])
+            .await
+            .unwrap());
+
+        let now = Utc::now();
+        let current = db
+            .get_export_transcriptions(
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+                0,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].transcription, "hello world");
+        assert_eq!(current[0].transcription_engine, "WhisperLargeV3");
+        let pending = db
+            .get_transcriptions_without_translation("de", 10)
+            .await
+            .unwrap();
+        assert_eq!(pending, vec![(id, "hello world".to_string())]);
+
+        let versions = db.get_audio_transcription_versions(id).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].audio_transcription_id, id);
+        assert_eq!(versions[0].transcription, "helo wrld");
+        assert_eq!(versions[0].transcription_engine, "WhisperTiny");
+        assert!(versions[0].words.is_empty());
+
+        // a second pass keeps the first one's words
+        db.replace_audio_transcription(id, "hello, world", "Deepgram", &[])
+            .await
+            .unwrap();
+        let versions = db.get_audio_transcription_versions(id).await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].transcription, "hello world");
+        assert_eq!(versions[1].words, words);
+    }
+
+    #[tokio::test]
+    async fn test_imported_audio_keeps_its_recording_time() {
+        let db = setup_test_db().await;
+        let recorded_at = Utc::now() - chrono::Duration::days(30);
+        assert!(!db.has_audio_chunk("imported_call.m4a").await.unwrap());
+        let device = AudioDevice {
+            name: "imported".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let transcriptions: Vec<ImportedTranscription> = [0.0, 30.0]
+            .into_iter()
+            .enumerate()
+            .map(|(offset_index, start)| ImportedTranscription {
+                transcription: format!("part {}", offset_index),
+                offset_index: offset_index as i64,
+                start_time: start,
+                end_time: start + 30.0,
+                timestamp: recorded_at + chrono::Duration::seconds(start as i64),
+                words: vec![TranscriptWord {
+                    word: "part".to_string(),
+                    start,
+                    end: start + 0.5,
+                }],
+            })
+            .collect();
+        db.insert_imported_audio(
+            "imported_call.m4a",
+            recorded_at,
+            "WhisperLargeV3Turbo",
+            &device,
+            &transcriptions,
+        )
+        .await
+        .unwrap();
+        assert!(db.has_audio_chunk("imported_call.m4a").await.unwrap());
+
+        let now = Utc::now();
+        assert!(db
+            .get_export_transcriptions(now - chrono::Duration::days(1), now, 0, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let imported = db
+            .get_export_transcriptions(
+                recorded_at - chrono::Duration::minutes(1),
+                recorded_at + chrono::Duration::minutes(1),
+                0,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].transcription, "part 0");
+        assert_eq!(imported[0].timestamp, recorded_at);
+        assert_eq!(
+            imported[1].timestamp,
+            recorded_at + chrono::Duration::seconds(30)
+        );
+        assert_eq!(imported[1].file_path, "imported_call.m4a");
+    }
+
+    #[tokio::test]
+    async fn test_imported_frames_are_stored_with_their_text() {
+        let db = setup_test_db().await;
+        let taken_at = Utc::now() - chrono::Duration::days(30);
+        let frames = [
+            ImportedFrame {
+                timestamp: taken_at,
+                phash: Some(42),
+                ocr: Some(("quarterly plan".to_string(), "[]".to_string())),
+            },
+            ImportedFrame {
+                timestamp: taken_at + chrono::Duration::seconds(1),
+                phash: None,
+                ocr: None,
+            },
+        ];
+        db.insert_imported_frames(
+            "imported_screenshot",
+            "imported",
+            Some("Screenshot.png"),
+            &OcrEngine::Tesseract,
+            &frames,
+        )
+        .await
+        .unwrap();
+        assert!(db.has_video_chunk("imported_screenshot").await.unwrap());
+
+        let stored = db
+            .get_export_frames(
+                taken_at - chrono::Duration::minutes(1),
+                taken_at + chrono::Duration::minutes(1),
+                0,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].timestamp, taken_at);
+        assert_eq!(stored[0].text.as_deref(), Some("quarterly plan"));
+        assert_eq!(stored[0].window_name.as_deref(), Some("Screenshot.png"));
+        assert_eq!(
+            (stored[1].offset_index, stored[1].text.as_deref()),
+            (1, None)
+        );
+    }
 }
@@ -0,0 +1,128 @@
+use crate::send_event;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Name of the events carrying a `CaptureEvent`
+pub const CAPTURE_EVENT: &str = "capture";
+
+/// Text read from a window, once it is stored.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OcrCapture {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+    pub focused: bool,
+    pub text: String,
+}
+
+/// A transcription, once it is stored.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TranscriptCapture {
+    pub audio_chunk_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub is_input_device: bool,
+    pub speaker_id: Option<i64>,
+    pub transcription: String,
+}
+
+/// The focused app or window of a monitor changed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AppFocusChange {
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub previous_app_name: Option<String>,
+    pub previous_window_name: Option<String>,
+}
+
+/// Something captured, sent as it is stored so clients don't have to poll the database.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureEvent {
+    Ocr(OcrCapture),
+    Transcript(TranscriptCapture),
+    AppFocus(AppFocusChange),
+}
+
+impl CaptureEvent {
+    /// `ocr`, `transcript` or `app_focus`, as serialized in `type`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CaptureEvent::Ocr(_) => "ocr",
+            CaptureEvent::Transcript(_) => "transcript",
+            CaptureEvent::AppFocus(_) => "app_focus",
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            CaptureEvent::Ocr(ocr) => ocr.timestamp,
+            CaptureEvent::Transcript(transcript) => transcript.timestamp,
+            CaptureEvent::AppFocus(focus) => focus.timestamp,
+        }
+    }
+
+    /// App on screen, none for transcriptions.
+    pub fn app_name(&self) -> Option<&str> {
+        match self {
+            CaptureEvent::Ocr(ocr) => Some(&ocr.app_name),
+            CaptureEvent::Transcript(_) => None,
+            CaptureEvent::AppFocus(focus) => Some(&focus.app_name),
+        }
+    }
+
+    /// The text captured, the window title of a focus change.
+    pub fn text(&self) -> &str {
+        match self {
+            CaptureEvent::Ocr(ocr) => &ocr.text,
+            CaptureEvent::Transcript(transcript) => &transcript.transcription,
+            CaptureEvent::AppFocus(focus) => &focus.window_name,
+        }
+    }
+}
+
+pub fn send_capture_event(event: CaptureEvent) {
+    if let Err(e) = send_event(CAPTURE_EVENT, event) {
+        tracing::debug!("failed to send capture event: {}", e);
+    }
+}
+
+/// Turns the focused windows seen on each monitor into focus changes.
+#[derive(Default)]
+pub struct FocusTracker {
+    focused: HashMap<String, (String, String)>,
+}
+
+impl FocusTracker {
+    /// The change, when `app_name` and `window_name` aren't what `device_name` had focused.
+    pub fn observe(
+        &mut self,
+        device_name: &str,
+        app_name: &str,
+        window_name: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Option<AppFocusChange> {
+        let current = (app_name.to_string(), window_name.to_string());
+        let previous = self
+            .focused
+            .insert(device_name.to_string(), current.clone());
+        if previous.as_ref() == Some(&current) {
+            return None;
+        }
+        let (previous_app_name, previous_window_name) = previous.unzip();
+        Some(AppFocusChange {
+            timestamp,
+            device_name: device_name.to_string(),
+            app_name: current.0,
+            window_name: current.1,
+            previous_app_name,
+            previous_window_name,
+        })
+    }
+}
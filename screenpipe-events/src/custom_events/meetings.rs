@@ -4,7 +4,7 @@ use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::time::{Duration, Instant};
 
 const MEETING_APPS: &[&str] = &["zoom", "teams", "meet", "webex", "skype", "slack"];
@@ -100,14 +100,13 @@ pub async fn poll_meetings_events() -> Result<()> {
                     .any(|keyword| window_ocr.window_name.to_lowercase().contains(keyword));
 
                 // Method 3: UI Element Analysis
-                let has_meeting_ui = window_ocr.text_json.iter().any(|elem| {
-                    elem.values().any(|text| {
-                        text.contains("Mute")
-                            || text.contains("Camera")
-                            || text.contains("Share Screen")
-                            || text.contains("Participants")
-                            || text.contains("Recording")
-                    })
+                let has_meeting_ui = window_ocr.lines.iter().any(|line| {
+                    let text = &line.text;
+                    text.contains("Mute")
+                        || text.contains("Camera")
+                        || text.contains("Share Screen")
+                        || text.contains("Participants")
+                        || text.contains("Recording")
                 });
 
                 if (is_meeting_app && (has_meeting_keywords || has_meeting_ui))
@@ -214,12 +213,18 @@ struct WindowOcr {
     pub window_name: String,
     pub app_name: String,
     pub text: String,
-    pub text_json: Vec<HashMap<String, String>>, // Change this line
+    #[serde(default)]
+    pub lines: Vec<OcrLine>,
     pub focused: bool,
     pub confidence: f64,
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct OcrLine {
+    pub text: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct UIFrame {
     pub window: String,
@@ -1 +1,2 @@
+pub mod captures;
 pub mod meetings;
@@ -4,4 +4,5 @@ pub use events_manager::*;
 
 mod custom_events;
 
+pub use custom_events::captures::*;
 pub use custom_events::meetings::*;
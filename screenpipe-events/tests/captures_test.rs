@@ -0,0 +1,44 @@
+use chrono::{TimeZone, Utc};
+use screenpipe_events::{CaptureEvent, FocusTracker, TranscriptCapture};
+
+#[test]
+fn test_focus_tracker_reports_changes_per_monitor() {
+    let at = Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap();
+    let mut tracker = FocusTracker::default();
+
+    let change = tracker
+        .observe("monitor_1", "Slack", "#general", at)
+        .unwrap();
+    assert_eq!(change.previous_app_name, None);
+    assert!(tracker
+        .observe("monitor_1", "Slack", "#general", at)
+        .is_none());
+    // another monitor keeps its own focus
+    assert!(tracker
+        .observe("monitor_2", "Code", "main.rs", at)
+        .is_some());
+
+    let change = tracker
+        .observe("monitor_1", "Slack", "#random", at)
+        .unwrap();
+    assert_eq!(change.app_name, "Slack");
+    assert_eq!(change.window_name, "#random");
+    assert_eq!(change.previous_window_name.as_deref(), Some("#general"));
+}
+
+#[test]
+fn test_capture_events_are_tagged_by_type() {
+    let event = CaptureEvent::Transcript(TranscriptCapture {
+        audio_chunk_id: 7,
+        timestamp: Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap(),
+        device_name: "MacBook Pro Microphone".to_string(),
+        is_input_device: true,
+        speaker_id: Some(1),
+        transcription: "send the invoice".to_string(),
+    });
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["type"], "transcript");
+    assert_eq!(json["audio_chunk_id"], 7);
+    assert_eq!(event.kind(), "transcript");
+    assert_eq!(serde_json::from_value::<CaptureEvent>(json).unwrap(), event);
+}
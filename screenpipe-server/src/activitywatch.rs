@@ -0,0 +1,265 @@
+use crate::analytics::MAX_FOCUS_GAP_SECS;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{Client, StatusCode, Url};
+use screenpipe_db::{DatabaseManager, WindowSession};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use sysinfo::{System, SystemExt};
+use tracing::{debug, info, warn};
+
+const CLIENT: &str = "screenpipe";
+pub const WINDOW_BUCKET_TYPE: &str = "currentwindow";
+pub const WEB_BUCKET_TYPE: &str = "web.tab.current";
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+// Events posted to aw-server per request
+const EVENT_BATCH: usize = 500;
+// How far back a sync goes, for empty buckets and after aw-server was away
+const MAX_SYNC_DAYS: i64 = 7;
+
+/// An event of an ActivityWatch bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AwEvent {
+    pub timestamp: DateTime<Utc>,
+    /// Seconds
+    pub duration: f64,
+    pub data: Value,
+}
+
+impl AwEvent {
+    pub fn end(&self) -> DateTime<Utc> {
+        self.timestamp + Duration::milliseconds((self.duration * 1000.0) as i64)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AwBucket {
+    pub id: String,
+    pub created: DateTime<Utc>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub client: String,
+    pub hostname: String,
+    pub events: Vec<AwEvent>,
+}
+
+/// Host the buckets are recorded on, aw-server's watchers use the same.
+pub fn hostname() -> String {
+    System::new()
+        .host_name()
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Named apart from aw-watcher-window's bucket, dashboards find it by its type and host.
+pub fn window_bucket_id(hostname: &str) -> String {
+    format!("{}-window_{}", CLIENT, hostname)
+}
+
+pub fn web_bucket_id(hostname: &str) -> String {
+    format!("{}-web_{}", CLIENT, hostname)
+}
+
+fn window_event(session: &WindowSession) -> AwEvent {
+    AwEvent {
+        timestamp: session.start_time,
+        duration: session_seconds(session),
+        data: json!({"app": session.app_name, "title": session.window_name}),
+    }
+}
+
+/// `None` outside browsers.
+fn web_event(session: &WindowSession) -> Option<AwEvent> {
+    let url = session
+        .browser_url
+        .as_deref()
+        .filter(|url| !url.is_empty())?;
+    Some(AwEvent {
+        timestamp: session.start_time,
+        duration: session_seconds(session),
+        data: json!({
+            "url": url,
+            "title": session.window_name,
+            "audible": false,
+            "incognito": false
+        }),
+    })
+}
+
+fn session_seconds(session: &WindowSession) -> f64 {
+    (session.end_time - session.start_time).num_milliseconds() as f64 / 1000.0
+}
+
+/// The focused windows of `sessions` as aw-watcher-window events, and the browser tabs
+/// among them as aw-watcher-web events.
+pub fn buckets(
+    sessions: &[WindowSession],
+    hostname: &str,
+    created: DateTime<Utc>,
+) -> Vec<AwBucket> {
+    let bucket = |id: String, kind: &str, events: Vec<AwEvent>| AwBucket {
+        id,
+        created,
+        kind: kind.to_string(),
+        client: CLIENT.to_string(),
+        hostname: hostname.to_string(),
+        events,
+    };
+    vec![
+        bucket(
+            window_bucket_id(hostname),
+            WINDOW_BUCKET_TYPE,
+            sessions.iter().map(window_event).collect(),
+        ),
+        bucket(
+            web_bucket_id(hostname),
+            WEB_BUCKET_TYPE,
+            sessions.iter().filter_map(web_event).collect(),
+        ),
+    ]
+}
+
+/// What aw-server imports, from its web UI or `POST /api/0/import`.
+pub fn import_document(buckets: &[AwBucket]) -> Value {
+    let buckets: Map<String, Value> = buckets
+        .iter()
+        .map(|bucket| (bucket.id.clone(), json!(bucket)))
+        .collect();
+    json!({ "buckets": buckets })
+}
+
+/// The buckets of the windows focused in `start..end`.
+pub async fn export_buckets(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<AwBucket>> {
+    let sessions = db
+        .get_window_sessions(start, end, MAX_FOCUS_GAP_SECS)
+        .await?;
+    Ok(buckets(&sessions, &hostname(), Utc::now()))
+}
+
+/// Where a sync continues: past the last event of the bucket, at most `MAX_SYNC_DAYS`
+/// back.
+pub fn sync_start(last: Option<&AwEvent>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let earliest = now - Duration::days(MAX_SYNC_DAYS);
+    match last {
+        // the last frame of the event starts a new one otherwise
+        Some(event) => (event.end() + Duration::milliseconds(1)).max(earliest),
+        None => earliest,
+    }
+}
+
+/// Keeps the buckets of a local aw-server up to date with the windows focused.
+pub struct ActivityWatchBridge {
+    db: Arc<DatabaseManager>,
+    client: Client,
+    url: Url,
+    hostname: String,
+}
+
+impl ActivityWatchBridge {
+    /// `url` of aw-server, e.g. http://localhost:5600.
+    pub fn new(db: Arc<DatabaseManager>, url: &str, hostname: &str) -> Result<Self> {
+        let url = Url::parse(url.trim()).map_err(|e| anyhow!("invalid url: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!("url must be http or https"));
+        }
+        Ok(Self {
+            db,
+            client: Client::new(),
+            url,
+            hostname: hostname.to_string(),
+        })
+    }
+
+    fn api(&self, path: &str) -> Result<Url> {
+        Ok(self.url.join(&format!("api/0/{}", path))?)
+    }
+
+    /// Posts the windows focused since the last sync. Returns how many events were posted.
+    pub async fn sync(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut posted = 0;
+        // empty buckets are created first, both are filled from the same sessions
+        let mut starts = Vec::new();
+        for bucket in buckets(&[], &self.hostname, now) {
+            self.create_bucket(&bucket).await?;
+            let start = sync_start(self.last_event(&bucket.id).await?.as_ref(), now);
+            starts.push((bucket.id, start));
+        }
+        let Some(earliest) = starts.iter().map(|(_, start)| *start).min() else {
+            return Ok(0);
+        };
+
+        let sessions = self
+            .db
+            .get_window_sessions(earliest, now, MAX_FOCUS_GAP_SECS)
+            .await?;
+        for (bucket, (id, start)) in buckets(&sessions, &self.hostname, now)
+            .into_iter()
+            .zip(starts)
+        {
+            let events: Vec<AwEvent> = bucket
+                .events
+                .into_iter()
+                .filter(|event| event.timestamp >= start)
+                .collect();
+            for batch in events.chunks(EVENT_BATCH) {
+                self.client
+                    .post(self.api(&format!("buckets/{}/events", id))?)
+                    .json(batch)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            posted += events.len();
+        }
+        Ok(posted)
+    }
+
+    async fn create_bucket(&self, bucket: &AwBucket) -> Result<()> {
+        let response = self
+            .client
+            .post(self.api(&format!("buckets/{}", bucket.id))?)
+            .json(&json!({
+                "client": bucket.client,
+                "type": bucket.kind,
+                "hostname": bucket.hostname
+            }))
+            .send()
+            .await?;
+        // 304 when it exists already
+        if response.status() != StatusCode::NOT_MODIFIED {
+            response.error_for_status()?;
+        }
+        Ok(())
+    }
+
+    async fn last_event(&self, id: &str) -> Result<Option<AwEvent>> {
+        let events: Vec<AwEvent> = self
+            .client
+            .get(self.api(&format!("buckets/{}/events", id))?)
+            .query(&[("limit", "1")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(events.into_iter().next())
+    }
+}
+
+/// Syncs the focused windows to aw-server every few minutes.
+pub async fn run_activitywatch_bridge(bridge: Arc<ActivityWatchBridge>) {
+    info!("sending focus events to activitywatch at {}", bridge.url);
+    loop {
+        match bridge.sync().await {
+            Ok(0) => {}
+            Ok(posted) => debug!("sent {} events to activitywatch", posted),
+            Err(e) => warn!("failed to send events to activitywatch: {}", e),
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
@@ -1,18 +1,10 @@
 use anyhow::Result;
 use image::DynamicImage;
 use regex::Regex;
-use screenpipe_db::DatabaseManager;
+use screenpipe_db::{DatabaseManager, VectorCollection};
+use screenpipe_vision::create_ocr_provider;
 use screenpipe_vision::utils::{compare_with_previous_image, OcrEngine};
 
-#[cfg(target_os = "macos")]
-use screenpipe_vision::perform_ocr_apple;
-
-#[cfg(target_os = "windows")]
-use screenpipe_vision::perform_ocr_windows;
-
-#[allow(unused)]
-use screenpipe_vision::perform_ocr_tesseract;
-
 use serde_json::json;
 use std::path::Path;
 use std::path::PathBuf;
@@ -166,7 +158,7 @@ pub async fn handle_index_command(
             previous_image = Some(frame.clone());
 
             // Use specified OCR engine or fall back to platform default
-            let engine = match ocr_engine {
+            let engine: OcrEngine = match ocr_engine {
                 Some(ref cli_engine) => cli_engine.clone().into(),
                 None => {
                     #[cfg(target_os = "macos")]
@@ -180,18 +172,17 @@ pub async fn handle_index_command(
             };
 
             // Do OCR processing directly
-            let (text, _, confidence): (String, String, Option<f64>) = match engine.clone() {
-                #[cfg(target_os = "macos")]
-                OcrEngine::AppleNative => perform_ocr_apple(frame, &[]),
-                #[cfg(target_os = "windows")]
-                OcrEngine::WindowsNative => perform_ocr_windows(&frame).await.unwrap(),
-                _ => {
-                    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-                    {
-                        perform_ocr_tesseract(&frame, Vec::new());
+            let (text, confidence) = match create_ocr_provider(&engine) {
+                Ok(provider) => match provider.recognize(frame, &[]).await {
+                    Ok(result) => (result.text, result.confidence),
+                    Err(e) => {
+                        warn!("ocr failed for frame {}: {}", frame_counter, e);
+                        ("".to_string(), None)
                     }
-                    warn!("unsupported ocr engine");
-                    ("".to_string(), "".to_string(), None)
+                },
+                Err(e) => {
+                    warn!("unsupported ocr engine: {}", e);
+                    ("".to_string(), None)
                 }
             };
 
@@ -205,7 +196,7 @@ pub async fn handle_index_command(
                     Ok(emb) => {
                         debug!("generated embedding for frame {}", frame_ids[idx]);
                         if let Err(e) = db
-                            .insert_embeddings(frame_ids[idx], serde_json::to_string(&emb)?)
+                            .upsert_embeddings(VectorCollection::OcrText, &[(frame_ids[idx], emb)])
                             .await
                         {
                             error!("error batch inserting embeddings: {}", e);
@@ -227,6 +218,7 @@ pub async fn handle_index_command(
                     &text,
                     "{}", // empty json
                     ocr_engine.clone().unwrap().into(),
+                    false,
                 )
                 .await
             {
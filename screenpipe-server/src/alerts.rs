@@ -0,0 +1,264 @@
+use crate::capture_events::{CaptureFilter, CaptureLog};
+use anyhow::Result;
+use oasgen::OaSchema;
+use regex::{Regex, RegexBuilder};
+use screenpipe_db::{AlertEvent, AlertRule, DatabaseManager};
+use screenpipe_events::{send_event, CaptureEvent};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast::error::RecvError, Mutex};
+use tracing::{info, warn};
+
+/// Name of the events sent when a rule matches, carrying the `AlertEvent`
+pub const ALERT_EVENT: &str = "alert";
+const DEFAULT_COOLDOWN_SECS: i64 = 300;
+const MAX_PATTERN_CHARS: usize = 1000;
+// Longer matches are cut before they are stored and shown
+const MAX_MATCH_CHARS: usize = 200;
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct AlertRuleRequest {
+    pub name: String,
+    /// Regex matched against OCR text, transcriptions and window titles, ignoring case,
+    /// e.g. `production.*error`
+    pub pattern: String,
+    /// Comma separated event types to check: ocr, transcript, app_focus. All by default
+    #[serde(default)]
+    pub types: Option<String>,
+    /// Comma separated parts of app names, e.g. "terminal"
+    #[serde(default)]
+    pub apps: Option<String>,
+    /// Seconds after an alert the rule stays quiet, 300 by default
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: i64,
+    /// Show alerts as desktop notifications, on by default
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+}
+
+fn default_cooldown_secs() -> i64 {
+    DEFAULT_COOLDOWN_SECS
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+impl AlertRuleRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.pattern.chars().count() > MAX_PATTERN_CHARS {
+            return Err(format!(
+                "pattern must be at most {} characters",
+                MAX_PATTERN_CHARS
+            ));
+        }
+        compile_pattern(&self.pattern)?;
+        if self.cooldown_secs < 0 {
+            return Err("cooldown_secs must not be negative".to_string());
+        }
+        CaptureFilter::parse(self.types.as_deref(), self.apps.as_deref(), None)?;
+        Ok(())
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    if pattern.trim().is_empty() {
+        return Err("pattern is required".to_string());
+    }
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("invalid pattern: {}", e))
+}
+
+/// A rule, ready to be checked against capture events.
+pub struct CompiledRule {
+    pub rule: AlertRule,
+    regex: Regex,
+    filter: CaptureFilter,
+    last_alert: Option<Instant>,
+}
+
+impl CompiledRule {
+    pub fn new(rule: AlertRule) -> Result<Self, String> {
+        Ok(Self {
+            regex: compile_pattern(&rule.pattern)?,
+            filter: CaptureFilter::parse(Some(&rule.types), Some(&rule.apps), None)?,
+            rule,
+            last_alert: None,
+        })
+    }
+
+    /// The text of `event` the rule matches, if it does.
+    pub fn find(&self, event: &CaptureEvent) -> Option<String> {
+        if !self.filter.matches(event) {
+            return None;
+        }
+        let found = self.regex.find(event.text())?;
+        Some(found.as_str().chars().take(MAX_MATCH_CHARS).collect())
+    }
+
+    /// Whether the rule is out of its cooldown at `now`, which then starts a new one.
+    pub fn try_alert(&mut self, now: Instant) -> bool {
+        let cooldown = Duration::from_secs(self.rule.cooldown_secs.max(0) as u64);
+        if self
+            .last_alert
+            .is_some_and(|last_alert| now.duration_since(last_alert) < cooldown)
+        {
+            return false;
+        }
+        self.last_alert = Some(now);
+        true
+    }
+}
+
+/// The alert rules, raising alerts on the capture events they match.
+pub struct AlertRules {
+    db: Arc<DatabaseManager>,
+    rules: Mutex<Vec<CompiledRule>>,
+}
+
+impl AlertRules {
+    pub fn new(db: Arc<DatabaseManager>) -> Self {
+        Self {
+            db,
+            rules: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reads the rules from the database.
+    pub async fn reload(&self) -> Result<()> {
+        let mut rules = Vec::new();
+        for rule in self.db.list_alert_rules().await? {
+            let id = rule.id;
+            match CompiledRule::new(rule) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => warn!("skipping alert rule {}: {}", id, e),
+            }
+        }
+        *self.rules.lock().await = rules;
+        Ok(())
+    }
+
+    pub async fn create(&self, request: &AlertRuleRequest) -> Result<AlertRule> {
+        let rule = self
+            .db
+            .insert_alert_rule(
+                request.name.trim(),
+                &request.pattern,
+                request.types.as_deref().unwrap_or_default().trim(),
+                request.apps.as_deref().unwrap_or_default().trim(),
+                request.cooldown_secs,
+                request.notify,
+            )
+            .await?;
+        let compiled = CompiledRule::new(rule.clone()).map_err(anyhow::Error::msg)?;
+        info!("alert rule {} added: {}", rule.id, rule.name);
+        self.rules.lock().await.push(compiled);
+        Ok(rule)
+    }
+
+    /// Whether the rule existed.
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let deleted = self.db.delete_alert_rule(id).await?;
+        self.rules.lock().await.retain(|rule| rule.rule.id != id);
+        Ok(deleted)
+    }
+
+    /// The rules `event` raises an alert for, with the text they matched.
+    pub async fn check(&self, event: &CaptureEvent, now: Instant) -> Vec<(AlertRule, String)> {
+        let mut rules = self.rules.lock().await;
+        rules
+            .iter_mut()
+            .filter_map(|rule| {
+                let matched = rule.find(event)?;
+                rule.try_alert(now).then(|| (rule.rule.clone(), matched))
+            })
+            .collect()
+    }
+
+    async fn raise(&self, rule: &AlertRule, matched_text: &str, event: &CaptureEvent) {
+        let (window_name, frame_id, audio_chunk_id) = match event {
+            CaptureEvent::Ocr(ocr) => (Some(ocr.window_name.as_str()), Some(ocr.frame_id), None),
+            CaptureEvent::Transcript(transcript) => (None, None, Some(transcript.audio_chunk_id)),
+            CaptureEvent::AppFocus(focus) => (Some(focus.window_name.as_str()), None, None),
+        };
+        info!("alert rule {} matched: {}", rule.name, matched_text);
+        match self
+            .db
+            .insert_alert_event(
+                rule.id,
+                event.kind(),
+                event.app_name(),
+                window_name,
+                matched_text,
+                frame_id,
+                audio_chunk_id,
+                event.timestamp(),
+            )
+            .await
+        {
+            Ok(id) => {
+                let _ = send_event(
+                    ALERT_EVENT,
+                    AlertEvent {
+                        id,
+                        rule_id: rule.id,
+                        event_type: event.kind().to_string(),
+                        app_name: event.app_name().map(str::to_string),
+                        window_name: window_name.map(str::to_string),
+                        matched_text: matched_text.to_string(),
+                        frame_id,
+                        audio_chunk_id,
+                        timestamp: event.timestamp(),
+                    },
+                );
+            }
+            Err(e) => warn!("failed to store the alert of rule {}: {}", rule.id, e),
+        }
+
+        if rule.notify {
+            let summary = format!("screenpipe: {}", rule.name);
+            let body = match event.app_name() {
+                Some(app_name) => format!("{} in {}", matched_text, app_name),
+                None => matched_text.to_string(),
+            };
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = show_notification(&summary, &body) {
+                    warn!("failed to show the alert notification: {}", e);
+                }
+            });
+        }
+    }
+}
+
+pub(crate) fn show_notification(summary: &str, body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("screenpipe")
+        .summary(summary)
+        .body(body)
+        .show()?;
+    Ok(())
+}
+
+/// Raises the alerts of the events of `capture_log`.
+pub async fn run_alerts(alerts: Arc<AlertRules>, capture_log: Arc<CaptureLog>) {
+    let (_, mut receiver) = capture_log.resume(None);
+    loop {
+        let event = match receiver.recv().await {
+            Ok((_, event)) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("alert rules skipped {} capture events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        for (rule, matched_text) in alerts.check(&event, Instant::now()).await {
+            alerts.raise(&rule, &matched_text, &event).await;
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{AppUsage, DatabaseManager, InputActivity};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Longest stretch until the next focused frame still counted as use, past it the
+/// computer is taken to be idle or locked
+pub const MAX_FOCUS_GAP_SECS: f64 = 120.0;
+/// Longest `range` accepted
+pub const MAX_ANALYTICS_DAYS: i64 = 90;
+/// Key presses and clicks within a minute from which on it counts as active work
+pub const ACTIVE_MIN_INPUTS: i64 = 10;
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct AppAnalyticsQuery {
+    /// How far back from `end`, e.g. `12h`, `7d` or `4w`
+    #[serde(default = "default_range")]
+    pub range: String,
+    /// End of the range, now by default
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+}
+
+fn default_range() -> String {
+    "7d".to_string()
+}
+
+impl AppAnalyticsQuery {
+    /// Start and end of the range.
+    pub fn bounds(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let duration = parse_range(&self.range)?;
+        let end = self.end.unwrap_or_else(Utc::now);
+        Ok((end - duration, end))
+    }
+}
+
+/// `12h`, `7d` or `4w` as a duration, up to `MAX_ANALYTICS_DAYS`.
+pub fn parse_range(range: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid range '{}', expected e.g. 12h, 7d or 4w", range);
+    let range = range.trim();
+    let unit = range.chars().last().ok_or_else(invalid)?;
+    let count: i64 = range[..range.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let hours = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        _ => return Err(invalid()),
+    };
+    if count <= 0 || count > MAX_ANALYTICS_DAYS * 24 / hours {
+        return Err(format!(
+            "range must be positive and at most {} days",
+            MAX_ANALYTICS_DAYS
+        ));
+    }
+    Ok(Duration::hours(count * hours))
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HourTime {
+    /// Start of the hour, UTC
+    pub hour: DateTime<Utc>,
+    pub seconds: f64,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DayTime {
+    /// UTC day
+    pub date: NaiveDate,
+    pub seconds: f64,
+    /// Hours the app was used in, oldest first
+    pub hours: Vec<HourTime>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowTime {
+    pub window_name: String,
+    pub seconds: f64,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppTime {
+    pub app_name: String,
+    pub seconds: f64,
+    /// Most used first
+    pub windows: Vec<WindowTime>,
+    /// Days the app was used on, oldest first
+    pub days: Vec<DayTime>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct AppAnalytics {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Most used first
+    pub apps: Vec<AppTime>,
+}
+
+#[derive(Default)]
+struct AppTotals {
+    seconds: f64,
+    windows: HashMap<String, f64>,
+    hours: BTreeMap<DateTime<Utc>, f64>,
+}
+
+/// Groups the hourly usage of every app and window by app, window and day.
+pub fn summarize_app_usage(usage: Vec<AppUsage>) -> Vec<AppTime> {
+    let mut apps: HashMap<String, AppTotals> = HashMap::new();
+    for row in usage {
+        let totals = apps.entry(row.app_name).or_default();
+        totals.seconds += row.seconds;
+        *totals.windows.entry(row.window_name).or_default() += row.seconds;
+        *totals.hours.entry(row.hour).or_default() += row.seconds;
+    }
+
+    let mut apps: Vec<AppTime> = apps
+        .into_iter()
+        .map(|(app_name, totals)| {
+            let mut windows: Vec<WindowTime> = totals
+                .windows
+                .into_iter()
+                .map(|(window_name, seconds)| WindowTime {
+                    window_name,
+                    seconds,
+                })
+                .collect();
+            windows.sort_by(|a, b| {
+                b.seconds
+                    .total_cmp(&a.seconds)
+                    .then_with(|| a.window_name.cmp(&b.window_name))
+            });
+
+            let mut days: Vec<DayTime> = Vec::new();
+            for (hour, seconds) in totals.hours {
+                let date = hour.date_naive();
+                match days.last_mut() {
+                    Some(day) if day.date == date => {
+                        day.seconds += seconds;
+                        day.hours.push(HourTime { hour, seconds });
+                    }
+                    _ => days.push(DayTime {
+                        date,
+                        seconds,
+                        hours: vec![HourTime { hour, seconds }],
+                    }),
+                }
+            }
+
+            AppTime {
+                app_name,
+                seconds: totals.seconds,
+                windows,
+                days,
+            }
+        })
+        .collect();
+    apps.sort_by(|a, b| {
+        b.seconds
+            .total_cmp(&a.seconds)
+            .then_with(|| a.app_name.cmp(&b.app_name))
+    });
+    apps
+}
+
+pub async fn app_analytics(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<AppAnalytics> {
+    let usage = db.get_app_usage(start, end, MAX_FOCUS_GAP_SECS).await?;
+    Ok(AppAnalytics {
+        start,
+        end,
+        apps: summarize_app_usage(usage),
+    })
+}
+
+/// How a minute was spent, from the input seen in it.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityLevel {
+    /// Typing or clicking
+    Active,
+    /// Scrolling and moving the mouse with little typing, e.g. reading or watching
+    Passive,
+    /// No input at all while it was recorded
+    Idle,
+}
+
+pub fn activity_level(activity: &InputActivity) -> ActivityLevel {
+    if activity.key_presses + activity.mouse_clicks >= ACTIVE_MIN_INPUTS {
+        ActivityLevel::Active
+    } else if activity.key_presses + activity.mouse_clicks + activity.mouse_moves + activity.scrolls
+        > 0
+    {
+        ActivityLevel::Passive
+    } else {
+        ActivityLevel::Idle
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActivityMinute {
+    /// Start of the minute, UTC
+    pub minute: DateTime<Utc>,
+    pub key_presses: i64,
+    pub mouse_clicks: i64,
+    pub mouse_moves: i64,
+    pub scrolls: i64,
+    pub level: ActivityLevel,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActivityAnalytics {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub active_minutes: i64,
+    pub passive_minutes: i64,
+    /// Minutes without input while it was recorded
+    pub idle_minutes: i64,
+    /// Minutes input wasn't recorded in, e.g. while screenpipe was off
+    pub unrecorded_minutes: i64,
+    /// Minutes with input, oldest first
+    pub minutes: Vec<ActivityMinute>,
+}
+
+/// Levels of the minutes recorded in `start..end`, and how many minutes of the range were
+/// spent at each.
+pub fn summarize_input_activity(
+    activity: Vec<InputActivity>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ActivityAnalytics {
+    let recorded = activity.len() as i64;
+    let minutes: Vec<ActivityMinute> = activity
+        .into_iter()
+        .map(|activity| ActivityMinute {
+            level: activity_level(&activity),
+            minute: activity.minute,
+            key_presses: activity.key_presses,
+            mouse_clicks: activity.mouse_clicks,
+            mouse_moves: activity.mouse_moves,
+            scrolls: activity.scrolls,
+        })
+        .collect();
+    let count = |level: ActivityLevel| {
+        minutes
+            .iter()
+            .filter(|minute| minute.level == level)
+            .count() as i64
+    };
+    ActivityAnalytics {
+        start,
+        end,
+        active_minutes: count(ActivityLevel::Active),
+        passive_minutes: count(ActivityLevel::Passive),
+        idle_minutes: count(ActivityLevel::Idle),
+        unrecorded_minutes: ((end - start).num_minutes() - recorded).max(0),
+        minutes: minutes
+            .into_iter()
+            .filter(|minute| minute.level != ActivityLevel::Idle)
+            .collect(),
+    }
+}
+
+pub async fn activity_analytics(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<ActivityAnalytics> {
+    let activity = db.get_input_activity(start, end).await?;
+    Ok(summarize_input_activity(activity, start, end))
+}
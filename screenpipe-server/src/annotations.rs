@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use serde::Deserialize;
+
+const MAX_TAG_CHARS: usize = 100;
+const MAX_NOTE_CHARS: usize = 10_000;
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct AnnotationRequest {
+    /// `vision` for a frame, `audio` for an audio chunk or `range` for a time range
+    pub content_type: String,
+    /// The frame or audio chunk, for `vision` and `audio`
+    #[serde(default)]
+    pub id: Option<i64>,
+    /// Start and end of a `range`
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    /// e.g. ["meeting", "acme"]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Free text, e.g. "quarterly review with the design team"
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl AnnotationRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.content_type.as_str() {
+            "vision" | "audio" => {
+                if self.id.is_none() {
+                    return Err(format!("id is required for {}", self.content_type));
+                }
+            }
+            "range" => {
+                if self.id.is_some() {
+                    return Err("a range takes start_time and end_time, not an id".to_string());
+                }
+                match (self.start_time, self.end_time) {
+                    (Some(start_time), Some(end_time)) if end_time < start_time => {
+                        return Err("end_time must not be before start_time".to_string());
+                    }
+                    (Some(_), Some(_)) => {}
+                    _ => return Err("start_time and end_time are required for a range".to_string()),
+                }
+            }
+            _ => return Err("content_type must be vision, audio or range".to_string()),
+        }
+
+        for tag in &self.tags {
+            let tag = tag.trim();
+            if tag.is_empty() {
+                return Err("tags must not be empty".to_string());
+            }
+            // stored tags are read back comma separated
+            if tag.contains(',') {
+                return Err(format!("tag '{}' must not contain a comma", tag));
+            }
+            if tag.chars().count() > MAX_TAG_CHARS {
+                return Err(format!("tags must be at most {} characters", MAX_TAG_CHARS));
+            }
+        }
+        if self
+            .note
+            .as_deref()
+            .is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS)
+        {
+            return Err(format!(
+                "note must be at most {} characters",
+                MAX_NOTE_CHARS
+            ));
+        }
+        if self.tags().is_empty() && self.note().is_none() {
+            return Err("a tag or a note is required".to_string());
+        }
+        Ok(())
+    }
+
+    /// The tags trimmed, without duplicates.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags.iter().map(|tag| tag.trim()) {
+            if !tag.is_empty() && !tags.iter().any(|seen| seen.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        }
+        tags
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note
+            .as_deref()
+            .map(str::trim)
+            .filter(|note| !note.is_empty())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::hybrid_search::{blend_results, includes_audio, includes_ocr, SEMANTIC_MAX_DISTANCE};
+use crate::llm::Llm;
+use crate::summarize::{build_prompt, cited_references, collect_sources, SummarySource};
+use crate::text_embeds::generate_embedding;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{ContentType, DatabaseManager, SearchExclusions, SearchResult, SearchSort};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Most search results an answer is looked for in
+pub const MAX_ASK_SOURCES: u32 = 100;
+const DEFAULT_ASK_SOURCES: u32 = 30;
+const MAX_QUESTION_CHARS: usize = 2000;
+// Words of the question searched for
+const MAX_KEYWORDS: usize = 12;
+const SYSTEM_PROMPT: &str = "You answer questions about what a person saw on their screen and \
+                             heard in their meetings, from numbered sources of their history. \
+                             Cite the sources the answer comes from like [2] or [2, 5]. The \
+                             sources are OCR and speech to text so expect typos. If they don't \
+                             answer the question, say so instead of guessing.";
+
+// Words of a question that say nothing about what it is looking for
+const QUESTION_STOPWORDS: &[&str] = &[
+    "about", "after", "and", "any", "are", "before", "can", "could", "did", "does", "for", "from",
+    "had", "has", "have", "how", "into", "mine", "our", "said", "saw", "see", "seen", "that",
+    "the", "their", "them", "then", "there", "they", "this", "was", "were", "what", "when",
+    "where", "which", "who", "whom", "why", "with", "you", "your",
+];
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct AskRequest {
+    pub question: String,
+    /// Only look at what was captured from then on. "today", "yesterday", "this week",
+    /// "last week" and "last hour" in the question narrow the range when it's not set
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    /// OCR text and transcriptions by default
+    #[serde(default = "default_content_type")]
+    pub content_type: ContentType,
+    /// Most search results the answer is looked for in, up to 100
+    #[serde(default = "default_ask_limit")]
+    pub limit: u32,
+}
+
+fn default_content_type() -> ContentType {
+    ContentType::AudioAndOcr
+}
+
+fn default_ask_limit() -> u32 {
+    DEFAULT_ASK_SOURCES
+}
+
+impl AskRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let question = self.question.trim();
+        if question.is_empty() {
+            return Err("question is required".to_string());
+        }
+        if question.chars().count() > MAX_QUESTION_CHARS {
+            return Err(format!(
+                "question must be at most {} characters",
+                MAX_QUESTION_CHARS
+            ));
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            if end <= start {
+                return Err("end_time must be after start_time".to_string());
+            }
+        }
+        if self.limit == 0 || self.limit > MAX_ASK_SOURCES {
+            return Err(format!("limit must be between 1 and {}", MAX_ASK_SOURCES));
+        }
+        Ok(())
+    }
+
+    /// The range searched, the one set or the one the question mentions.
+    pub fn time_range(&self, now: DateTime<Utc>) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        if self.start_time.is_some() || self.end_time.is_some() {
+            return (self.start_time, self.end_time);
+        }
+        match question_time_range(&self.question, now) {
+            Some((start, end)) => (Some(start), Some(end)),
+            None => (None, None),
+        }
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct Answer {
+    pub answer: String,
+    pub provider: String,
+    pub model: String,
+    /// The sources cited in `answer`, oldest first, with links to their frames
+    pub sources: Vec<SummarySource>,
+    /// Sources the answer was looked for in
+    pub source_count: usize,
+}
+
+/// The UTC range a question like "what did I read yesterday?" is about.
+pub fn question_time_range(
+    question: &str,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let question = question.to_lowercase();
+    let today = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0)?);
+    let this_week = today - Duration::days(now.weekday().num_days_from_monday() as i64);
+    if question.contains("yesterday") {
+        Some((today - Duration::days(1), today))
+    } else if question.contains("today") || question.contains("this morning") {
+        Some((today, now))
+    } else if question.contains("last week") {
+        Some((this_week - Duration::weeks(1), this_week))
+    } else if question.contains("this week") {
+        Some((this_week, now))
+    } else if question.contains("last hour") || question.contains("past hour") {
+        Some((now - Duration::hours(1), now))
+    } else {
+        None
+    }
+}
+
+/// The words of `question` worth searching for, as an FTS5 query matching any of them.
+/// Text with more of them ranks higher.
+pub fn keyword_query(question: &str) -> String {
+    let mut keywords: Vec<String> = Vec::new();
+    for word in question.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3
+            || QUESTION_STOPWORDS.contains(&word.as_str())
+            // the time range is searched by time instead
+            || matches!(
+                word.as_str(),
+                "today" | "yesterday" | "morning" | "week" | "hour" | "last" | "past"
+            )
+            || keywords.contains(&word)
+        {
+            continue;
+        }
+        keywords.push(word);
+        if keywords.len() == MAX_KEYWORDS {
+            break;
+        }
+    }
+    keywords
+        .iter()
+        .map(|word| format!("\"{}\"", word))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// The OCR text and transcriptions most relevant to the question, by keyword and, when
+/// text embeddings are available, by meaning.
+pub async fn retrieve_sources(
+    db: &DatabaseManager,
+    request: &AskRequest,
+    now: DateTime<Utc>,
+) -> Result<Vec<SummarySource>> {
+    let (start_time, end_time) = request.time_range(now);
+    let keywords = keyword_query(&request.question);
+    let mut lists = Vec::new();
+    // without keywords the newest captures of the range are all there is to go by
+    if !keywords.is_empty() || start_time.is_some() {
+        lists.push(
+            db.search(
+                &keywords,
+                request.content_type.clone(),
+                request.limit,
+                0,
+                start_time,
+                end_time,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                true,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Relevance,
+            )
+            .await?,
+        );
+    }
+
+    match generate_embedding(request.question.trim(), 0).await {
+        Ok(embedding) => {
+            if includes_ocr(&request.content_type) {
+                let ocr = db
+                    .search_similar_embeddings(
+                        embedding.clone(),
+                        request.limit,
+                        SEMANTIC_MAX_DISTANCE,
+                        start_time,
+                        end_time,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                    .await?;
+                lists.push(ocr.into_iter().map(SearchResult::OCR).collect());
+            }
+            if includes_audio(&request.content_type) {
+                let audio = db
+                    .search_similar_transcriptions(
+                        &embedding,
+                        request.limit,
+                        SEMANTIC_MAX_DISTANCE,
+                        start_time,
+                        end_time,
+                        None,
+                    )
+                    .await?;
+                lists.push(audio.into_iter().map(SearchResult::Audio).collect());
+            }
+        }
+        Err(e) => warn!("answering by keyword only, no question embedding: {}", e),
+    }
+
+    let mut results = blend_results(lists);
+    results.truncate(request.limit as usize);
+    Ok(collect_sources(results))
+}
+
+/// Asks `llm` to answer `question` from `sources`, which are returned when cited.
+pub async fn answer_question(
+    llm: &Llm,
+    question: &str,
+    sources: Vec<SummarySource>,
+    now: DateTime<Utc>,
+) -> Result<Answer> {
+    let instructions = format!(
+        "It is now {}. Question: {}",
+        now.format("%Y-%m-%d %H:%M UTC"),
+        question.trim()
+    );
+    let answer = llm
+        .complete(SYSTEM_PROMPT, &build_prompt(&sources, Some(&instructions)))
+        .await?;
+    let cited = cited_references(&answer);
+    let source_count = sources.len();
+    Ok(Answer {
+        answer,
+        provider: llm.provider.to_string(),
+        model: llm.model.clone(),
+        sources: sources
+            .into_iter()
+            .filter(|source| cited.contains(&source.reference))
+            .collect(),
+        source_count,
+    })
+}
@@ -0,0 +1,348 @@
+//! Recordings made elsewhere, like phone calls, transcribed into the timeline at the time
+//! they were recorded.
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+use screenpipe_audio::transcription::retranscribe::{load_recording, retranscribe_segment};
+use screenpipe_audio::transcription::stt::SAMPLE_RATE;
+use screenpipe_audio::transcription::stt_engine::SttEngine;
+use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType, ImportedTranscription};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "m4a", "aac", "flac", "ogg", "opus"];
+// Imported recordings are transcribed in pieces as long as recorded chunks
+const IMPORT_SEGMENT_SECS: f64 = 30.0;
+// Metadata tags holding when a recording was made, by how much they are trusted
+const TIME_TAGS: &[&str] = &["creation_time", "date", "com.apple.quicktime.creationdate"];
+
+/// What got imported of a recording.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ImportedRecording {
+    pub source: PathBuf,
+    /// Path of its audio chunk
+    pub file_path: String,
+    pub recorded_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    pub transcriptions: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AudioImportReport {
+    pub imported: Vec<ImportedRecording>,
+    /// Recordings imported before
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<FailedImport>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FailedImport {
+    pub source: PathBuf,
+    pub error: String,
+}
+
+/// How recordings are imported.
+pub struct AudioImport {
+    pub engine: AudioTranscriptionEngine,
+    pub languages: Vec<Language>,
+    /// Device the transcriptions are stored under
+    pub device: String,
+    /// Copies the recordings to this directory, otherwise they are read where they are
+    pub copy_to: Option<PathBuf>,
+}
+
+/// The recordings at `path`, a file or a directory searched recursively, oldest name first.
+pub fn find_audio_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(anyhow!("{} does not exist", path.display()));
+    }
+    let mut files: Vec<PathBuf> = WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_audio_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// When a recording was made: from its metadata `tags`, else a date and time in its
+/// `file_name` like phone recorders write, else when the file was last `modified`. Times
+/// without a timezone are local.
+pub fn recorded_at(
+    tags: &HashMap<String, String>,
+    file_name: &str,
+    modified: Option<SystemTime>,
+) -> Option<DateTime<Utc>> {
+    let tags: HashMap<String, &String> = tags
+        .iter()
+        .map(|(key, value)| (key.to_lowercase(), value))
+        .collect();
+    TIME_TAGS
+        .iter()
+        .filter_map(|tag| tags.get(*tag))
+        .find_map(|value| parse_time(value))
+        .or_else(|| time_in_file_name(file_name))
+        .or_else(|| modified.map(DateTime::<Utc>::from))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f %z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(local_time)
+}
+
+/// A local date and time in `file_name`, like `20240501_101500`, `2024-05-01 10-15-00` or
+/// `Screenshot 2024-05-01 at 2.15.00 PM`.
+pub fn time_in_file_name(file_name: &str) -> Option<DateTime<Utc>> {
+    let pattern = Regex::new(concat!(
+        r"(?i)(\d{4})-?(\d{2})-?(\d{2})(?:[ _T-]|\s+at\s+)?",
+        r"(\d{1,2})[-_:.h]?(\d{2})[-_:.m]?(\d{2})(?:\s*([ap])m\b)?",
+    ))
+    .ok()?;
+    let captures = pattern.captures(file_name)?;
+    let mut hour: u32 = captures[4].parse().ok()?;
+    // 12-hour clocks of macOS screenshots in the us, 12 AM is midnight
+    if let Some(half) = captures.get(7) {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour %= 12;
+        if half.as_str().eq_ignore_ascii_case("p") {
+            hour += 12;
+        }
+    }
+    let time = format!(
+        "{}-{}-{} {:02}:{}:{}",
+        &captures[1], &captures[2], &captures[3], hour, &captures[5], &captures[6]
+    );
+    NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(local_time)
+}
+
+fn local_time(time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Start and end in seconds of the pieces a recording of `duration_secs` is transcribed in.
+pub fn import_segments(duration_secs: f64) -> Vec<(f64, f64)> {
+    let mut segments = Vec::new();
+    let mut start = 0.0;
+    while start < duration_secs {
+        let end = (start + IMPORT_SEGMENT_SECS).min(duration_secs);
+        segments.push((start, end));
+        start = end;
+    }
+    segments
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeOutput {
+    format: ProbeFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeFormat {
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// The metadata tags of the recording at `path`, none when ffprobe can't read it.
+async fn metadata_tags(path: &Path) -> HashMap<String, String> {
+    let Some(ffmpeg_path) = find_ffmpeg_path() else {
+        return HashMap::new();
+    };
+    let output = Command::new(ffmpeg_path.with_file_name("ffprobe"))
+        .args(["-v", "quiet", "-print_format", "json", "-show_format"])
+        .arg(path)
+        .output()
+        .await;
+    match output {
+        Ok(output) if output.status.success() => {
+            serde_json::from_slice::<ProbeOutput>(&output.stdout)
+                .map(|probe| probe.format.tags)
+                .unwrap_or_default()
+        }
+        _ => {
+            debug!("no metadata read from {}", path.display());
+            HashMap::new()
+        }
+    }
+}
+
+/// Transcribes the recordings at `path` into `db`. Recordings imported before are skipped,
+/// the others are imported even when some fail.
+pub async fn import_audio(
+    db: &DatabaseManager,
+    backend: &dyn SttEngine,
+    import: &AudioImport,
+    path: &Path,
+) -> Result<AudioImportReport> {
+    let files = find_audio_files(path)?;
+    info!("found {} recordings to import", files.len());
+    if let Some(copy_to) = &import.copy_to {
+        tokio::fs::create_dir_all(copy_to).await?;
+    }
+    let mut report = AudioImportReport::default();
+    for source in files {
+        match import_recording(db, backend, import, &source).await {
+            Ok(Some(imported)) => {
+                info!(
+                    "imported {} recorded at {}: {} transcriptions",
+                    source.display(),
+                    imported.recorded_at,
+                    imported.transcriptions
+                );
+                report.imported.push(imported);
+            }
+            Ok(None) => report.skipped.push(source),
+            Err(e) => {
+                warn!("failed to import {}: {}", source.display(), e);
+                report.failed.push(FailedImport {
+                    source,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Path the audio chunk of `source` is stored under, the copy of recordings with the same
+/// name in different directories are told apart by a hash of where they were.
+pub fn chunk_path(copy_to: Option<&Path>, source: &Path) -> Result<String> {
+    let source = std::fs::canonicalize(source)?;
+    let path = match copy_to {
+        Some(copy_to) => {
+            let hash = format!("{:x}", Sha256::digest(source.to_string_lossy().as_bytes()));
+            let stem = source
+                .file_stem()
+                .ok_or_else(|| anyhow!("{} is not a file", source.display()))?
+                .to_string_lossy()
+                .replace(' ', "_");
+            let extension = source
+                .extension()
+                .map(|extension| format!(".{}", extension.to_string_lossy()))
+                .unwrap_or_default();
+            copy_to.join(format!("imported_{}_{}{}", stem, &hash[..12], extension))
+        }
+        None => source,
+    };
+    Ok(path.to_string_lossy().to_string())
+}
+
+async fn import_recording(
+    db: &DatabaseManager,
+    backend: &dyn SttEngine,
+    import: &AudioImport,
+    source: &Path,
+) -> Result<Option<ImportedRecording>> {
+    let file_path = chunk_path(import.copy_to.as_deref(), source)?;
+    if db.has_audio_chunk(&file_path).await? {
+        return Ok(None);
+    }
+    let tags = metadata_tags(source).await;
+    let modified = tokio::fs::metadata(source)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let recorded_at = recorded_at(&tags, &file_name, modified).unwrap_or_else(Utc::now);
+
+    let audio = load_recording(source.to_path_buf()).await?;
+    let duration_secs = audio.len() as f64 / SAMPLE_RATE as f64;
+
+    let device_name = format!("{} (input)", import.device);
+    let mut transcriptions = Vec::new();
+    for (offset_index, (start, end)) in import_segments(duration_secs).into_iter().enumerate() {
+        let transcript = retranscribe_segment(
+            backend,
+            &audio,
+            Some(start),
+            Some(end),
+            &device_name,
+            &import.languages,
+            &[],
+        )
+        .await?;
+        if transcript.text.is_empty() {
+            continue;
+        }
+        transcriptions.push(ImportedTranscription {
+            transcription: transcript.text,
+            offset_index: offset_index as i64,
+            start_time: start,
+            end_time: end,
+            timestamp: recorded_at + chrono::Duration::milliseconds((start * 1000.0) as i64),
+            words: transcript.words,
+        });
+    }
+
+    // stored once transcribed, a recording failing to import is imported again
+    if import.copy_to.is_some() {
+        tokio::fs::copy(source, &file_path).await?;
+    }
+    let device = AudioDevice {
+        name: import.device.clone(),
+        device_type: DeviceType::Input,
+    };
+    let inserted = db
+        .insert_imported_audio(
+            &file_path,
+            recorded_at,
+            &import.engine.to_string(),
+            &device,
+            &transcriptions,
+        )
+        .await;
+    if let Err(e) = inserted {
+        if import.copy_to.is_some() {
+            let _ = tokio::fs::remove_file(&file_path).await;
+        }
+        return Err(e.into());
+    }
+
+    Ok(Some(ImportedRecording {
+        source: source.to_path_buf(),
+        file_path,
+        recorded_at,
+        duration_secs,
+        transcriptions: transcriptions.len(),
+    }))
+}
@@ -0,0 +1,279 @@
+use anyhow::Result;
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, UPGRADE},
+        Method, StatusCode, Uri,
+    },
+    middleware::Next,
+    response::{IntoResponse, Json as JsonResponse, Response},
+};
+use screenpipe_db::{ApiToken, DatabaseManager};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Prefix of the tokens, to tell them apart from other secrets
+pub const TOKEN_PREFIX: &str = "sp_";
+
+/// What a token gives access to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    /// Search, timelines, summaries and the event streams, everything read as text
+    ReadSearch,
+    /// Screenshots, recordings and clips
+    ReadMedia,
+    /// Adding and removing tags, annotations and bookmarks
+    WriteTags,
+    /// Everything, including pipes, raw SQL, devices, webhooks and alert rules
+    Admin,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 4] = [
+        ApiScope::ReadSearch,
+        ApiScope::ReadMedia,
+        ApiScope::WriteTags,
+        ApiScope::Admin,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ApiScope::ReadSearch => "read-search",
+            ApiScope::ReadMedia => "read-media",
+            ApiScope::WriteTags => "write-tags",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.name() == name)
+    }
+}
+
+/// The scopes of a comma separated list like "read-search,read-media".
+pub fn parse_scopes(list: &str) -> Result<Vec<ApiScope>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| ApiScope::parse(name).ok_or_else(|| format!("unknown scope '{}'", name)))
+        .collect()
+}
+
+/// Whether a token with `scopes` can do what `required` allows.
+pub fn allows(scopes: &[ApiScope], required: ApiScope) -> bool {
+    scopes.contains(&ApiScope::Admin) || scopes.contains(&required)
+}
+
+/// Query parameters that answer screenshots along with the text.
+pub const IMAGE_PARAMS: [&str; 2] = ["include_frames", "images"];
+
+/// Whether `query` asks for screenshots with one of the `IMAGE_PARAMS`.
+pub fn asks_for_images(query: Option<&str>) -> bool {
+    query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(name, value)| IMAGE_PARAMS.contains(&name) && !matches!(value, "" | "0" | "false"))
+}
+
+/// The scope a request needs, none for the ones anyone may make. Reads of text that ask
+/// for screenshots too, like `/search?include_frames=true`, need read-media.
+pub fn required_scope(method: &Method, path: &str, query: Option<&str>) -> Option<ApiScope> {
+    let scope = route_scope(method, path)?;
+    if scope == ApiScope::ReadSearch && asks_for_images(query) {
+        return Some(ApiScope::ReadMedia);
+    }
+    Some(scope)
+}
+
+fn route_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    if method == Method::OPTIONS || matches!(path, "/health" | "/ws/health") {
+        return None;
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    Some(match segments.as_slice() {
+        ["tags", ..] => ApiScope::WriteTags,
+        ["annotations", ..] if method == Method::GET => ApiScope::ReadSearch,
+        ["annotations", ..] => ApiScope::WriteTags,
+        ["bookmarks", ..] if method == Method::GET => ApiScope::ReadSearch,
+        ["bookmarks", ..] => ApiScope::WriteTags,
+        // `/frames/export` included
+        ["frames", _] | ["frames", _, "recording"] => ApiScope::ReadMedia,
+        ["clip"] | ["stream", "frames"] | ["cold-storage", "fetch"] => ApiScope::ReadMedia,
+        ["experimental", "validate", "media"] => ApiScope::ReadMedia,
+        ["search", ..]
+        | ["semantic-search"]
+        | ["visual-search"]
+        | ["timeline"]
+        | ["analytics", ..]
+        | ["digest", _]
+        | ["meetings", ..]
+        | ["audio", "transcriptions", _, "versions"]
+        | ["summarize"]
+        | ["ask"]
+        | ["documents", ..]
+        | ["codes"]
+        | ["tables"]
+        | ["frames", _, "tables"]
+        | ["ws", "events"]
+        | ["sse", "events"]
+        | ["audio", "list"]
+        | ["vision", "list"]
+        | ["status"] => ApiScope::ReadSearch,
+        ["speakers", "unnamed" | "search" | "similar" | "enrolled"] => ApiScope::ReadSearch,
+        ["retranscribe", ..] if method == Method::GET => ApiScope::ReadSearch,
+        _ => ApiScope::Admin,
+    })
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A new random token, shown once.
+pub fn generate_token() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Stores a new token with `scopes`. Returns it with the token, which isn't stored.
+pub async fn create_token(
+    db: &DatabaseManager,
+    name: &str,
+    scopes: &[ApiScope],
+) -> Result<(ApiToken, String)> {
+    let token = generate_token();
+    let scopes: Vec<&str> = scopes.iter().map(ApiScope::name).collect();
+    let stored = db
+        .insert_api_token(name, &hash_token(&token), &scopes.join(","))
+        .await?;
+    Ok((stored, token))
+}
+
+/// Checks the tokens of the requests made to the HTTP API.
+pub struct ApiAuth {
+    db: Arc<DatabaseManager>,
+}
+
+impl ApiAuth {
+    pub fn new(db: Arc<DatabaseManager>) -> Self {
+        Self { db }
+    }
+
+    /// Whether `token` may do what `required` allows, the status to answer when not.
+    pub async fn authorize(
+        &self,
+        token: Option<&str>,
+        required: ApiScope,
+    ) -> Result<(), (StatusCode, String)> {
+        let Some(token) = token.filter(|token| !token.is_empty()) else {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "an API token is required, see `screenpipe token create`".to_string(),
+            ));
+        };
+        let stored = self
+            .db
+            .get_api_token_by_hash(&hash_token(token))
+            .await
+            .map_err(|e| {
+                warn!("failed to look up api token: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?
+            .ok_or((StatusCode::UNAUTHORIZED, "invalid API token".to_string()))?;
+
+        // unknown scopes of newer versions give nothing
+        let scopes: Vec<ApiScope> = stored
+            .scopes
+            .split(',')
+            .filter_map(ApiScope::parse)
+            .collect();
+        if !allows(&scopes, required) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!("the token needs the {} scope", required.name()),
+            ));
+        }
+        if let Err(e) = self.db.touch_api_token(stored.id).await {
+            debug!("failed to record the use of api token {}: {}", stored.id, e);
+        }
+        Ok(())
+    }
+}
+
+/// The token of `Authorization: Bearer <token>`, or of the `token` query parameter for
+/// clients that can't set headers: browser WebSockets and EventSource. Other requests
+/// can't pass it in their URL, which ends up in logs and histories.
+pub fn request_token(request: &Request) -> Option<&str> {
+    if let Some(header) = request.headers().get(AUTHORIZATION) {
+        return header.to_str().ok()?.strip_prefix("Bearer ").map(str::trim);
+    }
+    let websocket = request
+        .headers()
+        .get(UPGRADE)
+        .and_then(|upgrade| upgrade.to_str().ok())
+        .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    if !websocket && !request.uri().path().starts_with("/sse/") {
+        return None;
+    }
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// `uri` with the value of its `token` query parameter hidden, to be logged.
+pub fn redacted_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query: Vec<&str> = query
+        .split('&')
+        .map(|pair| {
+            if pair.starts_with("token=") {
+                "token=redacted"
+            } else {
+                pair
+            }
+        })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+/// Set on the requests whose token was checked, by the hash of the token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedToken(pub String);
+
+/// Middleware answering 401 or 403 to the requests whose token lacks the scope they need.
+/// The others get a `VerifiedToken`.
+pub async fn require_token(
+    State(auth): State<Arc<ApiAuth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let uri = request.uri();
+    if let Some(required) = required_scope(request.method(), uri.path(), uri.query()) {
+        // owned, the body isn't Sync and can't be borrowed across the lookup
+        let token = request_token(&request).map(str::to_string);
+        if let Err((status, error)) = auth.authorize(token.as_deref(), required).await {
+            return (status, JsonResponse(json!({"error": error}))).into_response();
+        }
+        if let Some(token) = token {
+            request
+                .extensions_mut()
+                .insert(VerifiedToken(hash_token(&token)));
+        }
+    }
+    next.run(request).await
+}
@@ -0,0 +1,468 @@
+use crate::storage::file_id;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use screenpipe_core::encryption::EncryptionKey;
+use screenpipe_db::{backup_database, database_is_encrypted, verify_database, DatabaseManager};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+const MANIFEST: &str = "manifest.json";
+const DATABASE: &str = "db.sqlite";
+const MEDIA_DIR: &str = "data";
+const MANIFEST_VERSION: u32 = 1;
+
+/// What a backup holds. It is written last, once every file it lists is in place.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Data dir the backup was taken of, recorded paths in the database point into it
+    pub source_dir: String,
+    /// The database is encrypted with the key in the keychain
+    pub encrypted: bool,
+    pub database: BackupFile,
+    pub media: Vec<BackupFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackupFile {
+    /// Relative to the backup, with `/` separators
+    pub path: String,
+    pub size: u64,
+    /// When the original was last modified, in milliseconds, to skip unchanged files
+    pub modified_ms: i64,
+    pub sha256: String,
+    /// The file this is a hard link to, frame blobs are linked into image chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linked_to: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupReport {
+    pub database_bytes: u64,
+    /// Media files new or changed since the last backup to the same directory
+    pub copied: usize,
+    pub copied_bytes: u64,
+    pub unchanged: usize,
+    /// Files of the last backup no longer in the data dir
+    pub removed: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub database_bytes: u64,
+    pub files: usize,
+    pub bytes: u64,
+    /// Recorded paths pointed at the data dir restored to
+    pub rebased_paths: u64,
+}
+
+/// Backs up the database and the recorded media of `screenpipe_dir` to `backup_dir`.
+/// The database is snapshotted online, so screenpipe can keep recording. Backing up to
+/// the same directory again only copies the media that changed.
+pub async fn backup(screenpipe_dir: &Path, backup_dir: &Path) -> Result<BackupReport> {
+    let media_dir = screenpipe_dir.join(MEDIA_DIR);
+    if backup_dir.starts_with(&media_dir) {
+        return Err(anyhow!("can't back up into the data dir it backs up"));
+    }
+    tokio::fs::create_dir_all(backup_dir).await?;
+
+    let previous = match read_manifest(backup_dir).await {
+        Ok(manifest) => manifest.media,
+        Err(e) => {
+            debug!(
+                "no previous backup in {:?}, backing up everything: {}",
+                backup_dir, e
+            );
+            Vec::new()
+        }
+    };
+
+    // the database goes first, media recorded while copying is only missing from it
+    let database_path = screenpipe_dir.join(DATABASE);
+    let key = database_key(&database_path)?;
+    let snapshot = partial_path(&backup_dir.join(DATABASE));
+    let _ = tokio::fs::remove_file(&snapshot).await;
+    backup_database(&database_path.to_string_lossy(), &snapshot, key.as_deref())
+        .await
+        .context("failed to back up the database")?;
+
+    let backup = backup_dir.to_path_buf();
+    let (media, mut report) =
+        tokio::task::spawn_blocking(move || backup_media(&media_dir, &backup, previous)).await??;
+
+    let (size, sha256) = hash_file(&snapshot, None)?;
+    tokio::fs::rename(&snapshot, backup_dir.join(DATABASE)).await?;
+    report.database_bytes = size;
+
+    let manifest = BackupManifest {
+        version: MANIFEST_VERSION,
+        created_at: Utc::now(),
+        source_dir: screenpipe_dir.to_string_lossy().into_owned(),
+        encrypted: key.is_some(),
+        database: BackupFile {
+            path: DATABASE.to_string(),
+            size,
+            modified_ms: 0,
+            sha256,
+            linked_to: None,
+        },
+        media,
+    };
+    let manifest_path = backup_dir.join(MANIFEST);
+    tokio::fs::write(
+        partial_path(&manifest_path),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await?;
+    tokio::fs::rename(partial_path(&manifest_path), &manifest_path).await?;
+
+    info!(
+        "backed up {:?}: {} files copied, {} unchanged, {} removed",
+        screenpipe_dir, report.copied, report.unchanged, report.removed
+    );
+    Ok(report)
+}
+
+/// Restores a backup to `screenpipe_dir`. Every file is checked against the manifest and
+/// the database against SQLite's integrity check before anything is written. An existing
+/// database is only replaced with `force`. Screenpipe must not be running.
+pub async fn restore(
+    backup_dir: &Path,
+    screenpipe_dir: &Path,
+    force: bool,
+) -> Result<RestoreReport> {
+    let manifest = read_manifest(backup_dir)
+        .await
+        .with_context(|| format!("{:?} is not a screenpipe backup", backup_dir))?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(anyhow!(
+            "backup version {} is newer than this screenpipe supports",
+            manifest.version
+        ));
+    }
+
+    let database_path = screenpipe_dir.join(DATABASE);
+    if database_path.exists() && !force {
+        return Err(anyhow!(
+            "{:?} already has a database, restore with --force to replace it",
+            screenpipe_dir
+        ));
+    }
+    let key = if manifest.encrypted {
+        Some(EncryptionKey::load()?.to_hex())
+    } else {
+        None
+    };
+
+    let backup = backup_dir.to_path_buf();
+    let checked = manifest.clone();
+    tokio::task::spawn_blocking(move || verify_backup(&backup, &checked)).await??;
+    verify_database(&backup_dir.join(DATABASE).to_string_lossy(), key.as_deref()).await?;
+
+    let mut report = RestoreReport {
+        database_bytes: manifest.database.size,
+        ..Default::default()
+    };
+    tokio::fs::create_dir_all(screenpipe_dir).await?;
+    let restored = partial_path(&database_path);
+    tokio::fs::copy(backup_dir.join(DATABASE), &restored).await?;
+    for suffix in ["-wal", "-shm"] {
+        let journal = PathBuf::from(format!("{}{}", database_path.to_string_lossy(), suffix));
+        match tokio::fs::remove_file(&journal).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    tokio::fs::rename(&restored, &database_path).await?;
+
+    let backup = backup_dir.to_path_buf();
+    let target = screenpipe_dir.to_path_buf();
+    let media = manifest.media.clone();
+    (report.files, report.bytes) =
+        tokio::task::spawn_blocking(move || restore_media(&backup, &target, &media)).await??;
+
+    let target_dir = screenpipe_dir.to_string_lossy().into_owned();
+    if manifest.source_dir != target_dir {
+        let database = database_path.to_string_lossy();
+        let db = match &key {
+            Some(key) => DatabaseManager::new_encrypted(&database, key).await?,
+            None => DatabaseManager::new(&database).await?,
+        };
+        report.rebased_paths = db
+            .rebase_media_paths(
+                &format!("{}/{}/", manifest.source_dir, MEDIA_DIR),
+                &format!("{}/{}/", target_dir, MEDIA_DIR),
+            )
+            .await?;
+        db.pool.close().await;
+    }
+
+    info!(
+        "restored the backup of {} taken at {}",
+        manifest.source_dir, manifest.created_at
+    );
+    Ok(report)
+}
+
+pub async fn read_manifest(backup_dir: &Path) -> Result<BackupManifest> {
+    let manifest = tokio::fs::read(backup_dir.join(MANIFEST)).await?;
+    Ok(serde_json::from_slice(&manifest)?)
+}
+
+fn database_key(database_path: &Path) -> Result<Option<String>> {
+    if !database_is_encrypted(&database_path.to_string_lossy()) {
+        return Ok(None);
+    }
+    Ok(Some(EncryptionKey::load()?.to_hex()))
+}
+
+fn backup_media(
+    media_dir: &Path,
+    backup_dir: &Path,
+    previous: Vec<BackupFile>,
+) -> Result<(Vec<BackupFile>, BackupReport)> {
+    let mut previous: HashMap<String, BackupFile> = previous
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+    let mut report = BackupReport::default();
+    let mut media: Vec<BackupFile> = Vec::new();
+    // index in `media` of the first path of each inode
+    let mut originals = HashMap::new();
+
+    for entry in WalkDir::new(media_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let path = relative_path(media_dir, entry.path());
+        let target = backup_dir.join(&path);
+        let modified_ms = modified_ms(&metadata);
+        let linked_to = file_id(&metadata)
+            .and_then(|id| originals.get(&id))
+            .map(|&index: &usize| &media[index]);
+
+        let file = match linked_to {
+            Some(original) => BackupFile {
+                path: path.clone(),
+                size: original.size,
+                modified_ms,
+                sha256: original.sha256.clone(),
+                linked_to: Some(original.path.clone()),
+            },
+            None => BackupFile {
+                path: path.clone(),
+                size: metadata.len(),
+                modified_ms,
+                sha256: String::new(),
+                linked_to: None,
+            },
+        };
+
+        let unchanged = previous.remove(&path).filter(|last| {
+            last.size == file.size
+                && last.modified_ms == file.modified_ms
+                && last.linked_to == file.linked_to
+                && fs::metadata(&target).is_ok_and(|copy| copy.len() == file.size)
+        });
+        let file = match (unchanged, &file.linked_to) {
+            (Some(last), _) => {
+                report.unchanged += 1;
+                last
+            }
+            (None, Some(original)) => {
+                link_or_copy(&backup_dir.join(original), &target)?;
+                report.copied += 1;
+                file
+            }
+            (None, None) => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let partial = partial_path(&target);
+                let (size, sha256) = match hash_file(entry.path(), Some(&partial)) {
+                    Ok(copied) => copied,
+                    // deleted while backing up, e.g. by retention
+                    Err(e) if is_not_found(&e) => continue,
+                    Err(e) => return Err(e),
+                };
+                fs::rename(&partial, &target)?;
+                report.copied += 1;
+                report.copied_bytes += size;
+                BackupFile {
+                    size,
+                    sha256,
+                    ..file
+                }
+            }
+        };
+
+        if file.linked_to.is_none() {
+            if let Some(id) = file_id(&metadata) {
+                originals.insert(id, media.len());
+            }
+        }
+        media.push(file);
+    }
+
+    for path in previous.into_keys() {
+        match fs::remove_file(backup_dir.join(&path)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                warn!("failed to remove {} from the backup: {}", path, e)
+            }
+            _ => report.removed += 1,
+        }
+    }
+    Ok((media, report))
+}
+
+/// Checks every file of the backup against the manifest.
+fn verify_backup(backup_dir: &Path, manifest: &BackupManifest) -> Result<()> {
+    let mut problems = Vec::new();
+    let mut paths = HashSet::new();
+    for file in std::iter::once(&manifest.database).chain(&manifest.media) {
+        let linked_elsewhere = file
+            .linked_to
+            .as_ref()
+            .is_some_and(|original| !paths.contains(original.as_str()));
+        if !is_safe_path(&file.path) || linked_elsewhere {
+            problems.push(format!("{}: invalid path", file.path));
+            continue;
+        }
+        paths.insert(file.path.as_str());
+
+        match hash_file(&backup_dir.join(&file.path), None) {
+            Ok((size, sha256)) if size == file.size && sha256 == file.sha256 => {}
+            Ok((size, _)) if size != file.size => problems.push(format!(
+                "{}: {} bytes, expected {}",
+                file.path, size, file.size
+            )),
+            Ok(_) => problems.push(format!("{}: checksum mismatch", file.path)),
+            Err(e) => problems.push(format!("{}: {}", file.path, e)),
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "backup failed verification, {} files are damaged: {}",
+        problems.len(),
+        problems
+            .iter()
+            .take(5)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("; ")
+    ))
+}
+
+fn restore_media(
+    backup_dir: &Path,
+    screenpipe_dir: &Path,
+    media: &[BackupFile],
+) -> Result<(usize, u64)> {
+    let mut bytes = 0;
+    for file in media {
+        let target = screenpipe_dir.join(&file.path);
+        match &file.linked_to {
+            Some(original) => link_or_copy(&screenpipe_dir.join(original), &target)?,
+            None => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let partial = partial_path(&target);
+                hash_file(&backup_dir.join(&file.path), Some(&partial))?;
+                fs::rename(&partial, &target)?;
+                bytes += file.size;
+            }
+        }
+    }
+    Ok((media.len(), bytes))
+}
+
+/// Size and SHA-256 of a file, copied to `copy_to` on the way when given.
+fn hash_file(path: &Path, copy_to: Option<&Path>) -> Result<(u64, String)> {
+    let mut reader = File::open(path)?;
+    let mut writer = copy_to.map(File::create).transpose()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        if let Some(writer) = writer.as_mut() {
+            writer.write_all(&buffer[..read])?;
+        }
+        size += read as u64;
+    }
+    if let Some(writer) = writer {
+        writer.sync_all()?;
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+/// Hard links `link` to `original` like in the data dir, copies it where links aren't
+/// supported.
+fn link_or_copy(original: &Path, link: &Path) -> Result<()> {
+    if let Some(parent) = link.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::remove_file(link) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    if let Err(e) = fs::hard_link(original, link) {
+        debug!("failed to link {:?}, copying it instead: {}", original, e);
+        fs::copy(original, link)?;
+    }
+    Ok(())
+}
+
+fn relative_path(media_dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(media_dir).unwrap_or(path);
+    let parts: Vec<_> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect();
+    format!("{}/{}", MEDIA_DIR, parts.join("/"))
+}
+
+// manifest paths stay inside the backup and the data dir
+fn is_safe_path(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|part| matches!(part, Component::Normal(_)))
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.partial", path.to_string_lossy()))
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::NotFound)
+}
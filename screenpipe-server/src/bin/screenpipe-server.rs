@@ -5,30 +5,82 @@ use dirs::home_dir;
 use futures::pin_mut;
 use port_check::is_local_ipv4_port_free;
 use screenpipe_audio::{
-    audio_manager::AudioManagerBuilder,
+    audio_manager::{standalone_stt_engine, AudioManagerBuilder},
     core::device::{
         default_input_device, default_output_device, list_audio_devices, parse_audio_device,
     },
+    preprocessing::AudioPreprocessing,
+    storage::AudioStorage,
+    transcription::whisper::model::set_models_dir as set_whisper_models_dir,
 };
-use screenpipe_core::find_ffmpeg_path;
+use screenpipe_core::encryption::{media_key, set_media_key, EncryptionKey};
+use screenpipe_core::{find_ffmpeg_path, InstallRecord, InstalledPipe, PipeSource, WasmPlugins};
 use screenpipe_db::{
-    create_migration_worker, DatabaseManager, MigrationCommand, MigrationConfig, MigrationStatus,
+    create_migration_worker, database_is_encrypted, DatabaseManager, MigrationCommand,
+    MigrationConfig, MigrationStatus,
 };
 use screenpipe_server::{
+    activitywatch::{
+        export_buckets, hostname, import_document, run_activitywatch_bridge, ActivityWatchBridge,
+    },
+    audio_import::{import_audio, AudioImport},
+    auth::{create_token, ApiAuth},
+    backup::{backup, restore},
+    calendar::{run_calendar_sync, CalendarSource, CalendarSync},
     cli::{
-        AudioCommand, Cli, CliAudioTranscriptionEngine, CliOcrEngine, Command, MigrationSubCommand,
-        OutputFormat, PipeCommand, VisionCommand,
+        AudioCommand, Cli, CliAudioTranscriptionEngine, CliExportFormat, CliOcrEngine,
+        CliVectorStore, Command, ImportCommand, MigrationSubCommand, OutputFormat, PipeCommand,
+        TokenCommand, VisionCommand,
     },
+    clipboard::{run_clipboard_monitor, ClipboardFilter, ClipboardMonitor},
+    cold_storage::{run_cold_storage, ColdStorage},
+    data_deletion::run_queued_deletions,
+    digest::{day_bounds, run_daily_digests, Digests},
+    encryption::run_media_encryption,
+    frame_import::{import_frames, FrameImport},
+    frame_storage::{monitor_frame_storage, FrameBlobStore, FrameStorage},
     handle_index_command,
+    idle_pause::{run_idle_pause, IdlePause},
+    input_activity::run_input_activity_monitor,
+    llm::{Llm, LlmProvider},
+    mcp::{run_mcp, McpServer},
+    meetings::{run_meeting_detection, run_meeting_notes, MeetingDetector},
+    obsidian::{run_obsidian_export, ObsidianExporter},
+    pipe_host::ServerPipeHost,
     pipe_manager::PipeInfo,
-    start_continuous_recording, watch_pid, PipeManager, ResourceMonitor, SCServer,
+    power::{run_power_throttle, PowerThrottle},
+    rate_limit::RateLimiter,
+    record_export::{export_records, parse_fields, RecordFormat, RecordWriter},
+    retention::{run_retention, Retention},
+    start_continuous_recording,
+    storage::{format_bytes, run_storage_quota, StorageManager},
+    text_embeds::run_text_embedder,
+    tls::{certificate_fingerprint, rustls_config, TlsSource},
+    translation::run_transcript_translator,
+    watch_pid, PipeManager, ResourceMonitor, SCServer,
 };
-use screenpipe_vision::monitor::list_monitors;
+use screenpipe_vision::capture_backend::{screen_capturer, set_screen_capturer};
+use screenpipe_vision::capture_region::monitor_region;
+use screenpipe_vision::capture_screenshot_by_window::WindowBounds;
+use screenpipe_vision::cursor::{set_cursor_options, CursorOptions};
+use screenpipe_vision::monitor::{list_monitors, monitor_fps, select_monitors};
+use screenpipe_vision::ocr_provider::create_ocr_provider;
+use screenpipe_vision::onnx_ocr::{set_models_dir, set_use_gpu};
 #[cfg(target_os = "macos")]
 use screenpipe_vision::run_ui;
+#[cfg(target_os = "linux")]
+use screenpipe_vision::validate_tesseract_languages;
+use screenpipe_vision::OcrEngine;
 use serde_json::{json, Value};
 use std::{
-    env, fs, io::Write, net::SocketAddr, ops::Deref, path::PathBuf, sync::Arc, time::Duration,
+    collections::HashMap,
+    env, fs,
+    io::Write,
+    net::SocketAddr,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 use tokio::{runtime::Runtime, signal, sync::broadcast};
 use tracing::{debug, error, info, warn};
@@ -48,6 +100,19 @@ const DISPLAY: &str = r"
 
 ";
 
+/// Opens the database in `local_data_dir`, encrypted when `encrypt` is set or it already
+/// is. The key also becomes the media key.
+async fn open_database(local_data_dir: &Path, encrypt: bool) -> anyhow::Result<DatabaseManager> {
+    let path = format!("{}/db.sqlite", local_data_dir.to_string_lossy());
+    if !encrypt && !database_is_encrypted(&path) {
+        return Ok(DatabaseManager::new(&path).await?);
+    }
+
+    let key = EncryptionKey::load()?;
+    set_media_key(key.clone());
+    Ok(DatabaseManager::new_encrypted(&path, &key.to_hex()).await?)
+}
+
 fn get_base_dir(custom_path: &Option<String>) -> anyhow::Result<PathBuf> {
     let default_path = home_dir()
         .ok_or_else(|| anyhow::anyhow!("failed to get home directory"))?
@@ -180,6 +245,9 @@ async fn main() -> anyhow::Result<()> {
 
     let local_data_dir = get_base_dir(&cli.data_dir)?;
     let local_data_dir_clone = local_data_dir.clone();
+    set_models_dir(local_data_dir.join("models"));
+    set_whisper_models_dir(local_data_dir.join("models"));
+    set_use_gpu(cli.enable_ocr_gpu);
 
     // Only set up logging if we're not running a pipe command with JSON output
     let should_log = match &cli.command {
@@ -195,7 +263,11 @@ async fn main() -> anyhow::Result<()> {
                 } | PipeCommand::Info {
                     output: OutputFormat::Text,
                     ..
-                } | PipeCommand::Enable { .. }
+                } | PipeCommand::Outdated {
+                    output: OutputFormat::Text,
+                    ..
+                } | PipeCommand::Upgrade { .. }
+                    | PipeCommand::Enable { .. }
                     | PipeCommand::Disable { .. }
                     | PipeCommand::Update { .. }
                     | PipeCommand::Purge { .. }
@@ -210,6 +282,39 @@ async fn main() -> anyhow::Result<()> {
             output: OutputFormat::Text,
             ..
         }) => true,
+        Some(Command::Backup {
+            output: OutputFormat::Json,
+            ..
+        })
+        | Some(Command::Restore {
+            output: OutputFormat::Json,
+            ..
+        })
+        | Some(Command::Export {
+            output: OutputFormat::Json,
+            ..
+        })
+        | Some(Command::Import {
+            subcommand:
+                ImportCommand::Audio {
+                    output: OutputFormat::Json,
+                    ..
+                }
+                | ImportCommand::Frames {
+                    output: OutputFormat::Json,
+                    ..
+                },
+        }) => false,
+        // stdout carries the records
+        Some(Command::Export {
+            format: CliExportFormat::Jsonl | CliExportFormat::Csv | CliExportFormat::Activitywatch,
+            file: None,
+            ..
+        }) => false,
+        // stdout carries the MCP messages
+        Some(Command::Mcp { .. }) => false,
+        // keep the token readable
+        Some(Command::Token { .. }) => false,
         _ => true,
     };
 
@@ -274,8 +379,14 @@ async fn main() -> anyhow::Result<()> {
                         ),
                         OutputFormat::Text => {
                             println!("available monitors:");
-                            for monitor in monitors.iter() {
-                                println!("  {}. {:?}", monitor.id(), monitor.name());
+                            for (index, monitor) in monitors.iter().enumerate() {
+                                println!(
+                                    "  {}. {:?} (index:{}{})",
+                                    monitor.id(),
+                                    monitor.name(),
+                                    index,
+                                    if monitor.is_primary() { ", primary" } else { "" }
+                                );
                             }
                         }
                     }
@@ -286,6 +397,143 @@ async fn main() -> anyhow::Result<()> {
                 cli.handle_completions(*shell)?;
                 return Ok(());
             }
+            Command::Mcp {
+                port,
+                scopes,
+                token,
+                url,
+                tls_fingerprint,
+            } => {
+                let scopes = scopes.iter().cloned().map(Into::into).collect();
+                let mut server = McpServer::new(*port, scopes).with_token(token.clone());
+                if let Some(url) = url {
+                    server = server.with_url(url, tls_fingerprint.as_deref())?;
+                }
+                run_mcp(server).await?;
+                return Ok(());
+            }
+            Command::Token { subcommand } => {
+                handle_token_command(subcommand).await?;
+                return Ok(());
+            }
+            Command::Import { subcommand } => {
+                handle_import_command(subcommand).await?;
+                return Ok(());
+            }
+            Command::Backup {
+                path,
+                data_dir,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let report = backup(&local_data_dir, path).await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                    OutputFormat::Text => println!(
+                        "backed up to {}: database {}, {} files copied ({}), {} unchanged, {} removed",
+                        path.display(),
+                        format_bytes(report.database_bytes),
+                        report.copied,
+                        format_bytes(report.copied_bytes),
+                        report.unchanged,
+                        report.removed
+                    ),
+                }
+                return Ok(());
+            }
+            Command::Restore {
+                path,
+                data_dir,
+                force,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let report = restore(path, &local_data_dir, *force).await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                    OutputFormat::Text => println!(
+                        "restored {} to {}: database {}, {} files ({})",
+                        path.display(),
+                        local_data_dir.display(),
+                        format_bytes(report.database_bytes),
+                        report.files,
+                        format_bytes(report.bytes)
+                    ),
+                }
+                return Ok(());
+            }
+            Command::Export {
+                format,
+                vault,
+                file,
+                fields,
+                compress,
+                from,
+                to,
+                data_dir,
+                output,
+            } => {
+                // notes are of local days
+                let today = if matches!(format, CliExportFormat::Obsidian) {
+                    chrono::Local::now().date_naive()
+                } else {
+                    chrono::Utc::now().date_naive()
+                };
+                let from = from.unwrap_or(today);
+                let to = to.unwrap_or(from);
+                if to < from {
+                    return Err(anyhow::anyhow!("--to must not be before --from"));
+                }
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = Arc::new(open_database(&local_data_dir, false).await?);
+                let record_format = match format {
+                    CliExportFormat::Obsidian => {
+                        let Some(vault) = vault else {
+                            return Err(anyhow::anyhow!(
+                                "--vault is required with --format obsidian"
+                            ));
+                        };
+                        export_notes(db, vault, from, to, output).await?;
+                        return Ok(());
+                    }
+                    CliExportFormat::Activitywatch => {
+                        export_activitywatch(&db, from, to, file.as_deref(), output).await?;
+                        return Ok(());
+                    }
+                    CliExportFormat::Jsonl => RecordFormat::Jsonl,
+                    CliExportFormat::Csv => RecordFormat::Csv,
+                };
+
+                let out: Box<dyn std::io::Write> = match file {
+                    Some(file) => Box::new(std::io::BufWriter::new(std::fs::File::create(file)?)),
+                    None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+                };
+                let mut writer = RecordWriter::new(
+                    out,
+                    record_format,
+                    parse_fields(fields.as_deref())?,
+                    compress.map(Into::into),
+                )?;
+                let (start, _) = day_bounds(from)?;
+                let (_, end) = day_bounds(to)?;
+                let records = export_records(&db, start, end, &mut writer).await?;
+                writer.finish()?;
+                if let Some(file) = file {
+                    match output {
+                        OutputFormat::Json => println!(
+                            "{}",
+                            serde_json::to_string_pretty(&json!({
+                                "records": records,
+                                "file": file,
+                            }))?
+                        ),
+                        OutputFormat::Text => {
+                            println!("wrote {} records to {}", records, file.display())
+                        }
+                    }
+                }
+                return Ok(());
+            }
             Command::Pipe { subcommand } => {
                 handle_pipe_command(subcommand, &pipe_manager).await?;
                 return Ok(());
@@ -301,17 +549,10 @@ async fn main() -> anyhow::Result<()> {
             } => {
                 // Initialize the database
                 let local_data_dir = get_base_dir(data_dir)?;
-                let db = Arc::new(
-                    DatabaseManager::new(&format!(
-                        "{}/db.sqlite",
-                        local_data_dir.to_string_lossy()
-                    ))
-                    .await
-                    .map_err(|e| {
-                        error!("failed to initialize database: {:?}", e);
-                        e
-                    })?,
-                );
+                let db = Arc::new(open_database(&local_data_dir, false).await.map_err(|e| {
+                    error!("failed to initialize database: {:?}", e);
+                    e
+                })?);
 
                 // Create a migration worker config
                 let config = MigrationConfig::new(*batch_size, *batch_delay_ms, *continue_on_error);
@@ -501,17 +742,10 @@ async fn main() -> anyhow::Result<()> {
                     debug!("debug logging enabled");
                 }
 
-                let db = Arc::new(
-                    DatabaseManager::new(&format!(
-                        "{}/db.sqlite",
-                        local_data_dir.to_string_lossy()
-                    ))
-                    .await
-                    .map_err(|e| {
-                        error!("failed to initialize database: {:?}", e);
-                        e
-                    })?,
-                );
+                let db = Arc::new(open_database(&local_data_dir, false).await.map_err(|e| {
+                    error!("failed to initialize database: {:?}", e);
+                    e
+                })?);
                 handle_index_command(
                     local_data_dir,
                     path.to_string(),
@@ -601,26 +835,105 @@ async fn main() -> anyhow::Result<()> {
     resource_monitor.start_monitoring(Duration::from_secs(30), Some(Duration::from_secs(60)));
 
     let db = Arc::new(
-        DatabaseManager::new(&format!("{}/db.sqlite", local_data_dir.to_string_lossy()))
+        open_database(&local_data_dir, cli.encrypt)
             .await
             .map_err(|e| {
                 eprintln!("failed to initialize database: {:?}", e);
                 e
+            })?
+            .with_vector_backend(&cli.vector_store.backend(&local_data_dir))
+            .await
+            .map_err(|e| {
+                eprintln!("failed to open vector store: {:?}", e);
+                e
             })?,
     );
 
     let db_server = db.clone();
+    let pipe_host = Arc::new(ServerPipeHost::new(db.clone(), cli.port));
+    pipe_manager.set_script_host(pipe_host.clone()).await;
 
     let warning_ocr_engine_clone = cli.ocr_engine.clone();
     let warning_audio_transcription_engine_clone = cli.audio_transcription_engine.clone();
-    let monitor_ids = if cli.monitor_id.is_empty() {
-        all_monitors.iter().map(|m| m.id()).collect::<Vec<_>>()
-    } else {
-        cli.monitor_id.clone()
-    };
+    let selected_monitors =
+        select_monitors(&all_monitors, &cli.monitor_id, &cli.ignored_monitor);
+    if selected_monitors.is_empty() && !all_monitors.is_empty() && !cli.disable_vision {
+        warn!("no monitor matches --monitor-id / --ignored-monitor, vision will not record");
+    }
+    let monitor_ids = selected_monitors.iter().map(|m| m.id()).collect::<Vec<_>>();
 
     let languages = cli.unique_languages().unwrap();
-    let languages_clone = languages.clone();
+    let ocr_languages = match cli.ocr_languages() {
+        Ok(ocr_languages) => ocr_languages,
+        Err(e) => {
+            eprintln!("invalid --ocr-lang value: {}", e);
+            std::process::exit(1);
+        }
+    };
+    #[cfg(target_os = "linux")]
+    if !cli.disable_vision
+        && (cli.ocr_engine == CliOcrEngine::Tesseract
+            || cli.ocr_fallback_engine.contains(&CliOcrEngine::Tesseract))
+    {
+        if let Err(e) = validate_tesseract_languages(&ocr_languages) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    let ocr_languages_clone = ocr_languages.clone();
+    let window_filters = match cli.window_filters() {
+        Ok(window_filters) => Arc::new(window_filters),
+        Err(e) => {
+            eprintln!("invalid window filter regex: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let accessibility = Arc::new(cli.accessibility_config());
+    let redaction_policy = match cli.redaction_policy() {
+        Ok(redaction_policy) => Arc::new(redaction_policy),
+        Err(e) => {
+            eprintln!("invalid redaction pattern: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let face_blur = cli.face_blur_config().map(Arc::new);
+    let plugins = if cli.enable_plugins {
+        match WasmPlugins::load(&local_data_dir.join("plugins")) {
+            Ok(plugins) => Some(Arc::new(plugins)),
+            Err(e) => {
+                eprintln!("failed to load plugins: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let plugin_names = plugins.as_ref().map(|plugins| plugins.names().join(", "));
+    let adaptive_fps = match cli.adaptive_fps_config() {
+        Ok(adaptive_fps) => adaptive_fps,
+        Err(e) => {
+            eprintln!("invalid adaptive fps settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let screen_recording = match cli.screen_recording_config() {
+        Ok(screen_recording) => screen_recording,
+        Err(e) => {
+            eprintln!("invalid recording settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !cli.disable_vision {
+        set_cursor_options(CursorOptions {
+            show_cursor: cli.show_cursor,
+            click_indicator: cli.click_indicator,
+        });
+        // probing captures a frame, surfacing missing permissions before recording starts
+        if let Err(e) = set_screen_capturer(cli.capture_backend, selected_monitors.first()).await {
+            eprintln!("{:?} capture backend unavailable: {}", cli.capture_backend, e);
+            std::process::exit(1);
+        }
+    }
 
     let ocr_engine_clone = cli.ocr_engine.clone();
     let vad_engine = cli.vad_engine.clone();
@@ -648,16 +961,68 @@ async fn main() -> anyhow::Result<()> {
         eprintln!("invalid fps value: {}. using default of 1.0", cli.fps);
         1.0
     };
+    let monitor_fps_overrides: HashMap<u32, f64> = all_monitors
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| monitor_ids.contains(&m.id()))
+        .map(|(index, m)| (m.id(), monitor_fps(&cli.monitor_fps, index, m, fps)))
+        .collect();
+    let monitor_fps_clone = monitor_fps_overrides.clone();
+    let monitor_regions: HashMap<u32, WindowBounds> = all_monitors
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| monitor_ids.contains(&m.id()))
+        .filter_map(|(index, m)| {
+            monitor_region(&cli.capture_region, index, m).map(|region| (m.id(), region))
+        })
+        .collect();
+    let monitor_regions_clone = monitor_regions.clone();
+    let frame_storage = match cli.frame_storage() {
+        Ok(frame_storage) => frame_storage,
+        Err(e) => {
+            eprintln!("invalid frame storage settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let frame_storage_by_monitor: HashMap<u32, FrameStorage> = all_monitors
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| monitor_ids.contains(&m.id()))
+        .map(|(index, m)| {
+            let storage =
+                monitor_frame_storage(&cli.monitor_frame_storage, index, m, frame_storage);
+            (m.id(), storage)
+        })
+        .collect();
+    let frame_storage_by_monitor_clone = frame_storage_by_monitor.clone();
 
     let audio_chunk_duration = Duration::from_secs(cli.audio_chunk_duration);
+    let audio_storage = match cli.audio_storage() {
+        Ok(audio_storage) => audio_storage,
+        Err(e) => {
+            eprintln!("invalid audio storage settings: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let mut audio_manager_builder = AudioManagerBuilder::new()
         .audio_chunk_duration(audio_chunk_duration)
+        .device_audio_chunk_durations(cli.device_audio_chunk_durations())
         .vad_engine(vad_engine.into())
         .vad_sensitivity(cli.vad_sensitivity.into())
+        .preprocessing(AudioPreprocessing {
+            noise_suppression: cli.noise_suppression,
+            normalization: !cli.disable_audio_normalization,
+        })
+        .storage(audio_storage)
+        .plugins(plugins.clone())
         .languages(languages.clone())
+        .device_languages(cli.device_languages())
+        .vocabulary(cli.vocabulary.clone(), cli.app_vocabularies())
         .transcription_engine(cli.audio_transcription_engine.into())
+        .device_transcription_engines(cli.device_transcription_engines())
         .realtime(cli.enable_realtime_audio_transcription)
+        .realtime_transcription_engine(cli.realtime_audio_transcription_engine.clone().into())
         .enabled_devices(audio_devices)
         .deepgram_api_key(cli.deepgram_api_key.clone())
         .output_path(PathBuf::from(output_path_clone.clone().to_string()));
@@ -680,16 +1045,30 @@ async fn main() -> anyhow::Result<()> {
                     output_path_clone.clone(),
                     fps,
                     Duration::from_secs(cli.video_chunk_duration),
-                    Arc::new(cli.ocr_engine.clone().into()),
+                    Arc::new(cli.ocr_engine_chain()),
                     monitor_ids_clone.clone(),
-                    cli.use_pii_removal,
+                    monitor_fps_clone.clone(),
+                    monitor_regions_clone.clone(),
+                    frame_storage_by_monitor_clone.clone(),
+                    redaction_policy.clone(),
+                    face_blur.clone(),
+                    plugins.clone(),
                     cli.disable_vision,
                     &vision_handle,
-                    &cli.ignored_windows,
-                    &cli.included_windows,
-                    languages_clone.clone(),
+                    window_filters.clone(),
+                    ocr_languages_clone.clone(),
                     cli.capture_unfocused_windows,
+                    cli.focused_window_only,
                     cli.enable_realtime_audio_transcription,
+                    cli.phash_threshold,
+                    adaptive_fps,
+                    cli.privacy_policy(),
+                    screen_recording,
+                    cli.scroll_stitching,
+                    cli.detect_codes,
+                    cli.image_embeddings,
+                    accessibility.clone(),
+                    cli.ocr_quality_config(),
                 );
 
                 let result = tokio::select! {
@@ -724,9 +1103,91 @@ async fn main() -> anyhow::Result<()> {
     #[cfg(feature = "llm")]
     debug!("LLM initialized");
 
-    let server = SCServer::new(
+    let cold_storage = match cli.cold_store() {
+        Ok(store) => store.map(|store| {
+            Arc::new(ColdStorage::new(
+                db.clone(),
+                store,
+                &local_data_dir.join("data").to_string_lossy(),
+                cli.cold_storage_after_days,
+            ))
+        }),
+        Err(e) => {
+            eprintln!("invalid cold storage settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    #[cfg(feature = "postgres")]
+    let central_database = match &cli.central_database {
+        Some(url) => match screenpipe_db::PostgresDatabase::connect(url).await {
+            Ok(central) => Some(Arc::new(central)),
+            Err(e) => {
+                eprintln!("failed to connect to the central database: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    #[cfg(not(feature = "postgres"))]
+    if cli.central_database.is_some() {
+        eprintln!("--central-database needs a build with the postgres feature");
+        std::process::exit(1);
+    }
+
+    let retention = cli.retention_policy().map(|policy| {
+        let retention = Retention::new(
+            db.clone(),
+            policy,
+            &local_data_dir.join("data").to_string_lossy(),
+        );
+        Arc::new(match &cold_storage {
+            Some(cold_storage) => retention.with_cold_storage(cold_storage.clone()),
+            None => retention,
+        })
+    });
+
+    let llm = cli.llm().map(Arc::new);
+
+    let digests = cli.daily_digest.then(|| {
+        let digest_llm = match &cli.digest_llm_url {
+            Some(url) => Some(Llm {
+                provider: LlmProvider::OpenAi,
+                url: url.clone(),
+                model: cli.digest_llm_model.clone(),
+                api_key: std::env::var("SCREENPIPE_DIGEST_LLM_API_KEY").ok(),
+            }),
+            None => llm.as_deref().cloned(),
+        };
+        Arc::new(Digests::new(db.clone(), digest_llm))
+    });
+
+    let obsidian = cli
+        .obsidian_vault
+        .clone()
+        .map(|vault| Arc::new(ObsidianExporter::new(db.clone(), vault)));
+
+    let storage = Arc::new(StorageManager::new(
+        db.clone(),
+        local_data_dir.clone(),
+        cli.max_storage,
+    ));
+
+    let power_throttle = cli.power_policy().map(|policy| {
+        let engine = OcrEngine::from(cli.throttle_ocr_engine.clone());
+        let cheap_ocr = match create_ocr_provider(&engine) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                warn!("throttling without a cheaper ocr engine: {}", e);
+                None
+            }
+        };
+        Arc::new(PowerThrottle::new(db.clone(), policy, cheap_ocr))
+    });
+
+    let mut server = SCServer::new(
         db_server,
-        SocketAddr::from(([127, 0, 0, 1], cli.port)),
+        SocketAddr::new(cli.listen, cli.port),
         local_data_dir_clone_2,
         pipe_manager.clone(),
         cli.disable_vision,
@@ -734,6 +1195,52 @@ async fn main() -> anyhow::Result<()> {
         cli.enable_ui_monitoring,
         audio_manager.clone(),
     );
+    if let Some(retention) = &retention {
+        server = server.with_retention(retention.clone());
+    }
+    if let Some(cold_storage) = &cold_storage {
+        server = server.with_cold_storage(cold_storage.clone());
+    }
+    if let Some(digests) = &digests {
+        server = server.with_digests(digests.clone());
+    }
+    if let Some(llm) = &llm {
+        server = server.with_llm(llm.clone());
+    }
+    if let Some(power_throttle) = &power_throttle {
+        server = server.with_power_throttle(power_throttle.clone());
+    }
+    if cli.require_auth {
+        if db.list_api_tokens().await?.is_empty() {
+            warn!(
+                "--require-auth is set but there are no tokens, \
+                 create one with `screenpipe token create`"
+            );
+        }
+        server = server.with_auth(Arc::new(ApiAuth::new(db.clone())));
+    }
+    if let Some(rate_limit) = cli.rate_limit {
+        server = server.with_rate_limit(Arc::new(RateLimiter::new(rate_limit)));
+    }
+    server = server.with_max_body_bytes((cli.max_request_body_mb << 20) as usize);
+    server = server.with_pipe_host(pipe_host);
+    let tls_source = cli.tls_source(&local_data_dir);
+    let mut tls_fingerprint = None;
+    if let Some(tls_source) = &tls_source {
+        let files = tls_source.files(cli.listen)?;
+        tls_fingerprint = std::fs::read_to_string(&files.cert)
+            .ok()
+            .and_then(|pem| certificate_fingerprint(&pem));
+        server = server.with_tls(rustls_config(&files).await?);
+    }
+    if !cli.listen.is_loopback() && (!cli.require_auth || tls_source.is_none()) {
+        warn!(
+            "listening on {} without --require-auth and TLS, anyone who can reach it \
+             can read what was recorded",
+            cli.listen
+        );
+    }
+    let server = server.with_storage(storage.clone());
 
     // print screenpipe in gradient
     println!("\n\n{}", DISPLAY.truecolor(147, 112, 219).bold());
@@ -752,6 +1259,12 @@ async fn main() -> anyhow::Result<()> {
     println!("│ setting                │ value                              │");
     println!("├────────────────────────┼────────────────────────────────────┤");
     println!("│ fps                    │ {:<34} │", cli.fps);
+    if let Some(adaptive_fps) = adaptive_fps {
+        println!(
+            "│ adaptive fps           │ {:<34} │",
+            format!("{} - {}", adaptive_fps.min_fps, adaptive_fps.max_fps)
+        );
+    }
     println!(
         "│ audio chunk duration   │ {:<34} │",
         format!("{} seconds", cli.audio_chunk_duration)
@@ -760,13 +1273,122 @@ async fn main() -> anyhow::Result<()> {
         "│ video chunk duration   │ {:<34} │",
         format!("{} seconds", cli.video_chunk_duration)
     );
+    println!("│ listen                 │ {:<34} │", cli.listen);
     println!("│ port                   │ {:<34} │", cli.port);
+    println!(
+        "│ tls                    │ {:<34} │",
+        match &tls_source {
+            Some(TlsSource::Files { .. }) => "certificate files",
+            Some(TlsSource::SelfSigned { .. }) => "self-signed",
+            None => "false",
+        }
+    );
     println!(
         "│ realtime audio enabled │ {:<34} │",
         cli.enable_realtime_audio_transcription
     );
+    println!(
+        "│ realtime audio engine  │ {:<34} │",
+        format!("{:?}", cli.realtime_audio_transcription_engine)
+    );
     println!("│ audio disabled         │ {:<34} │", cli.disable_audio);
     println!("│ vision disabled        │ {:<34} │", cli.disable_vision);
+    println!("│ capture backend        │ {:<34} │", screen_capturer().name());
+    if let Some(screen_recording) = screen_recording {
+        println!(
+            "│ video recording        │ {:<34} │",
+            format!(
+                "{} {:?} at {} fps",
+                screen_recording.codec.name(),
+                screen_recording.encoder,
+                screen_recording.fps
+            )
+        );
+    }
+    println!(
+        "│ frame storage          │ {:<34} │",
+        format!("{}", frame_storage)
+    );
+    println!("│ scroll stitching       │ {:<34} │", cli.scroll_stitching);
+    println!(
+        "│ blur faces             │ {:<34} │",
+        cli.face_blur_config()
+            .map(|face_blur| face_blur.apps().join(", "))
+            .unwrap_or_else(|| "false".to_string())
+    );
+    println!(
+        "│ plugins                │ {:<34} │",
+        plugin_names.unwrap_or_else(|| "false".to_string())
+    );
+    println!("│ detect codes           │ {:<34} │", cli.detect_codes);
+    println!("│ image embeddings       │ {:<34} │", cli.image_embeddings);
+    println!("│ text embeddings        │ {:<34} │", cli.text_embeddings);
+    match &retention {
+        Some(retention) => {
+            let policy = retention.policy();
+            println!(
+                "│ retain frames / ocr    │ {:<34} │",
+                format!("{} / {}", policy.screenshots, policy.ocr_text)
+            );
+            println!(
+                "│ retain audio / text    │ {:<34} │",
+                format!("{} / {}", policy.audio, policy.transcripts)
+            );
+        }
+        None => println!("│ retention              │ {:<34} │", false),
+    }
+    println!("│ encryption             │ {:<34} │", media_key().is_some());
+    println!(
+        "│ cold storage           │ {:<34} │",
+        cold_storage
+            .as_ref()
+            .map(|cold_storage| format!(
+                "after {}d to {}",
+                cold_storage.after_days(),
+                cold_storage.store()
+            ))
+            .unwrap_or_else(|| "false".to_string())
+    );
+    println!(
+        "│ daily digest           │ {:<34} │",
+        digests
+            .as_ref()
+            .map(|digests| match digests.llm() {
+                Some(llm) => format!("summarized by {}", llm.model),
+                None => "true".to_string(),
+            })
+            .unwrap_or_else(|| "false".to_string())
+    );
+    println!(
+        "│ llm                    │ {:<34} │",
+        llm.as_ref()
+            .map(|llm| format!("{} {}", llm.provider, llm.model))
+            .unwrap_or_else(|| "false".to_string())
+    );
+    println!("│ require auth           │ {:<34} │", cli.require_auth);
+    println!(
+        "│ rate limit             │ {:<34} │",
+        cli.rate_limit
+            .map(|rate_limit| format!("{} requests/min", rate_limit))
+            .unwrap_or_else(|| "false".to_string())
+    );
+    println!(
+        "│ central database       │ {:<34} │",
+        match &cli.central_database {
+            Some(_) => cli.machine_id(),
+            None => "false".to_string(),
+        }
+    );
+    println!(
+        "│ max storage            │ {:<34} │",
+        cli.max_storage
+            .map(|quota| quota.to_string())
+            .unwrap_or_else(|| "unlimited".to_string())
+    );
+    println!(
+        "│ vector store           │ {:<34} │",
+        format!("{:?}", cli.vector_store)
+    );
     println!(
         "│ audio engine           │ {:<34} │",
         format!("{:?}", warning_audio_transcription_engine_clone)
@@ -775,6 +1397,25 @@ async fn main() -> anyhow::Result<()> {
         "│ ocr engine             │ {:<34} │",
         format!("{:?}", ocr_engine_clone)
     );
+    println!(
+        "│ ocr languages          │ {:<34} │",
+        format_cell(
+            &ocr_languages
+                .iter()
+                .map(|language| language.to_string())
+                .collect::<Vec<_>>()
+                .join("+"),
+            VALUE_WIDTH
+        )
+    );
+    println!(
+        "│ ocr min confidence     │ {:<34} │",
+        format!(
+            "words: {}, frames: {}",
+            cli.min_word_confidence.map_or("off".to_string(), |c| c.to_string()),
+            cli.min_frame_confidence.map_or("off".to_string(), |c| c.to_string())
+        )
+    );
     println!(
         "│ vad engine             │ {:<34} │",
         format!("{:?}", vad_engine_clone)
@@ -783,6 +1424,22 @@ async fn main() -> anyhow::Result<()> {
         "│ vad sensitivity        │ {:<34} │",
         format!("{:?}", vad_sensitivity_clone)
     );
+    println!("│ noise suppression      │ {:<34} │", cli.noise_suppression);
+    println!(
+        "│ audio normalization    │ {:<34} │",
+        !cli.disable_audio_normalization
+    );
+    let audio_format = format!("{:?}", cli.audio_format).to_lowercase();
+    println!(
+        "│ audio storage          │ {:<34} │",
+        if audio_storage.transcript_only {
+            "transcript only".to_string()
+        } else if audio_storage.format.is_lossy() {
+            format!("{} {}kbps", audio_format, audio_storage.bitrate_kbps)
+        } else {
+            audio_format
+        }
+    );
     println!(
         "│ data directory         │ {:<34} │",
         local_data_dir_clone.display()
@@ -795,6 +1452,7 @@ async fn main() -> anyhow::Result<()> {
     println!("│ local llm              │ {:<34} │", cli.enable_llm);
 
     println!("│ use pii removal        │ {:<34} │", cli.use_pii_removal);
+    println!("│ blur redacted text     │ {:<34} │", cli.blur_redacted);
     println!(
         "│ ignored windows        │ {:<34} │",
         format_cell(&format!("{:?}", &ignored_windows_clone), VALUE_WIDTH)
@@ -815,6 +1473,24 @@ async fn main() -> anyhow::Result<()> {
         "│ capture unfocused wins │ {:<34} │",
         cli.capture_unfocused_windows
     );
+    println!(
+        "│ focused window only    │ {:<34} │",
+        cli.focused_window_only
+    );
+    println!(
+        "│ cursor                 │ {:<34} │",
+        format!(
+            "shown: {}, clicks: {}",
+            cli.show_cursor, cli.click_indicator
+        )
+    );
+    println!(
+        "│ privacy pause          │ {:<34} │",
+        format!(
+            "password fields: {}, private: {}",
+            cli.pause_on_password_fields, cli.pause_on_private_browsing
+        )
+    );
     println!(
         "│ auto-destruct pid      │ {:<34} │",
         cli.auto_destruct_pid.unwrap_or(0)
@@ -882,7 +1558,24 @@ async fn main() -> anyhow::Result<()> {
     } else {
         let total_monitors = monitor_ids.len();
         for (_, monitor) in monitor_ids.iter().enumerate().take(MAX_ITEMS_TO_DISPLAY) {
-            let monitor_str = format!("id: {}", monitor);
+            let mut monitor_str = match monitor_fps_overrides.get(monitor) {
+                Some(monitor_fps) if *monitor_fps != fps => {
+                    format!("id: {} ({} fps)", monitor, monitor_fps)
+                }
+                _ => format!("id: {}", monitor),
+            };
+            if let Some(region) = monitor_regions.get(monitor) {
+                monitor_str.push_str(&format!(
+                    " {}x{}+{}+{}",
+                    region.width, region.height, region.x, region.y
+                ));
+            }
+            match frame_storage_by_monitor.get(monitor) {
+                Some(storage) if *storage != frame_storage => {
+                    monitor_str.push_str(&format!(" [{}]", storage));
+                }
+                _ => {}
+            }
             let formatted_monitor = format_cell(&monitor_str, VALUE_WIDTH);
             println!("│ {:<22} │ {:<34} │", "", formatted_monitor);
         }
@@ -980,6 +1673,10 @@ async fn main() -> anyhow::Result<()> {
 
     println!("└────────────────────────┴────────────────────────────────────┘");
 
+    if let Some(fingerprint) = &tls_fingerprint {
+        println!("tls certificate sha-256 fingerprint: {}", fingerprint);
+    }
+
     // Add warning for cloud arguments and telemetry
     if warning_audio_transcription_engine_clone == CliAudioTranscriptionEngine::Deepgram
         || warning_ocr_engine_clone == CliOcrEngine::Unstructured
@@ -1029,6 +1726,117 @@ async fn main() -> anyhow::Result<()> {
         });
     }
 
+    if cli.text_embeddings {
+        tokio::spawn(run_text_embedder(db.clone()));
+    }
+
+    if let Some(language) = cli.translate_transcriptions.clone() {
+        match &llm {
+            Some(llm) => {
+                tokio::spawn(run_transcript_translator(db.clone(), llm.clone(), language));
+            }
+            None => warn!("--translate-transcriptions needs an --llm-provider, not translating"),
+        }
+    }
+
+    if let Some(retention) = retention {
+        tokio::spawn(run_retention(retention));
+    }
+
+    if media_key().is_some() {
+        tokio::spawn(run_media_encryption(db.clone()));
+    }
+
+    let blob_store =
+        FrameBlobStore::new(db.clone(), &local_data_dir.join("data").to_string_lossy());
+    tokio::spawn(run_queued_deletions(
+        db.clone(),
+        blob_store,
+        cold_storage.clone(),
+    ));
+
+    if let Some(cold_storage) = cold_storage {
+        tokio::spawn(run_cold_storage(cold_storage));
+    }
+
+    if let Some(digests) = digests {
+        tokio::spawn(run_daily_digests(digests));
+    }
+
+    if let Some(obsidian) = obsidian {
+        tokio::spawn(run_obsidian_export(obsidian));
+    }
+
+    if let Some(url) = &cli.activitywatch_url {
+        let bridge = ActivityWatchBridge::new(db.clone(), url, &hostname())?;
+        tokio::spawn(run_activitywatch_bridge(Arc::new(bridge)));
+    }
+
+    if let Some(calendar) = &cli.calendar {
+        let sync = CalendarSync::new(
+            db.clone(),
+            CalendarSource::parse(calendar)?,
+            cli.calendar_user.clone(),
+            std::env::var("SCREENPIPE_CALENDAR_PASSWORD").ok(),
+        );
+        tokio::spawn(run_calendar_sync(Arc::new(sync)));
+    }
+
+    if cli.detect_meetings {
+        tokio::spawn(run_meeting_detection(Arc::new(MeetingDetector::new(
+            db.clone(),
+        ))));
+        if let Some(llm) = &llm {
+            tokio::spawn(run_meeting_notes(db.clone(), llm.clone()));
+        }
+    }
+
+    if cli.enable_clipboard {
+        let filter = ClipboardFilter::new(&cli.clipboard_blocklist, &cli.clipboard_ignored_apps)?;
+        let monitor = ClipboardMonitor::new(db.clone(), filter);
+        tokio::spawn(run_clipboard_monitor(Arc::new(monitor)));
+    }
+
+    if cli.enable_input_activity {
+        tokio::spawn(run_input_activity_monitor(db.clone()));
+    }
+
+    if let Some(policy) = cli.idle_policy() {
+        let audio_manager = (!cli.disable_audio).then(|| audio_manager.clone());
+        let pause = IdlePause::new(db.clone(), audio_manager, policy);
+        tokio::spawn(run_idle_pause(pause));
+    }
+
+    if let Some(power_throttle) = power_throttle {
+        tokio::spawn(run_power_throttle(power_throttle));
+    }
+
+    #[cfg(feature = "postgres")]
+    if let Some(central_database) = central_database {
+        tokio::spawn(screenpipe_server::central_database::run_central_sync(
+            db.clone(),
+            central_database,
+            cli.machine_id(),
+        ));
+    }
+
+    if storage.quota().is_some() {
+        tokio::spawn(run_storage_quota(storage));
+    }
+
+    if cli.vector_store != CliVectorStore::Sqlite {
+        // copy the embeddings stored so far, then fold new ones into the ANN index now and then
+        let db = db.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = db.build_vector_indexes().await {
+                    error!("failed to build vector indexes: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+            }
+        });
+    }
+
     // Start pipes
     info!("starting pipes");
     let pipes = pipe_manager.list_pipes().await;
@@ -1200,9 +2008,109 @@ async fn handle_pipe_command(
             }
         }
 
+        PipeCommand::Install {
+            url,
+            output,
+            port,
+            registry,
+            enable,
+            yes,
+        } if !matches!(PipeSource::parse(url), PipeSource::Download(_)) => {
+            let installed = pipe_manager
+                .install_pipe(&PipeSource::parse(url), registry, *yes)
+                .await?;
+            match output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({
+                        "data": {
+                            "pipe_id": installed.id,
+                            "install": installed.record,
+                            "permissions": installed.permissions,
+                            "held_permissions": installed.held_permissions,
+                        },
+                        "success": true
+                    }))?
+                ),
+                OutputFormat::Text => print_installed_pipe(&installed),
+            }
+            if !installed.held_permissions.is_empty() {
+                // the version running still had its old permissions but shouldn't go on
+                let _ = post_pipe_actions(&client, *port, &installed.id, &["disable"]).await;
+            } else if *enable {
+                enable_pipe(&client, *port, &installed.id, pipe_manager).await?;
+            } else if let OutputFormat::Text = output {
+                println!("enable it with `screenpipe pipe enable {}`", installed.id);
+            }
+        }
+
+        PipeCommand::Outdated { output, registry } => {
+            let updates = pipe_manager.check_updates(registry).await?;
+            match output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({
+                        "data": updates,
+                        "success": true
+                    }))?
+                ),
+                OutputFormat::Text => {
+                    if updates.is_empty() {
+                        println!("no pipes installed from git or the registry");
+                    }
+                    for update in updates {
+                        let status = match (&update.error, &update.latest, &update.pinned) {
+                            (Some(error), _, _) => format!("failed to check: {}", error),
+                            (None, Some(latest), _) => format!("{} available", latest),
+                            (None, None, Some(pinned)) => format!("pinned to {}", pinned),
+                            (None, None, None) => "up to date".to_string(),
+                        };
+                        println!("  {}: {}, {}", update.id, update.installed, status);
+                    }
+                }
+            }
+        }
+
+        PipeCommand::Upgrade {
+            id,
+            port,
+            registry,
+            yes,
+        } => {
+            let info = pipe_manager
+                .get_pipe_info(id)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("pipe {} not found", id))?;
+            let record = InstallRecord::from_config(&info.config).ok_or_else(|| {
+                anyhow::anyhow!("pipe {} wasn't installed from git or the registry", id)
+            })?;
+            if let Some(pinned) = &record.pinned {
+                anyhow::bail!(
+                    "pipe {} is pinned to {}, install it at another version to change it",
+                    id,
+                    pinned
+                );
+            }
+            let installed = pipe_manager
+                .install_pipe(&record.source(), registry, *yes)
+                .await?;
+            print_installed_pipe(&installed);
+            if !installed.held_permissions.is_empty() {
+                let _ = post_pipe_actions(&client, *port, id, &["disable"]).await;
+            } else if info.enabled {
+                // restarted so the running pipe is the new version
+                match post_pipe_actions(&client, *port, id, &["disable", "enable"]).await {
+                    Ok(()) => println!("pipe {} restarted in running server", id),
+                    Err(_) => println!("note: server not running, pipe will start on next launch"),
+                }
+            }
+        }
+
         #[allow(deprecated)]
         PipeCommand::Download { url, output, port }
-        | PipeCommand::Install { url, output, port } => {
+        | PipeCommand::Install {
+            url, output, port, ..
+        } => {
             match client
                 .post(format!("{}:{}/pipes/download", server_url, port))
                 .json(&json!({ "url": url }))
@@ -1274,22 +2182,7 @@ async fn handle_pipe_command(
             }
         }
         PipeCommand::Enable { id, port } => {
-            match client
-                .post(format!("{}:{}/pipes/enable", server_url, port))
-                .json(&json!({ "pipe_id": id }))
-                .send()
-                .await
-            {
-                Ok(response) if response.status().is_success() => {
-                    println!("pipe {} enabled in running server", id);
-                }
-                _ => {
-                    pipe_manager
-                        .update_config(id, json!({"enabled": true}))
-                        .await?;
-                    println!("note: server not running, updated config only. pipe will start on next server launch");
-                }
-            }
+            enable_pipe(&client, *port, id, pipe_manager).await?;
         }
 
         PipeCommand::Disable { id, port } => {
@@ -1390,3 +2283,308 @@ async fn handle_pipe_command(
     }
     Ok(())
 }
+
+/// Posts `actions`, e.g. `disable`, for pipe `id` to the server running on `port`.
+async fn post_pipe_actions(
+    client: &reqwest::Client,
+    port: u16,
+    id: &str,
+    actions: &[&str],
+) -> Result<(), reqwest::Error> {
+    for action in actions {
+        client
+            .post(format!("http://localhost:{}/pipes/{}", port, action))
+            .json(&json!({ "pipe_id": id }))
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+    Ok(())
+}
+
+/// Enables pipe `id` in the server running on `port`, else in its config.
+async fn enable_pipe(
+    client: &reqwest::Client,
+    port: u16,
+    id: &str,
+    pipe_manager: &Arc<PipeManager>,
+) -> anyhow::Result<()> {
+    match client
+        .post(format!("http://localhost:{}/pipes/enable", port))
+        .json(&json!({ "pipe_id": id }))
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => {
+            println!("pipe {} enabled in running server", id);
+        }
+        _ => {
+            pipe_manager
+                .update_config(id, json!({"enabled": true}))
+                .await?;
+            println!("note: server not running, updated config only. pipe will start on next server launch");
+        }
+    }
+    Ok(())
+}
+
+fn print_installed_pipe(installed: &InstalledPipe) {
+    match &installed.record {
+        Some(record) => {
+            println!(
+                "installed pipe {} from {} at commit {}",
+                installed.id,
+                record.source,
+                &record.commit[..record.commit.len().min(12)]
+            );
+            match &record.pinned {
+                Some(pinned) => println!("  pinned to {}", pinned),
+                None => println!("  follows the latest version, see `screenpipe pipe outdated`"),
+            }
+        }
+        None => println!("installed pipe {}", installed.id),
+    }
+    if !installed.held_permissions.is_empty() {
+        println!(
+            "  asks for more permissions: {}",
+            installed.held_permissions.join(", ")
+        );
+        println!("  it keeps its old ones and is disabled, run again with --yes to grant them");
+    }
+    // what a third-party pipe can reach, before it is enabled
+    if let Some(permissions) = &installed.permissions {
+        let list = |items: &[String]| match items.is_empty() {
+            true => "none".to_string(),
+            false => items.join(", "),
+        };
+        println!("  permissions:");
+        println!("    notify: {}", permissions.notify);
+        println!("    events: {}", list(&permissions.events));
+        println!("    endpoints: {}", list(&permissions.endpoints));
+        println!("    images: {}", permissions.images);
+        println!("    http: {}", list(&permissions.http));
+    }
+}
+
+/// Writes the Obsidian notes of `from..=to`.
+async fn export_notes(
+    db: Arc<DatabaseManager>,
+    vault: &Path,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    output: &OutputFormat,
+) -> anyhow::Result<()> {
+    let exporter = ObsidianExporter::new(db, vault.to_path_buf());
+    let mut reports = Vec::new();
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        let report = exporter.export_day(date).await?;
+        if let OutputFormat::Text = output {
+            println!(
+                "wrote {}: {} highlights, {} frame images, {} transcriptions",
+                report.path.display(),
+                report.highlights,
+                report.images,
+                report.transcriptions
+            );
+        }
+        reports.push(report);
+    }
+    if let OutputFormat::Json = output {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+    Ok(())
+}
+
+/// Writes the ActivityWatch buckets of `from..=to` to `file`, or stdout.
+async fn export_activitywatch(
+    db: &DatabaseManager,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    file: Option<&Path>,
+    output: &OutputFormat,
+) -> anyhow::Result<()> {
+    let (start, _) = day_bounds(from)?;
+    let (_, end) = day_bounds(to)?;
+    let buckets = export_buckets(db, start, end).await?;
+    let document = serde_json::to_string_pretty(&import_document(&buckets))?;
+    let Some(file) = file else {
+        println!("{}", document);
+        return Ok(());
+    };
+    std::fs::write(file, document)?;
+    let events: usize = buckets.iter().map(|bucket| bucket.events.len()).sum();
+    match output {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({"events": events, "file": file}))?
+        ),
+        OutputFormat::Text => println!(
+            "wrote {} events to {}, import it in activitywatch",
+            events,
+            file.display()
+        ),
+    }
+    Ok(())
+}
+
+async fn handle_import_command(command: &ImportCommand) -> anyhow::Result<()> {
+    match command {
+        ImportCommand::Audio {
+            path,
+            audio_transcription_engine,
+            language,
+            device,
+            no_copy,
+            deepgram_api_key,
+            data_dir,
+            output,
+        } => {
+            let local_data_dir = get_base_dir(data_dir)?;
+            set_whisper_models_dir(local_data_dir.join("models"));
+            let db = open_database(&local_data_dir, false).await?;
+            let engine = audio_transcription_engine.clone().into();
+            let backend = standalone_stt_engine(&engine, deepgram_api_key.clone()).await?;
+            let import = AudioImport {
+                engine,
+                languages: language.clone(),
+                device: device.clone(),
+                copy_to: (!no_copy).then(|| local_data_dir.join("data")),
+            };
+            let report = import_audio(&db, backend.as_ref(), &import, path).await?;
+            match output {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                OutputFormat::Text => {
+                    for imported in &report.imported {
+                        println!(
+                            "imported {} recorded at {}: {} transcriptions",
+                            imported.source.display(),
+                            imported.recorded_at.with_timezone(&chrono::Local),
+                            imported.transcriptions
+                        );
+                    }
+                    for failed in &report.failed {
+                        println!(
+                            "failed to import {}: {}",
+                            failed.source.display(),
+                            failed.error
+                        );
+                    }
+                    println!(
+                        "{} imported, {} imported before, {} failed",
+                        report.imported.len(),
+                        report.skipped.len(),
+                        report.failed.len()
+                    );
+                }
+            }
+        }
+        ImportCommand::Frames {
+            path,
+            fps,
+            ocr_engine,
+            language,
+            device,
+            data_dir,
+            output,
+        } => {
+            let local_data_dir = get_base_dir(data_dir)?;
+            set_models_dir(local_data_dir.join("models"));
+            let db = open_database(&local_data_dir, false).await?;
+            let import = FrameImport {
+                ocr_engine: ocr_engine.clone(),
+                languages: language.clone(),
+                device: device.clone(),
+                fps: *fps,
+                output_path: local_data_dir.join("data"),
+            };
+            let report = import_frames(&db, &import, path).await?;
+            match output {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                OutputFormat::Text => {
+                    for imported in &report.imported {
+                        println!(
+                            "imported {} frames of {} from {}, {} characters of text",
+                            imported.frames,
+                            imported.source.display(),
+                            imported.first_frame_at.with_timezone(&chrono::Local),
+                            imported.text_chars
+                        );
+                    }
+                    for failed in &report.failed {
+                        println!(
+                            "failed to import {}: {}",
+                            failed.source.display(),
+                            failed.error
+                        );
+                    }
+                    println!(
+                        "{} imported, {} imported before, {} failed",
+                        report.imported.len(),
+                        report.skipped.len(),
+                        report.failed.len()
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_token_command(command: &TokenCommand) -> anyhow::Result<()> {
+    match command {
+        TokenCommand::Create {
+            name,
+            scopes,
+            data_dir,
+            output,
+        } => {
+            let db = open_database(&get_base_dir(data_dir)?, false).await?;
+            let scopes: Vec<_> = scopes.iter().cloned().map(Into::into).collect();
+            let (stored, token) = create_token(&db, name, &scopes).await?;
+            match output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({"token": token, "data": stored}))?
+                ),
+                OutputFormat::Text => {
+                    println!("created token {} ({}): {}", stored.id, stored.scopes, token);
+                    println!("it is not shown again, send it as `Authorization: Bearer <token>`");
+                }
+            }
+        }
+        TokenCommand::List { data_dir, output } => {
+            let db = open_database(&get_base_dir(data_dir)?, false).await?;
+            let tokens = db.list_api_tokens().await?;
+            match output {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({"data": tokens, "success": true}))?
+                ),
+                OutputFormat::Text => {
+                    println!("api tokens:");
+                    for token in tokens {
+                        println!(
+                            "  {} {} ({}), last used {}",
+                            token.id,
+                            token.name,
+                            token.scopes,
+                            token
+                                .last_used_at
+                                .map(|used| used.to_rfc3339())
+                                .unwrap_or_else(|| "never".to_string())
+                        );
+                    }
+                }
+            }
+        }
+        TokenCommand::Revoke { id, data_dir } => {
+            let db = open_database(&get_base_dir(data_dir)?, false).await?;
+            if db.delete_api_token(*id).await? {
+                println!("token {} revoked", id);
+            } else {
+                println!("no token {}", id);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use serde::Deserialize;
+
+const MAX_TITLE_CHARS: usize = 200;
+
+/// Pins a frame, a moment or, with neither, now. The media of pinned frames is kept by
+/// retention and the storage quota.
+#[derive(OaSchema, Deserialize, Debug, Default)]
+pub struct BookmarkRequest {
+    #[serde(default)]
+    pub frame_id: Option<i64>,
+    /// Pins the last frame captured at or before it
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// e.g. "signed the contract"
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl BookmarkRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.frame_id.is_some() && self.timestamp.is_some() {
+            return Err("a bookmark takes a frame_id or a timestamp, not both".to_string());
+        }
+        if self
+            .title
+            .as_deref()
+            .is_some_and(|title| title.trim().chars().count() > MAX_TITLE_CHARS)
+        {
+            return Err(format!(
+                "title must be at most {} characters",
+                MAX_TITLE_CHARS
+            ));
+        }
+        Ok(())
+    }
+
+    /// The moment pinned when no frame is given.
+    pub fn timestamp(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.frame_id {
+            Some(_) => None,
+            None => Some(self.timestamp.unwrap_or(now)),
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+    }
+}
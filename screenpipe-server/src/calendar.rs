@@ -0,0 +1,937 @@
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use regex::Regex;
+use reqwest::{header::CONTENT_TYPE, Client, Method, RequestBuilder, Url};
+use screenpipe_db::DatabaseManager;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(900);
+// Meetings are read this far back, and ahead so the ones about to start are tagged
+const SYNC_DAYS_BACK: i64 = 30;
+const SYNC_DAYS_AHEAD: i64 = 1;
+// Periods of a recurring event walked at most, e.g. days of a daily standup
+const MAX_PERIODS: i64 = 100_000;
+const MAX_TAG_CHARS: usize = 100;
+pub const MEETING_TAG: &str = "meeting";
+// Windows time zone names Outlook and Exchange write as TZID, and the IANA zone of their
+// main region, from the CLDR windowsZones table
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Greenland Standard Time", "America/Godthab"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Central Asia Standard Time", "Asia/Almaty"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Myanmar Standard Time", "Asia/Yangon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+];
+
+/// Where meetings are read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalendarSource {
+    /// An .ics file
+    File(PathBuf),
+    /// An .ics published at a URL, e.g. the secret iCal address of a calendar
+    Feed(Url),
+    /// A CalDAV calendar collection
+    CalDav(Url),
+}
+
+impl CalendarSource {
+    /// A path, an http(s) URL of an .ics or of a CalDAV calendar, or a webcal:// URL.
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = source.trim();
+        if let Some(rest) = source.strip_prefix("webcal://") {
+            let url = Url::parse(&format!("https://{}", rest))
+                .map_err(|e| anyhow!("invalid url: {}", e))?;
+            return Ok(Self::Feed(url));
+        }
+        if source.starts_with("http://") || source.starts_with("https://") {
+            let url = Url::parse(source).map_err(|e| anyhow!("invalid url: {}", e))?;
+            if url.path().ends_with(".ics") {
+                return Ok(Self::Feed(url));
+            }
+            return Ok(Self::CalDav(url));
+        }
+        if source.is_empty() {
+            return Err(anyhow!("calendar must be a file or a url"));
+        }
+        Ok(Self::File(PathBuf::from(source)))
+    }
+}
+
+impl fmt::Display for CalendarSource {
+    // URLs are left out, secret iCal addresses hold their token in the path
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Feed(url) => write!(f, "the calendar at {}", url.host_str().unwrap_or("")),
+            Self::CalDav(url) => write!(f, "caldav at {}", url.host_str().unwrap_or("")),
+        }
+    }
+}
+
+/// A meeting, or an occurrence of a recurring one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    /// UID of the event, with the start of the occurrence for recurring ones
+    pub uid: String,
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// The organizer and the attendees who didn't decline, rooms left out
+    pub attendees: Vec<String>,
+    pub location: Option<String>,
+}
+
+impl Meeting {
+    /// `meeting`, the title and the attendees, what is captured during it is tagged with.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec![MEETING_TAG.to_string()];
+        for name in std::iter::once(&self.title)
+            .chain(&self.attendees)
+            .map(|text| tag(text))
+        {
+            if !name.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                tags.push(name);
+            }
+        }
+        tags
+    }
+
+    /// Note of the meeting's annotation, e.g. "Standup with Alice, Bob".
+    pub fn note(&self) -> String {
+        if self.attendees.is_empty() {
+            return self.title.clone();
+        }
+        format!("{} with {}", self.title, self.attendees.join(", "))
+    }
+}
+
+/// Stored tags are read back comma separated.
+pub(crate) fn tag(text: &str) -> String {
+    text.replace(',', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_TAG_CHARS)
+        .collect()
+}
+
+struct Property {
+    name: String,
+    params: HashMap<String, String>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Lines of an iCalendar text, the folded ones joined back.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// `NAME;PARAM=value;PARAM="quoted":value`
+fn parse_property(line: &str) -> Option<Property> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quoted = false;
+    let mut chars = line.char_indices();
+    let value_start = loop {
+        let (i, c) = chars.next()?;
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => parts.push(std::mem::take(&mut part)),
+            ':' if !quoted => {
+                parts.push(part);
+                break i + 1;
+            }
+            c => part.push(c),
+        }
+    };
+    let mut parts = parts.into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.trim().to_ascii_uppercase(), value.to_string()))
+        })
+        .collect();
+    Some(Property {
+        name,
+        params,
+        value: line[value_start..].to_string(),
+    })
+}
+
+/// The properties of every VEVENT, those of its alarms left out.
+fn vevents(text: &str) -> Vec<Vec<Property>> {
+    let mut events = Vec::new();
+    let mut event: Option<Vec<Property>> = None;
+    // components nested in the event, e.g. VALARM
+    let mut nested = 0;
+    for property in unfold(text).iter().filter_map(|line| parse_property(line)) {
+        let name = property.name.clone();
+        let component = property.value.trim().to_ascii_uppercase();
+        match (name.as_str(), event.is_some()) {
+            ("BEGIN", false) if component == "VEVENT" => event = Some(Vec::new()),
+            ("BEGIN", true) => nested += 1,
+            ("END", true) if nested > 0 => nested -= 1,
+            ("END", true) if component == "VEVENT" => events.extend(event.take()),
+            (_, true) if nested == 0 => {
+                if let Some(properties) = event.as_mut() {
+                    properties.push(property);
+                }
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => text.push('\n'),
+                Some(c) => text.push(c),
+                None => {}
+            },
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// Time zone of a DATE-TIME.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Universal,
+    Named(Tz),
+    /// No TZID, or one that is neither an IANA nor a Windows name, read as local time
+    Floating,
+}
+
+/// The zone of a TZID, an IANA name or a Windows one such as Outlook's "Pacific Standard
+/// Time".
+fn named_zone(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_start_matches('/');
+    tzid.parse::<Tz>().ok().or_else(|| {
+        WINDOWS_ZONES
+            .iter()
+            .find(|(windows, _)| windows.eq_ignore_ascii_case(tzid))
+            .and_then(|(_, iana)| iana.parse().ok())
+    })
+}
+
+impl Zone {
+    fn to_utc(self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Universal => Some(Utc.from_utc_datetime(&time)),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc)),
+            Zone::Floating => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// A DATE-TIME in its time zone, `None` for DATE values, i.e. all-day events.
+fn parse_date_time(value: &str, tzid: Option<&str>) -> Option<(NaiveDateTime, Zone)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((time, Zone::Universal));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = tzid
+        .and_then(named_zone)
+        .map_or(Zone::Floating, Zone::Named);
+    Some((time, zone))
+}
+
+/// `P1W`, `PT1H30M`, `-P1D`...
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.trim_start_matches('+')),
+    };
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => time = true,
+            unit => {
+                let n: i64 = std::mem::take(&mut number).parse().ok()?;
+                duration = duration
+                    + match (unit, time) {
+                        ('W', false) => Duration::weeks(n),
+                        ('D', false) => Duration::days(n),
+                        ('H', true) => Duration::hours(n),
+                        ('M', true) => Duration::minutes(n),
+                        ('S', true) => Duration::seconds(n),
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    number.is_empty().then_some(duration * sign)
+}
+
+/// Name of the organizer or an attendee, their email when the calendar has none.
+fn person(property: &Property) -> Option<String> {
+    if let Some(name) = property
+        .param("CN")
+        .map(|name| unescape(name.trim()))
+        .filter(|name| !name.is_empty())
+    {
+        return Some(name);
+    }
+    let value = property.value.trim();
+    let email = value
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map_or(value, |_| &value[7..]);
+    (!email.is_empty()).then(|| email.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The parts of an RRULE occurrences are generated from. Rules with others, e.g. the
+/// second Tuesday of every month, only keep their first occurrence.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    frequency: Frequency,
+    interval: i64,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+    weekdays: Vec<Weekday>,
+}
+
+fn parse_rule(value: &str, zone: Zone) -> Option<Rule> {
+    let mut frequency = None;
+    let mut rule = Rule {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        weekdays: Vec::new(),
+    };
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        let value = value.trim();
+        match key.trim().to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => rule.interval = value.parse().ok().filter(|n| (1..=1000).contains(n))?,
+            "COUNT" => rule.count = Some(value.parse().ok()?),
+            "UNTIL" => {
+                rule.until = match parse_date_time(value, None) {
+                    Some((time, Zone::Floating)) => zone.to_utc(time),
+                    Some((time, zone)) => zone.to_utc(time),
+                    // a date, the whole day is in
+                    None => zone.to_utc(
+                        NaiveDate::parse_from_str(value, "%Y%m%d")
+                            .ok()?
+                            .and_hms_opt(23, 59, 59)?,
+                    ),
+                };
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    let day = day.trim();
+                    // only plain weekdays, not "1TU"
+                    if day.len() != 2 {
+                        return None;
+                    }
+                    rule.weekdays.push(match day.to_ascii_uppercase().as_str() {
+                        "MO" => Weekday::Mon,
+                        "TU" => Weekday::Tue,
+                        "WE" => Weekday::Wed,
+                        "TH" => Weekday::Thu,
+                        "FR" => Weekday::Fri,
+                        "SA" => Weekday::Sat,
+                        "SU" => Weekday::Sun,
+                        _ => return None,
+                    });
+                }
+            }
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    rule.frequency = frequency?;
+    if !rule.weekdays.is_empty() && matches!(rule.frequency, Frequency::Monthly | Frequency::Yearly)
+    {
+        return None;
+    }
+    Some(rule)
+}
+
+/// `time` `months` later, `None` when that month has no such day.
+fn add_months(time: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    let months = time.year() as i64 * 12 + time.month0() as i64 + months;
+    let year = i32::try_from(months.div_euclid(12)).ok()?;
+    let date = NaiveDate::from_ymd_opt(year, months.rem_euclid(12) as u32 + 1, time.day())?;
+    Some(date.and_time(time.time()))
+}
+
+/// Starts of the occurrences of `rule` from `first`, in its time zone, up to `until`.
+fn occurrences(
+    first: NaiveDateTime,
+    zone: Zone,
+    rule: &Rule,
+    until: DateTime<Utc>,
+) -> Vec<NaiveDateTime> {
+    let mut starts = Vec::new();
+    let mut generated = 0;
+    for period in 0..MAX_PERIODS {
+        let step = period * rule.interval;
+        let shifted = match rule.frequency {
+            Frequency::Daily => first.checked_add_signed(Duration::days(step)),
+            Frequency::Weekly => first.checked_add_signed(Duration::weeks(step)),
+            Frequency::Monthly | Frequency::Yearly => Some(first),
+        };
+        let Some(shifted) = shifted else { break };
+        let candidates = match rule.frequency {
+            Frequency::Daily => {
+                let day = shifted;
+                if rule.weekdays.is_empty() || rule.weekdays.contains(&day.weekday()) {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly if rule.weekdays.is_empty() => vec![shifted],
+            Frequency::Weekly => {
+                let week = shifted;
+                let monday = week - Duration::days(week.weekday().num_days_from_monday() as i64);
+                let mut days: Vec<NaiveDateTime> = rule
+                    .weekdays
+                    .iter()
+                    .map(|day| monday + Duration::days(day.num_days_from_monday() as i64))
+                    .filter(|day| *day >= first)
+                    .collect();
+                days.sort();
+                days
+            }
+            Frequency::Monthly => add_months(first, step).into_iter().collect(),
+            Frequency::Yearly => add_months(first, step * 12).into_iter().collect(),
+        };
+        for start in candidates {
+            if rule.count.is_some_and(|count| generated >= count) {
+                return starts;
+            }
+            let Some(utc) = zone.to_utc(start) else {
+                // skipped by a DST change
+                continue;
+            };
+            if utc > until || rule.until.is_some_and(|rule_until| utc > rule_until) {
+                return starts;
+            }
+            generated += 1;
+            starts.push(start);
+        }
+    }
+    starts
+}
+
+struct Event {
+    uid: String,
+    title: String,
+    start: (NaiveDateTime, Zone),
+    duration: Duration,
+    attendees: Vec<String>,
+    location: Option<String>,
+    cancelled: bool,
+    rule: Option<Rule>,
+    exdates: HashSet<DateTime<Utc>>,
+    /// Start of the occurrence of a recurring event this one replaces
+    recurrence_id: Option<DateTime<Utc>>,
+}
+
+/// `None` without a start time, e.g. all-day events.
+fn parse_event(properties: &[Property]) -> Option<Event> {
+    let find = |name: &str| properties.iter().find(|property| property.name == name);
+    let dtstart = find("DTSTART")?;
+    let start = parse_date_time(&dtstart.value, dtstart.param("TZID"))?;
+    let start_utc = start.1.to_utc(start.0)?;
+    let duration = match (find("DTEND"), find("DURATION")) {
+        (Some(dtend), _) => {
+            let (end, zone) = parse_date_time(&dtend.value, dtend.param("TZID"))?;
+            zone.to_utc(end)? - start_utc
+        }
+        (None, Some(duration)) => parse_duration(&duration.value)?,
+        (None, None) => Duration::zero(),
+    };
+    let title = find("SUMMARY")
+        .map(|summary| unescape(summary.value.trim()))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "Untitled meeting".to_string());
+
+    let mut attendees: Vec<String> = Vec::new();
+    for property in properties {
+        let joined = match property.name.as_str() {
+            "ORGANIZER" => true,
+            "ATTENDEE" => {
+                !property
+                    .param("PARTSTAT")
+                    .is_some_and(|status| status.eq_ignore_ascii_case("DECLINED"))
+                    && !property.param("CUTYPE").is_some_and(|kind| {
+                        kind.eq_ignore_ascii_case("ROOM") || kind.eq_ignore_ascii_case("RESOURCE")
+                    })
+            }
+            _ => false,
+        };
+        if let Some(person) = person(property).filter(|_| joined) {
+            if !attendees.iter().any(|a| a.eq_ignore_ascii_case(&person)) {
+                attendees.push(person);
+            }
+        }
+    }
+
+    let mut exdates = HashSet::new();
+    for exdate in properties
+        .iter()
+        .filter(|property| property.name == "EXDATE")
+    {
+        for value in exdate.value.split(',') {
+            let excluded = parse_date_time(value, exdate.param("TZID"))
+                .and_then(|(time, zone)| zone.to_utc(time));
+            exdates.extend(excluded);
+        }
+    }
+    let recurrence_id = find("RECURRENCE-ID").and_then(|recurrence_id| {
+        let (time, zone) = parse_date_time(&recurrence_id.value, recurrence_id.param("TZID"))?;
+        zone.to_utc(time)
+    });
+    let rule = find("RRULE").and_then(|rrule| {
+        let rule = parse_rule(&rrule.value, start.1);
+        if rule.is_none() {
+            debug!("only the first occurrence of rule {} is read", rrule.value);
+        }
+        rule
+    });
+
+    Some(Event {
+        uid: find("UID")
+            .map(|uid| uid.value.trim().to_string())
+            .filter(|uid| !uid.is_empty())
+            .unwrap_or_else(|| format!("{}@{}", title, start_utc.to_rfc3339())),
+        title,
+        start,
+        duration,
+        attendees,
+        location: find("LOCATION")
+            .map(|location| unescape(location.value.trim()))
+            .filter(|location| !location.is_empty()),
+        cancelled: find("STATUS")
+            .is_some_and(|status| status.value.trim().eq_ignore_ascii_case("CANCELLED")),
+        rule,
+        exdates,
+        recurrence_id,
+    })
+}
+
+impl Event {
+    fn meeting(&self, uid: String, start_time: DateTime<Utc>) -> Meeting {
+        Meeting {
+            uid,
+            title: self.title.clone(),
+            start_time,
+            end_time: start_time + self.duration.max(Duration::zero()),
+            attendees: self.attendees.clone(),
+            location: self.location.clone(),
+        }
+    }
+}
+
+fn occurrence_uid(uid: &str, start: DateTime<Utc>) -> String {
+    format!("{}/{}", uid, start.format("%Y%m%dT%H%M%SZ"))
+}
+
+/// Whether the text holds a calendar. Feeds answering with a login page or an empty body
+/// don't, and must not be read as a calendar without meetings.
+pub fn is_icalendar(text: &str) -> bool {
+    unfold(text)
+        .iter()
+        .filter_map(|line| parse_property(line))
+        .any(|property| {
+            property.name == "BEGIN" && property.value.trim().eq_ignore_ascii_case("VCALENDAR")
+        })
+}
+
+/// The meetings of an iCalendar text overlapping `start..end`, recurring events expanded
+/// and cancelled ones left out. Ordered by start.
+pub fn parse_ics(text: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Meeting> {
+    let events: Vec<Event> = vevents(text)
+        .iter()
+        .filter_map(|properties| parse_event(properties))
+        .collect();
+    let overridden: HashSet<(&str, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.as_str(), event.recurrence_id?)))
+        .collect();
+
+    let mut meetings = Vec::new();
+    for event in &events {
+        match (event.recurrence_id, &event.rule) {
+            (Some(recurrence_id), _) => {
+                if let Some(start_time) = event.start.1.to_utc(event.start.0) {
+                    meetings.push((
+                        event.cancelled,
+                        event.meeting(occurrence_uid(&event.uid, recurrence_id), start_time),
+                    ));
+                }
+            }
+            (None, Some(rule)) => {
+                for first in occurrences(event.start.0, event.start.1, rule, end) {
+                    let Some(start_time) = event.start.1.to_utc(first) else {
+                        continue;
+                    };
+                    if event.exdates.contains(&start_time)
+                        || overridden.contains(&(event.uid.as_str(), start_time))
+                    {
+                        continue;
+                    }
+                    meetings.push((
+                        event.cancelled,
+                        event.meeting(occurrence_uid(&event.uid, start_time), start_time),
+                    ));
+                }
+            }
+            (None, None) => {
+                if let Some(start_time) = event.start.1.to_utc(event.start.0) {
+                    meetings.push((
+                        event.cancelled,
+                        event.meeting(event.uid.clone(), start_time),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut meetings: Vec<Meeting> = meetings
+        .into_iter()
+        .filter(|(cancelled, meeting)| {
+            !cancelled && meeting.start_time < end && meeting.end_time >= start
+        })
+        .map(|(_, meeting)| meeting)
+        .collect();
+    meetings.sort_by(|a, b| a.start_time.cmp(&b.start_time).then(a.uid.cmp(&b.uid)));
+    meetings
+}
+
+/// The calendar data of a CalDAV REPORT response, one iCalendar text per event.
+pub fn calendar_data(xml: &str) -> Vec<String> {
+    let pattern = Regex::new(concat!(
+        r"(?s)<(?:[A-Za-z][\w.-]*:)?calendar-data\b[^>]*>",
+        r"(.*?)</(?:[A-Za-z][\w.-]*:)?calendar-data>"
+    ))
+    .expect("valid calendar-data pattern");
+    pattern
+        .captures_iter(xml)
+        .map(|captures| {
+            let data = captures[1].trim();
+            match data
+                .strip_prefix("<![CDATA[")
+                .and_then(|data| data.strip_suffix("]]>"))
+            {
+                Some(data) => data.to_string(),
+                None => data
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&#13;", "\r")
+                    .replace("&#xD;", "\r")
+                    .replace("&amp;", "&"),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Read from the calendar
+    pub meetings: usize,
+    /// New or changed since the last sync
+    pub updated: usize,
+    /// Gone from the calendar since
+    pub removed: u64,
+}
+
+/// Keeps the meetings of a calendar in the database, tagging what is captured during them
+/// with their title and attendees.
+pub struct CalendarSync {
+    db: Arc<DatabaseManager>,
+    source: CalendarSource,
+    client: Client,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl CalendarSync {
+    /// `username` and `password` log into feeds and CalDAV servers that need them.
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        source: CalendarSource,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Self {
+        Self {
+            db,
+            source,
+            client: Client::new(),
+            username,
+            password,
+        }
+    }
+
+    fn authenticated(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+
+    /// The meetings overlapping `start..end`.
+    pub async fn read(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<Meeting>> {
+        let text = match &self.source {
+            CalendarSource::File(path) => tokio::fs::read_to_string(path).await?,
+            CalendarSource::Feed(url) => {
+                self.authenticated(self.client.get(url.clone()))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?
+            }
+            CalendarSource::CalDav(url) => {
+                // the server expands recurring events itself
+                let range = format!(
+                    "start=\"{}\" end=\"{}\"",
+                    start.format("%Y%m%dT%H%M%SZ"),
+                    end.format("%Y%m%dT%H%M%SZ")
+                );
+                let body = format!(
+                    r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data><c:expand {range}/></c:calendar-data></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT"><c:time-range {range}/></c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#
+                );
+                let report = Method::from_bytes(b"REPORT")?;
+                let response = self
+                    .authenticated(self.client.request(report, url.clone()))
+                    .header("Depth", "1")
+                    .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?;
+                // a calendar without meetings in the range answers an empty multistatus
+                if !Regex::new(r"<(?:[A-Za-z][\w.-]*:)?multistatus\b")?.is_match(&response) {
+                    return Err(anyhow!("{} didn't answer a caldav report", self.source));
+                }
+                let events = calendar_data(&response);
+                if !events.iter().all(|event| is_icalendar(event)) {
+                    return Err(anyhow!(
+                        "{} answered events that aren't icalendar",
+                        self.source
+                    ));
+                }
+                if events.is_empty() {
+                    return Ok(Vec::new());
+                }
+                events.join("\n")
+            }
+        };
+        // removing the meetings not read would remove them all
+        if !is_icalendar(&text) {
+            return Err(anyhow!("{} isn't an icalendar", self.source));
+        }
+        Ok(parse_ics(&text, start, end))
+    }
+
+    /// Stores the meetings of the last `SYNC_DAYS_BACK` days and of the next day.
+    pub async fn sync(&self) -> Result<SyncReport> {
+        let now = Utc::now();
+        let start = now - Duration::days(SYNC_DAYS_BACK);
+        let end = now + Duration::days(SYNC_DAYS_AHEAD);
+        let meetings = self.read(start, end).await?;
+
+        let mut report = SyncReport {
+            meetings: meetings.len(),
+            ..Default::default()
+        };
+        for meeting in &meetings {
+            let updated = self
+                .db
+                .upsert_calendar_event(
+                    &meeting.uid,
+                    &meeting.title,
+                    meeting.start_time,
+                    meeting.end_time,
+                    &meeting.attendees,
+                    meeting.location.as_deref(),
+                    Some(&meeting.note()),
+                    &meeting.tags(),
+                )
+                .await?;
+            if updated {
+                report.updated += 1;
+            }
+        }
+        let uids: Vec<String> = meetings.into_iter().map(|meeting| meeting.uid).collect();
+        report.removed = self
+            .db
+            .delete_calendar_events_except(start, end, &uids)
+            .await?;
+        Ok(report)
+    }
+}
+
+/// Reads the calendar every few minutes.
+pub async fn run_calendar_sync(sync: Arc<CalendarSync>) {
+    info!("reading meetings from {}", sync.source);
+    loop {
+        match sync.sync().await {
+            Ok(report) if report.updated > 0 || report.removed > 0 => debug!(
+                "{} meetings in the calendar, {} updated, {} removed",
+                report.meetings, report.updated, report.removed
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("failed to read meetings from {}: {}", sync.source, e),
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
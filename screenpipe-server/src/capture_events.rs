@@ -0,0 +1,168 @@
+use chrono::Utc;
+use futures::StreamExt;
+use screenpipe_events::{subscribe_to_all_events, CaptureEvent, Event, CAPTURE_EVENT};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+const CAPTURE_KINDS: &[&str] = &["ocr", "transcript", "app_focus"];
+/// Capture events kept for clients resuming with `Last-Event-ID`
+pub const CAPTURE_LOG_SIZE: usize = 1000;
+
+/// Which capture events a client wants, from comma separated query parameters. Every set
+/// filter must match.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CaptureFilter {
+    /// `ocr`, `transcript` or `app_focus`
+    pub types: Vec<String>,
+    /// Parts of app names, ignoring case. Transcriptions have no app and don't match
+    pub apps: Vec<String>,
+    /// Words or phrases the text must contain one of, ignoring case. The window title
+    /// for focus changes
+    pub keywords: Vec<String>,
+}
+
+fn split_list(list: Option<&str>) -> Vec<String> {
+    list.unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl CaptureFilter {
+    pub fn parse(
+        types: Option<&str>,
+        apps: Option<&str>,
+        keywords: Option<&str>,
+    ) -> Result<Self, String> {
+        let filter = Self {
+            types: split_list(types),
+            apps: split_list(apps),
+            keywords: split_list(keywords),
+        };
+        if let Some(kind) = filter
+            .types
+            .iter()
+            .find(|kind| !CAPTURE_KINDS.contains(&kind.as_str()))
+        {
+            return Err(format!(
+                "unknown event type '{}', expected one of {}",
+                kind,
+                CAPTURE_KINDS.join(", ")
+            ));
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.apps.is_empty() && self.keywords.is_empty()
+    }
+
+    pub fn matches(&self, event: &CaptureEvent) -> bool {
+        if !self.types.is_empty() && !self.types.iter().any(|kind| kind == event.kind()) {
+            return false;
+        }
+        if !self.apps.is_empty() {
+            let Some(app_name) = event.app_name().map(str::to_lowercase) else {
+                return false;
+            };
+            if !self.apps.iter().any(|app| app_name.contains(app.as_str())) {
+                return false;
+            }
+        }
+        if !self.keywords.is_empty() {
+            let text = event.text().to_lowercase();
+            if !self
+                .keywords
+                .iter()
+                .any(|keyword| text.contains(keyword.as_str()))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The capture event carried by `event`, if it is one.
+pub fn as_capture_event(event: &Event) -> Option<CaptureEvent> {
+    if event.name != CAPTURE_EVENT {
+        return None;
+    }
+    serde_json::from_value(event.data.clone()).ok()
+}
+
+struct RecentCaptures {
+    next_id: u64,
+    events: VecDeque<(u64, CaptureEvent)>,
+}
+
+/// Numbers capture events in the order they are sent and keeps the last ones, so clients
+/// that reconnect get what they missed.
+pub struct CaptureLog {
+    recent: Mutex<RecentCaptures>,
+    sender: broadcast::Sender<(u64, CaptureEvent)>,
+    capacity: usize,
+}
+
+impl CaptureLog {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            recent: Mutex::new(RecentCaptures {
+                // ids keep growing across restarts, a client resuming after one gets
+                // everything kept instead of waiting for the ids it has seen
+                next_id: Utc::now().timestamp_millis().max(1) as u64,
+                events: VecDeque::with_capacity(capacity),
+            }),
+            sender,
+            capacity,
+        }
+    }
+
+    /// Numbers `event` and sends it to the subscribers. Returns its id.
+    pub fn push(&self, event: CaptureEvent) -> u64 {
+        let mut recent = self.recent.lock().unwrap();
+        let id = recent.next_id;
+        recent.next_id += 1;
+        if recent.events.len() == self.capacity {
+            recent.events.pop_front();
+        }
+        recent.events.push_back((id, event.clone()));
+        // sent under the lock, so `resume` neither misses nor repeats it
+        let _ = self.sender.send((id, event));
+        id
+    }
+
+    /// The kept events after `last_id`, none without one, and the events sent from then on.
+    pub fn resume(
+        &self,
+        last_id: Option<u64>,
+    ) -> (
+        Vec<(u64, CaptureEvent)>,
+        broadcast::Receiver<(u64, CaptureEvent)>,
+    ) {
+        let recent = self.recent.lock().unwrap();
+        let missed = match last_id {
+            Some(last_id) => recent
+                .events
+                .iter()
+                .filter(|(id, _)| *id > last_id)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        (missed, self.sender.subscribe())
+    }
+}
+
+/// Adds the capture events sent on the event bus to `log`.
+pub async fn record_capture_events(log: Arc<CaptureLog>) {
+    let mut stream = subscribe_to_all_events();
+    while let Some(event) = stream.next().await {
+        if let Some(capture) = as_capture_event(&event) {
+            log.push(capture);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use screenpipe_db::{CentralDatabase, DatabaseManager};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// Captures copied per query
+const SYNC_BATCH: i64 = 500;
+// How often new captures are copied to the central database
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Copied captures of one sync.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    pub texts: usize,
+    pub transcriptions: usize,
+}
+
+/// Copies the captures of `machine` the central database doesn't have yet. The central
+/// database tracks what it has, a machine that was offline catches up on the next sync.
+pub async fn sync_captures<D: CentralDatabase>(
+    local: &DatabaseManager,
+    central: &D,
+    machine: &str,
+) -> Result<SyncReport> {
+    let position = central.sync_position(machine).await?;
+    let mut report = SyncReport::default();
+
+    let mut after = position.text_id;
+    loop {
+        let texts = local.get_texts_after(after, SYNC_BATCH).await?;
+        let Some(last) = texts.last() else { break };
+        after = last.id;
+        central.insert_texts(machine, &texts).await?;
+        report.texts += texts.len();
+        if (texts.len() as i64) < SYNC_BATCH {
+            break;
+        }
+    }
+
+    let mut after = position.transcription_id;
+    loop {
+        let transcriptions = local.get_transcriptions_after(after, SYNC_BATCH).await?;
+        let Some(last) = transcriptions.last() else {
+            break;
+        };
+        after = last.id;
+        central
+            .insert_transcriptions(machine, &transcriptions)
+            .await?;
+        report.transcriptions += transcriptions.len();
+        if (transcriptions.len() as i64) < SYNC_BATCH {
+            break;
+        }
+    }
+    Ok(report)
+}
+
+pub async fn run_central_sync<D: CentralDatabase>(
+    local: Arc<DatabaseManager>,
+    central: Arc<D>,
+    machine: String,
+) {
+    info!("copying captures to the central database as {}", machine);
+    loop {
+        match sync_captures(&local, central.as_ref(), &machine).await {
+            Ok(report) if report.texts + report.transcriptions > 0 => info!(
+                "copied {} texts and {} transcriptions to the central database",
+                report.texts, report.transcriptions
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("failed to copy captures to the central database: {}", e),
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
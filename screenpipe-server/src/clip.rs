@@ -0,0 +1,337 @@
+use crate::video_utils::extract_frame_from_video;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use oasgen::OaSchema;
+use screenpipe_core::encryption::readable_media;
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::{DatabaseManager, VideoSegment};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+pub const MAX_CLIP_DURATION_SECS: i64 = 3600;
+const CLIP_FPS: &str = "10";
+// Stored frames are sampled down to this many, each one is extracted from its video chunk
+const MAX_CLIP_FRAMES: usize = 300;
+const FRAME_EXTRACT_CONCURRENCY: usize = 8;
+// Idle stretches between stored frames are shortened so the clip doesn't stall
+const MAX_FRAME_HOLD_SECS: f64 = 2.0;
+const MIN_FRAME_HOLD_SECS: f64 = 0.1;
+// Recordings split a little apart still count as one continuous stretch
+const SEGMENT_GAP_TOLERANCE_SECS: f64 = 1.0;
+const GIF_MAX_WIDTH: u32 = 960;
+// Clips are deleted once served, ones a cut download left behind after this long
+const MAX_CLIP_AGE: Duration = Duration::from_secs(3600);
+
+#[derive(OaSchema, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    #[default]
+    Mp4,
+    Gif,
+}
+
+impl ClipFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClipFormat::Mp4 => "mp4",
+            ClipFormat::Gif => "gif",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ClipFormat::Mp4 => "video/mp4",
+            ClipFormat::Gif => "image/gif",
+        }
+    }
+
+    /// ffmpeg arguments encoding the concatenated input into the clip.
+    pub fn output_args(&self) -> Vec<String> {
+        match self {
+            ClipFormat::Mp4 => [
+                "-vf",
+                "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2,format=yuv420p",
+                "-r",
+                CLIP_FPS,
+                "-c:v",
+                "libx264",
+                "-preset",
+                "veryfast",
+                "-crf",
+                "28",
+                "-movflags",
+                "+faststart",
+            ]
+            .map(String::from)
+            .to_vec(),
+            ClipFormat::Gif => vec![
+                "-vf".to_string(),
+                format!(
+                    "fps={},scale='min({},iw)':-2:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse",
+                    CLIP_FPS, GIF_MAX_WIDTH
+                ),
+                "-loop".to_string(),
+                "0".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct ClipQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub monitor: u32,
+    #[serde(default)]
+    pub format: ClipFormat,
+}
+
+impl ClipQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.end <= self.start {
+            return Err("end must be after start".to_string());
+        }
+        if (self.end - self.start).num_seconds() > MAX_CLIP_DURATION_SECS {
+            return Err(format!(
+                "clips are limited to {} seconds",
+                MAX_CLIP_DURATION_SECS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A rendered clip, the file is deleted once this is dropped.
+#[derive(Debug)]
+pub struct ClipFile {
+    pub path: PathBuf,
+}
+
+impl Drop for ClipFile {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                warn!("failed to remove clip {:?}: {}", self.path, e)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Renders what `monitor` showed between `start` and `end` into a file in `output_dir`.
+/// Cuts the continuous recording when it covers the whole range, otherwise stitches the
+/// stored frames into a slideshow. `None` when nothing was captured in the range.
+pub async fn create_clip(
+    db: &DatabaseManager,
+    query: &ClipQuery,
+    output_dir: &Path,
+) -> Result<Option<ClipFile>> {
+    let device_name = format!("monitor_{}", query.monitor);
+    let mut segments = db
+        .get_video_segments_in_range(&device_name, query.start, query.end)
+        .await?;
+    // encrypted recordings are cut from decrypted copies, they live until ffmpeg is done
+    let mut readable = Vec::with_capacity(segments.len());
+    for segment in &mut segments {
+        let media = readable_media(Path::new(&segment.file_path)).await?;
+        segment.file_path = media.to_str().to_string();
+        readable.push(media);
+    }
+
+    let mut extracted = Vec::new();
+    let concat_list = match segments_concat_list(&segments, query.start, query.end) {
+        Some(list) => {
+            debug!("cutting clip from {} recording segment(s)", segments.len());
+            list
+        }
+        None => {
+            let frames = db
+                .get_frames_in_range(&device_name, query.start, query.end)
+                .await?;
+            if frames.is_empty() {
+                return Ok(None);
+            }
+            let images = extract_frames(sample_evenly(frames, MAX_CLIP_FRAMES)).await;
+            if images.is_empty() {
+                return Err(anyhow!("no frame in the range could be extracted"));
+            }
+            debug!("stitching clip from {} stored frame(s)", images.len());
+            let list = frames_concat_list(&images, query.end);
+            extracted = images.into_iter().map(|(path, _)| path).collect();
+            list
+        }
+    };
+
+    tokio::fs::create_dir_all(output_dir).await?;
+    match remove_stale_clips(output_dir, MAX_CLIP_AGE).await {
+        Ok(0) => {}
+        Ok(removed) => debug!("removed {} stale clip(s)", removed),
+        Err(e) => warn!("failed to remove stale clips: {}", e),
+    }
+    let id = Uuid::new_v4();
+    let list_path = output_dir.join(format!("clip_{}.txt", id));
+    let output_path = output_dir.join(format!("clip_{}.{}", id, query.format.extension()));
+    tokio::fs::write(&list_path, concat_list).await?;
+
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let mut args = [
+        "-hide_banner",
+        "-loglevel",
+        "error",
+        "-f",
+        "concat",
+        "-safe",
+        "0",
+        "-i",
+    ]
+    .map(String::from)
+    .to_vec();
+    args.push(list_path.to_string_lossy().into_owned());
+    args.extend(query.format.output_args());
+    args.push("-y".to_string());
+    args.push(output_path.to_string_lossy().into_owned());
+
+    let output = Command::new(ffmpeg_path).args(&args).output().await;
+    if let Err(e) = tokio::fs::remove_file(&list_path).await {
+        warn!("failed to remove clip list {:?}: {}", list_path, e);
+    }
+    for image in &extracted {
+        if let Err(e) = tokio::fs::remove_file(image).await {
+            warn!("failed to remove clip frame {}: {}", image, e);
+        }
+    }
+    let output = output?;
+    let clip = ClipFile { path: output_path };
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to render clip: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    info!("clip rendered: {:?}", clip.path);
+    Ok(Some(clip))
+}
+
+/// Deletes the clips in `dir` older than `max_age`, returns how many.
+pub async fn remove_stale_clips(dir: &Path, max_age: Duration) -> Result<usize> {
+    let cutoff = SystemTime::now() - max_age;
+    let mut removed = 0;
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let is_clip = entry.file_name().to_string_lossy().starts_with("clip_");
+        let modified = entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified());
+        if is_clip && modified.is_ok_and(|modified| modified < cutoff) {
+            tokio::fs::remove_file(entry.path()).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Concat list cutting `start..end` out of the recording, `None` unless the segments cover
+/// the whole range without gaps.
+pub fn segments_concat_list(
+    segments: &[VideoSegment],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<String> {
+    let first = segments.first()?;
+    if first.start_time > start {
+        return None;
+    }
+
+    let mut list = String::from("ffconcat version 1.0\n");
+    let mut covered_until = start;
+    for segment in segments {
+        let gap = (segment.start_time - covered_until).num_milliseconds() as f64 / 1000.0;
+        if gap > SEGMENT_GAP_TOLERANCE_SECS {
+            return None;
+        }
+        let piece_start = covered_until.max(segment.start_time);
+        let piece_end = segment
+            .end_time
+            .map_or(end, |segment_end| segment_end.min(end));
+        if piece_end <= piece_start {
+            continue;
+        }
+
+        list.push_str(&format!(
+            "file '{}'\ninpoint {:.3}\noutpoint {:.3}\n",
+            escape_concat_path(&segment.file_path),
+            segment.offset_secs(piece_start),
+            segment.offset_secs(piece_end)
+        ));
+        covered_until = piece_end;
+        if covered_until >= end {
+            return Some(list);
+        }
+    }
+    None
+}
+
+/// Concat list showing each extracted frame until the next one was captured.
+pub fn frames_concat_list(images: &[(String, DateTime<Utc>)], end: DateTime<Utc>) -> String {
+    let mut list = String::from("ffconcat version 1.0\n");
+    for (index, (path, timestamp)) in images.iter().enumerate() {
+        let next = images.get(index + 1).map_or(end, |(_, next)| *next);
+        let hold = ((next - *timestamp).num_milliseconds() as f64 / 1000.0)
+            .clamp(MIN_FRAME_HOLD_SECS, MAX_FRAME_HOLD_SECS);
+        list.push_str(&format!(
+            "file '{}'\nduration {:.3}\n",
+            escape_concat_path(path),
+            hold
+        ));
+    }
+    // the demuxer ignores the duration of the last entry
+    if let Some((path, _)) = images.last() {
+        list.push_str(&format!("file '{}'\n", escape_concat_path(path)));
+    }
+    list
+}
+
+fn escape_concat_path(path: &str) -> String {
+    path.replace('\'', "'\\''")
+}
+
+fn sample_evenly<T>(items: Vec<T>, max: usize) -> Vec<T> {
+    if items.len() <= max {
+        return items;
+    }
+    let step = items.len() as f64 / max as f64;
+    let mut sampled = Vec::with_capacity(max);
+    for (index, item) in items.into_iter().enumerate() {
+        if (sampled.len() as f64 * step) as usize == index {
+            sampled.push(item);
+        }
+    }
+    sampled
+}
+
+async fn extract_frames(frames: Vec<(String, i64, DateTime<Utc>)>) -> Vec<(String, DateTime<Utc>)> {
+    stream::iter(frames)
+        .map(|(file_path, offset_index, timestamp)| async move {
+            match extract_frame_from_video(&file_path, offset_index).await {
+                Ok(image_path) => Some((image_path, timestamp)),
+                Err(e) => {
+                    warn!(
+                        "skipping frame {} of {} in clip: {}",
+                        offset_index, file_path, e
+                    );
+                    None
+                }
+            }
+        })
+        .buffered(FRAME_EXTRACT_CONCURRENCY)
+        .filter_map(|image| async move { image })
+        .collect()
+        .await
+}
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use arboard::Clipboard;
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_screenshot_by_window::focused_window;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+// Without a focused window read at the copy, a frame captured this long before it tells
+// which app it was copied from
+const FOCUS_LOOKBACK_SECS: i64 = 10;
+// Longer copies, e.g. whole files, are cut
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// Copies matching any of these are never stored: private keys and API tokens.
+pub const DEFAULT_BLOCKLIST: &[&str] = &[
+    r"-----BEGIN [A-Z ]*PRIVATE KEY-----",
+    r"\b[sr]k_(?:live|test)_[0-9A-Za-z]{16,}",
+    r"\bsk-[A-Za-z0-9_-]{20,}",
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}",
+    r"\bgithub_pat_[A-Za-z0-9_]{22,}",
+    r"\bAKIA[0-9A-Z]{16}\b",
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+];
+
+/// Password managers, nothing copied from them is stored.
+pub const DEFAULT_EXCLUDED_APPS: &[&str] = &[
+    "1Password",
+    "Bitwarden",
+    "KeePass",
+    "LastPass",
+    "Dashlane",
+    "Enpass",
+    "Keychain Access",
+];
+
+/// Clipboard types password managers add to what they copy, nothing carrying one is read:
+/// the nspasteboard.org markers of macOS and the hint of KDE on Linux.
+pub const CONCEALED_TYPES: &[&str] = &[
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "x-kde-passwordManagerHint",
+];
+
+static CARD_NUMBER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap());
+
+/// What copied text is left out: text matching a blocklisted pattern or holding a card
+/// number, and text copied from an excluded app.
+#[derive(Debug, Clone)]
+pub struct ClipboardFilter {
+    blocklist: Vec<Regex>,
+    excluded_apps: Vec<String>,
+}
+
+impl ClipboardFilter {
+    /// The default blocklist and apps, with `blocklist` regexes and `excluded_apps` added.
+    /// Apps match names that contain them, ignoring case.
+    pub fn new(blocklist: &[String], excluded_apps: &[String]) -> Result<Self, regex::Error> {
+        let blocklist = DEFAULT_BLOCKLIST
+            .iter()
+            .copied()
+            .chain(blocklist.iter().map(String::as_str))
+            .map(Regex::new)
+            .collect::<Result<_, _>>()?;
+        let excluded_apps = DEFAULT_EXCLUDED_APPS
+            .iter()
+            .copied()
+            .chain(excluded_apps.iter().map(String::as_str))
+            .map(str::trim)
+            .filter(|app| !app.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        Ok(Self {
+            blocklist,
+            excluded_apps,
+        })
+    }
+
+    pub fn is_sensitive(&self, text: &str) -> bool {
+        self.blocklist.iter().any(|pattern| pattern.is_match(text))
+            || CARD_NUMBER
+                .find_iter(text)
+                .any(|found| is_card_number(found.as_str()))
+    }
+
+    pub fn excludes_app(&self, app_name: &str) -> bool {
+        let app_name = app_name.to_lowercase();
+        self.excluded_apps
+            .iter()
+            .any(|excluded| app_name.contains(excluded.as_str()))
+    }
+}
+
+/// Whether any of the clipboard `types` marks its content as concealed.
+pub fn marks_concealed<'a>(types: impl IntoIterator<Item = &'a str>) -> bool {
+    types
+        .into_iter()
+        .any(|name| CONCEALED_TYPES.contains(&name.trim()))
+}
+
+/// 13 to 19 digits passing the Luhn check, spaces and dashes between them ignored.
+fn is_card_number(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Stores text copied to the clipboard, with the app it was copied from.
+pub struct ClipboardMonitor {
+    db: Arc<DatabaseManager>,
+    filter: ClipboardFilter,
+}
+
+impl ClipboardMonitor {
+    pub fn new(db: Arc<DatabaseManager>, filter: ClipboardFilter) -> Self {
+        Self { db, filter }
+    }
+
+    /// Stores `text` copied at `timestamp` from the `focused` app and window, read when it
+    /// was copied, or else from the app focused in the last frame captured before it.
+    /// Returns the id of the entry, `None` when it was left out.
+    pub async fn record(
+        &self,
+        text: &str,
+        timestamp: DateTime<Utc>,
+        focused: Option<(String, String)>,
+    ) -> Result<Option<i64>> {
+        if text.trim().is_empty() || self.filter.is_sensitive(text) {
+            return Ok(None);
+        }
+        let (app_name, window_name) = match focused {
+            Some((app_name, window_name)) => (
+                Some(app_name).filter(|name| !name.is_empty()),
+                Some(window_name).filter(|name| !name.is_empty()),
+            ),
+            None => self
+                .db
+                .latest_focused_window(
+                    timestamp - Duration::seconds(FOCUS_LOOKBACK_SECS),
+                    timestamp,
+                )
+                .await?
+                .unwrap_or_default(),
+        };
+        if app_name
+            .as_deref()
+            .is_some_and(|app_name| self.filter.excludes_app(app_name))
+        {
+            return Ok(None);
+        }
+        let text = match text.char_indices().nth(MAX_TEXT_CHARS) {
+            Some((end, _)) => &text[..end],
+            None => text,
+        };
+        let id = self
+            .db
+            .insert_clipboard_entry(timestamp, text, app_name.as_deref(), window_name.as_deref())
+            .await?;
+        Ok(Some(id))
+    }
+}
+
+/// Text seen on the clipboard, with the app and window focused then.
+struct Copied {
+    text: String,
+    timestamp: DateTime<Utc>,
+    focused: Option<(String, String)>,
+}
+
+/// Sends the text on the clipboard whenever it changes. What it held when the monitor
+/// started and what password managers mark as concealed are left out.
+fn watch_clipboard(tx: mpsc::Sender<Copied>) {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            warn!("failed to open the clipboard: {}", e);
+            return;
+        }
+    };
+    let mut last = clipboard.get_text().ok();
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        // fails when it holds no text, e.g. an image
+        let Ok(text) = clipboard.get_text() else {
+            continue;
+        };
+        if last.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        last = Some(text.clone());
+        if is_concealed() {
+            debug!("left out text marked concealed");
+            continue;
+        }
+        let copied = Copied {
+            text,
+            timestamp: Utc::now(),
+            // read now, frames are captured and stored a while after what they show
+            focused: focused_window(),
+        };
+        if tx.blocking_send(copied).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_concealed() -> bool {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {}
+
+    unsafe {
+        let pool: *mut Object = msg_send![class!(NSAutoreleasePool), new];
+        let pasteboard: *mut Object = msg_send![class!(NSPasteboard), generalPasteboard];
+        let types: *mut Object = msg_send![pasteboard, types];
+        let count: usize = if types.is_null() {
+            0
+        } else {
+            msg_send![types, count]
+        };
+        let names: Vec<String> = (0..count)
+            .filter_map(|i| {
+                let name: *mut Object = msg_send![types, objectAtIndex: i];
+                let utf8: *const c_char = msg_send![name, UTF8String];
+                (!utf8.is_null()).then(|| CStr::from_ptr(utf8).to_string_lossy().into_owned())
+            })
+            .collect();
+        let _: () = msg_send![pool, drain];
+        marks_concealed(names.iter().map(String::as_str))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_concealed() -> bool {
+    use windows::core::w;
+    use windows::Win32::System::DataExchange::{
+        IsClipboardFormatAvailable, RegisterClipboardFormatW,
+    };
+
+    // the formats Windows asks clipboard monitors and viewers to skip content with
+    [
+        w!("ExcludeClipboardContentFromMonitorProcessing"),
+        w!("Clipboard Viewer Ignore"),
+    ]
+    .into_iter()
+    .any(|name| unsafe {
+        let format = RegisterClipboardFormatW(name);
+        format != 0 && IsClipboardFormatAvailable(format).is_ok()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn is_concealed() -> bool {
+    use std::process::{Command, Stdio};
+
+    let mut list = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new("wl-paste");
+        command.arg("--list-types");
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-selection", "clipboard", "-o", "-t", "TARGETS"]);
+        command
+    };
+    // without the tools the types can't be listed, like before they were checked
+    match list.stdin(Stdio::null()).stderr(Stdio::null()).output() {
+        Ok(output) => marks_concealed(String::from_utf8_lossy(&output.stdout).lines()),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn is_concealed() -> bool {
+    false
+}
+
+/// Polls the clipboard for copied text until the monitor stops.
+pub async fn run_clipboard_monitor(monitor: Arc<ClipboardMonitor>) {
+    info!("recording text copied to the clipboard");
+    let (tx, mut rx) = mpsc::channel(16);
+    // the clipboard is read on a thread of its own, reading it blocks
+    std::thread::spawn(move || watch_clipboard(tx));
+    while let Some(copied) = rx.recv().await {
+        let recorded = monitor
+            .record(&copied.text, copied.timestamp, copied.focused)
+            .await;
+        match recorded {
+            Ok(Some(id)) => debug!("stored clipboard entry {}", id),
+            Ok(None) => debug!("left copied text out"),
+            Err(e) => warn!("failed to store copied text: {}", e),
+        }
+    }
+    warn!("stopped recording the clipboard");
+}
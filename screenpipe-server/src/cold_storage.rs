@@ -0,0 +1,408 @@
+use crate::frame_storage::FrameBlobStore;
+use crate::retention::{delete_media, disk_usage};
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use screenpipe_db::{DatabaseManager, MediaFile, MediaKind};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+// Files of each kind moved per query
+const TIER_BATCH: i64 = 100;
+// Local copies fetched back are deleted again after this long
+const FETCHED_TTL_HOURS: i64 = 24;
+// How often the background task moves old media
+const TIER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Lists the files of an image chunk directory, stored next to them
+const DIR_INDEX: &str = ".index";
+
+/// Where cold media is kept, an S3 compatible bucket or a directory, e.g. on a NAS.
+pub enum ColdStore {
+    S3 { bucket: Box<Bucket>, prefix: String },
+    Directory(PathBuf),
+}
+
+impl ColdStore {
+    /// Parses `s3://bucket/prefix` or a directory path. `endpoint` is the URL of an S3
+    /// compatible service like MinIO or Backblaze B2, AWS is used without it. Credentials
+    /// come from the AWS environment variables or the AWS profile.
+    pub fn parse(location: &str, endpoint: Option<&str>, region: &str) -> Result<Self> {
+        let Some(path) = location.strip_prefix("s3://") else {
+            let dir = location.strip_prefix("file://").unwrap_or(location);
+            return Ok(ColdStore::Directory(PathBuf::from(dir)));
+        };
+
+        let (name, prefix) = path.split_once('/').unwrap_or((path, ""));
+        if name.is_empty() {
+            return Err(anyhow!("cold storage url '{}' has no bucket", location));
+        }
+        let region = match endpoint {
+            Some(endpoint) => Region::Custom {
+                region: region.to_string(),
+                endpoint: endpoint.trim_end_matches('/').to_string(),
+            },
+            None => region.parse()?,
+        };
+        let credentials = Credentials::new(None, None, None, None, None).context(
+            "no credentials for cold storage, set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY",
+        )?;
+        let mut bucket = Bucket::new(name, region, credentials)?;
+        if endpoint.is_some() {
+            // compatible services rarely have a DNS name per bucket
+            bucket = bucket.with_path_style();
+        }
+        Ok(ColdStore::S3 {
+            bucket,
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    pub async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        match self {
+            ColdStore::S3 { bucket, prefix } => {
+                bucket.put_object(object_key(prefix, key), data).await?;
+            }
+            ColdStore::Directory(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let partial = PathBuf::from(format!("{}.partial", path.to_string_lossy()));
+                tokio::fs::write(&partial, data).await?;
+                tokio::fs::rename(&partial, &path).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match self {
+            ColdStore::S3 { bucket, prefix } => Ok(bucket
+                .get_object(object_key(prefix, key))
+                .await?
+                .bytes()
+                .to_vec()),
+            ColdStore::Directory(dir) => Ok(tokio::fs::read(dir.join(key)).await?),
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        match self {
+            ColdStore::S3 { bucket, prefix } => {
+                bucket.delete_object(object_key(prefix, key)).await?;
+            }
+            ColdStore::Directory(dir) => match tokio::fs::remove_file(dir.join(key)).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ColdStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColdStore::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket.name(), prefix),
+            ColdStore::Directory(dir) => write!(f, "{}", dir.display()),
+        }
+    }
+}
+
+fn object_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}/{}", prefix, key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TierReport {
+    pub files: usize,
+    pub bytes: u64,
+    /// Files that couldn't be moved, tried again on the next run
+    pub failed: usize,
+}
+
+/// Moves media older than a number of days to a [`ColdStore`]. The rows and the text
+/// recorded from the files stay in the database, a file is fetched back when it is
+/// opened and deleted locally again a day later.
+pub struct ColdStorage {
+    db: Arc<DatabaseManager>,
+    store: ColdStore,
+    data_dir: PathBuf,
+    after_days: u32,
+    blob_store: FrameBlobStore,
+    // one fetch at a time per file, they'd write the same `.partial` path
+    fetching: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ColdStorage {
+    /// `output_path` is where media is recorded to, object keys are relative to it.
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        store: ColdStore,
+        output_path: &str,
+        after_days: u32,
+    ) -> Self {
+        Self {
+            blob_store: FrameBlobStore::new(db.clone(), output_path),
+            db,
+            store,
+            data_dir: PathBuf::from(output_path),
+            after_days,
+            fetching: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn store(&self) -> &ColdStore {
+        &self.store
+    }
+
+    pub fn after_days(&self) -> u32 {
+        self.after_days
+    }
+
+    /// Moves the media recorded more than `after_days` ago to the store. Files failing to
+    /// move are skipped, they don't hold back the newer ones.
+    pub async fn tier_old_media(&self) -> Result<TierReport> {
+        let before = Utc::now() - ChronoDuration::days(self.after_days as i64);
+        let mut report = TierReport::default();
+        for kind in [
+            MediaKind::VideoChunk,
+            MediaKind::VideoSegment,
+            MediaKind::AudioChunk,
+        ] {
+            loop {
+                let files = self.db.get_media_to_tier(kind, before, TIER_BATCH).await?;
+                let mut moved = 0;
+                for file in &files {
+                    let bytes = disk_usage(Path::new(&file.file_path)).await;
+                    match self.tier(kind, file).await {
+                        Ok(()) => {
+                            moved += 1;
+                            report.bytes += bytes;
+                        }
+                        Err(e) => {
+                            warn!("failed to move {} to cold storage: {}", file.file_path, e);
+                            report.failed += 1;
+                        }
+                    }
+                }
+                report.files += moved;
+                // a batch of only failures comes back the same
+                if (files.len() as i64) < TIER_BATCH || moved == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn tier(&self, kind: MediaKind, file: &MediaFile) -> Result<()> {
+        let path = Path::new(&file.file_path);
+        let mut key = self.cold_key(path);
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                key.push('/');
+                let mut names = Vec::new();
+                let mut entries = tokio::fs::read_dir(path).await?;
+                while let Some(entry) = entries.next_entry().await? {
+                    if entry.file_type().await?.is_file() {
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        let data = tokio::fs::read(entry.path()).await?;
+                        self.store.put(&format!("{}{}", key, name), &data).await?;
+                        names.push(name);
+                    }
+                }
+                self.store
+                    .put(
+                        &format!("{}{}", key, DIR_INDEX),
+                        names.join("\n").as_bytes(),
+                    )
+                    .await?;
+            }
+            Ok(_) => {
+                let data = tokio::fs::read(path).await?;
+                self.store.put(&key, &data).await?;
+            }
+            // deleted by hand, there is nothing to move
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                self.db.mark_media_purged(kind, &[file.id]).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        // recorded before deleting, a crash in between only leaves a local copy behind
+        self.db.mark_media_tiered(kind, file.id, &key).await?;
+        delete_media(&self.blob_store, kind, file).await?;
+        debug!("moved {} to cold storage as {}", file.file_path, key);
+        Ok(())
+    }
+
+    /// Fetches `file_path` back from the store when it was moved there. Returns whether it
+    /// was fetched.
+    pub async fn ensure_local(self: &Arc<Self>, file_path: &str) -> Result<bool> {
+        if tokio::fs::metadata(file_path).await.is_ok() {
+            return Ok(false);
+        }
+        let Some(media) = self.db.get_cold_media(file_path).await? else {
+            return Ok(false);
+        };
+
+        // finishes when the request waiting for it times out, the next one finds the file
+        let lock = self.fetch_lock(file_path);
+        let cold = self.clone();
+        let path = PathBuf::from(file_path);
+        tokio::spawn(async move {
+            let _fetching = lock.lock().await;
+            // fetched while this one waited
+            if tokio::fs::metadata(&path).await.is_ok() {
+                return Ok(false);
+            }
+            cold.fetch(&media.cold_key, &path).await?;
+            cold.db.mark_media_fetched(media.kind, media.id).await?;
+            info!("fetched {} from cold storage", path.display());
+            Ok::<_, anyhow::Error>(true)
+        })
+        .await?
+    }
+
+    fn fetch_lock(&self, file_path: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut fetching = self.fetching.lock().unwrap_or_else(|e| e.into_inner());
+        // only the map holds the locks of fetches that are over
+        fetching.retain(|_, lock| Arc::strong_count(lock) > 1);
+        fetching.entry(file_path.to_string()).or_default().clone()
+    }
+
+    async fn fetch(&self, key: &str, path: &Path) -> Result<()> {
+        if !key.ends_with('/') {
+            return write_atomically(path, &self.store.get(key).await?).await;
+        }
+
+        let index = self.store.get(&format!("{}{}", key, DIR_INDEX)).await?;
+        let partial = PathBuf::from(format!("{}.partial", path.to_string_lossy()));
+        tokio::fs::create_dir_all(&partial).await?;
+        for name in String::from_utf8_lossy(&index).lines() {
+            if !is_normal_name(name) {
+                return Err(anyhow!("invalid file name '{}' in {}", name, key));
+            }
+            let data = self.store.get(&format!("{}{}", key, name)).await?;
+            tokio::fs::write(partial.join(name), data).await?;
+        }
+        tokio::fs::rename(&partial, path).await?;
+        Ok(())
+    }
+
+    /// Deletes the local copies fetched more than a day ago.
+    pub async fn evict_fetched(&self) -> Result<usize> {
+        let before = Utc::now() - ChronoDuration::hours(FETCHED_TTL_HOURS);
+        let mut evicted = 0;
+        for kind in [
+            MediaKind::VideoChunk,
+            MediaKind::VideoSegment,
+            MediaKind::AudioChunk,
+        ] {
+            let files = self
+                .db
+                .get_media_fetched_before(kind, before, TIER_BATCH)
+                .await?;
+            let mut ids = Vec::with_capacity(files.len());
+            for file in &files {
+                match delete_media(&self.blob_store, kind, file).await {
+                    Ok(()) => ids.push(file.id),
+                    Err(e) => warn!("failed to delete fetched {}: {}", file.file_path, e),
+                }
+            }
+            self.db.clear_media_fetched(kind, &ids).await?;
+            evicted += ids.len();
+        }
+        Ok(evicted)
+    }
+
+    /// Deletes the cold copy of a file, when retention purges it.
+    pub async fn forget(&self, file_path: &str) -> Result<()> {
+        let Some(media) = self.db.get_cold_media(file_path).await? else {
+            return Ok(());
+        };
+        if !media.cold_key.ends_with('/') {
+            return self.store.delete(&media.cold_key).await;
+        }
+
+        let index_key = format!("{}{}", media.cold_key, DIR_INDEX);
+        let index = self.store.get(&index_key).await?;
+        for name in String::from_utf8_lossy(&index).lines() {
+            self.store
+                .delete(&format!("{}{}", media.cold_key, name))
+                .await?;
+        }
+        self.store.delete(&index_key).await
+    }
+
+    /// The object key of a media file, its path in the data dir.
+    fn cold_key(&self, path: &Path) -> String {
+        let (base, relative) = match path.strip_prefix(&self.data_dir) {
+            Ok(relative) => (None, relative),
+            // e.g. videos added without copying them
+            Err(_) => (Some("external"), path),
+        };
+        base.into_iter()
+            .map(str::to_string)
+            .chain(relative.components().filter_map(|part| match part {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            }))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+fn is_normal_name(name: &str) -> bool {
+    let mut parts = Path::new(name).components();
+    matches!(parts.next(), Some(Component::Normal(_))) && parts.next().is_none()
+}
+
+async fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let partial = PathBuf::from(format!("{}.partial", path.to_string_lossy()));
+    tokio::fs::write(&partial, data).await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+/// Moves old media to cold storage once an hour.
+pub async fn run_cold_storage(cold: Arc<ColdStorage>) {
+    info!(
+        "moving media older than {} days to {}",
+        cold.after_days(),
+        cold.store()
+    );
+    loop {
+        match cold.tier_old_media().await {
+            Ok(report) => {
+                if report.files > 0 {
+                    info!(
+                        "moved {} files ({}) to cold storage",
+                        report.files,
+                        crate::storage::format_bytes(report.bytes)
+                    );
+                }
+                if report.failed > 0 {
+                    warn!("{} files failed to move to cold storage", report.failed);
+                }
+            }
+            Err(e) => warn!("failed to move media to cold storage: {}", e),
+        }
+        if let Err(e) = cold.evict_fetched().await {
+            warn!("failed to delete fetched media: {}", e);
+        }
+        tokio::time::sleep(TIER_INTERVAL).await;
+    }
+}
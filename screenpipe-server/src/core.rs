@@ -1,16 +1,43 @@
+use crate::frame_storage::{FrameBlobStore, FrameStorage};
+use crate::screen_recording::{record_screen, ScreenRecordingConfig};
 use crate::VideoCapture;
 use anyhow::Result;
+use chrono::Utc;
 use futures::future::join_all;
-use screenpipe_core::pii_removal::remove_pii;
-use screenpipe_core::Language;
-use screenpipe_db::{DatabaseManager, Speaker};
-use screenpipe_events::{poll_meetings_events, send_event};
+use image::DynamicImage;
+use screenpipe_core::{Language, RedactionPolicy, WasmPlugins};
+use screenpipe_db::{DatabaseManager, OcrEngine as DBOcrEngine, Speaker, WindowGeometry};
+use screenpipe_events::{
+    poll_meetings_events, send_capture_event, send_event, CaptureEvent, FocusTracker, OcrCapture,
+};
+use screenpipe_vision::accessibility::{AccessibilityConfig, TextSource};
+use screenpipe_vision::barcode;
 use screenpipe_vision::core::WindowOcr;
-use screenpipe_vision::OcrEngine;
+use screenpipe_vision::face_blur::FaceBlurConfig;
+use screenpipe_vision::capture_screenshot_by_window::{WindowBounds, WindowFilters};
+use screenpipe_vision::image_embedding::ImageEmbeddingModel;
+use screenpipe_vision::ocr_quality::OcrQualityConfig;
+use screenpipe_vision::privacy::PrivacyPolicy;
+use screenpipe_vision::scroll_stitch::{ScrollDocument, ScrollStitcher};
+use screenpipe_vision::table_detection::detect_tables;
+use screenpipe_vision::{AdaptiveFpsConfig, OcrEngine};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Handle;
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+// Frames waiting for the image embedder, further frames are skipped until it catches up
+const IMAGE_EMBEDDING_QUEUE: usize = 4;
+// Window images waiting for the code detector, same as the image embedder
+const CODE_DETECTION_QUEUE: usize = 4;
+// Window captures waiting for the scroll stitcher, same as the image embedder
+const SCROLL_STITCH_QUEUE: usize = 4;
+// Stored frames of a window a new capture of it is compared to with `phash_threshold`
+const PHASH_LOOKBACK: i64 = 10;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn start_continuous_recording(
@@ -20,29 +47,55 @@ pub async fn start_continuous_recording(
     video_chunk_duration: Duration,
     ocr_engine: Arc<OcrEngine>,
     monitor_ids: Vec<u32>,
-    use_pii_removal: bool,
+    monitor_fps: HashMap<u32, f64>,
+    monitor_regions: HashMap<u32, WindowBounds>,
+    monitor_frame_storage: HashMap<u32, FrameStorage>,
+    redaction_policy: Arc<RedactionPolicy>,
+    face_blur: Option<Arc<FaceBlurConfig>>,
+    plugins: Option<Arc<WasmPlugins>>,
     vision_disabled: bool,
     vision_handle: &Handle,
-    ignored_windows: &[String],
-    include_windows: &[String],
+    window_filters: Arc<WindowFilters>,
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
+    focused_window_only: bool,
     realtime_vision: bool,
+    phash_threshold: Option<u32>,
+    adaptive_fps: Option<AdaptiveFpsConfig>,
+    privacy_policy: PrivacyPolicy,
+    screen_recording: Option<ScreenRecordingConfig>,
+    scroll_stitching: bool,
+    detect_codes: bool,
+    image_embeddings: bool,
+    accessibility: Arc<AccessibilityConfig>,
+    ocr_quality: OcrQualityConfig,
 ) -> Result<()> {
     info!("Starting video recording for monitors {:?}", monitor_ids);
-    let video_tasks = if !vision_disabled {
+    let mut video_tasks = if !vision_disabled {
         monitor_ids
             .iter()
             .map(|&monitor_id| {
                 let db_manager_video = Arc::clone(&db);
                 let output_path_video = Arc::clone(&output_path);
                 let ocr_engine = Arc::clone(&ocr_engine);
-                let ignored_windows_video = ignored_windows.to_vec();
-                let include_windows_video = include_windows.to_vec();
+                let window_filters = Arc::clone(&window_filters);
+                let redaction_policy = Arc::clone(&redaction_policy);
+                let face_blur = face_blur.clone();
+                let plugins = plugins.clone();
+                let accessibility = Arc::clone(&accessibility);
 
                 let languages = languages.clone();
+                let fps = monitor_fps.get(&monitor_id).copied().unwrap_or(fps);
+                let capture_region = monitor_regions.get(&monitor_id).cloned();
+                let frame_storage = monitor_frame_storage
+                    .get(&monitor_id)
+                    .copied()
+                    .unwrap_or_default();
 
-                info!("Starting video recording for monitor {}", monitor_id);
+                info!(
+                    "Starting video recording for monitor {} at {} fps",
+                    monitor_id, fps
+                );
                 vision_handle.spawn(async move {
                     // Wrap in a loop with recovery logic
                     loop {
@@ -53,13 +106,25 @@ pub async fn start_continuous_recording(
                             fps,
                             ocr_engine.clone(),
                             monitor_id,
-                            use_pii_removal,
-                            &ignored_windows_video,
-                            &include_windows_video,
+                            redaction_policy.clone(),
+                            face_blur.clone(),
+                            plugins.clone(),
+                            window_filters.clone(),
                             video_chunk_duration,
+                            frame_storage,
                             languages.clone(),
                             capture_unfocused_windows,
+                            focused_window_only,
                             realtime_vision,
+                            phash_threshold,
+                            adaptive_fps,
+                            privacy_policy,
+                            capture_region.clone(),
+                            scroll_stitching,
+                            detect_codes,
+                            image_embeddings,
+                            accessibility.clone(),
+                            ocr_quality,
                         )
                         .await
                         {
@@ -85,6 +150,36 @@ pub async fn start_continuous_recording(
         })]
     };
 
+    if let (false, Some(config)) = (vision_disabled, screen_recording) {
+        video_tasks.extend(monitor_ids.iter().map(|&monitor_id| {
+            let db = Arc::clone(&db);
+            let output_path = Arc::clone(&output_path);
+            let window_filters = Arc::clone(&window_filters);
+            let capture_region = monitor_regions.get(&monitor_id).cloned();
+
+            vision_handle.spawn(async move {
+                loop {
+                    if let Err(e) = record_screen(
+                        db.clone(),
+                        output_path.clone(),
+                        monitor_id,
+                        config,
+                        window_filters.clone(),
+                        capture_unfocused_windows,
+                        focused_window_only,
+                        privacy_policy,
+                        capture_region.clone(),
+                    )
+                    .await
+                    {
+                        error!("screen recording for monitor {} failed: {}", monitor_id, e);
+                    }
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            })
+        }));
+    }
+
     if !vision_disabled {
         vision_handle.spawn(async move {
             info!("Starting meeting events polling");
@@ -117,16 +212,40 @@ async fn record_video(
     fps: f64,
     ocr_engine: Arc<OcrEngine>,
     monitor_id: u32,
-    use_pii_removal: bool,
-    ignored_windows: &[String],
-    include_windows: &[String],
+    redaction_policy: Arc<RedactionPolicy>,
+    face_blur: Option<Arc<FaceBlurConfig>>,
+    plugins: Option<Arc<WasmPlugins>>,
+    window_filters: Arc<WindowFilters>,
     video_chunk_duration: Duration,
+    frame_storage: FrameStorage,
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
+    focused_window_only: bool,
     realtime_vision: bool,
+    phash_threshold: Option<u32>,
+    adaptive_fps: Option<AdaptiveFpsConfig>,
+    privacy_policy: PrivacyPolicy,
+    capture_region: Option<WindowBounds>,
+    scroll_stitching: bool,
+    detect_codes: bool,
+    image_embeddings: bool,
+    accessibility: Arc<AccessibilityConfig>,
+    ocr_quality: OcrQualityConfig,
 ) -> Result<()> {
     info!("record_video: Starting for monitor {}", monitor_id);
     let device_name = Arc::new(format!("monitor_{}", monitor_id));
+    let scroll_stitcher =
+        scroll_stitching.then(|| spawn_scroll_stitcher(db.clone(), output_path.clone()));
+    let mut focus_tracker = FocusTracker::default();
+    // csv of the tables last stored per window of the last capture, a table staying on screen
+    // is stored once
+    let mut window_tables: HashMap<(String, String), Vec<String>> = HashMap::new();
+    let image_embedder = image_embeddings.then(|| spawn_image_embedder(db.clone()));
+    let code_detector =
+        detect_codes.then(|| spawn_code_detector(db.clone(), redaction_policy.clone()));
+    let blob_store = frame_storage
+        .deduplicates()
+        .then(|| Arc::new(FrameBlobStore::new(db.clone(), &output_path)));
 
     // Add heartbeat counter
     let mut heartbeat_counter: u64 = 0;
@@ -163,13 +282,24 @@ async fn record_video(
         &output_path,
         fps,
         video_chunk_duration,
+        frame_storage,
+        blob_store,
         new_chunk_callback,
         Arc::clone(&ocr_engine),
         monitor_id,
-        ignored_windows,
-        include_windows,
+        window_filters,
+        redaction_policy,
+        face_blur,
+        plugins,
         languages,
         capture_unfocused_windows,
+        focused_window_only,
+        phash_threshold,
+        adaptive_fps,
+        privacy_policy,
+        capture_region,
+        accessibility,
+        ocr_quality,
     );
 
     info!(
@@ -236,16 +366,50 @@ async fn record_video(
             );
 
             for window_result in &frame.window_ocr_results {
+                if let Some(threshold) = phash_threshold {
+                    // the capture loop only compares consecutive screens, this also catches
+                    // windows coming back and captures made before a restart
+                    let duplicate = db
+                        .find_near_duplicate_frame(
+                            &window_result.app_name,
+                            &window_result.window_name,
+                            window_result.phash as i64,
+                            threshold,
+                            PHASH_LOOKBACK,
+                        )
+                        .await;
+                    match duplicate {
+                        Ok(Some(frame_id)) => {
+                            debug!(
+                                "Skipping window {}, near duplicate of frame {}",
+                                window_result.window_name, frame_id
+                            );
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to look up near duplicate frames: {}", e),
+                    }
+                }
+
                 let insert_frame_start = std::time::Instant::now();
                 let result = db
                     .insert_frame(
                         &device_name,
                         None,
                         window_result.browser_url.as_deref(),
+                        window_result.browser_title.as_deref(),
                         Some(window_result.app_name.as_str()),
+                        window_result.app_id.as_deref(),
                         Some(window_result.window_name.as_str()),
+                        Some(&WindowGeometry {
+                            x: window_result.bounds.x,
+                            y: window_result.bounds.y,
+                            width: window_result.bounds.width,
+                            height: window_result.bounds.height,
+                        }),
                         window_result.focused,
-                        Some(window_result.visible_percentage)
+                        Some(window_result.visible_percentage),
+                        Some(window_result.phash as i64),
                     )
                     .await;
 
@@ -264,14 +428,22 @@ async fn record_video(
                             frame_id,
                             insert_duration.as_millis()
                         );
-                        let text_json =
-                            serde_json::to_string(&window_result.text_json).unwrap_or_default();
+                        let text_json = window_result.layout().to_text_json();
 
-                        let text = if use_pii_removal {
-                            &remove_pii(&window_result.text)
-                        } else {
-                            &window_result.text
-                        };
+                        // already redacted by the capture pipeline, see `redact_capture_result`
+                        let text = &window_result.text;
+                        let stored_at = Utc::now();
+
+                        if window_result.focused {
+                            if let Some(change) = focus_tracker.observe(
+                                &device_name,
+                                &window_result.app_name,
+                                &window_result.window_name,
+                                stored_at,
+                            ) {
+                                send_capture_event(CaptureEvent::AppFocus(change));
+                            }
+                        }
 
                         if realtime_vision {
                             let send_event_start = std::time::Instant::now();
@@ -280,13 +452,16 @@ async fn record_video(
                                 WindowOcr {
                                     image: Some(frame.image.clone()),
                                     text: text.clone(),
-                                    text_json: window_result.text_json.clone(),
+                                    lines: window_result.lines.clone(),
+                                    words: window_result.words.clone(),
                                     app_name: window_result.app_name.clone(),
+                                    app_id: window_result.app_id.clone(),
                                     window_name: window_result.window_name.clone(),
                                     focused: window_result.focused,
                                     confidence: window_result.confidence,
                                     timestamp: frame.timestamp,
                                     browser_url: window_result.browser_url.clone(),
+                                    browser_title: window_result.browser_title.clone(),
                                     visible_percentage: window_result.visible_percentage,
                                 },
                             ) {
@@ -303,13 +478,19 @@ async fn record_video(
                             }
                         }
 
+                        let text_engine = match window_result.source {
+                            TextSource::Ocr => (*ocr_engine).clone().into(),
+                            TextSource::Ax => DBOcrEngine::AppleAccessibility,
+                            TextSource::Uia => DBOcrEngine::WindowsUiAutomation,
+                        };
                         let insert_ocr_start = std::time::Instant::now();
                         if let Err(e) = db
                             .insert_ocr_text(
                                 frame_id,
                                 text,
                                 &text_json,
-                                Arc::new((*ocr_engine).clone().into()),
+                                Arc::new(text_engine),
+                                window_result.low_quality,
                             )
                             .await
                         {
@@ -333,6 +514,75 @@ async fn record_video(
                                 frame_id,
                                 ocr_insert_duration.as_millis()
                             );
+
+                            if !text.is_empty() {
+                                send_capture_event(CaptureEvent::Ocr(OcrCapture {
+                                    frame_id,
+                                    timestamp: stored_at,
+                                    device_name: device_name.to_string(),
+                                    app_name: window_result.app_name.clone(),
+                                    window_name: window_result.window_name.clone(),
+                                    browser_url: window_result.browser_url.clone(),
+                                    focused: window_result.focused,
+                                    text: text.clone(),
+                                }));
+                            }
+
+                            if let Some(stitcher) = &scroll_stitcher {
+                                let capture = ScrollCapture {
+                                    app_name: window_result.app_name.clone(),
+                                    window_name: window_result.window_name.clone(),
+                                    frame_id,
+                                    image: window_result.image.clone(),
+                                    text: text.clone(),
+                                };
+                                if stitcher.capacity() == 0 || stitcher.try_send(capture).is_err() {
+                                    debug!("Scroll stitcher busy, skipping frame {}", frame_id);
+                                }
+                            }
+
+                            if let Some(detector) = &code_detector {
+                                if detector.capacity() == 0
+                                    || detector
+                                        .try_send((frame_id, window_result.image.clone()))
+                                        .is_err()
+                                {
+                                    debug!("Code detector busy, skipping frame {}", frame_id);
+                                }
+                            }
+
+                            if let Some(embedder) = &image_embedder {
+                                // drop frames while the model is busy rather than queue them
+                                if embedder.capacity() == 0
+                                    || embedder
+                                        .try_send((frame_id, window_result.image.clone()))
+                                        .is_err()
+                                {
+                                    debug!("Image embedder busy, skipping frame {}", frame_id);
+                                }
+                            }
+
+                            let tables = detect_tables(&window_result.words);
+                            let csvs: Vec<String> = tables.iter().map(|t| t.to_csv()).collect();
+                            let window = (
+                                window_result.app_name.clone(),
+                                window_result.window_name.clone(),
+                            );
+                            if csvs.is_empty() {
+                                window_tables.remove(&window);
+                            } else if window_tables.get(&window) != Some(&csvs) {
+                                for (table, csv) in tables.iter().zip(&csvs) {
+                                    if let Err(e) =
+                                        db.insert_table(frame_id, &table.cells, csv).await
+                                    {
+                                        error!(
+                                            "Failed to insert table for frame {}: {}",
+                                            frame_id, e
+                                        );
+                                    }
+                                }
+                                window_tables.insert(window, csvs);
+                            }
                         }
                     }
                     Err(e) => {
@@ -343,6 +593,13 @@ async fn record_video(
                     }
                 }
             }
+
+            // windows that closed or changed title are forgotten
+            window_tables.retain(|(app_name, window_name), _| {
+                frame.window_ocr_results.iter().any(|window_result| {
+                    window_result.app_name == *app_name && window_result.window_name == *window_name
+                })
+            });
         } else {
             // Log when frame queue is empty
             if heartbeat_counter % 10 == 0 {
@@ -368,6 +625,176 @@ async fn record_video(
     }
 }
 
+struct ScrollCapture {
+    app_name: String,
+    window_name: String,
+    frame_id: i64,
+    image: DynamicImage,
+    text: String,
+}
+
+/// Stitches the window captures of a monitor sent to it one at a time. Documents still open
+/// are saved once the sender is dropped, when recording stops, on an error or when the task
+/// is aborted.
+fn spawn_scroll_stitcher(
+    db: Arc<DatabaseManager>,
+    output_path: Arc<String>,
+) -> mpsc::Sender<ScrollCapture> {
+    let (sender, mut receiver) = mpsc::channel::<ScrollCapture>(SCROLL_STITCH_QUEUE);
+    tokio::spawn(async move {
+        let mut stitcher = ScrollStitcher::default();
+        while let Some(capture) = receiver.recv().await {
+            let stitched = tokio::task::spawn_blocking(move || {
+                let documents = stitcher.push(
+                    &capture.app_name,
+                    &capture.window_name,
+                    capture.frame_id,
+                    &capture.image,
+                    &capture.text,
+                );
+                (stitcher, documents)
+            })
+            .await;
+            let documents = match stitched {
+                Ok((returned, documents)) => {
+                    stitcher = returned;
+                    documents
+                }
+                Err(e) => {
+                    error!("Failed to spawn blocking task: {}", e);
+                    return;
+                }
+            };
+            for document in documents {
+                save_scroll_document(db.clone(), output_path.clone(), document).await;
+            }
+        }
+        for document in stitcher.finish_all() {
+            save_scroll_document(db.clone(), output_path.clone(), document).await;
+        }
+    });
+    sender
+}
+
+/// Writes the stitched image next to the video chunks and records the document.
+async fn save_scroll_document(
+    db: Arc<DatabaseManager>,
+    output_path: Arc<String>,
+    document: ScrollDocument,
+) {
+    let image_path = PathBuf::from(output_path.as_str())
+        .join("documents")
+        .join(format!("document_{}.png", Uuid::new_v4()));
+    let image = document.image;
+    let save_path = image_path.clone();
+    let saved = tokio::task::spawn_blocking(move || -> Result<()> {
+        if let Some(parent) = save_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        image.save(&save_path)?;
+        Ok(())
+    })
+    .await;
+    match saved {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            error!("Failed to save scroll document image: {}", e);
+            return;
+        }
+        Err(e) => {
+            error!("Failed to spawn blocking task: {}", e);
+            return;
+        }
+    }
+
+    match db
+        .insert_scroll_document(
+            &document.app_name,
+            &document.window_name,
+            &document.text,
+            &image_path.to_string_lossy(),
+            &document.frame_ids,
+        )
+        .await
+    {
+        Ok(id) => debug!(
+            "Stitched {} frames of {} into document {}",
+            document.frame_ids.len(),
+            document.window_name,
+            id
+        ),
+        Err(e) => error!("Failed to insert scroll document: {}", e),
+    }
+}
+
+/// Decodes the codes in the window images sent to it one at a time and records the ones not
+/// seen in the last minute, redacted like the text of the frame.
+fn spawn_code_detector(
+    db: Arc<DatabaseManager>,
+    redaction_policy: Arc<RedactionPolicy>,
+) -> mpsc::Sender<(i64, DynamicImage)> {
+    let (sender, mut receiver) = mpsc::channel::<(i64, DynamicImage)>(CODE_DETECTION_QUEUE);
+    tokio::spawn(async move {
+        while let Some((frame_id, image)) = receiver.recv().await {
+            let detected = tokio::task::spawn_blocking(move || barcode::detect_codes(&image)).await;
+            let codes = match detected {
+                Ok(codes) => codes,
+                Err(e) => {
+                    error!("Failed to spawn blocking task: {}", e);
+                    continue;
+                }
+            };
+
+            let seen_since = Utc::now() - chrono::Duration::seconds(60);
+            for code in codes {
+                let payload = redaction_policy.redact(&code.payload);
+                match db
+                    .insert_frame_code(frame_id, &code.format, &payload, seen_since)
+                    .await
+                {
+                    Ok(Some(id)) => {
+                        debug!("Saved {} code {} for frame {}", code.format, id, frame_id)
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to insert frame code: {}", e),
+                }
+            }
+        }
+    });
+    sender
+}
+
+/// Embeds the frames sent to it one at a time and stores the embeddings for visual search.
+fn spawn_image_embedder(db: Arc<DatabaseManager>) -> mpsc::Sender<(i64, DynamicImage)> {
+    let (sender, mut receiver) = mpsc::channel::<(i64, DynamicImage)>(IMAGE_EMBEDDING_QUEUE);
+    tokio::spawn(async move {
+        let model = match tokio::task::spawn_blocking(ImageEmbeddingModel::shared).await {
+            Ok(Ok(model)) => model,
+            Ok(Err(e)) => {
+                error!("Failed to load image embedding model: {}", e);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to spawn blocking task: {}", e);
+                return;
+            }
+        };
+        while let Some((frame_id, image)) = receiver.recv().await {
+            let embedding = tokio::task::spawn_blocking(move || model.embed_image(&image)).await;
+            match embedding {
+                Ok(Ok(embedding)) => {
+                    if let Err(e) = db.insert_frame_embedding(frame_id, &embedding).await {
+                        error!("Failed to insert frame embedding: {}", e);
+                    }
+                }
+                Ok(Err(e)) => error!("Failed to embed frame {}: {}", frame_id, e),
+                Err(e) => error!("Failed to spawn blocking task: {}", e),
+            }
+        }
+    });
+    sender
+}
+
 pub async fn merge_speakers(
     db: &DatabaseManager,
     speaker_to_keep_id: i64,
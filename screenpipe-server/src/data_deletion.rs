@@ -0,0 +1,181 @@
+use crate::cold_storage::ColdStorage;
+use crate::frame_storage::FrameBlobStore;
+use crate::retention::{delete_media, disk_usage};
+use crate::search_query::parse_time;
+use anyhow::Result;
+use oasgen::OaSchema;
+use screenpipe_db::{DataDeletion, DataFilter, DatabaseManager, MediaFile, MediaKind};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Filters of `DELETE /data`, data matching all of them is deleted. One is required.
+#[derive(OaSchema, Deserialize, Debug, Default)]
+pub struct DeleteDataQuery {
+    /// Recorded at or after it, a date like 2024-01-01 or an RFC 3339 time
+    #[serde(default)]
+    pub after: Option<String>,
+    /// Recorded before it
+    #[serde(default)]
+    pub before: Option<String>,
+    /// Apps whose name contains it, e.g. zoom. Audio has no app and is kept.
+    #[serde(default)]
+    pub app: Option<String>,
+    #[serde(default)]
+    pub window: Option<String>,
+    /// A monitor or an audio device
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Only report what would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl DeleteDataQuery {
+    pub fn filter(&self) -> Result<DataFilter, String> {
+        let time = |name: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|value| {
+                    parse_time(value.trim()).ok_or_else(|| {
+                        format!(
+                            "invalid {} '{}', expected a date like 2024-01-01 or an RFC 3339 time",
+                            name, value
+                        )
+                    })
+                })
+                .transpose()
+        };
+        let name = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+
+        let filter = DataFilter {
+            start_time: time("after", &self.after)?,
+            end_time: time("before", &self.before)?,
+            app_name: name(&self.app),
+            window_name: name(&self.window),
+            device_name: name(&self.device),
+        };
+        if filter.is_empty() {
+            return Err("at least one of after, before, app, window or device is required".into());
+        }
+        if let (Some(start_time), Some(end_time)) = (filter.start_time, filter.end_time) {
+            if end_time <= start_time {
+                return Err("before must be later than after".to_string());
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[derive(OaSchema, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DataDeletionReport {
+    pub dry_run: bool,
+    pub deleted: DataDeletion,
+    /// Recordings left without data removed from disk. Recordings holding other frames
+    /// too stay, the chunk still recorded to is removed once it's closed.
+    pub media_files: u64,
+    pub media_bytes: u64,
+}
+
+// How often chunks queued while they were recorded to are checked
+const QUEUED_DELETION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Deletes the data matching `filter` and then the files it was recorded in, or only
+/// reports what would be deleted on a `dry_run`. Files that fail to delete are left to
+/// retention and the storage quota.
+pub async fn delete_data(
+    db: &DatabaseManager,
+    blob_store: &FrameBlobStore,
+    cold_storage: Option<&ColdStorage>,
+    filter: &DataFilter,
+    dry_run: bool,
+) -> Result<DataDeletionReport> {
+    let (deleted, media) = db.delete_data(filter, dry_run).await?;
+    let mut report = DataDeletionReport {
+        dry_run,
+        deleted,
+        media_files: 0,
+        media_bytes: 0,
+    };
+    (report.media_files, report.media_bytes) =
+        delete_files(db, blob_store, cold_storage, media, dry_run).await?;
+
+    if !dry_run {
+        info!(
+            "deleted {} frames, {} transcriptions and {} media files matching {:?}",
+            report.deleted.frames, report.deleted.transcriptions, report.media_files, filter
+        );
+    }
+    Ok(report)
+}
+
+/// Deletes the files of `media` from disk and cold storage and marks them purged, or only
+/// counts them on a `dry_run`. Returns the number and size of the files.
+async fn delete_files(
+    db: &DatabaseManager,
+    blob_store: &FrameBlobStore,
+    cold_storage: Option<&ColdStorage>,
+    media: Vec<(MediaKind, MediaFile)>,
+    dry_run: bool,
+) -> Result<(u64, u64)> {
+    let (mut files, mut total_bytes) = (0, 0);
+    for (kind, file) in media {
+        let bytes = disk_usage(Path::new(&file.file_path)).await;
+        if !dry_run {
+            if let Some(cold_storage) = cold_storage {
+                if let Err(e) = cold_storage.forget(&file.file_path).await {
+                    warn!(
+                        "failed to delete {} from cold storage: {}",
+                        file.file_path, e
+                    );
+                    continue;
+                }
+            }
+            if let Err(e) = delete_media(blob_store, kind, &file).await {
+                warn!("failed to delete {}: {}", file.file_path, e);
+                continue;
+            }
+            db.mark_media_purged(kind, &[file.id]).await?;
+        }
+        files += 1;
+        total_bytes += bytes;
+    }
+    Ok((files, total_bytes))
+}
+
+/// Deletes the files of the chunks deleted data was recorded to, once they are closed.
+pub async fn run_queued_deletions(
+    db: Arc<DatabaseManager>,
+    blob_store: FrameBlobStore,
+    cold_storage: Option<Arc<ColdStorage>>,
+) {
+    loop {
+        tokio::time::sleep(QUEUED_DELETION_INTERVAL).await;
+        let chunks = match db.closed_chunks_to_delete().await {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                warn!("failed to list chunks queued for deletion: {}", e);
+                continue;
+            }
+        };
+        if chunks.is_empty() {
+            continue;
+        }
+        let media = chunks
+            .into_iter()
+            .map(|chunk| (MediaKind::VideoChunk, chunk))
+            .collect();
+        match delete_files(&db, &blob_store, cold_storage.as_deref(), media, false).await {
+            Ok((files, _)) => info!("deleted {} chunks queued while recorded to", files),
+            Err(e) => warn!("failed to delete chunks queued for deletion: {}", e),
+        }
+    }
+}
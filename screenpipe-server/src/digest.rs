@@ -0,0 +1,346 @@
+use crate::analytics::{summarize_app_usage, AppTime, MAX_FOCUS_GAP_SECS};
+use crate::llm::Llm;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_db::{DatabaseManager, WindowSession};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// How often the digest of the day before is checked for
+const DIGEST_INTERVAL: Duration = Duration::from_secs(3600);
+const TOP_APPS: usize = 10;
+const TOP_TOPICS: usize = 20;
+// Distinct OCR texts and transcriptions topics are counted in
+const MAX_TOPIC_TEXTS: u32 = 5000;
+// Meeting app windows this close together are one meeting
+const MEETING_MERGE_GAP_SECS: i64 = 600;
+const MIN_MEETING_SECS: i64 = 300;
+const MEETING_APPS: &[&str] = &["zoom", "teams", "webex", "skype", "facetime"];
+const SUMMARY_PROMPT: &str = "You summarize a day of computer use from structured activity \
+                              data. Write a short paragraph about what the day was spent on, then \
+                              the highlights as a few bullet points. Only mention what is in the \
+                              data.";
+
+// Words too common in text and in app chrome to be a topic
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "because", "been", "before", "being", "both", "cancel",
+    "close", "could", "does", "done", "down", "each", "edit", "even", "file", "from", "going",
+    "have", "help", "here", "home", "into", "just", "know", "like", "more", "most", "much", "need",
+    "only", "open", "other", "over", "really", "right", "save", "search", "settings", "share",
+    "should", "some", "such", "than", "that", "their", "them", "then", "there", "these", "they",
+    "thing", "think", "this", "those", "through", "time", "tools", "very", "view", "want", "well",
+    "were", "what", "when", "where", "which", "while", "will", "window", "with", "would", "yeah",
+    "your",
+];
+
+// Parts of a window title, e.g. "report.pdf - Preview"
+static TITLE_SEPARATOR: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+[-—–|]\s+").unwrap());
+static DOCUMENT_FILE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^[^/\\]+\.(?:pdf|docx?|xlsx?|pptx?|odt|ods|odp|rtf|txt|md|csv|pages|numbers|key)$",
+    )
+    .unwrap()
+});
+static ONLINE_DOCUMENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(.+?) - Google (?:Docs|Sheets|Slides)").unwrap());
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Topic {
+    pub term: String,
+    /// OCR texts and transcriptions mentioning it
+    pub count: usize,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Meeting {
+    pub app_name: String,
+    /// Title of the meeting window focused longest
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// Transcriptions recorded during the meeting
+    pub transcriptions: usize,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OpenedDocument {
+    pub name: String,
+    pub app_name: String,
+    pub first_opened: DateTime<Utc>,
+    /// Time it was focused
+    pub seconds: i64,
+}
+
+/// What a day of captures was about, UTC.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyDigest {
+    pub date: NaiveDate,
+    /// Most used first
+    pub apps: Vec<AppTime>,
+    /// Most mentioned first
+    pub topics: Vec<Topic>,
+    pub meetings: Vec<Meeting>,
+    pub documents: Vec<OpenedDocument>,
+    /// Written by the digest LLM, when one is configured
+    pub summary: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+pub struct Digests {
+    db: Arc<DatabaseManager>,
+    llm: Option<Llm>,
+}
+
+impl Digests {
+    pub fn new(db: Arc<DatabaseManager>, llm: Option<Llm>) -> Self {
+        Self { db, llm }
+    }
+
+    pub fn llm(&self) -> Option<&Llm> {
+        self.llm.as_ref()
+    }
+
+    /// Summarizes what was captured on `date`, with the LLM summary when one is configured
+    /// and answers.
+    pub async fn generate(&self, date: NaiveDate) -> Result<DailyDigest> {
+        let (start, end) = day_bounds(date)?;
+        let usage = self
+            .db
+            .get_app_usage(start, end, MAX_FOCUS_GAP_SECS)
+            .await?;
+        let sessions = self
+            .db
+            .get_window_sessions(start, end, MAX_FOCUS_GAP_SECS)
+            .await?;
+        let mut texts = self
+            .db
+            .get_texts_between(start, end, MAX_TOPIC_TEXTS)
+            .await?;
+        let transcriptions = self
+            .db
+            .get_timeline_transcriptions(start, 0, end, MAX_TOPIC_TEXTS)
+            .await?;
+        let transcription_times: Vec<DateTime<Utc>> = transcriptions
+            .iter()
+            .map(|transcription| transcription.timestamp)
+            .collect();
+        texts.extend(
+            transcriptions
+                .into_iter()
+                .map(|transcription| transcription.transcription),
+        );
+
+        let mut apps = summarize_app_usage(usage);
+        apps.truncate(TOP_APPS);
+        let mut digest = DailyDigest {
+            date,
+            apps,
+            topics: top_topics(&texts, TOP_TOPICS),
+            meetings: find_meetings(&sessions, &transcription_times),
+            documents: opened_documents(&sessions),
+            summary: None,
+            generated_at: Utc::now(),
+        };
+        if let Some(llm) = &self.llm {
+            match llm
+                .complete(SUMMARY_PROMPT, &serde_json::to_string(&digest)?)
+                .await
+            {
+                Ok(summary) => digest.summary = Some(summary),
+                Err(e) => warn!("failed to summarize the digest of {}: {}", date, e),
+            }
+        }
+        Ok(digest)
+    }
+
+    pub async fn get(&self, date: NaiveDate) -> Result<Option<DailyDigest>> {
+        match self.db.get_daily_digest(date).await? {
+            Some(digest) => Ok(Some(serde_json::from_str(&digest)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The stored digest of `date`. A day without one is summarized, and stored once it is
+    /// over so later captures of the day still make it into the digest.
+    pub async fn get_or_generate(&self, date: NaiveDate) -> Result<DailyDigest> {
+        if let Some(digest) = self.get(date).await? {
+            return Ok(digest);
+        }
+        let digest = self.generate(date).await?;
+        if date < Utc::now().date_naive() {
+            self.store(&digest).await?;
+        }
+        Ok(digest)
+    }
+
+    async fn store(&self, digest: &DailyDigest) -> Result<()> {
+        self.db
+            .upsert_daily_digest(digest.date, &serde_json::to_string(digest)?)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Start of `date` and of the day after, in UTC.
+pub fn day_bounds(date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let start = date
+        .and_hms_opt(0, 0, 0)
+        .map(|start| Utc.from_utc_datetime(&start))
+        .ok_or_else(|| anyhow!("invalid date {}", date))?;
+    Ok((start, start + chrono::Duration::days(1)))
+}
+
+/// Words mentioned in the most texts. Words in more than half of them, like the labels of
+/// an app that was open all day, are left out.
+pub fn top_topics(texts: &[String], limit: usize) -> Vec<Topic> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        let words: HashSet<String> = text
+            .split(|c: char| !c.is_alphanumeric() && c != '-')
+            .map(|word| word.trim_matches('-').to_lowercase())
+            .filter(|word| {
+                (4..=24).contains(&word.chars().count())
+                    && word.chars().any(|c| c.is_alphabetic())
+                    && !STOPWORDS.contains(&word.as_str())
+            })
+            .collect();
+        for word in words {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+
+    let max_count = if texts.len() >= 10 {
+        texts.len() / 2
+    } else {
+        usize::MAX
+    };
+    let mut topics: Vec<Topic> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1 && *count <= max_count)
+        .map(|(term, count)| Topic { term, count })
+        .collect();
+    topics.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+    topics.truncate(limit);
+    topics
+}
+
+fn is_meeting_window(session: &WindowSession) -> bool {
+    let app_name = session.app_name.to_lowercase();
+    MEETING_APPS.iter().any(|app| app_name.contains(app))
+        // Google Meet in a browser tab
+        || session.window_name.starts_with("Meet - ")
+        || session
+            .browser_url
+            .as_deref()
+            .is_some_and(|url| url.contains("meet.google.com/"))
+}
+
+/// Stretches of meeting app windows, a few minutes apart at most and long enough to be a
+/// call rather than a glance at the app.
+pub fn find_meetings(
+    sessions: &[WindowSession],
+    transcription_times: &[DateTime<Utc>],
+) -> Vec<Meeting> {
+    let mut meetings: Vec<(Meeting, HashMap<String, i64>)> = Vec::new();
+    for session in sessions.iter().filter(|session| is_meeting_window(session)) {
+        let seconds = (session.end_time - session.start_time).num_seconds();
+        match meetings.last_mut() {
+            Some((meeting, titles))
+                if meeting.app_name == session.app_name
+                    && (session.start_time - meeting.end_time).num_seconds()
+                        <= MEETING_MERGE_GAP_SECS =>
+            {
+                meeting.end_time = meeting.end_time.max(session.end_time);
+                *titles.entry(session.window_name.clone()).or_default() += seconds;
+            }
+            _ => meetings.push((
+                Meeting {
+                    app_name: session.app_name.clone(),
+                    title: String::new(),
+                    start_time: session.start_time,
+                    end_time: session.end_time,
+                    transcriptions: 0,
+                },
+                HashMap::from([(session.window_name.clone(), seconds)]),
+            )),
+        }
+    }
+
+    meetings
+        .into_iter()
+        .filter(|(meeting, _)| {
+            (meeting.end_time - meeting.start_time).num_seconds() >= MIN_MEETING_SECS
+        })
+        .map(|(mut meeting, titles)| {
+            meeting.title = titles
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .map(|(title, _)| title)
+                .unwrap_or_default();
+            meeting.transcriptions = transcription_times
+                .iter()
+                .filter(|time| (meeting.start_time..=meeting.end_time).contains(time))
+                .count();
+            meeting
+        })
+        .collect()
+}
+
+/// Documents whose name shows in the title of a focused window, in the order they were
+/// first opened.
+pub fn opened_documents(sessions: &[WindowSession]) -> Vec<OpenedDocument> {
+    let mut documents: Vec<OpenedDocument> = Vec::new();
+    for session in sessions {
+        let Some(name) = document_name(&session.window_name) else {
+            continue;
+        };
+        let seconds = (session.end_time - session.start_time).num_seconds();
+        match documents
+            .iter_mut()
+            .find(|document| document.name == name && document.app_name == session.app_name)
+        {
+            Some(document) => document.seconds += seconds,
+            None => documents.push(OpenedDocument {
+                name: name.to_string(),
+                app_name: session.app_name.clone(),
+                first_opened: session.start_time,
+                seconds,
+            }),
+        }
+    }
+    documents
+}
+
+fn document_name(title: &str) -> Option<&str> {
+    if let Some(captures) = ONLINE_DOCUMENT.captures(title) {
+        return captures.get(1).map(|name| name.as_str().trim());
+    }
+    TITLE_SEPARATOR
+        .split(title)
+        .map(str::trim)
+        .find(|part| DOCUMENT_FILE.is_match(part))
+}
+
+/// Stores the digest of the day before once it is over, checked every hour.
+pub async fn run_daily_digests(digests: Arc<Digests>) {
+    info!("writing daily digests");
+    loop {
+        if let Some(yesterday) = Utc::now().date_naive().pred_opt() {
+            match digests.get(yesterday).await {
+                Ok(Some(_)) => {}
+                Ok(None) => match digests.get_or_generate(yesterday).await {
+                    Ok(_) => info!("wrote the daily digest of {}", yesterday),
+                    Err(e) => warn!("failed to write the daily digest of {}: {}", yesterday, e),
+                },
+                Err(e) => warn!("failed to read the daily digest of {}: {}", yesterday, e),
+            }
+        }
+        tokio::time::sleep(DIGEST_INTERVAL).await;
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use screenpipe_core::encryption::encrypt_media;
+use screenpipe_db::{DatabaseManager, MediaKind};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+// Files of each kind encrypted per round
+const ENCRYPTION_BATCH: i64 = 50;
+// Media this recent may still be written to, it is encrypted once finished
+const ENCRYPTION_MIN_AGE_MINUTES: i64 = 5;
+// Wait once every finished file is encrypted
+const ENCRYPTION_IDLE: Duration = Duration::from_secs(60);
+
+/// Encrypts finished media files with the media key in the background, oldest first.
+/// Files recorded before encryption was enabled are encrypted too.
+pub async fn run_media_encryption(db: Arc<DatabaseManager>) {
+    info!("encrypting media files at rest");
+    loop {
+        match encrypt_finished_media(&db).await {
+            Ok(0) => tokio::time::sleep(ENCRYPTION_IDLE).await,
+            Ok(count) => debug!("encrypted {} media files", count),
+            Err(e) => {
+                warn!("failed to encrypt media, retrying later: {}", e);
+                tokio::time::sleep(ENCRYPTION_IDLE).await;
+            }
+        }
+    }
+}
+
+/// Encrypts a batch of each kind of finished media. Returns how many files were
+/// encrypted or found encrypted already.
+pub async fn encrypt_finished_media(db: &DatabaseManager) -> Result<usize> {
+    let before = Utc::now() - ChronoDuration::minutes(ENCRYPTION_MIN_AGE_MINUTES);
+    let mut total = 0;
+    for kind in [
+        MediaKind::VideoChunk,
+        MediaKind::VideoSegment,
+        MediaKind::AudioChunk,
+    ] {
+        let files = db
+            .get_media_to_encrypt(kind, before, ENCRYPTION_BATCH)
+            .await?;
+        let mut encrypted = Vec::with_capacity(files.len());
+        for file in &files {
+            match encrypt_media(Path::new(&file.file_path)).await {
+                Ok(_) => encrypted.push(file.id),
+                // deleted by hand, nothing left to protect
+                Err(e)
+                    if e.downcast_ref::<std::io::Error>()
+                        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) =>
+                {
+                    encrypted.push(file.id)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        db.mark_media_encrypted(kind, &encrypted).await?;
+        total += encrypted.len();
+    }
+    Ok(total)
+}
@@ -0,0 +1,607 @@
+use crate::cold_storage::ColdStorage;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use screenpipe_core::encryption::readable_media;
+use screenpipe_db::{DatabaseManager, ExportFrame};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub const EXPORT_DIR: &str = "exports";
+/// The jobs, in [`EXPORT_DIR`]
+pub const JOBS_FILE: &str = "jobs.json";
+const MANIFEST: &str = "manifest.json";
+const FRAMES: &str = "frames.jsonl";
+const TRANSCRIPTIONS: &str = "transcriptions.jsonl";
+const ANNOTATIONS: &str = "annotations.json";
+const BOOKMARKS: &str = "bookmarks.json";
+const MEDIA_DIR: &str = "media";
+const MANIFEST_VERSION: u32 = 1;
+// Rows read from the database at a time
+const PAGE_SIZE: u32 = 500;
+// Exports read every recording of their range, more at once would only slow capture down
+const MAX_RUNNING_EXPORTS: usize = 1;
+
+fn default_include_media() -> bool {
+    true
+}
+
+/// Exports everything recorded in a time range to a zip archive.
+#[derive(OaSchema, Deserialize, Debug, Clone)]
+pub struct ExportRequest {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    /// Adds the recordings the frames and transcriptions are in, decrypted
+    #[serde(default = "default_include_media")]
+    pub include_media: bool,
+}
+
+impl ExportRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.end_time <= self.start_time {
+            return Err("end_time must be later than start_time".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+/// An export and how far along it is, kept in [`JOBS_FILE`] with its archive until it is
+/// deleted.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportJob {
+    pub id: String,
+    pub status: ExportStatus,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub include_media: bool,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Frames and transcriptions written
+    pub records_done: u64,
+    pub records_total: u64,
+    /// Recordings written
+    pub files_done: u64,
+    /// Counted once the records are written
+    pub files_total: Option<u64>,
+    /// 0 to 1, the records are the first half when media is included
+    pub progress: f64,
+    /// Size of the archive once done
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl ExportJob {
+    fn new(request: &ExportRequest) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            status: ExportStatus::Running,
+            start_time: request.start_time,
+            end_time: request.end_time,
+            include_media: request.include_media,
+            created_at: Utc::now(),
+            finished_at: None,
+            records_done: 0,
+            records_total: 0,
+            files_done: 0,
+            files_total: None,
+            progress: 0.0,
+            bytes: None,
+            error: None,
+        }
+    }
+
+    fn update_progress(&mut self) {
+        let records = fraction(self.records_done, self.records_total);
+        self.progress = match (self.include_media, self.files_total) {
+            (false, _) => records,
+            (true, None) => records / 2.0,
+            (true, Some(files_total)) => (records + fraction(self.files_done, files_total)) / 2.0,
+        };
+    }
+}
+
+fn fraction(done: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 1.0;
+    }
+    (done as f64 / total as f64).min(1.0)
+}
+
+/// The jobs kept in `path`, those running when the server stopped are failed.
+fn load_jobs(path: &Path) -> HashMap<String, ExportJob> {
+    let jobs = match std::fs::read_to_string(path) {
+        Ok(jobs) => jobs,
+        Err(_) => return HashMap::new(),
+    };
+    let jobs: Vec<ExportJob> = match serde_json::from_str(&jobs) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            warn!("ignoring unreadable {}: {}", path.display(), e);
+            return HashMap::new();
+        }
+    };
+    jobs.into_iter()
+        .map(|mut job| {
+            if job.status == ExportStatus::Running {
+                job.status = ExportStatus::Failed;
+                job.error = Some("interrupted by a restart, start it again".to_string());
+            }
+            (job.id.clone(), job)
+        })
+        .collect()
+}
+
+/// What an export holds, written last. Frames name their recording in `media`: a video
+/// with the frame at `offset_index`, or a directory with one image per frame.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub frames: u64,
+    pub transcriptions: u64,
+    pub annotations: u64,
+    pub bookmarks: u64,
+    pub media: Vec<ExportedFile>,
+    /// Recordings no longer on disk, e.g. removed by retention
+    pub missing_media: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedFile {
+    /// Inside the archive, with `/` separators
+    pub path: String,
+    /// Recorded path of the file
+    pub source: String,
+    pub size: u64,
+}
+
+pub struct Exports {
+    db: Arc<DatabaseManager>,
+    cold_storage: Option<Arc<ColdStorage>>,
+    dir: PathBuf,
+    jobs_path: PathBuf,
+    jobs: Mutex<HashMap<String, ExportJob>>,
+}
+
+impl Exports {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        cold_storage: Option<Arc<ColdStorage>>,
+        screenpipe_dir: &Path,
+    ) -> Self {
+        let dir = screenpipe_dir.join(EXPORT_DIR);
+        let jobs_path = dir.join(JOBS_FILE);
+        let exports = Self {
+            db,
+            cold_storage,
+            jobs: Mutex::new(load_jobs(&jobs_path)),
+            dir,
+            jobs_path,
+        };
+        // left by the exports the restart interrupted
+        for job in exports.list() {
+            if job.status == ExportStatus::Failed {
+                let _ = std::fs::remove_file(exports.partial_path(&job.id));
+            }
+        }
+        exports
+    }
+
+    fn save(&self) {
+        let jobs = self.list();
+        let saved = serde_json::to_vec(&jobs)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                std::fs::create_dir_all(&self.dir)?;
+                Ok(std::fs::write(&self.jobs_path, json)?)
+            });
+        if let Err(e) = saved {
+            warn!("failed to save {}: {}", self.jobs_path.display(), e);
+        }
+    }
+
+    /// Starts exporting in the background. Fails while another export is running.
+    pub fn start(self: &Arc<Self>, request: ExportRequest) -> Result<ExportJob, String> {
+        let job = ExportJob::new(&request);
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let running = jobs
+                .values()
+                .filter(|job| job.status == ExportStatus::Running)
+                .count();
+            if running >= MAX_RUNNING_EXPORTS {
+                return Err("an export is already running".to_string());
+            }
+            jobs.insert(job.id.clone(), job.clone());
+        }
+        self.save();
+
+        let exports = self.clone();
+        let id = job.id.clone();
+        tokio::spawn(async move {
+            let result = exports.export(&id, &request).await;
+            if result.is_err() {
+                let _ = tokio::fs::remove_file(exports.partial_path(&id)).await;
+            }
+            exports.update(&id, |job| {
+                job.finished_at = Some(Utc::now());
+                match result {
+                    Ok(bytes) => {
+                        job.status = ExportStatus::Done;
+                        job.bytes = Some(bytes);
+                        job.progress = 1.0;
+                    }
+                    Err(e) => {
+                        warn!("export {} failed: {}", job.id, e);
+                        job.status = ExportStatus::Failed;
+                        job.error = Some(e.to_string());
+                    }
+                }
+            });
+            exports.save();
+        });
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Option<ExportJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Vec<ExportJob> {
+        let mut jobs: Vec<ExportJob> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    /// The archive of a finished export.
+    pub fn archive(&self, id: &str) -> Option<PathBuf> {
+        self.get(id)
+            .filter(|job| job.status == ExportStatus::Done)
+            .map(|job| self.archive_path(&job.id))
+    }
+
+    /// Forgets a finished export and deletes its archive. Whether it existed, running
+    /// exports can't be deleted.
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            match jobs.get(id).map(|job| job.status) {
+                None => return Ok(false),
+                Some(ExportStatus::Running) => return Err(anyhow!("the export is still running")),
+                Some(_) => jobs.remove(id),
+            }
+        };
+        self.save();
+        if job.is_some_and(|job| job.status == ExportStatus::Done) {
+            tokio::fs::remove_file(self.archive_path(id)).await?;
+        }
+        Ok(true)
+    }
+
+    fn archive_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.zip", id))
+    }
+
+    fn partial_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.zip.partial", id))
+    }
+
+    fn update(&self, id: &str, update: impl FnOnce(&mut ExportJob)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            update(job);
+            if job.status == ExportStatus::Running {
+                job.update_progress();
+            }
+        }
+    }
+
+    /// Writes the archive of job `id`, returns its size. It is written next to its final
+    /// path and renamed once complete.
+    async fn export(&self, id: &str, request: &ExportRequest) -> Result<u64> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.archive_path(id);
+        let partial = self.partial_path(id);
+
+        // zip writes block, they are done on a thread of their own
+        let file = File::create(&partial)?;
+        let (entries, received) = mpsc::channel(PAGE_SIZE as usize);
+        let writer = tokio::task::spawn_blocking(move || write_archive(file, received));
+        let manifest = self.send_entries(id, request, &entries).await;
+        drop(entries);
+        // the writer failing is why the entries couldn't be sent
+        writer.await??;
+        let manifest = manifest?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        let bytes = tokio::fs::metadata(&path).await?.len();
+        info!(
+            "exported {} frames, {} transcriptions and {} media files to {:?}",
+            manifest.frames,
+            manifest.transcriptions,
+            manifest.media.len(),
+            path
+        );
+        Ok(bytes)
+    }
+
+    /// Sends what the archive of job `id` holds to its writer, the manifest last.
+    async fn send_entries(
+        &self,
+        id: &str,
+        request: &ExportRequest,
+        entries: &mpsc::Sender<ArchiveEntry>,
+    ) -> Result<ExportManifest> {
+        let (start, end) = (request.start_time, request.end_time);
+        let send = |entry| async move {
+            entries
+                .send(entry)
+                .await
+                .map_err(|_| anyhow!("the archive writer stopped"))
+        };
+
+        let (frames, transcriptions) = self.db.count_export_items(start, end).await?;
+        self.update(id, |job| {
+            job.records_total = (frames + transcriptions) as u64
+        });
+
+        let mut media_names = MediaNames::default();
+        let mut recordings = Vec::new();
+        let mut manifest = ExportManifest {
+            version: MANIFEST_VERSION,
+            created_at: Utc::now(),
+            start_time: start,
+            end_time: end,
+            frames: 0,
+            transcriptions: 0,
+            annotations: 0,
+            bookmarks: 0,
+            media: Vec::new(),
+            missing_media: Vec::new(),
+        };
+
+        send(ArchiveEntry::File(FRAMES)).await?;
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .db
+                .get_export_frames(start, end, after_id, PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.id;
+            for frame in &page {
+                let media = request
+                    .include_media
+                    .then(|| media_names.name(&frame.file_path, |source| recordings.push(source)));
+                send(ArchiveEntry::Record(frame_record(frame, media))).await?;
+            }
+            manifest.frames += page.len() as u64;
+            self.update(id, |job| job.records_done += page.len() as u64);
+        }
+
+        send(ArchiveEntry::File(TRANSCRIPTIONS)).await?;
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .db
+                .get_export_transcriptions(start, end, after_id, PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.id;
+            for transcription in &page {
+                let mut record = serde_json::to_value(transcription)?;
+                // transcripts whose audio wasn't kept have no recording
+                if request.include_media && !transcription.file_path.is_empty() {
+                    record["media"] = media_names
+                        .name(&transcription.file_path, |source| recordings.push(source))
+                        .into();
+                }
+                send(ArchiveEntry::Record(record)).await?;
+            }
+            manifest.transcriptions += page.len() as u64;
+            self.update(id, |job| job.records_done += page.len() as u64);
+        }
+
+        let annotations = self
+            .db
+            .list_annotations(None, None, Some(start), Some(end), None, None, u32::MAX, 0)
+            .await?;
+        send(ArchiveEntry::File(ANNOTATIONS)).await?;
+        send(ArchiveEntry::Json(serde_json::to_value(&annotations)?)).await?;
+        manifest.annotations = annotations.len() as u64;
+        let bookmarks = self
+            .db
+            .list_bookmarks(Some(start), Some(end), None, u32::MAX, 0)
+            .await?;
+        send(ArchiveEntry::File(BOOKMARKS)).await?;
+        send(ArchiveEntry::Json(serde_json::to_value(&bookmarks)?)).await?;
+        manifest.bookmarks = bookmarks.len() as u64;
+
+        self.update(id, |job| job.files_total = Some(recordings.len() as u64));
+        for (source, name) in recordings {
+            if let Some(cold_storage) = &self.cold_storage {
+                if let Err(e) = cold_storage.ensure_local(&source).await {
+                    warn!("failed to fetch {} from cold storage: {}", source, e);
+                }
+            }
+            let readable = match tokio::fs::metadata(&source).await {
+                Ok(_) => readable_media(Path::new(&source)).await,
+                Err(e) => Err(e.into()),
+            };
+            match readable {
+                // kept until the writer is done with the decrypted copy
+                Ok(readable) => {
+                    let (added, files) = oneshot::channel();
+                    send(ArchiveEntry::Media {
+                        path: readable.path().to_path_buf(),
+                        source,
+                        name,
+                        added,
+                    })
+                    .await?;
+                    let files = files
+                        .await
+                        .map_err(|_| anyhow!("the archive writer stopped"))??;
+                    manifest.media.extend(files);
+                }
+                Err(e) => {
+                    warn!("{} is not exported: {}", source, e);
+                    manifest.missing_media.push(source);
+                }
+            }
+            self.update(id, |job| job.files_done += 1);
+        }
+
+        send(ArchiveEntry::File(MANIFEST)).await?;
+        send(ArchiveEntry::Json(serde_json::to_value(&manifest)?)).await?;
+        Ok(manifest)
+    }
+}
+
+/// What is written to an archive, in order.
+enum ArchiveEntry {
+    /// A file the next entries are written to
+    File(&'static str),
+    /// One line of a JSONL file
+    Record(Value),
+    Json(Value),
+    /// The recording at `path`, its files are sent back once added
+    Media {
+        path: PathBuf,
+        source: String,
+        name: String,
+        added: oneshot::Sender<Result<Vec<ExportedFile>>>,
+    },
+}
+
+/// Writes the entries to a zip archive in `file` until their sender is dropped.
+fn write_archive(file: File, mut entries: mpsc::Receiver<ArchiveEntry>) -> Result<()> {
+    let mut zip = ZipWriter::new(file);
+    let json = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    while let Some(entry) = entries.blocking_recv() {
+        match entry {
+            ArchiveEntry::File(name) => zip.start_file(name, json)?,
+            ArchiveEntry::Record(record) => {
+                serde_json::to_writer(&mut zip, &record)?;
+                zip.write_all(b"\n")?;
+            }
+            ArchiveEntry::Json(value) => serde_json::to_writer_pretty(&mut zip, &value)?,
+            ArchiveEntry::Media {
+                path,
+                source,
+                name,
+                added,
+            } => {
+                let _ = added.send(add_media(&mut zip, &path, &source, &name));
+            }
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// A frame as written to the archive, its OCR JSON kept as JSON.
+fn frame_record(frame: &ExportFrame, media: Option<String>) -> Value {
+    let mut record = serde_json::to_value(frame).unwrap_or_default();
+    if let Some(text_json) = &frame.text_json {
+        record["text_json"] =
+            serde_json::from_str(text_json).unwrap_or_else(|_| text_json.as_str().into());
+    }
+    if let Some(media) = media {
+        record["media"] = media.into();
+    }
+    record
+}
+
+/// Names of the recordings in the archive, unique even when two share a file name.
+#[derive(Default)]
+struct MediaNames {
+    names: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl MediaNames {
+    /// Name of `source`, `new` is called with the source and name the first time.
+    fn name(&mut self, source: &str, new: impl FnOnce((String, String))) -> String {
+        if let Some(name) = self.names.get(source) {
+            return name.clone();
+        }
+        let file_name = Path::new(source)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "recording".to_string());
+        let mut name = format!("{}/{}", MEDIA_DIR, file_name);
+        let mut n = 1;
+        while !self.taken.insert(name.clone()) {
+            n += 1;
+            name = format!("{}/{}_{}", MEDIA_DIR, n, file_name);
+        }
+        self.names.insert(source.to_string(), name.clone());
+        new((source.to_string(), name.clone()));
+        name
+    }
+}
+
+/// Adds the recording at `path`, a file or an image chunk directory, as `name`.
+fn add_media(
+    zip: &mut ZipWriter<File>,
+    path: &Path,
+    source: &str,
+    name: &str,
+) -> Result<Vec<ExportedFile>> {
+    // recordings are compressed already
+    let stored = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+    let mut files = Vec::new();
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        entries.sort();
+        for entry in entries {
+            let file_name = entry.file_name().unwrap_or_default().to_string_lossy();
+            let path_in_zip = format!("{}/{}", name, file_name);
+            zip.start_file(path_in_zip.as_str(), stored)?;
+            let size = io::copy(&mut File::open(&entry)?, zip)?;
+            files.push(ExportedFile {
+                path: path_in_zip,
+                source: Path::new(source)
+                    .join(&*file_name)
+                    .to_string_lossy()
+                    .into_owned(),
+                size,
+            });
+        }
+    } else {
+        zip.start_file(name, stored)?;
+        let size = io::copy(&mut File::open(path)?, zip)?;
+        files.push(ExportedFile {
+            path: name.to_string(),
+            source: source.to_string(),
+            size,
+        });
+    }
+    Ok(files)
+}
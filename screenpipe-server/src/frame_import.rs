@@ -0,0 +1,298 @@
+//! Screenshots and screen recordings made before screenpipe ran, OCRed into the timeline at
+//! the time they were taken.
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use image::{DynamicImage, ImageFormat};
+use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_db::{DatabaseManager, ImportedFrame};
+use screenpipe_vision::phash::perceptual_hash;
+use screenpipe_vision::{create_ocr_provider, OcrEngine, OcrProvider};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::process::Command;
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::audio_import::{time_in_file_name, FailedImport};
+use crate::cli::CliOcrEngine;
+use crate::frame_storage::frame_file_name;
+use crate::video_utils::get_video_metadata;
+
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp", "tiff"];
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "webm", "avi"];
+
+/// What got imported of a screenshot or video.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ImportedFrames {
+    pub source: PathBuf,
+    /// Path of its image chunk
+    pub file_path: String,
+    pub first_frame_at: DateTime<Utc>,
+    pub frames: usize,
+    /// Characters of text OCR found
+    pub text_chars: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct FrameImportReport {
+    pub imported: Vec<ImportedFrames>,
+    /// Screenshots and videos imported before
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<FailedImport>,
+}
+
+/// How screenshots and videos are imported.
+pub struct FrameImport {
+    pub ocr_engine: CliOcrEngine,
+    pub languages: Vec<Language>,
+    /// Device the frames are stored under
+    pub device: String,
+    /// Frames sampled per second of a video
+    pub fps: f64,
+    /// Directory the image chunks are written to
+    pub output_path: PathBuf,
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extensions
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+}
+
+pub fn is_screenshot(path: &Path) -> bool {
+    has_extension(path, IMAGE_EXTENSIONS)
+}
+
+pub fn is_video(path: &Path) -> bool {
+    has_extension(path, VIDEO_EXTENSIONS)
+}
+
+/// The screenshots and videos at `path`, a file or a directory searched recursively, in
+/// name order.
+pub fn find_frame_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(anyhow!("{} does not exist", path.display()));
+    }
+    let mut files: Vec<PathBuf> = WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file() && (is_screenshot(entry.path()) || is_video(entry.path()))
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// When a screenshot was taken: the date and time in its `file_name`, like screenshot
+/// tools write, else when the file was last `modified`.
+pub fn taken_at(file_name: &str, modified: Option<SystemTime>) -> Option<DateTime<Utc>> {
+    time_in_file_name(file_name).or_else(|| modified.map(DateTime::<Utc>::from))
+}
+
+/// Timestamps of `frames` sampled at `fps` from a video started at `start`.
+pub fn sampled_frame_times(start: DateTime<Utc>, fps: f64, frames: usize) -> Vec<DateTime<Utc>> {
+    (0..frames)
+        .map(|index| start + Duration::milliseconds((index as f64 * 1000.0 / fps) as i64))
+        .collect()
+}
+
+/// Image chunk directory of `source` in `output_path`, the same for every import of it.
+pub fn chunk_dir(output_path: &Path, source: &Path) -> PathBuf {
+    let source = std::fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    let hash = format!("{:x}", Sha256::digest(source.to_string_lossy().as_bytes()));
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_default();
+    output_path.join(format!("imported_{}_{}", stem, &hash[..12]))
+}
+
+/// OCRs the screenshots and videos at `path` into `db`. Files imported before are skipped,
+/// the others are imported even when some fail.
+pub async fn import_frames(
+    db: &DatabaseManager,
+    import: &FrameImport,
+    path: &Path,
+) -> Result<FrameImportReport> {
+    if import.fps <= 0.0 {
+        return Err(anyhow!("fps must be greater than 0"));
+    }
+    let files = find_frame_files(path)?;
+    info!("found {} screenshots and videos to import", files.len());
+    let engine: OcrEngine = import.ocr_engine.clone().into();
+    let provider = create_ocr_provider(&engine)?;
+    let mut report = FrameImportReport::default();
+    for source in files {
+        match import_file(db, provider.as_ref(), import, &source).await {
+            Ok(Some(imported)) => {
+                info!(
+                    "imported {} frames of {} from {}",
+                    imported.frames,
+                    source.display(),
+                    imported.first_frame_at
+                );
+                report.imported.push(imported);
+            }
+            Ok(None) => report.skipped.push(source),
+            Err(e) => {
+                warn!("failed to import {}: {}", source.display(), e);
+                report.failed.push(FailedImport {
+                    source,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+async fn import_file(
+    db: &DatabaseManager,
+    provider: &dyn OcrProvider,
+    import: &FrameImport,
+    source: &Path,
+) -> Result<Option<ImportedFrames>> {
+    let dir = chunk_dir(&import.output_path, source);
+    let file_path = dir.to_string_lossy().to_string();
+    if db.has_video_chunk(&file_path).await? {
+        return Ok(None);
+    }
+    if dir.exists() {
+        // left behind by an import that failed midway
+        tokio::fs::remove_dir_all(&dir).await?;
+    }
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let written = if is_video(source) {
+        write_video_frames(import, source, &dir).await
+    } else {
+        write_screenshot(source, &dir).await
+    };
+    let times = match written {
+        Ok(times) => times,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            return Err(e);
+        }
+    };
+    let first_frame_at = *times
+        .first()
+        .ok_or_else(|| anyhow!("no frames in {}", source.display()))?;
+
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+    let ocr_engine: Arc<screenpipe_db::OcrEngine> = import.ocr_engine.clone().into();
+    let mut frames = Vec::with_capacity(times.len());
+    let mut text_chars = 0;
+    for (offset_index, timestamp) in times.iter().enumerate() {
+        let image = image::open(dir.join(frame_file_name(offset_index as i64, "png")))?;
+        let ocr = match provider.recognize(&image, &import.languages).await {
+            Ok(result) => {
+                text_chars += result.text.len();
+                Some((result.text.clone(), result.layout().to_text_json()))
+            }
+            Err(e) => {
+                warn!(
+                    "ocr failed for frame {} of {}: {}",
+                    offset_index, file_path, e
+                );
+                None
+            }
+        };
+        frames.push(ImportedFrame {
+            timestamp: *timestamp,
+            phash: Some(perceptual_hash(&image) as i64),
+            ocr,
+        });
+    }
+
+    // stored once OCRed, a file failing to import is imported again
+    let inserted = db
+        .insert_imported_frames(
+            &file_path,
+            &import.device,
+            file_name.as_deref(),
+            &ocr_engine,
+            &frames,
+        )
+        .await;
+    if let Err(e) = inserted {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        return Err(e.into());
+    }
+
+    Ok(Some(ImportedFrames {
+        source: source.to_path_buf(),
+        file_path,
+        first_frame_at,
+        frames: times.len(),
+        text_chars,
+    }))
+}
+
+/// Writes the screenshot at `source` as the only image of `dir`, returns when it was
+/// taken.
+async fn write_screenshot(source: &Path, dir: &Path) -> Result<Vec<DateTime<Utc>>> {
+    let modified = tokio::fs::metadata(source)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let taken_at = taken_at(&file_name, modified).unwrap_or_else(Utc::now);
+
+    let image: DynamicImage = image::open(source)?;
+    image.save_with_format(dir.join(frame_file_name(0, "png")), ImageFormat::Png)?;
+    Ok(vec![taken_at])
+}
+
+/// Samples the video at `source` into images of `dir` at `fps`, returns when each was
+/// shown.
+async fn write_video_frames(
+    import: &FrameImport,
+    source: &Path,
+    dir: &Path,
+) -> Result<Vec<DateTime<Utc>>> {
+    let metadata = get_video_metadata(&source.to_string_lossy()).await?;
+    let ffmpeg = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+    let output = Command::new(ffmpeg)
+        .args(["-loglevel", "error", "-i"])
+        .arg(source)
+        .args(["-vf", &format!("fps={}", import.fps), "-start_number", "0"])
+        .arg("-y")
+        .arg(dir.join("%06d.png"))
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to sample {}: {}",
+            source.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let mut frames = 0;
+    while dir.join(frame_file_name(frames as i64, "png")).exists() {
+        frames += 1;
+    }
+    Ok(sampled_frame_times(
+        metadata.creation_time,
+        import.fps,
+        frames,
+    ))
+}
@@ -0,0 +1,424 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use image::{imageops::FilterType, DynamicImage};
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::monitor::{MonitorSelector, SafeMonitor};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How captured frames are stored on disk.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// HEVC video chunks, the smallest for screens that change little between frames
+    #[default]
+    Video,
+    /// Lossless images, quality is ignored
+    Png,
+    Webp,
+    Avif,
+    /// JPEG XL, needs an ffmpeg built with libjxl
+    Jxl,
+}
+
+impl FrameFormat {
+    /// Extension of the frame images, `None` for video chunks.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            FrameFormat::Video => None,
+            FrameFormat::Png => Some("png"),
+            FrameFormat::Webp => Some("webp"),
+            FrameFormat::Avif => Some("avif"),
+            FrameFormat::Jxl => Some("jxl"),
+        }
+    }
+
+    /// ffmpeg encoder arguments for `quality` (1 - 100).
+    fn encoder_args(&self, quality: u8) -> Vec<String> {
+        let quality = quality.clamp(1, 100) as f32;
+        match self {
+            FrameFormat::Video | FrameFormat::Png => vec!["-c:v".into(), "png".into()],
+            FrameFormat::Webp => vec![
+                "-c:v".into(),
+                "libwebp".into(),
+                "-quality".into(),
+                quality.to_string(),
+            ],
+            FrameFormat::Avif => vec![
+                "-c:v".into(),
+                "libaom-av1".into(),
+                "-still-picture".into(),
+                "1".into(),
+                "-cpu-used".into(),
+                "8".into(),
+                "-crf".into(),
+                format!("{}", ((100.0 - quality) * 0.63).round()),
+            ],
+            // the quality to butteraugli distance mapping of cjxl
+            FrameFormat::Jxl => vec![
+                "-c:v".into(),
+                "libjxl".into(),
+                "-distance".into(),
+                format!("{:.2}", 0.1 + (100.0 - quality) * 0.09),
+            ],
+        }
+    }
+}
+
+/// Which frames of image chunks share one file.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameDedup {
+    /// Every frame gets its own file
+    #[default]
+    Off,
+    /// Frames with the same pixels. Frames that only look alike aren't merged, a perceptual
+    /// hash can't tell a typed character apart
+    Exact,
+}
+
+/// Format, quality and size frames of a monitor are stored with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStorage {
+    pub format: FrameFormat,
+    /// 1 - 100, used by webp, avif and jxl
+    pub quality: u8,
+    /// Frames are downscaled by this factor before they're stored, 1.0 keeps the full size
+    pub scale: f32,
+    /// Only applies to the image formats
+    pub dedup: FrameDedup,
+}
+
+impl Default for FrameStorage {
+    fn default() -> Self {
+        FrameStorage {
+            format: FrameFormat::Video,
+            quality: 75,
+            scale: 1.0,
+            dedup: FrameDedup::Off,
+        }
+    }
+}
+
+impl FrameStorage {
+    pub fn new(format: FrameFormat, quality: u8, scale: f32) -> Result<Self, String> {
+        if !(1..=100).contains(&quality) {
+            return Err(format!(
+                "frame quality must be between 1 and 100, got {}",
+                quality
+            ));
+        }
+        if !scale.is_finite() || scale <= 0.0 || scale > 1.0 {
+            return Err(format!(
+                "frame scale must be greater than 0 and at most 1, got {}",
+                scale
+            ));
+        }
+        Ok(FrameStorage {
+            format,
+            quality,
+            scale,
+            dedup: FrameDedup::Off,
+        })
+    }
+
+    pub fn with_dedup(mut self, dedup: FrameDedup) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Whether frames stored this way go through a [`FrameBlobStore`].
+    pub fn deduplicates(&self) -> bool {
+        self.dedup != FrameDedup::Off && self.format != FrameFormat::Video
+    }
+
+    /// `image` downscaled by [`FrameStorage::scale`].
+    pub fn downscale<'a>(&self, image: &'a DynamicImage) -> Cow<'a, DynamicImage> {
+        if self.scale >= 1.0 {
+            return Cow::Borrowed(image);
+        }
+        let width = ((image.width() as f32 * self.scale).round() as u32).max(1);
+        let height = ((image.height() as f32 * self.scale).round() as u32).max(1);
+        Cow::Owned(image.resize_exact(width, height, FilterType::Triangle))
+    }
+
+    /// Writes a PNG encoded frame to `path` in [`FrameStorage::format`].
+    pub async fn write_image(&self, png: &[u8], path: &Path) -> Result<()> {
+        if matches!(self.format, FrameFormat::Video | FrameFormat::Png) {
+            tokio::fs::write(path, png).await?;
+            return Ok(());
+        }
+
+        let ffmpeg = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+        let mut child = Command::new(ffmpeg)
+            .args([
+                "-loglevel",
+                "error",
+                "-f",
+                "image2pipe",
+                "-vcodec",
+                "png",
+                "-i",
+                "-",
+            ])
+            .args(self.format.encoder_args(self.quality))
+            .args(["-frames:v", "1", "-y"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("failed to open stdin");
+        stdin.write_all(png).await?;
+        drop(stdin);
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "ffmpeg failed to write {:?}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FrameStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format.to_possible_value() {
+            Some(format) => write!(f, "{}", format.get_name())?,
+            None => write!(f, "{:?}", self.format)?,
+        }
+        if matches!(
+            self.format,
+            FrameFormat::Webp | FrameFormat::Avif | FrameFormat::Jxl
+        ) {
+            write!(f, " q{}", self.quality)?;
+        }
+        if self.scale < 1.0 {
+            write!(f, " {}%", (self.scale * 100.0).round())?;
+        }
+        if let Some(dedup) = self
+            .deduplicates()
+            .then(|| self.dedup.to_possible_value())
+            .flatten()
+        {
+            write!(f, " dedup {}", dedup.get_name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Frame storage override for the monitors matching `selector`, parsed from
+/// `<selector>=<format>[,<quality>[,<scale>]]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorFrameStorage {
+    pub selector: MonitorSelector,
+    pub format: FrameFormat,
+    pub quality: Option<u8>,
+    pub scale: Option<f32>,
+}
+
+impl FromStr for MonitorFrameStorage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid monitor frame storage '{}', expected <selector>=<format>[,<quality>[,<scale>]]",
+                s
+            )
+        };
+        let (selector, storage) = s.rsplit_once('=').ok_or_else(invalid)?;
+        let mut parts = storage.split(',').map(str::trim);
+        let format = <FrameFormat as ValueEnum>::from_str(parts.next().unwrap_or_default(), true)
+            .map_err(|_| format!("invalid frame format in '{}'", s))?;
+        let quality = parts
+            .next()
+            .map(|quality| quality.parse::<u8>().map_err(|_| invalid()))
+            .transpose()?;
+        let scale = parts
+            .next()
+            .map(|scale| scale.parse::<f32>().map_err(|_| invalid()))
+            .transpose()?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        FrameStorage::new(format, quality.unwrap_or(75), scale.unwrap_or(1.0))
+            .map_err(|e| format!("{} in '{}'", e, s))?;
+        Ok(MonitorFrameStorage {
+            selector: selector.parse()?,
+            format,
+            quality,
+            scale,
+        })
+    }
+}
+
+/// Frame storage of `monitor`, the first matching override wins. Quality and scale the
+/// override leaves out come from `default`.
+pub fn monitor_frame_storage(
+    overrides: &[MonitorFrameStorage],
+    index: usize,
+    monitor: &SafeMonitor,
+    default: FrameStorage,
+) -> FrameStorage {
+    overrides
+        .iter()
+        .find(|o| o.selector.matches(index, monitor.id(), &monitor.get_info()))
+        .map_or(default, |o| FrameStorage {
+            format: o.format,
+            quality: o.quality.unwrap_or(default.quality),
+            scale: o.scale.unwrap_or(default.scale),
+            dedup: default.dedup,
+        })
+}
+
+/// File name of frame `offset_index` inside an image chunk directory.
+pub fn frame_file_name(offset_index: i64, extension: &str) -> String {
+    format!("{:06}.{}", offset_index, extension)
+}
+
+fn image_extension(chunk_dir: &Path) -> Option<&'static str> {
+    FrameFormat::value_variants()
+        .iter()
+        .filter_map(FrameFormat::extension)
+        .find(|extension| chunk_dir.join(frame_file_name(0, extension)).exists())
+}
+
+/// Image of frame `offset_index` when `file_path` is a chunk stored as images rather than
+/// a video.
+pub fn frame_image_path(file_path: &str, offset_index: i64) -> Option<PathBuf> {
+    let chunk_dir = Path::new(file_path);
+    if !chunk_dir.is_dir() {
+        return None;
+    }
+    let extension = image_extension(chunk_dir)?;
+    Some(chunk_dir.join(frame_file_name(offset_index, extension)))
+}
+
+/// File holding frame `offset_index` of a chunk and the offset of the frame in it: the
+/// video itself, or the image of the frame for chunks stored as images.
+pub fn frame_source(file_path: &str, offset_index: i64) -> (String, i64) {
+    match frame_image_path(file_path, offset_index) {
+        Some(image_path) => (image_path.to_string_lossy().into_owned(), 0),
+        None => (file_path.to_string(), offset_index),
+    }
+}
+
+/// ffmpeg input reading every frame of a chunk in order: the video itself, or an image
+/// sequence pattern for chunks stored as images.
+pub fn ffmpeg_input(file_path: &str) -> String {
+    let chunk_dir = Path::new(file_path);
+    match chunk_dir
+        .is_dir()
+        .then(|| image_extension(chunk_dir))
+        .flatten()
+    {
+        Some(extension) => chunk_dir
+            .join(format!("%06d.{}", extension))
+            .to_string_lossy()
+            .into_owned(),
+        None => file_path.to_string(),
+    }
+}
+
+/// Key frames with the same content share under `dedup`, `None` when frames aren't
+/// deduplicated.
+pub fn content_key(dedup: FrameDedup, image: &DynamicImage) -> Option<String> {
+    match dedup {
+        FrameDedup::Off => None,
+        FrameDedup::Exact => {
+            let mut hasher = Sha256::new();
+            hasher.update(image.width().to_le_bytes());
+            hasher.update(image.height().to_le_bytes());
+            hasher.update(image.to_rgba8().as_raw());
+            Some(format!("{:x}", hasher.finalize()))
+        }
+    }
+}
+
+/// Content addressed store of frame images, shared by the image chunks of every monitor.
+/// Chunks hard link their images to the blobs, the database counts the references so a
+/// blob is deleted with the last chunk pointing at it.
+pub struct FrameBlobStore {
+    db: Arc<DatabaseManager>,
+    dir: PathBuf,
+}
+
+impl FrameBlobStore {
+    /// Blobs are kept in `<output_path>/frame_blobs`.
+    pub fn new(db: Arc<DatabaseManager>, output_path: &str) -> Self {
+        FrameBlobStore {
+            db,
+            dir: Path::new(output_path).join("frame_blobs"),
+        }
+    }
+
+    fn blob_path(&self, name: &str) -> PathBuf {
+        self.dir.join(&name[..2]).join(name)
+    }
+
+    /// Stores a frame as image `offset_index` of the image chunk `chunk_dir`. The frame is
+    /// only encoded, with `png`, when no blob with `content_key` exists yet.
+    pub async fn write(
+        &self,
+        storage: &FrameStorage,
+        content_key: &str,
+        png: impl FnOnce() -> Vec<u8>,
+        chunk_dir: &Path,
+        offset_index: i64,
+    ) -> Result<()> {
+        let extension = storage.format.extension().unwrap_or("png");
+        let name = format!("{}.{}", content_key, extension);
+        let blob = self.blob_path(&name);
+        if !blob.exists() {
+            let blob_dir = blob.parent().expect("blob path has a parent");
+            tokio::fs::create_dir_all(blob_dir).await?;
+            // monitors can store the same frame at once, only complete blobs get the name
+            let partial = blob_dir.join(format!("{}.{}", Uuid::new_v4(), extension));
+            storage.write_image(&png(), &partial).await?;
+            tokio::fs::rename(&partial, &blob).await?;
+        }
+
+        let path = chunk_dir.join(frame_file_name(offset_index, extension));
+        if let Err(e) = tokio::fs::hard_link(&blob, &path).await {
+            debug!("failed to link {:?}, copying it instead: {}", blob, e);
+            tokio::fs::copy(&blob, &path).await?;
+        }
+        self.db
+            .add_frame_blob_ref(
+                &name,
+                &blob.to_string_lossy(),
+                &chunk_dir.to_string_lossy(),
+                offset_index,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes an image chunk and the blobs no other chunk points at. Returns how many
+    /// blobs were deleted.
+    pub async fn delete_chunk(&self, chunk_path: &str) -> Result<usize> {
+        let released = self.db.release_frame_blobs(chunk_path).await?;
+        if Path::new(chunk_path).is_dir() {
+            tokio::fs::remove_dir_all(chunk_path).await?;
+        }
+        for blob in &released {
+            if let Err(e) = tokio::fs::remove_file(blob).await {
+                warn!("failed to delete frame blob {}: {}", blob, e);
+            }
+        }
+        Ok(released.len())
+    }
+}
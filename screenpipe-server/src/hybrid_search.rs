@@ -0,0 +1,82 @@
+use oasgen::OaSchema;
+use screenpipe_db::{ContentType, SearchResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Cosine distance past which a text embedding doesn't count as a semantic match
+pub const SEMANTIC_MAX_DISTANCE: f32 = 0.5;
+// Reciprocal rank fusion constant, keeps the top few ranks of one list from drowning out
+// results found by both
+const RRF_K: f64 = 60.0;
+
+/// How `/search` matches `q`.
+#[derive(OaSchema, Debug, Deserialize, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Full text search
+    #[default]
+    Keyword,
+    /// Keyword matches blended with OCR text and transcriptions close in meaning to `q`,
+    /// needs `--text-embeddings`
+    Semantic,
+    /// Every word of `q`, up to a few typos each, for OCR misreads like "rn" for "m"
+    Fuzzy,
+    /// `q` as a case insensitive regex
+    Regex,
+}
+
+#[derive(Hash, PartialEq, Eq)]
+enum ResultKey {
+    Ocr(i64),
+    // keyword search returns one row per chunk and offset
+    Audio(i64, i64),
+    Ui(i64),
+    Clipboard(i64),
+}
+
+fn result_key(result: &SearchResult) -> ResultKey {
+    match result {
+        SearchResult::OCR(ocr) => ResultKey::Ocr(ocr.frame_id),
+        SearchResult::Audio(audio) => ResultKey::Audio(audio.audio_chunk_id, audio.offset_index),
+        SearchResult::UI(ui) => ResultKey::Ui(ui.id),
+        SearchResult::Clipboard(entry) => ResultKey::Clipboard(entry.id),
+    }
+}
+
+/// Merges ranked result lists with reciprocal rank fusion: every result scores
+/// `1 / (60 + rank)` in each list it appears in, results found by keyword and meaning
+/// come first. Ties keep the order of the lists.
+pub fn blend_results(lists: Vec<Vec<SearchResult>>) -> Vec<SearchResult> {
+    let mut blended: Vec<(f64, SearchResult)> = Vec::new();
+    let mut positions: HashMap<ResultKey, usize> = HashMap::new();
+    for list in lists {
+        for (rank, result) in list.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            match positions.get(&result_key(&result)) {
+                Some(position) => blended[*position].0 += score,
+                None => {
+                    positions.insert(result_key(&result), blended.len());
+                    blended.push((score, result));
+                }
+            }
+        }
+    }
+    blended.sort_by(|a, b| b.0.total_cmp(&a.0));
+    blended.into_iter().map(|(_, result)| result).collect()
+}
+
+/// OCR text is searched for `content_type`.
+pub fn includes_ocr(content_type: &ContentType) -> bool {
+    matches!(
+        content_type,
+        ContentType::All | ContentType::OCR | ContentType::OcrAndUi | ContentType::AudioAndOcr
+    )
+}
+
+/// Transcriptions are searched for `content_type`.
+pub fn includes_audio(content_type: &ContentType) -> bool {
+    matches!(
+        content_type,
+        ContentType::All | ContentType::Audio | ContentType::AudioAndUi | ContentType::AudioAndOcr
+    )
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use screenpipe_audio::audio_manager::AudioManager;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_pause::{pause_capture, resume_capture};
+use screenpipe_vision::idle::{idle_time, is_screen_locked};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleState {
+    Active,
+    /// No input for longer than the idle timeout
+    Idle,
+    Locked,
+}
+
+impl IdleState {
+    /// What capture is paused for in this state, stored with the pause.
+    pub fn pause_reason(self) -> Option<&'static str> {
+        match self {
+            IdleState::Active => None,
+            IdleState::Idle => Some("idle"),
+            IdleState::Locked => Some("locked"),
+        }
+    }
+}
+
+/// When capture pauses by itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdlePolicy {
+    pub pause_on_idle: bool,
+    pub pause_on_lock: bool,
+    /// Whether idle time stops audio recording too, calls only listened to have no input
+    pub pause_audio_on_idle: bool,
+    pub idle_timeout: Duration,
+}
+
+impl IdlePolicy {
+    /// The state of a computer with no input for `idle`, a lock showing the lock screen
+    /// coming before idle time. States the policy doesn't pause on count as active.
+    pub fn state(&self, idle: Option<Duration>, locked: bool) -> IdleState {
+        if self.pause_on_lock && locked {
+            IdleState::Locked
+        } else if self.pause_on_idle && idle.is_some_and(|idle| idle >= self.idle_timeout) {
+            IdleState::Idle
+        } else {
+            IdleState::Active
+        }
+    }
+
+    /// Whether audio recording stops while the computer is in `state`.
+    pub fn pauses_audio(&self, state: IdleState) -> bool {
+        match state {
+            IdleState::Active => false,
+            IdleState::Idle => self.pause_audio_on_idle,
+            IdleState::Locked => true,
+        }
+    }
+}
+
+/// Pauses screen capture, OCR and audio recording while the computer is idle or locked and
+/// resumes them on activity, storing every pause for the timeline. Audio stopped or started
+/// through the api in the meantime is left as it is.
+pub struct IdlePause {
+    db: Arc<DatabaseManager>,
+    audio_manager: Option<Arc<AudioManager>>,
+    policy: IdlePolicy,
+    // the reason capture is paused for and the id of the stored pause
+    paused: Option<(&'static str, i64)>,
+}
+
+impl IdlePause {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        audio_manager: Option<Arc<AudioManager>>,
+        policy: IdlePolicy,
+    ) -> Self {
+        Self {
+            db,
+            audio_manager,
+            policy,
+            paused: None,
+        }
+    }
+
+    pub fn policy(&self) -> &IdlePolicy {
+        &self.policy
+    }
+
+    /// Pauses or resumes capture for the computer entering `state` at `now`. Going from
+    /// locked to idle ends one pause and starts the next, audio stays stopped across if
+    /// idle time pauses it too.
+    pub async fn update(&mut self, state: IdleState, now: DateTime<Utc>) -> Result<()> {
+        let reason = state.pause_reason();
+        if self.paused.map(|(paused, _)| paused) == reason {
+            return Ok(());
+        }
+
+        if let Some((paused, id)) = self.paused.take() {
+            resume_capture(paused);
+            self.db.end_capture_pause(id, now).await?;
+            info!("resuming capture, computer no longer {}", paused);
+        }
+
+        if let Some(reason) = reason {
+            pause_capture(reason);
+            let id = self.db.start_capture_pause(reason, now).await?;
+            self.paused = Some((reason, id));
+            info!("pausing capture, computer {}", reason);
+        }
+
+        let Some(audio_manager) = &self.audio_manager else {
+            return Ok(());
+        };
+        if self.policy.pauses_audio(state) {
+            if let Err(e) = audio_manager.pause().await {
+                warn!("failed to stop audio recording for the pause: {}", e);
+            }
+        } else if let Err(e) = audio_manager.resume().await {
+            warn!("failed to start audio recording after the pause: {}", e);
+        }
+        Ok(())
+    }
+}
+
+/// Checks idle time and the lock screen every few seconds, pausing capture as they ask.
+pub async fn run_idle_pause(mut pause: IdlePause) {
+    info!("pausing capture while the computer is idle or locked");
+    // left open if the last run didn't get to resume
+    if let Err(e) = pause.db.end_open_capture_pauses(Utc::now()).await {
+        warn!("failed to end open capture pauses: {}", e);
+    }
+    let check_lock = pause.policy().pause_on_lock;
+    loop {
+        let idle = idle_time();
+        let locked = check_lock
+            && tokio::task::spawn_blocking(is_screen_locked)
+                .await
+                .unwrap_or(false);
+        let state = pause.policy().state(idle, locked);
+        if let Err(e) = pause.update(state, Utc::now()).await {
+            warn!("failed to store capture pause: {}", e);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
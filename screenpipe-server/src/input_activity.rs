@@ -0,0 +1,108 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use rdev::EventType;
+use screenpipe_db::{DatabaseManager, InputActivity};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// What an input event is counted as. Which key or button it was, and where the mouse
+/// went, is never looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    KeyPress,
+    MouseClick,
+    MouseMove,
+    Scroll,
+}
+
+impl InputKind {
+    /// `None` for key and button releases.
+    pub fn of(event: &EventType) -> Option<Self> {
+        match event {
+            EventType::KeyPress(_) => Some(InputKind::KeyPress),
+            EventType::ButtonPress(_) => Some(InputKind::MouseClick),
+            EventType::MouseMove { .. } => Some(InputKind::MouseMove),
+            EventType::Wheel { .. } => Some(InputKind::Scroll),
+            EventType::KeyRelease(_) | EventType::ButtonRelease(_) => None,
+        }
+    }
+}
+
+/// Counts input events until they are taken.
+#[derive(Default, Debug)]
+pub struct InputCounter {
+    key_presses: AtomicI64,
+    mouse_clicks: AtomicI64,
+    mouse_moves: AtomicI64,
+    scrolls: AtomicI64,
+}
+
+impl InputCounter {
+    pub fn count(&self, kind: InputKind) {
+        let count = match kind {
+            InputKind::KeyPress => &self.key_presses,
+            InputKind::MouseClick => &self.mouse_clicks,
+            InputKind::MouseMove => &self.mouse_moves,
+            InputKind::Scroll => &self.scrolls,
+        };
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The events counted since the last time, as the activity of `minute`.
+    pub fn take(&self, minute: DateTime<Utc>) -> InputActivity {
+        InputActivity {
+            minute,
+            key_presses: self.key_presses.swap(0, Ordering::Relaxed),
+            mouse_clicks: self.mouse_clicks.swap(0, Ordering::Relaxed),
+            mouse_moves: self.mouse_moves.swap(0, Ordering::Relaxed),
+            scrolls: self.scrolls.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Start of the minute `timestamp` falls in.
+pub fn minute_of(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp
+        .duration_trunc(Duration::minutes(1))
+        .unwrap_or(timestamp)
+}
+
+pub fn has_input(activity: &InputActivity) -> bool {
+    activity.key_presses + activity.mouse_clicks + activity.mouse_moves + activity.scrolls > 0
+}
+
+/// Counts keyboard and mouse input and stores the counts of every minute, none too. Idle
+/// minutes are told apart from the ones input wasn't listened to in, which aren't stored.
+pub async fn run_input_activity_monitor(db: Arc<DatabaseManager>) {
+    info!("recording keyboard and mouse activity levels");
+    let counter = Arc::new(InputCounter::default());
+    let listening = Arc::new(AtomicBool::new(true));
+    let listener = counter.clone();
+    let stopped = listening.clone();
+    // listening blocks the thread for as long as input is listened to
+    std::thread::spawn(move || {
+        let listened = rdev::listen(move |event| {
+            if let Some(kind) = InputKind::of(&event.event_type) {
+                listener.count(kind);
+            }
+        });
+        if let Err(e) = listened {
+            warn!("failed to listen to keyboard and mouse input: {:?}", e);
+        }
+        stopped.store(false, Ordering::Relaxed);
+    });
+
+    let mut minute = minute_of(Utc::now());
+    loop {
+        let wait = minute + Duration::minutes(1) - Utc::now();
+        tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+        if !listening.load(Ordering::Relaxed) {
+            break;
+        }
+        let activity = counter.take(minute);
+        if let Err(e) = db.add_input_activity(&activity).await {
+            warn!("failed to store input activity of {}: {}", minute, e);
+        }
+        minute = minute_of(Utc::now());
+    }
+}
@@ -1,16 +1,61 @@
+pub mod activitywatch;
 mod add;
+pub mod alerts;
+pub mod analytics;
+pub mod annotations;
+pub mod ask;
+pub mod audio_import;
+pub mod auth;
 mod auto_destruct;
+pub mod backup;
+pub mod bookmarks;
+pub mod calendar;
+pub mod capture_events;
+pub mod central_database;
 pub mod chunking;
+pub mod clip;
 pub mod cli;
+pub mod clipboard;
+pub mod cold_storage;
 pub mod core;
+pub mod data_deletion;
+pub mod digest;
+pub mod encryption;
+pub mod export;
 pub mod filtering;
+pub mod frame_import;
+pub mod frame_storage;
+pub mod hybrid_search;
+pub mod idle_pause;
+pub mod input_activity;
+pub mod llm;
+pub mod mcp;
+pub mod meetings;
+pub mod obsidian;
+pub mod pagination;
+pub mod pattern_search;
+pub mod pipe_host;
 pub mod pipe_manager;
+pub mod pipe_scheduler;
+pub mod power;
+pub mod rate_limit;
+pub mod record_export;
 mod resource_monitor;
+pub mod retention;
+pub mod retranscribe;
+pub mod screen_recording;
+pub mod search_query;
 mod server;
+pub mod storage;
+pub mod summarize;
 pub mod text_embeds;
+pub mod timeline;
+pub mod tls;
+pub mod translation;
 mod video;
 pub mod video_cache;
 pub mod video_utils;
+pub mod webhooks;
 pub use add::handle_index_command;
 pub use auto_destruct::watch_pid;
 pub use axum::Json as JsonResponse;
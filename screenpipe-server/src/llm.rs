@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const LLM_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_OUTPUT_TOKENS: u32 = 1024;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LlmProvider {
+    /// The chat API of a local Ollama
+    Ollama,
+    /// Any OpenAI compatible chat completions endpoint, e.g. OpenAI, LM Studio or vLLM
+    OpenAi,
+    /// The Anthropic messages API
+    Anthropic,
+}
+
+impl LlmProvider {
+    pub fn default_url(&self) -> &'static str {
+        match self {
+            LlmProvider::Ollama => "http://localhost:11434/api/chat",
+            LlmProvider::OpenAi => "https://api.openai.com/v1/chat/completions",
+            LlmProvider::Anthropic => "https://api.anthropic.com/v1/messages",
+        }
+    }
+
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            LlmProvider::Ollama => "llama3.2",
+            LlmProvider::OpenAi => "gpt-4o-mini",
+            LlmProvider::Anthropic => "claude-3-5-haiku-latest",
+        }
+    }
+}
+
+impl std::fmt::Display for LlmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LlmProvider::Ollama => "ollama",
+            LlmProvider::OpenAi => "openai",
+            LlmProvider::Anthropic => "anthropic",
+        })
+    }
+}
+
+/// A chat model answering one prompt at a time.
+#[derive(Debug, Clone)]
+pub struct Llm {
+    pub provider: LlmProvider,
+    /// Full URL of the chat endpoint
+    pub url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl Llm {
+    /// `provider` at its usual endpoint with its default model.
+    pub fn new(provider: LlmProvider) -> Self {
+        Self {
+            provider,
+            url: provider.default_url().to_string(),
+            model: provider.default_model().to_string(),
+            api_key: None,
+        }
+    }
+
+    /// The body of a request asking for the answer to `prompt` following `system`.
+    pub fn request_body(&self, system: &str, prompt: &str) -> Value {
+        match self.provider {
+            LlmProvider::Ollama => json!({
+                "model": self.model,
+                "stream": false,
+                "messages": [
+                    {"role": "system", "content": system},
+                    {"role": "user", "content": prompt},
+                ],
+            }),
+            LlmProvider::OpenAi => json!({
+                "model": self.model,
+                "messages": [
+                    {"role": "system", "content": system},
+                    {"role": "user", "content": prompt},
+                ],
+            }),
+            LlmProvider::Anthropic => json!({
+                "model": self.model,
+                "max_tokens": MAX_OUTPUT_TOKENS,
+                "system": system,
+                "messages": [{"role": "user", "content": prompt}],
+            }),
+        }
+    }
+
+    /// The text of the answer in a response body.
+    pub fn response_text(&self, body: &Value) -> Result<String> {
+        let text = match self.provider {
+            LlmProvider::Ollama => body["message"]["content"].as_str().map(str::to_string),
+            LlmProvider::OpenAi => body["choices"][0]["message"]["content"]
+                .as_str()
+                .map(str::to_string),
+            LlmProvider::Anthropic => body["content"].as_array().map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("")
+            }),
+        };
+        text.map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| anyhow!("no message in the {} response", self.provider))
+    }
+
+    pub async fn complete(&self, system: &str, prompt: &str) -> Result<String> {
+        let mut builder = Client::new()
+            .post(&self.url)
+            .timeout(LLM_TIMEOUT)
+            .json(&self.request_body(system, prompt));
+        if self.provider == LlmProvider::Anthropic {
+            builder = builder.header("anthropic-version", ANTHROPIC_VERSION);
+        }
+        if let Some(api_key) = &self.api_key {
+            builder = match self.provider {
+                LlmProvider::Anthropic => builder.header("x-api-key", api_key),
+                LlmProvider::Ollama | LlmProvider::OpenAi => builder.bearer_auth(api_key),
+            };
+        }
+        let response = builder.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{} returned {}: {}", self.provider, status, text));
+        }
+        let body: Value = response.json().await?;
+        self.response_text(&body)
+    }
+}
@@ -0,0 +1,414 @@
+use crate::tls::pinned_client;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tracing::{debug, info};
+
+/// MCP revision spoken over stdio
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+const MAX_SEARCH_RESULTS: u64 = 50;
+const DEFAULT_SEARCH_RESULTS: u64 = 10;
+const MAX_TIMELINE_ITEMS: u64 = 1000;
+const DEFAULT_TIMELINE_ITEMS: u64 = 200;
+const API_TIMEOUT: Duration = Duration::from_secs(60);
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// What MCP clients are allowed to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpScope {
+    /// `search_screen_history`, OCR text, transcriptions and UI text
+    Search,
+    /// `get_timeline`, apps, windows and text captured over a time range
+    Timeline,
+    /// `get_frame`, screenshots
+    Frames,
+}
+
+impl McpScope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            McpScope::Search => "search",
+            McpScope::Timeline => "timeline",
+            McpScope::Frames => "frames",
+        }
+    }
+}
+
+struct Tool {
+    name: &'static str,
+    scope: McpScope,
+    description: &'static str,
+    input_schema: fn() -> Value,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        name: "search_screen_history",
+        scope: McpScope::Search,
+        description: "Search the text read from the user's screen and transcribed from their \
+                      microphone and speakers, newest first. Returns matching OCR text, \
+                      transcriptions and UI text with the app, window and time they were \
+                      captured at.",
+        input_schema: search_schema,
+    },
+    Tool {
+        name: "get_timeline",
+        scope: McpScope::Timeline,
+        description: "What happened between two times, oldest first: the apps and windows \
+                      focused, the frames captured with the start of their text and the \
+                      transcriptions.",
+        input_schema: timeline_schema,
+    },
+    Tool {
+        name: "get_frame",
+        scope: McpScope::Frames,
+        description: "The screenshot of a frame, by the frame_id found with \
+                      search_screen_history or get_timeline.",
+        input_schema: frame_schema,
+    },
+];
+
+fn search_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "q": {
+                "type": "string",
+                "description": "Words to look for, e.g. `deploy failed`, `app:slack budget` \
+                                or `\"exact phrase\" -title:random`",
+            },
+            "content_type": {
+                "type": "string",
+                "enum": ["all", "ocr", "audio", "ui", "clipboard"],
+                "default": "all",
+            },
+            "mode": {
+                "type": "string",
+                "enum": ["keyword", "semantic", "fuzzy", "regex"],
+                "default": "keyword",
+            },
+            "start_time": {"type": "string", "format": "date-time"},
+            "end_time": {"type": "string", "format": "date-time"},
+            "app_name": {"type": "string"},
+            "window_name": {"type": "string"},
+            "limit": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": MAX_SEARCH_RESULTS,
+                "default": DEFAULT_SEARCH_RESULTS,
+            },
+            "offset": {"type": "integer", "minimum": 0, "default": 0},
+        },
+    })
+}
+
+fn timeline_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "start": {"type": "string", "format": "date-time"},
+            "end": {"type": "string", "format": "date-time"},
+            "limit": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": MAX_TIMELINE_ITEMS,
+                "default": DEFAULT_TIMELINE_ITEMS,
+            },
+        },
+        "required": ["start", "end"],
+    })
+}
+
+fn frame_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "frame_id": {"type": "integer"},
+        },
+        "required": ["frame_id"],
+    })
+}
+
+/// An MCP server on stdio whose tools read from the API of a running screenpipe.
+pub struct McpServer {
+    api_url: String,
+    scopes: Vec<McpScope>,
+    token: Option<String>,
+    client: Client,
+}
+
+impl McpServer {
+    pub fn new(port: u16, scopes: Vec<McpScope>) -> Self {
+        Self {
+            api_url: format!("http://localhost:{}", port),
+            scopes,
+            token: None,
+            client: Client::new(),
+        }
+    }
+
+    /// Talks to the screenpipe at `url` instead, e.g. https://laptop.local:3030. Its
+    /// certificate is pinned by `fingerprint` when given, checked against the system's
+    /// certificate authorities otherwise.
+    pub fn with_url(mut self, url: &str, fingerprint: Option<&str>) -> Result<Self> {
+        let url = url.trim_end_matches('/');
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(anyhow!("expected an http or https url, got {}", url));
+        }
+        if let Some(fingerprint) = fingerprint {
+            if !url.starts_with("https://") {
+                return Err(anyhow!("a certificate fingerprint needs an https url"));
+            }
+            self.client = pinned_client(fingerprint)?;
+        }
+        self.api_url = url.to_string();
+        Ok(self)
+    }
+
+    /// Sends `token` to a screenpipe started with --require-auth.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn scopes(&self) -> &[McpScope] {
+        &self.scopes
+    }
+
+    /// The tools allowed by the scopes, as listed to clients.
+    pub fn tools(&self) -> Vec<Value> {
+        TOOLS
+            .iter()
+            .filter(|tool| self.scopes.contains(&tool.scope))
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": (tool.input_schema)(),
+                })
+            })
+            .collect()
+    }
+
+    /// The response to a JSON-RPC message, none for notifications.
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                message.get("id").cloned().unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "expected a JSON-RPC request",
+            ));
+        };
+        let id = message.get("id").cloned()?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "screenpipe", "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({"tools": self.tools()})),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or_default();
+        let tool = TOOLS
+            .iter()
+            .find(|tool| tool.name == name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown tool {}", name)))?;
+        if !self.scopes.contains(&tool.scope) {
+            return Err((
+                INVALID_PARAMS,
+                format!(
+                    "{} is not allowed, start with --scope {}",
+                    name,
+                    tool.scope.name()
+                ),
+            ));
+        }
+
+        let arguments = &params["arguments"];
+        debug!("mcp tool call {} {}", name, arguments);
+        let content = match tool.scope {
+            McpScope::Search => self.search(arguments).await,
+            McpScope::Timeline => self.timeline(arguments).await,
+            McpScope::Frames => self.frame(arguments).await,
+        };
+        // failures go back to the model, which can fix its arguments
+        Ok(match content {
+            Ok(content) => json!({"content": content, "isError": false}),
+            Err(e) => json!({
+                "content": [{"type": "text", "text": e.to_string()}],
+                "isError": true,
+            }),
+        })
+    }
+
+    async fn search(&self, arguments: &Value) -> Result<Vec<Value>> {
+        let mut query = string_arguments(
+            arguments,
+            &[
+                "q",
+                "content_type",
+                "mode",
+                "start_time",
+                "end_time",
+                "app_name",
+                "window_name",
+            ],
+        );
+        let limit = clamped(
+            arguments,
+            "limit",
+            DEFAULT_SEARCH_RESULTS,
+            MAX_SEARCH_RESULTS,
+        )?;
+        query.push(("limit", limit.to_string()));
+        query.push((
+            "offset",
+            clamped(arguments, "offset", 0, u64::MAX)?.to_string(),
+        ));
+        let body = self.get_json("/search", &query).await?;
+        Ok(vec![text_content(&body)])
+    }
+
+    async fn timeline(&self, arguments: &Value) -> Result<Vec<Value>> {
+        let mut query = string_arguments(arguments, &["start", "end"]);
+        if query.len() < 2 {
+            return Err(anyhow!("start and end are required, as RFC 3339 times"));
+        }
+        let limit = clamped(
+            arguments,
+            "limit",
+            DEFAULT_TIMELINE_ITEMS,
+            MAX_TIMELINE_ITEMS,
+        )?;
+        query.push(("limit", limit.to_string()));
+        let body = self.get_json("/timeline", &query).await?;
+        Ok(vec![text_content(&body)])
+    }
+
+    async fn frame(&self, arguments: &Value) -> Result<Vec<Value>> {
+        let frame_id = arguments["frame_id"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("frame_id is required"))?;
+        let response = self.send(&format!("/frames/{}", frame_id), &[]).await?;
+        let mime_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("image/jpeg")
+            .to_string();
+        let image = response.bytes().await?;
+        Ok(vec![
+            json!({"type": "text", "text": format!("frame {}", frame_id)}),
+            json!({
+                "type": "image",
+                "data": general_purpose::STANDARD.encode(&image),
+                "mimeType": mime_type,
+            }),
+        ])
+    }
+
+    async fn send(&self, path: &str, query: &[(&str, String)]) -> Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .get(format!("{}{}", self.api_url, path))
+            .query(query)
+            .timeout(API_TIMEOUT);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("screenpipe is not reachable at {}: {}", self.api_url, e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{} returned {}: {}", path, status, text));
+        }
+        Ok(response)
+    }
+
+    async fn get_json(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        Ok(self.send(path, query).await?.json().await?)
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message},
+    })
+}
+
+fn text_content(body: &Value) -> Value {
+    json!({"type": "text", "text": body.to_string()})
+}
+
+/// The string arguments among `names` that are set, as query parameters.
+fn string_arguments<'a>(arguments: &Value, names: &[&'a str]) -> Vec<(&'a str, String)> {
+    names
+        .iter()
+        .filter_map(|name| {
+            arguments[*name]
+                .as_str()
+                .filter(|value| !value.is_empty())
+                .map(|value| (*name, value.to_string()))
+        })
+        .collect()
+}
+
+fn clamped(arguments: &Value, name: &str, default: u64, max: u64) -> Result<u64> {
+    match &arguments[name] {
+        Value::Null => Ok(default),
+        value => value
+            .as_u64()
+            .map(|value| value.min(max))
+            .ok_or_else(|| anyhow!("{} must be a positive integer", name)),
+    }
+}
+
+/// Answers MCP requests read from stdin, one JSON-RPC message per line, on stdout until
+/// stdin closes.
+pub async fn run_mcp(server: McpServer) -> Result<()> {
+    let scopes: Vec<&str> = server.scopes().iter().map(McpScope::name).collect();
+    info!("mcp server started, scopes: {}", scopes.join(", "));
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => server.handle_message(message).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            stdout.write_all(response.to_string().as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use crate::analytics::MAX_FOCUS_GAP_SECS;
+use crate::calendar::{tag, MEETING_TAG};
+use crate::digest::{find_meetings, Meeting};
+use crate::llm::Llm;
+use crate::summarize::{
+    build_prompt, gather_sources, summarize, SummarizeRequest, Summary, SummarySource,
+    MAX_SUMMARIZE_CHUNKS,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use screenpipe_db::{
+    ActionItem, CalendarEvent, ContentType, DatabaseManager, MeetingNotes, WindowSession,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const DETECTION_INTERVAL: Duration = Duration::from_secs(60);
+// Meetings are looked for this far back, longer ones are extended while they go on
+const DETECTION_LOOKBACK_HOURS: i64 = 3;
+const MAX_TRANSCRIPTIONS: u32 = 10_000;
+// Meeting app windows this close together are one meeting, so a meeting that ended this
+// long ago is over
+const MEETING_OVER_SECS: i64 = 600;
+// Meetings given notes per round
+const NOTES_BATCH: u32 = 8;
+// Wait once every meeting over has notes, or after the LLM failed
+const NOTES_IDLE: Duration = Duration::from_secs(60);
+const NOTES_PROMPT: &str = "You take the notes of a meeting from what was said and shown \
+                            during it, speech to text and OCR so expect typos. Answer with \
+                            JSON only, like {\"summary\": \"a short paragraph\", \
+                            \"decisions\": [\"...\"], \"action_items\": [{\"task\": \
+                            \"...\", \"owner\": \"who took it on, or null\", \"due\": \
+                            \"when, or null\"}]}. Only state what is in the sources.";
+
+/// Meeting app windows open while speech was transcribed, a meeting app left open on its
+/// own is not a meeting.
+pub fn detect_meetings(
+    sessions: &[WindowSession],
+    transcription_times: &[DateTime<Utc>],
+) -> Vec<Meeting> {
+    find_meetings(sessions, transcription_times)
+        .into_iter()
+        .filter(|meeting| meeting.transcriptions > 0)
+        .collect()
+}
+
+/// `meeting`, the app and the window title, what is captured during it is tagged with.
+pub fn meeting_tags(meeting: &Meeting) -> Vec<String> {
+    let mut tags = vec![MEETING_TAG.to_string()];
+    for name in [&meeting.app_name, &meeting.title].map(|text| tag(text)) {
+        if !name.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+            tags.push(name);
+        }
+    }
+    tags
+}
+
+/// Whether `meeting` ended long enough ago that nothing more will be added to it.
+pub fn is_over(meeting: &CalendarEvent, now: DateTime<Utc>) -> bool {
+    (now - meeting.end_time).num_seconds() >= MEETING_OVER_SECS
+}
+
+/// Keeps the meetings seen in meeting apps in the database, tagging what is captured
+/// during them.
+pub struct MeetingDetector {
+    db: Arc<DatabaseManager>,
+}
+
+impl MeetingDetector {
+    pub fn new(db: Arc<DatabaseManager>) -> Self {
+        Self { db }
+    }
+
+    /// Stores the meetings of the last few hours, returns how many are new or changed.
+    pub async fn detect(&self, now: DateTime<Utc>) -> Result<usize> {
+        let start = now - chrono::Duration::hours(DETECTION_LOOKBACK_HOURS);
+        let sessions = self
+            .db
+            .get_window_sessions(start, now, MAX_FOCUS_GAP_SECS)
+            .await?;
+        let transcription_times: Vec<DateTime<Utc>> = self
+            .db
+            .get_timeline_transcriptions(start, 0, now, MAX_TRANSCRIPTIONS)
+            .await?
+            .into_iter()
+            .map(|transcription| transcription.timestamp)
+            .collect();
+
+        let mut updated = 0;
+        for meeting in detect_meetings(&sessions, &transcription_times) {
+            let note = format!("{} in {}", meeting.title, meeting.app_name);
+            if self
+                .db
+                .upsert_detected_meeting(
+                    &meeting.app_name,
+                    &meeting.title,
+                    meeting.start_time,
+                    meeting.end_time,
+                    Some(&note),
+                    &meeting_tags(&meeting),
+                )
+                .await?
+            {
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+}
+
+/// Looks for meetings every minute.
+pub async fn run_meeting_detection(detector: Arc<MeetingDetector>) {
+    info!("detecting meetings in meeting apps");
+    loop {
+        match detector.detect(Utc::now()).await {
+            Ok(0) => {}
+            Ok(updated) => debug!("{} meetings detected or extended", updated),
+            Err(e) => warn!("failed to detect meetings: {}", e),
+        }
+        tokio::time::sleep(DETECTION_INTERVAL).await;
+    }
+}
+
+/// What was said and shown during `meeting`, as numbered sources.
+async fn meeting_sources(
+    db: &DatabaseManager,
+    meeting: &CalendarEvent,
+) -> Result<Vec<SummarySource>, sqlx::Error> {
+    let request = SummarizeRequest {
+        q: None,
+        start_time: Some(meeting.start_time),
+        end_time: Some(meeting.end_time),
+        content_type: ContentType::AudioAndOcr,
+        limit: MAX_SUMMARIZE_CHUNKS,
+        instructions: None,
+    };
+    gather_sources(db, &request).await
+}
+
+/// Summarizes what was said and shown during `meeting` with `llm`, `None` when nothing
+/// was captured. Meetings over are summarized by their notes, written now when they
+/// have none yet.
+pub async fn meeting_summary(
+    db: &DatabaseManager,
+    llm: &Llm,
+    meeting: &CalendarEvent,
+) -> Result<Option<Summary>> {
+    if is_over(meeting, Utc::now()) {
+        let notes = match db.get_meeting_notes(meeting.id).await? {
+            Some(notes) => notes,
+            None => write_meeting_notes(db, llm, meeting).await?,
+        };
+        if notes.summary.is_empty() {
+            return Ok(None);
+        }
+        // the notes don't cite their sources
+        return Ok(Some(Summary {
+            summary: notes.summary,
+            provider: notes.provider,
+            model: notes.model,
+            sources: Vec::new(),
+            source_count: 0,
+        }));
+    }
+
+    let sources = meeting_sources(db, meeting).await?;
+    if sources.is_empty() {
+        return Ok(None);
+    }
+    let instructions = format!(
+        "Summarize the meeting \"{}\": what was discussed, the decisions made and the \
+         action items with who owns them.",
+        meeting.title
+    );
+    Ok(Some(summarize(llm, sources, Some(&instructions)).await?))
+}
+
+/// The notes asked for in `NOTES_PROMPT`.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub struct NotesAnswer {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+    #[serde(default)]
+    pub action_items: Vec<ActionItem>,
+}
+
+/// The notes in the answer of the LLM, which is kept as the summary when it isn't the
+/// JSON asked for. Blank decisions and tasks are left out.
+pub fn parse_notes(answer: &str) -> NotesAnswer {
+    let answer = answer.trim();
+    // the JSON may come in a code block or after a sentence
+    let json = match (answer.find('{'), answer.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &answer[start..=end],
+        _ => answer,
+    };
+    let mut notes = serde_json::from_str::<NotesAnswer>(json).unwrap_or_else(|_| NotesAnswer {
+        summary: answer.to_string(),
+        ..Default::default()
+    });
+    notes.summary = notes.summary.trim().to_string();
+    notes
+        .decisions
+        .retain(|decision| !decision.trim().is_empty());
+    notes
+        .action_items
+        .retain(|item| !item.task.trim().is_empty());
+    notes
+}
+
+/// Writes the summary, decisions and action items of `meeting` with `llm` and stores them.
+/// Meetings nothing was captured during get empty notes.
+pub async fn write_meeting_notes(
+    db: &DatabaseManager,
+    llm: &Llm,
+    meeting: &CalendarEvent,
+) -> Result<MeetingNotes> {
+    let sources = meeting_sources(db, meeting).await?;
+    let notes = if sources.is_empty() {
+        NotesAnswer::default()
+    } else {
+        let instructions = format!("Take the notes of the meeting \"{}\".", meeting.title);
+        let answer = llm
+            .complete(NOTES_PROMPT, &build_prompt(&sources, Some(&instructions)))
+            .await?;
+        parse_notes(&answer)
+    };
+    Ok(db
+        .upsert_meeting_notes(
+            meeting.id,
+            &notes.summary,
+            &notes.decisions,
+            &notes.action_items,
+            &llm.provider.to_string(),
+            &llm.model,
+        )
+        .await?)
+}
+
+/// Writes the notes of detected meetings with `llm` once they are over, the last ended
+/// first. Meetings the LLM failed on are tried again once the others have notes.
+pub async fn run_meeting_notes(db: Arc<DatabaseManager>, llm: Arc<Llm>) {
+    info!("writing meeting notes with {}", llm.model);
+    let mut failed = Vec::new();
+    loop {
+        match write_pending_notes(&db, &llm, &mut failed).await {
+            Ok(0) => {
+                failed.clear();
+                tokio::time::sleep(NOTES_IDLE).await;
+            }
+            Ok(count) => debug!("went through {} meetings without notes", count),
+            Err(e) => {
+                warn!("failed to list the meetings without notes: {}", e);
+                tokio::time::sleep(NOTES_IDLE).await;
+            }
+        }
+    }
+}
+
+/// Writes the notes of a batch of meetings over, leaving out those in `failed` and adding
+/// those the LLM fails on. Returns how many were tried.
+async fn write_pending_notes(
+    db: &DatabaseManager,
+    llm: &Llm,
+    failed: &mut Vec<i64>,
+) -> Result<usize> {
+    let ended_before = Utc::now() - chrono::Duration::seconds(MEETING_OVER_SECS);
+    let meetings = db
+        .get_meetings_without_notes("detected", ended_before, failed, NOTES_BATCH)
+        .await?;
+    for meeting in &meetings {
+        if let Err(e) = write_meeting_notes(db, llm, meeting).await {
+            warn!("failed to write the notes of meeting {}: {}", meeting.id, e);
+            failed.push(meeting.id);
+        }
+    }
+    Ok(meetings.len())
+}
@@ -0,0 +1,358 @@
+use crate::frame_storage::frame_source;
+use crate::video_utils::extract_frame_from_video;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use screenpipe_db::{DatabaseManager, ExportFrame, ExportTranscription};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+pub const ATTACHMENTS_DIR: &str = "attachments";
+// In the vault, the last day whose note was written once it was over
+const STATE_FILE: &str = ".screenpipe-notes.json";
+// How often the notes of the days that are over are checked for
+const EXPORT_INTERVAL: Duration = Duration::from_secs(3600);
+// A window focused this long without switching is highlighted again
+const HIGHLIGHT_INTERVAL_SECS: i64 = 600;
+const SNIPPET_CHARS: usize = 280;
+// Rows read from the database at a time
+const PAGE_SIZE: u32 = 500;
+// Escaped in recorded text, it would otherwise add tags, links and formatting to the vault
+const MARKDOWN_SPECIAL: &[char] = &[
+    '\\', '`', '*', '_', '#', '[', ']', '<', '>', '|', '~', '=', '$', '%', '^',
+];
+
+/// A moment of the day shown in its note, the OCR text of the frame it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_name: String,
+    pub text: String,
+    /// Path of the frame image, relative to the vault
+    pub image: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NoteReport {
+    pub date: NaiveDate,
+    pub path: PathBuf,
+    pub highlights: usize,
+    pub transcriptions: usize,
+    pub images: usize,
+}
+
+/// Picks the frames highlighted in a note: the first one read of every app and window
+/// switched to, then one every `HIGHLIGHT_INTERVAL_SECS` while it stays focused. Monitors
+/// are followed separately.
+#[derive(Default)]
+pub struct HighlightPicker {
+    last: HashMap<String, (Option<String>, Option<String>, DateTime<Utc>)>,
+}
+
+impl HighlightPicker {
+    /// Frames in capture order.
+    pub fn pick(&mut self, frame: &ExportFrame) -> bool {
+        let read = frame
+            .text
+            .as_deref()
+            .is_some_and(|text| !text.trim().is_empty());
+        if !read || frame.focused == Some(false) {
+            return false;
+        }
+        let picked = match self.last.get(&frame.device_name) {
+            Some((app_name, window_name, timestamp)) => {
+                *app_name != frame.app_name
+                    || *window_name != frame.window_name
+                    || (frame.timestamp - *timestamp).num_seconds() >= HIGHLIGHT_INTERVAL_SECS
+            }
+            None => true,
+        };
+        if picked {
+            self.last.insert(
+                frame.device_name.clone(),
+                (
+                    frame.app_name.clone(),
+                    frame.window_name.clone(),
+                    frame.timestamp,
+                ),
+            );
+        }
+        picked
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct ExportState {
+    last_complete_day: Option<NaiveDate>,
+}
+
+/// Writes a Markdown note of every day to a folder, e.g. an Obsidian vault. Days and times
+/// are local. Frame images go to `attachments/<date>` next to the notes.
+pub struct ObsidianExporter {
+    db: Arc<DatabaseManager>,
+    vault: PathBuf,
+}
+
+impl ObsidianExporter {
+    pub fn new(db: Arc<DatabaseManager>, vault: PathBuf) -> Self {
+        Self { db, vault }
+    }
+
+    pub fn note_path(&self, date: NaiveDate) -> PathBuf {
+        self.vault.join(format!("{}.md", date))
+    }
+
+    /// Writes the notes of the days before `today` not written since they were over, from
+    /// the day before when none were yet. Notes written during their day are replaced.
+    pub async fn export_complete_days(&self, today: NaiveDate) -> Result<Vec<NoteReport>> {
+        let state_path = self.vault.join(STATE_FILE);
+        let mut state: ExportState = match tokio::fs::read_to_string(&state_path).await {
+            Ok(state) => serde_json::from_str(&state).unwrap_or_else(|e| {
+                warn!("ignoring unreadable {}: {}", state_path.display(), e);
+                ExportState::default()
+            }),
+            Err(_) => ExportState::default(),
+        };
+        let first = match state.last_complete_day {
+            Some(day) => day.succ_opt(),
+            None => today.pred_opt(),
+        };
+        let Some(first) = first else {
+            return Ok(Vec::new());
+        };
+
+        let mut reports = Vec::new();
+        for date in first.iter_days().take_while(|date| *date < today) {
+            reports.push(self.export_day(date).await?);
+            state.last_complete_day = Some(date);
+            tokio::fs::write(&state_path, serde_json::to_vec(&state)?).await?;
+        }
+        Ok(reports)
+    }
+
+    /// Writes the note of `date`, replacing the one written before.
+    pub async fn export_day(&self, date: NaiveDate) -> Result<NoteReport> {
+        let (start, end) = local_day_bounds(date)?;
+        let attachments = self.vault.join(ATTACHMENTS_DIR).join(date.to_string());
+
+        let mut picker = HighlightPicker::default();
+        let mut highlights = Vec::new();
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .db
+                .get_export_frames(start, end, after_id, PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.id;
+            for frame in page.iter().filter(|frame| frame.timestamp < end) {
+                if picker.pick(frame) {
+                    let image = self.save_image(frame, &attachments, date).await;
+                    highlights.push(Highlight {
+                        timestamp: frame.timestamp,
+                        app_name: frame.app_name.clone().unwrap_or_default(),
+                        window_name: frame.window_name.clone().unwrap_or_default(),
+                        text: frame.text.clone().unwrap_or_default(),
+                        image,
+                    });
+                }
+            }
+        }
+        highlights.sort_by_key(|highlight| highlight.timestamp);
+
+        let mut transcriptions = Vec::new();
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .db
+                .get_export_transcriptions(start, end, after_id, PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.id;
+            transcriptions.extend(page.into_iter().filter(|t| t.timestamp < end));
+        }
+        transcriptions.sort_by_key(|transcription| transcription.timestamp);
+
+        tokio::fs::create_dir_all(&self.vault).await?;
+        let path = self.note_path(date);
+        // written next to the note first, so the vault never shows half of it
+        let partial = self.vault.join(format!(".{}.md.partial", date));
+        let note = render_note(date, &highlights, &transcriptions, &Local);
+        tokio::fs::write(&partial, note).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        let report = NoteReport {
+            date,
+            path,
+            highlights: highlights.len(),
+            transcriptions: transcriptions.len(),
+            images: highlights.iter().filter(|h| h.image.is_some()).count(),
+        };
+        info!(
+            "wrote the note of {} to {:?}: {} highlights, {} transcriptions",
+            date, report.path, report.highlights, report.transcriptions
+        );
+        Ok(report)
+    }
+
+    /// Extracts the image of a highlighted frame, kept when it was extracted before.
+    /// Frames whose recording is gone have none.
+    async fn save_image(
+        &self,
+        frame: &ExportFrame,
+        attachments: &Path,
+        date: NaiveDate,
+    ) -> Option<String> {
+        let file_name = format!("frame-{}.jpg", frame.id);
+        let image = format!("{}/{}/{}", ATTACHMENTS_DIR, date, file_name);
+        let path = attachments.join(&file_name);
+        if tokio::fs::metadata(&path).await.is_ok() {
+            return Some(image);
+        }
+        let (source, _) = frame_source(&frame.file_path, frame.offset_index);
+        if tokio::fs::metadata(&source).await.is_err() {
+            debug!("no image of frame {}, {} is gone", frame.id, source);
+            return None;
+        }
+
+        let result = async {
+            let extracted = extract_frame_from_video(&frame.file_path, frame.offset_index).await?;
+            tokio::fs::create_dir_all(attachments).await?;
+            tokio::fs::copy(&extracted, &path).await?;
+            let _ = tokio::fs::remove_file(&extracted).await;
+            anyhow::Ok(())
+        };
+        match result.await {
+            Ok(()) => Some(image),
+            Err(e) => {
+                warn!("failed to save the image of frame {}: {}", frame.id, e);
+                None
+            }
+        }
+    }
+}
+
+/// Start of `date` and of the day after, in local time.
+fn local_day_bounds(date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let start_of = |date: NaiveDate| {
+        // some time changes skip midnight
+        (0..3)
+            .find_map(|hour| {
+                Local
+                    .from_local_datetime(&date.and_hms_opt(hour, 0, 0)?)
+                    .earliest()
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("invalid date {}", date))
+    };
+    let next = date
+        .succ_opt()
+        .ok_or_else(|| anyhow!("invalid date {}", date))?;
+    Ok((start_of(date)?, start_of(next)?))
+}
+
+/// The Markdown note of `date`, its times in `tz`.
+pub fn render_note<Tz: TimeZone>(
+    date: NaiveDate,
+    highlights: &[Highlight],
+    transcriptions: &[ExportTranscription],
+    tz: &Tz,
+) -> String
+where
+    Tz::Offset: Display,
+{
+    let mut note = format!(
+        "---\ndate: {}\ntags: [screenpipe]\n---\n\n# {}\n",
+        date, date
+    );
+    if highlights.is_empty() && transcriptions.is_empty() {
+        note.push_str("\nNothing was recorded.\n");
+        return note;
+    }
+
+    if !highlights.is_empty() {
+        note.push_str("\n## Screen\n");
+        for highlight in highlights {
+            let app_name = escape_markdown(highlight.app_name.trim());
+            let title = match highlight.window_name.trim() {
+                "" => app_name.clone(),
+                window_name => format!("{} - {}", app_name, escape_markdown(window_name)),
+            };
+            let _ = write!(
+                note,
+                "\n### {} {}\n\n> {}\n",
+                highlight.timestamp.with_timezone(tz).format("%H:%M"),
+                title,
+                escape_markdown(&snippet(&highlight.text))
+            );
+            if let Some(image) = &highlight.image {
+                let _ = writeln!(note, "\n![{}]({})", app_name, image);
+            }
+        }
+    }
+
+    if !transcriptions.is_empty() {
+        note.push_str("\n## Audio\n\n");
+        for transcription in transcriptions {
+            let text = one_line(&transcription.transcription);
+            if text.is_empty() {
+                continue;
+            }
+            let _ = writeln!(
+                note,
+                "- **{}** {}: {}",
+                transcription.timestamp.with_timezone(tz).format("%H:%M:%S"),
+                escape_markdown(&transcription.device),
+                escape_markdown(&text)
+            );
+        }
+    }
+    note
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` shown as written, not as Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_SPECIAL.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The start of an OCR text on one line.
+fn snippet(text: &str) -> String {
+    let text = one_line(text);
+    if text.chars().count() <= SNIPPET_CHARS {
+        return text;
+    }
+    let mut snippet: String = text.chars().take(SNIPPET_CHARS).collect();
+    snippet.push('…');
+    snippet
+}
+
+/// Writes the note of every day once it is over, checked every hour. Days missed while
+/// the server was off are written once it is back.
+pub async fn run_obsidian_export(exporter: Arc<ObsidianExporter>) {
+    info!("writing daily notes to {:?}", exporter.vault);
+    loop {
+        if let Err(e) = exporter
+            .export_complete_days(Local::now().date_naive())
+            .await
+        {
+            warn!("failed to write the daily notes: {}", e);
+        }
+        tokio::time::sleep(EXPORT_INTERVAL).await;
+    }
+}
@@ -0,0 +1,124 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Encodes where a page stops as an opaque string for clients.
+pub fn encode_cursor<T: Serialize>(cursor: &T) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    general_purpose::URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Result<T, String> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(|| "invalid cursor".to_string())
+}
+
+/// Where the next page of search results starts. Sent to clients as an opaque string,
+/// so how it pages can change without breaking them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "k", rename_all = "snake_case")]
+pub enum Cursor {
+    /// Newest first: the page continues with the results at or before `timestamp`, but
+    /// the `seen` ones at exactly `timestamp` already returned. What is captured meanwhile
+    /// is newer and doesn't move the rest.
+    Before {
+        timestamp: DateTime<Utc>,
+        seen: Vec<String>,
+    },
+    /// Any other order: the results from `offset` on of what was captured until `as_of`.
+    Offset { as_of: DateTime<Utc>, offset: u32 },
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        encode_cursor(self)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        decode_cursor(cursor)
+    }
+
+    /// The end of the time range a page starting at the cursor is searched in.
+    pub fn end_time(&self, end_time: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        let bound = match self {
+            Cursor::Before { timestamp, .. } => *timestamp,
+            Cursor::Offset { as_of, .. } => *as_of,
+        };
+        end_time.map_or(bound, |end_time| end_time.min(bound))
+    }
+
+    /// Results of the range of `end_time` to leave out.
+    pub fn offset(&self) -> u32 {
+        match self {
+            Cursor::Before { .. } => 0,
+            Cursor::Offset { offset, .. } => *offset,
+        }
+    }
+
+    /// Keys of the results at the end of the range already returned, the page fetches as
+    /// many more and leaves them out.
+    pub fn seen(&self) -> &[String] {
+        match self {
+            Cursor::Before { seen, .. } => seen,
+            Cursor::Offset { .. } => &[],
+        }
+    }
+}
+
+/// The cursor of the page after the newest first `results`, their timestamp and key, which
+/// `cursor` started. None when the page is the last.
+pub fn next_before_cursor(
+    cursor: Option<&Cursor>,
+    results: &[(DateTime<Utc>, String)],
+    limit: u32,
+) -> Option<Cursor> {
+    if results.len() < limit as usize {
+        return None;
+    }
+    let last = results.last()?.0;
+    let mut seen: Vec<String> = Vec::new();
+    // the whole page was at the timestamp the cursor stopped at
+    if let Some(Cursor::Before {
+        timestamp,
+        seen: already,
+    }) = cursor
+    {
+        if *timestamp == last {
+            seen.extend(already.iter().cloned());
+        }
+    }
+    seen.extend(
+        results
+            .iter()
+            .filter(|(timestamp, _)| *timestamp == last)
+            .map(|(_, key)| key.clone()),
+    );
+    Some(Cursor::Before {
+        timestamp: last,
+        seen,
+    })
+}
+
+/// The cursor of the page after one of `returned` results, started by `cursor` or, on the
+/// first page, at `now`. None when the page is the last.
+pub fn next_offset_cursor(
+    cursor: Option<&Cursor>,
+    returned: usize,
+    limit: u32,
+    now: DateTime<Utc>,
+) -> Option<Cursor> {
+    if returned < limit as usize {
+        return None;
+    }
+    let (as_of, offset) = match cursor {
+        Some(Cursor::Offset { as_of, offset }) => (*as_of, *offset),
+        _ => (now, 0),
+    };
+    Some(Cursor::Offset {
+        as_of,
+        offset: offset + returned as u32,
+    })
+}
@@ -0,0 +1,139 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use screenpipe_db::SearchResult;
+
+/// Most recent captures matching the other filters a fuzzy or regex search looks at, per
+/// content type. Narrow the time range to search further back.
+pub const SCAN_LIMIT: u32 = 5000;
+/// Longest `q` accepted for a fuzzy or regex search
+pub const MAX_PATTERN_LEN: usize = 256;
+// Compiled regex and lazy DFA sizes, patterns like `\w{1000}` blow up otherwise
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+// OCR misreads folded before comparing, e.g. "rn" for "m"
+const OCR_CONFUSIONS: [(&str, &str); 5] =
+    [("rn", "m"), ("vv", "w"), ("0", "o"), ("1", "l"), ("|", "l")];
+
+/// Matches captured text against `q` for the `fuzzy` and `regex` search modes. Neither
+/// can use the full text index, so the captures are scanned, at most `SCAN_LIMIT` of them.
+#[derive(Debug)]
+pub enum TextMatcher {
+    /// Every word of `q` appears in the text, up to a few typos each
+    Fuzzy(Vec<Vec<char>>),
+    Regex(Regex),
+}
+
+impl TextMatcher {
+    pub fn fuzzy(query: &str) -> Result<Self> {
+        check_length(query)?;
+        let words: Vec<Vec<char>> = normalize(query)
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.chars().collect())
+            .collect();
+        if words.is_empty() {
+            bail!("fuzzy search needs at least one word");
+        }
+        Ok(Self::Fuzzy(words))
+    }
+
+    /// A case insensitive regex. The regex crate matches in linear time, a scan takes at
+    /// most as long as reading the scanned text.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        check_length(pattern)?;
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .dfa_size_limit(REGEX_SIZE_LIMIT)
+            .build()?;
+        Ok(Self::Regex(regex))
+    }
+
+    /// Edits needed for `text` to match, `None` if it doesn't. Always 0 for a regex.
+    pub fn distance(&self, text: &str) -> Option<usize> {
+        match self {
+            Self::Fuzzy(words) => {
+                let text: Vec<char> = normalize(text).chars().collect();
+                words.iter().try_fold(0, |total, word| {
+                    let distance = substring_distance(word, &text);
+                    (distance <= max_edits(word.len())).then_some(total + distance)
+                })
+            }
+            Self::Regex(regex) => regex.is_match(text).then_some(0),
+        }
+    }
+
+    /// Keeps the results matching, newest first or, with `by_distance`, closest matches
+    /// first.
+    pub fn filter(&self, results: Vec<SearchResult>, by_distance: bool) -> Vec<SearchResult> {
+        let mut matched: Vec<(usize, SearchResult)> = results
+            .into_iter()
+            .filter_map(|result| Some((self.distance(result_text(&result))?, result)))
+            .collect();
+        matched.sort_by(|a, b| result_timestamp(&b.1).cmp(&result_timestamp(&a.1)));
+        if by_distance {
+            matched.sort_by_key(|(distance, _)| *distance);
+        }
+        matched.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+fn check_length(query: &str) -> Result<()> {
+    if query.chars().count() > MAX_PATTERN_LEN {
+        bail!("query is longer than {} characters", MAX_PATTERN_LEN);
+    }
+    Ok(())
+}
+
+fn normalize(text: &str) -> String {
+    OCR_CONFUSIONS
+        .iter()
+        .fold(text.to_lowercase(), |text, (from, to)| {
+            text.replace(from, to)
+        })
+}
+
+// Short words would match almost anything with a typo allowed
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Fewest insertions, deletions and substitutions turning `word` into some part of `text`.
+fn substring_distance(word: &[char], text: &[char]) -> usize {
+    // costs of matching the first i characters of the word ending at the current one of
+    // the text, a match can start anywhere so the empty prefix is free
+    let mut previous: Vec<usize> = (0..=word.len()).collect();
+    let mut best = previous[word.len()];
+    for &c in text {
+        let mut current = vec![0; word.len() + 1];
+        for i in 1..=word.len() {
+            let substitution = previous[i - 1] + usize::from(word[i - 1] != c);
+            current[i] = substitution.min(previous[i] + 1).min(current[i - 1] + 1);
+        }
+        best = best.min(current[word.len()]);
+        previous = current;
+    }
+    best
+}
+
+fn result_text(result: &SearchResult) -> &str {
+    match result {
+        SearchResult::OCR(ocr) => &ocr.ocr_text,
+        SearchResult::Audio(audio) => &audio.transcription,
+        SearchResult::UI(ui) => &ui.text,
+        SearchResult::Clipboard(entry) => &entry.text,
+    }
+}
+
+fn result_timestamp(result: &SearchResult) -> DateTime<Utc> {
+    match result {
+        SearchResult::OCR(ocr) => ocr.timestamp,
+        SearchResult::Audio(audio) => audio.timestamp,
+        SearchResult::UI(ui) => ui.timestamp,
+        SearchResult::Clipboard(entry) => entry.timestamp,
+    }
+}
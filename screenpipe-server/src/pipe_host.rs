@@ -0,0 +1,246 @@
+//! What pipes run in the sandbox reach screenpipe through.
+use anyhow::{anyhow, Result};
+use axum::body::{to_bytes, Body};
+use axum::http::{header::CONTENT_TYPE, Method, Request};
+use axum::Router;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use screenpipe_core::pipe_runtime::{strip_images, HostFuture};
+use screenpipe_core::{PipeHost, ReadRequest};
+use screenpipe_db::{ContentType, DatabaseManager, SearchExclusions, SearchSort};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, OnceLock};
+use tower::ServiceExt;
+use tracing::debug;
+
+use crate::alerts::show_notification;
+use crate::auth::{required_scope, ApiScope, IMAGE_PARAMS};
+
+const MAX_QUERY_RESULTS: u32 = 100;
+const DEFAULT_QUERY_RESULTS: u32 = 20;
+const MAX_READ_BYTES: usize = 10 * 1024 * 1024;
+
+/// What `pipe.query` searches with, a part of the parameters of `GET /search`.
+#[derive(Debug, Deserialize)]
+pub struct PipeQuery {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default)]
+    pub content_type: ContentType,
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+}
+
+/// Whether a pipe may read `path`, by the scope a token would need for it: text always,
+/// media with `images`, nothing that writes or administers.
+pub fn pipe_may_read(path: &str, images: bool) -> Result<()> {
+    match required_scope(&Method::GET, path, None) {
+        None | Some(ApiScope::ReadSearch) => Ok(()),
+        Some(ApiScope::ReadMedia) if images => Ok(()),
+        Some(ApiScope::ReadMedia) => Err(anyhow!("the pipe is not allowed to read images")),
+        Some(ApiScope::WriteTags | ApiScope::Admin) => {
+            Err(anyhow!("{} can't be read by pipes", path))
+        }
+    }
+}
+
+/// The path and query of a read of `path` with the query parameters `params`, which can
+/// only ask for frames when the pipe gets images.
+pub fn read_uri(path: &str, params: &Value, images: bool) -> Result<String> {
+    let mut url = Url::parse("http://localhost")?.join(path)?;
+    match params {
+        Value::Object(params) => {
+            let mut query = url.query_pairs_mut();
+            for (name, value) in params {
+                let asks_for_images = !matches!(value, Value::Null | Value::Bool(false))
+                    && value.as_str() != Some("false");
+                if !images && asks_for_images && IMAGE_PARAMS.contains(&name.as_str()) {
+                    return Err(anyhow!(
+                        "the pipe is not allowed to read images, {} needs the images permission",
+                        name
+                    ));
+                }
+                match value {
+                    Value::Null => {}
+                    Value::String(value) => {
+                        query.append_pair(name, value);
+                    }
+                    value => {
+                        query.append_pair(name, &value.to_string());
+                    }
+                }
+            }
+        }
+        Value::Null => {}
+        _ => return Err(anyhow!("query parameters must be an object")),
+    }
+    let uri = match url.query().filter(|query| !query.is_empty()) {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    Ok(uri)
+}
+
+pub struct ServerPipeHost {
+    db: Arc<DatabaseManager>,
+    port: u16,
+    // the routes of the API, reads don't go through its tokens but `pipe_may_read`
+    api: OnceLock<Router>,
+}
+
+impl ServerPipeHost {
+    /// Host of the pipes of the server listening on `port`.
+    pub fn new(db: Arc<DatabaseManager>, port: u16) -> Self {
+        Self {
+            db,
+            port,
+            api: OnceLock::new(),
+        }
+    }
+
+    /// Sets the routes `pipe.read` is answered by, reads fail before.
+    pub fn set_api(&self, api: Router) {
+        let _ = self.api.set(api);
+    }
+
+    async fn read_api(&self, request: ReadRequest) -> Result<Value> {
+        pipe_may_read(&request.path, request.images)?;
+        let api = self
+            .api
+            .get()
+            .ok_or_else(|| anyhow!("the API isn't ready yet"))?
+            .clone();
+        let uri = read_uri(&request.path, &request.params, request.images)?;
+        debug!("[{}] reads {}", request.pipe, uri);
+        let response = api.oneshot(Request::get(&uri).body(Body::empty())?).await?;
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = to_bytes(response.into_body(), MAX_READ_BYTES)
+            .await
+            .map_err(|e| anyhow!("failed to read {}: {}", request.path, e))?;
+        if !status.is_success() {
+            return Err(anyhow!(
+                "{} answered {}: {}",
+                request.path,
+                status,
+                String::from_utf8_lossy(&body)
+            ));
+        }
+        if content_type.starts_with("application/json") {
+            let mut answer: Value = serde_json::from_slice(&body)?;
+            // in case an endpoint answers screenshots whatever it is asked
+            if !request.images {
+                strip_images(&mut answer);
+            }
+            return Ok(answer);
+        }
+        if content_type.starts_with("text/") {
+            return Ok(Value::String(String::from_utf8_lossy(&body).into_owned()));
+        }
+        // the scope of the path should have kept media out already
+        if !request.images {
+            return Err(anyhow!("the pipe is not allowed to read images"));
+        }
+        Ok(json!({
+            "content_type": content_type,
+            "base64": general_purpose::STANDARD.encode(&body),
+        }))
+    }
+
+    async fn search(&self, params: Value) -> Result<Value> {
+        let query: PipeQuery =
+            serde_json::from_value(params).map_err(|e| anyhow!("invalid query: {}", e))?;
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_RESULTS)
+            .clamp(1, MAX_QUERY_RESULTS);
+        let exclusions = SearchExclusions::default();
+        let results = self
+            .db
+            .search(
+                &query.q,
+                query.content_type.clone(),
+                limit,
+                query.offset,
+                query.start_time,
+                query.end_time,
+                query.app_name.as_deref(),
+                query.window_name.as_deref(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &exclusions,
+                None,
+                SearchSort::default(),
+            )
+            .await?;
+        let total = self
+            .db
+            .count_search_results(
+                &query.q,
+                query.content_type,
+                query.start_time,
+                query.end_time,
+                query.app_name.as_deref(),
+                query.window_name.as_deref(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &exclusions,
+                None,
+            )
+            .await?;
+        Ok(json!({
+            "data": results,
+            "pagination": {"limit": limit, "offset": query.offset, "total": total},
+        }))
+    }
+}
+
+impl PipeHost for ServerPipeHost {
+    fn query(&self, params: Value) -> HostFuture<'_, Value> {
+        Box::pin(self.search(params))
+    }
+
+    fn notify(&self, pipe: String, title: String, body: String) -> HostFuture<'_, ()> {
+        Box::pin(async move {
+            debug!("[{}] notifies {}", pipe, title);
+            let summary = format!("{} ({})", title, pipe);
+            tokio::task::spawn_blocking(move || show_notification(&summary, &body)).await?
+        })
+    }
+
+    fn read(&self, request: ReadRequest) -> HostFuture<'_, Value> {
+        Box::pin(self.read_api(request))
+    }
+
+    fn api_port(&self) -> Option<u16> {
+        Some(self.port)
+    }
+}
@@ -1,5 +1,14 @@
+use crate::capture_events::as_capture_event;
+use crate::pipe_scheduler::{PipeSchedule, PipeScheduleStatus};
 use anyhow::Result;
-use screenpipe_core::{download_pipe, download_pipe_private, PipeState};
+use futures::StreamExt;
+use screenpipe_core::pipe_runtime::PIPE_API_TYPES;
+use screenpipe_core::{
+    check_updates, download_pipe, download_pipe_private, install_pipe, start_script_pipe,
+    InstalledPipe, PipeEvent, PipeHost, PipeSource, PipeState, PipeUpdate, ScriptPipe,
+    TRIGGER_PREFIX,
+};
+use screenpipe_events::subscribe_to_all_events;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -22,16 +31,23 @@ pub struct PipeInfo {
     pub is_nextjs: bool,
     pub desc: String,
     pub build_status: Option<Value>,
+    /// How the triggers of a running sandboxed pipe have run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<PipeScheduleStatus>,
 }
 
 struct PipeHandle {
-    state: PipeState,
+    /// None for pipes run in the sandbox, which have no process
+    state: Option<PipeState>,
     kill_tx: Sender<()>,
+    /// Triggers of a sandboxed pipe
+    schedule: Option<Arc<PipeSchedule>>,
 }
 
 pub struct PipeManager {
     screenpipe_dir: PathBuf,
     running_pipes: Arc<RwLock<HashMap<String, PipeHandle>>>,
+    script_host: RwLock<Option<Arc<dyn PipeHost>>>,
 }
 
 impl PipeManager {
@@ -39,9 +55,16 @@ impl PipeManager {
         PipeManager {
             screenpipe_dir,
             running_pipes: Arc::new(RwLock::new(HashMap::new())),
+            script_host: RwLock::new(None),
         }
     }
 
+    /// Sets what pipes run in the sandbox query and notify through, they fail to start
+    /// before it is set.
+    pub async fn set_script_host(&self, host: Arc<dyn PipeHost>) {
+        *self.script_host.write().await = Some(host);
+    }
+
     pub async fn update_config(&self, id: &str, new_config: Value) -> Result<()> {
         debug!("Updating config for pipe: {}", id);
         let pipe_dir = self.screenpipe_dir.join("pipes").join(id);
@@ -176,6 +199,7 @@ impl PipeManager {
                 .unwrap_or(false),
             desc: desc_pipe,
             build_status: config.get("buildStatus").cloned(),
+            schedule: None,
         }
     }
 
@@ -201,9 +225,32 @@ impl PipeManager {
             }
         }
 
+        let running_pipes = self.running_pipes.read().await;
+        for info in &mut pipe_infos {
+            info.schedule = running_pipes
+                .get(&info.id)
+                .and_then(|handle| handle.schedule.as_ref())
+                .map(|schedule| schedule.status());
+        }
         pipe_infos
     }
 
+    /// Installs the pipe of `source`, looking names up in the registry at `registry`. An
+    /// update asking for more permissions only gets them with `accept_permissions`.
+    pub async fn install_pipe(
+        &self,
+        source: &PipeSource,
+        registry: &str,
+        accept_permissions: bool,
+    ) -> Result<InstalledPipe> {
+        install_pipe(source, &self.screenpipe_dir, registry, accept_permissions).await
+    }
+
+    /// Checks the pipes installed from git or the registry for newer versions.
+    pub async fn check_updates(&self, registry: &str) -> Result<Vec<PipeUpdate>> {
+        check_updates(&self.screenpipe_dir, registry).await
+    }
+
     pub async fn download_pipe(&self, url: &str) -> Result<String> {
         // Remove any surrounding quotes and normalize backslashes
         let normalized_url = url.trim_matches('"').replace("\\", "/");
@@ -391,7 +438,7 @@ impl PipeManager {
             }
 
             match handle.state {
-                PipeState::Port(port) => {
+                Some(PipeState::Port(port)) => {
                     tokio::task::spawn(async move {
                         // killport doesn't seems working
                         #[cfg(unix)]
@@ -484,7 +531,7 @@ impl PipeManager {
                     .await
                     .map_err(|e| anyhow::anyhow!("Failed to kill port: {}", e))?;
                 }
-                PipeState::Pid(pid) => {
+                Some(PipeState::Pid(pid)) => {
                     // Force kill the process if it's still running
                     #[cfg(unix)]
                     {
@@ -506,6 +553,8 @@ impl PipeManager {
                         }
                     }
                 }
+                // the sandbox stops on the kill signal
+                None => {}
             }
 
             // Clean up cron jobs
@@ -520,8 +569,21 @@ impl PipeManager {
         let screenpipe_dir = self.screenpipe_dir.clone();
         let running_pipes = self.running_pipes.clone();
         let id_for_map = id.clone();
+        let script_host = self.script_host.read().await.clone();
 
         Ok(async move {
+            let pipe_dir = screenpipe_dir.join("pipes").join(&id);
+            match ScriptPipe::load(&id, &pipe_dir).await {
+                Ok(Some(pipe)) => {
+                    return run_script_pipe(pipe, script_host, running_pipes, &pipe_dir).await;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("[{}] failed to load pipe: {}", id, e);
+                    return Err(e);
+                }
+            }
+
             match screenpipe_core::run_pipe(&id, screenpipe_dir.clone()).await {
                 Ok((mut child, pipe_state)) => {
                     let (kill_tx, mut kill_rx) = mpsc::channel::<()>(1);
@@ -529,8 +591,9 @@ impl PipeManager {
                     running_pipes.write().await.insert(
                         id_for_map.clone(),
                         PipeHandle {
-                            state: pipe_state,
+                            state: Some(pipe_state),
                             kill_tx: kill_tx.clone(),
+                            schedule: None,
                         },
                     );
 
@@ -926,6 +989,88 @@ impl PipeManager {
     }
 }
 
+/// Runs `pipe` in the sandbox until it stops or gets the kill signal, passing it the events
+/// it handles and firing its triggers.
+async fn run_script_pipe(
+    pipe: ScriptPipe,
+    host: Option<Arc<dyn PipeHost>>,
+    running_pipes: Arc<RwLock<HashMap<String, PipeHandle>>>,
+    pipe_dir: &Path,
+) -> Result<()> {
+    let id = pipe.id.clone();
+    let host = host
+        .ok_or_else(|| anyhow::anyhow!("pipe {} runs in the sandbox, which isn't ready yet", id))?;
+    // lets editors check the script against the api it runs with
+    if let Err(e) = tokio::fs::write(pipe_dir.join("screenpipe.d.ts"), PIPE_API_TYPES).await {
+        debug!("[{}] failed to write screenpipe.d.ts: {}", id, e);
+    }
+
+    let schedule = PipeSchedule::from_config(&id, &pipe.config, &pipe.permissions)?.map(Arc::new);
+
+    let handle = Arc::new(start_script_pipe(pipe, host).await.map_err(|e| {
+        error!("[{}] failed to start pipe {}:", id, e);
+        e
+    })?);
+    if let Some(schedule) = &schedule {
+        for trigger in schedule.triggers() {
+            if !handle.wants(&format!("{}{}", TRIGGER_PREFIX, trigger.name)) {
+                warn!(
+                    "[{}] has no pipe.onTrigger handler for trigger {}",
+                    id, trigger.name
+                );
+            }
+        }
+    }
+    let (kill_tx, mut kill_rx) = mpsc::channel::<()>(1);
+    running_pipes.write().await.insert(
+        id.clone(),
+        PipeHandle {
+            state: None,
+            kill_tx,
+            schedule: schedule.clone(),
+        },
+    );
+    info!("started pipe: {} in the sandbox", id);
+
+    let crons = schedule
+        .as_ref()
+        .map(|schedule| schedule.spawn_crons(&handle))
+        .unwrap_or_default();
+    let mut events = subscribe_to_all_events();
+    loop {
+        tokio::select! {
+            _ = handle.wait() => break,
+            _ = kill_rx.recv() => {
+                handle.stop();
+                handle.wait().await;
+                break;
+            }
+            Some(event) = events.next() => {
+                if let Some(schedule) = &schedule {
+                    if let Some(capture) = as_capture_event(&event) {
+                        for trigger in schedule.matching(&capture) {
+                            tokio::spawn(schedule.clone().fire(
+                                handle.clone(),
+                                trigger.to_string(),
+                                Some(capture.clone()),
+                            ));
+                        }
+                    }
+                }
+                handle.send(PipeEvent {
+                    name: event.name,
+                    data: event.data,
+                });
+            }
+        }
+    }
+    for cron in crons {
+        cron.abort();
+    }
+    running_pipes.write().await.remove(&id);
+    Ok(())
+}
+
 // Helper function to recursively copy directories
 async fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<()> {
     let src = src.as_ref();
@@ -0,0 +1,375 @@
+//! Triggers of pipes run in the sandbox: cron schedules and capture events, each run by
+//! the `pipe.onTrigger` handler of its name. Capture triggers hand the pipe what was
+//! captured, so they need the permissions reading it would.
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use screenpipe_core::pipe_runtime::QUERY_ENDPOINT;
+use screenpipe_core::{PipeEvent, PipePermissions, ScriptPipeHandle, TRIGGER_PREFIX};
+use screenpipe_events::{CaptureEvent, CAPTURE_EVENT};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const DEFAULT_MAX_CONCURRENT_RUNS: usize = 1;
+// Runs kept for GET /pipes
+const HISTORY_SIZE: usize = 50;
+const MIN_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// When a trigger fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "on", rename_all = "snake_case")]
+pub enum PipeTrigger {
+    /// Cron expression with seconds in local time, e.g. `0 */15 * * * *`
+    Cron { schedule: String },
+    /// Every stored transcription
+    Transcript,
+    /// OCR text or transcriptions containing one of `keywords`, ignoring case
+    Keyword { keywords: Vec<String> },
+    /// Focus changes to an app whose name contains one of `apps`, ignoring case. Any app
+    /// when empty
+    AppFocus {
+        #[serde(default)]
+        apps: Vec<String>,
+    },
+}
+
+/// A trigger of the `triggers` of a pipe.json, e.g.
+/// `{"name": "standup", "on": "keyword", "keywords": ["standup"]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub trigger: PipeTrigger,
+}
+
+impl TriggerConfig {
+    pub fn matches(&self, event: &CaptureEvent) -> bool {
+        match (&self.trigger, event) {
+            (PipeTrigger::Transcript, CaptureEvent::Transcript(_)) => true,
+            (PipeTrigger::Keyword { keywords }, CaptureEvent::Ocr(_))
+            | (PipeTrigger::Keyword { keywords }, CaptureEvent::Transcript(_)) => {
+                let text = event.text().to_lowercase();
+                keywords
+                    .iter()
+                    .any(|keyword| text.contains(&keyword.to_lowercase()))
+            }
+            (PipeTrigger::AppFocus { apps }, CaptureEvent::AppFocus(focus)) => {
+                let app_name = focus.app_name.to_lowercase();
+                apps.is_empty()
+                    || apps
+                        .iter()
+                        .any(|app| app_name.contains(&app.to_lowercase()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `permissions` let the pipe see what fires the trigger: capture events and
+    /// searching them. Cron triggers fire with nothing.
+    pub fn allowed_by(&self, permissions: &PipePermissions) -> bool {
+        match self.trigger {
+            PipeTrigger::Cron { .. } => true,
+            PipeTrigger::Transcript
+            | PipeTrigger::Keyword { .. }
+            | PipeTrigger::AppFocus { .. } => {
+                permissions.allows_event(CAPTURE_EVENT)
+                    && permissions.allows_endpoint(QUERY_ENDPOINT)
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("triggers need a name"));
+        }
+        match &self.trigger {
+            PipeTrigger::Cron { schedule } => {
+                cron::Schedule::from_str(schedule)
+                    .map_err(|e| anyhow!("trigger {}: invalid schedule: {}", self.name, e))?;
+            }
+            PipeTrigger::Keyword { keywords } => {
+                if keywords.iter().all(|keyword| keyword.trim().is_empty()) {
+                    return Err(anyhow!("trigger {} has no keywords", self.name));
+                }
+            }
+            PipeTrigger::Transcript | PipeTrigger::AppFocus { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduleConfig {
+    #[serde(default)]
+    triggers: Vec<TriggerConfig>,
+    #[serde(default = "default_max_concurrent_runs")]
+    max_concurrent_runs: usize,
+}
+
+fn default_max_concurrent_runs() -> usize {
+    DEFAULT_MAX_CONCURRENT_RUNS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+/// A run of a trigger handler.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipeRun {
+    pub trigger: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub status: RunStatus,
+    pub error: Option<String>,
+}
+
+/// How a trigger backs off after failed runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TriggerBackoff {
+    pub consecutive_failures: u32,
+    /// The trigger is skipped until then
+    pub backoff_until: Option<DateTime<Utc>>,
+}
+
+/// How the triggers of a pipe have run, as `GET /pipes` returns it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipeScheduleStatus {
+    pub triggers: Vec<TriggerConfig>,
+    pub max_concurrent_runs: usize,
+    pub running: usize,
+    /// Triggers backing off after failed runs, by name
+    pub backoffs: BTreeMap<String, TriggerBackoff>,
+    /// Triggers skipped as `max_concurrent_runs` handlers were running
+    pub skipped_busy: u64,
+    /// Triggers skipped during a backoff
+    pub skipped_backoff: u64,
+    /// Last runs, newest first
+    pub history: Vec<PipeRun>,
+}
+
+#[derive(Default)]
+struct RunState {
+    history: VecDeque<PipeRun>,
+    // each trigger backs off on its own, a failing cron doesn't mute the others
+    backoffs: HashMap<String, TriggerBackoff>,
+    skipped_busy: u64,
+    skipped_backoff: u64,
+}
+
+/// How long triggers are skipped after `failures` failed runs in a row, doubling from 30
+/// seconds up to an hour.
+pub fn backoff(failures: u32) -> Duration {
+    if failures == 0 {
+        return Duration::ZERO;
+    }
+    let factor = 2u32.saturating_pow(failures - 1);
+    MIN_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// The triggers of a pipe and their runs.
+pub struct PipeSchedule {
+    pipe: String,
+    triggers: Vec<TriggerConfig>,
+    max_concurrent_runs: usize,
+    slots: Semaphore,
+    state: Mutex<RunState>,
+}
+
+impl PipeSchedule {
+    /// The schedule of the pipe.json `config`, none when it has no triggers. Triggers
+    /// `permissions` don't allow are left out.
+    pub fn from_config(
+        pipe: &str,
+        config: &Value,
+        permissions: &PipePermissions,
+    ) -> Result<Option<Self>> {
+        let mut config: ScheduleConfig = serde_json::from_value(config.clone())
+            .map_err(|e| anyhow!("invalid triggers of pipe {}: {}", pipe, e))?;
+        config.triggers.retain(|trigger| {
+            let allowed = trigger.allowed_by(permissions);
+            if !allowed {
+                warn!(
+                    "[{}] trigger {} needs the {} event and {} endpoint permissions to fire",
+                    pipe, trigger.name, CAPTURE_EVENT, QUERY_ENDPOINT
+                );
+            }
+            allowed
+        });
+        if config.triggers.is_empty() {
+            return Ok(None);
+        }
+        if config.max_concurrent_runs == 0 {
+            return Err(anyhow!(
+                "max_concurrent_runs of pipe {} must be at least 1",
+                pipe
+            ));
+        }
+        let mut names = HashSet::new();
+        for trigger in &config.triggers {
+            trigger.validate()?;
+            if !names.insert(trigger.name.as_str()) {
+                return Err(anyhow!("pipe {} has two {} triggers", pipe, trigger.name));
+            }
+        }
+        Ok(Some(Self {
+            pipe: pipe.to_string(),
+            triggers: config.triggers,
+            max_concurrent_runs: config.max_concurrent_runs,
+            slots: Semaphore::new(config.max_concurrent_runs),
+            state: Mutex::new(RunState::default()),
+        }))
+    }
+
+    pub fn triggers(&self) -> &[TriggerConfig] {
+        &self.triggers
+    }
+
+    /// Names of the triggers `event` fires.
+    pub fn matching(&self, event: &CaptureEvent) -> Vec<&str> {
+        self.triggers
+            .iter()
+            .filter(|trigger| trigger.matches(event))
+            .map(|trigger| trigger.name.as_str())
+            .collect()
+    }
+
+    /// Runs `run` for `trigger` unless `max_concurrent_runs` runs are going on or the
+    /// trigger backs off, then records how it went. None when it was skipped.
+    pub async fn run(
+        &self,
+        trigger: &str,
+        run: impl Future<Output = Result<()>>,
+    ) -> Option<RunStatus> {
+        let started_at = Utc::now();
+        let _slot = {
+            let mut state = self.state.lock().unwrap();
+            let backing_off = state
+                .backoffs
+                .get(trigger)
+                .and_then(|backoff| backoff.backoff_until)
+                .is_some_and(|until| started_at < until);
+            if backing_off {
+                state.skipped_backoff += 1;
+                debug!("[{}] backing off, skipped trigger {}", self.pipe, trigger);
+                return None;
+            }
+            let Ok(slot) = self.slots.try_acquire() else {
+                state.skipped_busy += 1;
+                debug!("[{}] busy, skipped trigger {}", self.pipe, trigger);
+                return None;
+            };
+            slot
+        };
+
+        let result = run.await;
+        let finished_at = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        let (status, error) = match result {
+            Ok(()) => {
+                state.backoffs.remove(trigger);
+                (RunStatus::Succeeded, None)
+            }
+            Err(e) => {
+                let trigger_backoff = state.backoffs.entry(trigger.to_string()).or_default();
+                trigger_backoff.consecutive_failures += 1;
+                let wait = backoff(trigger_backoff.consecutive_failures);
+                trigger_backoff.backoff_until = chrono::Duration::from_std(wait)
+                    .ok()
+                    .map(|wait| finished_at + wait);
+                warn!(
+                    "[{}] trigger {} failed, skipping it for {:?}: {}",
+                    self.pipe, trigger, wait, e
+                );
+                (RunStatus::Failed, Some(e.to_string()))
+            }
+        };
+        state.history.push_front(PipeRun {
+            trigger: trigger.to_string(),
+            started_at,
+            finished_at,
+            status,
+            error,
+        });
+        state.history.truncate(HISTORY_SIZE);
+        Some(status)
+    }
+
+    /// Runs the handler of `trigger` in the pipe of `handle`, with what fired it.
+    pub async fn fire(
+        self: Arc<Self>,
+        handle: Arc<ScriptPipeHandle>,
+        trigger: String,
+        event: Option<CaptureEvent>,
+    ) {
+        let data = json!({
+            "trigger": trigger,
+            "fired_at": Utc::now(),
+            "event": event,
+        });
+        let run = handle.run(PipeEvent {
+            name: format!("{}{}", TRIGGER_PREFIX, trigger),
+            data,
+        });
+        self.run(&trigger, run).await;
+    }
+
+    /// Fires the cron triggers on `handle`, until the tasks are aborted.
+    pub fn spawn_crons(self: &Arc<Self>, handle: &Arc<ScriptPipeHandle>) -> Vec<JoinHandle<()>> {
+        self.triggers
+            .iter()
+            .filter_map(|trigger| match &trigger.trigger {
+                PipeTrigger::Cron { schedule } => Some((
+                    trigger.name.clone(),
+                    cron::Schedule::from_str(schedule).ok()?,
+                )),
+                _ => None,
+            })
+            .map(|(name, schedule)| {
+                let pipe_schedule = self.clone();
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    info!("[{}] trigger {} scheduled", pipe_schedule.pipe, name);
+                    while let Some(next) = schedule.upcoming(Local).next() {
+                        let wait = (next - Local::now()).to_std().unwrap_or_default();
+                        tokio::time::sleep(wait).await;
+                        // long runs don't delay the next ones, which get skipped instead
+                        tokio::spawn(pipe_schedule.clone().fire(
+                            handle.clone(),
+                            name.clone(),
+                            None,
+                        ));
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub fn status(&self) -> PipeScheduleStatus {
+        let state = self.state.lock().unwrap();
+        PipeScheduleStatus {
+            triggers: self.triggers.clone(),
+            max_concurrent_runs: self.max_concurrent_runs,
+            running: self.max_concurrent_runs - self.slots.available_permits(),
+            backoffs: state
+                .backoffs
+                .iter()
+                .map(|(trigger, backoff)| (trigger.clone(), backoff.clone()))
+                .collect(),
+            skipped_busy: state.skipped_busy,
+            skipped_backoff: state.skipped_backoff,
+            history: state.history.iter().cloned().collect(),
+        }
+    }
+}
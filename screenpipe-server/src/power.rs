@@ -0,0 +1,317 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_pause::{pause_capture, resume_capture};
+use screenpipe_vision::ocr_provider::OcrProvider;
+use screenpipe_vision::throttle::{set_capture_throttle, CaptureThrottle};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use sysinfo::{ComponentExt, CpuExt, System, SystemExt};
+use tracing::{debug, info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const PAUSE_REASON: &str = "power";
+// Throttling for heat ends a little below the thresholds, or it would flap around them
+const TEMPERATURE_MARGIN: f32 = 5.0;
+const CPU_USAGE_MARGIN: f32 = 15.0;
+// Sensors reporting the temperature of the cpu, matched lowercase
+const CPU_SENSORS: [&str; 5] = ["cpu", "core", "package", "tctl", "tdie"];
+
+// Set once a failure to read the battery was warned about, it fails the same way every poll
+static BATTERY_FAILING: AtomicBool = AtomicBool::new(false);
+
+/// How much capture is held back, each level doing what the one before does and more.
+#[derive(
+    OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleLevel {
+    #[default]
+    None,
+    /// Capture at a lower rate
+    ReduceFps,
+    /// Capture at a lower rate and read frames with the cheaper OCR engine
+    CheapOcr,
+    /// No screen capture nor OCR, audio keeps recording
+    Pause,
+}
+
+/// Why capture is throttled.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ThrottleReason {
+    OnBattery,
+    LowBattery { percent: f32 },
+    CpuTemperature { celsius: f32 },
+    CpuUsage { percent: f32 },
+}
+
+impl ThrottleReason {
+    fn is_heat(&self) -> bool {
+        matches!(
+            self,
+            ThrottleReason::CpuTemperature { .. } | ThrottleReason::CpuUsage { .. }
+        )
+    }
+}
+
+impl fmt::Display for ThrottleReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThrottleReason::OnBattery => write!(f, "on battery"),
+            ThrottleReason::LowBattery { percent } => write!(f, "battery at {:.0}%", percent),
+            ThrottleReason::CpuTemperature { celsius } => write!(f, "cpu at {:.0}°C", celsius),
+            ThrottleReason::CpuUsage { percent } => write!(f, "cpu usage at {:.0}%", percent),
+        }
+    }
+}
+
+/// What the power state of the computer is, the fields the platform can't tell left out.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PowerReading {
+    /// False on computers without a battery
+    pub on_battery: Option<bool>,
+    pub battery_percent: Option<f32>,
+    /// Usage of all cores, 0 to 100
+    pub cpu_usage: Option<f32>,
+    /// Hottest cpu sensor, in degrees celsius
+    pub cpu_temperature: Option<f32>,
+}
+
+/// When capture is throttled and how much.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerPolicy {
+    pub on_battery: ThrottleLevel,
+    pub low_battery: ThrottleLevel,
+    /// Battery percentage at or below which `low_battery` applies
+    pub low_battery_percent: f32,
+    pub on_heat: ThrottleLevel,
+    pub max_cpu_temperature: f32,
+    pub max_cpu_usage: f32,
+    /// Captures wait this many times longer while the fps is reduced
+    pub fps_divisor: f64,
+}
+
+impl PowerPolicy {
+    /// The level `reading` calls for and why, the most any condition asks for. `hot`
+    /// tells the computer was throttled for heat already, it stays so until it cools
+    /// down a little below the thresholds.
+    pub fn level(&self, reading: &PowerReading, hot: bool) -> (ThrottleLevel, Vec<ThrottleReason>) {
+        let mut conditions = Vec::new();
+        if reading.on_battery == Some(true) {
+            conditions.push((self.on_battery, ThrottleReason::OnBattery));
+            if let Some(percent) = reading
+                .battery_percent
+                .filter(|percent| *percent <= self.low_battery_percent)
+            {
+                conditions.push((self.low_battery, ThrottleReason::LowBattery { percent }));
+            }
+        }
+        let (temperature_margin, usage_margin) = if hot {
+            (TEMPERATURE_MARGIN, CPU_USAGE_MARGIN)
+        } else {
+            (0.0, 0.0)
+        };
+        if let Some(celsius) = reading
+            .cpu_temperature
+            .filter(|celsius| *celsius >= self.max_cpu_temperature - temperature_margin)
+        {
+            conditions.push((self.on_heat, ThrottleReason::CpuTemperature { celsius }));
+        }
+        if let Some(percent) = reading
+            .cpu_usage
+            .filter(|percent| *percent >= self.max_cpu_usage - usage_margin)
+        {
+            conditions.push((self.on_heat, ThrottleReason::CpuUsage { percent }));
+        }
+
+        conditions.retain(|(level, _)| *level > ThrottleLevel::None);
+        let level = conditions
+            .iter()
+            .map(|(level, _)| *level)
+            .max()
+            .unwrap_or_default();
+        let reasons = conditions.into_iter().map(|(_, reason)| reason).collect();
+        (level, reasons)
+    }
+}
+
+/// The current throttle, served at /status.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ThrottleState {
+    pub level: ThrottleLevel,
+    /// Empty while not throttled
+    pub reasons: Vec<ThrottleReason>,
+    /// Since when capture has been held back at this level
+    pub since: Option<DateTime<Utc>>,
+    pub power: PowerReading,
+}
+
+/// Holds capture back as the power policy asks, as the battery drains or the cpu heats up.
+pub struct PowerThrottle {
+    db: Arc<DatabaseManager>,
+    policy: PowerPolicy,
+    cheap_ocr: Option<Arc<dyn OcrProvider>>,
+    state: RwLock<ThrottleState>,
+    // id of the stored pause while paused
+    pause_id: RwLock<Option<i64>>,
+}
+
+impl PowerThrottle {
+    /// `cheap_ocr` reads frames at the `CheapOcr` level, without one that level only
+    /// reduces the fps.
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        policy: PowerPolicy,
+        cheap_ocr: Option<Arc<dyn OcrProvider>>,
+    ) -> Self {
+        Self {
+            db,
+            policy,
+            cheap_ocr,
+            state: RwLock::new(ThrottleState::default()),
+            pause_id: RwLock::new(None),
+        }
+    }
+
+    pub fn state(&self) -> ThrottleState {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Throttles capture for `power` as read at `now`. Returns the new state.
+    pub async fn update(&self, power: PowerReading, now: DateTime<Utc>) -> ThrottleState {
+        let previous = self.state();
+        let hot = previous.reasons.iter().any(ThrottleReason::is_heat);
+        let (level, reasons) = self.policy.level(&power, hot);
+        let since = if level == previous.level {
+            previous.since
+        } else {
+            (level > ThrottleLevel::None).then_some(now)
+        };
+        let state = ThrottleState {
+            level,
+            reasons,
+            since,
+            power,
+        };
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = state.clone();
+
+        if level != previous.level {
+            if level == ThrottleLevel::None {
+                info!("capture no longer throttled");
+            } else {
+                let reasons: Vec<String> = state.reasons.iter().map(|r| r.to_string()).collect();
+                info!("throttling capture to {:?}: {}", level, reasons.join(", "));
+            }
+            self.apply(level, now).await;
+        }
+        state
+    }
+
+    async fn apply(&self, level: ThrottleLevel, now: DateTime<Utc>) {
+        let throttle = match level {
+            ThrottleLevel::None | ThrottleLevel::Pause => None,
+            ThrottleLevel::ReduceFps => Some(CaptureThrottle {
+                interval_factor: self.policy.fps_divisor,
+                ocr_provider: None,
+            }),
+            ThrottleLevel::CheapOcr => Some(CaptureThrottle {
+                interval_factor: self.policy.fps_divisor,
+                ocr_provider: self.cheap_ocr.clone(),
+            }),
+        };
+        set_capture_throttle(throttle);
+
+        let pause_id = *self.pause_id.read().unwrap_or_else(|e| e.into_inner());
+        match (level == ThrottleLevel::Pause, pause_id) {
+            (true, None) => {
+                pause_capture(PAUSE_REASON);
+                match self.db.start_capture_pause(PAUSE_REASON, now).await {
+                    Ok(id) => *self.pause_id.write().unwrap_or_else(|e| e.into_inner()) = Some(id),
+                    Err(e) => warn!("failed to store capture pause: {}", e),
+                }
+            }
+            (false, Some(id)) => {
+                resume_capture(PAUSE_REASON);
+                *self.pause_id.write().unwrap_or_else(|e| e.into_inner()) = None;
+                if let Err(e) = self.db.end_capture_pause(id, now).await {
+                    warn!("failed to store capture pause: {}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the computer runs on battery and how charged it is. Not on battery, with no
+/// percentage, without one.
+fn read_battery() -> (Option<bool>, Option<f32>) {
+    let batteries = match battery::Manager::new().and_then(|manager| manager.batteries()) {
+        Ok(batteries) => {
+            BATTERY_FAILING.store(false, Ordering::Relaxed);
+            batteries.flatten().collect::<Vec<_>>()
+        }
+        Err(e) => {
+            if BATTERY_FAILING.swap(true, Ordering::Relaxed) {
+                debug!("failed to read battery state: {}", e);
+            } else {
+                warn!("failed to read battery state: {}", e);
+            }
+            return (None, None);
+        }
+    };
+    if batteries.is_empty() {
+        return (Some(false), None);
+    }
+    let on_battery = batteries.iter().any(|battery| {
+        matches!(
+            battery.state(),
+            battery::State::Discharging | battery::State::Empty
+        )
+    });
+    let charge = batteries
+        .iter()
+        .map(|battery| battery.state_of_charge().value)
+        .sum::<f32>()
+        / batteries.len() as f32;
+    (Some(on_battery), Some(charge * 100.0))
+}
+
+/// The power state now, cpu usage being the average since the last reading.
+pub fn read_power(system: &mut System) -> PowerReading {
+    let (on_battery, battery_percent) = read_battery();
+    system.refresh_cpu();
+    system.refresh_components_list();
+    let cpu_temperature = system
+        .components()
+        .iter()
+        .filter(|component| {
+            let label = component.label().to_lowercase();
+            CPU_SENSORS.iter().any(|sensor| label.contains(sensor))
+        })
+        .map(|component| component.temperature())
+        .filter(|temperature| temperature.is_finite() && *temperature > 0.0)
+        .reduce(f32::max);
+    PowerReading {
+        on_battery,
+        battery_percent,
+        cpu_usage: Some(system.global_cpu_info().cpu_usage()),
+        cpu_temperature,
+    }
+}
+
+/// Reads the power state every 30 seconds, throttling capture as the policy asks.
+pub async fn run_power_throttle(throttle: Arc<PowerThrottle>) {
+    info!("throttling capture on battery and when the cpu runs hot");
+    let mut system = System::new();
+    // usage is measured between two refreshes
+    system.refresh_cpu();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let power = read_power(&mut system);
+        throttle.update(power, Utc::now()).await;
+    }
+}
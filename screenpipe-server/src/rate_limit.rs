@@ -0,0 +1,116 @@
+use crate::auth::VerifiedToken;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json as JsonResponse, Response},
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Body size accepted when none is configured, the one axum has by default
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 << 20;
+// Full buckets are dropped once this many clients were seen
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// The client a request is counted against: its token once auth checked it, or else its
+/// address, so made up tokens don't get budgets of their own.
+pub fn client_key(request: &Request) -> String {
+    if let Some(VerifiedToken(hash)) = request.extensions().get::<VerifiedToken>() {
+        // hashed, so the tokens aren't kept in memory
+        return format!("token:{}", &hash[..16]);
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets letting each client make `per_minute` requests a minute, in bursts of
+/// at most as many.
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute: per_minute.max(1),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn per_minute(&self) -> u32 {
+        self.per_minute
+    }
+
+    /// Counts a request of `key` at `now`. Errs with how long to wait when it made too many.
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let capacity = self.per_minute as f64;
+        let per_second = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(key) {
+            buckets.retain(|_, bucket| {
+                let idle = now.saturating_duration_since(bucket.updated).as_secs_f64();
+                bucket.tokens + idle * per_second < capacity
+            });
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Middleware answering 429 to the clients over their rate limit, with `Retry-After`.
+/// The health checks aren't counted.
+pub async fn limit_requests(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(request.uri().path(), "/health" | "/ws/health") {
+        return next.run(request).await;
+    }
+    let key = client_key(&request);
+    if let Err(retry_after) = limiter.check(&key, Instant::now()) {
+        debug!("rate limited {} on {}", key, request.uri().path());
+        let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            JsonResponse(json!({
+                "error": format!(
+                    "more than {} requests a minute, retry in {}s",
+                    limiter.per_minute(),
+                    secs
+                )
+            })),
+        )
+            .into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(secs));
+        return response;
+    }
+    next.run(request).await
+}
@@ -0,0 +1,314 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use screenpipe_db::{DatabaseManager, ExportFrame, ExportTranscription};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{self, Write};
+
+// Rows read from the database at a time
+const PAGE_SIZE: u32 = 500;
+
+/// Fields of a record, in the order they are written when none are selected.
+pub const FIELDS: &[&str] = &[
+    "type",
+    "id",
+    "timestamp",
+    "device",
+    "app_name",
+    "window_name",
+    "browser_url",
+    "focused",
+    "is_input_device",
+    "speaker_id",
+    "text",
+    "engine",
+    "file_path",
+    "offset_index",
+    "start_time",
+    "end_time",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// A header row with the field names, then one row per record
+    Csv,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// The OCR text of a frame or a transcription, with what it was recorded from. Fields
+/// the other kind has are empty.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ExportRecord {
+    /// `ocr` or `audio`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Frame or transcription id
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// Monitor or audio device
+    pub device: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+    pub is_input_device: Option<bool>,
+    pub speaker_id: Option<i64>,
+    pub text: String,
+    /// OCR or transcription engine
+    pub engine: Option<String>,
+    pub file_path: String,
+    /// Of the frame in its video chunk
+    pub offset_index: Option<i64>,
+    /// Seconds into the audio chunk
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+impl ExportRecord {
+    /// `None` for frames that haven't been read yet.
+    pub fn from_frame(frame: ExportFrame) -> Option<Self> {
+        Some(Self {
+            kind: "ocr",
+            id: frame.id,
+            timestamp: frame.timestamp,
+            device: frame.device_name,
+            app_name: frame.app_name,
+            window_name: frame.window_name,
+            browser_url: frame.browser_url,
+            focused: frame.focused,
+            is_input_device: None,
+            speaker_id: None,
+            text: frame.text?,
+            engine: frame.ocr_engine,
+            file_path: frame.file_path,
+            offset_index: Some(frame.offset_index),
+            start_time: None,
+            end_time: None,
+        })
+    }
+
+    pub fn from_transcription(transcription: ExportTranscription) -> Self {
+        Self {
+            kind: "audio",
+            id: transcription.id,
+            timestamp: transcription.timestamp,
+            device: transcription.device,
+            app_name: None,
+            window_name: None,
+            browser_url: None,
+            focused: None,
+            is_input_device: Some(transcription.is_input_device),
+            speaker_id: transcription.speaker_id,
+            text: transcription.transcription,
+            engine: Some(transcription.transcription_engine),
+            file_path: transcription.file_path,
+            offset_index: None,
+            start_time: transcription.start_time,
+            end_time: transcription.end_time,
+        }
+    }
+}
+
+/// Comma separated field names, every field when `None`.
+pub fn parse_fields(fields: Option<&str>) -> Result<Vec<&'static str>> {
+    let Some(fields) = fields else {
+        return Ok(FIELDS.to_vec());
+    };
+    let mut selected = Vec::new();
+    for name in fields
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let field = FIELDS
+            .iter()
+            .find(|field| **field == name)
+            .ok_or_else(|| anyhow!("unknown field '{}', expected {}", name, FIELDS.join(", ")))?;
+        if !selected.contains(field) {
+            selected.push(*field);
+        }
+    }
+    if selected.is_empty() {
+        return Err(anyhow!("no fields selected"));
+    }
+    Ok(selected)
+}
+
+enum Output<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buf),
+            Output::Gzip(out) => out.write(buf),
+            Output::Zstd(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            Output::Gzip(out) => out.flush(),
+            Output::Zstd(out) => out.flush(),
+        }
+    }
+}
+
+/// Writes records as JSON lines or CSV, compressed or not. `finish` must be called once
+/// the last record is written.
+pub struct RecordWriter<W: Write> {
+    out: Output<W>,
+    format: RecordFormat,
+    fields: Vec<&'static str>,
+    wrote_header: bool,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(
+        out: W,
+        format: RecordFormat,
+        fields: Vec<&'static str>,
+        compression: Option<Compression>,
+    ) -> Result<Self> {
+        let out = match compression {
+            None => Output::Plain(out),
+            Some(Compression::Gzip) => Output::Gzip(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::default(),
+            )),
+            Some(Compression::Zstd) => Output::Zstd(zstd::Encoder::new(out, 0)?),
+        };
+        Ok(Self {
+            out,
+            format,
+            fields,
+            wrote_header: false,
+        })
+    }
+
+    pub fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        let Value::Object(mut values) = serde_json::to_value(record)? else {
+            return Err(anyhow!("records serialize to objects"));
+        };
+        match self.format {
+            RecordFormat::Jsonl => {
+                // written by hand to keep the fields in the selected order
+                let mut line = String::from("{");
+                for (i, field) in self.fields.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    let value = values.remove(*field).unwrap_or_default();
+                    line.push_str(&format!("\"{}\":{}", field, value));
+                }
+                line.push_str("}\n");
+                self.out.write_all(line.as_bytes())?;
+            }
+            RecordFormat::Csv => {
+                if !self.wrote_header {
+                    self.write_csv_header()?;
+                }
+                let row: Vec<String> = self
+                    .fields
+                    .iter()
+                    .map(|field| match values.remove(*field).unwrap_or_default() {
+                        Value::Null => String::new(),
+                        Value::String(value) => value,
+                        value => value.to_string(),
+                    })
+                    .collect();
+                self.write_csv_row(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_csv_header(&mut self) -> Result<()> {
+        let header: Vec<String> = self.fields.iter().map(|field| field.to_string()).collect();
+        self.write_csv_row(&header)?;
+        self.wrote_header = true;
+        Ok(())
+    }
+
+    fn write_csv_row(&mut self, row: &[String]) -> Result<()> {
+        let row: Vec<String> = row.iter().map(|value| csv_field(value)).collect();
+        self.out.write_all(row.join(",").as_bytes())?;
+        self.out.write_all(b"\r\n")?;
+        Ok(())
+    }
+
+    /// Writes the header of an empty CSV export and ends the compressed stream.
+    pub fn finish(mut self) -> Result<W> {
+        if self.format == RecordFormat::Csv && !self.wrote_header {
+            self.write_csv_header()?;
+        }
+        let mut out = match self.out {
+            Output::Plain(out) => out,
+            Output::Gzip(out) => out.finish()?,
+            Output::Zstd(out) => out.finish()?,
+        };
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+/// Quoted when it holds a comma, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes the OCR text of the frames read in `start..end`, then the transcriptions.
+/// Returns how many records were written.
+pub async fn export_records<W: Write>(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    writer: &mut RecordWriter<W>,
+) -> Result<u64> {
+    let mut count = 0;
+    let mut after_id = 0;
+    loop {
+        let page = db
+            .get_export_frames(start, end, after_id, PAGE_SIZE)
+            .await?;
+        let Some(last) = page.last() else { break };
+        after_id = last.id;
+        for record in page
+            .into_iter()
+            .filter(|frame| frame.timestamp < end)
+            .filter_map(ExportRecord::from_frame)
+        {
+            writer.write(&record)?;
+            count += 1;
+        }
+    }
+
+    let mut after_id = 0;
+    loop {
+        let page = db
+            .get_export_transcriptions(start, end, after_id, PAGE_SIZE)
+            .await?;
+        let Some(last) = page.last() else { break };
+        after_id = last.id;
+        for transcription in page.into_iter().filter(|t| t.timestamp < end) {
+            writer.write(&ExportRecord::from_transcription(transcription))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
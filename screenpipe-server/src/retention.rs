@@ -0,0 +1,346 @@
+use crate::cold_storage::ColdStorage;
+use crate::frame_storage::FrameBlobStore;
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, MediaFile, MediaKind};
+use serde::Serialize;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+// Files looked up and deleted per query while purging
+const PURGE_BATCH: i64 = 500;
+// How often the background task enforces the policy
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long a data type is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPeriod {
+    Days(u32),
+    Forever,
+}
+
+impl RetentionPeriod {
+    /// Data recorded before the returned time is past the period.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            RetentionPeriod::Days(days) => Some(now - ChronoDuration::days(*days as i64)),
+            RetentionPeriod::Forever => None,
+        }
+    }
+
+    pub fn days(&self) -> Option<u32> {
+        match self {
+            RetentionPeriod::Days(days) => Some(*days),
+            RetentionPeriod::Forever => None,
+        }
+    }
+}
+
+impl FromStr for RetentionPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("forever") {
+            return Ok(RetentionPeriod::Forever);
+        }
+        match s.trim_end_matches('d').parse::<u32>() {
+            Ok(days) if days > 0 => Ok(RetentionPeriod::Days(days)),
+            _ => Err(format!(
+                "invalid retention period '{}', expected a number of days or 'forever'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RetentionPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionPeriod::Days(days) => write!(f, "{}d", days),
+            RetentionPeriod::Forever => write!(f, "forever"),
+        }
+    }
+}
+
+/// How long each kind of data is kept. Deleting screenshots or audio keeps the text
+/// recorded from them, deleting text keeps the files it was recorded from. Pinned frames
+/// keep their files and text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Stored frames and screen recordings
+    pub screenshots: RetentionPeriod,
+    pub ocr_text: RetentionPeriod,
+    /// Recorded audio files
+    pub audio: RetentionPeriod,
+    pub transcripts: RetentionPeriod,
+    /// Copied text
+    pub clipboard: RetentionPeriod,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            screenshots: RetentionPeriod::Days(30),
+            ocr_text: RetentionPeriod::Days(365),
+            audio: RetentionPeriod::Days(7),
+            transcripts: RetentionPeriod::Forever,
+            clipboard: RetentionPeriod::Days(30),
+        }
+    }
+}
+
+impl fmt::Display for RetentionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frames {} ocr {} audio {} text {} clipboard {}",
+            self.screenshots, self.ocr_text, self.audio, self.transcripts, self.clipboard
+        )
+    }
+}
+
+/// What a rule deleted, or would delete on a dry run.
+#[derive(OaSchema, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RulePurge {
+    /// Days the data is kept, null keeps it forever
+    pub retain_days: Option<u32>,
+    /// Data recorded before this time is past the rule
+    pub before: Option<DateTime<Utc>>,
+    /// Files for media, rows for text
+    pub count: u64,
+    /// Size of the files, 0 for text
+    pub bytes: u64,
+}
+
+impl RulePurge {
+    fn new(period: RetentionPeriod, before: Option<DateTime<Utc>>) -> Self {
+        Self {
+            retain_days: period.days(),
+            before,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(OaSchema, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub screenshots: RulePurge,
+    pub ocr_text: RulePurge,
+    pub audio: RulePurge,
+    pub transcripts: RulePurge,
+    pub clipboard: RulePurge,
+}
+
+/// Enforces a retention policy on the database and the recorded files.
+pub struct Retention {
+    db: Arc<DatabaseManager>,
+    policy: RetentionPolicy,
+    blob_store: FrameBlobStore,
+    cold_storage: Option<Arc<ColdStorage>>,
+}
+
+impl Retention {
+    /// `output_path` is where chunks are recorded to, for the frame blobs image chunks share.
+    pub fn new(db: Arc<DatabaseManager>, policy: RetentionPolicy, output_path: &str) -> Self {
+        Self {
+            blob_store: FrameBlobStore::new(db.clone(), output_path),
+            db,
+            policy,
+            cold_storage: None,
+        }
+    }
+
+    /// Purged media is deleted from cold storage too.
+    pub fn with_cold_storage(mut self, cold_storage: Arc<ColdStorage>) -> Self {
+        self.cold_storage = Some(cold_storage);
+        self
+    }
+
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Deletes everything past its rule, or only reports what would be deleted when
+    /// `dry_run` is set.
+    pub async fn enforce(&self, dry_run: bool) -> Result<RetentionReport> {
+        let now = Utc::now();
+
+        let before = self.policy.screenshots.cutoff(now);
+        let mut screenshots = RulePurge::new(self.policy.screenshots, before);
+        if let Some(before) = before {
+            for kind in [MediaKind::VideoChunk, MediaKind::VideoSegment] {
+                self.purge_media(kind, before, dry_run, &mut screenshots)
+                    .await?;
+            }
+        }
+
+        let before = self.policy.audio.cutoff(now);
+        let mut audio = RulePurge::new(self.policy.audio, before);
+        if let Some(before) = before {
+            self.purge_media(MediaKind::AudioChunk, before, dry_run, &mut audio)
+                .await?;
+        }
+
+        let before = self.policy.ocr_text.cutoff(now);
+        let mut ocr_text = RulePurge::new(self.policy.ocr_text, before);
+        if let Some(before) = before {
+            ocr_text.count = if dry_run {
+                self.db.count_ocr_text_before(before).await? as u64
+            } else {
+                self.db.delete_ocr_text_before(before).await?
+            };
+        }
+
+        let before = self.policy.transcripts.cutoff(now);
+        let mut transcripts = RulePurge::new(self.policy.transcripts, before);
+        if let Some(before) = before {
+            transcripts.count = if dry_run {
+                self.db.count_transcriptions_before(before).await? as u64
+            } else {
+                self.db.delete_transcriptions_before(before).await?
+            };
+        }
+
+        let before = self.policy.clipboard.cutoff(now);
+        let mut clipboard = RulePurge::new(self.policy.clipboard, before);
+        if let Some(before) = before {
+            clipboard.count = if dry_run {
+                self.db.count_clipboard_entries_before(before).await? as u64
+            } else {
+                self.db.delete_clipboard_entries_before(before).await?
+            };
+        }
+
+        Ok(RetentionReport {
+            dry_run,
+            screenshots,
+            ocr_text,
+            audio,
+            transcripts,
+            clipboard,
+        })
+    }
+
+    async fn purge_media(
+        &self,
+        kind: MediaKind,
+        before: DateTime<Utc>,
+        dry_run: bool,
+        purge: &mut RulePurge,
+    ) -> Result<()> {
+        loop {
+            // a dry run doesn't mark anything, so it has to see every file at once
+            let limit = if dry_run { -1 } else { PURGE_BATCH };
+            let files = self.db.get_media_to_purge(kind, before, limit).await?;
+            let done = dry_run || (files.len() as i64) < PURGE_BATCH;
+
+            let mut purged = Vec::with_capacity(files.len());
+            for file in &files {
+                let bytes = disk_usage(Path::new(&file.file_path)).await;
+                if dry_run {
+                    purge.count += 1;
+                    purge.bytes += bytes;
+                    continue;
+                }
+                match self.delete(kind, file).await {
+                    Ok(()) => {
+                        purge.count += 1;
+                        purge.bytes += bytes;
+                        purged.push(file.id);
+                    }
+                    Err(e) => warn!("failed to delete {}: {}", file.file_path, e),
+                }
+            }
+
+            if !dry_run {
+                self.db.mark_media_purged(kind, &purged).await?;
+                debug!("purged {} {:?} files before {}", purged.len(), kind, before);
+                // files that failed to delete are retried on the next run
+                if purged.len() < files.len() {
+                    return Ok(());
+                }
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn delete(&self, kind: MediaKind, file: &MediaFile) -> Result<()> {
+        if let Some(cold_storage) = &self.cold_storage {
+            cold_storage.forget(&file.file_path).await?;
+        }
+        delete_media(&self.blob_store, kind, file).await
+    }
+}
+
+/// Deletes a media file from disk, image chunks with the frame blobs only they point at.
+/// The caller marks it purged.
+pub(crate) async fn delete_media(
+    blob_store: &FrameBlobStore,
+    kind: MediaKind,
+    file: &MediaFile,
+) -> Result<()> {
+    let path = Path::new(&file.file_path);
+    let deleted = if kind == MediaKind::VideoChunk && path.is_dir() {
+        blob_store.delete_chunk(&file.file_path).await.map(|_| ())
+    } else {
+        tokio::fs::remove_file(path).await.map_err(Into::into)
+    };
+    match deleted {
+        // already gone, e.g. deleted by hand
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == ErrorKind::NotFound) =>
+        {
+            Ok(())
+        }
+        deleted => deleted,
+    }
+}
+
+/// Size of a file, or of the files in an image chunk directory.
+pub async fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = tokio::fs::metadata(path).await else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    let mut total = 0;
+    if let Ok(mut entries) = tokio::fs::read_dir(path).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Ok(metadata) = entry.metadata().await {
+                total += metadata.len();
+            }
+        }
+    }
+    total
+}
+
+/// Enforces the policy once an hour.
+pub async fn run_retention(retention: Arc<Retention>) {
+    info!("enforcing retention policy: {}", retention.policy());
+    loop {
+        match retention.enforce(false).await {
+            Ok(report) => info!(
+                "retention purged {} frame files, {} audio files, {} ocr texts and {} transcripts",
+                report.screenshots.count,
+                report.audio.count,
+                report.ocr_text.count,
+                report.transcripts.count
+            ),
+            Err(e) => warn!("failed to enforce retention policy: {}", e),
+        }
+        tokio::time::sleep(RETENTION_INTERVAL).await;
+    }
+}
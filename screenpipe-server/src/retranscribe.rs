@@ -0,0 +1,405 @@
+use crate::cli::CliAudioTranscriptionEngine;
+use crate::cold_storage::ColdStorage;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use oasgen::OaSchema;
+use screenpipe_audio::audio_manager::AudioManager;
+use screenpipe_audio::core::device::{AudioDevice, DeviceType};
+use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+use screenpipe_audio::transcription::retranscribe::{load_recording, retranscribe_segment};
+use screenpipe_audio::transcription::stt_engine::SttEngine;
+use screenpipe_core::encryption::readable_media;
+use screenpipe_db::{DatabaseManager, ExportTranscription};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const DEFAULT_MODEL: &str = "large-v3";
+// Transcriptions read from the database at a time
+const PAGE_SIZE: u32 = 200;
+// A better model is slower than the one transcribing live, one pass at a time is plenty
+const MAX_RUNNING_RETRANSCRIPTIONS: usize = 1;
+/// File in the screenpipe directory the jobs are kept in
+pub const JOBS_FILE: &str = "retranscriptions.json";
+
+/// The engine `model` names, either like `--audio-transcription-engine` or without its
+/// `whisper-` prefix. `large-v3` is `whisper-large`.
+pub fn parse_model(model: &str) -> Option<AudioTranscriptionEngine> {
+    let model = model.trim().to_lowercase();
+    let whisper = match model.strip_prefix("whisper-").unwrap_or(&model) {
+        "large-v3" => "large",
+        "large-v3-quantized" => "large-quantized",
+        other => other,
+    };
+    [model.clone(), format!("whisper-{}", whisper)]
+        .iter()
+        .find_map(|name| <CliAudioTranscriptionEngine as ValueEnum>::from_str(name, true).ok())
+        .map(Into::into)
+}
+
+fn default_model() -> String {
+    DEFAULT_MODEL.to_string()
+}
+
+/// Transcribes the audio recorded in a time range again with `model`.
+#[derive(OaSchema, Deserialize, Debug, Clone)]
+pub struct RetranscribeRequest {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Like `large-v3`, `whisper-large-v3-turbo` or `deepgram`
+    #[serde(default = "default_model")]
+    pub model: String,
+}
+
+impl RetranscribeRequest {
+    /// The engine to transcribe with.
+    pub fn validate(&self) -> Result<AudioTranscriptionEngine, String> {
+        if self.end <= self.start {
+            return Err("end must be later than start".to_string());
+        }
+        parse_model(&self.model).ok_or_else(|| format!("unknown model: {}", self.model))
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetranscriptionStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+/// A re-transcription and how far along it is, kept in [`JOBS_FILE`]. Replaced transcripts
+/// are kept as versions of their transcription.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RetranscriptionJob {
+    pub id: String,
+    pub status: RetranscriptionStatus,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub engine: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub transcriptions_done: u64,
+    pub transcriptions_total: u64,
+    /// Transcriptions given a new transcript
+    pub transcriptions_replaced: u64,
+    /// Transcriptions whose audio couldn't be read or transcribed, they are left as they are
+    pub transcriptions_failed: u64,
+    /// 0 to 1
+    pub progress: f64,
+    pub error: Option<String>,
+}
+
+impl RetranscriptionJob {
+    fn new(request: &RetranscribeRequest, engine: &AudioTranscriptionEngine) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            status: RetranscriptionStatus::Running,
+            start: request.start,
+            end: request.end,
+            engine: engine.to_string(),
+            created_at: Utc::now(),
+            finished_at: None,
+            transcriptions_done: 0,
+            transcriptions_total: 0,
+            transcriptions_replaced: 0,
+            transcriptions_failed: 0,
+            progress: 0.0,
+            error: None,
+        }
+    }
+
+    fn update_progress(&mut self) {
+        self.progress = if self.transcriptions_total == 0 {
+            1.0
+        } else {
+            (self.transcriptions_done as f64 / self.transcriptions_total as f64).min(1.0)
+        };
+    }
+}
+
+/// What became of one transcription.
+enum Outcome {
+    Replaced,
+    Unchanged,
+    Failed,
+}
+
+/// The jobs kept in `path`, those running when the server stopped are failed.
+pub fn load_jobs(path: &Path) -> HashMap<String, RetranscriptionJob> {
+    let jobs = match std::fs::read_to_string(path) {
+        Ok(jobs) => jobs,
+        Err(_) => return HashMap::new(),
+    };
+    let jobs: Vec<RetranscriptionJob> = match serde_json::from_str(&jobs) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            warn!("ignoring unreadable {}: {}", path.display(), e);
+            return HashMap::new();
+        }
+    };
+    jobs.into_iter()
+        .map(|mut job| {
+            if job.status == RetranscriptionStatus::Running {
+                job.status = RetranscriptionStatus::Failed;
+                job.error = Some("interrupted by a restart, start it again".to_string());
+            }
+            (job.id.clone(), job)
+        })
+        .collect()
+}
+
+pub struct Retranscriptions {
+    db: Arc<DatabaseManager>,
+    audio_manager: Arc<AudioManager>,
+    cold_storage: Option<Arc<ColdStorage>>,
+    jobs_path: PathBuf,
+    jobs: Mutex<HashMap<String, RetranscriptionJob>>,
+}
+
+impl Retranscriptions {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        audio_manager: Arc<AudioManager>,
+        cold_storage: Option<Arc<ColdStorage>>,
+        screenpipe_dir: &Path,
+    ) -> Self {
+        let jobs_path = screenpipe_dir.join(JOBS_FILE);
+        Self {
+            db,
+            audio_manager,
+            cold_storage,
+            jobs: Mutex::new(load_jobs(&jobs_path)),
+            jobs_path,
+        }
+    }
+
+    fn save(&self) {
+        let jobs = self.list();
+        let saved = serde_json::to_vec(&jobs)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(&self.jobs_path, json)?));
+        if let Err(e) = saved {
+            warn!("failed to save {}: {}", self.jobs_path.display(), e);
+        }
+    }
+
+    /// Starts re-transcribing with `engine` in the background. Fails while another
+    /// re-transcription is running.
+    pub fn start(
+        self: &Arc<Self>,
+        request: RetranscribeRequest,
+        engine: AudioTranscriptionEngine,
+    ) -> Result<RetranscriptionJob, String> {
+        let job = RetranscriptionJob::new(&request, &engine);
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let running = jobs
+                .values()
+                .filter(|job| job.status == RetranscriptionStatus::Running)
+                .count();
+            if running >= MAX_RUNNING_RETRANSCRIPTIONS {
+                return Err("a re-transcription is already running".to_string());
+            }
+            jobs.insert(job.id.clone(), job.clone());
+        }
+        self.save();
+
+        let retranscriptions = self.clone();
+        let id = job.id.clone();
+        let runtime = tokio::runtime::Handle::current();
+        // whisper transcribes on the thread polling it, which mustn't be a runtime worker
+        tokio::task::spawn_blocking(move || {
+            let result = runtime.block_on(retranscriptions.retranscribe(&id, &request, &engine));
+            retranscriptions.update(&id, |job| {
+                job.finished_at = Some(Utc::now());
+                match result {
+                    Ok(()) => {
+                        job.status = RetranscriptionStatus::Done;
+                        job.progress = 1.0;
+                    }
+                    Err(e) => {
+                        warn!("re-transcription {} failed: {}", job.id, e);
+                        job.status = RetranscriptionStatus::Failed;
+                        job.error = Some(e.to_string());
+                    }
+                }
+            });
+            retranscriptions.save();
+        });
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Option<RetranscriptionJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Vec<RetranscriptionJob> {
+        let mut jobs: Vec<RetranscriptionJob> =
+            self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    fn update(&self, id: &str, update: impl FnOnce(&mut RetranscriptionJob)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            update(job);
+            if job.status == RetranscriptionStatus::Running {
+                job.update_progress();
+            }
+        }
+    }
+
+    async fn retranscribe(
+        &self,
+        id: &str,
+        request: &RetranscribeRequest,
+        engine: &AudioTranscriptionEngine,
+    ) -> Result<()> {
+        let (start, end) = (request.start, request.end);
+        let backend = self.audio_manager.load_stt_engine(engine).await?;
+        let vocabulary = self.audio_manager.vocabulary().await.terms();
+        let engine_name = engine.to_string();
+        let (_, total) = self.db.count_export_items(start, end).await?;
+        self.update(id, |job| job.transcriptions_total = total as u64);
+
+        // transcriptions of a chunk follow each other, its audio is decoded once
+        let mut recording: Option<(String, Option<Vec<f32>>)> = None;
+        let (mut replaced, mut failed) = (0, 0);
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .db
+                .get_export_transcriptions(start, end, after_id, PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.id;
+            for transcription in &page {
+                // the audio wasn't kept, e.g. with --transcript-only
+                if transcription.file_path.is_empty() {
+                    self.update(id, |job| job.transcriptions_done += 1);
+                    continue;
+                }
+                if !matches!(&recording, Some((path, _)) if *path == transcription.file_path) {
+                    let audio = match self.load_audio(&transcription.file_path).await {
+                        Ok(audio) => Some(audio),
+                        Err(e) => {
+                            warn!("{} is not re-transcribed: {}", transcription.file_path, e);
+                            None
+                        }
+                    };
+                    recording = Some((transcription.file_path.clone(), audio));
+                }
+                let audio = recording.as_ref().and_then(|(_, audio)| audio.as_deref());
+                let outcome = match audio {
+                    Some(audio) => {
+                        self.retranscribe_one(
+                            transcription,
+                            audio,
+                            backend.as_ref(),
+                            &vocabulary,
+                            &engine_name,
+                        )
+                        .await
+                    }
+                    None => Outcome::Failed,
+                };
+                match outcome {
+                    Outcome::Replaced => replaced += 1,
+                    Outcome::Unchanged => {}
+                    Outcome::Failed => failed += 1,
+                }
+                self.update(id, |job| {
+                    job.transcriptions_done += 1;
+                    job.transcriptions_replaced = replaced;
+                    job.transcriptions_failed = failed;
+                });
+            }
+            self.save();
+        }
+
+        info!(
+            "re-transcribed {} transcriptions with {}, {} failed",
+            replaced, engine_name, failed
+        );
+        Ok(())
+    }
+
+    /// Mono audio of the chunk at `file_path`, fetched from cold storage and decrypted
+    /// first when needed.
+    async fn load_audio(&self, file_path: &str) -> Result<Vec<f32>> {
+        if let Some(cold_storage) = &self.cold_storage {
+            if let Err(e) = cold_storage.ensure_local(file_path).await {
+                warn!("failed to fetch {} from cold storage: {}", file_path, e);
+            }
+        }
+        let readable = readable_media(Path::new(file_path)).await?;
+        load_recording(readable.path().to_path_buf()).await
+    }
+
+    async fn retranscribe_one(
+        &self,
+        transcription: &ExportTranscription,
+        recording: &[f32],
+        backend: &dyn SttEngine,
+        vocabulary: &[String],
+        engine_name: &str,
+    ) -> Outcome {
+        let device_type = if transcription.is_input_device {
+            DeviceType::Input
+        } else {
+            DeviceType::Output
+        };
+        let device = AudioDevice::new(transcription.device.clone(), device_type).to_string();
+        let languages = self.audio_manager.languages_of(&device).await;
+        let transcript = match retranscribe_segment(
+            backend,
+            recording,
+            transcription.start_time,
+            transcription.end_time,
+            &device,
+            &languages,
+            vocabulary,
+        )
+        .await
+        {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                warn!(
+                    "failed to re-transcribe transcription {}: {}",
+                    transcription.id, e
+                );
+                return Outcome::Failed;
+            }
+        };
+        // silence to the new model, the old transcript is more likely right than nothing
+        if transcript.text.is_empty()
+            || (transcript.text == transcription.transcription.trim()
+                && engine_name == transcription.transcription_engine)
+        {
+            return Outcome::Unchanged;
+        }
+        match self
+            .db
+            .replace_audio_transcription(
+                transcription.id,
+                &transcript.text,
+                engine_name,
+                &transcript.words,
+            )
+            .await
+        {
+            Ok(_) => Outcome::Replaced,
+            Err(e) => {
+                warn!("failed to store transcription {}: {}", transcription.id, e);
+                Outcome::Failed
+            }
+        }
+    }
+}
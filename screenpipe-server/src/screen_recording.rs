@@ -0,0 +1,459 @@
+use crate::video::spawn_ffmpeg_loggers;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_pause::is_capture_paused;
+use screenpipe_vision::capture_screenshot_by_window::{mask_regions, WindowBounds, WindowFilters};
+use screenpipe_vision::monitor::get_monitor_by_id;
+use screenpipe_vision::privacy::PrivacyPolicy;
+use screenpipe_vision::utils::capture_masked_frame;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tracing::{debug, error, info, warn};
+
+pub(crate) const MAX_RECORDING_FPS: f64 = 30.0;
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VideoCodec {
+    #[default]
+    H264,
+    Hevc,
+}
+
+impl VideoCodec {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::Hevc => "hevc",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VideoEncoder {
+    /// First hardware encoder of the platform that works, then software
+    #[default]
+    Auto,
+    /// libx264 / libx265
+    Software,
+    /// Apple VideoToolbox, macOS only
+    Videotoolbox,
+    /// NVIDIA NVENC
+    Nvenc,
+    /// Intel Quick Sync
+    Qsv,
+    /// AMD AMF, Windows only
+    Amf,
+    /// VA-API, Linux only
+    Vaapi,
+}
+
+impl VideoEncoder {
+    /// Hardware encoders tried by [`VideoEncoder::Auto`], best first.
+    pub fn hardware_candidates() -> Vec<VideoEncoder> {
+        if cfg!(target_os = "macos") {
+            vec![VideoEncoder::Videotoolbox]
+        } else if cfg!(target_os = "windows") {
+            vec![VideoEncoder::Nvenc, VideoEncoder::Qsv, VideoEncoder::Amf]
+        } else {
+            vec![VideoEncoder::Nvenc, VideoEncoder::Vaapi, VideoEncoder::Qsv]
+        }
+    }
+
+    /// Name of the ffmpeg encoder, `Auto` resolves to software.
+    pub fn ffmpeg_name(&self, codec: VideoCodec) -> &'static str {
+        match (self, codec) {
+            (VideoEncoder::Auto | VideoEncoder::Software, VideoCodec::H264) => "libx264",
+            (VideoEncoder::Auto | VideoEncoder::Software, VideoCodec::Hevc) => "libx265",
+            (VideoEncoder::Videotoolbox, VideoCodec::H264) => "h264_videotoolbox",
+            (VideoEncoder::Videotoolbox, VideoCodec::Hevc) => "hevc_videotoolbox",
+            (VideoEncoder::Nvenc, VideoCodec::H264) => "h264_nvenc",
+            (VideoEncoder::Nvenc, VideoCodec::Hevc) => "hevc_nvenc",
+            (VideoEncoder::Qsv, VideoCodec::H264) => "h264_qsv",
+            (VideoEncoder::Qsv, VideoCodec::Hevc) => "hevc_qsv",
+            (VideoEncoder::Amf, VideoCodec::H264) => "h264_amf",
+            (VideoEncoder::Amf, VideoCodec::Hevc) => "hevc_amf",
+            (VideoEncoder::Vaapi, VideoCodec::H264) => "h264_vaapi",
+            (VideoEncoder::Vaapi, VideoCodec::Hevc) => "hevc_vaapi",
+        }
+    }
+
+    /// ffmpeg arguments between the raw frame input and the output file.
+    pub fn output_args(&self, codec: VideoCodec, fps: f64) -> Vec<String> {
+        // yuv420 needs even dimensions
+        let pad = "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2";
+        let filter = match self {
+            VideoEncoder::Vaapi => format!("{},format=nv12,hwupload", pad),
+            VideoEncoder::Qsv => format!("{},format=nv12", pad),
+            _ => format!("{},format=yuv420p", pad),
+        };
+        let mut args = vec![
+            "-vf".to_string(),
+            filter,
+            "-c:v".to_string(),
+            self.ffmpeg_name(codec).to_string(),
+        ];
+
+        match self {
+            VideoEncoder::Auto | VideoEncoder::Software => {
+                let preset = match codec {
+                    VideoCodec::H264 => "veryfast",
+                    VideoCodec::Hevc => "ultrafast",
+                };
+                args.extend(["-preset", preset, "-crf", "28"].map(String::from));
+            }
+            _ => args.extend(["-b:v", "2M"].map(String::from)),
+        }
+
+        // a keyframe every two seconds keeps deep links close to the requested offset
+        let gop = (fps * 2.0).ceil().max(1.0) as u32;
+        args.extend(["-g".to_string(), gop.to_string()]);
+        if codec == VideoCodec::Hevc {
+            // lets QuickTime and Safari play hevc mp4s
+            args.extend(["-tag:v", "hvc1"].map(String::from));
+        }
+        args
+    }
+
+    fn input_args(&self) -> Vec<String> {
+        match self {
+            VideoEncoder::Vaapi => vec!["-vaapi_device".to_string(), VAAPI_DEVICE.to_string()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Continuous video recording of each monitor, next to the OCR frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRecordingConfig {
+    pub fps: f64,
+    pub segment_duration: Duration,
+    pub codec: VideoCodec,
+    pub encoder: VideoEncoder,
+}
+
+impl ScreenRecordingConfig {
+    pub fn new(
+        fps: f64,
+        segment_duration: Duration,
+        codec: VideoCodec,
+        encoder: VideoEncoder,
+    ) -> Result<Self, String> {
+        if !fps.is_finite() || fps <= 0.0 || fps > MAX_RECORDING_FPS {
+            return Err(format!(
+                "recording fps must be between 0 and {}, got {}",
+                MAX_RECORDING_FPS, fps
+            ));
+        }
+        if segment_duration.is_zero() {
+            return Err("recording segment duration must be positive".to_string());
+        }
+        Ok(Self {
+            fps,
+            segment_duration,
+            codec,
+            encoder,
+        })
+    }
+}
+
+/// Resolves `Auto` to the first hardware encoder that can encode a test frame, falling
+/// back to software. Explicit encoders are returned as is so a missing one fails loudly.
+pub async fn resolve_encoder(requested: VideoEncoder, codec: VideoCodec) -> VideoEncoder {
+    if requested != VideoEncoder::Auto {
+        return requested;
+    }
+    for candidate in VideoEncoder::hardware_candidates() {
+        if probe_encoder(candidate, codec).await {
+            return candidate;
+        }
+        debug!("{} encoder unavailable", candidate.ffmpeg_name(codec));
+    }
+    VideoEncoder::Software
+}
+
+/// Encoders can be compiled into ffmpeg without a device to run on, encode one frame.
+async fn probe_encoder(encoder: VideoEncoder, codec: VideoCodec) -> bool {
+    let Some(ffmpeg) = find_ffmpeg_path() else {
+        return false;
+    };
+    let mut args = vec![
+        "-hide_banner".to_string(),
+        "-loglevel".to_string(),
+        "error".to_string(),
+    ];
+    args.extend(encoder.input_args());
+    args.extend(
+        [
+            "-f",
+            "lavfi",
+            "-i",
+            "color=black:size=256x256:rate=1",
+            "-frames:v",
+            "1",
+        ]
+        .map(String::from),
+    );
+    args.extend(encoder.output_args(codec, 1.0));
+    args.extend(["-f", "null", "-"].map(String::from));
+
+    Command::new(ffmpeg)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+struct Segment {
+    id: i64,
+    file_path: String,
+    child: Child,
+    stdin: ChildStdin,
+    start_time: DateTime<Utc>,
+    started: Instant,
+    frames_written: u64,
+    width: u32,
+    height: u32,
+}
+
+impl Segment {
+    #[allow(clippy::too_many_arguments)]
+    async fn start(
+        db: &DatabaseManager,
+        output_path: &str,
+        device_name: &str,
+        monitor_id: u32,
+        config: &ScreenRecordingConfig,
+        encoder: VideoEncoder,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let start_time = Utc::now();
+        let file_path = PathBuf::from(output_path)
+            .join(format!(
+                "monitor_{}_recording_{}.mp4",
+                monitor_id,
+                start_time.format("%Y-%m-%d_%H-%M-%S")
+            ))
+            .to_string_lossy()
+            .into_owned();
+
+        let ffmpeg = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+        let mut args = vec![
+            "-hide_banner".to_string(),
+            "-loglevel".to_string(),
+            "error".to_string(),
+        ];
+        args.extend(encoder.input_args());
+        args.extend([
+            "-f".to_string(),
+            "rawvideo".to_string(),
+            "-pix_fmt".to_string(),
+            "rgba".to_string(),
+            "-s".to_string(),
+            format!("{}x{}", width, height),
+            "-r".to_string(),
+            config.fps.to_string(),
+            "-i".to_string(),
+            "-".to_string(),
+        ]);
+        args.extend(encoder.output_args(config.codec, config.fps));
+        // fragmented so the segment being written can already be played back
+        args.extend(
+            [
+                "-movflags",
+                "+frag_keyframe+empty_moov+default_base_moof",
+                "-y",
+            ]
+            .map(String::from),
+        );
+        args.push(file_path.clone());
+
+        let mut command = Command::new(ffmpeg);
+        command
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        debug!("recording ffmpeg command: {:?}", command);
+        let mut child = command.spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("ffmpeg stdin unavailable"))?;
+        spawn_ffmpeg_loggers(child.stderr.take(), child.stdout.take());
+
+        let id = db
+            .insert_video_segment(
+                device_name,
+                &file_path,
+                start_time,
+                config.fps,
+                config.codec.name(),
+            )
+            .await?;
+        info!("recording segment {} started: {}", id, file_path);
+
+        Ok(Self {
+            id,
+            file_path,
+            child,
+            stdin,
+            start_time,
+            started: Instant::now(),
+            frames_written: 0,
+            width,
+            height,
+        })
+    }
+
+    /// Writes the frame as often as needed for the video to keep up with the wall clock,
+    /// so an offset into the file is the time since the segment started.
+    async fn write(&mut self, rgba: &[u8], fps: f64) -> Result<()> {
+        let due = (self.started.elapsed().as_secs_f64() * fps).floor() as u64 + 1;
+        while self.frames_written < due {
+            self.stdin.write_all(rgba).await?;
+            self.frames_written += 1;
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self, db: &DatabaseManager, fps: f64) {
+        let length = Duration::from_secs_f64(self.frames_written as f64 / fps);
+        let end_time = self.start_time
+            + chrono::Duration::from_std(length).unwrap_or_else(|_| chrono::Duration::zero());
+
+        let _ = self.stdin.flush().await;
+        drop(self.stdin);
+        match self.child.wait().await {
+            Ok(status) if !status.success() => {
+                warn!("ffmpeg exited with {} for {}", status, self.file_path)
+            }
+            Err(e) => warn!("failed to wait for ffmpeg: {}", e),
+            _ => {}
+        }
+
+        if let Err(e) = db.finish_video_segment(self.id, end_time).await {
+            error!("failed to finish recording segment {}: {}", self.id, e);
+        }
+        debug!("recording segment {} finished: {}", self.id, self.file_path);
+    }
+}
+
+/// Records `monitor_id` into rolling segments until an error stops it. Frames go through the
+/// same window masking and privacy pauses as OCR capture, a pause ends the current segment.
+#[allow(clippy::too_many_arguments)]
+pub async fn record_screen(
+    db: Arc<DatabaseManager>,
+    output_path: Arc<String>,
+    monitor_id: u32,
+    config: ScreenRecordingConfig,
+    window_filters: Arc<WindowFilters>,
+    capture_unfocused_windows: bool,
+    focused_window_only: bool,
+    privacy_policy: PrivacyPolicy,
+    capture_region: Option<WindowBounds>,
+) -> Result<()> {
+    let monitor = get_monitor_by_id(monitor_id)
+        .await
+        .ok_or_else(|| anyhow!("monitor {} not found", monitor_id))?;
+    let device_name = format!("monitor_{}", monitor_id);
+    let encoder = resolve_encoder(config.encoder, config.codec).await;
+    info!(
+        "recording monitor {} with {} at {} fps",
+        monitor_id,
+        encoder.ffmpeg_name(config.codec),
+        config.fps
+    );
+
+    let interval = Duration::from_secs_f64(1.0 / config.fps);
+    let mut segment: Option<Segment> = None;
+    let result = loop {
+        let tick = Instant::now();
+        if is_capture_paused() {
+            if let Some(paused) = segment.take() {
+                debug!("pausing recording of monitor {}", monitor_id);
+                paused.finish(&db, config.fps).await;
+            }
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+        let (mut image, windows, _, _) = match capture_masked_frame(
+            &monitor,
+            &window_filters,
+            capture_unfocused_windows,
+            focused_window_only,
+            capture_region.as_ref(),
+        )
+        .await
+        {
+            Ok(capture) => capture,
+            Err(e) => break Err(e),
+        };
+
+        if privacy_policy.is_enabled() {
+            if let Some(reason) = privacy_policy.check(&windows).await {
+                if let Some(paused) = segment.take() {
+                    debug!("pausing recording of monitor {}: {}", monitor_id, reason);
+                    paused.finish(&db, config.fps).await;
+                }
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+            mask_regions(&mut image, &privacy_policy.private_regions(&windows), 1.0);
+        }
+
+        let (width, height) = (image.width(), image.height());
+        let rolled = segment.as_ref().map_or(true, |current| {
+            current.started.elapsed() >= config.segment_duration
+                || current.width != width
+                || current.height != height
+        });
+        if rolled {
+            if let Some(finished) = segment.take() {
+                finished.finish(&db, config.fps).await;
+            }
+            match Segment::start(
+                &db,
+                &output_path,
+                &device_name,
+                monitor_id,
+                &config,
+                encoder,
+                width,
+                height,
+            )
+            .await
+            {
+                Ok(started) => segment = Some(started),
+                Err(e) => break Err(e),
+            }
+        }
+
+        if let Some(current) = segment.as_mut() {
+            if let Err(e) = current.write(image.to_rgba8().as_raw(), config.fps).await {
+                break Err(e);
+            }
+        }
+
+        if let Some(remaining) = interval.checked_sub(tick.elapsed()) {
+            tokio::time::sleep(remaining).await;
+        }
+    };
+
+    if let Some(current) = segment {
+        current.finish(&db, config.fps).await;
+    }
+    result
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use screenpipe_db::{SearchExclusions, TranscriptWord};
+
+/// Filters written inline in a search query, e.g.
+/// `app:slack "deploy failed" -title:random after:2024-05-01`. Explicit query parameters
+/// take precedence over them.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQueryFilters {
+    /// What's left of the query once the filters are taken out, matched against the text
+    pub text: String,
+    pub app_name: Option<String>,
+    /// Bundle id or executable name, e.g. `app_id:com.google.Chrome`
+    pub app_id: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+    /// `tag:meeting`, tagged with it or in a time range annotated with it
+    pub tag: Option<String>,
+    /// `-app:` filters, apps to leave out
+    pub excluded_app_names: Vec<String>,
+    /// `-title:` filters, windows to leave out
+    pub excluded_window_names: Vec<String>,
+    /// `after:2024-05-01`, captured from then on
+    pub after: Option<DateTime<Utc>>,
+    /// `before:2024-05-01`, captured until then
+    pub before: Option<DateTime<Utc>>,
+}
+
+impl SearchQueryFilters {
+    pub fn parse(query: &str) -> Self {
+        let mut filters = Self::default();
+        let mut text = Vec::new();
+
+        for token in tokenize(query) {
+            let Some((key, value)) = token.split_once(':') else {
+                text.push(token);
+                continue;
+            };
+            let value = unquote(value);
+            if value.is_empty() {
+                text.push(token);
+                continue;
+            }
+            match key.to_lowercase().as_str() {
+                "-app" => filters.excluded_app_names.push(value.to_string()),
+                "-title" | "-window" => filters.excluded_window_names.push(value.to_string()),
+                "after" | "since" => match parse_time(value) {
+                    Some(time) => filters.after = Some(time),
+                    None => text.push(token),
+                },
+                "before" | "until" => match parse_time(value) {
+                    Some(time) => filters.before = Some(time),
+                    None => text.push(token),
+                },
+                "app" => filters.app_name = Some(value.to_string()),
+                "app_id" | "bundle" => filters.app_id = Some(value.to_string()),
+                "title" | "window" => filters.window_name = Some(value.to_string()),
+                "url" => filters.browser_url = Some(value.to_string()),
+                "tag" => filters.tag = Some(value.to_string()),
+                "focused" => match value.to_lowercase().as_str() {
+                    "true" | "yes" | "1" => filters.focused = Some(true),
+                    "false" | "no" | "0" => filters.focused = Some(false),
+                    _ => text.push(token),
+                },
+                _ => text.push(token),
+            }
+        }
+
+        filters.text = text.join(" ");
+        filters
+    }
+
+    pub fn exclusions(&self) -> SearchExclusions {
+        SearchExclusions {
+            app_names: self.excluded_app_names.clone(),
+            window_names: self.excluded_window_names.clone(),
+        }
+    }
+
+    /// `text` as an FTS5 query. Words and phrases must all match, `OR` between two of them
+    /// matches either, `-word` or `NOT word` leaves out text containing it and `word*`
+    /// matches prefixes. Everything else is quoted so punctuation can't break the query.
+    /// Exclusions need at least one word to match.
+    pub fn fts_query(&self) -> String {
+        let mut terms: Vec<String> = Vec::new();
+        let mut excluded = Vec::new();
+        let mut negate_next = false;
+
+        for token in tokenize(&self.text) {
+            match token {
+                "OR" | "AND" => {
+                    if terms.last().is_some_and(|term| !is_operator(term)) {
+                        terms.push(token.to_string());
+                    }
+                }
+                "NOT" => negate_next = true,
+                _ => {
+                    let (negated, token) = match token.strip_prefix('-') {
+                        Some(rest) if !rest.is_empty() => (true, rest),
+                        _ => (negate_next, token),
+                    };
+                    negate_next = false;
+                    let Some(term) = fts_term(token) else {
+                        continue;
+                    };
+                    if negated {
+                        excluded.push(term);
+                    } else {
+                        terms.push(term);
+                    }
+                }
+            }
+        }
+        while terms.last().is_some_and(|term| is_operator(term)) {
+            terms.pop();
+        }
+
+        if terms.is_empty() {
+            return String::new();
+        }
+        let query = terms.join(" ");
+        if excluded.is_empty() {
+            query
+        } else {
+            format!("({}) NOT {}", query, excluded.join(" NOT "))
+        }
+    }
+
+    /// When the first of `words` matching a word or phrase of `text` is said. Words match
+    /// the terms they start with, ignoring case and punctuation, phrases match by their
+    /// first word. Excluded terms match nothing.
+    pub fn spoken_at(&self, words: &[TranscriptWord]) -> Option<f64> {
+        let mut terms = Vec::new();
+        let mut negate_next = false;
+        for token in tokenize(&self.text) {
+            match token {
+                "OR" | "AND" => {}
+                "NOT" => negate_next = true,
+                _ => {
+                    let negated = negate_next || token.starts_with('-');
+                    negate_next = false;
+                    let first_word = unquote(token.trim_end_matches('*'))
+                        .split_whitespace()
+                        .next();
+                    if let Some(term) = first_word
+                        .map(spoken_form)
+                        .filter(|term| !negated && !term.is_empty())
+                    {
+                        terms.push(term);
+                    }
+                }
+            }
+        }
+        words
+            .iter()
+            .find(|word| {
+                let word = spoken_form(&word.word);
+                terms.iter().any(|term| word.starts_with(term.as_str()))
+            })
+            .map(|word| word.start)
+    }
+}
+
+/// `word` lowercased without its punctuation.
+fn spoken_form(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_operator(term: &str) -> bool {
+    term == "OR" || term == "AND"
+}
+
+/// A word or phrase as an FTS5 string, keeping a trailing `*` for prefix matches.
+fn fts_term(token: &str) -> Option<String> {
+    let (token, prefix) = match token.strip_suffix('*') {
+        Some(token) => (token, "*"),
+        None => (token, ""),
+    };
+    let token = unquote(token);
+    if !token.chars().any(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    Some(format!("\"{}\"{}", token.replace('"', "\"\""), prefix))
+}
+
+/// A date, as midnight UTC, or an RFC 3339 time.
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+/// Whitespace separated tokens, keeping double quoted phrases together.
+fn tokenize(query: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+
+    for (index, c) in query.char_indices() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                start.get_or_insert(index);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if let Some(start) = start.take() {
+                    tokens.push(&query[start..index]);
+                }
+            }
+            _ => {
+                start.get_or_insert(index);
+            }
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&query[start..]);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .map(|value| value.strip_suffix('"').unwrap_or(value))
+        .unwrap_or(value)
+}
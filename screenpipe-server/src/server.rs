@@ -2,21 +2,30 @@ use axum::{
     body::Body,
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Json, Path, Query, State,
+        DefaultBodyLimit, Json, Path, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Json as JsonResponse, Response,
     },
-    http::StatusCode,
-    response::{IntoResponse, Json as JsonResponse, Response},
     routing::get,
     serve, Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use oasgen::{oasgen, OaSchema, Server};
 
 use screenpipe_core::Desktop;
 
 use chrono::TimeZone;
+use clap::ValueEnum;
 use screenpipe_db::{
-    ContentType, DatabaseManager, FrameData, Order, SearchMatch, SearchResult, Speaker,
-    TagContentType,
+    AlertEvent, AlertRule, Annotation, AudioTranscriptionVersion, Bookmark, CalendarEvent,
+    ContentType, DatabaseManager, FrameCode, FrameData, FrameSimilarity, FrameTable, MeetingNotes,
+    OcrTextLayout, OcrWord, Order, SearchMatch, SearchResult, SearchSort, Speaker,
+    StitchedDocument, TagContentType, TranscriptWord, VideoSegment, Webhook, WebhookDelivery,
+    WindowGeometry,
 };
 
 use tokio_util::io::ReaderStream;
@@ -31,24 +40,63 @@ use image::ImageFormat::{self};
 use screenpipe_events::{send_event, subscribe_to_all_events, Event as ScreenpipeEvent};
 
 use crate::{
+    alerts::{run_alerts, AlertRuleRequest, AlertRules},
+    analytics::{
+        activity_analytics, app_analytics, ActivityAnalytics, AppAnalytics, AppAnalyticsQuery,
+    },
+    annotations::AnnotationRequest,
+    ask::{answer_question, retrieve_sources, Answer, AskRequest},
+    auth::{redacted_uri, require_token, ApiAuth},
+    bookmarks::BookmarkRequest,
+    capture_events::{
+        as_capture_event, record_capture_events, CaptureFilter, CaptureLog, CAPTURE_LOG_SIZE,
+    },
+    cli::CliAudioTranscriptionEngine,
+    clip::{create_clip, ClipQuery},
+    cold_storage::ColdStorage,
+    data_deletion::{delete_data, DataDeletionReport, DeleteDataQuery},
+    digest::{DailyDigest, Digests},
     embedding::embedding_endpoint::create_embeddings,
+    export::{ExportJob, ExportRequest, ExportStatus, Exports},
+    frame_storage::FrameBlobStore,
+    hybrid_search::{
+        blend_results, includes_audio, includes_ocr, SearchMode, SEMANTIC_MAX_DISTANCE,
+    },
+    llm::Llm,
+    meetings::meeting_summary,
+    pagination::{next_before_cursor, next_offset_cursor, Cursor},
+    pattern_search::{TextMatcher, SCAN_LIMIT},
+    pipe_host::ServerPipeHost,
+    power::{PowerThrottle, ThrottleState},
+    rate_limit::{limit_requests, RateLimiter, DEFAULT_MAX_BODY_BYTES},
+    retention::{Retention, RetentionReport},
+    retranscribe::{RetranscribeRequest, RetranscriptionJob, Retranscriptions},
+    search_query::SearchQueryFilters,
+    storage::{StorageManager, StorageUsage},
+    summarize::{gather_sources, summarize, SummarizeRequest, Summary},
+    timeline::{collect_timeline, Timeline, TimelineQuery},
     video::{finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg, MAX_FPS},
     video_cache::{AudioEntry, DeviceFrame, FrameCache, FrameMetadata, TimeSeriesFrame},
     video_utils::{
         extract_frame, extract_frame_from_video, extract_high_quality_frame, merge_videos,
         validate_media, MergeVideosRequest, MergeVideosResponse, ValidateMediaParams,
     },
+    webhooks::{run_webhooks, WebhookRequest, Webhooks},
     PipeManager,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use screenpipe_audio::{
-    audio_manager::AudioManager,
+    audio_manager::{AudioManager, EngineSwitch},
     core::device::{
         default_input_device, default_output_device, list_audio_devices, AudioDevice, DeviceType,
     },
+    pcm_decode,
+    vad::{silence_stats, SilenceStats},
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use screenpipe_vision::capture_pause::capture_pause_reasons;
+use screenpipe_vision::image_embedding::ImageEmbeddingModel;
 use screenpipe_vision::monitor::{get_monitor_by_id, list_monitors};
 use screenpipe_vision::OcrEngine;
 use serde::{Deserialize, Deserializer, Serialize};
@@ -69,8 +117,8 @@ use tokio::{
     time::timeout,
 };
 
+use tower_http::cors::CorsLayer;
 use tower_http::{cors::Any, trace::TraceLayer};
-use tower_http::{cors::CorsLayer, trace::DefaultMakeSpan};
 
 // At the top of the file, add:
 #[cfg(feature = "experimental")]
@@ -92,6 +140,17 @@ pub struct AppState {
     pub ui_monitoring_enabled: bool,
     pub frame_cache: Option<Arc<FrameCache>>,
     pub frame_image_cache: Option<Arc<Mutex<FrameImageCache>>>,
+    pub retention: Option<Arc<Retention>>,
+    pub storage: Arc<StorageManager>,
+    pub cold_storage: Option<Arc<ColdStorage>>,
+    pub digests: Option<Arc<Digests>>,
+    pub llm: Option<Arc<Llm>>,
+    pub capture_log: Arc<CaptureLog>,
+    pub webhooks: Arc<Webhooks>,
+    pub alerts: Arc<AlertRules>,
+    pub exports: Arc<Exports>,
+    pub retranscriptions: Arc<Retranscriptions>,
+    pub power_throttle: Option<Arc<PowerThrottle>>,
 }
 
 // Update the SearchQuery struct
@@ -128,9 +187,66 @@ pub(crate) struct SearchQuery {
     #[serde(default)]
     browser_url: Option<String>,
     #[serde(default)]
+    app_id: Option<String>,
+    /// Only frames and audio tagged with it, or captured in a time range annotated with it
+    #[serde(default)]
+    tag: Option<String>,
+    /// Leave out OCR results stored as low quality, see `--min-frame-confidence`
+    #[serde(default)]
+    exclude_low_quality: bool,
+    #[serde(default)]
     min_visible_percentage: Option<f32>,
     #[serde(default)]
-    max_visible_percentage: Option<f32>
+    max_visible_percentage: Option<f32>,
+    #[serde(default)]
+    include_bounding_boxes: bool,
+    #[serde(default)]
+    include_recording: bool,
+    /// `semantic` blends in text close in meaning to `q`, filtered by time range and app.
+    /// `fuzzy` and `regex` scan the newest captures matching the other filters
+    #[serde(default)]
+    mode: SearchMode,
+    /// `time` for newest first, `relevance` for the best matches of `q` first
+    #[serde(default)]
+    sort: SearchSort,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct DocumentsQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    app_name: Option<String>,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CodesQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TablesQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    app_name: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
 }
 
 #[derive(OaSchema, Deserialize)]
@@ -141,6 +257,10 @@ pub(crate) struct PaginationQuery {
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_number_from_string")]
     offset: u32,
+    /// `next_cursor` of the previous page, to page without skipping or repeating results
+    /// when more are captured meanwhile. Replaces `offset`
+    #[serde(default)]
+    cursor: Option<String>,
 }
 
 fn deserialize_number_from_string<'de, D>(deserializer: D) -> Result<u32, D::Error>
@@ -163,6 +283,9 @@ pub struct PaginationInfo {
     pub limit: u32,
     pub offset: u32,
     pub total: i64,
+    /// Pass as `cursor` for the next page, none on the last one
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -182,6 +305,15 @@ pub struct DeleteSpeakerRequest {
     pub id: i64,
 }
 
+/// Enrolls `name` from a recording only they talk in, or from a speaker recognized so far.
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct EnrollSpeakerRequest {
+    pub name: String,
+    /// Audio file on this computer, e.g. a wav or mp4
+    pub audio_path: Option<String>,
+    pub speaker_id: Option<i64>,
+}
+
 #[derive(OaSchema, Deserialize)]
 struct MarkAsHallucinationRequest {
     speaker_id: i64,
@@ -193,6 +325,28 @@ pub enum ContentItem {
     OCR(OCRContent),
     Audio(AudioContent),
     UI(UiContent),
+    Clipboard(ClipboardContent),
+}
+
+impl ContentItem {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            ContentItem::OCR(ocr) => ocr.timestamp,
+            ContentItem::Audio(audio) => audio.timestamp,
+            ContentItem::UI(ui) => ui.timestamp,
+            ContentItem::Clipboard(clipboard) => clipboard.timestamp,
+        }
+    }
+
+    /// Tells the results at the same timestamp apart across pages.
+    pub fn key(&self) -> String {
+        match self {
+            ContentItem::OCR(ocr) => format!("ocr:{}", ocr.frame_id),
+            ContentItem::Audio(audio) => format!("audio:{}:{}", audio.chunk_id, audio.offset_index),
+            ContentItem::UI(ui) => format!("ui:{}", ui.id),
+            ContentItem::Clipboard(clipboard) => format!("clipboard:{}", clipboard.id),
+        }
+    }
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -208,8 +362,77 @@ pub struct OCRContent {
     pub frame: Option<String>,
     pub frame_name: Option<String>,
     pub browser_url: Option<String>,
+    /// Title of the browser tab, with `browser_url` it links back to the page
+    #[serde(default)]
+    pub browser_title: Option<String>,
+    /// Bundle id (macOS) or executable name of the app
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// Where the window sat in the monitor frame, in frame pixels
+    #[serde(default)]
+    pub window_geometry: Option<WindowGeometry>,
     pub focused: Option<bool>,
     pub visible_percentage: Option<f32>,
+    /// Overall OCR confidence fell below the configured floor
+    #[serde(default)]
+    pub low_quality: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub words: Option<Vec<OcrWord>>,
+    /// Moment of the continuous recording showing this frame, with `include_recording`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<RecordingLink>,
+    /// `ocr`, `ax` for text read from the macOS accessibility tree or `uia` for text read
+    /// from Windows UI Automation
+    #[serde(default)]
+    pub source: String,
+    /// Text around the terms matching `q`, which are wrapped in `<mark>` tags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct RecordingLink {
+    pub segment_id: i64,
+    pub file_path: String,
+    /// Seconds into the file
+    pub offset_secs: f64,
+    pub codec: String,
+}
+
+/// Media path to fetch back from cold storage, e.g. of an audio search result.
+#[derive(OaSchema, Deserialize)]
+pub struct ColdFetchQuery {
+    pub path: String,
+}
+
+#[derive(OaSchema, Serialize, Debug)]
+pub struct ColdFetchResponse {
+    pub path: String,
+    /// False when the file was on disk already
+    pub fetched: bool,
+}
+
+impl RecordingLink {
+    fn new(segment: VideoSegment, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            segment_id: segment.id,
+            offset_secs: segment.offset_secs(timestamp),
+            file_path: segment.file_path,
+            codec: segment.codec,
+        }
+    }
+}
+
+/// `source` of an OCR result, from the engine its text was stored with.
+fn text_source(ocr_engine: &str) -> &'static str {
+    match ocr_engine {
+        "AppleAccessibility" => "ax",
+        "WindowsUiAutomation" => "uia",
+        _ => "ocr",
+    }
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -217,6 +440,7 @@ pub struct AudioContent {
     pub chunk_id: i64,
     pub transcription: String,
     pub timestamp: DateTime<Utc>,
+    /// Empty when the audio wasn't kept, e.g. recorded with --transcript-only
     pub file_path: String,
     pub offset_index: i64,
     pub tags: Vec<String>,
@@ -225,6 +449,21 @@ pub struct AudioContent {
     pub speaker: Option<Speaker>,
     pub start_time: Option<f64>,
     pub end_time: Option<f64>,
+    /// Text around the terms matching `q`, which are wrapped in `<mark>` tags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// When each word was said, in seconds into `file_path`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<TranscriptWord>,
+    /// Seconds into `file_path` where a term of `q` is first said, to play the match from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_time: Option<f64>,
+    /// The transcription translated with `--translate-transcriptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -241,6 +480,23 @@ pub struct UiContent {
     pub browser_url: Option<String>,
 }
 
+/// Text copied to the clipboard, with `--enable-clipboard`.
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct ClipboardContent {
+    pub id: i64,
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+    /// App focused when it was copied
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// Text around the terms matching `q`, which are wrapped in `<mark>` tags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// BM25 relevance of the match, higher is better
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
 #[derive(OaSchema, Serialize)]
 pub(crate) struct ListDeviceResponse {
     name: String,
@@ -294,6 +550,19 @@ pub struct HealthCheckResponse {
     pub message: String,
     pub verbose_instructions: Option<String>,
     pub device_status_details: Option<String>,
+    /// Why capture is paused, e.g. `idle` or `locked`, empty while capturing
+    #[serde(default)]
+    pub capture_paused: Vec<String>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize)]
+pub struct StatusResponse {
+    /// Why capture is paused, e.g. `idle`, `locked` or `power`, empty while capturing
+    pub capture_paused: Vec<String>,
+    /// How much capture is held back to save power, none without a power policy
+    pub throttle: Option<ThrottleState>,
+    /// Audio left out as silence instead of being transcribed and stored
+    pub audio_silence: SilenceStats,
 }
 
 #[derive(OaSchema, Serialize, Deserialize)]
@@ -309,7 +578,7 @@ pub(crate) async fn search(
     State(state): State<Arc<AppState>>,
 ) -> Result<JsonResponse<SearchResponse>, (StatusCode, JsonResponse<serde_json::Value>)> {
     info!(
-        "received search request: query='{}', content_type={:?}, limit={}, offset={}, start_time={:?}, end_time={:?}, app_name={:?}, window_name={:?}, min_length={:?}, max_length={:?}, speaker_ids={:?}, frame_name={:?}, browser_url={:?}, focused={:?}, min_visible_percentage={:?}, max_visible_percentage={:?}",
+        "received search request: query='{}', content_type={:?}, limit={}, offset={}, start_time={:?}, end_time={:?}, app_name={:?}, window_name={:?}, min_length={:?}, max_length={:?}, speaker_ids={:?}, frame_name={:?}, browser_url={:?}, app_id={:?}, focused={:?}, min_visible_percentage={:?}, max_visible_percentage={:?}",
         query.q.as_deref().unwrap_or(""),
         query.content_type,
         query.pagination.limit,
@@ -323,59 +592,252 @@ pub(crate) async fn search(
         query.speaker_ids,
         query.frame_name,
         query.browser_url,
+        query.app_id,
         query.focused,
         query.min_visible_percentage,
         query.max_visible_percentage,
     );
 
-    let query_str = query.q.as_deref().unwrap_or("");
+    // inline filters like `app:chrome title:"pull request" after:2024-05-01`, explicit
+    // parameters win. A regex is taken as is
+    let q = query.q.as_deref().unwrap_or("");
+    let filters = if query.mode == SearchMode::Regex {
+        SearchQueryFilters {
+            text: q.to_string(),
+            ..Default::default()
+        }
+    } else {
+        SearchQueryFilters::parse(q)
+    };
+    let query_str = filters.text.as_str();
+    let match_query = filters.fts_query();
+    let exclusions = filters.exclusions();
+    let cursor = query
+        .pagination
+        .cursor
+        .as_deref()
+        .map(Cursor::decode)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    let start_time = query.start_time.or(filters.after);
+    let end_time = query.end_time.or(filters.before);
+    // the total stays the one of the whole range
+    let count_end_time = end_time;
+    let end_time = cursor
+        .as_ref()
+        .map_or(end_time, |cursor| Some(cursor.end_time(end_time)));
+    let page_offset = cursor
+        .as_ref()
+        .map_or(query.pagination.offset, Cursor::offset);
+    let app_name = query.app_name.as_deref().or(filters.app_name.as_deref());
+    let app_id = query.app_id.as_deref().or(filters.app_id.as_deref());
+    let window_name = query
+        .window_name
+        .as_deref()
+        .or(filters.window_name.as_deref());
+    let browser_url = query
+        .browser_url
+        .as_deref()
+        .or(filters.browser_url.as_deref());
+    let focused = query.focused.or(filters.focused);
+    let tag = query.tag.as_deref().or(filters.tag.as_deref());
+
+    let matcher = match query.mode {
+        SearchMode::Fuzzy => Some(TextMatcher::fuzzy(query_str)),
+        SearchMode::Regex => Some(TextMatcher::regex(query_str)),
+        SearchMode::Keyword | SearchMode::Semantic => None,
+    }
+    .transpose()
+    .map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("invalid query: {}", e)})),
+        )
+    })?;
 
     let content_type = query.content_type.clone();
+    // semantic matches can't be filtered by tag
+    let semantic = query.mode == SearchMode::Semantic && !query_str.is_empty() && tag.is_none();
+    // the results at the end of the range the last page returned come first, fetched again
+    // to be left out
+    let seen = cursor.as_ref().map_or(&[][..], Cursor::seen);
+    // semantic matches are blended with the keyword matches of every page up to this one,
+    // twice as many of each as needed so a result ranked high in one list makes the page
+    let (limit, offset) = if semantic {
+        ((page_offset + query.pagination.limit) * 2, 0)
+    } else {
+        (query.pagination.limit + seen.len() as u32, page_offset)
+    };
 
-    let (results, total) = try_join(
-        state.db.search(
-            query_str,
-            content_type.clone(),
-            query.pagination.limit,
-            query.pagination.offset,
-            query.start_time,
-            query.end_time,
-            query.app_name.as_deref(),
-            query.window_name.as_deref(),
-            query.min_length,
-            query.max_length,
-            query.speaker_ids.clone(),
-            query.frame_name.as_deref(),
-            query.browser_url.as_deref(),
-            query.focused,
-            query.min_visible_percentage,
-            query.max_visible_percentage,
-        ),
-        state.db.count_search_results(
-            query_str,
-            content_type,
-            query.start_time,
-            query.end_time,
-            query.app_name.as_deref(),
-            query.window_name.as_deref(),
-            query.min_length,
-            query.max_length,
-            query.speaker_ids.clone(),
-            query.frame_name.as_deref(),
-            query.browser_url.as_deref(),
-            query.focused,
-            query.min_visible_percentage,
-            query.max_visible_percentage,
-        ),
-    )
-    .await
-    .map_err(|e| {
+    let search_error = |e: sqlx::Error| {
         error!("failed to perform search operations: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             JsonResponse(json!({"error": format!("failed to perform search operations: {}", e)})),
         )
-    })?;
+    };
+
+    let scanned_search = matcher.is_some();
+    let (mut results, mut total) = if let Some(matcher) = matcher {
+        let scanned = state
+            .db
+            .search(
+                "",
+                content_type,
+                SCAN_LIMIT,
+                0,
+                start_time,
+                end_time,
+                app_name,
+                window_name,
+                query.min_length,
+                query.max_length,
+                query.speaker_ids.clone(),
+                query.frame_name.as_deref(),
+                browser_url,
+                focused,
+                query.min_visible_percentage,
+                query.max_visible_percentage,
+                app_id,
+                query.exclude_low_quality,
+                &exclusions,
+                tag,
+                SearchSort::Time,
+            )
+            .await
+            .map_err(search_error)?;
+        // edit distances over thousands of captures would hold up the runtime
+        let by_distance = query.sort == SearchSort::Relevance;
+        let matched = tokio::task::spawn_blocking(move || matcher.filter(scanned, by_distance))
+            .await
+            .map_err(|e| {
+                error!("failed to match results: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": format!("failed to match results: {}", e)})),
+                )
+            })?;
+        let total = matched.len();
+        let page = matched
+            .into_iter()
+            .skip(page_offset as usize)
+            .take(query.pagination.limit as usize)
+            .collect();
+        (page, total)
+    } else {
+        try_join(
+            state.db.search(
+                &match_query,
+                content_type.clone(),
+                limit,
+                offset,
+                start_time,
+                end_time,
+                app_name,
+                window_name,
+                query.min_length,
+                query.max_length,
+                query.speaker_ids.clone(),
+                query.frame_name.as_deref(),
+                browser_url,
+                focused,
+                query.min_visible_percentage,
+                query.max_visible_percentage,
+                app_id,
+                query.exclude_low_quality,
+                &exclusions,
+                tag,
+                query.sort,
+            ),
+            state.db.count_search_results(
+                &match_query,
+                content_type,
+                start_time,
+                count_end_time,
+                app_name,
+                window_name,
+                query.min_length,
+                query.max_length,
+                query.speaker_ids.clone(),
+                query.frame_name.as_deref(),
+                browser_url,
+                focused,
+                query.min_visible_percentage,
+                query.max_visible_percentage,
+                app_id,
+                query.exclude_low_quality,
+                &exclusions,
+                tag,
+            ),
+        )
+        .await
+        .map_err(search_error)?
+    };
+
+    if semantic {
+        let embedding = generate_embedding(query_str, 0).await.map_err(|e| {
+            error!("failed to generate embedding: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
+            )
+        })?;
+        let search_error = |e: anyhow::Error| {
+            error!("failed to search embeddings: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to search embeddings: {}", e)})),
+            )
+        };
+
+        let mut lists = vec![results];
+        if includes_ocr(&query.content_type) {
+            let ocr = state
+                .db
+                .search_similar_embeddings(
+                    embedding.clone(),
+                    limit,
+                    SEMANTIC_MAX_DISTANCE,
+                    start_time,
+                    end_time,
+                    app_name,
+                    window_name,
+                    browser_url,
+                    focused,
+                )
+                .await
+                .map_err(search_error)?;
+            lists.push(ocr.into_iter().map(SearchResult::OCR).collect());
+        }
+        // as by keyword, audio has no app, window or url to filter by
+        let screen_filtered = app_name.is_some()
+            || window_name.is_some()
+            || browser_url.is_some()
+            || focused.is_some()
+            || app_id.is_some();
+        if includes_audio(&query.content_type) && !screen_filtered {
+            let audio = state
+                .db
+                .search_similar_transcriptions(
+                    &embedding,
+                    limit,
+                    SEMANTIC_MAX_DISTANCE,
+                    start_time,
+                    end_time,
+                    query.speaker_ids.as_deref(),
+                )
+                .await
+                .map_err(search_error)?;
+            lists.push(audio.into_iter().map(SearchResult::Audio).collect());
+        }
+        let blended = blend_results(lists);
+        total = total.max(blended.len());
+        results = blended
+            .into_iter()
+            .skip(page_offset as usize)
+            .take(query.pagination.limit as usize)
+            .collect();
+    }
 
     let mut content_items: Vec<ContentItem> = results
         .iter()
@@ -392,8 +854,21 @@ pub(crate) async fn search(
                 frame: None,
                 frame_name: Some(ocr.frame_name.clone()),
                 browser_url: ocr.browser_url.clone(),
+                browser_title: ocr.browser_title.clone(),
+                app_id: ocr.app_id.clone(),
+                window_geometry: ocr.window_geometry,
                 focused: ocr.focused,
-                visible_percentage: Some(ocr.visible_percentage.clone())
+                visible_percentage: Some(ocr.visible_percentage),
+                low_quality: ocr.low_quality,
+                words: if query.include_bounding_boxes {
+                    Some(OcrTextLayout::from_text_json(&ocr.text_json).words)
+                } else {
+                    None
+                },
+                recording: None,
+                source: text_source(&ocr.ocr_engine).to_string(),
+                snippet: ocr.snippet.clone(),
+                score: ocr.score,
             }),
             SearchResult::Audio(audio) => ContentItem::Audio(AudioContent {
                 chunk_id: audio.audio_chunk_id,
@@ -407,6 +882,15 @@ pub(crate) async fn search(
                 speaker: audio.speaker.clone(),
                 start_time: audio.start_time,
                 end_time: audio.end_time,
+                snippet: audio.snippet.clone(),
+                score: audio.score,
+                words: audio.words.clone(),
+                match_time: if query.mode == SearchMode::Regex {
+                    None
+                } else {
+                    filters.spoken_at(&audio.words)
+                },
+                translation: audio.translation.clone(),
             }),
             SearchResult::UI(ui) => ContentItem::UI(UiContent {
                 id: ui.id,
@@ -420,8 +904,21 @@ pub(crate) async fn search(
                 frame_name: ui.frame_name.clone(),
                 browser_url: ui.browser_url.clone(),
             }),
+            SearchResult::Clipboard(entry) => ContentItem::Clipboard(ClipboardContent {
+                id: entry.id,
+                text: entry.text.clone(),
+                timestamp: entry.timestamp,
+                app_name: entry.app_name.clone(),
+                window_name: entry.window_name.clone(),
+                snippet: entry.snippet.clone(),
+                score: entry.score,
+            }),
         })
         .collect();
+    if !seen.is_empty() {
+        content_items.retain(|item| !seen.contains(&item.key()));
+        content_items.truncate(query.pagination.limit as usize);
+    }
 
     if query.include_frames {
         debug!("extracting frames for ocr content");
@@ -448,13 +945,51 @@ pub(crate) async fn search(
         }
     }
 
+    if query.include_recording {
+        for item in content_items.iter_mut() {
+            if let ContentItem::OCR(ref mut ocr_content) = item {
+                match state
+                    .db
+                    .get_video_segment_for_frame(ocr_content.frame_id)
+                    .await
+                {
+                    Ok(segment) => {
+                        ocr_content.recording = segment
+                            .map(|(segment, timestamp)| RecordingLink::new(segment, timestamp));
+                    }
+                    Err(e) => warn!(
+                        "failed to look up recording of frame {}: {}",
+                        ocr_content.frame_id, e
+                    ),
+                }
+            }
+        }
+    }
+
+    // newest first pages continue before their last result, the others at their offset
+    let next_cursor = if !scanned_search && !semantic && query.sort == SearchSort::Time {
+        let results: Vec<(DateTime<Utc>, String)> = content_items
+            .iter()
+            .map(|item| (item.timestamp(), item.key()))
+            .collect();
+        next_before_cursor(cursor.as_ref(), &results, query.pagination.limit)
+    } else {
+        next_offset_cursor(
+            cursor.as_ref(),
+            content_items.len(),
+            query.pagination.limit,
+            Utc::now(),
+        )
+    };
+
     info!("search completed: found {} results", total);
     Ok(JsonResponse(SearchResponse {
         data: content_items,
         pagination: PaginationInfo {
             limit: query.pagination.limit,
-            offset: query.pagination.offset,
+            offset: page_offset,
             total: total as i64,
+            next_cursor: next_cursor.as_ref().map(Cursor::encode),
         },
     }))
 }
@@ -598,68 +1133,457 @@ pub(crate) async fn remove_tags(
     }
 }
 
+/// Attaches tags and a note to a frame, an audio chunk or a time range. The tags of frames
+/// and audio chunks are added to their tags too, `/search?tag=` finds all of them.
 #[oasgen]
-pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<HealthCheckResponse> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let app_uptime = (now as i64) - (state.app_start_time.timestamp());
-    let grace_period = 120; // 2 minutes in seconds
-
-    // Get the status of all devices
-    let audio_devices = state.audio_manager.current_devices();
-    let mut device_statuses = Vec::new();
-    let mut global_audio_active = false;
-    let mut most_recent_audio_timestamp = 0; // Track the most recent timestamp
-
-    // Check each device
-    for device in &audio_devices {
-        let device_name = device.to_string();
-        let last_capture = screenpipe_audio::core::get_device_capture_time(&device_name);
-
-        // Update the most recent timestamp
-        most_recent_audio_timestamp = most_recent_audio_timestamp.max(last_capture);
-
-        let device_active = if app_uptime < grace_period {
-            true // Consider active during grace period
-        } else {
-            now - last_capture < 5 // Consider active if captured in last 5 seconds
-        };
+pub(crate) async fn create_annotation(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<AnnotationRequest>,
+) -> Result<JsonResponse<Annotation>, (StatusCode, JsonResponse<Value>)> {
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
 
-        // Track if any device is active
-        if device_active {
-            global_audio_active = true;
+    match state
+        .db
+        .insert_annotation(
+            &request.content_type,
+            request.id,
+            request.start_time,
+            request.end_time,
+            request.note(),
+            &request.tags(),
+        )
+        .await
+    {
+        Ok(annotation) => Ok(JsonResponse(annotation)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": format!("{} not found", request.content_type),
+                "id": request.id,
+            })),
+        )),
+        Err(e) => {
+            error!("Failed to create annotation: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
         }
-        debug!(target: "server", "device status: {} {}", device_name, device_active);
-
-        device_statuses.push((device_name, device_active, last_capture));
-    }
-
-    // Fallback to global timestamp if no devices are detected
-    if audio_devices.is_empty() {
-        let last_capture = screenpipe_audio::core::LAST_AUDIO_CAPTURE.load(Ordering::Relaxed);
-        global_audio_active = if app_uptime < grace_period {
-            true // Consider active during grace period
-        } else {
-            now - last_capture < 5 // Consider active if captured in last 5 seconds
-        };
     }
+}
 
-    let (last_frame, audio, last_ui) = match state.db.get_latest_timestamps().await {
-        Ok((frame, audio, ui)) => (frame, audio, ui),
-        Err(e) => {
-            error!("failed to get latest timestamps: {}", e);
-            (None, None, None)
-        }
-    };
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct AnnotationsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// `vision`, `audio` or `range`
+    #[serde(default)]
+    content_type: Option<String>,
+    /// The frame or audio chunk
+    #[serde(default)]
+    id: Option<i64>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tag: Option<String>,
+    /// `id` of the last annotation of the previous page, the page continues with later ones
+    #[serde(default)]
+    after_id: Option<i64>,
+}
 
-    let now = Utc::now();
-    let threshold = Duration::from_secs(1800); // 30 minutes
+/// The annotations overlapping the time range, oldest first.
+#[oasgen]
+pub(crate) async fn list_annotations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnnotationsQuery>,
+) -> Result<JsonResponse<Vec<Annotation>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_annotations(
+            query.content_type.as_deref(),
+            query.id,
+            query.start_time,
+            query.end_time,
+            query.tag.as_deref(),
+            query.after_id,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list annotations: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Deletes the annotation. The tags it added to a frame or an audio chunk stay, removing
+/// them is `DELETE /tags/:content_type/:id`.
+#[oasgen]
+pub(crate) async fn delete_annotation(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_annotation(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "annotation not found", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to delete annotation {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+/// Pins a frame or a moment, its media is kept by retention and the storage quota.
+#[oasgen]
+pub(crate) async fn create_bookmark(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<BookmarkRequest>,
+) -> Result<JsonResponse<Bookmark>, (StatusCode, JsonResponse<Value>)> {
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    match state
+        .db
+        .insert_bookmark(
+            request.frame_id,
+            request.timestamp(Utc::now()),
+            request.title(),
+        )
+        .await
+    {
+        Ok(bookmark) => Ok(JsonResponse(bookmark)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "frame not found", "id": request.frame_id})),
+        )),
+        Err(e) => {
+            error!("Failed to create bookmark: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BookmarksQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// `id` of the last bookmark of the previous page, the page continues with older ones
+    #[serde(default)]
+    before_id: Option<i64>,
+}
+
+/// The pinned moments, newest first.
+#[oasgen]
+pub(crate) async fn list_bookmarks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BookmarksQuery>,
+) -> Result<JsonResponse<Vec<Bookmark>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_bookmarks(
+            query.start_time,
+            query.end_time,
+            query.before_id,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list bookmarks: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Unpins a moment, its media is left to retention and the storage quota again.
+#[oasgen]
+pub(crate) async fn delete_bookmark(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_bookmark(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "bookmark not found", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to delete bookmark {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct MeetingsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// Part of the title or of an attendee, e.g. "standup"
+    #[serde(default)]
+    q: Option<String>,
+    /// `calendar` for the meetings read from the calendar, `detected` for those seen in
+    /// meeting apps
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// The meetings read from the calendar and detected in meeting apps, newest first, with
+/// how much was captured during them. Search with `tag` set to a title or an attendee for
+/// what was said and shown.
+#[oasgen]
+pub(crate) async fn list_meetings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MeetingsQuery>,
+) -> Result<JsonResponse<Vec<CalendarEvent>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_calendar_events(
+            query.q.as_deref(),
+            query.source.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list meetings: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// A meeting and what was transcribed during it.
+#[oasgen]
+pub(crate) async fn get_meeting(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: sqlx::Error| {
+        error!("Failed to get meeting {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+    let Some(meeting) = state
+        .db
+        .get_calendar_event(id)
+        .await
+        .map_err(internal_error)?
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "meeting not found", "id": id})),
+        ));
+    };
+
+    let mut transcriptions = Vec::new();
+    let mut after_id = 0;
+    loop {
+        let page = state
+            .db
+            .get_export_transcriptions(meeting.start_time, meeting.end_time, after_id, 500)
+            .await
+            .map_err(internal_error)?;
+        let Some(last) = page.last() else { break };
+        after_id = last.id;
+        transcriptions.extend(page);
+    }
+    transcriptions.sort_by_key(|transcription| transcription.timestamp);
+    let notes = state
+        .db
+        .get_meeting_notes(id)
+        .await
+        .map_err(internal_error)?;
+    Ok(JsonResponse(
+        json!({"meeting": meeting, "notes": notes, "transcriptions": transcriptions}),
+    ))
+}
+
+/// The summary, decisions and action items written for a detected meeting once it was
+/// over, by the meeting notes of --detect-meetings.
+#[oasgen]
+pub(crate) async fn get_meeting_notes(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<MeetingNotes>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.get_meeting_notes(id).await {
+        Ok(Some(notes)) => Ok(JsonResponse(notes)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "the meeting has no notes yet", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to get the notes of meeting {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+/// Summary of what was said and shown during a meeting, written with the configured LLM.
+/// Once the meeting is over it is the summary of its notes.
+#[oasgen]
+pub(crate) async fn get_meeting_summary(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Summary>, (StatusCode, JsonResponse<Value>)> {
+    let Some(llm) = &state.llm else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "summarization is not enabled, start with --llm-provider"}),
+            ),
+        ));
+    };
+    let meeting = match state.db.get_calendar_event(id).await {
+        Ok(Some(meeting)) => meeting,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "meeting not found", "id": id})),
+            ))
+        }
+        Err(e) => {
+            error!("Failed to get meeting {}: {}", id, e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ));
+        }
+    };
+
+    match meeting_summary(&state.db, llm, &meeting).await {
+        Ok(Some(summary)) => Ok(JsonResponse(summary)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "nothing was captured during the meeting", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to summarize meeting {}: {}", id, e);
+            Err((
+                StatusCode::BAD_GATEWAY,
+                JsonResponse(json!({"error": format!("Failed to summarize: {}", e)})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+pub(crate) async fn get_status(State(state): State<Arc<AppState>>) -> JsonResponse<StatusResponse> {
+    JsonResponse(StatusResponse {
+        capture_paused: capture_pause_reasons(),
+        throttle: state
+            .power_throttle
+            .as_ref()
+            .map(|throttle| throttle.state()),
+        audio_silence: silence_stats(),
+    })
+}
+
+#[oasgen]
+pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<HealthCheckResponse> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let app_uptime = (now as i64) - (state.app_start_time.timestamp());
+    let grace_period = 120; // 2 minutes in seconds
+
+    // Get the status of all devices
+    let audio_devices = state.audio_manager.current_devices();
+    let mut device_statuses = Vec::new();
+    let mut global_audio_active = false;
+    let mut most_recent_audio_timestamp = 0; // Track the most recent timestamp
+
+    // Check each device
+    for device in &audio_devices {
+        let device_name = device.to_string();
+        let last_capture = screenpipe_audio::core::get_device_capture_time(&device_name);
+
+        // Update the most recent timestamp
+        most_recent_audio_timestamp = most_recent_audio_timestamp.max(last_capture);
+
+        let device_active = if app_uptime < grace_period {
+            true // Consider active during grace period
+        } else {
+            now - last_capture < 5 // Consider active if captured in last 5 seconds
+        };
+
+        // Track if any device is active
+        if device_active {
+            global_audio_active = true;
+        }
+        debug!(target: "server", "device status: {} {}", device_name, device_active);
+
+        device_statuses.push((device_name, device_active, last_capture));
+    }
+
+    // Fallback to global timestamp if no devices are detected
+    if audio_devices.is_empty() {
+        let last_capture = screenpipe_audio::core::LAST_AUDIO_CAPTURE.load(Ordering::Relaxed);
+        global_audio_active = if app_uptime < grace_period {
+            true // Consider active during grace period
+        } else {
+            now - last_capture < 5 // Consider active if captured in last 5 seconds
+        };
+    }
+
+    let (last_frame, audio, last_ui) = match state.db.get_latest_timestamps().await {
+        Ok((frame, audio, ui)) => (frame, audio, ui),
+        Err(e) => {
+            error!("failed to get latest timestamps: {}", e);
+            (None, None, None)
+        }
+    };
+
+    let now = Utc::now();
+    let threshold = Duration::from_secs(1800); // 30 minutes
+    let capture_paused = capture_pause_reasons();
 
     let frame_status = if state.vision_disabled {
         "disabled"
+    } else if !capture_paused.is_empty() {
+        "paused"
     } else {
         match last_frame {
             Some(timestamp)
@@ -675,6 +1599,8 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
 
     let audio_status = if state.audio_disabled {
         "disabled".to_string()
+    } else if !capture_paused.is_empty() {
+        "paused".to_string()
     } else if global_audio_active {
         "ok".to_string()
     } else {
@@ -725,9 +1651,10 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         }
     };
 
-    let (overall_status, message, verbose_instructions, status_code) = if (frame_status == "ok"
-        || frame_status == "disabled")
-        && (audio_status == "ok" || audio_status == "disabled")
+    // paused while the computer is idle or locked is as it should be
+    let capturing = |status: &str| matches!(status, "ok" | "disabled" | "paused");
+    let (overall_status, message, verbose_instructions, status_code) = if capturing(frame_status)
+        && capturing(&audio_status)
         && (ui_status == "ok" || ui_status == "disabled")
     {
         (
@@ -738,10 +1665,10 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         )
     } else {
         let mut unhealthy_systems = Vec::new();
-        if frame_status != "ok" && frame_status != "disabled" {
+        if !capturing(frame_status) {
             unhealthy_systems.push("vision");
         }
-        if audio_status != "ok" && audio_status != "disabled" {
+        if !capturing(&audio_status) {
             unhealthy_systems.push("audio");
         }
         if ui_status != "ok" && ui_status != "disabled" {
@@ -776,6 +1703,7 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<He
         message,
         verbose_instructions,
         device_status_details,
+        capture_paused,
     })
 }
 
@@ -1051,6 +1979,17 @@ pub struct SCServer {
     vision_disabled: bool,
     audio_disabled: bool,
     ui_monitoring_enabled: bool,
+    retention: Option<Arc<Retention>>,
+    storage: Arc<StorageManager>,
+    cold_storage: Option<Arc<ColdStorage>>,
+    digests: Option<Arc<Digests>>,
+    llm: Option<Arc<Llm>>,
+    auth: Option<Arc<ApiAuth>>,
+    tls: Option<RustlsConfig>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_body_bytes: usize,
+    power_throttle: Option<Arc<PowerThrottle>>,
+    pipe_host: Option<Arc<ServerPipeHost>>,
 }
 
 impl SCServer {
@@ -1066,6 +2005,11 @@ impl SCServer {
         audio_manager: Arc<AudioManager>,
     ) -> Self {
         SCServer {
+            storage: Arc::new(StorageManager::new(
+                db.clone(),
+                screenpipe_dir.clone(),
+                None,
+            )),
             db,
             addr,
             screenpipe_dir,
@@ -1074,15 +2018,109 @@ impl SCServer {
             audio_disabled,
             ui_monitoring_enabled,
             audio_manager,
+            retention: None,
+            cold_storage: None,
+            digests: None,
+            llm: None,
+            auth: None,
+            tls: None,
+            rate_limiter: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            power_throttle: None,
+            pipe_host: None,
         }
     }
 
-    pub async fn start(self, enable_frame_cache: bool) -> Result<(), std::io::Error> {
-        // Create the OpenAPI server
-        let app = self.create_router(enable_frame_cache).await;
+    /// Reports what `retention` would delete through `/retention`.
+    pub fn with_retention(mut self, retention: Arc<Retention>) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Reports the usage and quota of `storage` through `/storage`.
+    pub fn with_storage(mut self, storage: Arc<StorageManager>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Fetches media moved to `cold_storage` back when it is opened.
+    pub fn with_cold_storage(mut self, cold_storage: Arc<ColdStorage>) -> Self {
+        self.cold_storage = Some(cold_storage);
+        self
+    }
+
+    /// Serves the daily digests of `digests` through `/digest/:date`.
+    pub fn with_digests(mut self, digests: Arc<Digests>) -> Self {
+        self.digests = Some(digests);
+        self
+    }
+
+    /// Writes the summaries of `/summarize` and the answers of `/ask` with `llm`.
+    pub fn with_llm(mut self, llm: Arc<Llm>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Requires a token with the right scope on every request but the health checks.
+    pub fn with_auth(mut self, auth: Arc<ApiAuth>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Serves HTTPS instead of HTTP.
+    pub fn with_tls(mut self, tls: RustlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Answers 429 to the clients making more requests than `rate_limiter` allows.
+    pub fn with_rate_limit(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Answers 413 to the requests with bodies over `max_body_bytes`.
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Reports how much `power_throttle` holds capture back through `/status`.
+    pub fn with_power_throttle(mut self, power_throttle: Arc<PowerThrottle>) -> Self {
+        self.power_throttle = Some(power_throttle);
+        self
+    }
+
+    /// Answers the `pipe.read` of sandboxed pipes through `pipe_host`.
+    pub fn with_pipe_host(mut self, pipe_host: Arc<ServerPipeHost>) -> Self {
+        self.pipe_host = Some(pipe_host);
+        self
+    }
+
+    pub async fn start(self, enable_frame_cache: bool) -> Result<(), std::io::Error> {
+        // Create the OpenAPI server
+        let app = self.create_router(enable_frame_cache).await;
 
         #[cfg(feature = "experimental")]
-        let app = app.route("/experimental/input_control", post(input_control_handler));
+        let app = {
+            let input_control = Router::new().route(
+                "/experimental/input_control",
+                axum::routing::post(input_control_handler),
+            );
+            app.merge(match &self.auth {
+                Some(auth) => {
+                    input_control.layer(middleware::from_fn_with_state(auth.clone(), require_token))
+                }
+                None => input_control,
+            })
+        };
+
+        if let Some(tls) = self.tls.clone() {
+            info!("Server listening on https://{}", self.addr);
+            return axum_server::bind_rustls(self.addr, tls)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await;
+        }
 
         // Create the listener
         let listener = TcpListener::bind(&self.addr).await?;
@@ -1100,6 +2138,18 @@ impl SCServer {
     }
 
     pub async fn create_router(&self, enable_frame_cache: bool) -> Router {
+        let capture_log = Arc::new(CaptureLog::new(CAPTURE_LOG_SIZE));
+        tokio::spawn(record_capture_events(capture_log.clone()));
+        let webhooks = Arc::new(Webhooks::new(self.db.clone()));
+        if let Err(e) = webhooks.reload().await {
+            warn!("failed to load webhooks: {}", e);
+        }
+        tokio::spawn(run_webhooks(webhooks.clone(), capture_log.clone()));
+        let alerts = Arc::new(AlertRules::new(self.db.clone()));
+        if let Err(e) = alerts.reload().await {
+            warn!("failed to load alert rules: {}", e);
+        }
+        tokio::spawn(run_alerts(alerts.clone(), capture_log.clone()));
         let app_state = Arc::new(AppState {
             db: self.db.clone(),
             audio_manager: self.audio_manager.clone(),
@@ -1125,6 +2175,26 @@ impl SCServer {
             } else {
                 None
             },
+            retention: self.retention.clone(),
+            storage: self.storage.clone(),
+            cold_storage: self.cold_storage.clone(),
+            digests: self.digests.clone(),
+            llm: self.llm.clone(),
+            capture_log,
+            webhooks,
+            alerts,
+            exports: Arc::new(Exports::new(
+                self.db.clone(),
+                self.cold_storage.clone(),
+                &self.screenpipe_dir,
+            )),
+            retranscriptions: Arc::new(Retranscriptions::new(
+                self.db.clone(),
+                self.audio_manager.clone(),
+                self.cold_storage.clone(),
+                &self.screenpipe_dir,
+            )),
+            power_throttle: self.power_throttle.clone(),
         });
 
         let cors = CorsLayer::new()
@@ -1135,12 +2205,67 @@ impl SCServer {
                 axum::http::header::CONTENT_TYPE,
                 axum::http::header::CACHE_CONTROL,
             ]);
-        let server = Server::axum()
+        let mut server = Server::axum();
+        server.openapi.info.title = "screenpipe".to_string();
+        server.openapi.info.version = env!("CARGO_PKG_VERSION").to_string();
+        server.openapi.info.description = Some(
+            "HTTP API of screenpipe. The streams /ws/events, /sse/events, /stream/frames, \
+             /frames/export and /ws/health are WebSockets or server-sent events and aren't \
+             described here."
+                .to_string(),
+        );
+        let server = server
             .get("/search", search)
+            .get("/clip", get_clip)
+            .get("/timeline", get_timeline)
+            .get("/analytics/apps", get_app_analytics)
+            .get("/analytics/activity", get_activity_analytics)
+            .get("/digest/:date", get_daily_digest)
+            .post("/summarize", summarize_handler)
+            .post("/ask", ask_handler)
+            .post("/webhooks", create_webhook)
+            .get("/webhooks", list_webhooks)
+            .delete("/webhooks/:id", delete_webhook)
+            .get("/webhooks/:id/deliveries", list_webhook_deliveries)
+            .post("/alerts/rules", create_alert_rule)
+            .get("/alerts/rules", list_alert_rules)
+            .delete("/alerts/rules/:id", delete_alert_rule)
+            .get("/alerts", list_alert_events)
+            .get("/documents", search_documents)
+            .get("/documents/:id", get_document)
+            .get("/codes", search_codes)
+            .get("/tables", search_tables)
+            .get("/retention", get_retention_report)
+            .delete("/data", delete_data_handler)
+            .post("/exports", create_export)
+            .get("/exports", list_exports)
+            .get("/exports/:id", get_export)
+            .get("/exports/:id/download", download_export)
+            .delete("/exports/:id", delete_export)
+            .post("/retranscribe", create_retranscription)
+            .get("/retranscribe", list_retranscriptions)
+            .get("/retranscribe/:id", get_retranscription)
+            .get(
+                "/audio/transcriptions/:id/versions",
+                get_transcription_versions,
+            )
+            .get("/storage", get_storage_usage)
+            .get("/cold-storage/fetch", fetch_cold_media)
             .get("/audio/list", api_list_audio_devices)
             .get("/vision/list", api_list_monitors)
+            .post("/tags", create_annotation)
             .post("/tags/:content_type/:id", add_tags)
             .delete("/tags/:content_type/:id", remove_tags)
+            .get("/annotations", list_annotations)
+            .delete("/annotations/:id", delete_annotation)
+            .post("/bookmarks", create_bookmark)
+            .get("/bookmarks", list_bookmarks)
+            .delete("/bookmarks/:id", delete_bookmark)
+            .get("/meetings", list_meetings)
+            .get("/meetings/:id", get_meeting)
+            .get("/meetings/:id/summary", get_meeting_summary)
+            .get("/meetings/:id/notes", get_meeting_notes)
+            .get("/pipes", list_pipes_handler)
             .get("/pipes/info/:pipe_id", get_pipe_info_handler)
             .get("/pipes/list", list_pipes_handler)
             .post("/pipes/download", download_pipe_handler)
@@ -1152,7 +2277,10 @@ impl SCServer {
             .post("/pipes/delete", delete_pipe_handler)
             .post("/pipes/purge", purge_pipe_handler)
             .get("/frames/:frame_id", get_frame_data)
+            .get("/frames/:frame_id/recording", get_frame_recording)
+            .get("/frames/:frame_id/tables", get_frame_tables)
             .get("/health", health_check)
+            .get("/status", get_status)
             .post("/raw_sql", execute_raw_sql)
             .post("/add", add_to_database)
             .get("/speakers/unnamed", get_unnamed_speakers_handler)
@@ -1162,6 +2290,9 @@ impl SCServer {
             .post("/speakers/hallucination", mark_as_hallucination_handler)
             .post("/speakers/merge", merge_speakers_handler)
             .get("/speakers/similar", get_similar_speakers_handler)
+            .get("/speakers/enrolled", get_enrolled_speakers_handler)
+            .post("/speakers/enroll", enroll_speaker_handler)
+            .post("/speakers/unenroll", unenroll_speaker_handler)
             .post("/experimental/frames/merge", merge_frames_handler)
             .get("/experimental/validate/media", validate_media_handler)
             .post("/experimental/operator", find_elements_handler)
@@ -1170,26 +2301,59 @@ impl SCServer {
             .post("/audio/start", start_audio)
             .post("/audio/stop", stop_audio)
             .get("/semantic-search", semantic_search_handler)
+            .get("/visual-search", visual_search_handler)
             .get("/pipes/build-status/:pipe_id", get_pipe_build_status)
             .get("/search/keyword", keyword_search_handler)
             .post("/v1/embeddings", create_embeddings)
             .post("/audio/device/start", start_audio_device)
             .post("/audio/device/stop", stop_audio_device)
+            .get("/audio/transcription-engine", get_transcription_engine)
+            .post("/audio/transcription-engine", set_transcription_engine)
             .route_yaml_spec("/openapi.yaml")
             .route_json_spec("/openapi.json")
             .freeze();
 
         // Build the main router with all routes
-        Router::new()
+        let router = Router::new()
             .merge(server.into_router())
             // NOTE: websockerts and sse is not supported by openapi so we move it down here
             .route("/stream/frames", get(stream_frames_handler))
             .route("/ws/events", get(ws_events_handler))
+            .route("/sse/events", get(sse_events_handler))
             .route("/ws/health", get(ws_health_handler))
             .route("/frames/export", get(handle_video_export_ws))
-            .with_state(app_state)
+            .with_state(app_state);
+        // before auth, what pipes read is checked against their permissions instead
+        if let Some(pipe_host) = &self.pipe_host {
+            pipe_host.set_api(router.clone());
+        }
+        // inside auth, so only the tokens it checked are counted apart from their address
+        let router = match &self.rate_limiter {
+            Some(rate_limiter) => router.layer(middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                limit_requests,
+            )),
+            None => router,
+        };
+        // inside cors, so refused requests still get its headers
+        let router = match &self.auth {
+            Some(auth) => router.layer(middleware::from_fn_with_state(auth.clone(), require_token)),
+            None => router,
+        };
+        router
+            .layer(DefaultBodyLimit::max(self.max_body_bytes))
             .layer(cors)
-            .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
+            // the fields of tower's default span, without the tokens of websocket urls
+            .layer(TraceLayer::new_for_http().make_span_with(
+                |request: &axum::http::Request<Body>| {
+                    tracing::debug_span!(
+                        "request",
+                        method = %request.method(),
+                        uri = %redacted_uri(request.uri()),
+                        version = ?request.version(),
+                    )
+                },
+            ))
     }
 }
 
@@ -1310,10 +2474,14 @@ async fn add_frame_to_db(
             device_name,
             Some(frame.timestamp.unwrap_or_else(Utc::now)),
             None,
+            None,
             frame.app_name.as_deref(),
+            None,
             frame.window_name.as_deref(),
+            None,
             false,
-            Some(frame.visible_percentage.unwrap_or(0.0))
+            Some(frame.visible_percentage.unwrap_or(0.0)),
+            None
         )
         .await?;
 
@@ -1324,6 +2492,7 @@ async fn add_frame_to_db(
                 &ocr.text,
                 ocr.text_json.as_deref().unwrap_or(""),
                 Arc::new(OcrEngine::default().into()), // Ideally could pass any str as ocr_engine since can be run outside of screenpipe
+                false,
             )
             .await?;
         }
@@ -1669,6 +2838,10 @@ pub struct GetUnnamedSpeakersRequest {
         default = "default_speaker_ids"
     )]
     speaker_ids: Option<Vec<i64>>,
+    /// `id` of the last speaker of the previous page, the page continues with the ones
+    /// heard less
+    #[serde(default)]
+    after_id: Option<i64>,
 }
 
 fn default_speaker_ids() -> Option<Vec<i64>> {
@@ -1703,7 +2876,12 @@ async fn get_unnamed_speakers_handler(
 ) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
     let speakers = state
         .db
-        .get_unnamed_speakers(request.limit, request.offset, request.speaker_ids)
+        .get_unnamed_speakers(
+            request.limit,
+            request.offset,
+            request.speaker_ids,
+            request.after_id,
+        )
         .await
         .map_err(|e| {
             (
@@ -1801,6 +2979,10 @@ async fn delete_speaker_handler(
 
     // delete all audio chunks from the file system
     for audio_chunk in audio_chunks {
+        // transcripts whose audio wasn't kept have no file
+        if audio_chunk.file_path.is_empty() {
+            continue;
+        }
         if audio_chunk.start_time.is_some() && audio_chunk.end_time.is_some() {
             std::fs::remove_file(audio_chunk.file_path).map_err(|e| {
                 (
@@ -1814,6 +2996,97 @@ async fn delete_speaker_handler(
     Ok(JsonResponse(json!({"success": true})))
 }
 
+#[oasgen]
+async fn enroll_speaker_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<EnrollSpeakerRequest>,
+) -> Result<JsonResponse<Speaker>, (StatusCode, JsonResponse<Value>)> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "name is required"})),
+        ));
+    }
+    let enrolled = match (payload.audio_path, payload.speaker_id) {
+        (Some(audio_path), None) => {
+            let decoded = tokio::task::spawn_blocking(move || pcm_decode(audio_path)).await;
+            let (samples, sample_rate) = match decoded {
+                Ok(Ok(decoded)) => decoded,
+                Ok(Err(e)) => {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        JsonResponse(json!({"error": format!("failed to read audio: {}", e)})),
+                    ))
+                }
+                Err(e) => {
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(json!({"error": e.to_string()})),
+                    ))
+                }
+            };
+            let embeddings = state
+                .audio_manager
+                .voice_embeddings(samples, sample_rate)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        JsonResponse(json!({"error": e.to_string()})),
+                    )
+                })?;
+            state.db.enroll_speaker(name, &embeddings).await
+        }
+        (None, Some(speaker_id)) => state.db.enroll_existing_speaker(speaker_id, name).await,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "either audio_path or speaker_id is required"})),
+            ))
+        }
+    };
+
+    match enrolled {
+        Ok(speaker) => Ok(JsonResponse(speaker)),
+        Err(sqlx::Error::RowNotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "speaker not found"})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+#[oasgen]
+async fn unenroll_speaker_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DeleteSpeakerRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    state.db.unenroll_speaker(payload.id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?;
+    Ok(JsonResponse(json!({"success": true})))
+}
+
+#[oasgen]
+async fn get_enrolled_speakers_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
+    let speakers = state.db.list_enrolled_speakers().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?;
+    Ok(JsonResponse(speakers))
+}
+
 #[oasgen]
 async fn mark_as_hallucination_handler(
     State(state): State<Arc<AppState>>,
@@ -1979,6 +3252,12 @@ pub struct AudioDeviceControlResponse {
 #[derive(OaSchema, Deserialize)]
 struct EventsQuery {
     images: Option<bool>,
+    /// Only send capture events of these comma separated types: ocr, transcript, app_focus
+    types: Option<String>,
+    /// Only send capture events of apps whose name contains one of these, comma separated
+    apps: Option<String>,
+    /// Only send capture events whose text contains one of these, comma separated
+    keywords: Option<String>,
 }
 
 #[derive(Debug, OaSchema, Deserialize)]
@@ -2016,7 +3295,9 @@ async fn semantic_search_handler(
     // Search database for similar embeddings
     match state
         .db
-        .search_similar_embeddings(embedding, limit, threshold)
+        .search_similar_embeddings(
+            embedding, limit, threshold, None, None, None, None, None, None,
+        )
         .await
     {
         Ok(results) => {
@@ -2033,6 +3314,59 @@ async fn semantic_search_handler(
     }
 }
 
+#[derive(Debug, OaSchema, Deserialize)]
+struct VisualSearchQuery {
+    q: String,
+    limit: Option<u32>,
+    #[serde(default)]
+    app_name: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// Frames that look like the description in `q`, needs `--image-embeddings`.
+#[oasgen]
+async fn visual_search_handler(
+    Query(query): Query<VisualSearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<FrameSimilarity>>, (StatusCode, JsonResponse<Value>)> {
+    let text = query.q.clone();
+    let embedding =
+        tokio::task::spawn_blocking(move || ImageEmbeddingModel::shared()?.embed_text(&text))
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+    let embedding = match embedding {
+        Ok(embedding) => embedding,
+        Err(e) => {
+            error!("failed to embed visual search query: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
+            ));
+        }
+    };
+
+    state
+        .db
+        .search_frames_by_embedding(
+            &embedding,
+            query.limit.unwrap_or(10),
+            query.app_name.as_deref(),
+            query.start_time,
+            query.end_time,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Database error: {}", e)})),
+            )
+        })
+}
+
 #[derive(Serialize, OaSchema, Deserialize)]
 pub struct VisionDeviceControlRequest {
     device_id: u32,
@@ -2119,10 +3453,21 @@ pub struct VisionDeviceControlResponse {
 
 // websocket events handler
 async fn ws_events_handler(ws: WebSocketUpgrade, query: Query<EventsQuery>) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, query))
+    let filter = match CaptureFilter::parse(
+        query.types.as_deref(),
+        query.apps.as_deref(),
+        query.keywords.as_deref(),
+    ) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))).into_response()
+        }
+    };
+    ws.on_upgrade(|socket| handle_socket(socket, query, filter))
 }
 
-async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
+/// Sends every event, or only the capture events matching `filter` when it is set.
+async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>, filter: CaptureFilter) {
     let (mut sender, mut receiver) = socket.split();
 
     let incoming = tokio::spawn(async move {
@@ -2143,6 +3488,12 @@ async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
             tokio::select! {
                 event = stream.next() => {
                     if let Some(mut event) = event {
+                        if !filter.is_empty()
+                            && !as_capture_event(&event)
+                                .is_some_and(|capture| filter.matches(&capture))
+                        {
+                            continue;
+                        }
                         if !query.images.unwrap_or(false) && (event.name == "ocr_result" || event.name == "ui_frame") {
                             if let Some(data) = event.data.as_object_mut() {
                                 data.remove("image");
@@ -2175,6 +3526,67 @@ async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
     debug!("WebSocket connection closed");
 }
 
+#[derive(Deserialize)]
+struct SseEventsQuery {
+    /// Only send events of these comma separated types: ocr, transcript, app_focus
+    types: Option<String>,
+    /// Only send events of apps whose name contains one of these, comma separated
+    apps: Option<String>,
+    /// Only send events whose text contains one of these, comma separated
+    keywords: Option<String>,
+    /// Resume after this event, for clients that can't set the `Last-Event-ID` header
+    last_event_id: Option<u64>,
+}
+
+// server-sent capture events, resumed after `Last-Event-ID` on reconnect
+async fn sse_events_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<SseEventsQuery>,
+) -> Response {
+    let filter = match CaptureFilter::parse(
+        query.types.as_deref(),
+        query.apps.as_deref(),
+        query.keywords.as_deref(),
+    ) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))).into_response()
+        }
+    };
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(query.last_event_id);
+
+    let (missed, receiver) = state.capture_log.resume(last_event_id);
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("sse client is too slow, skipped {} capture events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = futures::stream::iter(missed)
+        .chain(live)
+        .filter(move |(_, event)| futures::future::ready(filter.matches(event)))
+        .map(|(id, event)| {
+            SseEvent::default()
+                .id(id.to_string())
+                .event(event.kind())
+                .json_data(&event)
+        });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
 async fn ws_health_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
     ws.on_upgrade(move |socket| handle_health_socket(socket, state))
 }
@@ -2276,6 +3688,74 @@ async fn stop_audio_device(
     }))
 }
 
+#[derive(OaSchema, Debug, Deserialize)]
+struct TranscriptionEngineRequest {
+    /// Same names as `--audio-transcription-engine`, e.g. `whisper-small-quantized`
+    engine: String,
+}
+
+#[derive(OaSchema, Serialize, Deserialize)]
+pub struct TranscriptionEngineResponse {
+    /// Engine transcribing devices without one of their own
+    pub engine: String,
+    /// Engine whose model is being downloaded or loaded, it transcribes once it is
+    pub switching_to: Option<String>,
+    /// Why the last switch failed
+    pub switch_error: Option<String>,
+}
+
+#[oasgen]
+async fn get_transcription_engine(
+    State(state): State<Arc<AppState>>,
+) -> JsonResponse<TranscriptionEngineResponse> {
+    let (switching_to, switch_error) = match state.audio_manager.engine_switch().await {
+        Some(EngineSwitch::Loading(engine)) => (Some(engine.to_string()), None),
+        Some(EngineSwitch::Failed { engine, error }) => (
+            None,
+            Some(format!("failed to switch to {}: {}", engine, error)),
+        ),
+        None => (None, None),
+    };
+    JsonResponse(TranscriptionEngineResponse {
+        engine: state.audio_manager.transcription_engine().await.to_string(),
+        switching_to,
+        switch_error,
+    })
+}
+
+/// Swaps the transcription engine while audio keeps recording. Its model is downloaded in
+/// the background, poll `GET /audio/transcription-engine` until it transcribes.
+#[oasgen]
+async fn set_transcription_engine(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TranscriptionEngineRequest>,
+) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
+    let engine = <CliAudioTranscriptionEngine as ValueEnum>::from_str(&payload.engine, true)
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                JsonResponse(
+                    json!({"error": format!("unknown transcription engine: {}", payload.engine)}),
+                ),
+            )
+        })?;
+    if !state
+        .audio_manager
+        .switch_transcription_engine(engine.into())
+        .await
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": "the transcription engine is already being switched"})),
+        ));
+    }
+    Ok((
+        StatusCode::ACCEPTED,
+        get_transcription_engine(State(state)).await,
+    )
+        .into_response())
+}
+
 fn deserialize_frame_ids<'de, D>(deserializer: D) -> Result<Vec<i64>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -2675,6 +4155,7 @@ async fn keyword_search_handler(
             query.fuzzy_match,
             query.order,
             query.app_names,
+            query.after_frame_id,
         )
         .await
         .map_err(|e| {
@@ -2713,6 +4194,9 @@ pub struct KeywordSearchRequest {
     #[serde(default)]
     #[serde(deserialize_with = "from_comma_separated_string")]
     app_names: Option<Vec<String>>,
+    /// `frame_id` of the last match of the previous page, the page continues after it
+    #[serde(default)]
+    after_frame_id: Option<i64>,
 }
 
 #[oasgen]
@@ -2749,6 +4233,7 @@ pub async fn get_frame_data(
         // If not in cache or cache disabled, get from database
         match state.db.get_frame(frame_id).await {
             Ok(Some((file_path, offset_index))) => {
+                ensure_local(&state, &file_path).await;
                 match extract_frame_from_video(&file_path, offset_index).await {
                     Ok(frame_path) => {
                         // Store in cache if enabled and we can get the lock
@@ -2806,6 +4291,891 @@ pub async fn get_frame_data(
     }
 }
 
+#[oasgen]
+pub async fn get_frame_recording(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+) -> Result<JsonResponse<RecordingLink>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.get_video_segment_for_frame(frame_id).await {
+        Ok(Some((segment, timestamp))) => {
+            ensure_local(&state, &segment.file_path).await;
+            Ok(JsonResponse(RecordingLink::new(segment, timestamp)))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": "No recording covers this frame",
+                "frame_id": frame_id
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "error": format!("Database error: {}", e),
+                "frame_id": frame_id
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+pub async fn get_clip(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClipQuery>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    if let Err(e) = query.validate() {
+        return Err((StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))));
+    }
+
+    let output_dir = state.screenpipe_dir.join("clips");
+    let clip = match create_clip(&state.db, &query, &output_dir).await {
+        Ok(Some(clip)) => clip,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({
+                    "error": "Nothing was captured on this monitor in the requested range",
+                    "monitor": query.monitor
+                })),
+            ))
+        }
+        Err(e) => {
+            error!("Failed to create clip: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create clip: {}", e)})),
+            ));
+        }
+    };
+
+    let file = File::open(&clip.path).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to open clip: {}", e)})),
+        )
+    })?;
+    let file_name = clip
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // the clip is deleted once the stream is done with it
+    let stream = ReaderStream::new(file).map(move |chunk| {
+        let _clip = &clip;
+        chunk
+    });
+    Response::builder()
+        .header("content-type", query.format.content_type())
+        .header(
+            "content-disposition",
+            format!("inline; filename=\"{}\"", file_name),
+        )
+        .body(Body::from_stream(stream))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn get_timeline(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<JsonResponse<Timeline>, (StatusCode, JsonResponse<Value>)> {
+    if let Err(e) = query.validate() {
+        return Err((StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))));
+    }
+
+    collect_timeline(&state.db, &query)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to build timeline: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to build timeline: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn get_app_analytics(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AppAnalyticsQuery>,
+) -> Result<JsonResponse<AppAnalytics>, (StatusCode, JsonResponse<Value>)> {
+    let (start, end) = query
+        .bounds()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    app_analytics(&state.db, start, end)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to compute app usage: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to compute app usage: {}", e)})),
+            )
+        })
+}
+
+/// Minutes of keyboard and mouse input, recorded with `--enable-input-activity`, and
+/// whether they were active work, passive reading or idle.
+#[oasgen]
+pub async fn get_activity_analytics(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AppAnalyticsQuery>,
+) -> Result<JsonResponse<ActivityAnalytics>, (StatusCode, JsonResponse<Value>)> {
+    let (start, end) = query
+        .bounds()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    activity_analytics(&state.db, start, end)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to compute input activity: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to compute input activity: {}", e)})),
+            )
+        })
+}
+
+/// The digest of a UTC day, written when the day is over. Days without one are summarized
+/// on request.
+#[oasgen]
+pub async fn get_daily_digest(
+    State(state): State<Arc<AppState>>,
+    Path(date): Path<NaiveDate>,
+) -> Result<JsonResponse<DailyDigest>, (StatusCode, JsonResponse<Value>)> {
+    let Some(digests) = &state.digests else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "daily digests are not enabled, start with --daily-digest"}),
+            ),
+        ));
+    };
+    if date > Utc::now().date_naive() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "date is in the future", "date": date})),
+        ));
+    }
+
+    digests
+        .get_or_generate(date)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to get the digest of {}: {}", date, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(
+                    json!({"error": format!("Failed to get digest: {}", e), "date": date}),
+                ),
+            )
+        })
+}
+
+/// Summarizes the OCR text and transcriptions matching a query or captured in a time range
+/// with the configured LLM, citing the search results it was written from.
+#[oasgen]
+pub async fn summarize_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SummarizeRequest>,
+) -> Result<JsonResponse<Summary>, (StatusCode, JsonResponse<Value>)> {
+    let Some(llm) = &state.llm else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "summarization is not enabled, start with --llm-provider"}),
+            ),
+        ));
+    };
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    let sources = gather_sources(&state.db, &request).await.map_err(|e| {
+        error!("Failed to gather the text to summarize: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to search: {}", e)})),
+        )
+    })?;
+    if sources.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "nothing captured matches the request"})),
+        ));
+    }
+
+    summarize(llm, sources, request.instructions.as_deref())
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to summarize: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                JsonResponse(json!({"error": format!("Failed to summarize: {}", e)})),
+            )
+        })
+}
+
+/// Answers a question about the screen and audio history with the configured LLM, from
+/// the OCR text and transcriptions found by keyword and meaning.
+#[oasgen]
+pub async fn ask_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<AskRequest>,
+) -> Result<JsonResponse<Answer>, (StatusCode, JsonResponse<Value>)> {
+    let Some(llm) = &state.llm else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "question answering is not enabled, start with --llm-provider"}),
+            ),
+        ));
+    };
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    let now = Utc::now();
+    let sources = retrieve_sources(&state.db, &request, now)
+        .await
+        .map_err(|e| {
+            error!("Failed to retrieve the text to answer from: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to search: {}", e)})),
+            )
+        })?;
+    if sources.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "nothing captured matches the question"})),
+        ));
+    }
+
+    answer_question(llm, &request.question, sources, now)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to answer: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                JsonResponse(json!({"error": format!("Failed to answer: {}", e)})),
+            )
+        })
+}
+
+/// Registers a webhook the capture events matching its filters are posted to.
+#[oasgen]
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<WebhookRequest>,
+) -> Result<JsonResponse<Webhook>, (StatusCode, JsonResponse<Value>)> {
+    let filter = request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    state
+        .webhooks
+        .create(&request, filter)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to create webhook: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create webhook: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<Webhook>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_webhooks()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list webhooks: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.webhooks.delete(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "webhook not found", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to delete webhook {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct WebhookDeliveriesQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// `id` of the last delivery of the previous page, the page continues with older ones
+    #[serde(default)]
+    before_id: Option<i64>,
+}
+
+/// Every try to deliver an event to the webhook, newest first.
+#[oasgen]
+pub async fn list_webhook_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<JsonResponse<Vec<WebhookDelivery>>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: sqlx::Error| {
+        error!("Failed to list the deliveries of webhook {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+    if state
+        .db
+        .get_webhook(id)
+        .await
+        .map_err(internal_error)?
+        .is_none()
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "webhook not found", "id": id})),
+        ));
+    }
+    state
+        .db
+        .list_webhook_deliveries(
+            id,
+            query.before_id,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(internal_error)
+}
+
+/// Adds a rule raising an alert, stored and shown as a desktop notification, whenever
+/// captured text matches its pattern.
+#[oasgen]
+pub async fn create_alert_rule(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<AlertRuleRequest>,
+) -> Result<JsonResponse<AlertRule>, (StatusCode, JsonResponse<Value>)> {
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    state
+        .alerts
+        .create(&request)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to create alert rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create alert rule: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn list_alert_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<AlertRule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_alert_rules()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list alert rules: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn delete_alert_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.alerts.delete(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "alert rule not found", "id": id})),
+        )),
+        Err(e) => {
+            error!("Failed to delete alert rule {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct AlertEventsQuery {
+    /// Only the alerts of this rule
+    #[serde(default)]
+    rule_id: Option<i64>,
+    /// `id` of the last alert of the previous page, the page continues with older ones
+    #[serde(default)]
+    before_id: Option<i64>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+    #[serde(default)]
+    offset: u32,
+}
+
+/// The alerts raised, newest first.
+#[oasgen]
+pub async fn list_alert_events(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AlertEventsQuery>,
+) -> Result<JsonResponse<Vec<AlertEvent>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_alert_events(query.rule_id, query.before_id, query.limit, query.offset)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to list alerts: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn search_documents(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DocumentsQuery>,
+) -> Result<JsonResponse<Vec<StitchedDocument>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .search_scroll_documents(
+            &query.q,
+            query.app_name.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Database error: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn get_document(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<StitchedDocument>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.get_scroll_document(id).await {
+        Ok(Some(document)) => Ok(JsonResponse(document)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "Document not found", "id": id})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Database error: {}", e), "id": id})),
+        )),
+    }
+}
+
+#[oasgen]
+pub async fn search_codes(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CodesQuery>,
+) -> Result<JsonResponse<Vec<FrameCode>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .search_frame_codes(
+            &query.q,
+            query.format.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Database error: {}", e)})),
+            )
+        })
+}
+
+/// What the retention policy would delete if it ran now, without deleting anything.
+#[oasgen]
+pub async fn get_retention_report(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<RetentionReport>, (StatusCode, JsonResponse<Value>)> {
+    let Some(retention) = &state.retention else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "retention is not enabled, start with --enable-retention"}),
+            ),
+        ));
+    };
+
+    retention
+        .enforce(true)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("failed to compute retention report: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Deletes the frames, text, audio and embeddings matching the filters in one go, e.g.
+/// `DELETE /data?app=zoom&before=2024-01-01`, and the recordings left without data. With
+/// `dry_run=true` only reports what would be deleted.
+#[oasgen]
+pub async fn delete_data_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DeleteDataQuery>,
+) -> Result<JsonResponse<DataDeletionReport>, (StatusCode, JsonResponse<Value>)> {
+    let filter = query
+        .filter()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    let blob_store = FrameBlobStore::new(
+        state.db.clone(),
+        &state.screenpipe_dir.join("data").to_string_lossy(),
+    );
+
+    delete_data(
+        &state.db,
+        &blob_store,
+        state.cold_storage.as_deref(),
+        &filter,
+        query.dry_run,
+    )
+    .await
+    .map(JsonResponse)
+    .map_err(|e| {
+        error!("failed to delete data: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })
+}
+
+/// Starts exporting everything recorded in a time range to a zip archive, poll
+/// `/exports/:id` for its progress.
+#[oasgen]
+pub async fn create_export(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ExportRequest>,
+) -> Result<JsonResponse<ExportJob>, (StatusCode, JsonResponse<Value>)> {
+    request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    state
+        .exports
+        .start(request)
+        .map(JsonResponse)
+        .map_err(|e| (StatusCode::CONFLICT, JsonResponse(json!({"error": e}))))
+}
+
+#[oasgen]
+pub async fn list_exports(State(state): State<Arc<AppState>>) -> JsonResponse<Vec<ExportJob>> {
+    JsonResponse(state.exports.list())
+}
+
+fn export_not_found(id: &str) -> (StatusCode, JsonResponse<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        JsonResponse(json!({"error": "export not found", "id": id})),
+    )
+}
+
+#[oasgen]
+pub async fn get_export(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<JsonResponse<ExportJob>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .exports
+        .get(&id)
+        .map(JsonResponse)
+        .ok_or_else(|| export_not_found(&id))
+}
+
+/// The archive of a finished export.
+#[oasgen]
+pub async fn download_export(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let Some(path) = state.exports.archive(&id) else {
+        return Err(match state.exports.get(&id) {
+            Some(job) => (
+                StatusCode::CONFLICT,
+                JsonResponse(json!({"error": "export is not done", "status": job.status})),
+            ),
+            None => export_not_found(&id),
+        });
+    };
+
+    let file = File::open(&path).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to open export: {}", e)})),
+        )
+    })?;
+    Response::builder()
+        .header("content-type", "application/zip")
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"screenpipe-export-{}.zip\"", id),
+        )
+        .body(Body::from_stream(ReaderStream::new(file)))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Failed to create response: {}", e)})),
+            )
+        })
+}
+
+/// Deletes a finished export and its archive.
+#[oasgen]
+pub async fn delete_export(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    if state
+        .exports
+        .get(&id)
+        .is_some_and(|job| job.status == ExportStatus::Running)
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": "export is still running", "id": id})),
+        ));
+    }
+    match state.exports.delete(&id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err(export_not_found(&id)),
+        Err(e) => {
+            error!("failed to delete export {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string(), "id": id})),
+            ))
+        }
+    }
+}
+
+/// Transcribes the audio recorded in a time range again with a better model, poll
+/// `/retranscribe/:id` for its progress. Replaced transcripts are kept as versions.
+#[oasgen]
+pub async fn create_retranscription(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<RetranscribeRequest>,
+) -> Result<JsonResponse<RetranscriptionJob>, (StatusCode, JsonResponse<Value>)> {
+    let engine = request
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    state
+        .retranscriptions
+        .start(request, engine)
+        .map(JsonResponse)
+        .map_err(|e| (StatusCode::CONFLICT, JsonResponse(json!({"error": e}))))
+}
+
+#[oasgen]
+pub async fn list_retranscriptions(
+    State(state): State<Arc<AppState>>,
+) -> JsonResponse<Vec<RetranscriptionJob>> {
+    JsonResponse(state.retranscriptions.list())
+}
+
+#[oasgen]
+pub async fn get_retranscription(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<JsonResponse<RetranscriptionJob>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .retranscriptions
+        .get(&id)
+        .map(JsonResponse)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "re-transcription not found", "id": id})),
+            )
+        })
+}
+
+/// The transcripts an audio transcription had before it was re-transcribed, oldest first.
+#[oasgen]
+pub async fn get_transcription_versions(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Vec<AudioTranscriptionVersion>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .get_audio_transcription_versions(id)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("Failed to get the versions of transcription {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Fetches a media file back from cold storage, before opening it.
+#[oasgen]
+pub async fn fetch_cold_media(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ColdFetchQuery>,
+) -> Result<JsonResponse<ColdFetchResponse>, (StatusCode, JsonResponse<Value>)> {
+    let Some(cold_storage) = &state.cold_storage else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(
+                json!({"error": "cold storage is not enabled, start with --cold-storage"}),
+            ),
+        ));
+    };
+
+    match cold_storage.ensure_local(&query.path).await {
+        Ok(fetched) => Ok(JsonResponse(ColdFetchResponse {
+            path: query.path,
+            fetched,
+        })),
+        Err(e) => {
+            error!("failed to fetch {} from cold storage: {}", query.path, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+/// Fetches `file_path` back from cold storage if it was moved there. Errors are left to
+/// the read that follows.
+async fn ensure_local(state: &AppState, file_path: &str) {
+    if let Some(cold_storage) = &state.cold_storage {
+        if let Err(e) = cold_storage.ensure_local(file_path).await {
+            warn!("failed to fetch {} from cold storage: {}", file_path, e);
+        }
+    }
+}
+
+/// Disk usage of the data dir and the quota it is kept under.
+#[oasgen]
+pub async fn get_storage_usage(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<StorageUsage>, (StatusCode, JsonResponse<Value>)> {
+    state.storage.usage().await.map(JsonResponse).map_err(|e| {
+        error!("failed to compute storage usage: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })
+}
+
+#[oasgen]
+pub async fn search_tables(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TablesQuery>,
+) -> Result<JsonResponse<Vec<FrameTable>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .search_tables(
+            &query.q,
+            query.app_name.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("Database error: {}", e)})),
+            )
+        })
+}
+
+#[oasgen]
+pub async fn get_frame_tables(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+) -> Result<JsonResponse<Vec<FrameTable>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .get_tables_for_frame(frame_id)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({
+                    "error": format!("Database error: {}", e),
+                    "frame_id": frame_id
+                })),
+            )
+        })
+}
+
 async fn serve_file(path: &str) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
     match File::open(path).await {
         Ok(file) => {
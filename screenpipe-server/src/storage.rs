@@ -0,0 +1,288 @@
+use crate::frame_storage::FrameBlobStore;
+use crate::retention::{delete_media, disk_usage};
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, MediaFile, MediaKind};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+// Files of each kind looked up per eviction round
+const EVICTION_BATCH: i64 = 200;
+// Media this recent is never evicted, it may still be written to
+const EVICTION_MIN_AGE_MINUTES: i64 = 10;
+// How often the background task checks the quota
+const QUOTA_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const UNITS: &[(&str, u64)] = &[
+    ("TB", 1 << 40),
+    ("GB", 1 << 30),
+    ("MB", 1 << 20),
+    ("KB", 1 << 10),
+    ("B", 1),
+];
+
+/// Maximum size of the data dir, parsed from e.g. "50GB" or "500 MB". Units are
+/// multiples of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageQuota(pub u64);
+
+impl FromStr for StorageQuota {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_uppercase();
+        let (number, unit) = UNITS
+            .iter()
+            .find_map(|(name, unit)| {
+                upper
+                    .strip_suffix(name)
+                    .map(|number| (number.trim().to_string(), *unit))
+            })
+            // plain numbers are gigabytes
+            .unwrap_or((upper.clone(), 1 << 30));
+
+        match number.parse::<f64>() {
+            Ok(number) if number > 0.0 => Ok(StorageQuota((number * unit as f64) as u64)),
+            _ => Err(format!(
+                "invalid storage quota '{}', expected a size like 50GB",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StorageQuota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_bytes(self.0))
+    }
+}
+
+/// `bytes` in the largest unit it has at least one of, e.g. "1.5 GB".
+pub fn format_bytes(bytes: u64) -> String {
+    let (name, unit) = UNITS
+        .iter()
+        .find(|(_, unit)| bytes >= *unit)
+        .unwrap_or(&("B", 1));
+    if *unit == 1 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", bytes as f64 / *unit as f64, name)
+    }
+}
+
+#[derive(OaSchema, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Size of the whole data dir
+    pub total_bytes: u64,
+    /// The database with its write-ahead log
+    pub database_bytes: u64,
+    /// Recorded frames and audio
+    pub media_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    /// Time of the oldest media still on disk, everything before it was evicted or purged
+    pub oldest_media: Option<DateTime<Utc>>,
+}
+
+/// Measures the data dir and keeps it under the quota by evicting the oldest frames
+/// and audio. The text recorded from them stays.
+pub struct StorageManager {
+    db: Arc<DatabaseManager>,
+    screenpipe_dir: PathBuf,
+    quota: Option<StorageQuota>,
+    blob_store: FrameBlobStore,
+}
+
+impl StorageManager {
+    /// `screenpipe_dir` holds the database and the `data` dir media is recorded to.
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        screenpipe_dir: PathBuf,
+        quota: Option<StorageQuota>,
+    ) -> Self {
+        let output_path = screenpipe_dir.join("data");
+        Self {
+            blob_store: FrameBlobStore::new(db.clone(), &output_path.to_string_lossy()),
+            db,
+            screenpipe_dir,
+            quota,
+        }
+    }
+
+    pub fn quota(&self) -> Option<StorageQuota> {
+        self.quota
+    }
+
+    pub async fn usage(&self) -> Result<StorageUsage> {
+        let screenpipe_dir = self.screenpipe_dir.clone();
+        let (total_bytes, database_bytes, media_bytes) = tokio::task::spawn_blocking(move || {
+            let database_bytes = ["db.sqlite", "db.sqlite-wal", "db.sqlite-shm"]
+                .iter()
+                .filter_map(|name| std::fs::metadata(screenpipe_dir.join(name)).ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            (
+                dir_size(&screenpipe_dir),
+                database_bytes,
+                dir_size(&screenpipe_dir.join("data")),
+            )
+        })
+        .await?;
+
+        let mut oldest_media = None;
+        for kind in [
+            MediaKind::VideoChunk,
+            MediaKind::VideoSegment,
+            MediaKind::AudioChunk,
+        ] {
+            if let Some(file) = self
+                .db
+                .get_media_to_purge(kind, Utc::now(), 1)
+                .await?
+                .into_iter()
+                .next()
+            {
+                oldest_media = Some(match oldest_media {
+                    Some(oldest) if oldest < file.timestamp => oldest,
+                    _ => file.timestamp,
+                });
+            }
+        }
+
+        Ok(StorageUsage {
+            total_bytes,
+            database_bytes,
+            media_bytes,
+            quota_bytes: self.quota.map(|quota| quota.0),
+            oldest_media,
+        })
+    }
+
+    /// Evicts the oldest media until the data dir fits the quota, except the files of
+    /// pinned frames. Returns the number of bytes freed.
+    pub async fn enforce_quota(&self) -> Result<u64> {
+        let Some(quota) = self.quota else {
+            return Ok(0);
+        };
+        let usage = self.usage().await?;
+        if usage.total_bytes <= quota.0 {
+            return Ok(0);
+        }
+
+        let mut excess = usage.total_bytes - quota.0;
+        let mut freed = 0;
+        let before = Utc::now() - ChronoDuration::minutes(EVICTION_MIN_AGE_MINUTES);
+        while excess > 0 {
+            let candidates = self.eviction_candidates(before).await?;
+            if candidates.is_empty() {
+                warn!(
+                    "data dir is {} over the {} quota but no media is left to evict",
+                    format_bytes(excess),
+                    quota
+                );
+                break;
+            }
+
+            for (kind, file) in candidates {
+                let bytes = disk_usage(Path::new(&file.file_path)).await;
+                if let Err(e) = delete_media(&self.blob_store, kind, &file).await {
+                    // retried on the next check
+                    warn!("failed to evict {}: {}", file.file_path, e);
+                    return Ok(freed);
+                }
+                self.db.mark_media_purged(kind, &[file.id]).await?;
+                debug!("evicted {} ({})", file.file_path, format_bytes(bytes));
+
+                freed += bytes;
+                excess = excess.saturating_sub(bytes);
+                if excess == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(freed)
+    }
+
+    /// The oldest media of all kinds, oldest first. Stops where a kind's batch ends, so
+    /// older files of that kind can't come after newer ones of another.
+    async fn eviction_candidates(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<(MediaKind, MediaFile)>> {
+        let mut candidates = Vec::new();
+        let mut horizon: Option<DateTime<Utc>> = None;
+        for kind in [
+            MediaKind::VideoChunk,
+            MediaKind::VideoSegment,
+            MediaKind::AudioChunk,
+        ] {
+            // files moved to cold storage have nothing left on disk, pinned frames are kept
+            let files = self
+                .db
+                .get_media_to_evict(kind, before, EVICTION_BATCH)
+                .await?;
+            if files.len() as i64 == EVICTION_BATCH {
+                let last = files[files.len() - 1].timestamp;
+                horizon = Some(horizon.map_or(last, |horizon| horizon.min(last)));
+            }
+            candidates.extend(files.into_iter().map(|file| (kind, file)));
+        }
+
+        candidates.sort_by_key(|(_, file)| file.timestamp);
+        if let Some(horizon) = horizon {
+            candidates.retain(|(_, file)| file.timestamp <= horizon);
+        }
+        Ok(candidates)
+    }
+}
+
+/// Size of the files under `path`. Frame blobs are hard linked into image chunks, each
+/// file is counted once.
+pub fn dir_size(path: &Path) -> u64 {
+    let mut seen = HashSet::new();
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .filter(|metadata| file_id(metadata).map_or(true, |id| seen.insert(id)))
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(unix)]
+pub(crate) fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// without inodes every file counts
+#[cfg(not(unix))]
+pub(crate) fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Checks the quota every few minutes.
+pub async fn run_storage_quota(storage: Arc<StorageManager>) {
+    if let Some(quota) = storage.quota() {
+        info!("keeping the data dir under {}", quota);
+    }
+    loop {
+        match storage.enforce_quota().await {
+            Ok(0) => {}
+            Ok(freed) => info!(
+                "evicted {} of old media to stay under the quota",
+                format_bytes(freed)
+            ),
+            Err(e) => warn!("failed to enforce the storage quota: {}", e),
+        }
+        tokio::time::sleep(QUOTA_INTERVAL).await;
+    }
+}
@@ -0,0 +1,304 @@
+use crate::llm::Llm;
+use crate::search_query::SearchQueryFilters;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_db::{ContentType, DatabaseManager, SearchResult, SearchSort};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Most search results one summary is written from
+pub const MAX_SUMMARIZE_CHUNKS: u32 = 1000;
+const DEFAULT_SUMMARIZE_CHUNKS: u32 = 200;
+// Longer texts are cut before they go into the prompt
+const MAX_SOURCE_CHARS: usize = 1000;
+// Sources past this many characters of prompt are left out, newest first
+const MAX_PROMPT_CHARS: usize = 48_000;
+const SYSTEM_PROMPT: &str = "You summarize what a person saw on their screen and heard in \
+                             their meetings. The sources are numbered, cite the ones each \
+                             statement comes from like [2] or [2, 5]. Only state what is in the \
+                             sources, they are OCR and speech to text so expect typos.";
+
+static CITATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap());
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct SummarizeRequest {
+    /// Summarize what matches this query, in the syntax of `/search`
+    #[serde(default)]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    /// OCR text and transcriptions by default
+    #[serde(default = "default_content_type")]
+    pub content_type: ContentType,
+    /// Most search results to summarize, up to 1000
+    #[serde(default = "default_summarize_limit")]
+    pub limit: u32,
+    /// What the summary should focus on, e.g. "decisions made and who owns them"
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+fn default_content_type() -> ContentType {
+    ContentType::AudioAndOcr
+}
+
+fn default_summarize_limit() -> u32 {
+    DEFAULT_SUMMARIZE_CHUNKS
+}
+
+impl SummarizeRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let has_query = self.q.as_deref().is_some_and(|q| !q.trim().is_empty());
+        if !has_query && self.start_time.is_none() && self.end_time.is_none() {
+            return Err("a query, a start_time or an end_time is required".to_string());
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            if end <= start {
+                return Err("end_time must be after start_time".to_string());
+            }
+        }
+        if self.limit == 0 || self.limit > MAX_SUMMARIZE_CHUNKS {
+            return Err(format!(
+                "limit must be between 1 and {}",
+                MAX_SUMMARIZE_CHUNKS
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Ocr,
+    Audio,
+    Ui,
+    Clipboard,
+}
+
+/// A piece of captured text the summary was written from.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SummarySource {
+    /// Number the summary cites it by, e.g. `[3]`
+    pub reference: usize,
+    #[serde(rename = "type")]
+    pub kind: SourceKind,
+    /// Frame id of OCR text, audio chunk id of a transcription, UI monitoring id of UI text,
+    /// clipboard entry id of copied text
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    /// Device a transcription was recorded on
+    pub device_name: Option<String>,
+    pub text: String,
+    /// `/frames/:frame_id` of OCR text, the screenshot it was read from
+    pub frame_url: Option<String>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct Summary {
+    pub summary: String,
+    pub provider: String,
+    pub model: String,
+    /// The sources cited in `summary`, oldest first
+    pub sources: Vec<SummarySource>,
+    /// Sources the summary was written from
+    pub source_count: usize,
+}
+
+/// Numbers the distinct texts of `results` oldest first, up to the prompt budget.
+pub fn collect_sources(results: Vec<SearchResult>) -> Vec<SummarySource> {
+    let mut sources: Vec<SummarySource> = results
+        .into_iter()
+        .map(|result| match result {
+            SearchResult::OCR(ocr) => SummarySource {
+                reference: 0,
+                kind: SourceKind::Ocr,
+                id: ocr.frame_id,
+                timestamp: ocr.timestamp,
+                app_name: Some(ocr.app_name),
+                window_name: Some(ocr.window_name),
+                device_name: None,
+                text: ocr.ocr_text,
+                frame_url: Some(format!("/frames/{}", ocr.frame_id)),
+            },
+            SearchResult::Audio(audio) => SummarySource {
+                reference: 0,
+                kind: SourceKind::Audio,
+                id: audio.audio_chunk_id,
+                timestamp: audio.timestamp,
+                app_name: None,
+                window_name: None,
+                device_name: Some(audio.device_name),
+                text: audio.transcription,
+                frame_url: None,
+            },
+            SearchResult::UI(ui) => SummarySource {
+                reference: 0,
+                kind: SourceKind::Ui,
+                id: ui.id,
+                timestamp: ui.timestamp,
+                app_name: Some(ui.app_name),
+                window_name: Some(ui.window_name),
+                device_name: None,
+                text: ui.text,
+                frame_url: None,
+            },
+            SearchResult::Clipboard(entry) => SummarySource {
+                reference: 0,
+                kind: SourceKind::Clipboard,
+                id: entry.id,
+                timestamp: entry.timestamp,
+                app_name: entry.app_name,
+                window_name: entry.window_name,
+                device_name: None,
+                text: entry.text,
+                frame_url: None,
+            },
+        })
+        .collect();
+
+    // newest first, so the budget cuts the oldest
+    sources.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let mut seen = HashSet::new();
+    let mut prompt_chars = 0;
+    let mut kept = Vec::new();
+    for mut source in sources {
+        let text = source.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() || !seen.insert(text.clone()) {
+            continue;
+        }
+        source.text = match text.char_indices().nth(MAX_SOURCE_CHARS) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        };
+        prompt_chars += source.text.len();
+        if prompt_chars > MAX_PROMPT_CHARS && !kept.is_empty() {
+            break;
+        }
+        kept.push(source);
+    }
+
+    kept.reverse();
+    for (i, source) in kept.iter_mut().enumerate() {
+        source.reference = i + 1;
+    }
+    kept
+}
+
+/// The numbered sources, one per line, followed by the instructions.
+pub fn build_prompt(sources: &[SummarySource], instructions: Option<&str>) -> String {
+    let mut prompt = String::from("Sources:\n");
+    for source in sources {
+        let origin = match source.kind {
+            SourceKind::Audio => format!(
+                "audio, {}",
+                source.device_name.as_deref().unwrap_or_default()
+            ),
+            SourceKind::Ocr | SourceKind::Ui => format!(
+                "screen, {} - {}",
+                source.app_name.as_deref().unwrap_or_default(),
+                source.window_name.as_deref().unwrap_or_default()
+            ),
+            SourceKind::Clipboard => format!(
+                "clipboard, {}",
+                source.app_name.as_deref().unwrap_or_default()
+            ),
+        };
+        prompt.push_str(&format!(
+            "[{}] {} ({}): {}\n",
+            source.reference,
+            source.timestamp.format("%Y-%m-%d %H:%M"),
+            origin,
+            source.text
+        ));
+    }
+    prompt.push('\n');
+    match instructions.map(str::trim).filter(|text| !text.is_empty()) {
+        Some(instructions) => prompt.push_str(instructions),
+        None => prompt.push_str("Summarize the sources."),
+    }
+    prompt
+}
+
+/// The source numbers cited in `summary`, as `[2]` or `[2, 5]`.
+pub fn cited_references(summary: &str) -> BTreeSet<usize> {
+    CITATION
+        .captures_iter(summary)
+        .flat_map(|captures| {
+            captures[1]
+                .split(',')
+                .filter_map(|reference| reference.trim().parse().ok())
+                .collect::<Vec<usize>>()
+        })
+        .collect()
+}
+
+/// The search results matching `request`, as numbered sources.
+pub async fn gather_sources(
+    db: &DatabaseManager,
+    request: &SummarizeRequest,
+) -> Result<Vec<SummarySource>, sqlx::Error> {
+    let filters = SearchQueryFilters::parse(request.q.as_deref().unwrap_or_default());
+    let match_query = filters.fts_query();
+    let sort = if match_query.is_empty() {
+        SearchSort::Time
+    } else {
+        SearchSort::Relevance
+    };
+    let results = db
+        .search(
+            &match_query,
+            request.content_type.clone(),
+            request.limit,
+            0,
+            request.start_time.or(filters.after),
+            request.end_time.or(filters.before),
+            filters.app_name.as_deref(),
+            filters.window_name.as_deref(),
+            None,
+            None,
+            None,
+            None,
+            filters.browser_url.as_deref(),
+            filters.focused,
+            None,
+            None,
+            filters.app_id.as_deref(),
+            true,
+            &filters.exclusions(),
+            None,
+            sort,
+        )
+        .await?;
+    Ok(collect_sources(results))
+}
+
+/// Asks `llm` for a summary of `sources`, which are returned when cited.
+pub async fn summarize(
+    llm: &Llm,
+    sources: Vec<SummarySource>,
+    instructions: Option<&str>,
+) -> Result<Summary> {
+    let summary = llm
+        .complete(SYSTEM_PROMPT, &build_prompt(&sources, instructions))
+        .await?;
+    let cited = cited_references(&summary);
+    let source_count = sources.len();
+    Ok(Summary {
+        summary,
+        provider: llm.provider.to_string(),
+        model: llm.model.clone(),
+        sources: sources
+            .into_iter()
+            .filter(|source| cited.contains(&source.reference))
+            .collect(),
+        source_count,
+    })
+}
@@ -1,7 +1,14 @@
 use anyhow::Result;
 use reqwest::Client;
+use screenpipe_db::{DatabaseManager, VectorCollection};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+// Texts of each kind embedded per round
+const EMBEDDING_BATCH: u32 = 32;
+// Wait once everything is embedded, or after ollama failed
+const EMBEDDER_IDLE: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Serialize)]
 struct OllamaRequest {
@@ -47,3 +54,60 @@ pub async fn generate_embedding(text: &str, frame_id: i64) -> Result<Vec<f32>> {
     
     Ok(embedding.embedding)
 }
+
+/// Embeds OCR text and transcriptions that have no embedding yet, for
+/// `/search?mode=semantic`. New text is embedded first, history after it.
+pub async fn run_text_embedder(db: Arc<DatabaseManager>) {
+    info!("starting text embedder");
+    loop {
+        match embed_pending_text(&db).await {
+            Ok(0) => tokio::time::sleep(EMBEDDER_IDLE).await,
+            Ok(count) => debug!("embedded {} texts", count),
+            Err(e) => {
+                warn!("failed to embed text, retrying later: {}", e);
+                tokio::time::sleep(EMBEDDER_IDLE).await;
+            }
+        }
+    }
+}
+
+/// Embeds a batch of each kind of text. Texts ollama fails on are left out of the batch and
+/// tried again a few times, unless it failed on all of them and likely isn't running.
+async fn embed_pending_text(db: &DatabaseManager) -> Result<usize> {
+    let ocr_text = db.get_ocr_text_without_embeddings(EMBEDDING_BATCH).await?;
+    let transcriptions = db
+        .get_transcriptions_without_embeddings(EMBEDDING_BATCH)
+        .await?;
+    let pending = ocr_text.len() + transcriptions.len();
+
+    let mut failed = Vec::new();
+    let mut last_error = None;
+    let mut count = 0;
+    for (collection, texts) in [
+        (VectorCollection::OcrText, ocr_text),
+        (VectorCollection::Transcriptions, transcriptions),
+    ] {
+        let mut embeddings = Vec::new();
+        for (id, text) in texts {
+            match generate_embedding(&text, id).await {
+                Ok(embedding) => embeddings.push((id, embedding)),
+                Err(e) => {
+                    failed.push((collection, id));
+                    last_error = Some(e);
+                }
+            }
+        }
+        db.upsert_embeddings(collection, &embeddings).await?;
+        count += embeddings.len();
+    }
+
+    if let Some(e) = last_error {
+        if failed.len() == pending {
+            return Err(e);
+        }
+    }
+    for (collection, id) in failed {
+        db.record_embedding_failure(collection, id).await?;
+    }
+    Ok(count)
+}
@@ -0,0 +1,408 @@
+use crate::analytics::{activity_level, ActivityLevel};
+use crate::input_activity::minute_of;
+use crate::pagination::{decode_cursor, encode_cursor};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::try_join;
+use oasgen::OaSchema;
+use screenpipe_db::{
+    Annotation, AudioDeviceEvent, CapturePause, DatabaseManager, InputActivity, TimelineFrame,
+    TimelineTranscription,
+};
+use serde::{Deserialize, Serialize};
+
+/// Most frames, and most transcriptions, returned by one timeline request
+pub const MAX_TIMELINE_ITEMS: u32 = 10_000;
+const DEFAULT_TIMELINE_ITEMS: u32 = 1000;
+// Characters of OCR text sent along with each frame
+const SNIPPET_CHARS: u32 = 200;
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct TimelineQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Most frames and most transcriptions to return, up to 10000
+    #[serde(default = "default_timeline_limit")]
+    pub limit: u32,
+    /// `next_cursor` of the previous page, continues past the frames and transcriptions at
+    /// `next_start` it already returned. Replaces `start`
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Where the next page of the timeline starts: at `start`, the frames from `frame_id` on
+/// and the transcriptions from `transcription_id` on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimelineCursor {
+    pub start: DateTime<Utc>,
+    pub frame_id: i64,
+    pub transcription_id: i64,
+}
+
+impl TimelineCursor {
+    pub fn encode(&self) -> String {
+        encode_cursor(self)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        decode_cursor(cursor)
+    }
+}
+
+fn default_timeline_limit() -> u32 {
+    DEFAULT_TIMELINE_ITEMS
+}
+
+impl TimelineQuery {
+    /// Where the page starts, the cursor when set.
+    pub fn page_start(&self) -> Result<TimelineCursor, String> {
+        match &self.cursor {
+            Some(cursor) => TimelineCursor::decode(cursor),
+            None => Ok(TimelineCursor {
+                start: self.start,
+                frame_id: 0,
+                transcription_id: 0,
+            }),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.page_start()?;
+        if self.end <= self.start {
+            return Err("end must be after start".to_string());
+        }
+        if self.limit == 0 || self.limit > MAX_TIMELINE_ITEMS {
+            return Err(format!(
+                "limit must be between 1 and {}",
+                MAX_TIMELINE_ITEMS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A frame captured on any monitor.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameEvent {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub browser_url: Option<String>,
+}
+
+/// Start of the text read from a frame.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OcrSnippet {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+/// The focused app or window changed.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AppFocus {
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub app_name: String,
+    pub window_name: String,
+}
+
+/// Keyboard and mouse input went from one level to another, recorded with
+/// `--enable-input-activity`.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActivityChange {
+    pub timestamp: DateTime<Utc>,
+    /// `None` once input stopped being recorded
+    pub level: Option<ActivityLevel>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
+pub enum TimelineItem {
+    /// Active work, passive reading or idle from here on
+    Activity(ActivityChange),
+    /// Shown where it starts
+    Annotation(Annotation),
+    AppFocus(AppFocus),
+    /// An audio device disconnected or came back, and where recording moved meanwhile
+    AudioDevice(AudioDeviceEvent),
+    Frame(FrameEvent),
+    Ocr(OcrSnippet),
+    /// Capture paused, e.g. while the computer was idle or locked
+    Paused(CapturePause),
+    /// Capture resumed after the pause
+    Resumed(CapturePause),
+    Transcript(TimelineTranscription),
+}
+
+impl TimelineItem {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            TimelineItem::Activity(change) => change.timestamp,
+            TimelineItem::Annotation(annotation) => annotation.start_time,
+            TimelineItem::AppFocus(focus) => focus.timestamp,
+            TimelineItem::AudioDevice(event) => event.timestamp,
+            TimelineItem::Frame(frame) => frame.timestamp,
+            TimelineItem::Ocr(snippet) => snippet.timestamp,
+            TimelineItem::Paused(pause) => pause.start_time,
+            TimelineItem::Resumed(pause) => pause.end_time.unwrap_or(pause.start_time),
+            TimelineItem::Transcript(transcription) => transcription.timestamp,
+        }
+    }
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct Timeline {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Oldest first
+    pub items: Vec<TimelineItem>,
+    /// Set when `limit` cut the timeline short, request again from there for the rest
+    pub next_start: Option<DateTime<Utc>>,
+    /// Set along with `next_start`, requesting with it leaves out what this page returned
+    /// at `next_start`
+    pub next_cursor: Option<String>,
+}
+
+/// Interleaves frames and transcriptions, both oldest first, into one timeline. Every
+/// frame is followed by its OCR snippet and preceded by an app focus event when the
+/// focused app or window differs from the one before, the first frame always has one.
+pub fn merge_timeline(
+    frames: Vec<TimelineFrame>,
+    transcriptions: Vec<TimelineTranscription>,
+) -> Vec<TimelineItem> {
+    let mut items = Vec::with_capacity(frames.len() * 2 + transcriptions.len());
+    let mut focus: Option<(String, String)> = None;
+
+    for frame in frames {
+        // frames of other monitors and windows in the background don't move the focus
+        if frame.focused != Some(false) {
+            let current = (frame.app_name.clone(), frame.window_name.clone());
+            if focus.as_ref() != Some(&current) {
+                items.push(TimelineItem::AppFocus(AppFocus {
+                    timestamp: frame.timestamp,
+                    device_name: frame.device_name.clone(),
+                    app_name: current.0.clone(),
+                    window_name: current.1.clone(),
+                }));
+                focus = Some(current);
+            }
+        }
+        let snippet = frame.snippet.filter(|text| !text.is_empty());
+        items.push(TimelineItem::Frame(FrameEvent {
+            frame_id: frame.frame_id,
+            timestamp: frame.timestamp,
+            device_name: frame.device_name,
+            app_name: frame.app_name,
+            window_name: frame.window_name,
+            browser_url: frame.browser_url,
+        }));
+        if let Some(text) = snippet {
+            items.push(TimelineItem::Ocr(OcrSnippet {
+                frame_id: frame.frame_id,
+                timestamp: frame.timestamp,
+                text,
+            }));
+        }
+    }
+    items.extend(transcriptions.into_iter().map(TimelineItem::Transcript));
+
+    // stable, a frame keeps its focus event and snippet around it
+    items.sort_by_key(TimelineItem::timestamp);
+    items
+}
+
+/// Where capture paused and resumed between `start` and `end`, oldest first.
+pub fn pause_events(
+    pauses: Vec<CapturePause>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<TimelineItem> {
+    let in_range = |timestamp: DateTime<Utc>| timestamp >= start && timestamp < end;
+    let mut items: Vec<TimelineItem> = pauses
+        .into_iter()
+        .flat_map(|pause| {
+            let paused = in_range(pause.start_time).then(|| TimelineItem::Paused(pause.clone()));
+            let resumed = pause
+                .end_time
+                .filter(|end_time| in_range(*end_time))
+                .map(|_| TimelineItem::Resumed(pause));
+            paused.into_iter().chain(resumed)
+        })
+        .collect();
+    items.sort_by_key(TimelineItem::timestamp);
+    items
+}
+
+/// Where the activity level of the minutes, oldest first, changes. Input recorded until
+/// `until` is missing from the gaps between them and after the last one.
+pub fn activity_events(activity: Vec<InputActivity>, until: DateTime<Utc>) -> Vec<TimelineItem> {
+    let mut items = Vec::new();
+    let mut last: Option<(DateTime<Utc>, ActivityLevel)> = None;
+    let minute = Duration::minutes(1);
+    for activity in activity {
+        let level = activity_level(&activity);
+        let unrecorded = last.filter(|(previous, _)| activity.minute > *previous + minute);
+        if let Some((previous, _)) = unrecorded {
+            items.push(TimelineItem::Activity(ActivityChange {
+                timestamp: previous + minute,
+                level: None,
+            }));
+        }
+        if unrecorded.is_some() || last.is_none_or(|(_, previous)| previous != level) {
+            items.push(TimelineItem::Activity(ActivityChange {
+                timestamp: activity.minute,
+                level: Some(level),
+            }));
+        }
+        last = Some((activity.minute, level));
+    }
+    if let Some((previous, _)) = last.filter(|(previous, _)| *previous + minute < until) {
+        items.push(TimelineItem::Activity(ActivityChange {
+            timestamp: previous + minute,
+            level: None,
+        }));
+    }
+    items
+}
+
+/// What was captured between `query.start` and `query.end`. When more than `query.limit`
+/// frames or transcriptions were, the timeline stops at the first one left out.
+pub async fn collect_timeline(db: &DatabaseManager, query: &TimelineQuery) -> Result<Timeline> {
+    let page = query.page_start().map_err(anyhow::Error::msg)?;
+    let start = page.start;
+    let limit = query.limit as usize;
+    let (((((frames, transcriptions), annotations), pauses), device_events), activity) = try_join(
+        try_join(
+            try_join(
+                try_join(
+                    try_join(
+                        db.get_timeline_frames(
+                            start,
+                            page.frame_id,
+                            query.end,
+                            query.limit + 1,
+                            SNIPPET_CHARS,
+                        ),
+                        db.get_timeline_transcriptions(
+                            start,
+                            page.transcription_id,
+                            query.end,
+                            query.limit + 1,
+                        ),
+                    ),
+                    db.list_annotations(
+                        None,
+                        None,
+                        Some(start),
+                        Some(query.end),
+                        None,
+                        None,
+                        query.limit + 1,
+                        0,
+                    ),
+                ),
+                db.list_capture_pauses(start, query.end, query.limit + 1),
+            ),
+            db.list_audio_device_events(start, query.end, query.limit + 1),
+        ),
+        db.get_input_activity(start, query.end),
+    )
+    .await?;
+    // the minute being counted isn't stored yet
+    let activity = activity_events(activity, query.end.min(minute_of(Utc::now())));
+
+    // where each item is on the timeline: frames come first at a timestamp, then
+    // transcriptions, then the rest
+    const FRAME: u8 = 0;
+    const TRANSCRIPT: u8 = 1;
+    const OTHER: u8 = 2;
+    let cut = [
+        frames
+            .get(limit)
+            .map(|frame| (frame.timestamp, FRAME, frame.frame_id)),
+        transcriptions
+            .get(limit)
+            .map(|transcription| (transcription.timestamp, TRANSCRIPT, transcription.id)),
+        // ranges started before `start` come first, a page of only those isn't cut
+        annotations
+            .get(limit)
+            .map(|annotation| annotation.start_time)
+            .filter(|timestamp| *timestamp > start)
+            .map(|timestamp| (timestamp, OTHER, 0)),
+        pauses
+            .get(limit)
+            .map(|pause| pause.start_time)
+            .filter(|timestamp| *timestamp > start)
+            .map(|timestamp| (timestamp, OTHER, 0)),
+        device_events
+            .get(limit)
+            .map(|event| (event.timestamp, OTHER, 0)),
+        activity
+            .get(limit)
+            .map(|change| (change.timestamp(), OTHER, 0)),
+    ]
+    .into_iter()
+    .flatten()
+    .min();
+    let before_cut = |position: (DateTime<Utc>, u8, i64)| cut.is_none_or(|cut| position < cut);
+
+    let frames = frames
+        .into_iter()
+        .filter(|frame| before_cut((frame.timestamp, FRAME, frame.frame_id)))
+        .collect();
+    let transcriptions = transcriptions
+        .into_iter()
+        .filter(|transcription| before_cut((transcription.timestamp, TRANSCRIPT, transcription.id)))
+        .collect();
+    let mut items = merge_timeline(frames, transcriptions);
+    // an annotation shows where it starts, the ranges started earlier were on the page before
+    items.extend(
+        annotations
+            .into_iter()
+            .take(limit)
+            .filter(|annotation| annotation.start_time >= start)
+            .map(TimelineItem::Annotation),
+    );
+    items.extend(pause_events(
+        pauses.into_iter().take(limit).collect(),
+        start,
+        query.end,
+    ));
+    items.extend(
+        device_events
+            .into_iter()
+            .take(limit)
+            .map(TimelineItem::AudioDevice),
+    );
+    items.extend(activity.into_iter().take(limit));
+    // stable, so an annotation stays after the frame it is on
+    items.sort_by_key(|item| item.timestamp());
+    items.retain(|item| match item {
+        TimelineItem::AppFocus(_)
+        | TimelineItem::Frame(_)
+        | TimelineItem::Ocr(_)
+        | TimelineItem::Transcript(_) => true,
+        _ => before_cut((item.timestamp(), OTHER, 0)),
+    });
+
+    // what comes before the cut at its timestamp was returned
+    let next_cursor = cut.map(|(timestamp, kind, id)| TimelineCursor {
+        start: timestamp,
+        frame_id: if kind == FRAME { id } else { i64::MAX },
+        transcription_id: match kind {
+            FRAME => 0,
+            TRANSCRIPT => id,
+            _ => i64::MAX,
+        },
+    });
+    Ok(Timeline {
+        start,
+        end: query.end,
+        items,
+        next_start: cut.map(|(timestamp, _, _)| timestamp),
+        next_cursor: next_cursor.as_ref().map(TimelineCursor::encode),
+    })
+}
@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use sysinfo::{System, SystemExt};
+use tracing::info;
+
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+/// Where the certificate of the HTTPS server comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TlsSource {
+    /// PEM files, e.g. from a local CA or Let's Encrypt
+    Files { cert: PathBuf, key: PathBuf },
+    /// A certificate generated once in `dir` and reused on the next starts
+    SelfSigned { dir: PathBuf },
+}
+
+/// The PEM certificate and key the server is started with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsSource {
+    /// The certificate and key, generating them for `listen` when self-signed and missing.
+    pub fn files(&self, listen: IpAddr) -> Result<TlsFiles> {
+        match self {
+            TlsSource::Files { cert, key } => {
+                for path in [cert, key] {
+                    if !path.is_file() {
+                        return Err(anyhow!("{} does not exist", path.display()));
+                    }
+                }
+                Ok(TlsFiles {
+                    cert: cert.clone(),
+                    key: key.clone(),
+                })
+            }
+            TlsSource::SelfSigned { dir } => self_signed_files(dir, listen),
+        }
+    }
+}
+
+/// The names a self-signed certificate is valid for: localhost, the listen address and
+/// the hostname.
+pub fn certificate_names(listen: IpAddr, hostname: Option<&str>) -> Vec<String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if !listen.is_unspecified() && !listen.is_loopback() {
+        names.push(listen.to_string());
+    }
+    if let Some(hostname) = hostname.map(str::trim).filter(|name| !name.is_empty()) {
+        names.push(hostname.to_string());
+        if !hostname.contains('.') {
+            // how macOS and mDNS resolve it on the LAN
+            names.push(format!("{}.local", hostname));
+        }
+    }
+    names
+}
+
+fn self_signed_files(dir: &Path, listen: IpAddr) -> Result<TlsFiles> {
+    let files = TlsFiles {
+        cert: dir.join(CERT_FILE),
+        key: dir.join(KEY_FILE),
+    };
+    if files.cert.is_file() && files.key.is_file() {
+        return Ok(files);
+    }
+
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let hostname = System::new().host_name();
+    let names = certificate_names(listen, hostname.as_deref());
+    let certified = rcgen::generate_simple_self_signed(names.clone())?;
+    fs::write(&files.cert, certified.cert.pem())?;
+    write_private(&files.key, &certified.key_pair.serialize_pem())?;
+    info!(
+        "generated a self-signed certificate for {} in {}",
+        names.join(", "),
+        dir.display()
+    );
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// SHA-256 of the first certificate of `pem`, as colon separated hex, to check the
+/// certificate a client is shown.
+pub fn certificate_fingerprint(pem: &str) -> Option<String> {
+    let body: String = pem
+        .lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN CERTIFICATE"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .collect();
+    if body.trim().is_empty() {
+        return None;
+    }
+    let der = general_purpose::STANDARD.decode(body.trim()).ok()?;
+    Some(der_fingerprint(&der))
+}
+
+/// SHA-256 of a DER certificate, as colon separated hex.
+pub fn der_fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// `fingerprint` as hex without separators, whatever its case and separators.
+fn normalize_fingerprint(fingerprint: &str) -> Result<String> {
+    let hex: String = fingerprint
+        .chars()
+        .filter(|c| !matches!(c, ':' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!(
+            "expected a sha-256 fingerprint like the one printed at startup, got {}",
+            fingerprint
+        ));
+    }
+    Ok(hex)
+}
+
+/// An HTTPS client trusting only the certificate with `fingerprint`, e.g. of a screenpipe
+/// started with --tls-self-signed. The certificate is pinned, its names aren't checked.
+pub fn pinned_client(fingerprint: &str) -> Result<reqwest::Client> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = PinnedCertificate {
+        fingerprint: normalize_fingerprint(fingerprint)?,
+        provider: provider.clone(),
+    };
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(reqwest::Client::builder()
+        .use_preconfigured_tls(config)
+        .build()?)
+}
+
+#[derive(Debug)]
+struct PinnedCertificate {
+    fingerprint: String,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificate {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = der_fingerprint(end_entity).replace(':', "");
+        if fingerprint == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "the certificate {} is not the one pinned",
+                der_fingerprint(end_entity)
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// The rustls config of the server, from PEM files.
+pub async fn rustls_config(files: &TlsFiles) -> Result<axum_server::tls_rustls::RustlsConfig> {
+    axum_server::tls_rustls::RustlsConfig::from_pem_file(&files.cert, &files.key)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to load the certificate {} and key {}",
+                files.cert.display(),
+                files.key.display()
+            )
+        })
+}
@@ -0,0 +1,103 @@
+use crate::llm::Llm;
+use anyhow::Result;
+use screenpipe_core::Language;
+use screenpipe_db::DatabaseManager;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+// Transcriptions translated per round
+const TRANSLATION_BATCH: u32 = 16;
+// Wait once everything is translated, or after the LLM failed
+const TRANSLATOR_IDLE: Duration = Duration::from_secs(30);
+// What the LLM answers for a transcription already in the language
+const ALREADY_TRANSLATED: &str = "<same>";
+
+pub fn system_prompt(language: &Language) -> String {
+    format!(
+        "You translate speech to text transcripts to {}. Answer with the translation only, \
+         keep names and technical terms as they are. When the transcript already is in {} \
+         answer {}.",
+        language_name(language),
+        language_name(language),
+        ALREADY_TRANSLATED
+    )
+}
+
+fn language_name(language: &Language) -> String {
+    let name = language.to_string();
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+/// The translation to store for `answer`, empty when the transcription needn't be translated.
+pub fn stored_translation(transcription: &str, answer: &str) -> String {
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case(ALREADY_TRANSLATED)
+        || answer.eq_ignore_ascii_case(transcription.trim())
+    {
+        String::new()
+    } else {
+        answer.to_string()
+    }
+}
+
+/// Translates transcriptions to `language` with `llm` in the background, the translations
+/// are searched along with the transcriptions. New transcriptions are translated first.
+pub async fn run_transcript_translator(
+    db: Arc<DatabaseManager>,
+    llm: Arc<Llm>,
+    language: Language,
+) {
+    info!(
+        "translating transcriptions to {} with {}",
+        language, llm.model
+    );
+    loop {
+        match translate_pending(&db, &llm, &language).await {
+            Ok(0) => tokio::time::sleep(TRANSLATOR_IDLE).await,
+            Ok(count) => debug!("translated {} transcriptions", count),
+            Err(e) => {
+                warn!("failed to translate transcriptions, retrying later: {}", e);
+                tokio::time::sleep(TRANSLATOR_IDLE).await;
+            }
+        }
+    }
+}
+
+/// Translates a batch of transcriptions. The ones the LLM fails on are tried again after the
+/// others, unless it failed on all of them and likely is unreachable.
+async fn translate_pending(db: &DatabaseManager, llm: &Llm, language: &Language) -> Result<usize> {
+    let code = language.as_lang_code();
+    let system = system_prompt(language);
+    let pending = db
+        .get_transcriptions_without_translation(code, TRANSLATION_BATCH)
+        .await?;
+    let mut failed = Vec::new();
+    let mut last_error = None;
+    for (id, transcription) in &pending {
+        match llm.complete(&system, transcription).await {
+            Ok(answer) => {
+                let translation = stored_translation(transcription, &answer);
+                db.set_audio_transcription_translation(*id, &translation, code)
+                    .await?;
+            }
+            Err(e) => {
+                debug!("failed to translate transcription {}: {}", id, e);
+                failed.push(*id);
+                last_error = Some(e);
+            }
+        }
+    }
+    if let Some(e) = last_error {
+        if failed.len() == pending.len() {
+            return Err(e);
+        }
+    }
+    for id in failed {
+        db.record_translation_failure(id).await?;
+    }
+    Ok(pending.len())
+}
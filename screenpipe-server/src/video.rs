@@ -1,10 +1,20 @@
+use crate::frame_storage::{
+    content_key, frame_file_name, FrameBlobStore, FrameFormat, FrameStorage,
+};
 use chrono::Utc;
 use crossbeam::queue::ArrayQueue;
 use image::ImageFormat::{self};
-use screenpipe_core::{find_ffmpeg_path, Language};
+use screenpipe_core::{find_ffmpeg_path, Language, RedactionPolicy, WasmPlugins};
 use screenpipe_vision::monitor::get_monitor_by_id;
 use screenpipe_vision::{
-    capture_screenshot_by_window::WindowFilters, continuous_capture, CaptureResult, OcrEngine,
+    accessibility::AccessibilityConfig,
+    capture_screenshot_by_window::{WindowBounds, WindowFilters},
+    continuous_capture,
+    face_blur::{blur_faces, FaceBlurConfig},
+    ocr_quality::OcrQualityConfig,
+    plugins::apply_plugins,
+    privacy::PrivacyPolicy,
+    redaction::redact_capture_result, AdaptiveFpsConfig, CaptureResult, OcrEngine,
 };
 use std::borrow::Cow;
 use std::path::PathBuf;
@@ -42,13 +52,24 @@ impl VideoCapture {
         output_path: &str,
         fps: f64,
         video_chunk_duration: Duration,
+        frame_storage: FrameStorage,
+        blob_store: Option<Arc<FrameBlobStore>>,
         new_chunk_callback: impl Fn(&str) + Send + Sync + 'static,
         ocr_engine: Arc<OcrEngine>,
         monitor_id: u32,
-        ignore_list: &[String],
-        include_list: &[String],
+        window_filters: Arc<WindowFilters>,
+        redaction_policy: Arc<RedactionPolicy>,
+        face_blur: Option<Arc<FaceBlurConfig>>,
+        plugins: Option<Arc<WasmPlugins>>,
         languages: Vec<Language>,
         capture_unfocused_windows: bool,
+        focused_window_only: bool,
+        phash_threshold: Option<u32>,
+        adaptive_fps: Option<AdaptiveFpsConfig>,
+        privacy_policy: PrivacyPolicy,
+        capture_region: Option<WindowBounds>,
+        accessibility: Arc<AccessibilityConfig>,
+        ocr_quality: OcrQualityConfig,
     ) -> Self {
         let fps = if fps.is_finite() && fps > 0.0 {
             fps
@@ -72,7 +93,6 @@ impl VideoCapture {
         let capture_video_frame_queue = video_frame_queue.clone();
         let capture_ocr_frame_queue = ocr_frame_queue.clone();
         let (result_sender, mut result_receiver) = channel(512);
-        let window_filters = Arc::new(WindowFilters::new(ignore_list, include_list));
 
         // Add parameters for monitoring restart
         let capture_ocr_engine = ocr_engine.clone();
@@ -111,6 +131,13 @@ impl VideoCapture {
                     capture_window_filters.clone(),
                     capture_languages.clone(),
                     capture_unfocused,
+                    focused_window_only,
+                    phash_threshold,
+                    adaptive_fps,
+                    privacy_policy,
+                    capture_region.clone(),
+                    accessibility.clone(),
+                    ocr_quality,
                 )
                 .await
                 {
@@ -175,7 +202,7 @@ impl VideoCapture {
                 true
             }
 
-            while let Some(result) = result_receiver.recv().await {
+            while let Some(mut result) = result_receiver.recv().await {
                 let frame_number = result.frame_number;
                 processed_count += 1;
 
@@ -199,6 +226,30 @@ impl VideoCapture {
 
                 debug!("Received frame {} for queueing", frame_number);
 
+                // Redact before the frame is shared with the video and OCR writers
+                redact_capture_result(&mut result, &redaction_policy);
+                if let Some(face_blur) = &face_blur {
+                    blur_faces(&mut result, face_blur).await;
+                }
+                if let Some(plugins) = &plugins {
+                    let plugins = Arc::clone(plugins);
+                    let processed = tokio::task::spawn_blocking(move || {
+                        let kept = apply_plugins(&mut result, monitor_id, &plugins);
+                        (result, kept)
+                    })
+                    .await;
+                    match processed {
+                        Ok((processed, true)) => result = processed,
+                        Ok((_, false)) => {
+                            debug!("frame {} dropped by a plugin", frame_number);
+                            continue;
+                        }
+                        Err(e) => {
+                            error!("plugins failed on frame {}: {}", frame_number, e);
+                            continue;
+                        }
+                    }
+                }
                 let result = Arc::new(result);
 
                 let video_pushed = push_to_queue(&capture_video_frame_queue, &result, "Video");
@@ -236,16 +287,34 @@ impl VideoCapture {
                 "Starting save_frames_as_video task for monitor {}",
                 monitor_id
             );
-            match save_frames_as_video(
-                &video_frame_queue_clone,
-                &output_path,
-                fps,
-                new_chunk_callback_clone,
-                monitor_id,
-                video_chunk_duration,
-            )
-            .await
-            {
+            let saved = match frame_storage.format {
+                FrameFormat::Video => {
+                    save_frames_as_video(
+                        &video_frame_queue_clone,
+                        &output_path,
+                        fps,
+                        new_chunk_callback_clone,
+                        monitor_id,
+                        video_chunk_duration,
+                        frame_storage,
+                    )
+                    .await
+                }
+                _ => {
+                    save_frames_as_images(
+                        &video_frame_queue_clone,
+                        &output_path,
+                        fps,
+                        new_chunk_callback_clone,
+                        monitor_id,
+                        video_chunk_duration,
+                        frame_storage,
+                        blob_store,
+                    )
+                    .await
+                }
+            };
+            match saved {
                 Ok(_) => warn!(
                     "save_frames_as_video task completed unexpectedly for monitor {}",
                     monitor_id
@@ -412,6 +481,7 @@ async fn save_frames_as_video(
     new_chunk_callback: Arc<dyn Fn(&str) + Send + Sync>,
     monitor_id: u32,
     video_chunk_duration: Duration,
+    frame_storage: FrameStorage,
 ) -> Result<(), anyhow::Error> {
     info!(
         "Starting save_frames_as_video function for monitor {}",
@@ -443,10 +513,10 @@ async fn save_frames_as_video(
             frame_count = 0;
             debug!("Waiting for first frame for monitor {}", monitor_id);
             let first_frame = wait_for_first_frame(frame_queue).await;
-            let buffer = encode_frame(&first_frame);
+            let buffer = encode_frame(&first_frame, &frame_storage);
             debug!("Got first frame for new chunk for monitor {}", monitor_id);
 
-            let output_file = create_output_file(output_path, monitor_id);
+            let output_file = create_output_file(output_path, monitor_id, ".mp4");
             info!(
                 "Starting new video chunk: {} for monitor {}",
                 output_file, monitor_id
@@ -511,6 +581,7 @@ async fn save_frames_as_video(
             &mut frame_count,
             frames_per_video,
             fps,
+            &frame_storage,
         )
         .await;
 
@@ -525,6 +596,79 @@ async fn save_frames_as_video(
     Ok(())
 }
 
+/// Stores every frame as its own image, chunks are directories holding
+/// `frames_per_chunk` images named by their offset. With a `blob_store` frames with the
+/// same content are stored once.
+#[allow(clippy::too_many_arguments)]
+async fn save_frames_as_images(
+    frame_queue: &Arc<ArrayQueue<Arc<CaptureResult>>>,
+    output_path: &str,
+    fps: f64,
+    new_chunk_callback: Arc<dyn Fn(&str) + Send + Sync>,
+    monitor_id: u32,
+    video_chunk_duration: Duration,
+    frame_storage: FrameStorage,
+    blob_store: Option<Arc<FrameBlobStore>>,
+) -> Result<(), anyhow::Error> {
+    info!(
+        "Starting save_frames_as_images for monitor {} as {}",
+        monitor_id, frame_storage
+    );
+    let extension = frame_storage.format.extension().unwrap_or("png");
+    let frames_per_chunk = ((fps * video_chunk_duration.as_secs_f64()).ceil() as usize).max(1);
+    let mut chunk_dir: Option<PathBuf> = None;
+    let mut frame_count = 0;
+
+    loop {
+        let frame = wait_for_first_frame(frame_queue).await;
+        let dir = match chunk_dir.take() {
+            Some(dir) if frame_count < frames_per_chunk => dir,
+            _ => {
+                let dir = PathBuf::from(create_output_file(output_path, monitor_id, ""));
+                if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+                    error!("Failed to create frame directory {:?}: {}", dir, e);
+                    continue;
+                }
+                info!(
+                    "Starting new image chunk: {:?} for monitor {}",
+                    dir, monitor_id
+                );
+                new_chunk_callback(&dir.to_string_lossy());
+                frame_count = 0;
+                dir
+            }
+        };
+
+        let offset_index = frame_count as i64;
+        let written = match (&blob_store, content_key(frame_storage.dedup, &frame.image)) {
+            (Some(blob_store), Some(key)) => {
+                blob_store
+                    .write(
+                        &frame_storage,
+                        &key,
+                        || encode_frame(&frame, &frame_storage),
+                        &dir,
+                        offset_index,
+                    )
+                    .await
+            }
+            _ => {
+                let path = dir.join(frame_file_name(offset_index, extension));
+                frame_storage
+                    .write_image(&encode_frame(&frame, &frame_storage), &path)
+                    .await
+            }
+        };
+        match written {
+            Ok(()) => debug!("Wrote frame {} of {:?}", offset_index, dir),
+            Err(e) => error!("Failed to write frame for monitor {}: {}", monitor_id, e),
+        }
+        // the frame is stored at this offset either way, the next ones stay aligned with it
+        frame_count += 1;
+        chunk_dir = Some(dir);
+    }
+}
+
 async fn wait_for_first_frame(
     frame_queue: &Arc<ArrayQueue<Arc<CaptureResult>>>,
 ) -> Arc<CaptureResult> {
@@ -537,26 +681,29 @@ async fn wait_for_first_frame(
     }
 }
 
-fn encode_frame(frame: &CaptureResult) -> Vec<u8> {
+fn encode_frame(frame: &CaptureResult, frame_storage: &FrameStorage) -> Vec<u8> {
     let mut buffer = Vec::new();
-    frame
-        .image
+    frame_storage
+        .downscale(&frame.image)
         .write_to(&mut std::io::Cursor::new(&mut buffer), ImageFormat::Png)
         .expect("Failed to encode frame");
     buffer
 }
 
-fn create_output_file(output_path: &str, monitor_id: u32) -> String {
+fn create_output_file(output_path: &str, monitor_id: u32, suffix: &str) -> String {
     let time = Utc::now();
     let formatted_time = time.format("%Y-%m-%d_%H-%M-%S").to_string();
     PathBuf::from(output_path)
-        .join(format!("monitor_{}_{}.mp4", monitor_id, formatted_time))
+        .join(format!(
+            "monitor_{}_{}{}",
+            monitor_id, formatted_time, suffix
+        ))
         .to_str()
         .expect("Failed to create valid path")
         .to_string()
 }
 
-fn spawn_ffmpeg_loggers(stderr: Option<ChildStderr>, stdout: Option<ChildStdout>) {
+pub(crate) fn spawn_ffmpeg_loggers(stderr: Option<ChildStderr>, stdout: Option<ChildStdout>) {
     if let Some(stderr) = stderr {
         tokio::spawn(log_ffmpeg_output(BufReader::new(stderr), "stderr"));
     }
@@ -571,11 +718,12 @@ async fn process_frames(
     frame_count: &mut usize,
     frames_per_video: usize,
     fps: f64,
+    frame_storage: &FrameStorage,
 ) {
     let write_timeout = Duration::from_secs_f64(1.0 / fps);
     while *frame_count < frames_per_video {
         if let Some(frame) = frame_queue.pop() {
-            let buffer = encode_frame(&frame);
+            let buffer = encode_frame(&frame, frame_storage);
             if let Some(stdin) = current_stdin.as_mut() {
                 if let Err(e) = write_frame_with_retry(stdin, &buffer).await {
                     error!("Failed to write frame to ffmpeg after max retries: {}", e);
@@ -1,13 +1,15 @@
+use crate::frame_storage::ffmpeg_input;
 use anyhow::Result;
 use bincode;
 use chrono::{DateTime, Duration, Utc};
 use dirs::cache_dir;
+use screenpipe_core::encryption::{encrypt_if_enabled, read_media, readable_media};
 use screenpipe_core::find_ffmpeg_path;
 use screenpipe_db::{DatabaseManager, FrameData, OCREntry};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, HashMap};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::fs;
@@ -164,7 +166,7 @@ impl FrameDiskCache {
     }
 
     async fn load_index(&mut self) -> Result<()> {
-        match fs::read(&self.index_path).await {
+        match read_media(&self.index_path).await {
             Ok(data) if !data.is_empty() => match bincode::deserialize::<Vec<CachedFrame>>(&data) {
                 Ok(frames) => {
                     for frame in frames {
@@ -200,7 +202,7 @@ impl FrameDiskCache {
             bincode::serialize(&frames)?
         };
 
-        fs::write(&temp_path, encoded).await?;
+        fs::write(&temp_path, encrypt_if_enabled(&encoded)?).await?;
         fs::rename(&temp_path, &self.index_path).await?;
         Ok(())
     }
@@ -253,7 +255,7 @@ impl FrameDiskCache {
             audio_entries: audio_entries.to_vec(),
         };
 
-        fs::write(&frame_path, frame_data).await?;
+        fs::write(&frame_path, encrypt_if_enabled(frame_data)?).await?;
 
         self.entries.insert(
             (timestamp, device_id.to_string()),
@@ -304,7 +306,7 @@ impl FrameDiskCache {
 
             if should_verify {
                 debug!("verifying checksum for cached frame");
-                let frame_data = fs::read(&frame_path).await?;
+                let frame_data = read_media(frame_path).await?;
                 let mut hasher = Sha256::new();
                 hasher.update(&frame_data);
                 let checksum = format!("{:x}", hasher.finalize());
@@ -321,7 +323,7 @@ impl FrameDiskCache {
                 )))
             } else {
                 // Fast path - skip checksum verification
-                let frame_data = fs::read(&frame_path).await?;
+                let frame_data = read_media(frame_path).await?;
                 Ok(Some((
                     frame_data,
                     entry.frame.metadata.clone(),
@@ -647,13 +649,14 @@ async fn extract_frame(
     frame_tx: FrameChannel,
     cache_tx: mpsc::Sender<CacheMessage>,
 ) -> Result<usize> {
-    if !is_video_file_complete(&ffmpeg, &video_file_path).await? {
+    let media = readable_media(Path::new(&video_file_path)).await?;
+    if !is_video_file_complete(&ffmpeg, &video_file_path, media.to_str()).await? {
         debug!("skipping incomplete video file: {}", video_file_path);
         return Ok(0);
     }
 
     // Get source FPS from video metadata
-    let source_fps = match get_video_fps(&ffmpeg, &video_file_path).await {
+    let source_fps = match get_video_fps(&ffmpeg, media.to_str()).await {
         Ok(fps) => fps,
         Err(e) => {
             error!("failed to get video fps, using default 1fps: {}", e);
@@ -700,7 +703,7 @@ async fn extract_frame(
     let mut cmd = Command::new(&ffmpeg);
     cmd.args([
         "-i",
-        &video_file_path,
+        &ffmpeg_input(media.to_str()),
         "-vf",
         &format!("{},format=yuv420p,scale=iw*0.8:ih*0.8", select_filter),
         "-strict",
@@ -814,7 +817,12 @@ async fn extract_frame(
     Ok(processed)
 }
 
-async fn is_video_file_complete(ffmpeg_path: &PathBuf, file_path: &str) -> Result<bool> {
+/// `readable_path` is `file_path` or its decrypted copy, the age is checked on `file_path`.
+async fn is_video_file_complete(
+    ffmpeg_path: &PathBuf,
+    file_path: &str,
+    readable_path: &str,
+) -> Result<bool> {
     if let Ok(metadata) = tokio::fs::metadata(file_path).await {
         if let Ok(modified) = metadata.modified() {
             let age = SystemTime::now()
@@ -826,8 +834,9 @@ async fn is_video_file_complete(ffmpeg_path: &PathBuf, file_path: &str) -> Resul
         }
     }
 
+    let input = ffmpeg_input(readable_path);
     match Command::new(ffmpeg_path)
-        .args(["-v", "error", "-i", file_path, "-f", "null", "-"])
+        .args(["-v", "error", "-i", &input, "-f", "null", "-"])
         .output()
         .await
     {
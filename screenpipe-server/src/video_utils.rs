@@ -1,9 +1,11 @@
+use crate::frame_storage::frame_source;
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
 use chrono::NaiveDateTime;
 use chrono::{DateTime, Utc};
 use image::DynamicImage;
 use oasgen::OaSchema;
+use screenpipe_core::encryption::readable_media;
 use screenpipe_core::find_ffmpeg_path;
 use screenpipe_db::VideoMetadata as DBVideoMetadata;
 use serde::{Deserialize, Serialize};
@@ -37,6 +39,9 @@ struct Stream {
 }
 
 pub async fn extract_frame(file_path: &str, offset_index: i64) -> Result<String> {
+    let (source, offset_index) = frame_source(file_path, offset_index);
+    let source = readable_media(Path::new(&source)).await?;
+    let file_path = source.to_str();
     let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
 
     let offset_seconds = offset_index as f64 / 1000.0;
@@ -115,9 +120,10 @@ pub async fn validate_media(file_path: &str) -> Result<()> {
         return Err(anyhow::anyhow!("media file does not exist: {}", file_path));
     }
 
+    let media = readable_media(Path::new(file_path)).await?;
     let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
     let status = Command::new(ffmpeg_path)
-        .args(["-v", "error", "-i", file_path, "-f", "null", "-"])
+        .args(["-v", "error", "-i", media.to_str(), "-f", "null", "-"])
         .output()
         .await?;
 
@@ -148,14 +154,18 @@ pub async fn merge_videos(
     // create a temporary file to store the list of input videos
     let temp_file = output_dir.join("input_list.txt");
     let mut file = tokio::fs::File::create(&temp_file).await?;
+    // decrypted copies of encrypted videos live until ffmpeg merged them
+    let mut videos = Vec::with_capacity(request.video_paths.len());
     for video_path in &request.video_paths {
         // video validation before writing in txt
         if let Err(e) = validate_media(video_path).await {
             error!("invalid file in merging, skipping: {:?}", e);
             continue;
         }
+        let video = readable_media(Path::new(video_path)).await?;
         // Escape single quotes in the file path
-        let escaped_path = video_path.replace("'", "'\\''");
+        let escaped_path = video.to_str().replace("'", "'\\''");
+        videos.push(video);
         tokio::io::AsyncWriteExt::write_all(
             &mut file,
             format!("file '{}'\n", escaped_path).as_bytes(),
@@ -555,6 +565,9 @@ impl VideoMetadataOverride {
 }
 
 pub async fn extract_frame_from_video(file_path: &str, offset_index: i64) -> Result<String> {
+    let (source, offset_index) = frame_source(file_path, offset_index);
+    let source = readable_media(Path::new(&source)).await?;
+    let file_path = source.to_str();
     let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
 
     let source_fps = match get_video_fps(&ffmpeg_path, file_path).await {
@@ -659,6 +672,9 @@ pub async fn extract_high_quality_frame(
     offset_index: i64,
     output_dir: &Path,
 ) -> Result<String> {
+    let (source, offset_index) = frame_source(file_path, offset_index);
+    let source = readable_media(Path::new(&source)).await?;
+    let file_path = source.to_str();
     let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
 
     let source_fps = match get_video_fps(&ffmpeg_path, file_path).await {
@@ -0,0 +1,269 @@
+use crate::capture_events::{CaptureFilter, CaptureLog};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use oasgen::OaSchema;
+use reqwest::{Client, Url};
+use screenpipe_db::{DatabaseManager, Webhook};
+use screenpipe_events::CaptureEvent;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast::error::RecvError, RwLock, Semaphore};
+use tracing::{debug, info, warn};
+
+/// Tries per event before a delivery is given up
+pub const MAX_DELIVERY_ATTEMPTS: i64 = 5;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+// Events are dropped while this many deliveries are still being tried
+const MAX_PENDING_DELIVERIES: usize = 256;
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct WebhookRequest {
+    /// http or https URL the capture events are posted to
+    pub url: String,
+    /// Signs the bodies, sent as `X-Screenpipe-Signature: sha256=<hex HMAC>`
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Comma separated event types: ocr, transcript, app_focus
+    #[serde(default)]
+    pub types: Option<String>,
+    /// Comma separated parts of app names
+    #[serde(default)]
+    pub apps: Option<String>,
+    /// Comma separated words or phrases, e.g. "invoice"
+    #[serde(default)]
+    pub keywords: Option<String>,
+}
+
+impl WebhookRequest {
+    pub fn validate(&self) -> Result<CaptureFilter, String> {
+        let url = Url::parse(self.url.trim()).map_err(|e| format!("invalid url: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("url must be http or https".to_string());
+        }
+        if self
+            .secret
+            .as_deref()
+            .is_some_and(|secret| secret.is_empty())
+        {
+            return Err("secret must not be empty".to_string());
+        }
+        CaptureFilter::parse(
+            self.types.as_deref(),
+            self.apps.as_deref(),
+            self.keywords.as_deref(),
+        )
+    }
+}
+
+/// Hex HMAC-SHA256 of `body` keyed by `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Waited before try `attempt` + 1, doubling from 2s.
+pub fn retry_delay(attempt: i64) -> Duration {
+    FIRST_RETRY_DELAY * 2u32.pow((attempt - 1).clamp(0, 10) as u32)
+}
+
+/// Whether a response with `status` is worth trying again.
+pub fn is_retryable(status: u16) -> bool {
+    status >= 500 || status == 408 || status == 429
+}
+
+/// The registered webhooks, posting the capture events matching their filters.
+pub struct Webhooks {
+    db: Arc<DatabaseManager>,
+    client: Client,
+    hooks: RwLock<Vec<(Webhook, CaptureFilter)>>,
+    pending: Arc<Semaphore>,
+}
+
+impl Webhooks {
+    pub fn new(db: Arc<DatabaseManager>) -> Self {
+        Self {
+            db,
+            client: Client::new(),
+            hooks: RwLock::new(Vec::new()),
+            pending: Arc::new(Semaphore::new(MAX_PENDING_DELIVERIES)),
+        }
+    }
+
+    /// Reads the webhooks from the database.
+    pub async fn reload(&self) -> Result<()> {
+        let mut hooks = Vec::new();
+        for webhook in self.db.list_webhooks().await? {
+            match CaptureFilter::parse(
+                Some(&webhook.types),
+                Some(&webhook.apps),
+                Some(&webhook.keywords),
+            ) {
+                Ok(filter) => hooks.push((webhook, filter)),
+                Err(e) => warn!("skipping webhook {}: {}", webhook.id, e),
+            }
+        }
+        *self.hooks.write().await = hooks;
+        Ok(())
+    }
+
+    pub async fn create(&self, request: &WebhookRequest, filter: CaptureFilter) -> Result<Webhook> {
+        let webhook = self
+            .db
+            .insert_webhook(
+                request.url.trim(),
+                request.secret.as_deref(),
+                request.types.as_deref().unwrap_or_default().trim(),
+                request.apps.as_deref().unwrap_or_default().trim(),
+                request.keywords.as_deref().unwrap_or_default().trim(),
+            )
+            .await?;
+        info!("webhook {} registered for {}", webhook.id, webhook.url);
+        self.hooks.write().await.push((webhook.clone(), filter));
+        Ok(webhook)
+    }
+
+    /// Whether the webhook existed.
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let deleted = self.db.delete_webhook(id).await?;
+        self.hooks
+            .write()
+            .await
+            .retain(|(webhook, _)| webhook.id != id);
+        Ok(deleted)
+    }
+
+    async fn is_registered(&self, id: i64) -> bool {
+        self.hooks
+            .read()
+            .await
+            .iter()
+            .any(|(webhook, _)| webhook.id == id)
+    }
+
+    async fn matching(&self, event: &CaptureEvent) -> Vec<Webhook> {
+        self.hooks
+            .read()
+            .await
+            .iter()
+            .filter(|(_, filter)| filter.matches(event))
+            .map(|(webhook, _)| webhook.clone())
+            .collect()
+    }
+
+    /// Posts `event` to `webhook` until it is accepted, logging every try.
+    async fn deliver(&self, webhook: Webhook, event_id: u64, event: CaptureEvent) {
+        let body = serde_json::to_vec(&event).unwrap_or_default();
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            if attempt > 1 {
+                tokio::time::sleep(retry_delay(attempt - 1)).await;
+                if !self.is_registered(webhook.id).await {
+                    return;
+                }
+            }
+
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .timeout(DELIVERY_TIMEOUT)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header("X-Screenpipe-Event", event.kind())
+                .header("X-Screenpipe-Event-Id", event_id.to_string())
+                .header("X-Screenpipe-Webhook-Id", webhook.id.to_string());
+            if let Some(secret) = &webhook.secret {
+                request = request.header(
+                    "X-Screenpipe-Signature",
+                    format!("sha256={}", sign(secret, &body)),
+                );
+            }
+            let started = Instant::now();
+            let (status_code, error, retry) = match request.body(body.clone()).send().await {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None, false)
+                }
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let text = response.text().await.unwrap_or_default();
+                    let error =
+                        format!("{} {}", status, text.chars().take(200).collect::<String>());
+                    (Some(status), Some(error), is_retryable(status))
+                }
+                Err(e) => (None, Some(e.to_string()), true),
+            };
+            let duration_ms = started.elapsed().as_millis() as i64;
+            let success = error.is_none();
+            if let Err(e) = self
+                .db
+                .insert_webhook_delivery(
+                    webhook.id,
+                    event_id as i64,
+                    event.kind(),
+                    attempt,
+                    success,
+                    status_code.map(i64::from),
+                    error.as_deref(),
+                    duration_ms,
+                )
+                .await
+            {
+                warn!(
+                    "failed to log the delivery to webhook {}: {}",
+                    webhook.id, e
+                );
+            }
+
+            match error {
+                None => return,
+                Some(error) if retry && attempt < MAX_DELIVERY_ATTEMPTS => debug!(
+                    "webhook {} try {} failed, retrying: {}",
+                    webhook.id, attempt, error
+                ),
+                Some(error) => {
+                    warn!(
+                        "giving up on event {} for webhook {} after {} tries: {}",
+                        event_id, webhook.id, attempt, error
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Delivers the events of `capture_log` to the webhooks they match.
+pub async fn run_webhooks(webhooks: Arc<Webhooks>, capture_log: Arc<CaptureLog>) {
+    let (_, mut receiver) = capture_log.resume(None);
+    loop {
+        let (event_id, event) = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("webhooks skipped {} capture events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        for webhook in webhooks.matching(&event).await {
+            let Ok(permit) = webhooks.pending.clone().try_acquire_owned() else {
+                warn!(
+                    "too many pending webhook deliveries, dropping event {} for webhook {}",
+                    event_id, webhook.id
+                );
+                continue;
+            };
+            let webhooks = webhooks.clone();
+            let event = event.clone();
+            tokio::spawn(async move {
+                webhooks.deliver(webhook, event_id, event).await;
+                drop(permit);
+            });
+        }
+    }
+}
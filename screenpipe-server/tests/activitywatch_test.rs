@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use screenpipe_db::{DatabaseManager, WindowSession};
+    use screenpipe_server::activitywatch::{
+        buckets, import_document, sync_start, web_bucket_id, window_bucket_id, ActivityWatchBridge,
+        AwEvent, WEB_BUCKET_TYPE, WINDOW_BUCKET_TYPE,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn session(minute: i64, app_name: &str, browser_url: Option<&str>) -> WindowSession {
+        let start_time =
+            Utc.with_ymd_and_hms(2025, 5, 21, 9, 0, 0).unwrap() + Duration::minutes(minute);
+        WindowSession {
+            app_name: app_name.to_string(),
+            window_name: "main".to_string(),
+            browser_url: browser_url.map(str::to_string),
+            start_time,
+            end_time: start_time + Duration::seconds(90),
+            frame_count: 45,
+        }
+    }
+
+    #[test]
+    fn test_sessions_become_window_and_web_events() {
+        let sessions = [
+            session(0, "Code", None),
+            session(5, "Arc", Some("https://docs.rs")),
+            session(10, "Arc", Some("")),
+        ];
+        let created = Utc::now();
+        let buckets = buckets(&sessions, "laptop", created);
+        assert_eq!(buckets.len(), 2);
+
+        let window = &buckets[0];
+        assert_eq!(window.id, window_bucket_id("laptop"));
+        assert_eq!(window.kind, WINDOW_BUCKET_TYPE);
+        assert_eq!(window.hostname, "laptop");
+        assert_eq!(window.created, created);
+        assert_eq!(window.events.len(), 3);
+        assert_eq!(window.events[0].timestamp, sessions[0].start_time);
+        assert_eq!(window.events[0].duration, 90.0);
+        assert_eq!(
+            window.events[0].data,
+            json!({"app": "Code", "title": "main"})
+        );
+
+        // only the tab with a url
+        let web = &buckets[1];
+        assert_eq!(web.id, web_bucket_id("laptop"));
+        assert_eq!(web.kind, WEB_BUCKET_TYPE);
+        assert_eq!(web.events.len(), 1);
+        assert_eq!(web.events[0].timestamp, sessions[1].start_time);
+        assert_eq!(web.events[0].data["url"], "https://docs.rs");
+        assert_eq!(web.events[0].data["title"], "main");
+    }
+
+    #[test]
+    fn test_import_document_is_keyed_by_bucket_id() {
+        let document = import_document(&buckets(&[session(0, "Code", None)], "laptop", Utc::now()));
+        let window = &document["buckets"][window_bucket_id("laptop")];
+        assert_eq!(window["type"], WINDOW_BUCKET_TYPE);
+        assert_eq!(window["client"], "screenpipe");
+        assert_eq!(window["events"].as_array().unwrap().len(), 1);
+        assert!(document["buckets"][web_bucket_id("laptop")]["events"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sync_continues_after_the_last_event() {
+        let now = Utc::now();
+        assert_eq!(sync_start(None, now), now - Duration::days(7));
+
+        let last = AwEvent {
+            timestamp: now - Duration::hours(1),
+            duration: 60.0,
+            data: json!({}),
+        };
+        assert_eq!(
+            sync_start(Some(&last), now),
+            now - Duration::minutes(59) + Duration::milliseconds(1)
+        );
+
+        // aw-server away for longer than a sync goes back
+        let stale = AwEvent {
+            timestamp: now - Duration::days(30),
+            duration: 60.0,
+            data: json!({}),
+        };
+        assert_eq!(sync_start(Some(&stale), now), now - Duration::days(7));
+    }
+
+    #[tokio::test]
+    async fn test_bridge_needs_an_http_url() {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        assert!(ActivityWatchBridge::new(db.clone(), "http://localhost:5600", "laptop").is_ok());
+        assert!(ActivityWatchBridge::new(db.clone(), "localhost:5600", "laptop").is_err());
+        assert!(ActivityWatchBridge::new(db, "ftp://localhost:5600", "laptop").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use screenpipe_db::AlertRule;
+    use screenpipe_events::{CaptureEvent, OcrCapture, TranscriptCapture};
+    use screenpipe_server::alerts::{AlertRuleRequest, CompiledRule};
+    use std::time::{Duration, Instant};
+
+    fn rule(pattern: &str, types: &str, apps: &str, cooldown_secs: i64) -> AlertRule {
+        AlertRule {
+            id: 1,
+            name: "prod errors".to_string(),
+            pattern: pattern.to_string(),
+            types: types.to_string(),
+            apps: apps.to_string(),
+            cooldown_secs,
+            notify: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn ocr(app_name: &str, text: &str) -> CaptureEvent {
+        CaptureEvent::Ocr(OcrCapture {
+            frame_id: 1,
+            timestamp: Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap(),
+            device_name: "monitor_1".to_string(),
+            app_name: app_name.to_string(),
+            window_name: "zsh".to_string(),
+            browser_url: None,
+            focused: true,
+            text: text.to_string(),
+        })
+    }
+
+    fn request(name: &str, pattern: &str, cooldown_secs: i64) -> AlertRuleRequest {
+        AlertRuleRequest {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            types: Some("ocr".to_string()),
+            apps: Some("terminal".to_string()),
+            cooldown_secs,
+            notify: true,
+        }
+    }
+
+    #[test]
+    fn test_validate_alert_rule_request() {
+        assert!(request("prod errors", "production.*error", 300)
+            .validate()
+            .is_ok());
+        assert!(request(" ", "production.*error", 300).validate().is_err());
+        assert!(request("prod errors", "production(", 300)
+            .validate()
+            .is_err());
+        assert!(request("prod errors", "", 300).validate().is_err());
+        assert!(request("prod errors", "error", -1).validate().is_err());
+    }
+
+    #[test]
+    fn test_rule_matches_pattern_in_app() {
+        let rule = CompiledRule::new(rule("production.*error", "ocr", "terminal", 0)).unwrap();
+        assert_eq!(
+            rule.find(&ocr("Terminal", "deploy: Production DB error: timeout")),
+            Some("Production DB error".to_string())
+        );
+        assert_eq!(rule.find(&ocr("Slack", "production error?")), None);
+        assert_eq!(rule.find(&ocr("Terminal", "production is fine")), None);
+
+        let transcript = CaptureEvent::Transcript(TranscriptCapture {
+            audio_chunk_id: 1,
+            timestamp: Utc::now(),
+            device_name: "microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: "production error".to_string(),
+        });
+        assert_eq!(rule.find(&transcript), None);
+    }
+
+    #[test]
+    fn test_rule_cooldown() {
+        let mut rule = CompiledRule::new(rule("error", "", "", 60)).unwrap();
+        let now = Instant::now();
+        assert!(rule.try_alert(now));
+        assert!(!rule.try_alert(now + Duration::from_secs(30)));
+        assert!(rule.try_alert(now + Duration::from_secs(61)));
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+    use screenpipe_db::{AppUsage, DatabaseManager, InputActivity};
+    use screenpipe_server::analytics::{
+        activity_analytics, parse_range, summarize_app_usage, summarize_input_activity,
+        ActivityLevel, AppAnalyticsQuery,
+    };
+    use screenpipe_server::input_activity::{minute_of, InputCounter, InputKind};
+
+    fn hour(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn usage(at: DateTime<Utc>, app_name: &str, window_name: &str, seconds: f64) -> AppUsage {
+        AppUsage {
+            hour: at,
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            seconds,
+        }
+    }
+
+    #[test]
+    fn test_parses_ranges() {
+        assert_eq!(parse_range("12h"), Ok(Duration::hours(12)));
+        assert_eq!(parse_range("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_range(" 4w "), Ok(Duration::weeks(4)));
+        assert!(parse_range("0d").is_err());
+        assert!(parse_range("-1d").is_err());
+        assert!(parse_range("91d").is_err());
+        assert!(parse_range("7").is_err());
+        assert!(parse_range("7 days").is_err());
+        assert!(parse_range("7é").is_err());
+        assert!(parse_range("").is_err());
+
+        let query = AppAnalyticsQuery {
+            range: "1d".to_string(),
+            end: Some(hour(2, 0)),
+        };
+        assert_eq!(query.bounds(), Ok((hour(1, 0), hour(2, 0))));
+    }
+
+    #[test]
+    fn test_summarizes_by_app_window_and_day() {
+        let apps = summarize_app_usage(vec![
+            usage(hour(1, 9), "Slack", "#general", 600.0),
+            usage(hour(1, 9), "Code", "main.rs", 1200.0),
+            usage(hour(1, 10), "Slack", "#random", 300.0),
+            usage(hour(1, 10), "Code", "main.rs", 1800.0),
+            usage(hour(2, 9), "Slack", "#general", 900.0),
+        ]);
+
+        let names: Vec<&str> = apps.iter().map(|app| app.app_name.as_str()).collect();
+        assert_eq!(names, vec!["Code", "Slack"]);
+        assert_eq!(apps[0].seconds, 3000.0);
+
+        let slack = &apps[1];
+        assert_eq!(slack.seconds, 1800.0);
+        let windows: Vec<(&str, f64)> = slack
+            .windows
+            .iter()
+            .map(|window| (window.window_name.as_str(), window.seconds))
+            .collect();
+        assert_eq!(windows, vec![("#general", 1500.0), ("#random", 300.0)]);
+
+        assert_eq!(slack.days.len(), 2);
+        assert_eq!(
+            slack.days[0].date,
+            NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+        );
+        assert_eq!(slack.days[0].seconds, 900.0);
+        let hours: Vec<DateTime<Utc>> = slack.days[0].hours.iter().map(|h| h.hour).collect();
+        assert_eq!(hours, vec![hour(1, 9), hour(1, 10)]);
+        assert_eq!(slack.days[1].seconds, 900.0);
+    }
+
+    fn activity(minute: u32, key_presses: i64, mouse_clicks: i64, scrolls: i64) -> InputActivity {
+        InputActivity {
+            minute: Utc.with_ymd_and_hms(2024, 6, 1, 9, minute, 0).unwrap(),
+            key_presses,
+            mouse_clicks,
+            mouse_moves: 0,
+            scrolls,
+        }
+    }
+
+    #[test]
+    fn test_tells_active_work_from_reading_and_idle_time() {
+        let summary = summarize_input_activity(
+            vec![
+                activity(0, 40, 3, 0),
+                activity(1, 2, 0, 12),
+                activity(2, 0, 0, 0),
+                activity(5, 0, 10, 2),
+            ],
+            hour(1, 9),
+            hour(1, 10),
+        );
+        let levels: Vec<ActivityLevel> = summary.minutes.iter().map(|m| m.level).collect();
+        assert_eq!(
+            levels,
+            vec![
+                ActivityLevel::Active,
+                ActivityLevel::Passive,
+                ActivityLevel::Active
+            ]
+        );
+        assert_eq!(summary.active_minutes, 2);
+        assert_eq!(summary.passive_minutes, 1);
+        assert_eq!(summary.idle_minutes, 1);
+        // minutes without a count weren't recorded, not idle
+        assert_eq!(summary.unrecorded_minutes, 56);
+    }
+
+    #[test]
+    fn test_counts_input_without_what_it_was() {
+        let counter = InputCounter::default();
+        counter.count(InputKind::KeyPress);
+        counter.count(InputKind::KeyPress);
+        counter.count(InputKind::Scroll);
+        assert_eq!(
+            InputKind::of(&rdev::EventType::KeyRelease(rdev::Key::KeyA)),
+            None
+        );
+
+        let minute = minute_of(Utc.with_ymd_and_hms(2024, 6, 1, 9, 3, 42).unwrap());
+        assert_eq!(minute, Utc.with_ymd_and_hms(2024, 6, 1, 9, 3, 0).unwrap());
+        assert_eq!(counter.take(minute), activity(3, 2, 0, 1));
+        assert_eq!(counter.take(minute), activity(3, 0, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_minutes_add_up_in_the_database() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.add_input_activity(&activity(0, 4, 1, 0)).await.unwrap();
+        db.add_input_activity(&activity(0, 6, 0, 3)).await.unwrap();
+        db.add_input_activity(&activity(1, 0, 0, 0)).await.unwrap();
+        db.add_input_activity(&activity(30, 0, 0, 5)).await.unwrap();
+
+        let summary = activity_analytics(&db, hour(1, 9), hour(1, 9) + Duration::minutes(10))
+            .await
+            .unwrap();
+        assert_eq!(summary.minutes.len(), 1);
+        assert_eq!(summary.minutes[0].key_presses, 10);
+        assert_eq!(summary.minutes[0].scrolls, 3);
+        assert_eq!(summary.minutes[0].level, ActivityLevel::Active);
+        assert_eq!(summary.idle_minutes, 1);
+        assert_eq!(summary.unrecorded_minutes, 8);
+    }
+}
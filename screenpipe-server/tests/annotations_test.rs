@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::annotations::AnnotationRequest;
+    use serde_json::json;
+
+    fn request(value: serde_json::Value) -> AnnotationRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validates_annotation_requests() {
+        let frame = request(json!({"content_type": "vision", "id": 3, "tags": ["meeting"]}));
+        assert!(frame.validate().is_ok());
+
+        let range = request(json!({
+            "content_type": "range",
+            "start_time": "2025-05-19T09:00:00Z",
+            "end_time": "2025-05-19T10:00:00Z",
+            "note": "design review"
+        }));
+        assert!(range.validate().is_ok());
+
+        for invalid in [
+            json!({"content_type": "vision", "tags": ["meeting"]}),
+            json!({"content_type": "ui", "id": 3, "tags": ["meeting"]}),
+            json!({"content_type": "range", "start_time": "2025-05-19T09:00:00Z", "note": "x"}),
+            json!({
+                "content_type": "range",
+                "start_time": "2025-05-19T10:00:00Z",
+                "end_time": "2025-05-19T09:00:00Z",
+                "note": "x"
+            }),
+            json!({"content_type": "audio", "id": 3, "tags": ["a,b"]}),
+            json!({"content_type": "audio", "id": 3, "tags": [" "]}),
+            json!({"content_type": "audio", "id": 3, "note": "  "}),
+        ] {
+            assert!(request(invalid.clone()).validate().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_trims_tags_and_note() {
+        let annotation = request(json!({
+            "content_type": "audio",
+            "id": 1,
+            "tags": [" meeting ", "Meeting", "acme"],
+            "note": "  call with acme "
+        }));
+        assert_eq!(annotation.tags(), vec!["meeting", "acme"]);
+        assert_eq!(annotation.note(), Some("call with acme"));
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use screenpipe_server::ask::{keyword_query, question_time_range, AskRequest};
+
+    // a Wednesday
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 5, 15, 30, 0).unwrap()
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, 0, 0, 0).unwrap()
+    }
+
+    fn request(question: &str) -> AskRequest {
+        AskRequest {
+            question: question.to_string(),
+            start_time: None,
+            end_time: None,
+            content_type: Default::default(),
+            limit: 30,
+        }
+    }
+
+    #[test]
+    fn test_keyword_query_leaves_out_question_words() {
+        assert_eq!(
+            keyword_query("What was the docker command my coworker pasted yesterday?"),
+            r#""docker" OR "command" OR "coworker" OR "pasted""#
+        );
+        assert_eq!(keyword_query("what did I see today?"), "");
+    }
+
+    #[test]
+    fn test_question_time_range() {
+        assert_eq!(
+            question_time_range("what did I read yesterday?", now()),
+            Some((day(4), day(5)))
+        );
+        assert_eq!(
+            question_time_range("Which PRs did I review today", now()),
+            Some((day(5), now()))
+        );
+        assert_eq!(
+            question_time_range("who was in the call last week", now()),
+            Some((Utc.with_ymd_and_hms(2024, 5, 27, 0, 0, 0).unwrap(), day(3)))
+        );
+        assert_eq!(
+            question_time_range("what is the wifi password", now()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_set_range_wins_over_the_question() {
+        let mut asked = request("what did I read yesterday?");
+        assert_eq!(asked.time_range(now()), (Some(day(4)), Some(day(5))));
+        asked.start_time = Some(day(1));
+        assert_eq!(asked.time_range(now()), (Some(day(1)), None));
+
+        assert!(request("  ").validate().is_err());
+        asked.end_time = Some(day(1));
+        assert!(asked.validate().is_err());
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+    use screenpipe_server::audio_import::{
+        chunk_path, find_audio_files, import_segments, recorded_at,
+    };
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    fn local(year: i32, month: u32, day: u32, h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        let time = NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap();
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn tags(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_recordings_are_dated_by_their_metadata_first() {
+        let modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert_eq!(
+            recorded_at(
+                &tags(&[("creation_time", "2024-05-01T10:15:00.000000Z")]),
+                "Call_20230101_090000.m4a",
+                modified
+            ),
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 10, 15, 0).unwrap())
+        );
+        // tag names differ in case between containers, dates without a timezone are local
+        assert_eq!(
+            recorded_at(
+                &tags(&[("DATE", "2024-05-01 10:15:00")]),
+                "recording.mp3",
+                None
+            ),
+            Some(local(2024, 5, 1, 10, 15, 0))
+        );
+        // unreadable tags are passed over
+        assert_eq!(
+            recorded_at(&tags(&[("date", "2024")]), "recording.mp3", modified),
+            modified.map(DateTime::<Utc>::from)
+        );
+    }
+
+    #[test]
+    fn test_recordings_are_dated_by_their_file_name_then_modified_time() {
+        for name in [
+            "Call recording John_20240501_101500.m4a",
+            "2024-05-01 10-15-00.wav",
+            "REC_2024-05-01_10.15.00.mp3",
+            "20240501T101500.flac",
+        ] {
+            assert_eq!(
+                recorded_at(&HashMap::new(), name, None),
+                Some(local(2024, 5, 1, 10, 15, 0)),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            recorded_at(&HashMap::new(), "20241341_250000.wav", None),
+            None
+        );
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            recorded_at(&HashMap::new(), "voice memo.m4a", Some(modified)),
+            Some(DateTime::<Utc>::from(modified))
+        );
+    }
+
+    #[test]
+    fn test_recordings_are_transcribed_in_pieces() {
+        assert_eq!(
+            import_segments(70.0),
+            vec![(0.0, 30.0), (30.0, 60.0), (60.0, 70.0)]
+        );
+        assert_eq!(import_segments(30.0), vec![(0.0, 30.0)]);
+        assert!(import_segments(0.0).is_empty());
+    }
+
+    #[test]
+    fn test_audio_files_are_found_in_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = dir.path().join("calls");
+        std::fs::create_dir(&calls).unwrap();
+        for file in ["b.MP3", "notes.txt", "calls/a.m4a", "calls/cover.jpg"] {
+            std::fs::write(dir.path().join(file), b"").unwrap();
+        }
+
+        let files = find_audio_files(dir.path()).unwrap();
+        assert_eq!(files, vec![dir.path().join("b.MP3"), calls.join("a.m4a")]);
+        assert_eq!(
+            find_audio_files(&calls.join("a.m4a")).unwrap(),
+            vec![calls.join("a.m4a")]
+        );
+        assert!(find_audio_files(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_copies_of_recordings_with_the_same_name_are_told_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let copy_to = dir.path().join("data");
+        for day in ["monday", "tuesday"] {
+            std::fs::create_dir(dir.path().join(day)).unwrap();
+            std::fs::write(dir.path().join(day).join("call.m4a"), b"").unwrap();
+        }
+        let monday = chunk_path(Some(&copy_to), &dir.path().join("monday/call.m4a")).unwrap();
+        let tuesday = chunk_path(Some(&copy_to), &dir.path().join("tuesday/call.m4a")).unwrap();
+        assert_ne!(monday, tuesday);
+        assert!(monday.starts_with(&*copy_to.to_string_lossy()));
+        assert!(monday.ends_with(".m4a"));
+        // the same recording imported again maps to the same chunk
+        assert_eq!(
+            chunk_path(
+                Some(&copy_to),
+                &dir.path().join("monday/../monday/call.m4a")
+            )
+            .unwrap(),
+            monday
+        );
+
+        let source = std::fs::canonicalize(dir.path().join("monday/call.m4a")).unwrap();
+        assert_eq!(chunk_path(None, &source).unwrap(), source.to_string_lossy());
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use axum::{middleware, routing::get, Router};
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::auth::{
+        allows, create_token, generate_token, hash_token, parse_scopes, redacted_uri,
+        require_token, required_scope, ApiAuth, ApiScope, TOKEN_PREFIX,
+    };
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_required_scopes() {
+        let method = Method::GET;
+        assert_eq!(required_scope(&method, "/health", None), None);
+        assert_eq!(required_scope(&Method::OPTIONS, "/search", None), None);
+        assert_eq!(
+            required_scope(&method, "/search", None),
+            Some(ApiScope::ReadSearch)
+        );
+        assert_eq!(
+            required_scope(&method, "/frames/12/tables", None),
+            Some(ApiScope::ReadSearch)
+        );
+        assert_eq!(
+            required_scope(&method, "/frames/12", None),
+            Some(ApiScope::ReadMedia)
+        );
+        assert_eq!(
+            required_scope(&method, "/frames/export", None),
+            Some(ApiScope::ReadMedia)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/tags/vision/1", None),
+            Some(ApiScope::WriteTags)
+        );
+        assert_eq!(
+            required_scope(&method, "/annotations", None),
+            Some(ApiScope::ReadSearch)
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, "/annotations/3", None),
+            Some(ApiScope::WriteTags)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/bookmarks", None),
+            Some(ApiScope::WriteTags)
+        );
+        assert_eq!(
+            required_scope(&method, "/meetings/2", None),
+            Some(ApiScope::ReadSearch)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/raw_sql", None),
+            Some(ApiScope::Admin)
+        );
+        assert_eq!(
+            required_scope(&method, "/webhooks", None),
+            Some(ApiScope::Admin)
+        );
+        assert_eq!(
+            required_scope(&method, "/exports/1/download", None),
+            Some(ApiScope::Admin)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/retranscribe", None),
+            Some(ApiScope::Admin)
+        );
+        assert_eq!(
+            required_scope(&method, "/retranscribe/1", None),
+            Some(ApiScope::ReadSearch)
+        );
+        // text is read-search, the screenshots some reads answer with it aren't
+        assert_eq!(
+            required_scope(&method, "/search", Some("q=x&include_frames=true")),
+            Some(ApiScope::ReadMedia)
+        );
+        assert_eq!(
+            required_scope(&method, "/ws/events", Some("images=true&token=x")),
+            Some(ApiScope::ReadMedia)
+        );
+        assert_eq!(
+            required_scope(&method, "/search", Some("include_frames=false")),
+            Some(ApiScope::ReadSearch)
+        );
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!(
+            parse_scopes("read-search, read-media").unwrap(),
+            vec![ApiScope::ReadSearch, ApiScope::ReadMedia]
+        );
+        assert!(parse_scopes("read-everything").is_err());
+        assert!(allows(&[ApiScope::Admin], ApiScope::WriteTags));
+        assert!(allows(&[ApiScope::ReadSearch], ApiScope::ReadSearch));
+        assert!(!allows(&[ApiScope::ReadSearch], ApiScope::ReadMedia));
+    }
+
+    #[test]
+    fn test_tokens_are_random_and_hashed() {
+        let token = generate_token();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(token, generate_token());
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_tokens_are_kept_out_of_logs() {
+        let uri = "/ws/events?images=false&token=sp_secret".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/ws/events?images=false&token=redacted");
+        let uri = "/search?q=x".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/search?q=x");
+    }
+
+    async fn status(app: &Router, uri: &str, token: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_require_token() {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let (_, token) = create_token(&db, "laptop", &[ApiScope::ReadSearch])
+            .await
+            .unwrap();
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/search", get(|| async { "results" }))
+            .route("/raw_sql", get(|| async { "rows" }))
+            .route("/sse/events", get(|| async { "events" }))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(ApiAuth::new(db.clone())),
+                require_token,
+            ));
+
+        assert_eq!(status(&app, "/health", None).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/search", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/search", Some("sp_wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(status(&app, "/search", Some(&token)).await, StatusCode::OK);
+        // only clients that can't set headers pass the token in the url
+        assert_eq!(
+            status(&app, &format!("/sse/events?token={}", token), None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, &format!("/search?q=x&token={}", token), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/raw_sql", Some(&token)).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let used = db.get_api_token_by_hash(&hash_token(&token)).await.unwrap();
+        assert!(used.unwrap().last_used_at.is_some());
+    }
+}
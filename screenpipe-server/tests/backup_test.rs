@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::backup::{backup, read_manifest, restore};
+    use std::path::Path;
+
+    async fn setup_data_dir(dir: &Path) -> DatabaseManager {
+        let chunk = dir.join("data").join("monitor_1_chunk");
+        std::fs::create_dir_all(&chunk).unwrap();
+        std::fs::create_dir_all(dir.join("data").join("frame_blobs")).unwrap();
+
+        let blob = dir.join("data").join("frame_blobs").join("a.webp");
+        std::fs::write(&blob, b"frame").unwrap();
+        std::fs::hard_link(&blob, chunk.join("000000.webp")).unwrap();
+        std::fs::write(dir.join("data").join("audio.mp4"), b"audio").unwrap();
+
+        let db = DatabaseManager::new(&dir.join("db.sqlite").to_string_lossy())
+            .await
+            .unwrap();
+        db.insert_audio_chunk(&format!("{}/data/audio.mp4", dir.to_string_lossy()))
+            .await
+            .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_incremental_backup_and_restore() {
+        let source = tempfile::tempdir().unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let _db = setup_data_dir(source.path()).await;
+
+        let report = backup(source.path(), backups.path()).await.unwrap();
+        assert_eq!(report.copied, 3);
+        assert_eq!(report.unchanged, 0);
+        assert!(report.database_bytes > 0);
+        let manifest = read_manifest(backups.path()).await.unwrap();
+        assert_eq!(
+            manifest
+                .media
+                .iter()
+                .filter(|file| file.linked_to.is_some())
+                .count(),
+            if cfg!(unix) { 1 } else { 0 }
+        );
+
+        // only what changed is copied again
+        std::fs::write(source.path().join("data").join("new.mp4"), b"new").unwrap();
+        std::fs::remove_file(source.path().join("data").join("audio.mp4")).unwrap();
+        let report = backup(source.path(), backups.path()).await.unwrap();
+        assert_eq!(report.copied, 1);
+        assert_eq!(report.copied_bytes, 3);
+        assert_eq!(report.unchanged, 2);
+        assert_eq!(report.removed, 1);
+
+        let report = restore(backups.path(), target.path(), false).await.unwrap();
+        assert_eq!(report.files, 3);
+        assert_eq!(report.rebased_paths, 1);
+        assert_eq!(
+            std::fs::read(target.path().join("data").join("new.mp4")).unwrap(),
+            b"new"
+        );
+
+        let db = DatabaseManager::new(&target.path().join("db.sqlite").to_string_lossy())
+            .await
+            .unwrap();
+        let path: String = sqlx::query_scalar("SELECT file_path FROM audio_chunks")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            path,
+            format!("{}/data/audio.mp4", target.path().to_string_lossy())
+        );
+
+        // the database is only replaced on request
+        assert!(restore(backups.path(), target.path(), false).await.is_err());
+        assert!(restore(backups.path(), target.path(), true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_damaged_backup() {
+        let source = tempfile::tempdir().unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let _db = setup_data_dir(source.path()).await;
+        backup(source.path(), backups.path()).await.unwrap();
+
+        std::fs::write(backups.path().join("data").join("audio.mp4"), b"AUDIO").unwrap();
+        let error = restore(backups.path(), target.path(), false)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("data/audio.mp4: checksum mismatch"));
+        // nothing was restored
+        assert!(!target.path().join("db.sqlite").exists());
+    }
+}
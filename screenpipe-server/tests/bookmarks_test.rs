@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use screenpipe_server::bookmarks::BookmarkRequest;
+
+    #[test]
+    fn test_bookmark_pins_a_frame_a_moment_or_now() {
+        let now = Utc.with_ymd_and_hms(2025, 5, 20, 9, 0, 0).unwrap();
+        let moment = Utc.with_ymd_and_hms(2025, 5, 20, 8, 30, 0).unwrap();
+
+        let frame = BookmarkRequest {
+            frame_id: Some(7),
+            title: Some("  signed the contract ".to_string()),
+            ..Default::default()
+        };
+        assert!(frame.validate().is_ok());
+        assert_eq!(frame.timestamp(now), None);
+        assert_eq!(frame.title(), Some("signed the contract"));
+
+        let at = BookmarkRequest {
+            timestamp: Some(moment),
+            title: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(at.timestamp(now), Some(moment));
+        assert_eq!(at.title(), None);
+
+        assert_eq!(BookmarkRequest::default().timestamp(now), Some(now));
+
+        let both = BookmarkRequest {
+            frame_id: Some(7),
+            timestamp: Some(moment),
+            ..Default::default()
+        };
+        assert!(both.validate().is_err());
+        let long = BookmarkRequest {
+            title: Some("a".repeat(201)),
+            ..Default::default()
+        };
+        assert!(long.validate().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::calendar::{
+        calendar_data, is_icalendar, parse_ics, CalendarSource, CalendarSync, Meeting,
+    };
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:review@example.com\r
+DTSTART:20250521T140000Z\r
+DTEND:20250521T150000Z\r
+SUMMARY:Design review\\, Q3\r
+DESCRIPTION:a long description folded\r
+  over two lines\r
+ORGANIZER;CN=Alice:mailto:alice@example.com\r
+ATTENDEE;CN=\"Bob, Jr\";PARTSTAT=ACCEPTED:mailto:bob@example.com\r
+ATTENDEE;PARTSTAT=NEEDS-ACTION:mailto:carol@example.com\r
+ATTENDEE;CN=Dave;PARTSTAT=DECLINED:mailto:dave@example.com\r
+ATTENDEE;CN=Room 4;CUTYPE=ROOM:mailto:room4@example.com\r
+LOCATION:Room 4\r
+BEGIN:VALARM\r
+TRIGGER:-PT10M\r
+DESCRIPTION:Reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup@example.com\r
+DTSTART;TZID=Europe/Paris:20250519T093000\r
+DURATION:PT15M\r
+SUMMARY:Standup\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR;COUNT=10\r
+EXDATE;TZID=Europe/Paris:20250521T093000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup@example.com\r
+RECURRENCE-ID;TZID=Europe/Paris:20250520T093000\r
+DTSTART;TZID=Europe/Paris:20250520T100000\r
+DURATION:PT15M\r
+SUMMARY:Standup (moved)\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup@example.com\r
+RECURRENCE-ID;TZID=Europe/Paris:20250522T093000\r
+DTSTART;TZID=Europe/Paris:20250522T093000\r
+DURATION:PT15M\r
+SUMMARY:Standup\r
+STATUS:CANCELLED\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:offsite@example.com\r
+DTSTART;VALUE=DATE:20250521\r
+DTEND;VALUE=DATE:20250522\r
+SUMMARY:Offsite\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn meetings() -> Vec<Meeting> {
+        parse_ics(
+            CALENDAR,
+            Utc.with_ymd_and_hms(2025, 5, 19, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 5, 24, 0, 0, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_single_event_with_attendees() {
+        let meetings = meetings();
+        let review = meetings
+            .iter()
+            .find(|meeting| meeting.uid == "review@example.com")
+            .unwrap();
+        assert_eq!(review.title, "Design review, Q3");
+        assert_eq!(
+            review.start_time,
+            Utc.with_ymd_and_hms(2025, 5, 21, 14, 0, 0).unwrap()
+        );
+        assert_eq!(review.end_time - review.start_time, Duration::hours(1));
+        // declined and rooms left out, emails without a name
+        assert_eq!(review.attendees, ["Alice", "Bob, Jr", "carol@example.com"]);
+        assert_eq!(review.location.as_deref(), Some("Room 4"));
+        assert_eq!(
+            review.tags(),
+            [
+                "meeting",
+                "Design review Q3",
+                "Alice",
+                "Bob Jr",
+                "carol@example.com"
+            ]
+        );
+        assert_eq!(
+            review.note(),
+            "Design review, Q3 with Alice, Bob, Jr, carol@example.com"
+        );
+    }
+
+    #[test]
+    fn test_recurring_event_is_expanded() {
+        let standups: Vec<Meeting> = meetings()
+            .into_iter()
+            .filter(|meeting| meeting.uid.starts_with("standup@example.com/"))
+            .collect();
+        // Mon, Tue moved to 10:00, Wed excluded, Thu cancelled, Fri
+        let starts: Vec<_> = standups.iter().map(|meeting| meeting.start_time).collect();
+        assert_eq!(
+            starts,
+            [
+                Utc.with_ymd_and_hms(2025, 5, 19, 7, 30, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 5, 20, 8, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 5, 23, 7, 30, 0).unwrap(),
+            ]
+        );
+        assert_eq!(standups[1].title, "Standup (moved)");
+        // the moved occurrence keeps the uid of the one it replaces
+        assert_eq!(standups[1].uid, "standup@example.com/20250520T073000Z");
+        assert!(standups
+            .iter()
+            .all(|meeting| meeting.end_time - meeting.start_time == Duration::minutes(15)));
+    }
+
+    #[test]
+    fn test_all_day_events_and_other_days_are_left_out() {
+        let meetings = meetings();
+        assert!(meetings.iter().all(|meeting| meeting.title != "Offsite"));
+
+        let next_month = parse_ics(
+            CALENDAR,
+            Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 6, 30, 0, 0, 0).unwrap(),
+        );
+        // COUNT=10 ends the standups on the 30th of May
+        assert!(next_month.is_empty());
+    }
+
+    #[test]
+    fn test_windows_time_zones_are_read() {
+        let calendar = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:sync@example.com\n\
+                        DTSTART;TZID=Pacific Standard Time:20250521T090000\n\
+                        DTEND;TZID=Pacific Standard Time:20250521T093000\n\
+                        SUMMARY:Planning\nEND:VEVENT\nEND:VCALENDAR\n";
+        let meetings = parse_ics(
+            calendar,
+            Utc.with_ymd_and_hms(2025, 5, 19, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 5, 24, 0, 0, 0).unwrap(),
+        );
+        assert_eq!(meetings.len(), 1);
+        // daylight saving time in los angeles
+        assert_eq!(
+            meetings[0].start_time,
+            Utc.with_ymd_and_hms(2025, 5, 21, 16, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_login_pages_are_not_calendars() {
+        assert!(is_icalendar(CALENDAR));
+        assert!(is_icalendar("BEGIN:VCALENDAR\nEND:VCALENDAR\n"));
+        assert!(!is_icalendar(""));
+        assert!(!is_icalendar("<html><body>Sign in</body></html>"));
+    }
+
+    #[test]
+    fn test_source_is_a_file_a_feed_or_caldav() {
+        assert_eq!(
+            CalendarSource::parse("/home/me/work.ics").unwrap(),
+            CalendarSource::File(PathBuf::from("/home/me/work.ics"))
+        );
+        assert!(matches!(
+            CalendarSource::parse("webcal://example.com/cal/basic.ics").unwrap(),
+            CalendarSource::Feed(url) if url.scheme() == "https"
+        ));
+        assert!(matches!(
+            CalendarSource::parse("https://example.com/cal/basic.ics").unwrap(),
+            CalendarSource::Feed(_)
+        ));
+        assert!(matches!(
+            CalendarSource::parse("https://dav.example.com/calendars/me/work/").unwrap(),
+            CalendarSource::CalDav(_)
+        ));
+        assert!(CalendarSource::parse(" ").is_err());
+    }
+
+    #[test]
+    fn test_calendar_data_of_a_caldav_response() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+<d:response><d:propstat><d:prop>
+<cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:R&amp;D &lt;sync&gt;
+END:VEVENT
+END:VCALENDAR</cal:calendar-data>
+</d:prop></d:propstat></d:response>
+<d:response><d:propstat><d:prop>
+<calendar-data><![CDATA[BEGIN:VCALENDAR
+END:VCALENDAR]]></calendar-data>
+</d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        let data = calendar_data(xml);
+        assert_eq!(data.len(), 2);
+        assert!(data[0].contains("SUMMARY:R&D <sync>"));
+        assert_eq!(data[1], "BEGIN:VCALENDAR\nEND:VCALENDAR");
+    }
+
+    #[tokio::test]
+    async fn test_sync_stores_and_removes_meetings() {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("work.ics");
+        let start = Utc::now() - Duration::hours(2);
+        let event = format!(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:sync@example.com\nDTSTART:{}\nDURATION:PT1H\n\
+             SUMMARY:Retro\nATTENDEE;CN=Bob:mailto:bob@example.com\nEND:VEVENT\nEND:VCALENDAR\n",
+            start.format("%Y%m%dT%H%M%SZ")
+        );
+        std::fs::write(&path, &event).unwrap();
+        let sync = CalendarSync::new(db.clone(), CalendarSource::File(path.clone()), None, None);
+
+        let report = sync.sync().await.unwrap();
+        assert_eq!((report.meetings, report.updated, report.removed), (1, 1, 0));
+        // unchanged the second time
+        assert_eq!(sync.sync().await.unwrap().updated, 0);
+        let stored = db
+            .list_calendar_events(Some("bob"), None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].title, "Retro");
+        let annotations = db
+            .list_annotations(Some("range"), None, None, None, Some("retro"), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(annotations.len(), 1);
+
+        // a feed serving something else keeps the meetings
+        std::fs::write(&path, "<html><body>Sign in</body></html>").unwrap();
+        assert!(sync.sync().await.is_err());
+        assert_eq!(
+            db.list_calendar_events(None, None, None, None, 10, 0)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        std::fs::write(&path, "BEGIN:VCALENDAR\nEND:VCALENDAR\n").unwrap();
+        assert_eq!(sync.sync().await.unwrap().removed, 1);
+        assert!(db
+            .list_calendar_events(None, None, None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use screenpipe_events::{
+        AppFocusChange, CaptureEvent, Event, OcrCapture, TranscriptCapture, CAPTURE_EVENT,
+    };
+    use screenpipe_server::capture_events::{as_capture_event, CaptureFilter, CaptureLog};
+
+    fn at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 1, 9, 0, 0).unwrap()
+    }
+
+    fn ocr(app_name: &str, text: &str) -> CaptureEvent {
+        CaptureEvent::Ocr(OcrCapture {
+            frame_id: 1,
+            timestamp: at(),
+            device_name: "monitor_1".to_string(),
+            app_name: app_name.to_string(),
+            window_name: "window".to_string(),
+            browser_url: None,
+            focused: true,
+            text: text.to_string(),
+        })
+    }
+
+    fn transcript(text: &str) -> CaptureEvent {
+        CaptureEvent::Transcript(TranscriptCapture {
+            audio_chunk_id: 1,
+            timestamp: at(),
+            device_name: "microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: text.to_string(),
+        })
+    }
+
+    fn focus(app_name: &str, window_name: &str) -> CaptureEvent {
+        CaptureEvent::AppFocus(AppFocusChange {
+            timestamp: at(),
+            device_name: "monitor_1".to_string(),
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            previous_app_name: None,
+            previous_window_name: None,
+        })
+    }
+
+    #[test]
+    fn test_filter_by_type_app_and_keyword() {
+        let filter =
+            CaptureFilter::parse(None, Some("slack, Mail"), Some("Invoice,due date")).unwrap();
+        assert!(filter.matches(&ocr("Slack", "the INVOICE is attached")));
+        assert!(filter.matches(&ocr("Mail", "due date is friday")));
+        assert!(!filter.matches(&ocr("Code", "invoice.rs")));
+        assert!(!filter.matches(&ocr("Slack", "lunch?")));
+        // transcriptions have no app
+        assert!(!filter.matches(&transcript("pay the invoice")));
+        assert!(filter.matches(&focus("Slack", "Invoices - Acme")));
+
+        let filter = CaptureFilter::parse(Some("transcript,app_focus"), None, None).unwrap();
+        assert!(filter.matches(&transcript("hello")));
+        assert!(filter.matches(&focus("Code", "main.rs")));
+        assert!(!filter.matches(&ocr("Code", "fn main")));
+
+        assert!(CaptureFilter::parse(Some("ocr,frames"), None, None).is_err());
+        assert!(CaptureFilter::parse(None, Some(" , "), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_reads_capture_events_only() {
+        let event = Event {
+            name: CAPTURE_EVENT.to_string(),
+            data: serde_json::to_value(transcript("hello")).unwrap(),
+        };
+        assert_eq!(as_capture_event(&event), Some(transcript("hello")));
+
+        let other = Event {
+            name: "ocr_result".to_string(),
+            data: serde_json::json!({"text": "hello"}),
+        };
+        assert_eq!(as_capture_event(&other), None);
+    }
+
+    #[test]
+    fn test_capture_log_resumes_after_last_event_id() {
+        let log = CaptureLog::new(2);
+        let first = log.push(ocr("Slack", "one"));
+        let second = log.push(ocr("Slack", "two"));
+        assert!(second > first);
+
+        let (missed, _) = log.resume(None);
+        assert!(missed.is_empty());
+        let (missed, mut receiver) = log.resume(Some(first));
+        assert_eq!(missed, vec![(second, ocr("Slack", "two"))]);
+
+        let third = log.push(transcript("three"));
+        assert_eq!(receiver.try_recv().unwrap(), (third, transcript("three")));
+        // only the last two are kept
+        let (missed, _) = log.resume(Some(0));
+        let ids: Vec<u64> = missed.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![second, third]);
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_db::{
+        AudioDevice, CapturedText, CapturedTranscription, CentralDatabase, DatabaseManager,
+        DeviceType, SyncPosition,
+    };
+    use screenpipe_server::central_database::sync_captures;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Keeps the captures in memory, the way a central database stores them.
+    #[derive(Default)]
+    struct MemoryDatabase {
+        texts: Mutex<HashMap<(String, i64), CapturedText>>,
+        transcriptions: Mutex<HashMap<(String, i64), CapturedTranscription>>,
+    }
+
+    impl CentralDatabase for MemoryDatabase {
+        async fn insert_texts(
+            &self,
+            machine: &str,
+            texts: &[CapturedText],
+        ) -> Result<(), sqlx::Error> {
+            let mut stored = self.texts.lock().unwrap();
+            for text in texts {
+                stored.insert((machine.to_string(), text.id), text.clone());
+            }
+            Ok(())
+        }
+
+        async fn insert_transcriptions(
+            &self,
+            machine: &str,
+            transcriptions: &[CapturedTranscription],
+        ) -> Result<(), sqlx::Error> {
+            let mut stored = self.transcriptions.lock().unwrap();
+            for transcription in transcriptions {
+                stored.insert(
+                    (machine.to_string(), transcription.id),
+                    transcription.clone(),
+                );
+            }
+            Ok(())
+        }
+
+        async fn sync_position(&self, machine: &str) -> Result<SyncPosition, sqlx::Error> {
+            let max_id = |ids: Vec<&(String, i64)>| {
+                ids.into_iter()
+                    .filter(|(owner, _)| owner == machine)
+                    .map(|(_, id)| *id)
+                    .max()
+                    .unwrap_or(0)
+            };
+            Ok(SyncPosition {
+                text_id: max_id(self.texts.lock().unwrap().keys().collect()),
+                transcription_id: max_id(self.transcriptions.lock().unwrap().keys().collect()),
+            })
+        }
+    }
+
+    async fn insert_transcription(db: &DatabaseManager, text: &str) {
+        let audio_chunk_id = db.get_or_insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            text,
+            0,
+            "",
+            &AudioDevice {
+                name: "microphone".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_copies_only_new_captures() {
+        let laptop = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        let desktop = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        let central = MemoryDatabase::default();
+
+        insert_transcription(&laptop, "first").await;
+        insert_transcription(&laptop, "second").await;
+        insert_transcription(&desktop, "other machine").await;
+
+        let report = sync_captures(&laptop, &central, "laptop").await.unwrap();
+        assert_eq!(report.transcriptions, 2);
+        let report = sync_captures(&desktop, &central, "desktop").await.unwrap();
+        assert_eq!(report.transcriptions, 1);
+
+        // nothing new
+        let report = sync_captures(&laptop, &central, "laptop").await.unwrap();
+        assert_eq!(report.transcriptions, 0);
+
+        insert_transcription(&laptop, "third").await;
+        let report = sync_captures(&laptop, &central, "laptop").await.unwrap();
+        assert_eq!(report.transcriptions, 1);
+        let transcriptions = central.transcriptions.lock().unwrap();
+        assert_eq!(transcriptions.len(), 4);
+        assert_eq!(
+            transcriptions[&("laptop".to_string(), 3)].transcription,
+            "third"
+        );
+        assert_eq!(
+            transcriptions[&("desktop".to_string(), 1)].transcription,
+            "other machine"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_db::VideoSegment;
+    use screenpipe_server::clip::{
+        frames_concat_list, remove_stale_clips, segments_concat_list, ClipFile, ClipFormat,
+        ClipQuery,
+    };
+    use tempfile::TempDir;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 4, 10, 15, 0, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn segment(id: i64, file_path: &str, start: i64, end: Option<i64>) -> VideoSegment {
+        VideoSegment {
+            id,
+            device_name: "monitor_1".to_string(),
+            file_path: file_path.to_string(),
+            start_time: at(start),
+            end_time: end.map(at),
+            fps: 5.0,
+            codec: "h264".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_segments_cut_across_files() {
+        let segments = vec![
+            segment(1, "first.mp4", 0, Some(300)),
+            segment(2, "second.mp4", 300, None),
+        ];
+        let list = segments_concat_list(&segments, at(240), at(360)).unwrap();
+        assert_eq!(
+            list,
+            "ffconcat version 1.0\n\
+             file 'first.mp4'\ninpoint 240.000\noutpoint 300.000\n\
+             file 'second.mp4'\ninpoint 0.000\noutpoint 60.000\n"
+        );
+    }
+
+    #[test]
+    fn test_segments_with_gaps_fall_back_to_frames() {
+        // recording paused between the segments
+        let segments = vec![
+            segment(1, "first.mp4", 0, Some(100)),
+            segment(2, "second.mp4", 200, None),
+        ];
+        assert!(segments_concat_list(&segments, at(50), at(250)).is_none());
+        // started after the clip begins
+        assert!(segments_concat_list(&segments[1..], at(150), at(250)).is_none());
+        // stopped before the clip ends
+        assert!(segments_concat_list(&segments[..1], at(50), at(150)).is_none());
+        assert!(segments_concat_list(&[], at(0), at(10)).is_none());
+    }
+
+    #[test]
+    fn test_frames_are_held_until_the_next_one() {
+        let images = vec![
+            ("a.jpg".to_string(), at(0)),
+            ("it's.jpg".to_string(), at(1)),
+            ("c.jpg".to_string(), at(60)),
+        ];
+        let list = frames_concat_list(&images, at(61));
+        assert_eq!(
+            list,
+            "ffconcat version 1.0\n\
+             file 'a.jpg'\nduration 1.000\n\
+             file 'it'\\''s.jpg'\nduration 2.000\n\
+             file 'c.jpg'\nduration 1.000\n\
+             file 'c.jpg'\n"
+        );
+    }
+
+    #[test]
+    fn test_clip_query_validation() {
+        let query = |start: i64, end: i64| ClipQuery {
+            start: at(start),
+            end: at(end),
+            monitor: 1,
+            format: ClipFormat::Gif,
+        };
+        assert!(query(0, 60).validate().is_ok());
+        assert!(query(60, 60).validate().is_err());
+        assert!(query(0, 2 * 3600).validate().is_err());
+    }
+
+    #[test]
+    fn test_gif_uses_a_palette() {
+        let args = ClipFormat::Gif.output_args();
+        assert!(args.iter().any(|arg| arg.contains("palettegen")));
+        assert_eq!(ClipFormat::Gif.content_type(), "image/gif");
+        assert_eq!(ClipFormat::Mp4.extension(), "mp4");
+    }
+
+    #[tokio::test]
+    async fn test_clips_are_removed() {
+        let dir = TempDir::new().unwrap();
+        let served = dir.path().join("clip_served.mp4");
+        std::fs::write(&served, b"clip").unwrap();
+        drop(ClipFile {
+            path: served.clone(),
+        });
+        assert!(!served.exists());
+
+        let left = dir.path().join("clip_left.gif");
+        let other = dir.path().join("notes.txt");
+        std::fs::write(&left, b"clip").unwrap();
+        std::fs::write(&other, b"notes").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let max_age = std::time::Duration::from_millis(10);
+        assert_eq!(remove_stale_clips(dir.path(), max_age).await.unwrap(), 1);
+        assert!(!left.exists());
+        assert!(other.exists());
+        // recent clips are still being served
+        std::fs::write(&left, b"clip").unwrap();
+        let max_age = std::time::Duration::from_secs(3600);
+        assert_eq!(remove_stale_clips(dir.path(), max_age).await.unwrap(), 0);
+    }
+}
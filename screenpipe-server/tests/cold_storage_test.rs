@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::cold_storage::{ColdStorage, ColdStore};
+    use std::sync::Arc;
+
+    async fn setup() -> (Arc<DatabaseManager>, tempfile::TempDir, tempfile::TempDir) {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        (
+            db,
+            tempfile::tempdir().unwrap(),
+            tempfile::tempdir().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_directory_store() {
+        let store = ColdStore::parse("file:///mnt/nas/screenpipe", None, "us-east-1").unwrap();
+        assert_eq!(store.to_string(), "/mnt/nas/screenpipe");
+        assert!(ColdStore::parse("s3:///prefix", None, "us-east-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_old_audio_moves_to_cold_storage_and_back() {
+        let (db, data, cold) = setup().await;
+        let audio = data.path().join("old.mp4");
+        std::fs::write(&audio, b"audio").unwrap();
+        let audio_path = audio.to_string_lossy().into_owned();
+        db.insert_audio_chunk(&audio_path).await.unwrap();
+        db.insert_audio_chunk("new.mp4").await.unwrap();
+        sqlx::query("UPDATE audio_chunks SET timestamp = ?1 WHERE file_path = ?2")
+            .bind(Utc::now() - chrono::Duration::days(40))
+            .bind(&audio_path)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let cold_storage = Arc::new(ColdStorage::new(
+            db.clone(),
+            ColdStore::Directory(cold.path().to_path_buf()),
+            &data.path().to_string_lossy(),
+            30,
+        ));
+        let report = cold_storage.tier_old_media().await.unwrap();
+        assert_eq!((report.files, report.bytes), (1, 5));
+        assert!(!audio.exists());
+        assert_eq!(
+            std::fs::read(cold.path().join("old.mp4")).unwrap(),
+            b"audio"
+        );
+        // already moved
+        assert_eq!(cold_storage.tier_old_media().await.unwrap().files, 0);
+
+        assert!(cold_storage.ensure_local(&audio_path).await.unwrap());
+        assert_eq!(std::fs::read(&audio).unwrap(), b"audio");
+        assert!(!cold_storage.ensure_local(&audio_path).await.unwrap());
+
+        // the local copy is deleted again a day later
+        assert_eq!(cold_storage.evict_fetched().await.unwrap(), 0);
+        sqlx::query("UPDATE audio_chunks SET fetched_at = ?1")
+            .bind(Utc::now() - chrono::Duration::hours(25))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(cold_storage.evict_fetched().await.unwrap(), 1);
+        assert!(!audio.exists());
+
+        cold_storage.forget(&audio_path).await.unwrap();
+        assert!(!cold.path().join("old.mp4").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_files_failing_to_move_are_skipped() {
+        let (db, data, cold) = setup().await;
+        // a link to itself can't be read
+        let broken = data.path().join("broken.mp4");
+        std::os::unix::fs::symlink(&broken, &broken).unwrap();
+        let audio = data.path().join("old.mp4");
+        std::fs::write(&audio, b"audio").unwrap();
+        for path in [&broken, &audio] {
+            db.insert_audio_chunk(&path.to_string_lossy())
+                .await
+                .unwrap();
+        }
+        sqlx::query("UPDATE audio_chunks SET timestamp = ?1")
+            .bind(Utc::now() - chrono::Duration::days(40))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let cold_storage = Arc::new(ColdStorage::new(
+            db.clone(),
+            ColdStore::Directory(cold.path().to_path_buf()),
+            &data.path().to_string_lossy(),
+            30,
+        ));
+        let report = cold_storage.tier_old_media().await.unwrap();
+        assert_eq!((report.files, report.failed), (1, 1));
+        assert!(cold.path().join("old.mp4").exists());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_of_a_file_fetch_it_once() {
+        let (db, data, cold) = setup().await;
+        let audio = data.path().join("old.mp4");
+        std::fs::write(&audio, b"audio").unwrap();
+        let audio_path = audio.to_string_lossy().into_owned();
+        db.insert_audio_chunk(&audio_path).await.unwrap();
+        sqlx::query("UPDATE audio_chunks SET timestamp = ?1")
+            .bind(Utc::now() - chrono::Duration::days(40))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let cold_storage = Arc::new(ColdStorage::new(
+            db.clone(),
+            ColdStore::Directory(cold.path().to_path_buf()),
+            &data.path().to_string_lossy(),
+            30,
+        ));
+        cold_storage.tier_old_media().await.unwrap();
+
+        let (first, second) = tokio::join!(
+            cold_storage.ensure_local(&audio_path),
+            cold_storage.ensure_local(&audio_path)
+        );
+        let fetched = [first.unwrap(), second.unwrap()];
+        assert_eq!(fetched.iter().filter(|fetched| **fetched).count(), 1);
+        assert_eq!(std::fs::read(&audio).unwrap(), b"audio");
+    }
+
+    #[tokio::test]
+    async fn test_image_chunks_move_as_directories() {
+        let (db, data, cold) = setup().await;
+        let chunk = data.path().join("monitor_1_chunk");
+        std::fs::create_dir(&chunk).unwrap();
+        std::fs::write(chunk.join("000000.webp"), b"first").unwrap();
+        std::fs::write(chunk.join("000001.webp"), b"second").unwrap();
+        let chunk_path = chunk.to_string_lossy().into_owned();
+        db.insert_video_chunk(&chunk_path, "test_device")
+            .await
+            .unwrap();
+        db.insert_frame(
+            "test_device",
+            Some(Utc::now() - chrono::Duration::days(40)),
+            None,
+            None,
+            Some("test"),
+            None,
+            Some(""),
+            None,
+            false,
+            Some(1.0),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let cold_storage = Arc::new(ColdStorage::new(
+            db.clone(),
+            ColdStore::Directory(cold.path().to_path_buf()),
+            &data.path().to_string_lossy(),
+            30,
+        ));
+        assert_eq!(cold_storage.tier_old_media().await.unwrap().files, 1);
+        assert!(!chunk.exists());
+        assert!(cold.path().join("monitor_1_chunk").join(".index").exists());
+
+        assert!(cold_storage.ensure_local(&chunk_path).await.unwrap());
+        assert_eq!(std::fs::read(chunk.join("000001.webp")).unwrap(), b"second");
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use screenpipe_server::data_deletion::DeleteDataQuery;
+
+    #[test]
+    fn test_delete_data_query_filter() {
+        let query = DeleteDataQuery {
+            app: Some(" zoom ".to_string()),
+            before: Some("2024-01-01".to_string()),
+            dry_run: true,
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+        assert_eq!(filter.app_name.as_deref(), Some("zoom"));
+        assert_eq!(
+            filter.end_time,
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(filter.start_time, None);
+        assert!(!filter.matches_audio());
+
+        let query = DeleteDataQuery {
+            after: Some("2024-01-01T09:30:00Z".to_string()),
+            ..Default::default()
+        };
+        let filter = query.filter().unwrap();
+        assert_eq!(
+            filter.start_time,
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap())
+        );
+        assert!(filter.matches_audio());
+    }
+
+    #[test]
+    fn test_delete_data_query_needs_a_valid_filter() {
+        // deleting everything takes more than forgetting the filters
+        assert!(DeleteDataQuery::default().filter().is_err());
+        let blank = DeleteDataQuery {
+            app: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert!(blank.filter().is_err());
+
+        let invalid = DeleteDataQuery {
+            before: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.filter().unwrap_err().contains("before"));
+
+        let inverted = DeleteDataQuery {
+            after: Some("2024-02-01".to_string()),
+            before: Some("2024-01-01".to_string()),
+            ..Default::default()
+        };
+        assert!(inverted.filter().is_err());
+    }
+}
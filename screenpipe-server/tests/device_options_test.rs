@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use screenpipe_core::Language;
+    use screenpipe_server::cli::{Cli, DeviceAudioChunkDuration, DeviceLanguages};
+    use std::time::Duration;
+
+    #[test]
+    fn test_device_options_override_the_defaults() {
+        let cli = Cli::try_parse_from([
+            "screenpipe",
+            "--language",
+            "english",
+            "--device-language",
+            "Zoom (output)=german,english",
+            "--device-audio-chunk-duration",
+            "MacBook Pro Microphone (input)=10",
+        ])
+        .unwrap();
+
+        let languages = cli.device_languages();
+        assert_eq!(
+            languages.get("Zoom (output)"),
+            Some(&vec![Language::German, Language::English])
+        );
+        assert!(!languages.contains_key("MacBook Pro Microphone (input)"));
+        let durations = cli.device_audio_chunk_durations();
+        assert_eq!(
+            durations.get("MacBook Pro Microphone (input)"),
+            Some(&Duration::from_secs(10))
+        );
+        assert_eq!(cli.audio_chunk_duration, 30);
+    }
+
+    #[test]
+    fn test_invalid_device_options_are_rejected() {
+        assert!("Zoom (output)".parse::<DeviceLanguages>().is_err());
+        assert!("Zoom (output)=klingon".parse::<DeviceLanguages>().is_err());
+        assert!("Zoom (output)=0"
+            .parse::<DeviceAudioChunkDuration>()
+            .is_err());
+        assert!("Zoom (output)=ten"
+            .parse::<DeviceAudioChunkDuration>()
+            .is_err());
+
+        // only the last '=' separates the device from its value
+        let duration = "a=b (input)=5".parse::<DeviceAudioChunkDuration>().unwrap();
+        assert_eq!(duration.device, "a=b (input)");
+        assert_eq!(duration.seconds, 5);
+    }
+}
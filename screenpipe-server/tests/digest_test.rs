@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_db::WindowSession;
+    use screenpipe_server::digest::{find_meetings, opened_documents, top_topics};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn session(app_name: &str, window_name: &str, start: i64, end: i64) -> WindowSession {
+        WindowSession {
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            browser_url: None,
+            start_time: at(start),
+            end_time: at(end),
+            frame_count: 10,
+        }
+    }
+
+    #[test]
+    fn test_top_topics() {
+        let texts: Vec<String> = [
+            "Quarterly budget review with finance",
+            "budget numbers for the quarterly review",
+            "Lunch with Alice",
+            "finance team: budget approved",
+        ]
+        .iter()
+        .map(|text| text.to_string())
+        .collect();
+
+        let topics: Vec<(String, usize)> = top_topics(&texts, 3)
+            .into_iter()
+            .map(|topic| (topic.term, topic.count))
+            .collect();
+        assert_eq!(
+            topics,
+            vec![
+                ("budget".to_string(), 3),
+                ("finance".to_string(), 2),
+                ("quarterly".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_meetings() {
+        let sessions = vec![
+            session("zoom.us", "Zoom Meeting", 0, 20),
+            session("Code", "main.rs", 20, 25),
+            // back to the same call after a few minutes, so the same meeting
+            session("zoom.us", "Weekly sync", 25, 30),
+            // a glance at the app is not a meeting
+            session("zoom.us", "Zoom", 120, 122),
+            session("Google Chrome", "Meet - Design review", 180, 240),
+        ];
+        let transcriptions = vec![at(5), at(29), at(100), at(200)];
+
+        let meetings = find_meetings(&sessions, &transcriptions);
+        assert_eq!(meetings.len(), 2);
+
+        assert_eq!(meetings[0].app_name, "zoom.us");
+        assert_eq!(meetings[0].title, "Zoom Meeting");
+        assert_eq!(meetings[0].start_time, at(0));
+        assert_eq!(meetings[0].end_time, at(30));
+        assert_eq!(meetings[0].transcriptions, 2);
+
+        assert_eq!(meetings[1].title, "Meet - Design review");
+        assert_eq!(meetings[1].transcriptions, 1);
+    }
+
+    #[test]
+    fn test_opened_documents() {
+        let sessions = vec![
+            session("Preview", "report.pdf - Preview", 0, 10),
+            session(
+                "Google Chrome",
+                "Q3 plan - Google Docs - Google Chrome",
+                10,
+                15,
+            ),
+            session("Firefox", "Mozilla Firefox - notes.txt", 15, 20),
+            session("Slack", "general - Acme - Slack", 20, 30),
+            session("Preview", "report.pdf - Preview", 30, 35),
+        ];
+
+        let documents: Vec<(String, String, i64)> = opened_documents(&sessions)
+            .into_iter()
+            .map(|document| (document.name, document.app_name, document.seconds))
+            .collect();
+        assert_eq!(
+            documents,
+            vec![
+                ("report.pdf".to_string(), "Preview".to_string(), 900),
+                ("Q3 plan".to_string(), "Google Chrome".to_string(), 300),
+                ("notes.txt".to_string(), "Firefox".to_string(), 300),
+            ]
+        );
+    }
+}
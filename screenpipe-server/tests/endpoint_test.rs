@@ -5,9 +5,10 @@ mod tests {
     use axum::http::{Request, StatusCode};
     use axum::Router;
     use chrono::DateTime;
-    use chrono::{Duration, Utc};
+    use chrono::{Duration, SecondsFormat, Utc};
     use screenpipe_audio::audio_manager::AudioManagerBuilder;
-    use screenpipe_db::{ContentType, DatabaseManager, SearchResult};
+    use screenpipe_db::{ContentType, DatabaseManager, SearchExclusions, SearchResult, SearchSort};
+    use screenpipe_server::timeline::{Timeline, TimelineItem};
     use screenpipe_server::PipeManager;
     use screenpipe_server::SCServer;
     use screenpipe_server::{ContentItem, PaginatedResponse};
@@ -179,11 +180,11 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, None, None, true, None)
+            .insert_frame("test_device", None, None, None, None, None, None, None, true, None, None)
             .await
             .unwrap();
         let frame_id2 = db
-            .insert_frame("test_device", None, None, None, None, true, None)
+            .insert_frame("test_device", None, None, None, None, None, None, None, true, None, None)
             .await
             .unwrap();
         db.insert_ocr_text(
@@ -191,6 +192,7 @@ mod tests {
             "This is a test OCR text", // 21 chars
             "",
             Arc::new(OcrEngine::Tesseract.into()),
+            false,
         )
         .await
         .unwrap();
@@ -199,6 +201,7 @@ mod tests {
             "Another OCR text for testing that should be longer than thirty characters", // >30 chars
             "",
             Arc::new(OcrEngine::Tesseract.into()),
+            false,
         )
         .await
         .unwrap();
@@ -255,7 +258,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 3);
@@ -277,7 +284,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -299,7 +310,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -321,7 +336,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -343,7 +362,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 1);
@@ -365,7 +388,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -387,7 +414,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(count, 1);
@@ -403,7 +434,7 @@ mod tests {
             .await
             .unwrap();
         let frame_id1 = db
-            .insert_frame("test_device", None, None, None, None, true, None)
+            .insert_frame("test_device", None, None, None, None, None, None, None, true, None, None)
             .await
             .unwrap();
         let audio_chunk_id1 = db.insert_audio_chunk("test_audio1.wav").await.unwrap();
@@ -427,6 +458,7 @@ mod tests {
             "old ocr text",
             "",
             Arc::new(OcrEngine::Tesseract.into()),
+            false,
         )
         .await
         .unwrap();
@@ -473,7 +505,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(ocr_results.len(), 0);
@@ -496,7 +533,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(audio_results.len(), 0);
@@ -520,7 +562,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(ocr_results.len(), 1);
@@ -548,7 +595,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
         assert_eq!(audio_results.len(), 1);
@@ -575,7 +627,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(ocr_count, 1);
@@ -596,7 +652,11 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None)
             .await
             .unwrap();
         assert_eq!(audio_count, 1);
@@ -617,7 +677,7 @@ mod tests {
             .await
             .unwrap();
         let old_frame_id = db
-            .insert_frame("test_device", None, None, None, None, true, None)
+            .insert_frame("test_device", None, None, None, None, None, None, None, true, None, None)
             .await
             .unwrap();
 
@@ -627,7 +687,7 @@ mod tests {
             .await
             .unwrap();
         let recent_frame_id = db
-            .insert_frame("test_device", None, None, None, None, true, None)
+            .insert_frame("test_device", None, None, None, None, None, None, None, true, None, None)
             .await
             .unwrap();
 
@@ -651,6 +711,7 @@ mod tests {
             "old task: write documentation",
             "",
             Arc::new(OcrEngine::Tesseract.into()),
+            false,
         )
         .await
         .unwrap();
@@ -660,6 +721,7 @@ mod tests {
             "current task: fix bug #123",
             "",
             Arc::new(OcrEngine::Tesseract.into()),
+            false,
         )
         .await
         .unwrap();
@@ -683,7 +745,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -715,7 +782,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -763,7 +835,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -788,7 +865,12 @@ mod tests {
                 None,
                 None,
                 None
-            )
+            ,
+                None,
+                false,
+                &SearchExclusions::default(),
+                None,
+                SearchSort::Time)
             .await
             .unwrap();
 
@@ -814,4 +896,205 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_openapi_spec_lists_the_routes() {
+        let (app, _db) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+        assert_eq!(spec["info"]["title"], "screenpipe");
+        assert_eq!(spec["info"]["version"], env!("CARGO_PKG_VERSION"));
+        for path in [
+            "/search",
+            "/health",
+            "/timeline",
+            "/webhooks",
+            "/alerts/rules",
+            "/search/keyword",
+        ] {
+            assert!(spec["paths"].get(path).is_some(), "{} is missing", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_cursor_is_stable_while_capturing() {
+        let (app, db) = setup_test_app().await;
+        db.insert_video_chunk("test_video1.mp4", "test_device")
+            .await
+            .unwrap();
+        let insert_ocr_frame = |timestamp: DateTime<Utc>| {
+            let db = db.clone();
+            async move {
+                let frame_id = db
+                    .insert_frame(
+                        "test_device",
+                        Some(timestamp),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        true,
+                        None,
+                        None,
+                    )
+                    .await
+                    .unwrap();
+                db.insert_ocr_text(
+                    frame_id,
+                    "paging text",
+                    "",
+                    Arc::new(OcrEngine::Tesseract.into()),
+                    false,
+                )
+                .await
+                .unwrap();
+                frame_id
+            }
+        };
+        let start = Utc::now() - Duration::hours(1);
+        let mut frame_ids = Vec::new();
+        // frames of two monitors share a timestamp, pages cut between them
+        for i in 0..5 {
+            frame_ids.push(insert_ocr_frame(start + Duration::seconds(i / 2)).await);
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        for page in 0.. {
+            let uri = match &cursor {
+                Some(cursor) => format!("/search?content_type=ocr&limit=2&cursor={}", cursor),
+                None => "/search?content_type=ocr&limit=2".to_string(),
+            };
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let results: PaginatedResponse<ContentItem> = serde_json::from_slice(&body).unwrap();
+            for item in results.data {
+                if let ContentItem::OCR(ocr) = item {
+                    seen.push(ocr.frame_id);
+                }
+            }
+            if page == 0 {
+                // shifts the offsets of everything after the first page
+                insert_ocr_frame(Utc::now()).await;
+            }
+            match results.pagination.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        frame_ids.reverse();
+        assert_eq!(seen, frame_ids);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/search?cursor=not-a-cursor")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_pages_through_frames_at_the_same_time() {
+        let (app, db) = setup_test_app().await;
+        db.insert_video_chunk("monitor_1.mp4", "monitor_1")
+            .await
+            .unwrap();
+        // three monitors captured at once, with what was said meanwhile
+        let timestamp = Utc::now() - Duration::minutes(5);
+        let mut frame_ids = Vec::new();
+        for _ in 0..3 {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    Some(timestamp),
+                    None,
+                    None,
+                    Some("Slack"),
+                    None,
+                    Some("general"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription_at(
+            audio_chunk_id,
+            "hello",
+            0,
+            "",
+            &screenpipe_db::AudioDevice {
+                name: "microphone".to_string(),
+                device_type: screenpipe_db::DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+            timestamp,
+        )
+        .await
+        .unwrap();
+
+        let start = (timestamp - Duration::minutes(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let end = (timestamp + Duration::minutes(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut seen_frames = Vec::new();
+        let mut transcripts = 0;
+        let mut cursor: Option<String> = None;
+        for _ in 0..10 {
+            let mut uri = format!("/timeline?start={}&end={}&limit=1", start, end);
+            if let Some(cursor) = &cursor {
+                uri.push_str(&format!("&cursor={}", cursor));
+            }
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let timeline: Timeline = serde_json::from_slice(&body).unwrap();
+            for item in timeline.items {
+                match item {
+                    TimelineItem::Frame(frame) => seen_frames.push(frame.frame_id),
+                    TimelineItem::Transcript(_) => transcripts += 1,
+                    _ => {}
+                }
+            }
+            cursor = timeline.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen_frames, frame_ids);
+        assert_eq!(transcripts, 1);
+    }
 }
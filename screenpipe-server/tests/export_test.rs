@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType, OcrEngine};
+    use screenpipe_server::export::{ExportManifest, ExportRequest, ExportStatus, Exports};
+    use std::io::Read;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_export_request_needs_a_range() {
+        let request: ExportRequest = serde_json::from_value(serde_json::json!({
+            "start_time": "2025-05-21T09:00:00Z",
+            "end_time": "2025-05-21T10:00:00Z"
+        }))
+        .unwrap();
+        assert!(request.include_media);
+        assert!(request.validate().is_ok());
+
+        let inverted = ExportRequest {
+            start_time: request.end_time,
+            end_time: request.start_time,
+            include_media: false,
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_records_and_media_to_a_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let video = dir.path().join("monitor_1.mp4");
+        std::fs::write(&video, b"video").unwrap();
+        db.insert_video_chunk(&video.to_string_lossy(), "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                None,
+                Some("Slack"),
+                None,
+                Some("general"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "hello team",
+            r#"[{"text":"hello"}]"#,
+            Arc::new(OcrEngine::Tesseract),
+            false,
+        )
+        .await
+        .unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("gone.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+        db.insert_audio_transcription(audio_chunk_id, "hi", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let exports = Arc::new(Exports::new(db.clone(), None, dir.path()));
+        let request = ExportRequest {
+            start_time: Utc::now() - Duration::hours(1),
+            end_time: Utc::now() + Duration::hours(1),
+            include_media: true,
+        };
+        let job = exports.start(request.clone()).unwrap();
+        assert_eq!(job.status, ExportStatus::Running);
+        // one at a time
+        assert!(exports.start(request).is_err());
+
+        let mut job = exports.get(&job.id).unwrap();
+        for _ in 0..100 {
+            if job.status != ExportStatus::Running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            job = exports.get(&job.id).unwrap();
+        }
+        assert_eq!(job.status, ExportStatus::Done, "{:?}", job.error);
+        assert_eq!((job.records_done, job.records_total), (2, 2));
+        assert_eq!(job.progress, 1.0);
+
+        let archive = exports.archive(&job.id).unwrap();
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&archive).unwrap()).unwrap();
+        let read = |zip: &mut zip::ZipArchive<std::fs::File>, name: &str| {
+            let mut content = String::new();
+            zip.by_name(name)
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            content
+        };
+        let manifest: ExportManifest =
+            serde_json::from_str(&read(&mut zip, "manifest.json")).unwrap();
+        assert_eq!((manifest.frames, manifest.transcriptions), (1, 1));
+        assert_eq!(manifest.media.len(), 1);
+        assert_eq!(manifest.media[0].path, "media/monitor_1.mp4");
+        assert_eq!(manifest.missing_media, vec!["gone.mp4"]);
+        assert_eq!(read(&mut zip, "media/monitor_1.mp4"), "video");
+
+        let frame: serde_json::Value =
+            serde_json::from_str(read(&mut zip, "frames.jsonl").lines().next().unwrap()).unwrap();
+        assert_eq!(frame["app_name"], "Slack");
+        assert_eq!(frame["text_json"][0]["text"], "hello");
+        assert_eq!(frame["media"], "media/monitor_1.mp4");
+
+        // still listed after a restart, once saved
+        let mut restarted = Exports::new(db.clone(), None, dir.path());
+        for _ in 0..100 {
+            if restarted.get(&job.id).as_ref() == Some(&job) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            restarted = Exports::new(db.clone(), None, dir.path());
+        }
+        assert_eq!(restarted.get(&job.id), Some(job.clone()));
+        assert_eq!(restarted.archive(&job.id), Some(archive.clone()));
+
+        assert!(exports.delete(&job.id).await.unwrap());
+        assert!(!archive.exists());
+        assert!(exports.get(&job.id).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+    use screenpipe_server::frame_import::{
+        chunk_dir, find_frame_files, is_screenshot, is_video, sampled_frame_times, taken_at,
+    };
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    fn local(year: i32, month: u32, day: u32, h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        let time = NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap();
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_screenshots_are_dated_by_their_file_name_then_modified_time() {
+        for name in [
+            "Screenshot 2024-05-01 at 10.15.00.png",
+            "Screenshot_20240501-101500.png",
+            "Screenshot 2024-05-01 101500.png",
+        ] {
+            assert_eq!(
+                taken_at(name, None),
+                Some(local(2024, 5, 1, 10, 15, 0)),
+                "{}",
+                name
+            );
+        }
+        // us locale macos screenshots are on a 12 hour clock
+        assert_eq!(
+            taken_at("Screenshot 2024-05-01 at 2.15.00 PM.png", None),
+            Some(local(2024, 5, 1, 14, 15, 0))
+        );
+        assert_eq!(
+            taken_at("Screenshot 2024-05-01 at 12.05.00\u{202f}AM.png", None),
+            Some(local(2024, 5, 1, 0, 5, 0))
+        );
+        assert_eq!(
+            taken_at("Screenshot 2024-05-01 at 12.05.00 pm.png", None),
+            Some(local(2024, 5, 1, 12, 5, 0))
+        );
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            taken_at("diagram.png", Some(modified)),
+            Some(DateTime::<Utc>::from(modified))
+        );
+        assert_eq!(taken_at("diagram.png", None), None);
+    }
+
+    #[test]
+    fn test_video_frames_are_timed_by_the_sampling_rate() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+        assert_eq!(
+            sampled_frame_times(start, 2.0, 3),
+            vec![
+                start,
+                start + Duration::milliseconds(500),
+                start + Duration::seconds(1)
+            ]
+        );
+        assert!(sampled_frame_times(start, 1.0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_screenshots_and_videos_are_found_in_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let recordings = dir.path().join("recordings");
+        std::fs::create_dir(&recordings).unwrap();
+        for file in [
+            "a.PNG",
+            "notes.txt",
+            "recordings/demo.mov",
+            "recordings/b.jpg",
+        ] {
+            std::fs::write(dir.path().join(file), b"").unwrap();
+        }
+
+        let files = find_frame_files(dir.path()).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("a.PNG"),
+                recordings.join("b.jpg"),
+                recordings.join("demo.mov")
+            ]
+        );
+        assert!(is_screenshot(Path::new("a.PNG")));
+        assert!(is_video(Path::new("demo.mov")));
+        assert!(!is_video(Path::new("a.PNG")));
+        assert!(find_frame_files(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_chunks_are_named_after_their_source() {
+        let data = Path::new("/data");
+        let chunk = chunk_dir(data, Path::new("/screenshots/Screenshot 1.png"));
+        assert_eq!(
+            chunk,
+            chunk_dir(data, Path::new("/screenshots/Screenshot 1.png"))
+        );
+        assert_ne!(chunk, chunk_dir(data, Path::new("/other/Screenshot 1.png")));
+        assert!(chunk.starts_with(data));
+        assert!(chunk
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("imported_Screenshot_1_"));
+    }
+}
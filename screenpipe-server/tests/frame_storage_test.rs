@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, RgbImage};
+    use screenpipe_server::frame_storage::{
+        content_key, ffmpeg_input, frame_file_name, frame_source, FrameDedup, FrameFormat,
+        FrameStorage, MonitorFrameStorage,
+    };
+    use screenpipe_vision::monitor::MonitorSelector;
+
+    #[test]
+    fn test_parse_monitor_frame_storage() {
+        let storage: MonitorFrameStorage = "index:1=webp,60,0.5".parse().unwrap();
+        assert_eq!(storage.selector, MonitorSelector::Index(1));
+        assert_eq!(storage.format, FrameFormat::Webp);
+        assert_eq!(storage.quality, Some(60));
+        assert_eq!(storage.scale, Some(0.5));
+
+        let storage: MonitorFrameStorage = "primary=AVIF".parse().unwrap();
+        assert_eq!(storage.format, FrameFormat::Avif);
+        assert_eq!(storage.quality, None);
+        assert_eq!(storage.scale, None);
+
+        assert!("primary".parse::<MonitorFrameStorage>().is_err());
+        assert!("primary=gif".parse::<MonitorFrameStorage>().is_err());
+        assert!("primary=webp,0".parse::<MonitorFrameStorage>().is_err());
+        assert!("primary=webp,80,2".parse::<MonitorFrameStorage>().is_err());
+        assert!("primary=webp,80,0.5,1"
+            .parse::<MonitorFrameStorage>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_downscale() {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(1920, 1080));
+
+        let full = FrameStorage::default();
+        assert_eq!(full.downscale(&image).width(), 1920);
+
+        let half = FrameStorage::new(FrameFormat::Webp, 60, 0.5).unwrap();
+        let scaled = half.downscale(&image);
+        assert_eq!((scaled.width(), scaled.height()), (960, 540));
+        assert_eq!(half.to_string(), "webp q60 50%");
+        assert_eq!(full.to_string(), "video");
+    }
+
+    #[test]
+    fn test_image_chunks_resolve_to_frame_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk = dir.path().to_string_lossy().into_owned();
+        for offset in 0..3 {
+            std::fs::write(dir.path().join(frame_file_name(offset, "webp")), b"").unwrap();
+        }
+
+        let (source, offset) = frame_source(&chunk, 2);
+        assert!(source.ends_with("000002.webp"));
+        assert_eq!(offset, 0);
+        assert!(ffmpeg_input(&chunk).ends_with("%06d.webp"));
+
+        let video = dir.path().join("monitor_1.mp4");
+        let video = video.to_string_lossy();
+        assert_eq!(frame_source(&video, 7), (video.to_string(), 7));
+        assert_eq!(ffmpeg_input(&video), video);
+    }
+
+    #[test]
+    fn test_content_keys() {
+        let gradient = RgbImage::from_fn(640, 480, |x, _| {
+            let value = (x * 255 / 640) as u8;
+            image::Rgb([value, value, value])
+        });
+        let mut changed = gradient.clone();
+        changed.put_pixel(600, 20, image::Rgb([255, 0, 0]));
+        let image = DynamicImage::ImageRgb8(gradient);
+        let changed = DynamicImage::ImageRgb8(changed);
+
+        assert_eq!(content_key(FrameDedup::Off, &image), None);
+
+        let exact = content_key(FrameDedup::Exact, &image).unwrap();
+        assert_eq!(content_key(FrameDedup::Exact, &image), Some(exact.clone()));
+        assert_ne!(content_key(FrameDedup::Exact, &changed), Some(exact));
+
+        let storage = FrameStorage::new(FrameFormat::Webp, 60, 1.0)
+            .unwrap()
+            .with_dedup(FrameDedup::Exact);
+        assert!(storage.deduplicates());
+        assert_eq!(storage.to_string(), "webp q60 dedup exact");
+        assert!(!FrameStorage::default()
+            .with_dedup(FrameDedup::Exact)
+            .deduplicates());
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use screenpipe_db::{AudioResult, ContentType, DeviceType, OCRResult, SearchResult};
+    use screenpipe_server::hybrid_search::{blend_results, includes_audio, includes_ocr};
+
+    fn ocr(frame_id: i64) -> SearchResult {
+        SearchResult::OCR(OCRResult {
+            frame_id,
+            frame_name: String::new(),
+            ocr_text: format!("frame {}", frame_id),
+            text_json: String::new(),
+            timestamp: Utc::now(),
+            file_path: String::new(),
+            offset_index: 0,
+            app_name: String::new(),
+            ocr_engine: String::new(),
+            low_quality: false,
+            window_name: String::new(),
+            tags: Vec::new(),
+            browser_url: None,
+            browser_title: None,
+            app_id: None,
+            window_geometry: None,
+            focused: None,
+            visible_percentage: 1.0,
+            snippet: None,
+            score: None,
+        })
+    }
+
+    fn audio(audio_chunk_id: i64) -> SearchResult {
+        SearchResult::Audio(AudioResult {
+            audio_chunk_id,
+            transcription: String::new(),
+            timestamp: Utc::now(),
+            file_path: String::new(),
+            offset_index: 0,
+            transcription_engine: String::new(),
+            tags: Vec::new(),
+            device_name: String::new(),
+            device_type: DeviceType::Input,
+            speaker: None,
+            start_time: None,
+            end_time: None,
+            snippet: None,
+            score: None,
+            words: Vec::new(),
+            translation: None,
+        })
+    }
+
+    fn keys(results: &[SearchResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|result| match result {
+                SearchResult::OCR(ocr) => format!("ocr {}", ocr.frame_id),
+                SearchResult::Audio(audio) => format!("audio {}", audio.audio_chunk_id),
+                SearchResult::UI(ui) => format!("ui {}", ui.id),
+                SearchResult::Clipboard(entry) => format!("clipboard {}", entry.id),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_results_found_by_keyword_and_meaning_come_first() {
+        let keyword = vec![ocr(1), ocr(2), audio(1)];
+        let semantic_ocr = vec![ocr(3), ocr(2)];
+        let semantic_audio = vec![audio(1)];
+
+        let blended = blend_results(vec![keyword, semantic_ocr, semantic_audio]);
+        assert_eq!(keys(&blended), vec!["audio 1", "ocr 2", "ocr 1", "ocr 3"]);
+    }
+
+    #[test]
+    fn test_ties_keep_list_order() {
+        let blended = blend_results(vec![vec![ocr(1)], vec![audio(1)]]);
+        assert_eq!(keys(&blended), vec!["ocr 1", "audio 1"]);
+        assert!(blend_results(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_content_types() {
+        assert!(includes_ocr(&ContentType::All) && includes_audio(&ContentType::All));
+        assert!(includes_ocr(&ContentType::OcrAndUi) && !includes_audio(&ContentType::OcrAndUi));
+        assert!(!includes_ocr(&ContentType::UI) && !includes_audio(&ContentType::UI));
+    }
+}
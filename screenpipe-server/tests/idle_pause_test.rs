@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::idle_pause::{IdlePause, IdlePolicy, IdleState};
+    use screenpipe_vision::capture_pause::{capture_pause_reasons, is_capture_paused};
+    use std::sync::Arc;
+
+    fn policy(pause_on_idle: bool, pause_on_lock: bool) -> IdlePolicy {
+        IdlePolicy {
+            pause_on_idle,
+            pause_on_lock,
+            pause_audio_on_idle: false,
+            idle_timeout: std::time::Duration::from_secs(300),
+        }
+    }
+
+    #[test]
+    fn test_idle_state_follows_the_policy() {
+        let both = policy(true, true);
+        let minutes = |m: u64| Some(std::time::Duration::from_secs(m * 60));
+        assert_eq!(both.state(minutes(1), false), IdleState::Active);
+        assert_eq!(both.state(minutes(5), false), IdleState::Idle);
+        // locked comes first, the lock screen usually shows after some idle time
+        assert_eq!(both.state(minutes(10), true), IdleState::Locked);
+        // idle time unknown
+        assert_eq!(both.state(None, false), IdleState::Active);
+
+        assert_eq!(
+            policy(false, true).state(minutes(10), false),
+            IdleState::Active
+        );
+        assert_eq!(
+            policy(true, false).state(minutes(1), true),
+            IdleState::Active
+        );
+        assert_eq!(IdleState::Locked.pause_reason(), Some("locked"));
+        assert_eq!(IdleState::Active.pause_reason(), None);
+
+        // calls only listened to have no input, idle time stops audio only if asked
+        assert!(!both.pauses_audio(IdleState::Idle));
+        assert!(both.pauses_audio(IdleState::Locked));
+        let with_audio = IdlePolicy {
+            pause_audio_on_idle: true,
+            ..both
+        };
+        assert!(with_audio.pauses_audio(IdleState::Idle));
+        assert!(!with_audio.pauses_audio(IdleState::Active));
+    }
+
+    #[tokio::test]
+    async fn test_pauses_are_stored_and_resumed() {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let mut pause = IdlePause::new(db.clone(), None, policy(true, true));
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+
+        pause.update(IdleState::Active, at(0)).await.unwrap();
+        assert!(!is_capture_paused());
+
+        pause.update(IdleState::Idle, at(10)).await.unwrap();
+        assert_eq!(capture_pause_reasons(), ["idle"]);
+        // still idle, nothing changes
+        pause.update(IdleState::Idle, at(20)).await.unwrap();
+        pause.update(IdleState::Locked, at(30)).await.unwrap();
+        assert_eq!(capture_pause_reasons(), ["locked"]);
+        pause.update(IdleState::Active, at(40)).await.unwrap();
+        assert!(!is_capture_paused());
+
+        let pauses = db.list_capture_pauses(at(0), at(60), 10).await.unwrap();
+        let stored: Vec<_> = pauses
+            .iter()
+            .map(|pause| (pause.reason.as_str(), pause.start_time, pause.end_time))
+            .collect();
+        assert_eq!(
+            stored,
+            [
+                ("idle", at(10), Some(at(30))),
+                ("locked", at(30), Some(at(40)))
+            ]
+        );
+
+        // left open by a crash
+        let open = db.start_capture_pause("idle", at(50)).await.unwrap();
+        assert_eq!(db.end_open_capture_pauses(at(70)).await.unwrap(), 1);
+        let pauses = db.list_capture_pauses(at(45), at(60), 10).await.unwrap();
+        assert_eq!(pauses.len(), 1);
+        assert_eq!((pauses[0].id, pauses[0].end_time), (open, Some(at(70))));
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::llm::{Llm, LlmProvider};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_body_per_provider() {
+        let body = Llm::new(LlmProvider::Ollama).request_body("be brief", "hello");
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "hello");
+
+        let body = Llm::new(LlmProvider::Anthropic).request_body("be brief", "hello");
+        assert_eq!(body["system"], "be brief");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert!(body["max_tokens"].is_u64());
+    }
+
+    #[test]
+    fn test_response_text_per_provider() {
+        let ollama = Llm::new(LlmProvider::Ollama);
+        assert_eq!(
+            ollama
+                .response_text(&json!({"message": {"role": "assistant", "content": " hi \n"}}))
+                .unwrap(),
+            "hi"
+        );
+
+        let openai = Llm::new(LlmProvider::OpenAi);
+        assert_eq!(
+            openai
+                .response_text(&json!({"choices": [{"message": {"content": "hi"}}]}))
+                .unwrap(),
+            "hi"
+        );
+        assert!(openai.response_text(&json!({"choices": []})).is_err());
+
+        let anthropic = Llm::new(LlmProvider::Anthropic);
+        let body = json!({"content": [
+            {"type": "text", "text": "hello "},
+            {"type": "tool_use", "id": "x"},
+            {"type": "text", "text": "world"},
+        ]});
+        assert_eq!(anthropic.response_text(&body).unwrap(), "hello world");
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::mcp::{McpScope, McpServer, MCP_PROTOCOL_VERSION};
+    use serde_json::{json, Value};
+
+    fn tool_names(server: &McpServer) -> Vec<String> {
+        server
+            .tools()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_notifications() {
+        let server = McpServer::new(3030, vec![McpScope::Search]);
+        let response = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {"protocolVersion": MCP_PROTOCOL_VERSION, "capabilities": {}},
+            }))
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], MCP_PROTOCOL_VERSION);
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert_eq!(server.handle_message(notification).await, None);
+
+        let response = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": "a", "method": "resources/list"}))
+            .await
+            .unwrap();
+        assert_eq!(response["id"], "a");
+        assert_eq!(response["error"]["code"], -32601);
+    }
+
+    #[tokio::test]
+    async fn test_tools_follow_scopes() {
+        let server = McpServer::new(
+            3030,
+            vec![McpScope::Search, McpScope::Timeline, McpScope::Frames],
+        );
+        assert_eq!(
+            tool_names(&server),
+            vec!["search_screen_history", "get_timeline", "get_frame"]
+        );
+
+        let server = McpServer::new(3030, vec![McpScope::Search]);
+        assert_eq!(tool_names(&server), vec!["search_screen_history"]);
+        let response = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["tools"].as_array().unwrap().len(), 1);
+
+        let response = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": {"name": "get_frame", "arguments": {"frame_id": 1}},
+            }))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], -32602);
+        let message = response["error"]["message"].as_str().unwrap();
+        assert!(message.contains("--scope frames"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_go_back_to_the_model() {
+        let server = McpServer::new(3030, vec![McpScope::Timeline]);
+        let response = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "tools/call",
+                "params": {"name": "get_timeline", "arguments": {"start": "2024-06-01T00:00:00Z"}},
+            }))
+            .await
+            .unwrap();
+        let result: &Value = &response["result"];
+        assert_eq!(result["isError"], true);
+        assert!(result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("start and end are required"));
+    }
+
+    #[test]
+    fn test_pinned_urls_need_https_and_a_fingerprint() {
+        let fingerprint = ["AB"; 32].join(":");
+        let server = || McpServer::new(3030, vec![McpScope::Search]);
+        assert!(server()
+            .with_url("https://laptop.local:3030/", Some(&fingerprint))
+            .is_ok());
+        // lowercase and without separators, like other tools print it
+        let compact = fingerprint.replace(':', "").to_lowercase();
+        assert!(server()
+            .with_url("https://laptop.local:3030", Some(&compact))
+            .is_ok());
+        assert!(server().with_url("http://localhost:3030", None).is_ok());
+
+        assert!(server()
+            .with_url("http://localhost:3030", Some(&fingerprint))
+            .is_err());
+        assert!(server()
+            .with_url("https://localhost:3030", Some("AB:CD"))
+            .is_err());
+        assert!(server().with_url("localhost:3030", None).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_db::{ActionItem, CalendarEvent, WindowSession};
+    use screenpipe_server::meetings::{detect_meetings, is_over, meeting_tags, parse_notes};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn session(app_name: &str, window_name: &str, start: i64, end: i64) -> WindowSession {
+        WindowSession {
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            browser_url: None,
+            start_time: at(start),
+            end_time: at(end),
+            frame_count: 10,
+        }
+    }
+
+    #[test]
+    fn test_meetings_need_speech() {
+        let sessions = vec![
+            session("zoom.us", "Zoom Meeting", 0, 30),
+            // left open without a call
+            session("Microsoft Teams", "Chat | Microsoft Teams", 60, 90),
+        ];
+        let transcriptions = vec![at(10), at(20)];
+
+        let meetings = detect_meetings(&sessions, &transcriptions);
+        assert_eq!(meetings.len(), 1);
+        assert_eq!(meetings[0].app_name, "zoom.us");
+        assert_eq!(meetings[0].transcriptions, 2);
+        assert_eq!(
+            meeting_tags(&meetings[0]),
+            vec!["meeting", "zoom.us", "Zoom Meeting"]
+        );
+    }
+
+    #[test]
+    fn test_meeting_is_over_after_a_while() {
+        let meeting = CalendarEvent {
+            id: 1,
+            uid: "detected:zoom.us:0".to_string(),
+            source: "detected".to_string(),
+            app_name: Some("zoom.us".to_string()),
+            title: "Zoom Meeting".to_string(),
+            start_time: at(0),
+            end_time: at(30),
+            attendees: Vec::new(),
+            location: None,
+            annotation_id: None,
+            frame_count: 0,
+            transcription_count: 0,
+        };
+        assert!(!is_over(&meeting, at(32)));
+        assert!(is_over(&meeting, at(45)));
+    }
+
+    #[test]
+    fn test_parses_notes() {
+        let answer = "Here are the notes:\n```json\n{\"summary\": \" Planned the launch. \", \
+                      \"decisions\": [\"Ship on Monday\", \" \"], \"action_items\": \
+                      [{\"task\": \"Write the release notes\", \"owner\": \"Alice\", \
+                      \"due\": null}, {\"task\": \"\"}]}\n```";
+        let notes = parse_notes(answer);
+        assert_eq!(notes.summary, "Planned the launch.");
+        assert_eq!(notes.decisions, vec!["Ship on Monday"]);
+        assert_eq!(
+            notes.action_items,
+            vec![ActionItem {
+                task: "Write the release notes".to_string(),
+                owner: Some("Alice".to_string()),
+                due: None,
+            }]
+        );
+
+        // not the JSON asked for, kept as the summary
+        let notes = parse_notes("We planned the launch.");
+        assert_eq!(notes.summary, "We planned the launch.");
+        assert!(notes.decisions.is_empty() && notes.action_items.is_empty());
+    }
+}
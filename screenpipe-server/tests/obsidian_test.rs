@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, NaiveDate, TimeZone, Utc};
+    use screenpipe_db::{DatabaseManager, ExportFrame, ExportTranscription};
+    use screenpipe_server::obsidian::{
+        render_note, Highlight, HighlightPicker, NoteReport, ObsidianExporter,
+    };
+    use std::sync::Arc;
+
+    fn frame(minute: i64, app_name: &str, text: &str) -> ExportFrame {
+        ExportFrame {
+            id: minute,
+            timestamp: Utc.with_ymd_and_hms(2025, 5, 21, 9, 0, 0).unwrap()
+                + Duration::minutes(minute),
+            device_name: "monitor_1".to_string(),
+            app_name: Some(app_name.to_string()),
+            window_name: Some("main".to_string()),
+            browser_url: None,
+            focused: Some(true),
+            file_path: "gone.mp4".to_string(),
+            offset_index: 0,
+            text: Some(text.to_string()),
+            text_json: None,
+            ocr_engine: None,
+        }
+    }
+
+    #[test]
+    fn test_highlights_window_switches_and_long_stretches() {
+        let mut picker = HighlightPicker::default();
+        let picked: Vec<i64> = [
+            frame(0, "Slack", "hello"),
+            frame(1, "Slack", "hello again"),
+            frame(2, "Code", ""),
+            frame(3, "Code", "fn main"),
+            frame(14, "Code", "fn main() {}"),
+            frame(15, "Slack", "back"),
+        ]
+        .iter()
+        .filter(|frame| picker.pick(frame))
+        .map(|frame| frame.id)
+        .collect();
+        // frames not read yet are skipped
+        assert_eq!(picked, vec![0, 3, 14, 15]);
+    }
+
+    #[test]
+    fn test_render_note() {
+        let date = NaiveDate::from_ymd_opt(2025, 5, 21).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2025, 5, 21, 9, 14, 0).unwrap();
+        let highlights = vec![Highlight {
+            timestamp,
+            app_name: "Slack".to_string(),
+            window_name: "general".to_string(),
+            text: "standup\n  at ten".to_string(),
+            image: Some("attachments/2025-05-21/frame-1.jpg".to_string()),
+        }];
+        let transcriptions = vec![ExportTranscription {
+            id: 1,
+            timestamp: timestamp + Duration::minutes(1),
+            device: "microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: " let's start ".to_string(),
+            transcription_engine: "whisper".to_string(),
+            start_time: None,
+            end_time: None,
+            file_path: "audio.mp4".to_string(),
+        }];
+
+        let note = render_note(date, &highlights, &transcriptions, &Utc);
+        assert!(note.starts_with("---\ndate: 2025-05-21\n"));
+        assert!(note.contains("### 09:14 Slack - general\n\n> standup at ten\n"));
+        assert!(note.contains("![Slack](attachments/2025-05-21/frame-1.jpg)"));
+        assert!(note.contains("- **09:15:00** microphone: let's start\n"));
+
+        assert!(render_note(date, &[], &[], &Utc).contains("Nothing was recorded."));
+
+        // recorded text doesn't add tags or links to the vault
+        let highlights = vec![Highlight {
+            text: "#standup [[notes]]".to_string(),
+            ..highlights[0].clone()
+        }];
+        let note = render_note(date, &highlights, &[], &Utc);
+        assert!(note.contains(r"> \#standup \[\[notes\]\]"), "{}", note);
+    }
+
+    #[tokio::test]
+    async fn test_export_day_writes_the_note() {
+        let vault = tempfile::tempdir().unwrap();
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        db.insert_video_chunk("gone.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                None,
+                None,
+                Some("Slack"),
+                None,
+                Some("general"),
+                None,
+                true,
+                Some(1.0),
+                None,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "standup at ten",
+            "",
+            Arc::new(screenpipe_db::OcrEngine::Tesseract),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let exporter = ObsidianExporter::new(db, vault.path().to_path_buf());
+        let today = Local::now().date_naive();
+        let report = exporter.export_day(today).await.unwrap();
+        assert_eq!((report.highlights, report.transcriptions), (1, 0));
+        // the recording is gone, so the frame has no image
+        assert_eq!(report.images, 0);
+        let note = std::fs::read_to_string(exporter.note_path(today)).unwrap();
+        assert!(note.contains("> standup at ten"));
+    }
+    #[tokio::test]
+    async fn test_days_over_are_written_once_and_backfilled() {
+        let vault = tempfile::tempdir().unwrap();
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let exporter = ObsidianExporter::new(db, vault.path().to_path_buf());
+        let day = |n| NaiveDate::from_ymd_opt(2025, 5, n).unwrap();
+
+        // written during the day, e.g. by `screenpipe export`
+        exporter.export_day(day(20)).await.unwrap();
+        let dates = |reports: Vec<NoteReport>| {
+            reports
+                .into_iter()
+                .map(|report| report.date)
+                .collect::<Vec<_>>()
+        };
+        let written = exporter.export_complete_days(day(21)).await.unwrap();
+        assert_eq!(dates(written), vec![day(20)]);
+        assert!(exporter
+            .export_complete_days(day(21))
+            .await
+            .unwrap()
+            .is_empty());
+        // the server was off for two days
+        let written = exporter.export_complete_days(day(24)).await.unwrap();
+        assert_eq!(dates(written), vec![day(21), day(22), day(23)]);
+        assert!(exporter.note_path(day(23)).exists());
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_server::pagination::{next_before_cursor, next_offset_cursor, Cursor};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 20, 9, 0, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn seen(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cursor_round_trips() {
+        for cursor in [
+            Cursor::Before {
+                timestamp: at(3),
+                seen: seen(&["ocr:7", "audio:2:0"]),
+            },
+            Cursor::Offset {
+                as_of: at(0),
+                offset: 40,
+            },
+        ] {
+            let encoded = cursor.encode();
+            assert!(encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            assert_eq!(Cursor::decode(&encoded), Ok(cursor));
+        }
+        assert!(Cursor::decode("not-a-cursor").is_err());
+        assert!(Cursor::decode("").is_err());
+    }
+
+    #[test]
+    fn test_cursor_bounds_the_time_range() {
+        let cursor = Cursor::Before {
+            timestamp: at(10),
+            seen: seen(&["ui:3"]),
+        };
+        assert_eq!(cursor.end_time(None), at(10));
+        assert_eq!(cursor.end_time(Some(at(20))), at(10));
+        assert_eq!(cursor.end_time(Some(at(5))), at(5));
+        assert_eq!(cursor.offset(), 0);
+        assert_eq!(cursor.seen(), ["ui:3"]);
+    }
+
+    #[test]
+    fn test_next_before_cursor() {
+        let page = |results: &[(i64, &str)]| -> Vec<(DateTime<Utc>, String)> {
+            results
+                .iter()
+                .map(|(secs, key)| (at(*secs), key.to_string()))
+                .collect()
+        };
+        // a short page is the last
+        assert_eq!(
+            next_before_cursor(None, &page(&[(3, "ocr:3"), (2, "ocr:2")]), 3),
+            None
+        );
+
+        assert_eq!(
+            next_before_cursor(
+                None,
+                &page(&[(5, "ocr:5"), (4, "ocr:4"), (4, "audio:1:0")]),
+                3
+            ),
+            Some(Cursor::Before {
+                timestamp: at(4),
+                seen: seen(&["ocr:4", "audio:1:0"]),
+            })
+        );
+
+        // a page all at the timestamp the cursor stopped at leaves those out too
+        let cursor = Cursor::Before {
+            timestamp: at(4),
+            seen: seen(&["ocr:4", "audio:1:0"]),
+        };
+        assert_eq!(
+            next_before_cursor(Some(&cursor), &page(&[(4, "ui:9"), (4, "ui:8")]), 2),
+            Some(Cursor::Before {
+                timestamp: at(4),
+                seen: seen(&["ocr:4", "audio:1:0", "ui:9", "ui:8"]),
+            })
+        );
+        assert_eq!(
+            next_before_cursor(Some(&cursor), &page(&[(4, "ui:9"), (1, "ocr:1")]), 2),
+            Some(Cursor::Before {
+                timestamp: at(1),
+                seen: seen(&["ocr:1"]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_next_offset_cursor() {
+        assert_eq!(next_offset_cursor(None, 1, 2, at(0)), None);
+        let first = next_offset_cursor(None, 2, 2, at(0)).unwrap();
+        assert_eq!(
+            first,
+            Cursor::Offset {
+                as_of: at(0),
+                offset: 2,
+            }
+        );
+        // pages keep the time of the first
+        assert_eq!(
+            next_offset_cursor(Some(&first), 2, 2, at(60)),
+            Some(Cursor::Offset {
+                as_of: at(0),
+                offset: 4,
+            })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use screenpipe_db::{OCRResult, SearchResult};
+    use screenpipe_server::pattern_search::{TextMatcher, MAX_PATTERN_LEN};
+
+    fn ocr(frame_id: i64, text: &str) -> SearchResult {
+        SearchResult::OCR(OCRResult {
+            frame_id,
+            frame_name: String::new(),
+            ocr_text: text.to_string(),
+            text_json: String::new(),
+            timestamp: Utc::now() - Duration::seconds(frame_id),
+            file_path: String::new(),
+            offset_index: 0,
+            app_name: String::new(),
+            ocr_engine: String::new(),
+            low_quality: false,
+            window_name: String::new(),
+            tags: Vec::new(),
+            browser_url: None,
+            browser_title: None,
+            app_id: None,
+            window_geometry: None,
+            focused: None,
+            visible_percentage: 1.0,
+            snippet: None,
+            score: None,
+        })
+    }
+
+    fn frame_ids(results: Vec<SearchResult>) -> Vec<i64> {
+        results
+            .into_iter()
+            .map(|result| match result {
+                SearchResult::OCR(ocr) => ocr.frame_id,
+                _ => panic!("expected OCR result"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fuzzy_tolerates_ocr_typos() {
+        let matcher = TextMatcher::fuzzy("modern deployment").unwrap();
+        assert_eq!(matcher.distance("Modern Deployment"), Some(0));
+        // "rn" misread for "m" and the other way around
+        assert_eq!(matcher.distance("rnodern dep1oyment"), Some(0));
+        assert_eq!(matcher.distance("modem deploymnet"), Some(2));
+        // words merged by OCR
+        assert_eq!(matcher.distance("themodern-deploymentpipeline"), Some(0));
+        assert_eq!(matcher.distance("modern development"), None);
+
+        // short words must match exactly
+        let matcher = TextMatcher::fuzzy("cat").unwrap();
+        assert_eq!(matcher.distance("the cat sat"), Some(0));
+        assert_eq!(matcher.distance("the car sat"), None);
+
+        assert!(TextMatcher::fuzzy("  ... ").is_err());
+    }
+
+    #[test]
+    fn test_regex_is_case_insensitive_and_bounded() {
+        let matcher = TextMatcher::regex(r"invoice #\d{4}").unwrap();
+        assert_eq!(matcher.distance("INVOICE #2024 paid"), Some(0));
+        assert_eq!(matcher.distance("invoice #20"), None);
+
+        assert!(TextMatcher::regex("unclosed (group").is_err());
+        assert!(TextMatcher::regex(r"\w{1000}{1000}").is_err());
+        assert!(TextMatcher::regex(&"a".repeat(MAX_PATTERN_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_filter_orders_by_time_or_distance() {
+        let matcher = TextMatcher::fuzzy("deployment").unwrap();
+        let results = || {
+            vec![
+                ocr(3, "deploymnet failed"),
+                ocr(1, "deployment failed"),
+                ocr(2, "lunch menu"),
+            ]
+        };
+        assert_eq!(frame_ids(matcher.filter(results(), false)), vec![1, 3]);
+        assert_eq!(frame_ids(matcher.filter(results(), true)), vec![1, 3]);
+
+        // the closest match first, newer ones first among equally close
+        let results = || {
+            vec![
+                ocr(1, "deploymnet failed"),
+                ocr(2, "deployment failed"),
+                ocr(3, "deployment done"),
+            ]
+        };
+        assert_eq!(frame_ids(matcher.filter(results(), false)), vec![1, 2, 3]);
+        assert_eq!(frame_ids(matcher.filter(results(), true)), vec![2, 3, 1]);
+    }
+}
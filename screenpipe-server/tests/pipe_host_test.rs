@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::pipe_host::{pipe_may_read, read_uri};
+    use serde_json::json;
+
+    #[test]
+    fn test_pipes_read_text_and_images_only_when_allowed() {
+        assert!(pipe_may_read("/search", false).is_ok());
+        assert!(pipe_may_read("/meetings/12/summary", false).is_ok());
+        assert!(pipe_may_read("/frames/12", false).is_err());
+        assert!(pipe_may_read("/frames/12", true).is_ok());
+        assert!(pipe_may_read("/clip", true).is_ok());
+        // whatever a token needs admin or write access for stays out of reach
+        assert!(pipe_may_read("/pipes/list", true).is_err());
+        assert!(pipe_may_read("/tags/vision/1", true).is_err());
+        assert!(pipe_may_read("/raw_sql", true).is_err());
+    }
+
+    #[test]
+    fn test_read_uri_encodes_params_and_refuses_frames() {
+        let params = json!({"q": "standup notes", "include_frames": true});
+        assert!(read_uri("/search", &params, false).is_err());
+        assert!(read_uri("/ws/events", &json!({"images": "true"}), false).is_err());
+        assert_eq!(
+            read_uri("/search", &json!({"include_frames": false}), false).unwrap(),
+            "/search?include_frames=false"
+        );
+        assert_eq!(
+            read_uri("/search", &json!({"q": "standup notes"}), false).unwrap(),
+            "/search?q=standup+notes"
+        );
+        assert!(read_uri("/search", &params, true)
+            .unwrap()
+            .contains("include_frames=true"));
+        assert_eq!(
+            read_uri("/search", &json!({"limit": 5, "app_name": null}), false).unwrap(),
+            "/search?limit=5"
+        );
+        assert_eq!(
+            read_uri("/meetings", &json!({}), false).unwrap(),
+            "/meetings"
+        );
+        assert!(read_uri("/meetings", &json!(["a"]), false).is_err());
+    }
+}
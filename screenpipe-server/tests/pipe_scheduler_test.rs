@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use chrono::Utc;
+    use screenpipe_core::PipePermissions;
+    use screenpipe_events::{AppFocusChange, CaptureEvent, OcrCapture, TranscriptCapture};
+    use screenpipe_server::pipe_scheduler::{backoff, PipeSchedule, PipeTrigger, RunStatus};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    fn ocr(text: &str) -> CaptureEvent {
+        CaptureEvent::Ocr(OcrCapture {
+            frame_id: 1,
+            timestamp: Utc::now(),
+            device_name: "monitor_1".to_string(),
+            app_name: "Slack".to_string(),
+            window_name: "general".to_string(),
+            browser_url: None,
+            focused: true,
+            text: text.to_string(),
+        })
+    }
+
+    fn transcript(text: &str) -> CaptureEvent {
+        CaptureEvent::Transcript(TranscriptCapture {
+            audio_chunk_id: 1,
+            timestamp: Utc::now(),
+            device_name: "MacBook Pro Microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: text.to_string(),
+        })
+    }
+
+    fn focus(app_name: &str) -> CaptureEvent {
+        CaptureEvent::AppFocus(AppFocusChange {
+            timestamp: Utc::now(),
+            device_name: "monitor_1".to_string(),
+            app_name: app_name.to_string(),
+            window_name: "main.rs".to_string(),
+            previous_app_name: Some("Slack".to_string()),
+            previous_window_name: None,
+        })
+    }
+
+    // what capture triggers need
+    fn capture_permissions() -> PipePermissions {
+        PipePermissions {
+            events: vec!["capture".to_string()],
+            endpoints: vec!["/search".to_string()],
+            ..PipePermissions::default()
+        }
+    }
+
+    fn schedule(max_concurrent_runs: usize) -> PipeSchedule {
+        schedule_with(max_concurrent_runs, &capture_permissions())
+    }
+
+    fn schedule_with(max_concurrent_runs: usize, permissions: &PipePermissions) -> PipeSchedule {
+        PipeSchedule::from_config(
+            "standup",
+            &json!({
+                "runtime": "sandbox",
+                "max_concurrent_runs": max_concurrent_runs,
+                "triggers": [
+                    {"name": "morning", "on": "cron", "schedule": "0 0 9 * * Mon-Fri"},
+                    {"name": "heard", "on": "transcript"},
+                    {"name": "blocker", "on": "keyword", "keywords": ["Blocked"]},
+                    {"name": "coding", "on": "app_focus", "apps": ["code", "zed"]},
+                ],
+            }),
+            permissions,
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pipe_triggers_are_read_from_pipe_json() {
+        let schedule = schedule(1);
+        assert_eq!(schedule.triggers().len(), 4);
+        assert_eq!(
+            schedule.triggers()[0].trigger,
+            PipeTrigger::Cron {
+                schedule: "0 0 9 * * Mon-Fri".to_string()
+            }
+        );
+        assert!(PipeSchedule::from_config(
+            "none",
+            &json!({"runtime": "sandbox"}),
+            &capture_permissions()
+        )
+        .unwrap()
+        .is_none());
+
+        for triggers in [
+            json!([{"name": "bad", "on": "cron", "schedule": "every morning"}]),
+            json!([{"name": "empty", "on": "keyword", "keywords": []}]),
+            json!([{"name": "", "on": "transcript"}]),
+            json!([{"name": "twice", "on": "transcript"}, {"name": "twice", "on": "transcript"}]),
+            json!([{"name": "unknown", "on": "reboot"}]),
+        ] {
+            assert!(
+                PipeSchedule::from_config(
+                    "bad",
+                    &json!({ "triggers": triggers }),
+                    &capture_permissions()
+                )
+                .is_err(),
+                "{}",
+                triggers
+            );
+        }
+        assert!(PipeSchedule::from_config(
+            "bad",
+            &json!({"max_concurrent_runs": 0, "triggers": [{"name": "a", "on": "transcript"}]}),
+            &capture_permissions()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_capture_triggers_need_the_permissions_to_read_captures() {
+        // without them only the cron trigger is left, which fires with nothing
+        let schedule = schedule_with(1, &PipePermissions::default());
+        assert_eq!(schedule.triggers().len(), 1);
+        assert_eq!(schedule.triggers()[0].name, "morning");
+        assert!(schedule
+            .matching(&transcript("I'm blocked on the review"))
+            .is_empty());
+
+        let events_only = PipePermissions {
+            events: vec!["capture".to_string()],
+            ..PipePermissions::default()
+        };
+        assert_eq!(schedule_with(1, &events_only).triggers().len(), 1);
+        assert_eq!(schedule_with(1, &capture_permissions()).triggers().len(), 4);
+    }
+
+    #[test]
+    fn test_capture_events_fire_matching_triggers() {
+        let schedule = schedule(1);
+        assert_eq!(
+            schedule.matching(&transcript("I'm blocked on the review")),
+            ["heard", "blocker"]
+        );
+        assert_eq!(schedule.matching(&ocr("still BLOCKED")), ["blocker"]);
+        assert!(schedule.matching(&ocr("all good")).is_empty());
+        assert_eq!(schedule.matching(&focus("Visual Studio Code")), ["coding"]);
+        assert!(schedule.matching(&focus("Slack")).is_empty());
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_an_hour() {
+        assert_eq!(backoff(0), Duration::ZERO);
+        assert_eq!(backoff(1), Duration::from_secs(30));
+        assert_eq!(backoff(3), Duration::from_secs(120));
+        assert_eq!(backoff(20), Duration::from_secs(3600));
+        assert_eq!(backoff(u32::MAX), Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_runs_are_limited_recorded_and_backed_off() {
+        let schedule = schedule(1);
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let running = schedule.run(
+            "heard",
+            async move { release_rx.await.map_err(|e| anyhow!(e)) },
+        );
+        let busy = async {
+            tokio::task::yield_now().await;
+            assert_eq!(schedule.status().running, 1);
+            let skipped = schedule.run("blocker", async { Ok(()) }).await;
+            release_tx.send(()).unwrap();
+            skipped
+        };
+        let (ran, skipped) = tokio::join!(running, busy);
+        assert_eq!(ran, Some(RunStatus::Succeeded));
+        assert_eq!(skipped, None);
+
+        let failed = schedule.run("blocker", async { Err(anyhow!("sheet is gone")) });
+        assert_eq!(failed.await, Some(RunStatus::Failed));
+        assert_eq!(schedule.run("blocker", async { Ok(()) }).await, None);
+        // the other triggers don't back off with it
+        assert_eq!(
+            schedule.run("heard", async { Ok(()) }).await,
+            Some(RunStatus::Succeeded)
+        );
+
+        let status = schedule.status();
+        assert_eq!(status.running, 0);
+        assert_eq!(status.skipped_busy, 1);
+        assert_eq!(status.skipped_backoff, 1);
+        assert_eq!(status.backoffs.len(), 1);
+        let blocker = &status.backoffs["blocker"];
+        assert_eq!(blocker.consecutive_failures, 1);
+        assert!(blocker
+            .backoff_until
+            .is_some_and(|until| until > Utc::now()));
+        assert_eq!(status.history.len(), 3);
+        assert_eq!(status.history[0].trigger, "heard");
+        assert_eq!(status.history[1].trigger, "blocker");
+        assert_eq!(status.history[1].error.as_deref(), Some("sheet is gone"));
+        assert_eq!(status.history[2].status, RunStatus::Succeeded);
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use clap::Parser;
+    use screenpipe_db::DatabaseManager;
+    use screenpipe_server::cli::Cli;
+    use screenpipe_server::power::{
+        PowerPolicy, PowerReading, PowerThrottle, ThrottleLevel, ThrottleReason,
+    };
+    use screenpipe_vision::capture_pause::capture_pause_reasons;
+    use screenpipe_vision::throttle::throttled_interval;
+    use std::sync::Arc;
+
+    fn policy() -> PowerPolicy {
+        PowerPolicy {
+            on_battery: ThrottleLevel::ReduceFps,
+            low_battery: ThrottleLevel::Pause,
+            low_battery_percent: 20.0,
+            on_heat: ThrottleLevel::CheapOcr,
+            max_cpu_temperature: 90.0,
+            max_cpu_usage: 90.0,
+            fps_divisor: 4.0,
+        }
+    }
+
+    fn reading(on_battery: bool, battery_percent: f32, cpu_temperature: f32) -> PowerReading {
+        PowerReading {
+            on_battery: Some(on_battery),
+            battery_percent: Some(battery_percent),
+            cpu_usage: Some(30.0),
+            cpu_temperature: Some(cpu_temperature),
+        }
+    }
+
+    #[test]
+    fn test_most_demanding_condition_wins() {
+        let policy = policy();
+        assert_eq!(
+            policy.level(&reading(false, 100.0, 60.0), false),
+            (ThrottleLevel::None, vec![])
+        );
+        assert_eq!(
+            policy.level(&reading(true, 80.0, 60.0), false),
+            (ThrottleLevel::ReduceFps, vec![ThrottleReason::OnBattery])
+        );
+        // plugged in with a low battery is fine
+        assert_eq!(
+            policy.level(&reading(false, 10.0, 60.0), false).0,
+            ThrottleLevel::None
+        );
+        let (level, reasons) = policy.level(&reading(true, 15.0, 95.0), false);
+        assert_eq!(level, ThrottleLevel::Pause);
+        assert_eq!(
+            reasons,
+            [
+                ThrottleReason::OnBattery,
+                ThrottleReason::LowBattery { percent: 15.0 },
+                ThrottleReason::CpuTemperature { celsius: 95.0 },
+            ]
+        );
+        assert_eq!(reasons[1].to_string(), "battery at 15%");
+    }
+
+    #[test]
+    fn test_heat_throttling_ends_below_the_threshold() {
+        let policy = policy();
+        assert_eq!(
+            policy.level(&reading(false, 100.0, 87.0), false).0,
+            ThrottleLevel::None
+        );
+        // already throttled, it has to cool down past the margin first
+        assert_eq!(
+            policy.level(&reading(false, 100.0, 87.0), true).0,
+            ThrottleLevel::CheapOcr
+        );
+        assert_eq!(
+            policy.level(&reading(false, 100.0, 80.0), true).0,
+            ThrottleLevel::None
+        );
+
+        let busy = PowerReading {
+            cpu_usage: Some(97.0),
+            ..PowerReading::default()
+        };
+        assert_eq!(policy.level(&busy, false).0, ThrottleLevel::CheapOcr);
+        // not known
+        assert_eq!(
+            policy.level(&PowerReading::default(), true).0,
+            ThrottleLevel::None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_throttle_slows_down_and_pauses_capture() {
+        let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        let throttle = PowerThrottle::new(db.clone(), policy(), None);
+        let interval = std::time::Duration::from_secs(1);
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+
+        let state = throttle.update(reading(true, 80.0, 60.0), at(0)).await;
+        assert_eq!(state.level, ThrottleLevel::ReduceFps);
+        assert_eq!(state.since, Some(at(0)));
+        assert_eq!(throttled_interval(interval), interval * 4);
+        // same level, still throttled since the first reading
+        let state = throttle.update(reading(true, 70.0, 60.0), at(30)).await;
+        assert_eq!(state.since, Some(at(0)));
+
+        let state = throttle.update(reading(true, 10.0, 60.0), at(60)).await;
+        assert_eq!(state.level, ThrottleLevel::Pause);
+        assert_eq!(capture_pause_reasons(), ["power"]);
+
+        let state = throttle.update(reading(false, 12.0, 60.0), at(90)).await;
+        assert_eq!((state.level, state.since), (ThrottleLevel::None, None));
+        assert!(capture_pause_reasons().is_empty());
+        assert_eq!(throttled_interval(interval), interval);
+        assert_eq!(throttle.state(), state);
+
+        let pauses = db.list_capture_pauses(at(0), at(120), 10).await.unwrap();
+        assert_eq!(pauses.len(), 1);
+        assert_eq!(pauses[0].reason, "power");
+        assert_eq!(
+            (pauses[0].start_time, pauses[0].end_time),
+            (at(60), Some(at(90)))
+        );
+    }
+
+    #[test]
+    fn test_fps_divisor_is_bounded() {
+        let divisor = |value: &str| {
+            Cli::try_parse_from(["screenpipe", "--throttle-fps-divisor", value])
+                .map(|cli| cli.throttle_fps_divisor)
+        };
+        assert_eq!(divisor("2.5").unwrap(), 2.5);
+        // Duration::mul_f64 panics on a factor this large
+        assert!(divisor("1e300").is_err());
+        assert!(divisor("0.5").is_err());
+        assert!(divisor("NaN").is_err());
+    }
+}
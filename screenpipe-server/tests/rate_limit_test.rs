@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header::RETRY_AFTER, Request, StatusCode};
+    use axum::{middleware, routing::get, Router};
+    use screenpipe_server::auth::{hash_token, VerifiedToken};
+    use screenpipe_server::rate_limit::{client_key, limit_requests, RateLimiter};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_rate_limiter_refills_over_time() {
+        let limiter = RateLimiter::new(60);
+        let now = Instant::now();
+        for _ in 0..60 {
+            assert!(limiter.check("ip:127.0.0.1", now).is_ok());
+        }
+        let retry_after = limiter.check("ip:127.0.0.1", now).unwrap_err();
+        assert!(retry_after <= Duration::from_secs(1));
+
+        // other clients have their own budget
+        assert!(limiter.check("ip:10.0.0.2", now).is_ok());
+
+        // 60 a minute is one a second
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.check("ip:127.0.0.1", later).is_ok());
+        assert!(limiter.check("ip:127.0.0.1", later).is_err());
+    }
+
+    #[test]
+    fn test_client_key_prefers_a_verified_token() {
+        let addr: SocketAddr = "192.168.1.5:50000".parse().unwrap();
+        let mut request = Request::get("/search").body(Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(addr));
+        assert_eq!(client_key(&request), "ip:192.168.1.5");
+
+        let mut request = Request::get("/search")
+            .header("Authorization", "Bearer sp_abc")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(addr));
+        // any token can be made up, it's only told apart once auth checked it
+        assert_eq!(client_key(&request), "ip:192.168.1.5");
+
+        request
+            .extensions_mut()
+            .insert(VerifiedToken(hash_token("sp_abc")));
+        let key = client_key(&request);
+        assert!(key.starts_with("token:"));
+        assert!(!key.contains("sp_abc"));
+    }
+
+    #[tokio::test]
+    async fn test_limit_requests_answers_too_many_requests() {
+        let limiter = Arc::new(RateLimiter::new(2));
+        let app = Router::new()
+            .route("/search", get(|| async { "ok" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(limiter, limit_requests));
+        let request = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(request("/search")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(request("/search")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "30");
+
+        // health checks aren't counted
+        let response = app.clone().oneshot(request("/health")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
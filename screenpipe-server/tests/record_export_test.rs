@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType, ExportTranscription, OcrEngine};
+    use screenpipe_server::record_export::{
+        export_records, parse_fields, Compression, ExportRecord, RecordFormat, RecordWriter, FIELDS,
+    };
+    use std::io::Read;
+    use std::sync::Arc;
+
+    fn record() -> ExportRecord {
+        ExportRecord::from_transcription(ExportTranscription {
+            id: 7,
+            timestamp: Utc.with_ymd_and_hms(2025, 5, 22, 9, 0, 0).unwrap(),
+            device: "microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: "hi, \"team\"".to_string(),
+            transcription_engine: "whisper".to_string(),
+            start_time: Some(1.5),
+            end_time: None,
+            file_path: "audio.mp4".to_string(),
+        })
+    }
+
+    fn write(format: RecordFormat, fields: &str, compression: Option<Compression>) -> Vec<u8> {
+        let mut writer = RecordWriter::new(
+            Vec::new(),
+            format,
+            parse_fields(Some(fields)).unwrap(),
+            compression,
+        )
+        .unwrap();
+        writer.write(&record()).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_fields(None).unwrap(), FIELDS);
+        assert_eq!(
+            parse_fields(Some(" text, timestamp,text ")).unwrap(),
+            vec!["text", "timestamp"]
+        );
+        assert!(parse_fields(Some("text,color")).is_err());
+        assert!(parse_fields(Some(",")).is_err());
+    }
+
+    #[test]
+    fn test_writes_selected_fields_as_jsonl_and_csv() {
+        let jsonl = write(RecordFormat::Jsonl, "type,text,start_time,app_name", None);
+        assert_eq!(
+            String::from_utf8(jsonl).unwrap(),
+            concat!(
+                r#"{"type":"audio","text":"hi, \"team\"","start_time":1.5,"app_name":null}"#,
+                "\n"
+            )
+        );
+
+        let csv = write(RecordFormat::Csv, "id,text,app_name,is_input_device", None);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "id,text,app_name,is_input_device\r\n7,\"hi, \"\"team\"\"\",,true\r\n"
+        );
+    }
+
+    #[test]
+    fn test_compresses_records() {
+        let plain = write(RecordFormat::Jsonl, "id,text", None);
+
+        let mut gzip = String::new();
+        flate2::read::GzDecoder::new(
+            &write(RecordFormat::Jsonl, "id,text", Some(Compression::Gzip))[..],
+        )
+        .read_to_string(&mut gzip)
+        .unwrap();
+        assert_eq!(gzip.as_bytes(), plain);
+
+        let zstd =
+            zstd::decode_all(&write(RecordFormat::Jsonl, "id,text", Some(Compression::Zstd))[..])
+                .unwrap();
+        assert_eq!(zstd, plain);
+    }
+
+    #[tokio::test]
+    async fn test_exports_ocr_text_then_transcriptions() {
+        let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+        db.insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for text in [Some("invoice 42"), None] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor_1",
+                    None,
+                    None,
+                    None,
+                    Some("Mail"),
+                    None,
+                    Some("inbox"),
+                    None,
+                    true,
+                    Some(1.0),
+                    None,
+                )
+                .await
+                .unwrap();
+            if let Some(text) = text {
+                db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract), false)
+                    .await
+                    .unwrap();
+            }
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+        db.insert_audio_transcription(audio_chunk_id, "hello", 0, "", &device, None, None, None)
+            .await
+            .unwrap();
+
+        let mut writer = RecordWriter::new(
+            Vec::new(),
+            RecordFormat::Csv,
+            parse_fields(Some("type,app_name,text")).unwrap(),
+            None,
+        )
+        .unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        let end = Utc::now() + Duration::hours(1);
+        // frames not read yet have no record
+        assert_eq!(
+            export_records(&db, start, end, &mut writer).await.unwrap(),
+            2
+        );
+        assert_eq!(
+            String::from_utf8(writer.finish().unwrap()).unwrap(),
+            "type,app_name,text\r\nocr,Mail,invoice 42\r\naudio,,hello\r\n"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use screenpipe_server::retention::{disk_usage, RetentionPeriod, RetentionPolicy};
+
+    #[test]
+    fn test_parse_retention_period() {
+        assert_eq!("30".parse(), Ok(RetentionPeriod::Days(30)));
+        assert_eq!("7d".parse(), Ok(RetentionPeriod::Days(7)));
+        assert_eq!("Forever".parse(), Ok(RetentionPeriod::Forever));
+        assert!("0".parse::<RetentionPeriod>().is_err());
+        assert!("a week".parse::<RetentionPeriod>().is_err());
+        assert_eq!(RetentionPeriod::Days(7).to_string(), "7d");
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let policy = RetentionPolicy::default();
+        assert_eq!(policy.screenshots, RetentionPeriod::Days(30));
+        assert_eq!(policy.ocr_text, RetentionPeriod::Days(365));
+        assert_eq!(policy.audio, RetentionPeriod::Days(7));
+        assert_eq!(policy.transcripts, RetentionPeriod::Forever);
+        assert_eq!(policy.clipboard, RetentionPeriod::Days(30));
+
+        let now = Utc::now();
+        assert_eq!(
+            policy.audio.cutoff(now),
+            Some(now - chrono::Duration::days(7))
+        );
+        assert_eq!(policy.transcripts.cutoff(now), None);
+    }
+
+    #[tokio::test]
+    async fn test_disk_usage_of_image_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("000000.webp"), [0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("000001.webp"), [0u8; 50]).unwrap();
+
+        assert_eq!(disk_usage(dir.path()).await, 150);
+        assert_eq!(disk_usage(&dir.path().join("000001.webp")).await, 50);
+        assert_eq!(disk_usage(&dir.path().join("missing.mp4")).await, 0);
+    }
+}
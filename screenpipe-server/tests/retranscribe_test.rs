@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use screenpipe_audio::core::engine::AudioTranscriptionEngine;
+    use screenpipe_server::retranscribe::{
+        load_jobs, parse_model, RetranscribeRequest, RetranscriptionStatus, DEFAULT_MODEL,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_models_are_named_with_or_without_whisper() {
+        assert_eq!(
+            parse_model("large-v3"),
+            Some(AudioTranscriptionEngine::WhisperLargeV3)
+        );
+        assert_eq!(
+            parse_model("whisper-large"),
+            Some(AudioTranscriptionEngine::WhisperLargeV3)
+        );
+        assert_eq!(
+            parse_model("Large-V3-Turbo"),
+            Some(AudioTranscriptionEngine::WhisperLargeV3Turbo)
+        );
+        assert_eq!(
+            parse_model("medium-quantized"),
+            Some(AudioTranscriptionEngine::WhisperMediumQuantized)
+        );
+        assert_eq!(
+            parse_model("deepgram"),
+            Some(AudioTranscriptionEngine::Deepgram)
+        );
+        assert_eq!(parse_model("large-v4"), None);
+        assert!(parse_model(DEFAULT_MODEL).is_some());
+    }
+
+    #[test]
+    fn test_requests_are_validated() {
+        let now = Utc::now();
+        let request = |start, model: &str| RetranscribeRequest {
+            start,
+            end: now,
+            model: model.to_string(),
+        };
+        assert_eq!(
+            request(now - Duration::hours(1), "large-v3").validate(),
+            Ok(AudioTranscriptionEngine::WhisperLargeV3)
+        );
+        assert!(request(now, "large-v3").validate().is_err());
+        assert!(request(now - Duration::hours(1), "nope")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_jobs_running_when_the_server_stopped_are_failed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("retranscriptions.json");
+        assert!(load_jobs(&path).is_empty());
+
+        let job = |id: &str, status: &str| {
+            json!({
+                "id": id,
+                "status": status,
+                "start": "2024-05-01T09:00:00Z",
+                "end": "2024-05-01T10:00:00Z",
+                "engine": "WhisperLargeV3",
+                "created_at": "2024-05-01T10:05:00Z",
+                "finished_at": null,
+                "transcriptions_done": 3,
+                "transcriptions_total": 10,
+                "transcriptions_replaced": 2,
+                "transcriptions_failed": 0,
+                "progress": 0.3,
+                "error": null
+            })
+        };
+        std::fs::write(
+            &path,
+            json!([job("running", "running"), job("done", "done")]).to_string(),
+        )
+        .unwrap();
+        let jobs = load_jobs(&path);
+        assert_eq!(jobs["running"].status, RetranscriptionStatus::Failed);
+        assert!(jobs["running"].error.is_some());
+        assert_eq!(jobs["running"].transcriptions_replaced, 2);
+        assert_eq!(jobs["done"].status, RetranscriptionStatus::Done);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load_jobs(&path).is_empty());
+    }
+}
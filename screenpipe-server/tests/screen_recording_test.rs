@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use screenpipe_server::cli::Cli;
+    use screenpipe_server::screen_recording::{ScreenRecordingConfig, VideoCodec, VideoEncoder};
+    use std::time::Duration;
+
+    fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn test_encoder_names() {
+        assert_eq!(
+            VideoEncoder::Software.ffmpeg_name(VideoCodec::H264),
+            "libx264"
+        );
+        assert_eq!(VideoEncoder::Auto.ffmpeg_name(VideoCodec::Hevc), "libx265");
+        assert_eq!(
+            VideoEncoder::Videotoolbox.ffmpeg_name(VideoCodec::Hevc),
+            "hevc_videotoolbox"
+        );
+        assert_eq!(
+            VideoEncoder::Nvenc.ffmpeg_name(VideoCodec::H264),
+            "h264_nvenc"
+        );
+    }
+
+    #[test]
+    fn test_hardware_candidates_never_include_software() {
+        let candidates = VideoEncoder::hardware_candidates();
+        assert!(!candidates.is_empty());
+        assert!(!candidates.contains(&VideoEncoder::Auto));
+        assert!(!candidates.contains(&VideoEncoder::Software));
+    }
+
+    #[test]
+    fn test_output_args() {
+        let args = VideoEncoder::Software.output_args(VideoCodec::H264, 5.0);
+        assert_eq!(arg_after(&args, "-c:v"), Some("libx264"));
+        assert_eq!(arg_after(&args, "-crf"), Some("28"));
+        assert_eq!(arg_after(&args, "-g"), Some("10"));
+        assert_eq!(arg_after(&args, "-tag:v"), None);
+
+        let args = VideoEncoder::Videotoolbox.output_args(VideoCodec::Hevc, 0.5);
+        assert_eq!(arg_after(&args, "-b:v"), Some("2M"));
+        assert_eq!(arg_after(&args, "-g"), Some("1"));
+        assert_eq!(arg_after(&args, "-tag:v"), Some("hvc1"));
+
+        let args = VideoEncoder::Vaapi.output_args(VideoCodec::H264, 5.0);
+        assert!(arg_after(&args, "-vf").unwrap().ends_with("hwupload"));
+    }
+
+    #[test]
+    fn test_recording_config_validation() {
+        let segment = Duration::from_secs(300);
+        assert!(
+            ScreenRecordingConfig::new(5.0, segment, VideoCodec::H264, VideoEncoder::Auto).is_ok()
+        );
+        assert!(
+            ScreenRecordingConfig::new(0.0, segment, VideoCodec::H264, VideoEncoder::Auto).is_err()
+        );
+        assert!(
+            ScreenRecordingConfig::new(60.0, segment, VideoCodec::H264, VideoEncoder::Auto)
+                .is_err()
+        );
+        assert!(ScreenRecordingConfig::new(
+            5.0,
+            Duration::ZERO,
+            VideoCodec::H264,
+            VideoEncoder::Auto
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_recordings_are_refused_with_blurring() {
+        let config = |args: &[&str]| {
+            Cli::try_parse_from(["screenpipe", "--record-video"].iter().chain(args))
+                .unwrap()
+                .screen_recording_config()
+        };
+        assert!(config(&[]).unwrap().is_some());
+        // recordings would show what screenshots blur
+        assert!(config(&["--blur-faces"]).is_err());
+        assert!(config(&["--use-pii-removal", "--blur-redacted"]).is_err());
+    }
+}
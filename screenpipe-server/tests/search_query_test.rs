@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use screenpipe_db::TranscriptWord;
+    use screenpipe_server::search_query::SearchQueryFilters;
+
+    #[test]
+    fn test_parses_inline_filters() {
+        let filters = SearchQueryFilters::parse(r#"app:chrome title:"pull request" review"#);
+        assert_eq!(
+            filters,
+            SearchQueryFilters {
+                text: "review".to_string(),
+                app_name: Some("chrome".to_string()),
+                window_name: Some("pull request".to_string()),
+                ..Default::default()
+            }
+        );
+
+        let filters =
+            SearchQueryFilters::parse("bundle:com.google.Chrome focused:true url:github.com");
+        assert_eq!(filters.text, "");
+        assert_eq!(filters.app_id.as_deref(), Some("com.google.Chrome"));
+        assert_eq!(filters.focused, Some(true));
+        assert_eq!(filters.browser_url.as_deref(), Some("github.com"));
+
+        let filters = SearchQueryFilters::parse("tag:meeting roadmap");
+        assert_eq!(filters.text, "roadmap");
+        assert_eq!(filters.tag.as_deref(), Some("meeting"));
+    }
+
+    #[test]
+    fn test_keeps_text_that_is_not_a_filter() {
+        let filters =
+            SearchQueryFilters::parse(r#""meeting notes" at 10:30 focused:maybe title: todo"#);
+        assert_eq!(
+            filters.text,
+            r#""meeting notes" at 10:30 focused:maybe title: todo"#
+        );
+        assert_eq!(filters.focused, None);
+        assert_eq!(filters.window_name, None);
+    }
+
+    #[test]
+    fn test_parses_exclusions_and_dates() {
+        let filters = SearchQueryFilters::parse(
+            r#"app:slack "deploy failed" -channel:random -app:zoom -title:"Daily standup" after:2024-05-01 before:2024-05-08T12:00:00Z"#,
+        );
+        assert_eq!(filters.app_name.as_deref(), Some("slack"));
+        assert_eq!(filters.excluded_app_names, vec!["zoom"]);
+        assert_eq!(filters.excluded_window_names, vec!["Daily standup"]);
+        assert_eq!(
+            filters.after,
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            filters.before,
+            Some(Utc.with_ymd_and_hms(2024, 5, 8, 12, 0, 0).unwrap())
+        );
+        assert_eq!(filters.text, r#""deploy failed" -channel:random"#);
+        assert_eq!(
+            filters.fts_query(),
+            r#"("deploy failed") NOT "channel:random""#
+        );
+
+        // not a date
+        let filters = SearchQueryFilters::parse("after:lunch");
+        assert_eq!(filters.after, None);
+        assert_eq!(filters.text, "after:lunch");
+    }
+
+    #[test]
+    fn test_builds_fts_query() {
+        let fts_query = |query: &str| SearchQueryFilters::parse(query).fts_query();
+        assert_eq!(fts_query("deploy OR release"), r#""deploy" OR "release""#);
+        assert_eq!(
+            fts_query("deplo* NOT staging"),
+            r#"("deplo"*) NOT "staging""#
+        );
+        assert_eq!(fts_query(r#"say "hi" OR"#), r#""say" "hi""#);
+        // punctuation is quoted instead of breaking the query
+        assert_eq!(fts_query("node.js (v18)"), r#""node.js" "(v18)""#);
+        // nothing to match
+        assert_eq!(fts_query("-staging ..."), "");
+        assert_eq!(fts_query(""), "");
+    }
+
+    #[test]
+    fn test_finds_when_a_term_is_said() {
+        let words: Vec<TranscriptWord> = [("Let's", 1.0), ("ship", 1.4), ("the", 1.7)]
+            .into_iter()
+            .chain([("Deployment,", 2.0), ("today.", 2.6)])
+            .map(|(word, start)| TranscriptWord {
+                word: word.to_string(),
+                start,
+                end: start + 0.3,
+            })
+            .collect();
+        let spoken_at = |query: &str| SearchQueryFilters::parse(query).spoken_at(&words);
+
+        assert_eq!(spoken_at("deploy*"), Some(2.0));
+        assert_eq!(spoken_at("today OR ship"), Some(1.4));
+        assert_eq!(spoken_at(r#""lets ship""#), Some(1.0));
+        assert_eq!(spoken_at("-ship today"), Some(2.6));
+        assert_eq!(spoken_at("NOT ship"), None);
+        assert_eq!(spoken_at("app:zoom"), None);
+        assert_eq!(SearchQueryFilters::parse("ship").spoken_at(&[]), None);
+    }
+}
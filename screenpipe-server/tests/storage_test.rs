@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::storage::{dir_size, format_bytes, StorageQuota};
+
+    #[test]
+    fn test_parse_storage_quota() {
+        assert_eq!("50GB".parse(), Ok(StorageQuota(50 << 30)));
+        assert_eq!("500 mb".parse(), Ok(StorageQuota(500 << 20)));
+        assert_eq!("1.5TB".parse(), Ok(StorageQuota(3 << 39)));
+        // plain numbers are gigabytes
+        assert_eq!("20".parse(), Ok(StorageQuota(20 << 30)));
+        assert!("0GB".parse::<StorageQuota>().is_err());
+        assert!("lots".parse::<StorageQuota>().is_err());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 << 29), "1.5 GB");
+        assert_eq!(StorageQuota(50 << 30).to_string(), "50.0 GB");
+    }
+
+    #[test]
+    fn test_dir_size_counts_hard_links_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk = dir.path().join("monitor_1");
+        std::fs::create_dir(&chunk).unwrap();
+        std::fs::write(dir.path().join("blob.webp"), [0u8; 100]).unwrap();
+        std::fs::write(chunk.join("000000.webp"), [0u8; 40]).unwrap();
+        std::fs::hard_link(dir.path().join("blob.webp"), chunk.join("000001.webp")).unwrap();
+
+        let expected = if cfg!(unix) { 140 } else { 240 };
+        assert_eq!(dir_size(dir.path()), expected);
+    }
+}
@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_db::{AudioResult, DeviceType, OCRResult, SearchResult};
+    use screenpipe_server::summarize::{
+        build_prompt, cited_references, collect_sources, SourceKind, SummarizeRequest,
+    };
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn ocr(frame_id: i64, minutes: i64, text: &str) -> SearchResult {
+        SearchResult::OCR(OCRResult {
+            frame_id,
+            frame_name: String::new(),
+            ocr_text: text.to_string(),
+            text_json: String::new(),
+            timestamp: at(minutes),
+            file_path: String::new(),
+            offset_index: 0,
+            app_name: "Slack".to_string(),
+            ocr_engine: String::new(),
+            low_quality: false,
+            window_name: "#general".to_string(),
+            tags: Vec::new(),
+            browser_url: None,
+            browser_title: None,
+            app_id: None,
+            window_geometry: None,
+            focused: None,
+            visible_percentage: 1.0,
+            snippet: None,
+            score: None,
+        })
+    }
+
+    fn audio(audio_chunk_id: i64, minutes: i64, text: &str) -> SearchResult {
+        SearchResult::Audio(AudioResult {
+            audio_chunk_id,
+            transcription: text.to_string(),
+            timestamp: at(minutes),
+            file_path: String::new(),
+            offset_index: 0,
+            transcription_engine: String::new(),
+            tags: Vec::new(),
+            device_name: "MacBook Pro Microphone".to_string(),
+            device_type: DeviceType::Input,
+            speaker: None,
+            start_time: None,
+            end_time: None,
+            snippet: None,
+            score: None,
+            words: Vec::new(),
+            translation: None,
+        })
+    }
+
+    #[test]
+    fn test_collects_distinct_sources_oldest_first() {
+        let sources = collect_sources(vec![
+            ocr(3, 10, "deploy  failed\non staging"),
+            audio(1, 5, "let's roll back the deploy"),
+            // the same screen captured again
+            ocr(2, 1, "deploy failed on staging"),
+            ocr(4, 12, "   "),
+        ]);
+
+        let sources: Vec<(usize, SourceKind, i64, &str)> = sources
+            .iter()
+            .map(|source| {
+                (
+                    source.reference,
+                    source.kind,
+                    source.id,
+                    source.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (1, SourceKind::Audio, 1, "let's roll back the deploy"),
+                (2, SourceKind::Ocr, 3, "deploy failed on staging"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prompt_numbers_sources() {
+        let sources = collect_sources(vec![
+            ocr(1, 0, "deploy failed"),
+            audio(1, 5, "roll it back"),
+        ]);
+        let prompt = build_prompt(&sources, Some("List the decisions"));
+        assert_eq!(
+            prompt,
+            "Sources:\n\
+             [1] 2024-06-01 09:00 (screen, Slack - #general): deploy failed\n\
+             [2] 2024-06-01 09:05 (audio, MacBook Pro Microphone): roll it back\n\
+             \n\
+             List the decisions"
+        );
+    }
+
+    #[test]
+    fn test_parses_citations() {
+        let cited = cited_references("The deploy failed [1] and was rolled back [2, 4][5]. [x]");
+        assert_eq!(cited.into_iter().collect::<Vec<_>>(), vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_request_validation() {
+        let request = |q: Option<&str>, start: Option<i64>, end: Option<i64>| SummarizeRequest {
+            q: q.map(str::to_string),
+            start_time: start.map(at),
+            end_time: end.map(at),
+            content_type: Default::default(),
+            limit: 200,
+            instructions: None,
+        };
+        assert!(request(Some("deploy"), None, None).validate().is_ok());
+        assert!(request(None, Some(0), Some(60)).validate().is_ok());
+        assert!(request(Some("  "), None, None).validate().is_err());
+        assert!(request(None, Some(60), Some(0)).validate().is_err());
+
+        let mut too_many = request(Some("deploy"), None, None);
+        too_many.limit = 5000;
+        assert!(too_many.validate().is_err());
+    }
+}
@@ -148,7 +148,7 @@ async fn test_add_tags_and_search() {
                 assert!(audio.tags.contains(&"test".to_string()));
                 assert!(audio.tags.contains(&"audio".to_string()));
             }
-            ContentItem::UI(_) => {
+            ContentItem::UI(_) | ContentItem::Clipboard(_) => {
                 unreachable!()
             }
         }
@@ -348,13 +348,97 @@ async fn test_search_by_multiple_tags() {
                 assert!(audio.tags.contains(&"work".to_string()));
                 assert!(audio.tags.contains(&"call".to_string()));
             }
-            ContentItem::UI(_) => {
-                panic!("UI content should not be included in the results");
+            ContentItem::UI(_) | ContentItem::Clipboard(_) => {
+                panic!("UI content and copied text should not be included in the results");
             }
         }
     }
 }
 
+#[tokio::test]
+async fn test_annotate_and_search_by_tag() {
+    let (app, db) = setup_test_app().await;
+    insert_test_data(&db).await;
+
+    let post = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/tags")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap()
+    };
+    let get = |uri: &str| {
+        Request::builder()
+            .method("GET")
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post(json!({
+            "content_type": "vision",
+            "id": 1,
+            "tags": ["meeting"],
+            "note": "standup"
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(post(json!({
+            "content_type": "range",
+            "start_time": "2024-01-01T09:00:00Z",
+            "end_time": "2024-01-01T10:00:00Z",
+            "tags": ["meeting"]
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // neither a tag nor a note
+    let response = app
+        .clone()
+        .oneshot(post(json!({"content_type": "vision", "id": 1})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            json!({"content_type": "vision", "id": 99, "tags": ["meeting"]}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .clone()
+        .oneshot(get("/search?tag=meeting&content_type=audio+ocr"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let search_results: PaginatedResponse<ContentItem> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(search_results.data.len(), 1);
+    assert!(matches!(&search_results.data[0], ContentItem::OCR(ocr) if ocr.frame_id == 1));
+
+    let response = app
+        .clone()
+        .oneshot(get("/annotations?tag=meeting"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let annotations: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(annotations.len(), 2);
+}
+
 async fn insert_test_data(db: &Arc<DatabaseManager>) {
     // Insert test video chunk
     let _video_chunk_id = db
@@ -368,9 +452,13 @@ async fn insert_test_data(db: &Arc<DatabaseManager>) {
             "test_device",
             None,
             None,
+            None,
             Some("test_app"),
+            None,
             Some("test_window"),
+            None,
             true,
+            None,
             None
         )
         .await
@@ -382,6 +470,7 @@ async fn insert_test_data(db: &Arc<DatabaseManager>) {
         "Test OCR text",
         "{'text': 'Test OCR text', 'confidence': 0.9}",
         Arc::new(OcrEngine::Tesseract.into()),
+        false,
     )
     .await
     .unwrap();
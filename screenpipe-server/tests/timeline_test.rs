@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use screenpipe_db::{CapturePause, InputActivity, TimelineFrame, TimelineTranscription};
+    use screenpipe_server::timeline::{
+        activity_events, merge_timeline, pause_events, TimelineCursor, TimelineItem, TimelineQuery,
+    };
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 15, 9, 0, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn frame(frame_id: i64, secs: i64, app_name: &str, focused: Option<bool>) -> TimelineFrame {
+        TimelineFrame {
+            frame_id,
+            timestamp: at(secs),
+            device_name: "monitor_1".to_string(),
+            app_name: app_name.to_string(),
+            window_name: "window".to_string(),
+            browser_url: None,
+            focused,
+            snippet: Some(format!("text of frame {}", frame_id)),
+        }
+    }
+
+    fn transcription(id: i64, secs: i64) -> TimelineTranscription {
+        TimelineTranscription {
+            id,
+            timestamp: at(secs),
+            device: "microphone".to_string(),
+            is_input_device: true,
+            speaker_id: None,
+            transcription: format!("transcription {}", id),
+            start_time: None,
+            end_time: None,
+        }
+    }
+
+    fn describe(items: &[TimelineItem]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| match item {
+                TimelineItem::Activity(change) => match change.level {
+                    Some(level) => format!("{:?}", level).to_lowercase(),
+                    None => "unrecorded".to_string(),
+                },
+                TimelineItem::Annotation(annotation) => format!("annotation {}", annotation.id),
+                TimelineItem::AppFocus(focus) => format!("focus {}", focus.app_name),
+                TimelineItem::AudioDevice(event) => format!("{} {}", event.event, event.device),
+                TimelineItem::Frame(frame) => format!("frame {}", frame.frame_id),
+                TimelineItem::Ocr(snippet) => format!("ocr {}", snippet.frame_id),
+                TimelineItem::Paused(pause) => format!("paused {}", pause.reason),
+                TimelineItem::Resumed(pause) => format!("resumed {}", pause.reason),
+                TimelineItem::Transcript(transcription) => {
+                    format!("transcript {}", transcription.id)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merges_frames_and_transcripts_by_time() {
+        let mut unread = frame(4, 30, "Slack", Some(true));
+        unread.snippet = None;
+        let items = merge_timeline(
+            vec![
+                frame(1, 0, "Slack", Some(true)),
+                frame(2, 10, "Slack", None),
+                // another monitor in the background
+                frame(3, 20, "Zoom", Some(false)),
+                unread,
+                frame(5, 40, "Code", Some(true)),
+            ],
+            vec![transcription(1, 5), transcription(2, 40)],
+        );
+        assert_eq!(
+            describe(&items),
+            vec![
+                "focus Slack",
+                "frame 1",
+                "ocr 1",
+                "transcript 1",
+                "frame 2",
+                "ocr 2",
+                "frame 3",
+                "ocr 3",
+                "frame 4",
+                "focus Code",
+                "frame 5",
+                "ocr 5",
+                "transcript 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_items_are_tagged_by_type() {
+        let items = merge_timeline(Vec::new(), vec![transcription(1, 0)]);
+        let json = serde_json::to_value(&items[0]).unwrap();
+        assert_eq!(json["type"], "transcript");
+        assert_eq!(json["content"]["transcription"], "transcription 1");
+    }
+
+    #[test]
+    fn test_pauses_show_where_they_start_and_end() {
+        let pause = |id: i64, reason: &str, start: i64, end: Option<i64>| CapturePause {
+            id,
+            reason: reason.to_string(),
+            start_time: at(start),
+            end_time: end.map(at),
+        };
+        let items = pause_events(
+            vec![
+                // started before the range, only its end is in it
+                pause(1, "locked", -30, Some(10)),
+                pause(2, "idle", 20, Some(40)),
+                // still paused
+                pause(3, "idle", 50, None),
+                // resumed after the range
+                pause(4, "locked", 55, Some(90)),
+            ],
+            at(0),
+            at(60),
+        );
+        assert_eq!(
+            describe(&items),
+            vec![
+                "resumed locked",
+                "paused idle",
+                "resumed idle",
+                "paused idle",
+                "paused locked",
+            ]
+        );
+        assert_eq!(items[0].timestamp(), at(10));
+    }
+
+    #[test]
+    fn test_activity_shows_where_its_level_changes() {
+        let activity = |minute: i64, key_presses: i64, scrolls: i64| InputActivity {
+            minute: at(minute * 60),
+            key_presses,
+            mouse_clicks: 0,
+            mouse_moves: 0,
+            scrolls,
+        };
+        let items = activity_events(
+            vec![
+                activity(0, 40, 0),
+                activity(1, 30, 0),
+                activity(2, 0, 8),
+                activity(3, 0, 0),
+                // input wasn't recorded in minutes 4 and 5
+                activity(6, 0, 0),
+            ],
+            at(10 * 60),
+        );
+        assert_eq!(
+            describe(&items),
+            vec![
+                "active",
+                "passive",
+                "idle",
+                "unrecorded",
+                "idle",
+                "unrecorded"
+            ]
+        );
+        let timestamps: Vec<DateTime<Utc>> = items.iter().map(TimelineItem::timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![at(0), at(120), at(180), at(240), at(360), at(420)]
+        );
+        // the minute being counted isn't missing
+        assert_eq!(activity_events(vec![activity(0, 40, 0)], at(60)).len(), 1);
+    }
+
+    #[test]
+    fn test_query_validation() {
+        let query = |start: i64, end: i64, limit: u32| TimelineQuery {
+            start: at(start),
+            end: at(end),
+            limit,
+            cursor: None,
+        };
+        assert!(query(0, 60, 1000).validate().is_ok());
+        assert!(query(60, 60, 1000).validate().is_err());
+        assert!(query(0, 60, 0).validate().is_err());
+        assert!(query(0, 60, 20_000).validate().is_err());
+
+        let cursor = TimelineCursor {
+            start: at(30),
+            frame_id: 7,
+            transcription_id: 0,
+        };
+        let continued = TimelineQuery {
+            cursor: Some(cursor.encode()),
+            ..query(0, 60, 1000)
+        };
+        assert!(continued.validate().is_ok());
+        assert_eq!(continued.page_start(), Ok(cursor));
+        let invalid = TimelineQuery {
+            cursor: Some("not-a-cursor".to_string()),
+            ..query(0, 60, 1000)
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
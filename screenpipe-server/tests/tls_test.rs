@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::tls::{certificate_fingerprint, certificate_names, TlsSource};
+    use std::net::{IpAddr, Ipv4Addr};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_certificate_names() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert_eq!(
+            certificate_names(localhost, None),
+            vec!["localhost", "127.0.0.1", "::1"]
+        );
+        assert_eq!(
+            certificate_names(IpAddr::V4(Ipv4Addr::UNSPECIFIED), Some("studio")),
+            vec!["localhost", "127.0.0.1", "::1", "studio", "studio.local"]
+        );
+        assert_eq!(
+            certificate_names("192.168.1.20".parse().unwrap(), Some("box.lan")),
+            vec!["localhost", "127.0.0.1", "::1", "192.168.1.20", "box.lan"]
+        );
+    }
+
+    #[test]
+    fn test_self_signed_certificate_is_reused() {
+        let dir = tempdir().unwrap();
+        let source = TlsSource::SelfSigned {
+            dir: dir.path().join("tls"),
+        };
+        let listen = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        let files = source.files(listen).unwrap();
+        let pem = std::fs::read_to_string(&files.cert).unwrap();
+        assert!(std::fs::read_to_string(&files.key)
+            .unwrap()
+            .contains("PRIVATE KEY"));
+        let fingerprint = certificate_fingerprint(&pem).unwrap();
+        assert_eq!(fingerprint.split(':').count(), 32);
+
+        let again = source.files(listen).unwrap();
+        assert_eq!(again, files);
+        let pem_again = std::fs::read_to_string(&again.cert).unwrap();
+        assert_eq!(certificate_fingerprint(&pem_again), Some(fingerprint));
+    }
+
+    #[test]
+    fn test_missing_certificate_files() {
+        let dir = tempdir().unwrap();
+        let source = TlsSource::Files {
+            cert: dir.path().join("cert.pem"),
+            key: dir.path().join("key.pem"),
+        };
+        assert!(source.files(IpAddr::V4(Ipv4Addr::LOCALHOST)).is_err());
+        assert_eq!(certificate_fingerprint("not a certificate"), None);
+    }
+
+    #[tokio::test]
+    async fn test_rustls_config_loads_self_signed_certificate() {
+        let dir = tempdir().unwrap();
+        let source = TlsSource::SelfSigned {
+            dir: dir.path().to_path_buf(),
+        };
+        let files = source.files(IpAddr::V4(Ipv4Addr::LOCALHOST)).unwrap();
+        assert!(screenpipe_server::tls::rustls_config(&files).await.is_ok());
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::Language;
+    use screenpipe_server::translation::{stored_translation, system_prompt};
+
+    #[test]
+    fn test_system_prompt_names_the_language() {
+        let prompt = system_prompt(&Language::English);
+        assert!(prompt.contains("to English."));
+        assert!(prompt.contains("<same>"));
+    }
+
+    #[test]
+    fn test_untranslated_answers_are_stored_empty() {
+        assert_eq!(
+            stored_translation("on livre vendredi", " we ship on friday\n"),
+            "we ship on friday"
+        );
+        assert_eq!(stored_translation("we ship on friday", "<same>"), "");
+        assert_eq!(
+            stored_translation("we ship on friday", "We ship on Friday"),
+            ""
+        );
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Result;
 use dirs::{self, home_dir};
 use screenpipe_core::Language;
 use screenpipe_server::video_utils::extract_frames_from_video;
-use screenpipe_vision::capture_screenshot_by_window::CapturedWindow;
+use screenpipe_vision::capture_screenshot_by_window::{CapturedWindow, WindowBounds};
 #[cfg(target_os = "macos")]
 use screenpipe_vision::perform_ocr_apple;
 use std::path::PathBuf;
@@ -131,13 +131,20 @@ async fn test_extract_frames_and_ocr() -> Result<()> {
         app_name: "test_app".to_string(),
         is_focused: true,
         process_id: 1234,
-        visible_percentage: 1.0
+        visible_percentage: 1.0,
+        bounds: WindowBounds {
+            x: 0,
+            y: 0,
+            width: first_frame.width(),
+            height: first_frame.height(),
+        },
     };
 
     // perform ocr using apple native (macos only)
     #[cfg(target_os = "macos")]
     {
-        let (text, _, confidence) = perform_ocr_apple(&captured_window.image, &[Language::English]);
+        let result = perform_ocr_apple(&captured_window.image, &[Language::English]);
+        let (text, confidence) = (result.text, result.confidence);
 
         println!("ocr confidence: {}", confidence.unwrap_or(0.0));
         println!("extracted text: {}", text);
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_server::webhooks::{is_retryable, retry_delay, sign, WebhookRequest};
+    use std::time::Duration;
+
+    fn request(url: &str, secret: Option<&str>, types: Option<&str>) -> WebhookRequest {
+        WebhookRequest {
+            url: url.to_string(),
+            secret: secret.map(str::to_string),
+            types: types.map(str::to_string),
+            apps: None,
+            keywords: Some("invoice".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_sign_matches_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // keys longer than a block are hashed first
+        let long_key = "k".repeat(131);
+        assert_eq!(sign(&long_key, b"body").len(), 64);
+        assert_ne!(sign(&long_key, b"body"), sign(&long_key[..130], b"body"));
+    }
+
+    #[test]
+    fn test_validate_webhook_request() {
+        let filter = request("https://n8n.local/webhook/1", Some("secret"), Some("ocr"))
+            .validate()
+            .unwrap();
+        assert_eq!(filter.types, vec!["ocr"]);
+        assert_eq!(filter.keywords, vec!["invoice"]);
+
+        assert!(request("not a url", None, None).validate().is_err());
+        assert!(request("ftp://files.local", None, None).validate().is_err());
+        assert!(request("http://localhost:5678", Some(""), None)
+            .validate()
+            .is_err());
+        assert!(request("http://localhost:5678", None, Some("frames"))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_retries_back_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(4), Duration::from_secs(16));
+
+        assert!(is_retryable(503));
+        assert!(is_retryable(429));
+        assert!(!is_retryable(404));
+        assert!(!is_retryable(400));
+    }
+}
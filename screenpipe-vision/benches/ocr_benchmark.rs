@@ -83,7 +83,7 @@ fn bench_apple_vision_ocr(c: &mut Criterion) {
     group.bench_function(BenchmarkId::new("Performance", ""), |b| {
         b.iter(|| {
             let result = perform_ocr_apple(black_box(&image), &[]);
-            assert!(!result.0.is_empty(), "OCR failed");
+            assert!(!result.text.is_empty(), "OCR failed");
         })
     });
 
@@ -107,7 +107,7 @@ fn bench_apple_vision_ocr_with_accuracy(c: &mut Criterion) {
                 let result = perform_ocr_apple(black_box(&image), &[]);
                 total_duration += start.elapsed();
 
-                let accuracy = calculate_accuracy(&result.0, EXPECTED_KEYWORDS);
+                let accuracy = calculate_accuracy(&result.text, EXPECTED_KEYWORDS);
                 total_accuracy += accuracy;
             }
 
@@ -134,7 +134,7 @@ fn bench_tesseract_ocr(c: &mut Criterion) {
 
             for _ in 0..iters {
                 let start = std::time::Instant::now();
-                let (result, _) = perform_ocr_tesseract(black_box(&image));
+                let result = perform_ocr_tesseract(black_box(&image), vec![]).text;
                 total_duration += start.elapsed();
 
                 let accuracy = calculate_accuracy(&result, EXPECTED_KEYWORDS);
@@ -169,7 +169,7 @@ fn bench_windows_ocr(c: &mut Criterion) {
 
                     for _ in 0..iters {
                         let start = std::time::Instant::now();
-                        let (result, _, _) = perform_ocr_windows(black_box(&image)).await.unwrap();
+                        let result = perform_ocr_windows(black_box(&image), &[]).await.unwrap().text;
                         total_duration += start.elapsed();
 
                         let accuracy = calculate_accuracy(&result, EXPECTED_KEYWORDS);
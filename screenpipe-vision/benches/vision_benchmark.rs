@@ -6,8 +6,11 @@
 use std::sync::Arc;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use screenpipe_vision::accessibility::AccessibilityConfig;
 use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
 use screenpipe_vision::monitor::get_default_monitor;
+use screenpipe_vision::ocr_quality::OcrQualityConfig;
+use screenpipe_vision::privacy::PrivacyPolicy;
 use screenpipe_vision::{continuous_capture, OcrEngine};
 use tokio::sync::mpsc;
 use tokio::time::Duration;
@@ -25,6 +28,13 @@ async fn benchmark_continuous_capture(duration_secs: u64) -> f64 {
             window_filters,
             vec![],
             false,
+            false,
+            None,
+            None,
+            PrivacyPolicy::default(),
+            None,
+            Arc::new(AccessibilityConfig::default()),
+            OcrQualityConfig::default(),
         )
         .await;
     });
@@ -1,5 +1,8 @@
 use clap::Parser;
 use screenpipe_core::Language;
+use screenpipe_vision::accessibility::AccessibilityConfig;
+use screenpipe_vision::ocr_quality::OcrQualityConfig;
+use screenpipe_vision::privacy::PrivacyPolicy;
 use screenpipe_vision::{
     capture_screenshot_by_window::WindowFilters, continuous_capture, OcrEngine,
 };
@@ -49,6 +52,13 @@ async fn main() {
         window_filters,
         languages.clone(),
         false,
+        false,
+        None,
+        None,
+        PrivacyPolicy::default(),
+        None,
+        Arc::new(AccessibilityConfig::default()),
+        OcrQualityConfig::default(),
     )
     .await;
 
@@ -57,11 +67,11 @@ async fn main() {
         if let Some(result) = result_rx.recv().await {
             for window_result in &result.window_ocr_results {
                 println!(
-                    "Window: {}\nApp: {}\nText length: {}\nJSON data: {:?}",
+                    "Window: {}\nApp: {}\nText length: {}\nLines: {:?}",
                     window_result.window_name,
                     window_result.app_name,
                     window_result.text.len(),
-                    window_result.text_json // Use {:?} to print the JSON data
+                    window_result.lines
                 );
             }
         }
@@ -3,12 +3,15 @@ use base64::{engine::general_purpose, Engine as _};
 use clap::Parser;
 use futures_util::{SinkExt, StreamExt};
 use image::ImageEncoder;
+use screenpipe_vision::accessibility::AccessibilityConfig;
 use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::ocr_quality::OcrQualityConfig;
+use screenpipe_vision::privacy::PrivacyPolicy;
 use screenpipe_vision::{
     continuous_capture, monitor::get_default_monitor, CaptureResult, OcrEngine,
 };
 use serde::Serialize;
-use std::collections::HashMap;
+use screenpipe_vision::OcrLine;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::{TcpListener, TcpStream};
@@ -28,7 +31,7 @@ pub struct SimplifiedWindowResult {
     pub window_name: String,
     pub app_name: String,
     pub text: String,
-    pub text_json: Vec<HashMap<String, String>>, // Change this line
+    pub lines: Vec<OcrLine>,
     pub focused: bool,
     pub confidence: f64,
 }
@@ -102,6 +105,13 @@ async fn main() -> Result<()> {
             window_filters,
             vec![],
             false,
+            false,
+            None,
+            None,
+            PrivacyPolicy::default(),
+            None,
+            Arc::new(AccessibilityConfig::default()),
+            OcrQualityConfig::default(),
         )
         .await
     });
@@ -151,7 +161,7 @@ async fn run_websocket_server(
                             window_name: window.window_name,
                             app_name: window.app_name,
                             text: window.text,
-                            text_json: window.text_json, // Add this line
+                            lines: window.lines,
                             focused: window.focused,
                             confidence: window.confidence,
                         }
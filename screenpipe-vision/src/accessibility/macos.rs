@@ -0,0 +1,181 @@
+use accessibility_sys::{
+    kAXChildrenAttribute, kAXErrorSuccess, kAXFocusedWindowAttribute, kAXPositionAttribute,
+    kAXRoleAttribute, kAXSecureTextFieldSubrole, kAXSizeAttribute, kAXSubroleAttribute,
+    kAXTitleAttribute, kAXValueAttribute, kAXValueTypeCGPoint, kAXValueTypeCGSize,
+    AXUIElementCopyAttributeValue, AXUIElementCreateApplication, AXUIElementRef,
+    AXUIElementSetAttributeValue, AXUIElementSetMessagingTimeout, AXValueGetValue, AXValueRef,
+};
+use anyhow::Result;
+use core_foundation::{
+    array::CFArray,
+    base::{CFGetTypeID, CFRelease, CFTypeRef, TCFType},
+    boolean::CFBoolean,
+    string::CFString,
+};
+use std::ffi::c_void;
+
+use super::{
+    AccessibilityNode, AccessibilityText, AccessibilityTextExtractor, ScreenRect, TextSource,
+    MAX_DEPTH, MAX_NODES,
+};
+
+// Elements whose value is the text they show
+const TEXT_ROLES: [&str; 4] = ["AXStaticText", "AXTextArea", "AXTextField", "AXHeading"];
+// Controls labelled through their title
+const LABEL_ROLES: [&str; 6] = [
+    "AXButton",
+    "AXLink",
+    "AXMenuButton",
+    "AXCheckBox",
+    "AXRadioButton",
+    "AXTab",
+];
+// Seconds an unresponsive app may block one attribute read
+const MESSAGING_TIMEOUT_SECS: f32 = 0.25;
+
+#[repr(C)]
+#[derive(Default)]
+struct CGPoint {
+    x: f64,
+    y: f64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CGSize {
+    width: f64,
+    height: f64,
+}
+
+#[derive(Default)]
+pub struct MacOSAccessibilityExtractor;
+
+impl MacOSAccessibilityExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Attribute value with a +1 retain count, released by the caller.
+    unsafe fn copy_attribute(
+        element: AXUIElementRef,
+        attribute: &'static str,
+    ) -> Option<CFTypeRef> {
+        let mut value: CFTypeRef = std::ptr::null_mut();
+        let status = AXUIElementCopyAttributeValue(
+            element,
+            CFString::from_static_string(attribute).as_concrete_TypeRef(),
+            &mut value,
+        );
+        (status == kAXErrorSuccess && !value.is_null()).then_some(value)
+    }
+
+    unsafe fn string_attribute(element: AXUIElementRef, attribute: &'static str) -> Option<String> {
+        let value = Self::copy_attribute(element, attribute)?;
+        if CFGetTypeID(value) != CFString::type_id() {
+            CFRelease(value);
+            return None;
+        }
+        Some(CFString::wrap_under_create_rule(value as _).to_string())
+    }
+
+    unsafe fn frame(element: AXUIElementRef) -> Option<ScreenRect> {
+        let position = Self::copy_attribute(element, kAXPositionAttribute)?;
+        let mut point = CGPoint::default();
+        let has_point = AXValueGetValue(
+            position as AXValueRef,
+            kAXValueTypeCGPoint,
+            &mut point as *mut CGPoint as *mut c_void,
+        );
+        CFRelease(position);
+
+        let size = Self::copy_attribute(element, kAXSizeAttribute)?;
+        let mut extent = CGSize::default();
+        let has_size = AXValueGetValue(
+            size as AXValueRef,
+            kAXValueTypeCGSize,
+            &mut extent as *mut CGSize as *mut c_void,
+        );
+        CFRelease(size);
+
+        (has_point && has_size).then_some(ScreenRect {
+            x: point.x,
+            y: point.y,
+            width: extent.width,
+            height: extent.height,
+        })
+    }
+
+    unsafe fn collect(element: AXUIElementRef, depth: usize, nodes: &mut Vec<AccessibilityNode>) {
+        if nodes.len() >= MAX_NODES || depth > MAX_DEPTH {
+            return;
+        }
+
+        let role = Self::string_attribute(element, kAXRoleAttribute).unwrap_or_default();
+        if Self::string_attribute(element, kAXSubroleAttribute).as_deref()
+            == Some(kAXSecureTextFieldSubrole)
+        {
+            return;
+        }
+
+        let text = if TEXT_ROLES.contains(&role.as_str()) {
+            Self::string_attribute(element, kAXValueAttribute)
+        } else if LABEL_ROLES.contains(&role.as_str()) {
+            Self::string_attribute(element, kAXTitleAttribute)
+        } else {
+            None
+        };
+        if let Some(text) = text.filter(|text| !text.trim().is_empty()) {
+            nodes.push(AccessibilityNode {
+                role: role.clone(),
+                text,
+                depth,
+                frame: Self::frame(element),
+            });
+        }
+
+        let Some(children) = Self::copy_attribute(element, kAXChildrenAttribute) else {
+            return;
+        };
+        let children = CFArray::<*const c_void>::wrap_under_create_rule(children as _);
+        for child in children.iter() {
+            Self::collect(*child as AXUIElementRef, depth + 1, nodes);
+        }
+    }
+}
+
+impl AccessibilityTextExtractor for MacOSAccessibilityExtractor {
+    fn extract_text(&self, process_id: i32) -> Result<Option<AccessibilityText>> {
+        unsafe {
+            let app_element = AXUIElementCreateApplication(process_id);
+            AXUIElementSetMessagingTimeout(app_element, MESSAGING_TIMEOUT_SECS);
+            // Chromium and Electron apps only build their tree once an assistive app asks
+            AXUIElementSetAttributeValue(
+                app_element,
+                CFString::from_static_string("AXManualAccessibility").as_concrete_TypeRef(),
+                CFBoolean::true_value().as_CFTypeRef(),
+            );
+
+            let Some(window) = Self::copy_attribute(app_element, kAXFocusedWindowAttribute) else {
+                CFRelease(app_element as CFTypeRef);
+                return Ok(None);
+            };
+
+            let window_frame = Self::frame(window as AXUIElementRef);
+            let mut nodes = Vec::new();
+            Self::collect(window as AXUIElementRef, 0, &mut nodes);
+
+            CFRelease(window);
+            CFRelease(app_element as CFTypeRef);
+
+            let text = AccessibilityText {
+                window_frame,
+                nodes,
+            };
+            Ok((!text.is_empty()).then_some(text))
+        }
+    }
+
+    fn source(&self) -> TextSource {
+        TextSource::Ax
+    }
+}
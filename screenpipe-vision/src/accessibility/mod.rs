@@ -0,0 +1,308 @@
+use crate::ocr_provider::OcrResult;
+use anyhow::Result;
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
+use serde::{Deserialize, Serialize};
+
+// Deep or huge trees (e.g. spreadsheets) are cut off instead of stalling capture
+pub const MAX_NODES: usize = 5_000;
+pub const MAX_DEPTH: usize = 64;
+
+/// Where the text of a window came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextSource {
+    #[default]
+    Ocr,
+    /// The macOS accessibility (AX) tree
+    Ax,
+    /// Windows UI Automation elements, merged with OCR
+    Uia,
+}
+
+impl TextSource {
+    /// Whether OCR still runs and fills in what the elements don't report. UI Automation
+    /// often exposes only control names for custom drawn content, so it's merged with OCR
+    /// instead of replacing it.
+    pub fn merges_with_ocr(&self) -> bool {
+        matches!(self, TextSource::Uia)
+    }
+}
+
+/// A rectangle in global screen points.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScreenRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Text of one accessibility element, in tree order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessibilityNode {
+    pub role: String,
+    pub text: String,
+    pub depth: usize,
+    pub frame: Option<ScreenRect>,
+}
+
+/// Text read from the accessibility tree of a window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessibilityText {
+    pub window_frame: Option<ScreenRect>,
+    pub nodes: Vec<AccessibilityNode>,
+}
+
+impl AccessibilityText {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.iter().all(|node| node.text.trim().is_empty())
+    }
+
+    /// The text laid out like an OCR pass over the window image: one line per line of
+    /// element text, boxes mapped from screen points to pixels of an image of
+    /// `image_width` x `image_height` showing the window. Word boxes are estimated from
+    /// character offsets, elements only report the frame of their whole text.
+    pub fn to_ocr_result(&self, image_width: u32, image_height: u32) -> OcrResult {
+        let mut lines = Vec::new();
+        let mut words = Vec::new();
+
+        for node in &self.nodes {
+            let node_lines: Vec<&str> = node
+                .text
+                .lines()
+                .map(str::trim_end)
+                .filter(|line| !line.trim().is_empty())
+                .collect();
+            let bbox = node
+                .frame
+                .zip(self.window_frame)
+                .map(|(frame, window)| to_image_bounds(&frame, &window, image_width, image_height))
+                .unwrap_or_default();
+            let line_height = bbox.height / node_lines.len().max(1) as f32;
+
+            for (index, line) in node_lines.into_iter().enumerate() {
+                let line_bbox = TextBounds {
+                    top: bbox.top + line_height * index as f32,
+                    height: line_height,
+                    ..bbox.clone()
+                };
+                words.extend(estimate_words(line, &line_bbox));
+                lines.push(OcrLine {
+                    text: line.to_string(),
+                    conf: 1.0,
+                    bbox: line_bbox,
+                });
+            }
+        }
+
+        OcrResult {
+            text: lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            lines,
+            words,
+            confidence: Some(1.0),
+        }
+    }
+}
+
+/// `accessibility` with the OCR lines and words it doesn't already cover appended, e.g. text
+/// drawn on a canvas or inside images that no element reports.
+pub fn merge_with_ocr(accessibility: OcrResult, ocr: OcrResult) -> OcrResult {
+    let covered = |bbox: &TextBounds| {
+        let (x, y) = (bbox.left + bbox.width / 2.0, bbox.top + bbox.height / 2.0);
+        accessibility.lines.iter().any(|line| {
+            x >= line.bbox.left
+                && x <= line.bbox.left + line.bbox.width
+                && y >= line.bbox.top
+                && y <= line.bbox.top + line.bbox.height
+        })
+    };
+    let known_text: Vec<String> = accessibility
+        .lines
+        .iter()
+        .map(|line| normalize(&line.text))
+        .collect();
+
+    let extra_lines: Vec<OcrLine> = ocr
+        .lines
+        .into_iter()
+        .filter(|line| {
+            let text = normalize(&line.text);
+            !text.is_empty()
+                && !covered(&line.bbox)
+                && !known_text.iter().any(|known| known.contains(&text))
+        })
+        .collect();
+    let extra_words: Vec<OcrWord> = ocr
+        .words
+        .into_iter()
+        .filter(|word| {
+            extra_lines.iter().any(|line| {
+                word.bbox.left >= line.bbox.left
+                    && word.bbox.left <= line.bbox.left + line.bbox.width
+                    && word.bbox.top + word.bbox.height / 2.0 >= line.bbox.top
+                    && word.bbox.top + word.bbox.height / 2.0 <= line.bbox.top + line.bbox.height
+            })
+        })
+        .collect();
+
+    let mut text = accessibility.text;
+    for line in &extra_lines {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&line.text);
+    }
+    let mut lines = accessibility.lines;
+    lines.extend(extra_lines);
+    let mut words = accessibility.words;
+    words.extend(extra_words);
+
+    OcrResult {
+        text,
+        lines,
+        words,
+        confidence: accessibility.confidence,
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn to_image_bounds(
+    frame: &ScreenRect,
+    window: &ScreenRect,
+    image_width: u32,
+    image_height: u32,
+) -> TextBounds {
+    if window.width <= 0.0 || window.height <= 0.0 {
+        return TextBounds::default();
+    }
+    let scale_x = image_width as f64 / window.width;
+    let scale_y = image_height as f64 / window.height;
+    let left = ((frame.x - window.x) * scale_x).clamp(0.0, image_width as f64);
+    let top = ((frame.y - window.y) * scale_y).clamp(0.0, image_height as f64);
+    let right = ((frame.x + frame.width - window.x) * scale_x).clamp(0.0, image_width as f64);
+    let bottom = ((frame.y + frame.height - window.y) * scale_y).clamp(0.0, image_height as f64);
+    TextBounds {
+        left: left as f32,
+        top: top as f32,
+        width: (right - left) as f32,
+        height: (bottom - top) as f32,
+    }
+}
+
+fn estimate_words(line: &str, bbox: &TextBounds) -> Vec<OcrWord> {
+    let char_count = line.chars().count().max(1) as f32;
+    let char_width = bbox.width / char_count;
+    let mut words = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+
+    let mut push = |from: usize, to: usize, text: &str| {
+        words.push(OcrWord {
+            text: text.to_string(),
+            conf: 1.0,
+            bbox: TextBounds {
+                left: bbox.left + char_width * from as f32,
+                width: char_width * (to - from) as f32,
+                ..bbox.clone()
+            },
+        });
+    };
+
+    let mut chars = 0;
+    for (byte_offset, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some((start_byte, start_char))) => {
+                push(start_char, chars, &line[start_byte..byte_offset]);
+                start = None;
+            }
+            (false, None) => start = Some((byte_offset, chars)),
+            _ => {}
+        }
+        chars += 1;
+    }
+    if let Some((start_byte, start_char)) = start {
+        push(start_char, chars, &line[start_byte..]);
+    }
+    words
+}
+
+/// Apps whose text is read from the accessibility tree instead of OCR, matched as
+/// case insensitive substrings of the app name like the window filters. Empty means OCR
+/// everywhere.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessibilityConfig {
+    apps: Vec<String>,
+}
+
+impl AccessibilityConfig {
+    pub fn new(apps: &[String]) -> Self {
+        Self {
+            apps: apps.iter().map(|app| app.to_lowercase()).collect(),
+        }
+    }
+
+    pub fn is_enabled_for(&self, app_name: &str) -> bool {
+        let app_name = app_name.to_lowercase();
+        self.apps.iter().any(|app| app_name.contains(app.as_str()))
+    }
+}
+
+// Trait definition
+pub trait AccessibilityTextExtractor {
+    /// Text of the focused window of the given process, `None` when the app exposes
+    /// nothing readable.
+    fn extract_text(&self, process_id: i32) -> Result<Option<AccessibilityText>>;
+
+    fn source(&self) -> TextSource;
+}
+
+// Factory function
+pub fn create_accessibility_extractor() -> Box<dyn AccessibilityTextExtractor> {
+    #[cfg(target_os = "macos")]
+    return Box::new(MacOSAccessibilityExtractor::new());
+
+    #[cfg(target_os = "windows")]
+    return Box::new(WindowsAccessibilityExtractor::new());
+
+    #[cfg(target_os = "linux")]
+    return Box::new(UnsupportedAccessibilityExtractor::new());
+}
+
+// Unsupported implementation
+#[derive(Default)]
+pub struct UnsupportedAccessibilityExtractor;
+
+impl UnsupportedAccessibilityExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl AccessibilityTextExtractor for UnsupportedAccessibilityExtractor {
+    fn extract_text(&self, _process_id: i32) -> Result<Option<AccessibilityText>> {
+        Ok(None)
+    }
+
+    fn source(&self) -> TextSource {
+        TextSource::Ocr
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::MacOSAccessibilityExtractor;
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use windows::WindowsAccessibilityExtractor;
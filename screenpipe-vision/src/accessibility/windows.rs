@@ -0,0 +1,132 @@
+use anyhow::Result;
+use uiautomation::controls::ControlType;
+use uiautomation::types::UIProperty;
+use uiautomation::{UIAutomation, UIElement, UITreeWalker};
+
+use super::{
+    AccessibilityNode, AccessibilityText, AccessibilityTextExtractor, ScreenRect, TextSource,
+    MAX_DEPTH, MAX_NODES,
+};
+
+// Elements whose value is the text they show
+const VALUE_TYPES: [ControlType; 2] = [ControlType::Edit, ControlType::Document];
+// Elements whose name is the text they show
+const NAME_TYPES: [ControlType; 11] = [
+    ControlType::Text,
+    ControlType::Hyperlink,
+    ControlType::Button,
+    ControlType::ListItem,
+    ControlType::MenuItem,
+    ControlType::TabItem,
+    ControlType::TreeItem,
+    ControlType::DataItem,
+    ControlType::HeaderItem,
+    ControlType::CheckBox,
+    ControlType::RadioButton,
+];
+
+#[derive(Default)]
+pub struct WindowsAccessibilityExtractor;
+
+impl WindowsAccessibilityExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn frame(element: &UIElement) -> Option<ScreenRect> {
+        let rect = element.get_bounding_rectangle().ok()?;
+        Some(ScreenRect {
+            x: rect.get_left() as f64,
+            y: rect.get_top() as f64,
+            width: rect.get_width() as f64,
+            height: rect.get_height() as f64,
+        })
+    }
+
+    fn text(element: &UIElement, control_type: ControlType) -> Option<String> {
+        if VALUE_TYPES.contains(&control_type) {
+            if element.is_password().unwrap_or(true) {
+                return None;
+            }
+            element
+                .get_property_value(UIProperty::ValueValue)
+                .ok()
+                .and_then(|value| value.get_string().ok())
+                .filter(|value| !value.trim().is_empty())
+                .or_else(|| element.get_name().ok())
+        } else if NAME_TYPES.contains(&control_type) {
+            element.get_name().ok()
+        } else {
+            None
+        }
+    }
+
+    fn collect(
+        walker: &UITreeWalker,
+        element: &UIElement,
+        depth: usize,
+        nodes: &mut Vec<AccessibilityNode>,
+    ) {
+        if nodes.len() >= MAX_NODES || depth > MAX_DEPTH {
+            return;
+        }
+        if element.is_offscreen().unwrap_or(false) {
+            return;
+        }
+
+        if let Ok(control_type) = element.get_control_type() {
+            if let Some(text) =
+                Self::text(element, control_type).filter(|text| !text.trim().is_empty())
+            {
+                nodes.push(AccessibilityNode {
+                    role: format!("{:?}", control_type),
+                    text,
+                    depth,
+                    frame: Self::frame(element),
+                });
+            }
+        }
+
+        let mut child = walker.get_first_child(element).ok();
+        while let Some(current) = child {
+            Self::collect(walker, &current, depth + 1, nodes);
+            child = walker.get_next_sibling(&current).ok();
+        }
+    }
+}
+
+impl AccessibilityTextExtractor for WindowsAccessibilityExtractor {
+    fn extract_text(&self, process_id: i32) -> Result<Option<AccessibilityText>> {
+        let automation = UIAutomation::new()?;
+        let focused = automation.get_focused_element()?;
+
+        // UI Automation reports the globally focused element, only trust it for this process
+        if focused.get_process_id()? as i32 != process_id {
+            return Ok(None);
+        }
+
+        // Climb from the focused element to its top level window
+        let walker = automation.get_control_view_walker()?;
+        let root = automation.get_root_element()?;
+        let mut window = focused;
+        while let Ok(parent) = walker.get_parent(&window) {
+            if automation.compare_elements(&parent, &root)? {
+                break;
+            }
+            window = parent;
+        }
+
+        let mut nodes = Vec::new();
+        Self::collect(&walker, &window, 0, &mut nodes);
+
+        let text = AccessibilityText {
+            window_frame: Self::frame(&window),
+            nodes,
+        };
+        Ok((!text.is_empty()).then_some(text))
+    }
+
+    fn source(&self) -> TextSource {
+        TextSource::Uia
+    }
+}
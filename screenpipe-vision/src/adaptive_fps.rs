@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+// Same difference below which `should_skip_frame` treats a frame as unchanged
+const ACTIVITY_THRESHOLD: f64 = 0.006;
+// Each idle frame slows capture down by this factor until `min_fps` is reached
+const IDLE_DECAY: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveFpsConfig {
+    pub min_fps: f64,
+    pub max_fps: f64,
+    /// Scales how small a change counts as activity, higher values ramp up on smaller changes
+    pub sensitivity: f64,
+}
+
+impl AdaptiveFpsConfig {
+    pub fn new(min_fps: f64, max_fps: f64, sensitivity: f64) -> Result<Self, String> {
+        if !min_fps.is_finite() || min_fps <= 0.0 {
+            return Err(format!("min fps must be greater than 0, got {}", min_fps));
+        }
+        if !max_fps.is_finite() || max_fps < min_fps {
+            return Err(format!(
+                "max fps must be at least min fps ({}), got {}",
+                min_fps, max_fps
+            ));
+        }
+        if !sensitivity.is_finite() || sensitivity <= 0.0 {
+            return Err(format!(
+                "fps sensitivity must be greater than 0, got {}",
+                sensitivity
+            ));
+        }
+
+        Ok(Self {
+            min_fps,
+            max_fps,
+            sensitivity,
+        })
+    }
+}
+
+/// Picks the delay before the next capture from how much recent frames changed: near
+/// identical frames gradually slow capture down to `min_fps`, any activity jumps straight
+/// back to `max_fps` so the start of a change isn't missed.
+#[derive(Debug, Clone)]
+pub struct AdaptiveFpsScheduler {
+    config: AdaptiveFpsConfig,
+    fps: f64,
+}
+
+impl AdaptiveFpsScheduler {
+    pub fn new(config: AdaptiveFpsConfig) -> Self {
+        Self {
+            config,
+            fps: config.max_fps,
+        }
+    }
+
+    /// Feeds the difference (0.0 - 1.0, histogram and SSIM average) between the last two frames.
+    pub fn observe(&mut self, difference: f64) {
+        if difference >= ACTIVITY_THRESHOLD / self.config.sensitivity {
+            self.fps = self.config.max_fps;
+        } else {
+            self.fps = (self.fps * IDLE_DECAY).max(self.config.min_fps);
+        }
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps)
+    }
+}
@@ -0,0 +1,69 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Looked up once per process, windows of the same app are captured every frame
+static APP_IDS: Lazy<Mutex<HashMap<i32, Option<String>>>> = Lazy::new(Default::default);
+
+/// Stable identifier of the app owning a process: the bundle id on macOS (e.g.
+/// "com.google.Chrome"), the executable name elsewhere (e.g. "chrome.exe"). Unlike the app
+/// name it doesn't change with the UI language, `None` when the process can't be inspected.
+pub fn app_id(process_id: i32) -> Option<String> {
+    if process_id <= 0 {
+        return None;
+    }
+    let mut app_ids = APP_IDS.lock().unwrap_or_else(|e| e.into_inner());
+    app_ids
+        .entry(process_id)
+        .or_insert_with(|| lookup_app_id(process_id))
+        .clone()
+}
+
+#[cfg(target_os = "macos")]
+fn lookup_app_id(process_id: i32) -> Option<String> {
+    use cidre::ns;
+
+    ns::RunningApp::with_pid(process_id)
+        .and_then(|app| app.bundle_id())
+        .map(|bundle_id| bundle_id.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn lookup_app_id(process_id: i32) -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let handle =
+            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id as u32).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        );
+        let _ = CloseHandle(handle);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        executable_name(std::path::Path::new(&path))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn lookup_app_id(process_id: i32) -> Option<String> {
+    let path = std::fs::read_link(format!("/proc/{}/exe", process_id)).ok()?;
+    executable_name(&path)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn executable_name(path: &std::path::Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
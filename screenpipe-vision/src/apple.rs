@@ -4,11 +4,11 @@ use cidre::{
     ns,
     vn::{self, ImageRequestHandler, RecognizeTextRequest},
 };
+use crate::ocr_provider::OcrResult;
 use image::DynamicImage;
 use image::GenericImageView;
-use tracing::error;
 use screenpipe_core::Language;
-use serde::{Deserialize, Serialize};
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
 use std::collections::HashMap;
 use std::sync::OnceLock;
 use std::{ffi::c_void, ptr::null_mut};
@@ -40,28 +40,6 @@ pub fn get_apple_languages(languages: &[Language]) -> Vec<String> {
         .collect()
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OcrResultBBox {
-    x: f64,
-    y: f64,
-    height: f64,
-    width: f64,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct OcrTextElement {
-    bounding_box: Vec<OcrResultBBox>,
-    confidence: f32,
-    text: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct OcrResult {
-    ocr_result: String,
-    text_elements: Vec<OcrTextElement>,
-    overall_confidence: f32,
-}
-
 #[no_mangle]
 #[cfg(target_os = "macos")]
 extern "C" fn release_callback(_refcon: *mut c_void, _data_ptr: *const *const c_void) {
@@ -69,10 +47,7 @@ extern "C" fn release_callback(_refcon: *mut c_void, _data_ptr: *const *const c_
 }
 
 #[cfg(target_os = "macos")]
-pub fn perform_ocr_apple(
-    image: &DynamicImage,
-    languages: &[Language],
-) -> (String, String, Option<f64>) {
+pub fn perform_ocr_apple(image: &DynamicImage, languages: &[Language]) -> OcrResult {
     cidre::objc::ar_pool(|| {
         // Convert languages to Apple format and create ns::Array
         let apple_languages = get_apple_languages(languages);
@@ -86,11 +61,10 @@ pub fn perform_ocr_apple(
         let raw_data = rgb.as_raw();
 
         let mut overall_confidence = 0.0;
-        let default_ocr_result = (
-            String::from(""),
-            String::from("[]"),
-            Some(overall_confidence),
-        );
+        let default_ocr_result = OcrResult {
+            confidence: Some(overall_confidence),
+            ..Default::default()
+        };
 
         let width = usize::try_from(width).unwrap();
         let height = usize::try_from(height).unwrap();
@@ -127,50 +101,89 @@ pub fn perform_ocr_apple(
 
         if let Some(results) = request.results() {
             if !results.is_empty() {
-                let mut ocr_results_vec: Vec<serde_json::Value> = Vec::new();
+                let mut lines: Vec<OcrLine> = Vec::new();
+                let mut words: Vec<OcrWord> = Vec::new();
                 let mut ocr_text: String = String::new();
                 results.iter().for_each(|result| {
                     let observation_result = result.top_candidates(1).get(0).unwrap();
                     let text = observation_result.string();
                     let confidence = observation_result.confidence() as f64;
-                    let bbox = observation_result
+                    let line_text = text.to_string();
+                    let line_bbox = match observation_result
                         .bounding_box_for_range(ns::Range::new(0, text.len()))
-                        .unwrap()
-                        .bounding_box();
-                    let x = bbox.origin.x;
-                    let y = bbox.origin.y;
-                    let height = bbox.size.height;
-                    let width = bbox.size.width;
-
-                    ocr_results_vec.push(serde_json::json!({
-                        "level": "0",
-                        "page_num": "0",
-                        "block_num": "0",
-                        "par_num": "0",
-                        "line_num": "0",
-                        "word_num": "0",
-                        "left": x.to_string(),
-                        "top": y.to_string(),
-                        "width": width.to_string(),
-                        "height": height.to_string(),
-                        "conf": confidence.to_string(),
-                        "text": text.to_string(),
-                    }));
+                    {
+                        Ok(observation) => to_pixel_bounds(observation.bounding_box(), width, height),
+                        Err(_) => TextBounds::default(),
+                    };
+
+                    // Vision only returns line observations, word boxes are resolved per range
+                    for (byte_offset, word) in split_words(&line_text) {
+                        let start = line_text[..byte_offset].encode_utf16().count();
+                        let len = word.encode_utf16().count();
+                        let bbox = observation_result
+                            .bounding_box_for_range(ns::Range::new(start, len))
+                            .map(|observation| {
+                                to_pixel_bounds(observation.bounding_box(), width, height)
+                            })
+                            .unwrap_or_else(|_| line_bbox.clone());
+                        words.push(OcrWord {
+                            text: word.to_string(),
+                            conf: confidence as f32,
+                            bbox,
+                        });
+                    }
+
+                    lines.push(OcrLine {
+                        text: line_text.clone(),
+                        conf: confidence as f32,
+                        bbox: line_bbox,
+                    });
 
                     overall_confidence += confidence;
-                    ocr_text.push_str(text.to_string().as_str());
+                    ocr_text.push_str(line_text.as_str());
                 });
 
-                let json_output_string =
-                    serde_json::to_string(&ocr_results_vec).unwrap_or_else(|e| {
-                        error!("Failed to serialize JSON output: {}", e);
-                        "[]".to_string()
-                    });
-
-                return (ocr_text, json_output_string, Some(overall_confidence));
+                return OcrResult {
+                    text: ocr_text,
+                    lines,
+                    words,
+                    confidence: Some(overall_confidence),
+                };
             }
         }
 
         default_ocr_result
     })
 }
+
+/// Vision boxes are normalized with a bottom-left origin, convert them to pixels from the
+/// top-left like every other engine.
+#[cfg(target_os = "macos")]
+fn to_pixel_bounds(bbox: cidre::cg::Rect, width: usize, height: usize) -> TextBounds {
+    let (width, height) = (width as f64, height as f64);
+    TextBounds {
+        left: (bbox.origin.x * width) as f32,
+        top: ((1.0 - bbox.origin.y - bbox.size.height) * height) as f32,
+        width: (bbox.size.width * width) as f32,
+        height: (bbox.size.height * height) as f32,
+    }
+}
+
+fn split_words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+    words
+}
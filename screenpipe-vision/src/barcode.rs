@@ -0,0 +1,34 @@
+use image::DynamicImage;
+use rxing::helpers::detect_multiple_in_luma;
+
+/// A QR code or barcode read from a captured window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedCode {
+    /// Symbology, e.g. `qrcode` or `ean 13`
+    pub format: String,
+    /// Decoded content, e.g. a link or an `otpauth://` provisioning uri
+    pub payload: String,
+}
+
+/// Every QR code and barcode in `image`, each payload once. Decoding scans the whole image,
+/// run it off the async runtime.
+pub fn detect_codes(image: &DynamicImage) -> Vec<DetectedCode> {
+    let luma = image.to_luma8();
+    let (width, height) = luma.dimensions();
+    // rxing reports "nothing found" as an error
+    let Ok(results) = detect_multiple_in_luma(luma.into_raw(), width, height) else {
+        return Vec::new();
+    };
+
+    let mut codes: Vec<DetectedCode> = Vec::new();
+    for result in results {
+        let code = DetectedCode {
+            format: result.getBarcodeFormat().to_string(),
+            payload: result.getText().to_string(),
+        };
+        if !code.payload.is_empty() && !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    codes
+}
@@ -1,5 +1,9 @@
+use crate::core::BROWSER_NAMES;
 use anyhow::Result;
 
+// Separators browsers put between the tab title and their own name
+const TITLE_SEPARATORS: [&str; 3] = [" - ", " — ", " – "];
+
 // Trait definition
 pub trait BrowserUrlDetector {
     fn get_active_url(&self, app_name: &str, process_id: i32) -> Result<Option<String>>;
@@ -32,6 +36,51 @@ impl BrowserUrlDetector for UnsupportedUrlDetector {
     }
 }
 
+/// Title of the active tab of a browser window. Browsers append their name (and sometimes
+/// the profile) to the tab title, e.g. "Pull request · repo - Google Chrome", `None` when
+/// `app_name` isn't a browser.
+pub fn tab_title_from_window(app_name: &str, window_name: &str) -> Option<String> {
+    let app_name = app_name.to_lowercase();
+    if !BROWSER_NAMES
+        .iter()
+        .any(|browser| app_name.contains(browser))
+    {
+        return None;
+    }
+
+    let mut title = window_name.trim_end();
+    loop {
+        let Some((index, separator)) = TITLE_SEPARATORS
+            .iter()
+            .filter_map(|separator| title.rfind(separator).map(|index| (index, *separator)))
+            .max_by_key(|(index, _)| *index)
+        else {
+            break;
+        };
+        let suffix = title[index + separator.len()..].to_lowercase();
+        let names_browser = suffix.contains(&app_name)
+            || BROWSER_NAMES.iter().any(|browser| suffix.contains(browser));
+        if !names_browser {
+            break;
+        }
+        title = title[..index].trim_end();
+    }
+
+    // Edge reports "<title> and 3 more pages" for windows with several tabs
+    if let Some(index) = title.rfind(" and ") {
+        let rest = &title[index + " and ".len()..];
+        let mut parts = rest.splitn(2, ' ');
+        if let (Some(count), Some(pages)) = (parts.next(), parts.next()) {
+            if count.parse::<u32>().is_ok() && pages.starts_with("more page") {
+                title = &title[..index];
+            }
+        }
+    }
+
+    let title = title.trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
 // Re-export MacOS implementation
 #[cfg(target_os = "macos")]
 mod macos;
@@ -0,0 +1,255 @@
+use super::{packed_to_image, CaptureCapabilities, CaptureFuture, PixelOrder, ScreenCapturer};
+use crate::cursor::cursor_options;
+use crate::monitor::SafeMonitor;
+use anyhow::{anyhow, Result};
+use cidre::{arc, cm, cv, define_obj_type, dispatch, ns, objc, sc};
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tracing::{debug, error, info};
+
+// Upper bound on how often the stream delivers changed frames, idle screens deliver none
+const MAX_STREAM_FPS: i32 = 30;
+const FIRST_FRAME_ATTEMPTS: u32 = 50;
+const FIRST_FRAME_POLL: Duration = Duration::from_millis(100);
+
+/// Captures monitors from a ScreenCaptureKit stream per display instead of polling
+/// screenshots. The stream only delivers a frame when the display content changed, so
+/// [`ScreenCapturer::frame_sequence`] lets the capture loop skip idle screens without taking
+/// a screenshot or hashing anything.
+pub struct ScreenCaptureKitCapturer {
+    streams: AsyncMutex<HashMap<u32, Arc<Mutex<LatestFrame>>>>,
+}
+
+impl ScreenCaptureKitCapturer {
+    pub fn new() -> Self {
+        Self {
+            streams: AsyncMutex::new(HashMap::new()),
+        }
+    }
+
+    async fn stream_for(&self, monitor: &SafeMonitor) -> Result<Arc<Mutex<LatestFrame>>> {
+        let mut streams = self.streams.lock().await;
+        if let Some(latest) = streams.get(&monitor.id()) {
+            return Ok(latest.clone());
+        }
+
+        // Match the pixel size of xcap screenshots so window bounds scale the same way
+        let (width, height) = {
+            let frame = monitor.capture_image().await?;
+            (frame.width() as usize, frame.height() as usize)
+        };
+        let latest = start_display_stream(monitor.id(), width, height).await?;
+        info!(
+            "screencapturekit stream started for monitor {} at {}x{}",
+            monitor.id(),
+            width,
+            height
+        );
+        streams.insert(monitor.id(), latest.clone());
+        Ok(latest)
+    }
+}
+
+impl Default for ScreenCaptureKitCapturer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenCapturer for ScreenCaptureKitCapturer {
+    fn name(&self) -> &str {
+        "screencapturekit"
+    }
+
+    fn capabilities(&self) -> CaptureCapabilities {
+        CaptureCapabilities {
+            windows: true,
+            change_notifications: true,
+            dirty_regions: false,
+            cursor: true,
+        }
+    }
+
+    fn capture_monitor<'a>(&'a self, monitor: &'a SafeMonitor) -> CaptureFuture<'a> {
+        Box::pin(async move {
+            let latest = self.stream_for(monitor).await?;
+            for _ in 0..FIRST_FRAME_ATTEMPTS {
+                let frame = latest.lock().unwrap().to_image();
+                if let Some(frame) = frame {
+                    return Ok(frame);
+                }
+                tokio::time::sleep(FIRST_FRAME_POLL).await;
+            }
+            Err(anyhow!("no frame received for monitor {}", monitor.id()))
+        })
+    }
+
+    fn frame_sequence(&self, monitor: &SafeMonitor) -> Option<u64> {
+        let streams = self.streams.try_lock().ok()?;
+        let latest = streams.get(&monitor.id())?;
+        let sequence = latest.lock().unwrap().sequence;
+        Some(sequence)
+    }
+}
+
+/// Last changed frame of a display. Pixels stay in the retained buffer until a capture asks
+/// for them, most delivered frames are never converted.
+#[derive(Default)]
+struct LatestFrame {
+    buffer: Option<RetainedPixelBuf>,
+    sequence: u64,
+}
+
+impl LatestFrame {
+    fn to_image(&self) -> Option<DynamicImage> {
+        let buffer = self.buffer.as_ref()?;
+        pixel_buf_to_image(&buffer.0)
+    }
+}
+
+struct RetainedPixelBuf(arc::R<cv::PixelBuf>);
+
+// CVPixelBuffers are reference counted CoreFoundation objects, safe to hand across threads
+unsafe impl Send for RetainedPixelBuf {}
+
+#[repr(C)]
+struct FrameOutputInner {
+    latest: Arc<Mutex<LatestFrame>>,
+}
+
+define_obj_type!(FrameOutput + sc::stream::OutputImpl, FrameOutputInner, FRAME_OUTPUT);
+
+impl sc::stream::Output for FrameOutput {}
+
+#[objc::add_methods]
+impl sc::stream::OutputImpl for FrameOutput {
+    extern "C" fn impl_stream_did_output_sample_buf(
+        &mut self,
+        _cmd: Option<&objc::Sel>,
+        _stream: &sc::Stream,
+        sample_buf: &mut cm::SampleBuf,
+        kind: sc::OutputType,
+    ) {
+        if kind != sc::OutputType::Screen {
+            return;
+        }
+        // idle and blank frames carry no pixels
+        let Some(image_buf) = sample_buf.image_buf() else {
+            return;
+        };
+
+        let mut latest = self.inner_mut().latest.lock().unwrap();
+        latest.buffer = Some(RetainedPixelBuf(image_buf.retained()));
+        latest.sequence += 1;
+    }
+}
+
+/// Starts the stream on a dedicated thread that owns the ScreenCaptureKit objects for the
+/// lifetime of the process.
+async fn start_display_stream(
+    display_id: u32,
+    width: usize,
+    height: usize,
+) -> Result<Arc<Mutex<LatestFrame>>> {
+    let latest = Arc::new(Mutex::new(LatestFrame::default()));
+    let thread_latest = latest.clone();
+    let (ready_tx, ready_rx) = oneshot::channel();
+
+    std::thread::Builder::new()
+        .name(format!("screencapturekit-{}", display_id))
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.into()));
+                    return;
+                }
+            };
+            match runtime.block_on(run_display_stream(display_id, width, height, thread_latest)) {
+                Ok((_stream, _output)) => {
+                    let _ = ready_tx.send(Ok(()));
+                    // the stream stops once these are released
+                    loop {
+                        std::thread::park();
+                    }
+                }
+                Err(e) => {
+                    error!("failed to start screencapturekit stream: {}", e);
+                    let _ = ready_tx.send(Err(e));
+                }
+            }
+        })?;
+
+    ready_rx
+        .await
+        .map_err(|_| anyhow!("screencapturekit thread exited before the stream started"))??;
+    Ok(latest)
+}
+
+async fn run_display_stream(
+    display_id: u32,
+    width: usize,
+    height: usize,
+    latest: Arc<Mutex<LatestFrame>>,
+) -> Result<(arc::R<sc::Stream>, arc::R<FrameOutput>)> {
+    let content = sc::ShareableContent::current()
+        .await
+        .map_err(|e| anyhow!("screen recording permission missing or denied: {:?}", e))?;
+    let displays = content.displays();
+    let display = displays
+        .iter()
+        .find(|display| display.display_id().0 == display_id)
+        .ok_or_else(|| anyhow!("display {} not shareable", display_id))?;
+
+    let mut cfg = sc::StreamCfg::new();
+    cfg.set_width(width);
+    cfg.set_height(height);
+    cfg.set_minimum_frame_interval(cm::Time::new(1, MAX_STREAM_FPS));
+    cfg.set_pixel_format(cv::PixelFormat::_32_BGRA);
+    cfg.set_shows_cursor(cursor_options().show_cursor);
+
+    let excluded_windows = ns::Array::new();
+    let filter = sc::ContentFilter::with_display_excluding_windows(display, &excluded_windows);
+    let stream = sc::Stream::new(&filter, &cfg);
+    let output = FrameOutput::with(FrameOutputInner { latest });
+    let queue = dispatch::Queue::serial_with_ar_pool();
+    stream
+        .add_stream_output(output.as_ref(), sc::OutputType::Screen, Some(&queue))
+        .map_err(|e| anyhow!("failed to attach screencapturekit output: {:?}", e))?;
+    stream
+        .start()
+        .await
+        .map_err(|e| anyhow!("failed to start screencapturekit stream: {:?}", e))?;
+    debug!("screencapturekit stream running for display {}", display_id);
+
+    Ok((stream, output))
+}
+
+fn pixel_buf_to_image(buffer: &cv::PixelBuf) -> Option<DynamicImage> {
+    let mut buffer = buffer.retained();
+    let width = buffer.width();
+    let height = buffer.height();
+    let stride = buffer.bytes_per_row();
+
+    unsafe {
+        buffer
+            .lock_base_addr(cv::pixel_buffer::LockFlags::READ_ONLY)
+            .result()
+            .ok()?;
+        let base = buffer.base_address() as *const u8;
+        let image = if base.is_null() {
+            None
+        } else {
+            let bytes = std::slice::from_raw_parts(base, stride * height);
+            packed_to_image(bytes, width as u32, height as u32, stride, PixelOrder::Bgra)
+        };
+        let _ = buffer.unlock_lock_base_addr(cv::pixel_buffer::LockFlags::READ_ONLY);
+        image
+    }
+}
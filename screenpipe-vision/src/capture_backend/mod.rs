@@ -0,0 +1,312 @@
+use crate::capture_screenshot_by_window::WindowBounds;
+use crate::monitor::SafeMonitor;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use image::DynamicImage;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+mod wayland;
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "macos")]
+pub use macos::ScreenCaptureKitCapturer;
+#[cfg(target_os = "windows")]
+pub use windows::DxgiCapturer;
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+pub use wayland::WaylandCapturer;
+
+// Generous enough for the Wayland portal dialog on first run
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub type CaptureFuture<'a> = Pin<Box<dyn Future<Output = Result<DynamicImage>> + Send + 'a>>;
+
+/// What a capturer can do beyond grabbing monitor frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CaptureCapabilities {
+    /// Individual windows are captured next to the monitor frame. Without it the whole
+    /// frame is OCRed as a single window.
+    pub windows: bool,
+    /// Frames are only delivered when the screen changed, see
+    /// [`ScreenCapturer::frame_sequence`]
+    pub change_notifications: bool,
+    /// Changed regions are reported, see [`ScreenCapturer::take_dirty_regions`]
+    pub dirty_regions: bool,
+    /// Frames contain the mouse cursor when [`crate::cursor::CursorOptions::show_cursor`]
+    /// is set, otherwise it's drawn onto them
+    pub cursor: bool,
+}
+
+impl fmt::Display for CaptureCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let capabilities: Vec<&str> = [
+            (self.windows, "windows"),
+            (self.change_notifications, "change notifications"),
+            (self.dirty_regions, "dirty regions"),
+            (self.cursor, "cursor"),
+        ]
+        .iter()
+        .filter(|(supported, _)| *supported)
+        .map(|(_, name)| *name)
+        .collect();
+        if capabilities.is_empty() {
+            write!(f, "monitor frames only")
+        } else {
+            write!(f, "{}", capabilities.join(", "))
+        }
+    }
+}
+
+/// Where monitor frames come from. xcap, ScreenCaptureKit, DXGI and PipeWire all implement
+/// it, the capture loop only talks to whichever one [`set_screen_capturer`] picked.
+pub trait ScreenCapturer: Send + Sync {
+    fn name(&self) -> &str;
+    fn capabilities(&self) -> CaptureCapabilities;
+    fn capture_monitor<'a>(&'a self, monitor: &'a SafeMonitor) -> CaptureFuture<'a>;
+    /// Counter bumped whenever the capturer received a frame with changed content, `None`
+    /// when it can't tell and every capture has to be hashed.
+    fn frame_sequence(&self, _monitor: &SafeMonitor) -> Option<u64> {
+        None
+    }
+    /// Regions of the monitor frame, in frame pixels, that changed since the previous call.
+    /// `None` when changes aren't tracked and frames have to be compared.
+    fn take_dirty_regions(&self, _monitor: &SafeMonitor) -> Option<Vec<WindowBounds>> {
+        None
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaptureBackendKind {
+    /// First backend of the platform that works: ScreenCaptureKit on macOS, DXGI on Windows,
+    /// the Wayland portal on Wayland sessions when built with the wayland feature, then xcap
+    #[default]
+    Auto,
+    Xcap,
+    /// PipeWire stream negotiated through xdg-desktop-portal, Linux only
+    Wayland,
+    /// Change driven ScreenCaptureKit stream, macOS only
+    #[clap(name = "screencapturekit")]
+    ScreenCaptureKit,
+    /// DXGI Desktop Duplication with dirty rectangles, Windows only
+    Dxgi,
+}
+
+impl CaptureBackendKind {
+    /// Backends tried by [`CaptureBackendKind::Auto`], best first. xcap always comes last.
+    pub fn candidates() -> Vec<CaptureBackendKind> {
+        let mut candidates = Vec::new();
+        if cfg!(target_os = "macos") {
+            candidates.push(CaptureBackendKind::ScreenCaptureKit);
+        }
+        if cfg!(target_os = "windows") {
+            candidates.push(CaptureBackendKind::Dxgi);
+        }
+        if is_wayland_session() {
+            candidates.push(CaptureBackendKind::Wayland);
+        }
+        candidates.push(CaptureBackendKind::Xcap);
+        candidates
+    }
+}
+
+/// Screenshots through xcap, works everywhere except Wayland only sessions.
+pub struct XcapCapturer;
+
+impl ScreenCapturer for XcapCapturer {
+    fn name(&self) -> &str {
+        "xcap"
+    }
+
+    fn capabilities(&self) -> CaptureCapabilities {
+        CaptureCapabilities {
+            windows: true,
+            ..Default::default()
+        }
+    }
+
+    fn capture_monitor<'a>(&'a self, monitor: &'a SafeMonitor) -> CaptureFuture<'a> {
+        Box::pin(monitor.capture_image())
+    }
+}
+
+static SCREEN_CAPTURER: OnceLock<Arc<dyn ScreenCapturer>> = OnceLock::new();
+
+/// Picks the capturer used by every capture in the process, probing it on `probe_monitor`
+/// when given. `Auto` takes the first candidate whose probe succeeds, an explicit kind fails
+/// when its probe does. Only the first call has an effect, captures made before it use xcap.
+pub async fn set_screen_capturer(
+    kind: CaptureBackendKind,
+    probe_monitor: Option<&SafeMonitor>,
+) -> Result<Arc<dyn ScreenCapturer>> {
+    let capturer = select_screen_capturer(kind, probe_monitor).await?;
+    info!(
+        "using {} screen capturer ({})",
+        capturer.name(),
+        capturer.capabilities()
+    );
+    Ok(SCREEN_CAPTURER.get_or_init(|| capturer).clone())
+}
+
+pub fn screen_capturer() -> Arc<dyn ScreenCapturer> {
+    SCREEN_CAPTURER
+        .get_or_init(|| Arc::new(XcapCapturer))
+        .clone()
+}
+
+pub async fn select_screen_capturer(
+    kind: CaptureBackendKind,
+    probe_monitor: Option<&SafeMonitor>,
+) -> Result<Arc<dyn ScreenCapturer>> {
+    if kind != CaptureBackendKind::Auto {
+        let capturer = create_screen_capturer(kind)?;
+        if let Some(monitor) = probe_monitor {
+            probe_screen_capturer(capturer.as_ref(), monitor).await?;
+        }
+        return Ok(capturer);
+    }
+
+    for candidate in CaptureBackendKind::candidates() {
+        let capturer = match create_screen_capturer(candidate) {
+            Ok(capturer) => capturer,
+            Err(e) => {
+                info!("skipping {:?} screen capturer: {}", candidate, e);
+                continue;
+            }
+        };
+        let Some(monitor) = probe_monitor else {
+            return Ok(capturer);
+        };
+        match probe_screen_capturer(capturer.as_ref(), monitor).await {
+            Ok(()) => return Ok(capturer),
+            Err(e) => warn!("{} screen capturer unusable: {}", capturer.name(), e),
+        }
+    }
+    Ok(Arc::new(XcapCapturer))
+}
+
+/// Captures one frame to check the capturer works here, e.g. that permissions are granted.
+pub async fn probe_screen_capturer(
+    capturer: &dyn ScreenCapturer,
+    monitor: &SafeMonitor,
+) -> Result<()> {
+    let frame = timeout(PROBE_TIMEOUT, capturer.capture_monitor(monitor))
+        .await
+        .map_err(|_| anyhow!("timed out capturing monitor {}", monitor.id()))??;
+    if frame.width() == 0 || frame.height() == 0 {
+        return Err(anyhow!("empty frame from monitor {}", monitor.id()));
+    }
+    Ok(())
+}
+
+pub fn create_screen_capturer(kind: CaptureBackendKind) -> Result<Arc<dyn ScreenCapturer>> {
+    match kind {
+        CaptureBackendKind::Xcap => Ok(Arc::new(XcapCapturer)),
+        CaptureBackendKind::Wayland => create_wayland_capturer(),
+        CaptureBackendKind::ScreenCaptureKit => create_screencapturekit_capturer(),
+        CaptureBackendKind::Dxgi => create_dxgi_capturer(),
+        CaptureBackendKind::Auto => create_screen_capturer(CaptureBackendKind::candidates()[0]),
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+fn create_wayland_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Ok(Arc::new(WaylandCapturer::new()))
+}
+
+#[cfg(not(all(target_os = "linux", feature = "wayland")))]
+fn create_wayland_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Err(anyhow!(
+        "the wayland capture backend needs a linux build with the wayland feature"
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn create_screencapturekit_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Ok(Arc::new(ScreenCaptureKitCapturer::new()))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn create_screencapturekit_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Err(anyhow!(
+        "the screencapturekit capture backend is only available on macos"
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn create_dxgi_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Ok(Arc::new(DxgiCapturer::new()))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_dxgi_capturer() -> Result<Arc<dyn ScreenCapturer>> {
+    Err(anyhow!("the dxgi capture backend is only available on windows"))
+}
+
+/// True on Wayland sessions, including ones where XWayland is running and xcap appears to
+/// work but only sees X11 clients.
+pub fn is_wayland_session() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    match std::env::var("XDG_SESSION_TYPE") {
+        Ok(session_type) => session_type.eq_ignore_ascii_case("wayland"),
+        Err(_) => std::env::var_os("WAYLAND_DISPLAY").is_some(),
+    }
+}
+
+/// Byte order of 4 byte pixels delivered by streaming backends.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "windows",
+    all(target_os = "linux", feature = "wayland")
+))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PixelOrder {
+    Bgra,
+    Rgba,
+}
+
+/// Copies a packed 32 bit frame with `stride` bytes per row into an opaque RGBA image.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "windows",
+    all(target_os = "linux", feature = "wayland")
+))]
+pub(crate) fn packed_to_image(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    order: PixelOrder,
+) -> Option<DynamicImage> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let row = width as usize * 4;
+    let stride = if stride == 0 { row } else { stride };
+    if stride < row || bytes.len() < stride * (height as usize - 1) + row {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for y in 0..height as usize {
+        for pixel in bytes[y * stride..y * stride + row].chunks_exact(4) {
+            let (r, g, b) = match order {
+                PixelOrder::Bgra => (pixel[2], pixel[1], pixel[0]),
+                PixelOrder::Rgba => (pixel[0], pixel[1], pixel[2]),
+            };
+            // x formats leave the alpha byte undefined
+            pixels.extend_from_slice(&[r, g, b, 255]);
+        }
+    }
+    image::RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+}
@@ -0,0 +1,392 @@
+use super::{packed_to_image, CaptureCapabilities, CaptureFuture, PixelOrder, ScreenCapturer};
+use crate::cursor::cursor_options;
+use crate::monitor::SafeMonitor;
+use anyhow::{anyhow, Result};
+use ashpd::desktop::screencast::{CursorMode, Screencast, SourceType};
+use ashpd::desktop::{PersistMode, Session};
+use image::DynamicImage;
+use pipewire as pw;
+use pw::properties::properties;
+use pw::spa;
+use pw::spa::param::format::{FormatProperties, MediaSubtype, MediaType};
+use pw::spa::param::video::{VideoFormat, VideoInfoRaw};
+use pw::spa::pod::Pod;
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, OnceCell};
+use tracing::{debug, error, info, warn};
+
+// The compositor sends the first frame shortly after the stream is connected
+const FIRST_FRAME_ATTEMPTS: u32 = 50;
+const FIRST_FRAME_POLL: Duration = Duration::from_millis(100);
+
+/// Captures monitors through the xdg-desktop-portal ScreenCast interface. The portal asks
+/// the user which screens to share on first use, the grant is remembered with a restore
+/// token so later runs start without a prompt. Monitors are still listed through xcap, which
+/// sees them via XWayland, and matched to portal streams by size.
+pub struct WaylandCapturer {
+    session: OnceCell<ScreencastSession>,
+}
+
+impl WaylandCapturer {
+    pub fn new() -> Self {
+        Self {
+            session: OnceCell::new(),
+        }
+    }
+}
+
+impl Default for WaylandCapturer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenCapturer for WaylandCapturer {
+    fn name(&self) -> &str {
+        "wayland"
+    }
+
+    // The portal only shares whole screens and frames are read from the stream as they come
+    fn capabilities(&self) -> CaptureCapabilities {
+        CaptureCapabilities {
+            cursor: true,
+            ..Default::default()
+        }
+    }
+
+    fn capture_monitor<'a>(&'a self, monitor: &'a SafeMonitor) -> CaptureFuture<'a> {
+        Box::pin(async move {
+            let session = self.session.get_or_try_init(ScreencastSession::start).await?;
+            let node_id = session.node_for(monitor).ok_or_else(|| {
+                anyhow!("no screencast stream shared for monitor {}", monitor.id())
+            })?;
+
+            for _ in 0..FIRST_FRAME_ATTEMPTS {
+                let frame = session.frames.lock().unwrap().get(&node_id).cloned();
+                if let Some(frame) = frame {
+                    return Ok(frame);
+                }
+                tokio::time::sleep(FIRST_FRAME_POLL).await;
+            }
+            Err(anyhow!("no frame received for monitor {}", monitor.id()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PortalStream {
+    node_id: u32,
+    /// Top left corner on the desktop, not every portal tells
+    position: Option<(i32, i32)>,
+    size: Option<(i32, i32)>,
+}
+
+struct ScreencastSession {
+    streams: Vec<PortalStream>,
+    /// Latest frame of each stream by PipeWire node id
+    frames: Arc<Mutex<HashMap<u32, DynamicImage>>>,
+}
+
+impl ScreencastSession {
+    /// Negotiates the portal session and runs the PipeWire loop on a dedicated thread, the
+    /// PipeWire objects are not `Send` so they never leave it.
+    async fn start() -> Result<Self> {
+        let frames = Arc::new(Mutex::new(HashMap::new()));
+        let thread_frames = frames.clone();
+        let (ready_tx, ready_rx) = oneshot::channel();
+
+        std::thread::Builder::new()
+            .name("wayland-capture".to_string())
+            .spawn(move || run_session(thread_frames, ready_tx))?;
+
+        let streams = ready_rx
+            .await
+            .map_err(|_| anyhow!("wayland capture thread exited before sharing a screen"))??;
+        info!("wayland screencast started with {} stream(s)", streams.len());
+
+        Ok(Self { streams, frames })
+    }
+
+    /// The stream of `monitor`, found by its position on the desktop. Without one it is
+    /// matched by size, as long as no other stream has the same size.
+    fn node_for(&self, monitor: &SafeMonitor) -> Option<u32> {
+        let info = monitor.get_info();
+        let position = Some((info.x, info.y));
+        let size = Some((info.width as i32, info.height as i32));
+        let by_size = || {
+            let mut same_size = self.streams.iter().filter(|stream| stream.size == size);
+            match (same_size.next(), same_size.next()) {
+                (Some(only), None) => Some(only),
+                _ => None,
+            }
+        };
+        self.streams
+            .iter()
+            .find(|stream| stream.position.is_some() && stream.position == position)
+            .or_else(by_size)
+            .or_else(|| match self.streams.as_slice() {
+                [only] => Some(only),
+                _ => None,
+            })
+            .map(|stream| stream.node_id)
+    }
+}
+
+struct StreamState {
+    node_id: u32,
+    format: VideoInfoRaw,
+    frames: Arc<Mutex<HashMap<u32, DynamicImage>>>,
+}
+
+fn run_session(
+    frames: Arc<Mutex<HashMap<u32, DynamicImage>>>,
+    ready_tx: oneshot::Sender<Result<Vec<PortalStream>>>,
+) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let _ = ready_tx.send(Err(e.into()));
+            return;
+        }
+    };
+    // the portal session has to outlive the PipeWire loop, closing it stops the streams
+    let (_proxy, _session, streams, fd) = match runtime.block_on(open_portal()) {
+        Ok(portal) => portal,
+        Err(e) => {
+            let _ = ready_tx.send(Err(e));
+            return;
+        }
+    };
+
+    if let Err(e) = run_pipewire(fd, &streams, frames, ready_tx) {
+        error!("wayland capture stopped: {}", e);
+    }
+}
+
+async fn open_portal() -> Result<(
+    Screencast<'static>,
+    Session<'static, Screencast<'static>>,
+    Vec<PortalStream>,
+    OwnedFd,
+)> {
+    let proxy = Screencast::new().await?;
+    let session = proxy.create_session().await?;
+    let restore_token = load_restore_token();
+    proxy
+        .select_sources(
+            &session,
+            if cursor_options().show_cursor {
+                CursorMode::Embedded
+            } else {
+                CursorMode::Hidden
+            },
+            SourceType::Monitor.into(),
+            true,
+            restore_token.as_deref(),
+            PersistMode::ExplicitlyRevoked,
+        )
+        .await?;
+
+    let response = proxy.start(&session, None).await?.response()?;
+    if let Some(token) = response.restore_token() {
+        save_restore_token(token);
+    }
+    let streams: Vec<PortalStream> = response
+        .streams()
+        .iter()
+        .map(|stream| PortalStream {
+            node_id: stream.pipe_wire_node_id(),
+            position: stream.position(),
+            size: stream.size(),
+        })
+        .collect();
+    if streams.is_empty() {
+        return Err(anyhow!("no screen was shared through the screencast portal"));
+    }
+
+    let fd = proxy.open_pipe_wire_remote(&session).await?;
+    Ok((proxy, session, streams, fd))
+}
+
+fn run_pipewire(
+    fd: OwnedFd,
+    portal_streams: &[PortalStream],
+    frames: Arc<Mutex<HashMap<u32, DynamicImage>>>,
+    ready_tx: oneshot::Sender<Result<Vec<PortalStream>>>,
+) -> Result<()> {
+    pw::init();
+    let mainloop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&mainloop)?;
+    let core = context.connect_fd(fd, None)?;
+
+    let mut streams = Vec::new();
+    for portal_stream in portal_streams {
+        let stream = pw::stream::Stream::new(
+            &core,
+            "screenpipe",
+            properties! {
+                *pw::keys::MEDIA_TYPE => "Video",
+                *pw::keys::MEDIA_CATEGORY => "Capture",
+                *pw::keys::MEDIA_ROLE => "Screen",
+            },
+        )?;
+
+        let listener = stream
+            .add_local_listener_with_user_data(StreamState {
+                node_id: portal_stream.node_id,
+                format: VideoInfoRaw::new(),
+                frames: frames.clone(),
+            })
+            .param_changed(|_, state, id, param| {
+                let Some(param) = param else {
+                    return;
+                };
+                if id != spa::param::ParamType::Format.as_raw() {
+                    return;
+                }
+                match spa::param::format_utils::parse_format(param) {
+                    Ok((MediaType::Video, MediaSubtype::Raw)) => {}
+                    _ => return,
+                }
+                if let Err(e) = state.format.parse(param) {
+                    warn!("failed to parse screencast format: {:?}", e);
+                    return;
+                }
+                debug!(
+                    "screencast stream {} negotiated {:?} {}x{}",
+                    state.node_id,
+                    state.format.format(),
+                    state.format.size().width,
+                    state.format.size().height
+                );
+            })
+            .process(|stream, state| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let Some(data) = buffer.datas_mut().first_mut() else {
+                    return;
+                };
+                let size = state.format.size();
+                let stride = data.chunk().stride().max(0) as usize;
+                let offset = data.chunk().offset() as usize;
+                let format = state.format.format();
+                let Some(bytes) = data.data() else {
+                    return;
+                };
+                let Some(bytes) = bytes.get(offset..) else {
+                    return;
+                };
+                if let Some(image) = frame_to_image(bytes, size.width, size.height, stride, format)
+                {
+                    state.frames.lock().unwrap().insert(state.node_id, image);
+                }
+            })
+            .register()?;
+
+        let format = format_param()?;
+        let mut params = [Pod::from_bytes(&format)
+            .ok_or_else(|| anyhow!("invalid screencast format parameters"))?];
+        stream.connect(
+            spa::utils::Direction::Input,
+            Some(portal_stream.node_id),
+            pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+            &mut params,
+        )?;
+        streams.push((stream, listener));
+    }
+
+    let _ = ready_tx.send(Ok(portal_streams.to_vec()));
+    mainloop.run();
+    Ok(())
+}
+
+/// Formats we can turn into an RGBA image without a conversion library.
+fn format_param() -> Result<Vec<u8>> {
+    let object = spa::pod::object!(
+        spa::utils::SpaTypes::ObjectParamFormat,
+        spa::param::ParamType::EnumFormat,
+        spa::pod::property!(FormatProperties::MediaType, Id, MediaType::Video),
+        spa::pod::property!(FormatProperties::MediaSubtype, Id, MediaSubtype::Raw),
+        spa::pod::property!(
+            FormatProperties::VideoFormat,
+            Choice,
+            Enum,
+            Id,
+            VideoFormat::BGRx,
+            VideoFormat::BGRx,
+            VideoFormat::BGRA,
+            VideoFormat::RGBx,
+            VideoFormat::RGBA
+        ),
+        spa::pod::property!(
+            FormatProperties::VideoSize,
+            Choice,
+            Range,
+            Rectangle,
+            spa::utils::Rectangle {
+                width: 1920,
+                height: 1080
+            },
+            spa::utils::Rectangle {
+                width: 1,
+                height: 1
+            },
+            spa::utils::Rectangle {
+                width: 8192,
+                height: 8192
+            }
+        ),
+    );
+
+    let (cursor, _) = spa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(Vec::new()),
+        &spa::pod::Value::Object(object),
+    )
+    .map_err(|e| anyhow!("failed to serialize screencast format: {:?}", e))?;
+    Ok(cursor.into_inner())
+}
+
+fn frame_to_image(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    format: VideoFormat,
+) -> Option<DynamicImage> {
+    let order = match format {
+        VideoFormat::BGRx | VideoFormat::BGRA => PixelOrder::Bgra,
+        VideoFormat::RGBx | VideoFormat::RGBA => PixelOrder::Rgba,
+        _ => return None,
+    };
+    packed_to_image(bytes, width, height, stride, order)
+}
+
+fn restore_token_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("screenpipe").join("wayland-restore-token"))
+}
+
+fn load_restore_token() -> Option<String> {
+    let token = std::fs::read_to_string(restore_token_path()?).ok()?;
+    let token = token.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+fn save_restore_token(token: &str) {
+    let Some(path) = restore_token_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = std::fs::write(&path, token) {
+        warn!("failed to save the screencast restore token: {}", e);
+    }
+}
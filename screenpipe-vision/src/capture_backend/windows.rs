@@ -0,0 +1,329 @@
+use super::{packed_to_image, CaptureCapabilities, CaptureFuture, PixelOrder, ScreenCapturer};
+use crate::capture_screenshot_by_window::WindowBounds;
+use crate::monitor::SafeMonitor;
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+use windows::core::Interface;
+use windows::Win32::Foundation::{HMODULE, RECT};
+use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_UNKNOWN;
+use windows::Win32::Graphics::Direct3D11::{
+    D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D,
+    D3D11_CPU_ACCESS_READ, D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_MAPPED_SUBRESOURCE,
+    D3D11_MAP_READ, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+};
+use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
+use windows::Win32::Graphics::Dxgi::{
+    CreateDXGIFactory1, IDXGIFactory1, IDXGIOutput1, IDXGIOutputDuplication, IDXGIResource,
+    DXGI_ERROR_ACCESS_LOST, DXGI_ERROR_WAIT_TIMEOUT, DXGI_OUTDUPL_FRAME_INFO,
+    DXGI_OUTDUPL_MOVE_RECT,
+};
+
+// Short wait so an idle screen doesn't hold up the capture loop
+const ACQUIRE_TIMEOUT_MS: u32 = 50;
+
+/// Captures monitors through DXGI Desktop Duplication. Besides the frame, the compositor
+/// reports which rectangles changed since the previous frame, the capture loop uses them
+/// instead of comparing every full frame. Falls back to xcap for monitors that can't be
+/// duplicated, e.g. while the secure desktop is shown.
+pub struct DxgiCapturer {
+    outputs: Mutex<HashMap<u32, Arc<Mutex<Duplication>>>>,
+}
+
+impl DxgiCapturer {
+    pub fn new() -> Self {
+        Self {
+            outputs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn duplication_for(&self, monitor_id: u32) -> Result<Arc<Mutex<Duplication>>> {
+        let mut outputs = self.outputs.lock().unwrap();
+        if let Some(duplication) = outputs.get(&monitor_id) {
+            return Ok(duplication.clone());
+        }
+        let duplication = Arc::new(Mutex::new(Duplication::new(monitor_id)?));
+        outputs.insert(monitor_id, duplication.clone());
+        Ok(duplication)
+    }
+
+    fn forget(&self, monitor_id: u32) {
+        self.outputs.lock().unwrap().remove(&monitor_id);
+    }
+
+    fn capture_blocking(&self, monitor_id: u32) -> Result<Option<DynamicImage>> {
+        let duplication = self.duplication_for(monitor_id)?;
+        let result = duplication.lock().unwrap().capture();
+        if result.is_err() {
+            // access is lost on mode changes and desktop switches, duplicate again next time
+            self.forget(monitor_id);
+        }
+        result
+    }
+}
+
+impl Default for DxgiCapturer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenCapturer for DxgiCapturer {
+    fn name(&self) -> &str {
+        "dxgi"
+    }
+
+    fn capabilities(&self) -> CaptureCapabilities {
+        CaptureCapabilities {
+            windows: true,
+            change_notifications: false,
+            dirty_regions: true,
+            cursor: false,
+        }
+    }
+
+    fn capture_monitor<'a>(&'a self, monitor: &'a SafeMonitor) -> CaptureFuture<'a> {
+        Box::pin(async move {
+            let monitor_id = monitor.id();
+            match self.capture_blocking(monitor_id) {
+                Ok(Some(image)) => Ok(image),
+                Ok(None) => monitor.capture_image().await,
+                Err(e) => {
+                    warn!(
+                        "desktop duplication failed for monitor {}: {}, using xcap",
+                        monitor_id, e
+                    );
+                    monitor.capture_image().await
+                }
+            }
+        })
+    }
+
+    fn take_dirty_regions(&self, monitor: &SafeMonitor) -> Option<Vec<WindowBounds>> {
+        let duplication = self.outputs.lock().unwrap().get(&monitor.id())?.clone();
+        let regions = duplication.lock().unwrap().dirty.take();
+        regions
+    }
+}
+
+struct Duplication {
+    device: ID3D11Device,
+    context: ID3D11DeviceContext,
+    duplication: IDXGIOutputDuplication,
+    staging: Option<(ID3D11Texture2D, u32, u32)>,
+    last_frame: Option<DynamicImage>,
+    /// Changed regions accumulated since the last `take_dirty_regions`, `None` until the
+    /// first frame so it is compared like any other backend
+    dirty: Option<Vec<WindowBounds>>,
+}
+
+// D3D11 devices are free threaded and the immediate context is only used behind the mutex
+unsafe impl Send for Duplication {}
+
+impl Duplication {
+    /// Finds the DXGI output driving the monitor, xcap ids are the monitor handle.
+    fn new(monitor_id: u32) -> Result<Self> {
+        unsafe {
+            let factory: IDXGIFactory1 = CreateDXGIFactory1()?;
+            let mut adapter_index = 0;
+            while let Ok(adapter) = factory.EnumAdapters1(adapter_index) {
+                let mut output_index = 0;
+                while let Ok(output) = adapter.EnumOutputs(output_index) {
+                    let desc = output.GetDesc()?;
+                    if desc.Monitor.0 as usize as u32 == monitor_id {
+                        let mut device = None;
+                        let mut context = None;
+                        // an explicit adapter requires the unknown driver type
+                        D3D11CreateDevice(
+                            &adapter,
+                            D3D_DRIVER_TYPE_UNKNOWN,
+                            HMODULE::default(),
+                            D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                            None,
+                            D3D11_SDK_VERSION,
+                            Some(&mut device),
+                            None,
+                            Some(&mut context),
+                        )?;
+                        let device = device.ok_or_else(|| anyhow!("no d3d11 device"))?;
+                        let context = context.ok_or_else(|| anyhow!("no d3d11 context"))?;
+                        let duplication =
+                            output.cast::<IDXGIOutput1>()?.DuplicateOutput(&device)?;
+                        debug!("desktop duplication started for monitor {}", monitor_id);
+
+                        return Ok(Self {
+                            device,
+                            context,
+                            duplication,
+                            staging: None,
+                            last_frame: None,
+                            dirty: None,
+                        });
+                    }
+                    output_index += 1;
+                }
+                adapter_index += 1;
+            }
+        }
+        Err(anyhow!("no dxgi output for monitor {}", monitor_id))
+    }
+
+    /// `None` when nothing was presented since duplication started.
+    fn capture(&mut self) -> Result<Option<DynamicImage>> {
+        let mut info = DXGI_OUTDUPL_FRAME_INFO::default();
+        let mut resource: Option<IDXGIResource> = None;
+        let acquired = unsafe {
+            self.duplication
+                .AcquireNextFrame(ACQUIRE_TIMEOUT_MS, &mut info, &mut resource)
+        };
+        if let Err(e) = acquired {
+            if e.code() == DXGI_ERROR_WAIT_TIMEOUT {
+                // nothing was presented since the previous frame
+                if self.last_frame.is_some() {
+                    self.dirty.get_or_insert_with(Vec::new);
+                }
+                return Ok(self.last_frame.clone());
+            }
+            if e.code() == DXGI_ERROR_ACCESS_LOST {
+                return Err(anyhow!("desktop duplication access lost"));
+            }
+            return Err(e.into());
+        }
+
+        let result = self.read_frame(&info, resource);
+        unsafe {
+            let _ = self.duplication.ReleaseFrame();
+        }
+        result.map(Some)
+    }
+
+    fn read_frame(
+        &mut self,
+        info: &DXGI_OUTDUPL_FRAME_INFO,
+        resource: Option<IDXGIResource>,
+    ) -> Result<DynamicImage> {
+        // only the pointer moved
+        if info.LastPresentTime == 0 {
+            if let Some(frame) = &self.last_frame {
+                self.dirty.get_or_insert_with(Vec::new);
+                return Ok(frame.clone());
+            }
+        }
+
+        let texture: ID3D11Texture2D = resource
+            .ok_or_else(|| anyhow!("frame without desktop image"))?
+            .cast()?;
+        let image = self.copy_texture(&texture)?;
+
+        let first_frame = self.last_frame.is_none();
+        let regions = self.changed_regions(info)?;
+        if !first_frame {
+            self.dirty.get_or_insert_with(Vec::new).extend(regions);
+        }
+        self.last_frame = Some(image.clone());
+        Ok(image)
+    }
+
+    fn changed_regions(&self, info: &DXGI_OUTDUPL_FRAME_INFO) -> Result<Vec<WindowBounds>> {
+        let buffer_size = info.TotalMetadataBufferSize;
+        if buffer_size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut regions = Vec::new();
+        unsafe {
+            // moved regions change their destination, the source shows up as dirty
+            let move_size = std::mem::size_of::<DXGI_OUTDUPL_MOVE_RECT>();
+            let mut move_rects =
+                vec![DXGI_OUTDUPL_MOVE_RECT::default(); buffer_size as usize / move_size + 1];
+            let mut required = 0;
+            self.duplication.GetFrameMoveRects(
+                (move_rects.len() * move_size) as u32,
+                move_rects.as_mut_ptr(),
+                &mut required,
+            )?;
+            let count = required as usize / move_size;
+            regions.extend(
+                move_rects[..count]
+                    .iter()
+                    .map(|moved| rect_bounds(&moved.DestinationRect)),
+            );
+
+            let rect_size = std::mem::size_of::<RECT>();
+            let mut dirty_rects = vec![RECT::default(); buffer_size as usize / rect_size + 1];
+            let mut required = 0;
+            self.duplication.GetFrameDirtyRects(
+                (dirty_rects.len() * rect_size) as u32,
+                dirty_rects.as_mut_ptr(),
+                &mut required,
+            )?;
+            let count = required as usize / rect_size;
+            regions.extend(dirty_rects[..count].iter().map(rect_bounds));
+        }
+        Ok(regions)
+    }
+
+    fn copy_texture(&mut self, texture: &ID3D11Texture2D) -> Result<DynamicImage> {
+        unsafe {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            texture.GetDesc(&mut desc);
+
+            let staging = match &self.staging {
+                Some((staging, width, height))
+                    if *width == desc.Width && *height == desc.Height =>
+                {
+                    staging.clone()
+                }
+                _ => {
+                    let staging_desc = D3D11_TEXTURE2D_DESC {
+                        Width: desc.Width,
+                        Height: desc.Height,
+                        MipLevels: 1,
+                        ArraySize: 1,
+                        Format: desc.Format,
+                        SampleDesc: DXGI_SAMPLE_DESC {
+                            Count: 1,
+                            Quality: 0,
+                        },
+                        Usage: D3D11_USAGE_STAGING,
+                        BindFlags: 0,
+                        CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+                        MiscFlags: 0,
+                    };
+                    let mut staging = None;
+                    self.device
+                        .CreateTexture2D(&staging_desc, None, Some(&mut staging))?;
+                    let staging = staging.ok_or_else(|| anyhow!("no staging texture"))?;
+                    self.staging = Some((staging.clone(), desc.Width, desc.Height));
+                    staging
+                }
+            };
+
+            self.context.CopyResource(&staging, texture);
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            self.context
+                .Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+            let stride = mapped.RowPitch as usize;
+            let bytes = std::slice::from_raw_parts(
+                mapped.pData as *const u8,
+                stride * desc.Height as usize,
+            );
+            // duplication always hands out B8G8R8A8 desktops
+            let image = packed_to_image(bytes, desc.Width, desc.Height, stride, PixelOrder::Bgra);
+            self.context.Unmap(&staging, 0);
+
+            image.ok_or_else(|| anyhow!("unexpected desktop texture layout"))
+        }
+    }
+}
+
+fn rect_bounds(rect: &RECT) -> WindowBounds {
+    WindowBounds {
+        x: rect.left,
+        y: rect.top,
+        width: (rect.right - rect.left).max(0) as u32,
+        height: (rect.bottom - rect.top).max(0) as u32,
+    }
+}
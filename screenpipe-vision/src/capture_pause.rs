@@ -0,0 +1,40 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Reasons screen capture is paused for in this process, e.g. `idle`. No frame is captured,
+/// OCRed or recorded while any is set.
+static PAUSE_REASONS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Pauses capture until `resume_capture` is called with the same reason. Returns whether it
+/// wasn't paused for it already.
+pub fn pause_capture(reason: &str) -> bool {
+    PAUSE_REASONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(reason.to_string())
+}
+
+/// Returns whether capture was paused for `reason`. It only resumes once no reason is left.
+pub fn resume_capture(reason: &str) -> bool {
+    PAUSE_REASONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(reason)
+}
+
+pub fn is_capture_paused() -> bool {
+    !PAUSE_REASONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_empty()
+}
+
+/// Why capture is paused, sorted. Empty while capturing.
+pub fn capture_pause_reasons() -> Vec<String> {
+    PAUSE_REASONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,112 @@
+use crate::capture_screenshot_by_window::{CapturedWindow, WindowBounds};
+use crate::monitor::{MonitorSelector, SafeMonitor};
+use image::DynamicImage;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Part of a monitor to capture instead of the whole screen, parsed from
+/// `<selector>=<x>,<y>,<width>,<height>` in monitor coordinates (points on HiDPI screens).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorRegion {
+    pub selector: MonitorSelector,
+    pub region: WindowBounds,
+}
+
+impl FromStr for MonitorRegion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid capture region '{}', expected <selector>=<x>,<y>,<width>,<height>",
+                s
+            )
+        };
+        let (selector, rect) = s.rsplit_once('=').ok_or_else(invalid)?;
+        let values = rect
+            .split(',')
+            .map(|value| value.trim().parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x, y, width, height] = values[..] else {
+            return Err(invalid());
+        };
+        if x < 0 || y < 0 || width <= 0 || height <= 0 {
+            return Err(format!(
+                "capture region '{}' must have a positive size inside the monitor",
+                s
+            ));
+        }
+
+        Ok(MonitorRegion {
+            selector: selector.parse()?,
+            region: WindowBounds {
+                x: x as i32,
+                y: y as i32,
+                width: width as u32,
+                height: height as u32,
+            },
+        })
+    }
+}
+
+/// Region to capture on `monitor`, the first matching entry wins.
+pub fn monitor_region(
+    regions: &[MonitorRegion],
+    index: usize,
+    monitor: &SafeMonitor,
+) -> Option<WindowBounds> {
+    regions
+        .iter()
+        .find(|r| r.selector.matches(index, monitor.id(), &monitor.get_info()))
+        .map(|r| r.region.clone())
+}
+
+/// Crops the monitor frame to `region`, in frame pixels, and keeps only the part of each
+/// window inside it. Window bounds end up relative to the cropped frame.
+pub fn crop_to_region(
+    image: &mut DynamicImage,
+    windows: &mut Vec<CapturedWindow>,
+    region: &WindowBounds,
+) {
+    let frame = WindowBounds {
+        x: 0,
+        y: 0,
+        width: image.width(),
+        height: image.height(),
+    };
+    let Some(region) = region.intersect(&frame) else {
+        warn!(
+            "capture region {:?} is outside the {}x{} frame, capturing the whole monitor",
+            region,
+            image.width(),
+            image.height()
+        );
+        return;
+    };
+
+    *image = image.crop_imm(region.x as u32, region.y as u32, region.width, region.height);
+
+    windows.retain_mut(|window| {
+        let Some(inside) = window.bounds.intersect(&region) else {
+            return false;
+        };
+
+        // window images can be captured at a different scale than the frame
+        let scale_x = window.image.width() as f32 / window.bounds.width.max(1) as f32;
+        let scale_y = window.image.height() as f32 / window.bounds.height.max(1) as f32;
+        let crop_x = ((inside.x - window.bounds.x) as f32 * scale_x) as u32;
+        let crop_y = ((inside.y - window.bounds.y) as f32 * scale_y) as u32;
+        let crop_width = ((inside.width as f32 * scale_x) as u32).max(1);
+        let crop_height = ((inside.height as f32 * scale_y) as u32).max(1);
+        window.image = window.image.crop_imm(crop_x, crop_y, crop_width, crop_height);
+
+        window.visible_percentage *= inside.area() as f32 / window.bounds.area().max(1) as f32;
+        window.bounds = WindowBounds {
+            x: inside.x - region.x,
+            y: inside.y - region.y,
+            width: inside.width,
+            height: inside.height,
+        };
+        true
+    });
+}
@@ -1,5 +1,6 @@
-use image::DynamicImage;
+use image::{DynamicImage, Rgba};
 use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
 use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
@@ -151,22 +152,34 @@ pub struct CapturedWindow {
     pub process_id: i32,
     pub is_focused: bool,
     pub visible_percentage: f32,
+    /// Where the window sits in the monitor frame, in frame pixels
+    pub bounds: WindowBounds,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-struct WindowBounds {
-    x: i32,
-    y: i32,
-    width: u32,
-    height: u32,
+pub struct WindowBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
 }
 
 impl WindowBounds {
-    fn area(&self) -> u32 {
+    /// Converts monitor coordinates into frame pixels, see [`mask_regions`].
+    pub fn scaled(&self, scale: f32) -> WindowBounds {
+        WindowBounds {
+            x: (self.x as f32 * scale).round() as i32,
+            y: (self.y as f32 * scale).round() as i32,
+            width: (self.width as f32 * scale).round() as u32,
+            height: (self.height as f32 * scale).round() as u32,
+        }
+    }
+
+    pub(crate) fn area(&self) -> u32 {
         self.width * self.height
     }
 
-    fn intersect(&self, other: &WindowBounds) -> Option<WindowBounds> {
+    pub(crate) fn intersect(&self, other: &WindowBounds) -> Option<WindowBounds> {
         let x1 = self.x.max(other.x);
         let y1 = self.y.max(other.y);
         let x2 = (self.x + self.width as i32).min(other.x + other.width as i32);
@@ -183,11 +196,43 @@ impl WindowBounds {
             None
         }
     }
+
+    /// Parts of `self` not covered by `other`, as up to four rectangles.
+    fn subtract(&self, other: &WindowBounds) -> Vec<WindowBounds> {
+        let Some(overlap) = self.intersect(other) else {
+            return vec![self.clone()];
+        };
+
+        let right = self.x + self.width as i32;
+        let bottom = self.y + self.height as i32;
+        let overlap_right = overlap.x + overlap.width as i32;
+        let overlap_bottom = overlap.y + overlap.height as i32;
+
+        [
+            // above and below the overlap, full width
+            (self.x, self.y, right, overlap.y),
+            (self.x, overlap_bottom, right, bottom),
+            // left and right of the overlap, overlap height
+            (self.x, overlap.y, overlap.x, overlap_bottom),
+            (overlap_right, overlap.y, right, overlap_bottom),
+        ]
+        .into_iter()
+        .filter(|(x1, y1, x2, y2)| x2 > x1 && y2 > y1)
+        .map(|(x1, y1, x2, y2)| WindowBounds {
+            x: x1,
+            y: y1,
+            width: (x2 - x1) as u32,
+            height: (y2 - y1) as u32,
+        })
+        .collect()
+    }
 }
 
 pub struct WindowFilters {
     ignore_set: HashSet<String>,
     include_set: HashSet<String>,
+    ignore_title_patterns: Vec<Regex>,
+    include_title_patterns: Vec<Regex>,
 }
 
 impl WindowFilters {
@@ -195,40 +240,96 @@ impl WindowFilters {
         Self {
             ignore_set: ignore_list.iter().map(|s| s.to_lowercase()).collect(),
             include_set: include_list.iter().map(|s| s.to_lowercase()).collect(),
+            ignore_title_patterns: Vec::new(),
+            include_title_patterns: Vec::new(),
         }
     }
 
+    /// Adds case insensitive regexes matched against window titles, on top of the substring
+    /// lists matched against app names and titles.
+    pub fn with_title_patterns(
+        mut self,
+        ignore_patterns: &[String],
+        include_patterns: &[String],
+    ) -> Result<Self, regex::Error> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+                .collect::<Result<Vec<_>, _>>()
+        };
+        self.ignore_title_patterns = compile(ignore_patterns)?;
+        self.include_title_patterns = compile(include_patterns)?;
+        Ok(self)
+    }
+
     // O(n) - we could figure out a better way to do this
     pub fn is_valid(&self, app_name: &str, title: &str) -> bool {
         let app_name_lower = app_name.to_lowercase();
         let title_lower = title.to_lowercase();
 
-        // If include list is empty, we're done
-        if self.include_set.is_empty() {
-            return true;
-        }
-
-        // Check include list
+        // Ignore list wins over the include list, a window like a password manager must never
+        // be captured because it also matched a broad include
         if self
-            .include_set
+            .ignore_set
             .iter()
-            .any(|include| app_name_lower.contains(include) || title_lower.contains(include))
+            .any(|ignore| app_name_lower.contains(ignore) || title_lower.contains(ignore))
+            || self
+                .ignore_title_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(title))
         {
+            return false;
+        }
+
+        // If include list is empty, we're done
+        if self.include_set.is_empty() && self.include_title_patterns.is_empty() {
             return true;
         }
 
-        // Check ignore list first (usually smaller)
-        if !self.ignore_set.is_empty()
-            && self
-                .ignore_set
+        self.include_set
+            .iter()
+            .any(|include| app_name_lower.contains(include) || title_lower.contains(include))
+            || self
+                .include_title_patterns
                 .iter()
-                .any(|ignore| app_name_lower.contains(ignore) || title_lower.contains(ignore))
-        {
-            return false;
-        }
+                .any(|pattern| pattern.is_match(title))
+    }
+}
+
+/// Paints the given regions, in monitor coordinates, black in the monitor frame so
+/// filtered windows don't leak through the full screen capture. `scale` converts monitor
+/// coordinates into image pixels on HiDPI screens.
+pub fn mask_regions(image: &mut DynamicImage, regions: &[WindowBounds], scale: f32) {
+    if regions.is_empty() {
+        return;
+    }
 
-        false
+    let mut rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let to_pixels = |value: i64, max: u32| ((value.max(0) as f32 * scale).ceil() as u32).min(max);
+    for region in regions {
+        let left = to_pixels(region.x as i64, width);
+        let top = to_pixels(region.y as i64, height);
+        let right = to_pixels(region.x as i64 + region.width as i64, width);
+        let bottom = to_pixels(region.y as i64 + region.height as i64, height);
+        for y in top..bottom {
+            for x in left..right {
+                rgba.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
     }
+    *image = DynamicImage::ImageRgba8(rgba);
+}
+
+/// Parts of `frame` not covered by any of `windows`, in the same coordinates as their bounds.
+pub fn uncovered_regions(frame: &WindowBounds, windows: &[CapturedWindow]) -> Vec<WindowBounds> {
+    windows.iter().fold(vec![frame.clone()], |regions, window| {
+        regions
+            .iter()
+            .flat_map(|region| region.subtract(&window.bounds))
+            .collect()
+    })
 }
 
 fn calculate_visible_percentage(
@@ -279,11 +380,26 @@ fn calculate_visible_percentage(
     visible_percentage.clamp(0.0, 1.0)
 }
 
+/// App and title of the window focused now, `None` when none is or windows can't be
+/// listed. Blocks while they are.
+pub fn focused_window() -> Option<(String, String)> {
+    let windows = Window::all().ok()?;
+    let focused = windows
+        .iter()
+        .find(|window| window.is_focused().unwrap_or(false))?;
+    Some((
+        focused.app_name().unwrap_or_default().to_string(),
+        focused.title().unwrap_or_default().to_string(),
+    ))
+}
+
+/// Captures every visible window passing the filters. Also returns the bounds, relative to
+/// the monitor, of on screen windows rejected by `window_filters` so callers can mask them.
 pub async fn capture_all_visible_windows(
     monitor: &SafeMonitor,
     window_filters: &WindowFilters,
     capture_unfocused_windows: bool,
-) -> Result<Vec<CapturedWindow>, Box<dyn Error>> {
+) -> Result<(Vec<CapturedWindow>, Vec<WindowBounds>), Box<dyn Error>> {
     // Get monitor global coordinates from raw Monitor object
     let monitor_id = monitor.id();
     let raw_monitor = tokio::task::spawn_blocking(move || {
@@ -349,6 +465,21 @@ pub async fn capture_all_visible_windows(
         })
         .collect();
 
+    // Windows drawn over others, system overlays and transparent windows hide nothing
+    let occluding_window_indices: HashSet<usize> = all_windows
+        .iter()
+        .enumerate()
+        .filter_map(|(index, window)| {
+            let app_name = window.app_name().unwrap_or_default();
+            let title = window.title().unwrap_or_default();
+            let is_overlay = SKIP_APPS.contains(app_name.as_str())
+                || SKIP_TITLES.contains(title.as_str())
+                || transparent_window_indices.contains(&index);
+            (!is_overlay).then_some(index)
+        })
+        .collect();
+    let mut filtered_regions = Vec::new();
+
     // Get windows and immediately extract the data we need
     let windows_data = all_windows
         .into_iter()
@@ -360,6 +491,28 @@ pub async fn capture_all_visible_windows(
             let is_focused = window.is_focused().unwrap_or(false);
             let process_id = window.pid().unwrap_or(0);
 
+            // Filtered windows are never captured, only remembered to be masked out
+            if !window_filters.is_valid(&app_name, &title) {
+                if let Some(on_screen) = window_bounds[index].intersect(&monitor_bounds) {
+                    // Only mask what is visible, windows are listed front to back so
+                    // everything before this one is drawn on top of it
+                    let mut visible = vec![on_screen];
+                    for above in (0..index).filter(|i| occluding_window_indices.contains(i)) {
+                        visible = visible
+                            .iter()
+                            .flat_map(|region| region.subtract(&window_bounds[above]))
+                            .collect();
+                    }
+                    filtered_regions.extend(visible.into_iter().map(|region| WindowBounds {
+                        x: region.x - monitor_bounds.x,
+                        y: region.y - monitor_bounds.y,
+                        width: region.width,
+                        height: region.height,
+                    }));
+                }
+                return None;
+            }
+
             // Capture image immediately while we have access to the window
             match window.capture_image() {
                 Ok(buffer) => {
@@ -382,7 +535,14 @@ pub async fn capture_all_visible_windows(
                         &monitor_bounds
                     ) as f32;
 
-                    Some((app_name, title, is_focused, buffer, process_id, visible_percentage))
+                    let bounds = WindowBounds {
+                        x: window_bounds[index].x - monitor_bounds.x,
+                        y: window_bounds[index].y - monitor_bounds.y,
+                        width: window_bounds[index].width,
+                        height: window_bounds[index].height,
+                    };
+
+                    Some((app_name, title, is_focused, buffer, process_id, visible_percentage, bounds))
                 },
                 Err(_) => None,
             }
@@ -392,7 +552,9 @@ pub async fn capture_all_visible_windows(
     let mut all_captured_images = Vec::new();
 
     // Process the captured data
-    for (app_name, window_name, is_focused, buffer, process_id, visible_percentage) in windows_data {
+    for (app_name, window_name, is_focused, buffer, process_id, visible_percentage, bounds) in
+        windows_data
+    {
         // Convert to DynamicImage
         let image = DynamicImage::ImageRgba8(
             image::ImageBuffer::from_raw(buffer.width(), buffer.height(), buffer.into_raw())
@@ -402,8 +564,7 @@ pub async fn capture_all_visible_windows(
         // Apply filters
         let is_valid = !SKIP_APPS.contains(app_name.as_str())
             && !SKIP_TITLES.contains(window_name.as_str())
-            && (capture_unfocused_windows || (is_focused && monitor.id() == monitor.id()))
-            && window_filters.is_valid(&app_name, &window_name);
+            && (capture_unfocused_windows || (is_focused && monitor.id() == monitor.id()));
 
         if is_valid {
             all_captured_images.push(CapturedWindow {
@@ -413,9 +574,10 @@ pub async fn capture_all_visible_windows(
                 process_id: process_id as i32,
                 is_focused,
                 visible_percentage,
+                bounds,
             });
         }
     }
 
-    Ok(all_captured_images)
+    Ok((all_captured_images, filtered_regions))
 }
@@ -1,36 +1,44 @@
-#[cfg(target_os = "macos")]
-use crate::apple::perform_ocr_apple;
-use crate::capture_screenshot_by_window::CapturedWindow;
-use crate::capture_screenshot_by_window::WindowFilters;
-use crate::custom_ocr::perform_ocr_custom;
-#[cfg(target_os = "windows")]
-use crate::microsoft::perform_ocr_windows;
+use crate::accessibility::{
+    create_accessibility_extractor, merge_with_ocr, AccessibilityConfig, AccessibilityText,
+    TextSource,
+};
+use crate::adaptive_fps::{AdaptiveFpsConfig, AdaptiveFpsScheduler};
+use crate::app_id::app_id;
+use crate::capture_backend::screen_capturer;
+use crate::capture_pause::is_capture_paused;
+use crate::capture_screenshot_by_window::{mask_regions, CapturedWindow};
+use crate::capture_screenshot_by_window::{WindowBounds, WindowFilters};
+use crate::dark_mode::normalize_for_ocr;
 use crate::monitor::get_monitor_by_id;
-use crate::tesseract::perform_ocr_tesseract;
+use crate::ocr_provider::{create_ocr_provider, OcrProvider, OcrResult};
+use crate::ocr_quality::OcrQualityConfig;
+use crate::partial_ocr::PartialOcrCache;
+use crate::phash::{hamming_distance, perceptual_hash};
+use crate::privacy::PrivacyPolicy;
+use crate::throttle::{throttled_interval, throttled_ocr_provider};
 use crate::utils::OcrEngine;
 use crate::utils::{capture_screenshot, compare_with_previous_image};
+use crate::video_playback::{VideoPlaybackDetector, VideoPlaybackEvent};
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
 use image::codecs::jpeg::JpegEncoder;
 use image::DynamicImage;
 use screenpipe_core::Language;
-use screenpipe_integrations::unstructured_ocr::perform_ocr_cloud;
+use screenpipe_db::{OcrLine, OcrTextLayout, OcrWord};
+use screenpipe_events::send_event;
 use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
 use serde::Serializer;
-use serde_json;
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::{
-    collections::HashMap,
-    time::{Duration, Instant, UNIX_EPOCH},
-};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::mpsc::Sender;
 use tracing::{debug, error, warn};
 
-use crate::browser_utils::create_url_detector;
+use crate::browser_utils::{create_url_detector, tab_title_from_window};
 
 fn serialize_image<S>(image: &Option<DynamicImage>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -111,12 +119,34 @@ pub struct WindowOcrResult {
     pub image: DynamicImage,
     pub window_name: String,
     pub app_name: String,
+    /// Bundle id or executable name of the app, see `app_id::app_id`
+    pub app_id: Option<String>,
     pub text: String,
-    pub text_json: Vec<HashMap<String, String>>, // Change this line
+    pub lines: Vec<OcrLine>,
+    pub words: Vec<OcrWord>,
     pub focused: bool,
     pub confidence: f64,
+    /// `confidence` is below the configured floor, see `OcrQualityConfig`
+    pub low_quality: bool,
     pub browser_url: Option<String>,
+    /// Title of the active tab when the window is a browser
+    pub browser_title: Option<String>,
     pub visible_percentage: f32,
+    /// Perceptual hash of the window image, see `phash::perceptual_hash`
+    pub phash: u64,
+    /// Where the window sits in the monitor frame, in frame pixels
+    pub bounds: WindowBounds,
+    pub source: TextSource,
+}
+
+impl WindowOcrResult {
+    /// Layout persisted alongside the text, see `OcrTextLayout::to_text_json`.
+    pub fn layout(&self) -> OcrTextLayout {
+        OcrTextLayout {
+            lines: self.lines.clone(),
+            words: self.words.clone(),
+        }
+    }
 }
 
 pub struct OcrTaskData {
@@ -127,13 +157,14 @@ pub struct OcrTaskData {
     pub result_tx: Sender<CaptureResult>,
 }
 
-const BROWSER_NAMES: [&str; 9] = [
+pub(crate) const BROWSER_NAMES: [&str; 9] = [
     "chrome", "firefox", "safari", "edge", "brave", "arc", "chromium", "vivaldi", "opera",
 ];
 
 #[derive(Debug)]
 pub enum ContinuousCaptureError {
     MonitorNotFound,
+    UnsupportedOcrEngine(String),
     ErrorCapturingScreenshot(String),
     ErrorProcessingOcr(String),
     ErrorSendingOcrResult(String),
@@ -145,7 +176,8 @@ impl std::fmt::Display for ContinuousCaptureError {
     }
 }
 
-pub async fn continuous_capture( 
+#[allow(clippy::too_many_arguments)]
+pub async fn continuous_capture(
     result_tx: Sender<CaptureResult>,
     interval: Duration,
     ocr_engine: OcrEngine,
@@ -153,11 +185,33 @@ pub async fn continuous_capture(
     window_filters: Arc<WindowFilters>,
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
+    focused_window_only: bool,
+    phash_threshold: Option<u32>,
+    adaptive_fps: Option<AdaptiveFpsConfig>,
+    privacy_policy: PrivacyPolicy,
+    capture_region: Option<WindowBounds>,
+    accessibility: Arc<AccessibilityConfig>,
+    ocr_quality: OcrQualityConfig,
 ) -> Result<(), ContinuousCaptureError> {
     let mut frame_counter: u64 = 0;
     let mut previous_image: Option<DynamicImage> = None;
+    let mut previous_image_hash: Option<u64> = None;
+    let mut partial_ocr_cache = PartialOcrCache::new();
+    let mut video_detector = VideoPlaybackDetector::new();
+    // Without adaptive fps every capture waits the fixed `interval`
+    let mut fps_scheduler = adaptive_fps.map(AdaptiveFpsScheduler::new);
+    // slowed down further while throttled, e.g. on battery
+    let next_interval = |scheduler: &Option<AdaptiveFpsScheduler>| {
+        throttled_interval(
+            scheduler
+                .as_ref()
+                .map_or(interval, AdaptiveFpsScheduler::interval),
+        )
+    };
     let mut max_average: Option<MaxAverageFrame> = None;
     let mut max_avg_value = 0.0;
+    let capturer = screen_capturer();
+    let mut last_frame_sequence: Option<u64> = None;
 
     debug!(
         "continuous_capture: Starting using monitor: {:?}",
@@ -172,23 +226,87 @@ pub async fn continuous_capture(
         }
     };
 
+    // 2. Resolve the OCR backend once, every frame dispatches through it
+    let ocr_provider = create_ocr_provider(&ocr_engine).map_err(|e| {
+        error!("Failed to create ocr provider: {}", e);
+        ContinuousCaptureError::UnsupportedOcrEngine(e.to_string())
+    })?;
+
     loop {
-        // 3. Capture screenshot
-        let capture_result =
-            match capture_screenshot(&monitor, &window_filters, capture_unfocused_windows).await {
-                Ok(result) => result,
-                Err(e) => {
-                    debug!("error capturing screenshot: {}", e);
-                    return Err(ContinuousCaptureError::ErrorCapturingScreenshot(
-                        e.to_string(),
-                    ));
+        // Nothing is captured while paused, e.g. when the computer is idle or locked
+        if is_capture_paused() {
+            frame_counter += 1;
+            tokio::time::sleep(next_interval(&fps_scheduler)).await;
+            continue;
+        }
+
+        // Streaming backends know when the screen didn't change, skip before capturing
+        if let Some(sequence) = capturer.frame_sequence(&monitor) {
+            if last_frame_sequence == Some(sequence) {
+                if let Some(scheduler) = fps_scheduler.as_mut() {
+                    scheduler.observe(0.0);
                 }
-            };
+                frame_counter += 1;
+                tokio::time::sleep(next_interval(&fps_scheduler)).await;
+                continue;
+            }
+            last_frame_sequence = Some(sequence);
+        }
+
+        // 3. Capture screenshot
+        let capture_result = match capture_screenshot(
+            &monitor,
+            &window_filters,
+            capture_unfocused_windows,
+            focused_window_only,
+            capture_region.as_ref(),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                debug!("error capturing screenshot: {}", e);
+                return Err(ContinuousCaptureError::ErrorCapturingScreenshot(
+                    e.to_string(),
+                ));
+            }
+        };
 
         // 4. Process captured image
-        let (image, window_images, image_hash, _capture_duration) = capture_result;
+        let (image, mut window_images, image_hash, _capture_duration, changed_fraction) =
+            capture_result;
+
+        // Nothing of a frame showing a password field or private window is kept
+        if privacy_policy.is_enabled() {
+            if let Some(reason) = privacy_policy.check(&window_images).await {
+                debug!("Skipping frame {}: {}", frame_counter, reason);
+                frame_counter += 1;
+                tokio::time::sleep(next_interval(&fps_scheduler)).await;
+                continue;
+            }
+            privacy_policy.filter_windows(&mut window_images);
+        }
 
-        let should_skip = should_skip_frame(
+        // Near-identical perceptual hashes mean nothing meaningful changed on screen. Only
+        // when asked, a hash of 8x8 cells misses a line of text changing
+        if let (Some(previous_hash), Some(phash_threshold)) = (previous_image_hash, phash_threshold)
+        {
+            let distance = hamming_distance(previous_hash, image_hash);
+            if distance <= phash_threshold {
+                debug!(
+                    "Skipping frame {} due to perceptual hash distance {} <= {}",
+                    frame_counter, distance, phash_threshold
+                );
+                if let Some(scheduler) = fps_scheduler.as_mut() {
+                    scheduler.observe(0.0);
+                }
+                frame_counter += 1;
+                tokio::time::sleep(next_interval(&fps_scheduler)).await;
+                continue;
+            }
+        }
+
+        let (should_skip, difference) = should_skip_frame(
             &previous_image,
             &image,
             &mut max_average,
@@ -196,22 +314,37 @@ pub async fn continuous_capture(
             &mut max_avg_value,
             &window_images,
             image_hash,
+            changed_fraction,
             result_tx.clone(),
         )
         .await;
 
+        if let Some(scheduler) = fps_scheduler.as_mut() {
+            scheduler.observe(difference);
+        }
+
         if should_skip {
             frame_counter += 1;
-            tokio::time::sleep(interval).await;
+            tokio::time::sleep(next_interval(&fps_scheduler)).await;
             continue;
         }
 
         previous_image = Some(image);
+        previous_image_hash = Some(image_hash);
 
         // 5. Process max average frame if available
         if let Some(max_avg_frame) = max_average.take() {
-            if let Err(e) =
-                process_max_average_frame(max_avg_frame, &ocr_engine, languages.clone()).await
+            let ocr_provider = throttled_ocr_provider().unwrap_or_else(|| ocr_provider.clone());
+            if let Err(e) = process_max_average_frame(
+                max_avg_frame,
+                ocr_provider.as_ref(),
+                languages.clone(),
+                &mut partial_ocr_cache,
+                &mut video_detector,
+                &accessibility,
+                ocr_quality,
+            )
+            .await
             {
                 error!("Error processing max average frame: {}", e);
             }
@@ -220,7 +353,7 @@ pub async fn continuous_capture(
         }
 
         frame_counter += 1;
-        tokio::time::sleep(interval).await;
+        tokio::time::sleep(next_interval(&fps_scheduler)).await;
     }
 }
 
@@ -232,17 +365,24 @@ async fn should_skip_frame(
     max_avg_value: &mut f64,
     window_images: &Vec<CapturedWindow>,
     image_hash: u64,
+    changed_fraction: Option<f64>,
     result_tx: Sender<CaptureResult>,
-) -> bool {
-    let current_average = match compare_with_previous_image(
-        previous_image.as_ref(),
-        current_image,
-        max_average,
-        frame_counter,
-        max_avg_value,
-    )
-    .await
-    {
+) -> (bool, f64) {
+    // Dirty regions from the backend already say how much changed, no need for SSIM
+    let compared = match changed_fraction {
+        Some(fraction) => Ok(fraction),
+        None => {
+            compare_with_previous_image(
+                previous_image.as_ref(),
+                current_image,
+                max_average,
+                frame_counter,
+                max_avg_value,
+            )
+            .await
+        }
+    };
+    let current_average = match compared {
         Ok(avg) => avg,
         Err(e) => {
             error!("Error comparing images: {}", e);
@@ -261,7 +401,7 @@ async fn should_skip_frame(
             "Skipping frame {} due to low average difference: {:.3}",
             frame_counter, current_average
         );
-        true
+        (true, current_average)
     } else {
         if current_average > *max_avg_value {
             *max_average = Some(MaxAverageFrame {
@@ -275,14 +415,18 @@ async fn should_skip_frame(
             });
             *max_avg_value = current_average;
         }
-        false
+        (false, current_average)
     }
 }
 
 async fn process_max_average_frame(
     max_avg_frame: MaxAverageFrame,
-    ocr_engine: &OcrEngine,
+    ocr_provider: &dyn OcrProvider,
     languages: Vec<Language>,
+    partial_ocr_cache: &mut PartialOcrCache,
+    video_detector: &mut VideoPlaybackDetector,
+    accessibility: &AccessibilityConfig,
+    ocr_quality: OcrQualityConfig,
 ) -> Result<(), ContinuousCaptureError> {
     let ocr_task_data = OcrTaskData {
         image: max_avg_frame.image,
@@ -292,7 +436,17 @@ async fn process_max_average_frame(
         result_tx: max_avg_frame.result_tx,
     };
 
-    if let Err(e) = process_ocr_task(ocr_task_data, ocr_engine, languages).await {
+    if let Err(e) = process_ocr_task(
+        ocr_task_data,
+        ocr_provider,
+        languages,
+        partial_ocr_cache,
+        video_detector,
+        accessibility,
+        ocr_quality,
+    )
+    .await
+    {
         error!("Error processing OCR task: {}", e);
         return Err(ContinuousCaptureError::ErrorProcessingOcr(e.to_string()));
     }
@@ -310,10 +464,21 @@ pub struct MaxAverageFrame {
     pub average: f64,
 }
 
+/// OCRs every window of the frame. Windows seen in the previous frame only have their
+/// changed regions re-read, see [`PartialOcrCache`]. The focused window of apps opted into
+/// `accessibility` is read from the accessibility tree instead when it exposes text, or
+/// merged with OCR for sources that don't cover everything, see
+/// [`TextSource::merges_with_ocr`]. Text below the `ocr_quality` thresholds is dropped or
+/// flagged, see [`OcrQualityConfig`], and regions playing video are left out, see
+/// [`VideoPlaybackDetector`].
 pub async fn process_ocr_task(
     ocr_task_data: OcrTaskData,
-    ocr_engine: &OcrEngine,
+    ocr_provider: &dyn OcrProvider,
     languages: Vec<Language>,
+    partial_ocr_cache: &mut PartialOcrCache,
+    video_detector: &mut VideoPlaybackDetector,
+    accessibility: &AccessibilityConfig,
+    ocr_quality: OcrQualityConfig,
 ) -> Result<(), ContinuousCaptureError> {
     let OcrTaskData {
         image,
@@ -332,12 +497,21 @@ pub async fn process_ocr_task(
     let mut window_ocr_results = Vec::new();
     let mut total_confidence = 0.0;
     let mut window_count = 0;
+    let mut visible_windows = HashSet::new();
 
     for captured_window in window_images {
+        visible_windows.insert((
+            captured_window.app_name.clone(),
+            captured_window.window_name.clone(),
+        ));
         let ocr_result = process_window_ocr(
             captured_window,
-            ocr_engine,
+            ocr_provider,
             &languages,
+            partial_ocr_cache,
+            video_detector,
+            accessibility,
+            ocr_quality,
             &mut total_confidence,
             &mut window_count,
         )
@@ -346,6 +520,8 @@ pub async fn process_ocr_task(
 
         window_ocr_results.push(ocr_result);
     }
+    partial_ocr_cache.retain_windows(&visible_windows);
+    video_detector.retain_windows(&visible_windows);
 
     // Create and send the result
     let capture_result = CaptureResult {
@@ -367,8 +543,12 @@ pub async fn process_ocr_task(
 
 async fn process_window_ocr(
     captured_window: CapturedWindow,
-    ocr_engine: &OcrEngine,
+    ocr_provider: &dyn OcrProvider,
     languages: &[Language],
+    partial_ocr_cache: &mut PartialOcrCache,
+    video_detector: &mut VideoPlaybackDetector,
+    accessibility: &AccessibilityConfig,
+    ocr_quality: OcrQualityConfig,
     total_confidence: &mut f64,
     window_count: &mut u32,
 ) -> Result<WindowOcrResult, ContinuousCaptureError> {
@@ -381,12 +561,83 @@ async fn process_window_ocr(
         captured_window.process_id,
     )
     .await;
+    let browser_title = tab_title_from_window(&app_name, &captured_window.window_name);
 
-    // Perform OCR based on the selected engine
-    let (window_text, window_json_output, confidence) =
-        perform_ocr_with_engine(ocr_engine, &captured_window.image, languages.to_vec())
-            .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string()))?;
+    let video = video_detector.observe(
+        &app_name,
+        &captured_window.window_name,
+        &captured_window.image,
+    );
+    if let Some(video) = video.as_ref().filter(|video| video.started) {
+        debug!(
+            "video playing in {} - {}, skipping its ocr",
+            app_name, captured_window.window_name
+        );
+        let _ = send_event(
+            "video_playing",
+            VideoPlaybackEvent {
+                app_name: app_name.clone(),
+                window_name: captured_window.window_name.clone(),
+                x: video.region.x,
+                y: video.region.y,
+                width: video.region.width,
+                height: video.region.height,
+            },
+        );
+    }
+
+    let accessibility_text =
+        if captured_window.is_focused && accessibility.is_enabled_for(&app_name) {
+            get_accessibility_text(captured_window.process_id).await
+        } else {
+            None
+        };
+
+    let accessibility_result = accessibility_text.map(|(text, source)| {
+        let result = text.to_ocr_result(
+            captured_window.image.width(),
+            captured_window.image.height(),
+        );
+        (result, source)
+    });
+
+    let (ocr_result, source) = match accessibility_result {
+        Some((result, source)) if !source.merges_with_ocr() => (result, source),
+        accessibility_result => {
+            let ocr_result = match &video {
+                // Nothing but video to read
+                Some(video) if video.covers_window => OcrResult::default(),
+                video => {
+                    // Perform OCR through the selected provider, only on regions that changed.
+                    // Dark windows are inverted first, per window since dark terminals and
+                    // light browsers often share a screen. Video is blanked out, it then
+                    // reads as unchanged too.
+                    let mut ocr_image = normalize_for_ocr(&captured_window.image);
+                    if let Some(video) = video {
+                        mask_regions(ocr_image.to_mut(), std::slice::from_ref(&video.region), 1.0);
+                    }
+                    partial_ocr_cache
+                        .recognize(
+                            &captured_window.app_name,
+                            &captured_window.window_name,
+                            &ocr_image,
+                            ocr_provider,
+                            languages,
+                        )
+                        .await
+                        .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string()))?
+                }
+            };
+            match accessibility_result {
+                Some((result, source)) => (merge_with_ocr(result, ocr_result), source),
+                None => (ocr_result, TextSource::Ocr),
+            }
+        }
+    };
+    let ocr_result = ocr_quality.filter(ocr_result);
+    let confidence = ocr_result.confidence;
+    let low_quality = ocr_quality.is_low_quality(confidence);
+    let phash = perceptual_hash(&captured_window.image);
 
     // Update confidence metrics
     if let Some(conf) = confidence {
@@ -398,15 +649,43 @@ async fn process_window_ocr(
         image: captured_window.image,
         window_name: captured_window.window_name,
         app_name: captured_window.app_name,
-        text: window_text,
-        text_json: parse_json_output(&window_json_output),
+        app_id: app_id(captured_window.process_id),
+        text: ocr_result.text,
+        lines: ocr_result.lines,
+        words: ocr_result.words,
         focused: captured_window.is_focused,
         confidence: confidence.unwrap_or(0.0),
+        low_quality,
         browser_url,
+        browser_title,
         visible_percentage: captured_window.visible_percentage,
+        phash,
+        bounds: captured_window.bounds,
+        source,
     })
 }
 
+async fn get_accessibility_text(process_id: i32) -> Option<(AccessibilityText, TextSource)> {
+    match tokio::task::spawn_blocking(move || {
+        let extractor = create_accessibility_extractor();
+        extractor
+            .extract_text(process_id)
+            .map(|text| text.map(|text| (text, extractor.source())))
+    })
+    .await
+    {
+        Ok(Ok(text)) => text,
+        Ok(Err(e)) => {
+            debug!("Accessibility text unavailable, falling back to OCR: {}", e);
+            None
+        }
+        Err(e) => {
+            error!("Failed to spawn blocking task: {}", e);
+            None
+        }
+    }
+}
+
 async fn get_browser_url_if_needed(
     app_name: &str,
     is_focused: bool,
@@ -436,31 +715,6 @@ async fn get_browser_url_if_needed(
     }
 }
 
-async fn perform_ocr_with_engine(
-    ocr_engine: &OcrEngine,
-    image: &DynamicImage,
-    languages: Vec<Language>,
-) -> Result<(String, String, Option<f64>), ContinuousCaptureError> {
-    match ocr_engine {
-        OcrEngine::Unstructured => perform_ocr_cloud(image, languages)
-            .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string())),
-        OcrEngine::Tesseract => Ok(perform_ocr_tesseract(image, languages)),
-        #[cfg(target_os = "windows")]
-        OcrEngine::WindowsNative => perform_ocr_windows(image)
-            .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string())),
-        #[cfg(target_os = "macos")]
-        OcrEngine::AppleNative => Ok(perform_ocr_apple(image, &languages)),
-        OcrEngine::Custom(config) => perform_ocr_custom(image, languages, config)
-            .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string())),
-        _ => Err(ContinuousCaptureError::ErrorProcessingOcr(
-            "Unsupported OCR engine".to_string(),
-        )),
-    }
-}
-
 async fn send_ocr_result(
     result_tx: &Sender<CaptureResult>,
     capture_result: CaptureResult,
@@ -506,16 +760,6 @@ fn log_ocr_performance(
     );
 }
 
-fn parse_json_output(json_output: &str) -> Vec<HashMap<String, String>> {
-    let parsed_output: Vec<HashMap<String, String>> = serde_json::from_str(json_output)
-        .unwrap_or_else(|e| {
-            error!("Failed to parse JSON output: {}", e);
-            Vec::new()
-        });
-
-    parsed_output
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RealtimeVisionEvent {
     Ocr(WindowOcr),
@@ -531,8 +775,11 @@ pub struct WindowOcr {
     pub image: Option<DynamicImage>,
     pub window_name: String,
     pub app_name: String,
+    #[serde(default)]
+    pub app_id: Option<String>,
     pub text: String,
-    pub text_json: Vec<HashMap<String, String>>, // Change this line
+    pub lines: Vec<OcrLine>,
+    pub words: Vec<OcrWord>,
     pub focused: bool,
     pub confidence: f64,
     #[serde(
@@ -541,6 +788,8 @@ pub struct WindowOcr {
     )]
     pub timestamp: Instant,
     pub browser_url: Option<String>,
+    #[serde(default)]
+    pub browser_title: Option<String>,
     pub visible_percentage: f32,
 }
 
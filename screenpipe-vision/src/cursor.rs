@@ -0,0 +1,250 @@
+use crate::monitor::SafeMonitor;
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::sync::OnceLock;
+
+// Classic arrow, in cursor points with the hotspot at the origin
+const ARROW_OUTLINE: [(f32, f32); 7] = [
+    (0.0, 0.0),
+    (0.0, 16.0),
+    (4.0, 12.5),
+    (7.0, 18.5),
+    (9.5, 17.5),
+    (6.5, 11.5),
+    (11.5, 11.5),
+];
+const ARROW_FILL: [(f32, f32); 7] = [
+    (1.0, 2.5),
+    (1.0, 13.6),
+    (4.4, 10.6),
+    (7.5, 17.0),
+    (8.2, 16.7),
+    (5.0, 10.5),
+    (9.0, 10.5),
+];
+const CLICK_RADIUS: f32 = 14.0;
+const CLICK_THICKNESS: f32 = 3.0;
+const CLICK_COLOR: [u8; 3] = [255, 196, 0];
+
+/// Whether captured frames show the mouse cursor and where it clicked, for recordings meant
+/// to be replayed as tutorials. Both are off by default, the cursor only adds noise to OCR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorOptions {
+    pub show_cursor: bool,
+    /// Draws a ring around the cursor in frames captured while the left button is held
+    pub click_indicator: bool,
+}
+
+static CURSOR_OPTIONS: OnceLock<CursorOptions> = OnceLock::new();
+
+/// Sets the cursor options of every capture in the process. Only the first call has an
+/// effect, it has to come before [`crate::capture_backend::set_screen_capturer`] since
+/// streaming backends bake the cursor setting into their stream.
+pub fn set_cursor_options(options: CursorOptions) {
+    let _ = CURSOR_OPTIONS.set(options);
+}
+
+pub fn cursor_options() -> CursorOptions {
+    CURSOR_OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// Mouse position in global screen coordinates, the same space as monitor and window
+/// bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorState {
+    pub x: f64,
+    pub y: f64,
+    pub pressed: bool,
+}
+
+/// Draws the cursor (unless the capturer already did, see `native_cursor`) and the click
+/// indicator into a frame of `monitor`.
+pub fn overlay_cursor(
+    image: &mut DynamicImage,
+    monitor: &SafeMonitor,
+    options: CursorOptions,
+    native_cursor: bool,
+) {
+    let draw_cursor = options.show_cursor && !native_cursor;
+    if !draw_cursor && !options.click_indicator {
+        return;
+    }
+    let Some(state) = cursor_state() else {
+        return;
+    };
+    let info = monitor.get_info();
+    let x = state.x - info.x as f64;
+    let y = state.y - info.y as f64;
+    if x < 0.0 || y < 0.0 || x >= info.width as f64 || y >= info.height as f64 {
+        return;
+    }
+
+    let scale = image.width() as f32 / info.width.max(1) as f32;
+    let (x, y) = (x as f32 * scale, y as f32 * scale);
+    with_rgba(image, |rgba| {
+        if options.click_indicator && state.pressed {
+            draw_click_indicator(rgba, x, y, scale);
+        }
+        if draw_cursor {
+            self::draw_cursor(rgba, x, y, scale);
+        }
+    });
+}
+
+/// Draws an arrow cursor with its hotspot at (`x`, `y`), in frame pixels. `scale` is the
+/// number of frame pixels per screen point.
+pub fn draw_cursor(image: &mut RgbaImage, x: f32, y: f32, scale: f32) {
+    fill_polygon(image, &ARROW_OUTLINE, x, y, scale, Rgba([0, 0, 0, 255]));
+    fill_polygon(image, &ARROW_FILL, x, y, scale, Rgba([255, 255, 255, 255]));
+}
+
+/// Draws a ring centered on (`x`, `y`), in frame pixels.
+pub fn draw_click_indicator(image: &mut RgbaImage, x: f32, y: f32, scale: f32) {
+    let outer = CLICK_RADIUS * scale;
+    let inner = (CLICK_RADIUS - CLICK_THICKNESS) * scale;
+    for_pixels_around(image, x, y, outer, |pixel, px, py| {
+        let distance = ((px - x).powi(2) + (py - y).powi(2)).sqrt();
+        if distance >= inner && distance <= outer {
+            blend(pixel, CLICK_COLOR, 0.8);
+        }
+    });
+}
+
+fn fill_polygon(
+    image: &mut RgbaImage,
+    points: &[(f32, f32)],
+    x: f32,
+    y: f32,
+    scale: f32,
+    color: Rgba<u8>,
+) {
+    let points: Vec<(f32, f32)> = points
+        .iter()
+        .map(|(px, py)| (x + px * scale, y + py * scale))
+        .collect();
+    let extent = 20.0 * scale;
+    for_pixels_around(
+        image,
+        x + extent / 2.0,
+        y + extent / 2.0,
+        extent,
+        |pixel, px, py| {
+            if contains(&points, px, py) {
+                *pixel = color;
+            }
+        },
+    );
+}
+
+/// Runs `f` on the pixels of the square of half side `radius` around (`x`, `y`), clipped to
+/// the image, with the coordinates of each pixel center.
+fn for_pixels_around(
+    image: &mut RgbaImage,
+    x: f32,
+    y: f32,
+    radius: f32,
+    mut f: impl FnMut(&mut Rgba<u8>, f32, f32),
+) {
+    let (width, height) = image.dimensions();
+    let clip = |value: f32, max: u32| (value.max(0.0) as u32).min(max);
+    for py in clip(y - radius, height)..clip(y + radius + 1.0, height) {
+        for px in clip(x - radius, width)..clip(x + radius + 1.0, width) {
+            f(
+                image.get_pixel_mut(px, py),
+                px as f32 + 0.5,
+                py as f32 + 0.5,
+            );
+        }
+    }
+}
+
+// Even-odd rule
+fn contains(points: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut previous = points[points.len() - 1];
+    for &current in points {
+        let (x1, y1) = previous;
+        let (x2, y2) = current;
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: [u8; 3], alpha: f32) {
+    for (channel, value) in pixel.0.iter_mut().zip(color) {
+        *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+    }
+}
+
+fn with_rgba(image: &mut DynamicImage, f: impl FnOnce(&mut RgbaImage)) {
+    if let Some(rgba) = image.as_mut_rgba8() {
+        f(rgba);
+        return;
+    }
+    let mut rgba = image.to_rgba8();
+    f(&mut rgba);
+    *image = DynamicImage::ImageRgba8(rgba);
+}
+
+#[cfg(target_os = "macos")]
+fn cursor_state() -> Option<CursorState> {
+    use core_foundation::base::{CFRelease, CFTypeRef};
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    // kCGEventSourceStateCombinedSessionState and kCGMouseButtonLeft
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const LEFT_BUTTON: u32 = 0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreate(source: *const c_void) -> *mut c_void;
+        fn CGEventGetLocation(event: *mut c_void) -> CGPoint;
+        fn CGEventSourceButtonState(state_id: i32, button: u32) -> bool;
+    }
+
+    unsafe {
+        let event = CGEventCreate(std::ptr::null());
+        if event.is_null() {
+            return None;
+        }
+        let location = CGEventGetLocation(event);
+        CFRelease(event as CFTypeRef);
+        Some(CursorState {
+            x: location.x,
+            y: location.y,
+            pressed: CGEventSourceButtonState(COMBINED_SESSION_STATE, LEFT_BUTTON),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn cursor_state() -> Option<CursorState> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON};
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    unsafe {
+        let mut point = POINT::default();
+        GetCursorPos(&mut point).ok()?;
+        Some(CursorState {
+            x: point.x as f64,
+            y: point.y as f64,
+            // high bit set while the button is down
+            pressed: GetAsyncKeyState(VK_LBUTTON.0 as i32) < 0,
+        })
+    }
+}
+
+// Wayland hides the global pointer position, portal streams embed the cursor instead
+#[cfg(target_os = "linux")]
+fn cursor_state() -> Option<CursorState> {
+    None
+}
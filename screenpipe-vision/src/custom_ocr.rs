@@ -1,7 +1,9 @@
+use crate::ocr_provider::OcrResult;
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
 use image::DynamicImage;
 use screenpipe_core::Language;
+use screenpipe_db::{OcrLine, OcrWord};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,7 +47,7 @@ pub async fn perform_ocr_custom(
     image: &DynamicImage,
     languages: Vec<Language>,
     config: &CustomOcrConfig,
-) -> Result<(String, String, Option<f64>)> {
+) -> Result<OcrResult> {
     // Convert image to RGB before encoding to JPEG
     let rgb_image = image.to_rgb8();
 
@@ -79,16 +81,21 @@ pub async fn perform_ocr_custom(
     // Handle the response
     let ocr_result: OcrResponse = response.json().await?;
 
-    Ok((
-        ocr_result.text,
-        ocr_result.structured_data.to_string(),
-        Some(ocr_result.confidence),
-    ))
+    Ok(OcrResult {
+        text: ocr_result.text,
+        lines: ocr_result.lines,
+        words: ocr_result.words,
+        confidence: Some(ocr_result.confidence),
+    })
 }
 
+/// `lines` and `words` are optional so servers that only return plain text keep working.
 #[derive(Debug, Deserialize)]
 struct OcrResponse {
     text: String,
-    structured_data: serde_json::Value,
+    #[serde(default)]
+    lines: Vec<OcrLine>,
+    #[serde(default)]
+    words: Vec<OcrWord>,
     confidence: f64,
 }
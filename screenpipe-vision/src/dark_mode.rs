@@ -0,0 +1,50 @@
+use image::DynamicImage;
+use std::borrow::Cow;
+
+// Every 4th pixel of every 4th row is plenty to find the background and keeps this cheap
+// next to OCR
+const SAMPLE_STEP: u32 = 4;
+// Median luminance below which the background is taken as dark. Text covers a small share
+// of a window, so the median lands on the background.
+const DARK_MEDIAN: u8 = 96;
+
+/// Luminance histogram of a subsample of `image`.
+pub fn luminance_histogram(image: &DynamicImage) -> [u32; 256] {
+    let luma = image.to_luma8();
+    let mut histogram = [0u32; 256];
+    for y in (0..luma.height()).step_by(SAMPLE_STEP as usize) {
+        for x in (0..luma.width()).step_by(SAMPLE_STEP as usize) {
+            histogram[luma.get_pixel(x, y)[0] as usize] += 1;
+        }
+    }
+    histogram
+}
+
+/// Whether `image` shows light text on a dark background, e.g. a terminal or an app in
+/// dark mode.
+pub fn is_dark(image: &DynamicImage) -> bool {
+    let histogram = luminance_histogram(image);
+    let total: u32 = histogram.iter().sum();
+    if total == 0 {
+        return false;
+    }
+    let mut seen = 0;
+    for (luminance, count) in histogram.iter().enumerate() {
+        seen += count;
+        if seen * 2 >= total {
+            return (luminance as u8) < DARK_MEDIAN;
+        }
+    }
+    false
+}
+
+/// `image` inverted when it's dark, OCR engines are trained on and read dark text on light
+/// backgrounds far better. Inverting keeps the geometry, so text boxes apply to the original.
+pub fn normalize_for_ocr(image: &DynamicImage) -> Cow<'_, DynamicImage> {
+    if !is_dark(image) {
+        return Cow::Borrowed(image);
+    }
+    let mut inverted = image.clone();
+    inverted.invert();
+    Cow::Owned(inverted)
+}
@@ -0,0 +1,69 @@
+use crate::ocr_provider::OcrResult;
+use crate::onnx_ocr::{ensure_model_file, models_dir, OnnxOcrModels, OnnxOcrPipeline};
+use anyhow::Result;
+use image::DynamicImage;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::info;
+
+// PP-OCRv3 english mobile models, a few MB each. Latin script only, in exchange they run
+// comfortably on a single core without tesseract or any other system install
+const EMBEDDED_DET_URL: &str =
+    "https://huggingface.co/SWHL/RapidOCR/resolve/main/PP-OCRv3/en_PP-OCRv3_det_infer.onnx";
+const EMBEDDED_REC_URL: &str =
+    "https://huggingface.co/SWHL/RapidOCR/resolve/main/PP-OCRv3/en_PP-OCRv3_rec_infer.onnx";
+const EMBEDDED_DICT_URL: &str =
+    "https://raw.githubusercontent.com/PaddlePaddle/PaddleOCR/release/2.7/ppocr/utils/en_dict.txt";
+// sha256 of the files above, same as the paddle engine
+const EMBEDDED_DET_SHA256: &str = "";
+const EMBEDDED_REC_SHA256: &str = "";
+const EMBEDDED_DICT_SHA256: &str = "";
+
+// Lower than the paddle default to keep detection cheap on low-power machines
+const EMBEDDED_DET_MAX_SIDE: u32 = 736;
+
+static EMBEDDED_PIPELINE: OnceCell<Arc<OnnxOcrPipeline>> = OnceCell::const_new();
+
+/// Fetches the embedded engine models into `<models dir>/embedded` on first use.
+pub async fn get_or_download_embedded_models() -> Result<OnnxOcrModels> {
+    let dir = models_dir()?.join("embedded");
+    Ok(OnnxOcrModels {
+        detection: ensure_model_file(
+            &dir,
+            "en_PP-OCRv3_det_infer.onnx",
+            EMBEDDED_DET_URL,
+            EMBEDDED_DET_SHA256,
+        )
+        .await?,
+        recognition: ensure_model_file(
+            &dir,
+            "en_PP-OCRv3_rec_infer.onnx",
+            EMBEDDED_REC_URL,
+            EMBEDDED_REC_SHA256,
+        )
+        .await?,
+        dictionary: ensure_model_file(&dir, "en_dict.txt", EMBEDDED_DICT_URL, EMBEDDED_DICT_SHA256)
+            .await?,
+    })
+}
+
+async fn embedded_pipeline() -> Result<Arc<OnnxOcrPipeline>> {
+    EMBEDDED_PIPELINE
+        .get_or_try_init(|| async {
+            let models = get_or_download_embedded_models().await?;
+            info!("loading embedded ocr models from {:?}", models.detection.parent());
+            let pipeline = tokio::task::spawn_blocking(move || {
+                OnnxOcrPipeline::with_det_max_side(&models, EMBEDDED_DET_MAX_SIDE)
+            })
+            .await??;
+            Ok::<_, anyhow::Error>(Arc::new(pipeline))
+        })
+        .await
+        .cloned()
+}
+
+pub async fn perform_ocr_embedded(image: &DynamicImage) -> Result<OcrResult> {
+    let pipeline = embedded_pipeline().await?;
+    let image = image.clone();
+    tokio::task::spawn_blocking(move || pipeline.recognize(&image)).await?
+}
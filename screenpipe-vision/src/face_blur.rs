@@ -0,0 +1,270 @@
+use crate::core::CaptureResult;
+use crate::onnx_ocr::{create_session, ensure_model_file, models_dir};
+use crate::redaction::{blur_regions, window_to_frame};
+use anyhow::{anyhow, Result};
+use image::{imageops::FilterType, DynamicImage};
+use ndarray::Array4;
+use ort::Session;
+use screenpipe_db::TextBounds;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+// UltraFace RFB-320, ~1MB and a few milliseconds per window on a CPU
+const FACE_MODEL_URL: &str = "https://github.com/onnx/models/raw/main/validated/vision/body_analysis/ultraface/models/version-RFB-320.onnx";
+const FACE_MODEL_FILE: &str = "version-RFB-320.onnx";
+// sha256 of the model above, it is refused without one
+const FACE_MODEL_SHA256: &str = "";
+const INPUT_WIDTH: u32 = 320;
+const INPUT_HEIGHT: u32 = 240;
+const FACE_THRESHOLD: f32 = 0.7;
+const NMS_IOU: f32 = 0.3;
+// boxes hug the face, grow them so hair, ears and chin are covered too
+const FACE_PADDING: f32 = 0.2;
+// a model that failed to download or load is tried again this much later, not every frame
+const MODEL_RETRY: Duration = Duration::from_secs(600);
+
+/// Apps whose windows get faces blurred when no list is configured.
+pub const DEFAULT_FACE_BLUR_APPS: &[&str] = &["zoom", "meet", "teams", "facetime", "webex"];
+
+static FACE_DETECTOR: OnceCell<Arc<FaceDetector>> = OnceCell::const_new();
+static FACE_DETECTOR_FAILED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Which windows faces are blurred in.
+#[derive(Debug, Clone)]
+pub struct FaceBlurConfig {
+    apps: Vec<String>,
+}
+
+impl Default for FaceBlurConfig {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_FACE_BLUR_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+        )
+    }
+}
+
+impl FaceBlurConfig {
+    /// Blurs faces in windows whose app or window name contains one of `apps`, ignoring
+    /// case. Matching the window name catches calls running in a browser, e.g. "Meet - ...".
+    pub fn new(apps: Vec<String>) -> Self {
+        Self {
+            apps: apps
+                .into_iter()
+                .map(|app| app.trim().to_lowercase())
+                .filter(|app| !app.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn apps(&self) -> &[String] {
+        &self.apps
+    }
+
+    pub fn matches(&self, app_name: &str, window_name: &str) -> bool {
+        let app_name = app_name.to_lowercase();
+        let window_name = window_name.to_lowercase();
+        self.apps
+            .iter()
+            .any(|app| app_name.contains(app.as_str()) || window_name.contains(app.as_str()))
+    }
+}
+
+/// Small ONNX face detector, finds faces in video call windows so they can be blurred.
+pub struct FaceDetector {
+    session: Session,
+}
+
+impl FaceDetector {
+    pub fn new(model: &Path) -> Result<Self> {
+        Ok(Self {
+            session: create_session(model)?,
+        })
+    }
+
+    /// Faces in `image`, in image pixels.
+    pub fn detect(&self, image: &DynamicImage) -> Result<Vec<TextBounds>> {
+        if image.width() == 0 || image.height() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let resized = image
+            .resize_exact(INPUT_WIDTH, INPUT_HEIGHT, FilterType::Triangle)
+            .to_rgb8();
+        let mut tensor = Array4::<f32>::zeros((1, 3, INPUT_HEIGHT as usize, INPUT_WIDTH as usize));
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            for c in 0..3 {
+                tensor[[0, c, y as usize, x as usize]] = (pixel[c] as f32 - 127.0) / 128.0;
+            }
+        }
+
+        let outputs = self.session.run(ort::inputs![tensor]?)?;
+        let scores = outputs[0].try_extract_tensor::<f32>()?;
+        let boxes = outputs[1].try_extract_tensor::<f32>()?;
+        if scores.len() / 2 != boxes.len() / 4 {
+            return Err(anyhow!(
+                "unexpected face detection output shapes {:?} and {:?}",
+                scores.shape(),
+                boxes.shape()
+            ));
+        }
+        let scores: Vec<f32> = scores.iter().copied().collect();
+        let boxes: Vec<f32> = boxes.iter().copied().collect();
+
+        Ok(faces_from_outputs(
+            &scores,
+            &boxes,
+            image.width() as f32,
+            image.height() as f32,
+        ))
+    }
+}
+
+/// Turns the detector outputs into padded face boxes in pixels of a `width` x `height`
+/// image. `scores` holds a background and a face score per candidate, `boxes` the
+/// candidate corners normalized to 0..1. Overlapping candidates keep the most confident.
+pub fn faces_from_outputs(
+    scores: &[f32],
+    boxes: &[f32],
+    width: f32,
+    height: f32,
+) -> Vec<TextBounds> {
+    let mut candidates: Vec<(f32, [f32; 4])> = scores
+        .chunks_exact(2)
+        .zip(boxes.chunks_exact(4))
+        .filter(|(score, _)| score[1] >= FACE_THRESHOLD)
+        .map(|(score, corners)| (score[1], [corners[0], corners[1], corners[2], corners[3]]))
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut kept: Vec<[f32; 4]> = Vec::new();
+    for (_, corners) in candidates {
+        if kept.iter().all(|face| iou(face, &corners) < NMS_IOU) {
+            kept.push(corners);
+        }
+    }
+
+    kept.into_iter()
+        .map(|[x1, y1, x2, y2]| {
+            let pad_x = (x2 - x1) * FACE_PADDING;
+            let pad_y = (y2 - y1) * FACE_PADDING;
+            let left = ((x1 - pad_x) * width).max(0.0);
+            let top = ((y1 - pad_y) * height).max(0.0);
+            TextBounds {
+                left,
+                top,
+                width: ((x2 + pad_x) * width).min(width) - left,
+                height: ((y2 + pad_y) * height).min(height) - top,
+            }
+        })
+        .collect()
+}
+
+fn iou(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let overlap_x = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let overlap_y = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let overlap = overlap_x * overlap_y;
+    let area = |c: &[f32; 4]| (c[2] - c[0]).max(0.0) * (c[3] - c[1]).max(0.0);
+    let union = area(a) + area(b) - overlap;
+    if union <= 0.0 {
+        0.0
+    } else {
+        overlap / union
+    }
+}
+
+async fn face_detector() -> Result<Arc<FaceDetector>> {
+    if let Some(detector) = FACE_DETECTOR.get() {
+        return Ok(detector.clone());
+    }
+    let failed_at = *FACE_DETECTOR_FAILED_AT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if failed_at.is_some_and(|failed_at| failed_at.elapsed() < MODEL_RETRY) {
+        return Err(anyhow!("face detection model unavailable"));
+    }
+    let detector = FACE_DETECTOR
+        .get_or_try_init(|| async {
+            let dir = models_dir()?.join("ultraface");
+            let model =
+                ensure_model_file(&dir, FACE_MODEL_FILE, FACE_MODEL_URL, FACE_MODEL_SHA256).await?;
+            info!("loading face detection model from {:?}", model);
+            let detector = tokio::task::spawn_blocking(move || FaceDetector::new(&model)).await??;
+            Ok::<_, anyhow::Error>(Arc::new(detector))
+        })
+        .await
+        .cloned();
+    if let Err(e) = &detector {
+        warn!(
+            "failed to load the face detection model, call windows are blurred whole: {}",
+            e
+        );
+        *FACE_DETECTOR_FAILED_AT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+    detector
+}
+
+/// Blurs the faces in every window of `config`'s apps, in the window images and in the
+/// monitor frame. A window faces can't be looked for in, e.g. while the model is missing,
+/// is blurred whole. Returns how many faces and whole windows were blurred.
+pub async fn blur_faces(result: &mut CaptureResult, config: &FaceBlurConfig) -> usize {
+    let windows: Vec<usize> = result
+        .window_ocr_results
+        .iter()
+        .enumerate()
+        .filter(|(_, window)| config.matches(&window.app_name, &window.window_name))
+        .map(|(index, _)| index)
+        .collect();
+    if windows.is_empty() {
+        return 0;
+    }
+
+    let detector = face_detector().await.ok();
+    let mut blurred = 0;
+    for index in windows {
+        let window = &mut result.window_ocr_results[index];
+        let faces = match &detector {
+            Some(detector) => {
+                let image = window.image.clone();
+                let detector = detector.clone();
+                match tokio::task::spawn_blocking(move || detector.detect(&image)).await {
+                    Ok(Ok(faces)) => Some(faces),
+                    Ok(Err(e)) => {
+                        warn!("failed to detect faces in {}: {}", window.app_name, e);
+                        None
+                    }
+                    Err(e) => {
+                        warn!("face detection of {} panicked: {}", window.app_name, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let faces = faces.unwrap_or_else(|| {
+            vec![TextBounds {
+                left: 0.0,
+                top: 0.0,
+                width: window.image.width() as f32,
+                height: window.image.height() as f32,
+            }]
+        });
+        if faces.is_empty() {
+            continue;
+        }
+
+        debug!("blurring {} faces in {}", faces.len(), window.app_name);
+        blur_regions(&mut window.image, &faces);
+        let frame_faces = window_to_frame(window, &faces);
+        blur_regions(&mut result.image, &frame_faces);
+        blurred += faces.len();
+    }
+    blurred
+}
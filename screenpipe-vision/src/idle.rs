@@ -0,0 +1,94 @@
+use std::time::Duration;
+use tracing::debug;
+use user_idle::UserIdle;
+
+/// How long since the last keyboard or mouse input, `None` when the platform can't tell.
+pub fn idle_time() -> Option<Duration> {
+    match UserIdle::get_time() {
+        Ok(idle) => Some(Duration::from_secs(idle.as_seconds())),
+        Err(e) => {
+            debug!("failed to get idle time: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Whether the lock screen is showing. False when the platform can't tell.
+pub fn is_screen_locked() -> bool {
+    #[cfg(target_os = "macos")]
+    return macos::is_screen_locked();
+
+    #[cfg(target_os = "windows")]
+    return windows::is_screen_locked();
+
+    #[cfg(target_os = "linux")]
+    return linux::is_screen_locked();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    false
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use core_foundation::{
+        base::{CFType, TCFType},
+        boolean::CFBoolean,
+        dictionary::{CFDictionary, CFDictionaryRef},
+        string::CFString,
+    };
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+    }
+
+    pub fn is_screen_locked() -> bool {
+        let session = unsafe { CGSessionCopyCurrentDictionary() };
+        // null when not running in a login session, e.g. over ssh
+        if session.is_null() {
+            return false;
+        }
+        let session: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_create_rule(session) };
+        session
+            .find(&CFString::from_static_string("CGSSessionScreenIsLocked"))
+            .and_then(|locked| locked.downcast::<CFBoolean>())
+            .is_some_and(bool::from)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP,
+    };
+
+    /// The lock screen runs on the secure desktop, which can't be switched to from here.
+    pub fn is_screen_locked() -> bool {
+        unsafe {
+            let Ok(desktop) =
+                OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP)
+            else {
+                return true;
+            };
+            let locked = SwitchDesktop(desktop).is_err();
+            let _ = CloseDesktop(desktop);
+            locked
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::process::Command;
+
+    /// Asks logind, which desktop environments tell when they lock the session.
+    pub fn is_screen_locked() -> bool {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+        Command::new("loginctl")
+            .args(["show-session", &session, "--property=LockedHint", "--value"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "yes")
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Error as E, Result};
+use candle::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::clip::{self, ClipConfig, ClipModel};
+use hf_hub::{api::sync::Api, Repo, RepoType};
+use image::{imageops::FilterType, DynamicImage};
+use std::sync::OnceLock;
+use tokenizers::Tokenizer;
+
+/// Length of the embeddings, matches `frame_embeddings.embedding`.
+pub const IMAGE_EMBEDDING_DIM: usize = 512;
+
+const CLIP_REPO: &str = "openai/clip-vit-base-patch32";
+// the safetensors weights of the repo only exist on this revision
+const CLIP_REVISION: &str = "refs/pr/15";
+// positions of the CLIP text encoder
+const MAX_TEXT_TOKENS: usize = 77;
+
+/// CLIP ViT-B/32. Embeds frames and search text into the same space, so frames can be found
+/// by describing what was on screen, e.g. "bar chart" or "terminal with red error text".
+pub struct ImageEmbeddingModel {
+    model: ClipModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    image_size: usize,
+}
+
+impl ImageEmbeddingModel {
+    /// Loads the model, downloading it from Hugging Face on first use.
+    pub fn new() -> Result<Self> {
+        let device = Device::new_metal(0).unwrap_or(Device::new_cuda(0).unwrap_or(Device::Cpu));
+
+        let api = Api::new()?;
+        let repo = api.repo(Repo::with_revision(
+            CLIP_REPO.to_string(),
+            RepoType::Model,
+            CLIP_REVISION.to_string(),
+        ));
+        let model_path = repo.get("model.safetensors")?;
+        let tokenizer = Tokenizer::from_file(repo.get("tokenizer.json")?).map_err(E::msg)?;
+
+        let config = ClipConfig::vit_base_patch32();
+        let vb =
+            unsafe { VarBuilder::from_mmaped_safetensors(&[model_path], DType::F32, &device)? };
+        let model = ClipModel::new(vb, &config)?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            image_size: config.image_size,
+        })
+    }
+
+    /// Model shared by capture and search, loaded on the first call.
+    pub fn shared() -> Result<&'static ImageEmbeddingModel> {
+        static MODEL: OnceLock<ImageEmbeddingModel> = OnceLock::new();
+        if let Some(model) = MODEL.get() {
+            return Ok(model);
+        }
+        let model = Self::new()?;
+        Ok(MODEL.get_or_init(|| model))
+    }
+
+    /// L2 normalized embedding of `image`. Runs the vision transformer, call it off the
+    /// async runtime.
+    pub fn embed_image(&self, image: &DynamicImage) -> Result<Vec<f32>> {
+        let size = self.image_size as u32;
+        let pixels = image
+            .resize_to_fill(size, size, FilterType::Triangle)
+            .to_rgb8()
+            .into_raw();
+        let pixels = Tensor::from_vec(pixels, (self.image_size, self.image_size, 3), &self.device)?
+            .permute((2, 0, 1))?
+            .to_dtype(DType::F32)?
+            .affine(2. / 255., -1.)?
+            .unsqueeze(0)?;
+
+        let features = self.model.get_image_features(&pixels)?;
+        Ok(clip::div_l2_norm(&features)?.squeeze(0)?.to_vec1()?)
+    }
+
+    /// L2 normalized embedding of a text query, comparable with [`Self::embed_image`].
+    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        let encoding = self.tokenizer.encode(text, true).map_err(E::msg)?;
+        let mut ids = encoding.get_ids().to_vec();
+        // the text features are pooled at the end of text token, keep it when truncating
+        if ids.len() > MAX_TEXT_TOKENS {
+            let end = ids[ids.len() - 1];
+            ids.truncate(MAX_TEXT_TOKENS - 1);
+            ids.push(end);
+        }
+        let input_ids = Tensor::new(ids.as_slice(), &self.device)?.unsqueeze(0)?;
+
+        let features = self.model.get_text_features(&input_ids)?;
+        Ok(clip::div_l2_norm(&features)?.squeeze(0)?.to_vec1()?)
+    }
+}
@@ -1,24 +1,61 @@
+pub mod accessibility;
+pub mod adaptive_fps;
+pub mod app_id;
 #[cfg(target_os = "macos")]
 pub mod apple;
+pub mod barcode;
+pub mod capture_backend;
+pub mod capture_pause;
+pub mod capture_region;
 pub mod core;
+pub mod cursor;
 pub mod custom_ocr;
+pub mod dark_mode;
+pub mod embedded;
+pub mod face_blur;
+pub mod idle;
+pub mod image_embedding;
 #[cfg(target_os = "windows")]
 pub mod microsoft;
 pub mod monitor;
+pub mod ocr_fallback;
+pub mod ocr_provider;
+pub mod ocr_quality;
+pub mod onnx_ocr;
+pub mod paddle;
+pub mod partial_ocr;
+pub mod phash;
+pub mod plugins;
+pub mod privacy;
+pub mod reading_order;
+pub mod redaction;
+pub mod scroll_stitch;
+pub mod table_detection;
 #[cfg(target_os = "macos")]
 pub mod run_ui_monitoring_macos;
 pub mod tesseract;
+pub mod text_direction;
+pub mod throttle;
 pub mod utils;
+pub mod video_playback;
 #[cfg(target_os = "macos")]
 pub use apple::perform_ocr_apple;
+pub use adaptive_fps::{AdaptiveFpsConfig, AdaptiveFpsScheduler};
 pub use core::{continuous_capture, process_ocr_task, CaptureResult, RealtimeVisionEvent, UIFrame};
 // pub use types::CaptureResult;
 pub use utils::OcrEngine;
+pub use ocr_fallback::{FallbackOcrProvider, OcrFallbackConfig};
+pub use ocr_provider::{
+    create_ocr_provider, register_ocr_provider, ConfidenceScale, OcrProvider, OcrResult,
+};
+pub use screenpipe_db::{OcrLine, OcrTextLayout, OcrWord, TextBounds};
 pub mod capture_screenshot_by_window;
 pub use custom_ocr::perform_ocr_custom;
 #[cfg(target_os = "windows")]
 pub use microsoft::perform_ocr_windows;
 #[cfg(target_os = "macos")]
 pub use run_ui_monitoring_macos::run_ui;
-pub use tesseract::perform_ocr_tesseract;
+pub use embedded::perform_ocr_embedded;
+pub use paddle::perform_ocr_paddle;
+pub use tesseract::{perform_ocr_tesseract, validate_tesseract_languages};
 pub mod browser_utils;
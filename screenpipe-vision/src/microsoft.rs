@@ -1,10 +1,15 @@
-use image::{DynamicImage, GenericImageView};
+use crate::ocr_provider::OcrResult;
 use anyhow::Result;
+use image::{DynamicImage, GenericImageView};
+use screenpipe_core::Language;
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
 
 #[cfg(target_os = "windows")]
-pub async fn perform_ocr_windows(image: &DynamicImage) -> Result<(String, String, Option<f64>)> {
+pub async fn perform_ocr_windows(image: &DynamicImage, languages: &[Language]) -> Result<OcrResult> {
     use std::io::Cursor;
     use windows::{
+        core::HSTRING,
+        Globalization::Language as WindowsLanguage,
         Graphics::Imaging::BitmapDecoder,
         Media::Ocr::OcrEngine as WindowsOcrEngine,
         Storage::Streams::{DataWriter, InMemoryRandomAccessStream},
@@ -14,7 +19,7 @@ pub async fn perform_ocr_windows(image: &DynamicImage) -> Result<(String, String
     let (width, height) = image.dimensions();
     if width == 0 || height == 0 {
         // Return an empty result instead of panicking
-        return Ok(("".to_string(), "[]".to_string(), None));
+        return Ok(OcrResult::default());
     }
 
     let mut buffer = Vec::new();
@@ -34,16 +39,56 @@ pub async fn perform_ocr_windows(image: &DynamicImage) -> Result<(String, String
 
     let bitmap = decoder.GetSoftwareBitmapAsync()?.get()?;
 
-    let engine = WindowsOcrEngine::TryCreateFromUserProfileLanguages()?;
+    // Windows OCR recognizes a single language per engine, use the first requested one
+    // that has a language pack installed, otherwise the user profile languages
+    let requested_language = languages.iter().find_map(|language| {
+        let language = WindowsLanguage::CreateLanguage(&HSTRING::from(language.as_lang_code())).ok()?;
+        WindowsOcrEngine::IsLanguageSupported(&language)
+            .unwrap_or(false)
+            .then_some(language)
+    });
+    let engine = match requested_language {
+        Some(language) => WindowsOcrEngine::TryCreateFromLanguage(&language)?,
+        None => WindowsOcrEngine::TryCreateFromUserProfileLanguages()?,
+    };
     let result = engine.RecognizeAsync(&bitmap)?.get()?;
 
     let text = result.Text()?.to_string();
 
-    let json_output = serde_json::json!([{
-        "text": text,
-        "confidence": "1.0" // Windows OCR doesn't provide confidence scores
-    }])
-    .to_string();
+    // Windows OCR doesn't provide confidence scores
+    let mut lines = Vec::new();
+    let mut words = Vec::new();
+    for line in result.Lines()? {
+        let mut line_bbox: Option<TextBounds> = None;
+        for word in line.Words()? {
+            let rect = word.BoundingRect()?;
+            let bbox = TextBounds {
+                left: rect.X,
+                top: rect.Y,
+                width: rect.Width,
+                height: rect.Height,
+            };
+            line_bbox = Some(match line_bbox {
+                Some(current) => current.union(&bbox),
+                None => bbox.clone(),
+            });
+            words.push(OcrWord {
+                text: word.Text()?.to_string(),
+                conf: 1.0,
+                bbox,
+            });
+        }
+        lines.push(OcrLine {
+            text: line.Text()?.to_string(),
+            conf: 1.0,
+            bbox: line_bbox.unwrap_or_default(),
+        });
+    }
 
-    Ok((text, json_output, Some(1.0)))
+    Ok(OcrResult {
+        text,
+        lines,
+        words,
+        confidence: Some(1.0),
+    })
 }
@@ -1,5 +1,6 @@
 use anyhow::{Error, Result};
 use image::DynamicImage;
+use std::str::FromStr;
 use std::sync::Arc;
 use tracing;
 use xcap::Monitor;
@@ -12,6 +13,9 @@ pub struct SafeMonitor {
 
 #[derive(Clone)]
 pub struct MonitorData {
+    /// Position of the top left corner in global screen coordinates
+    pub x: i32,
+    pub y: i32,
     pub width: u32,
     pub height: u32,
     pub name: String,
@@ -22,6 +26,8 @@ impl SafeMonitor {
     pub fn new(monitor: Monitor) -> Self {
         let monitor_id = monitor.id().unwrap();
         let monitor_data = Arc::new(MonitorData {
+            x: monitor.x().unwrap(),
+            y: monitor.y().unwrap(),
             width: monitor.width().unwrap(),
             height: monitor.height().unwrap(),
             name: monitor.name().unwrap().to_string(),
@@ -136,3 +142,123 @@ pub async fn get_monitor_by_id(id: u32) -> Option<SafeMonitor> {
         None
     })
 }
+
+/// Picks monitors by id, position in the monitor list, name or as the primary display.
+///
+/// Parsed from `<id>`, `id:<id>`, `index:<n>`, `name:<text>` (case insensitive substring)
+/// or `primary`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorSelector {
+    Id(u32),
+    Index(usize),
+    Name(String),
+    Primary,
+}
+
+impl MonitorSelector {
+    pub fn matches(&self, index: usize, id: u32, info: &MonitorData) -> bool {
+        match self {
+            MonitorSelector::Id(selected) => *selected == id,
+            MonitorSelector::Index(selected) => *selected == index,
+            MonitorSelector::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            MonitorSelector::Primary => info.is_primary,
+        }
+    }
+}
+
+impl FromStr for MonitorSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("primary") {
+            return Ok(MonitorSelector::Primary);
+        }
+
+        let parse_number = |value: &str| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid monitor selector '{}', expected a number", s))
+        };
+        match s.split_once(':') {
+            Some(("id", id)) => parse_number(id).map(MonitorSelector::Id),
+            Some(("index", index)) => parse_number(index)
+                .map(|index: u32| MonitorSelector::Index(index as usize)),
+            Some(("name", name)) if !name.trim().is_empty() => {
+                Ok(MonitorSelector::Name(name.trim().to_string()))
+            }
+            _ => s.parse().map(MonitorSelector::Id).map_err(|_| {
+                format!(
+                    "invalid monitor selector '{}', expected <id>, id:<id>, index:<n>, \
+                     name:<text> or primary",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+/// Monitors matched by any of `include` and none of `exclude`, in list order. An empty
+/// `include` selects every monitor.
+pub fn select_monitors(
+    monitors: &[SafeMonitor],
+    include: &[MonitorSelector],
+    exclude: &[MonitorSelector],
+) -> Vec<SafeMonitor> {
+    monitors
+        .iter()
+        .enumerate()
+        .filter(|(index, monitor)| {
+            let matches = |selector: &MonitorSelector| {
+                selector.matches(*index, monitor.id(), &monitor.monitor_data)
+            };
+            (include.is_empty() || include.iter().any(matches))
+                && !exclude.iter().any(matches)
+        })
+        .map(|(_, monitor)| monitor.clone())
+        .collect()
+}
+
+/// Capture rate override for the monitors matching `selector`, parsed from
+/// `<selector>=<fps>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorFps {
+    pub selector: MonitorSelector,
+    pub fps: f64,
+}
+
+impl FromStr for MonitorFps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, fps) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("invalid monitor fps '{}', expected <selector>=<fps>", s))?;
+        let fps: f64 = fps
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid fps in '{}'", s))?;
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(format!("fps must be greater than 0 in '{}'", s));
+        }
+
+        Ok(MonitorFps {
+            selector: selector.parse()?,
+            fps,
+        })
+    }
+}
+
+/// Fps for `monitor`, the first matching override wins.
+pub fn monitor_fps(
+    overrides: &[MonitorFps],
+    index: usize,
+    monitor: &SafeMonitor,
+    default_fps: f64,
+) -> f64 {
+    overrides
+        .iter()
+        .find(|o| o.selector.matches(index, monitor.id(), &monitor.monitor_data))
+        .map_or(default_fps, |o| o.fps)
+}
@@ -0,0 +1,95 @@
+use crate::ocr_provider::{create_ocr_provider, OcrFuture, OcrProvider, OcrResult};
+use crate::utils::OcrEngine;
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use screenpipe_core::Language;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Ordered list of engines tried one after the other on the same frame.
+#[derive(Debug, Clone)]
+pub struct OcrFallbackConfig {
+    pub engines: Vec<OcrEngine>,
+    /// Results below this confidence (0.0 - 1.0) are retried on the next engine
+    pub min_confidence: f64,
+}
+
+/// Runs a frame through each engine of the chain until one succeeds with a confidence at
+/// or above the threshold. When none does, the most confident result is kept. Results are
+/// scored from 0 to 1 whichever engine read them.
+pub struct FallbackOcrProvider {
+    providers: Vec<Arc<dyn OcrProvider>>,
+    min_confidence: f64,
+}
+
+impl FallbackOcrProvider {
+    pub fn new(config: &OcrFallbackConfig) -> Result<Self> {
+        if config.engines.is_empty() {
+            return Err(anyhow!("ocr fallback chain needs at least one engine"));
+        }
+
+        let providers = config
+            .engines
+            .iter()
+            .map(create_ocr_provider)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            providers,
+            min_confidence: config.min_confidence,
+        })
+    }
+}
+
+impl OcrProvider for FallbackOcrProvider {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move {
+            let mut best: Option<(f64, OcrResult)> = None;
+            let mut last_error = None;
+
+            for provider in &self.providers {
+                match provider.recognize(image, languages).await {
+                    Ok(result) => {
+                        let result = provider.confidence_scale().to_unit(result);
+                        // engines without a confidence score are trusted
+                        let confidence = result.confidence.unwrap_or(1.0);
+                        if confidence >= self.min_confidence {
+                            return Ok(result);
+                        }
+                        debug!(
+                            "ocr provider {} returned confidence {:.2} below {:.2}, trying next engine",
+                            provider.name(),
+                            confidence,
+                            self.min_confidence
+                        );
+                        let is_better = match &best {
+                            Some((best_confidence, _)) => confidence > *best_confidence,
+                            None => true,
+                        };
+                        if is_better {
+                            best = Some((confidence, result));
+                        }
+                    }
+                    Err(e) => {
+                        debug!("ocr provider {} failed, trying next engine: {}", provider.name(), e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+
+            match (best, last_error) {
+                (Some((_, result)), _) => Ok(result),
+                (None, Some(e)) => Err(e),
+                (None, None) => Ok(OcrResult::default()),
+            }
+        })
+    }
+}
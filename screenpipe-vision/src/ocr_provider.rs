@@ -0,0 +1,352 @@
+#[cfg(target_os = "macos")]
+use crate::apple::perform_ocr_apple;
+use crate::custom_ocr::{perform_ocr_custom, CustomOcrConfig};
+use crate::embedded::perform_ocr_embedded;
+#[cfg(target_os = "windows")]
+use crate::microsoft::perform_ocr_windows;
+use crate::ocr_fallback::FallbackOcrProvider;
+use crate::paddle::perform_ocr_paddle;
+use crate::tesseract::perform_ocr_tesseract;
+use crate::utils::OcrEngine;
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use screenpipe_core::Language;
+use screenpipe_db::{OcrLine, OcrTextLayout, OcrWord, TextBounds};
+use screenpipe_integrations::unstructured_ocr::perform_ocr_cloud;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+/// Output of a single OCR pass over an image.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OcrResult {
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+    pub words: Vec<OcrWord>,
+    pub confidence: Option<f64>,
+}
+
+impl OcrResult {
+    pub fn layout(&self) -> OcrTextLayout {
+        OcrTextLayout {
+            lines: self.lines.clone(),
+            words: self.words.clone(),
+        }
+    }
+}
+
+/// How an engine scores `OcrResult::confidence` and the `conf` of its lines and words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfidenceScale {
+    /// 0 to 1
+    #[default]
+    Unit,
+    /// 0 to 100, like tesseract
+    Percent,
+    /// Lines from 0 to 1, the overall confidence being the sum of theirs, like Apple Vision
+    LineSum,
+}
+
+impl ConfidenceScale {
+    /// `conf` of a line or word, between 0 and 1.
+    pub fn unit(self, conf: f64) -> f64 {
+        match self {
+            ConfidenceScale::Percent => conf / 100.0,
+            ConfidenceScale::Unit | ConfidenceScale::LineSum => conf,
+        }
+        .clamp(0.0, 1.0)
+    }
+
+    /// The overall confidence of `result`, between 0 and 1.
+    pub fn overall(self, result: &OcrResult) -> Option<f64> {
+        let confidence = result.confidence?;
+        Some(match self {
+            ConfidenceScale::LineSum if !result.lines.is_empty() => {
+                (confidence / result.lines.len() as f64).clamp(0.0, 1.0)
+            }
+            scale => scale.unit(confidence),
+        })
+    }
+
+    /// `result` with every confidence between 0 and 1.
+    pub fn to_unit(self, mut result: OcrResult) -> OcrResult {
+        result.confidence = self.overall(&result);
+        for line in &mut result.lines {
+            line.conf = self.unit(line.conf as f64) as f32;
+        }
+        for word in &mut result.words {
+            word.conf = self.unit(word.conf as f64) as f32;
+        }
+        result
+    }
+}
+
+pub type OcrFuture<'a> = Pin<Box<dyn Future<Output = Result<OcrResult>> + Send + 'a>>;
+
+/// A backend able to turn an image into text.
+///
+/// Implement this and call [`register_ocr_provider`] to plug a custom backend into the
+/// capture pipeline, then select it with `OcrEngine::Provider(name)`.
+pub trait OcrProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn recognize<'a>(&'a self, image: &'a DynamicImage, languages: &'a [Language])
+        -> OcrFuture<'a>;
+
+    /// How the results are scored, compared across engines once brought to 0 - 1.
+    fn confidence_scale(&self) -> ConfidenceScale {
+        ConfidenceScale::Unit
+    }
+}
+
+static OCR_PROVIDERS: Lazy<RwLock<HashMap<String, Arc<dyn OcrProvider>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Registers a provider under its `name()`, replacing any provider previously registered
+/// under the same name.
+pub fn register_ocr_provider(provider: Arc<dyn OcrProvider>) {
+    let name = provider.name().to_string();
+    OCR_PROVIDERS
+        .write()
+        .expect("ocr provider registry poisoned")
+        .insert(name, provider);
+}
+
+pub fn unregister_ocr_provider(name: &str) -> Option<Arc<dyn OcrProvider>> {
+    OCR_PROVIDERS
+        .write()
+        .expect("ocr provider registry poisoned")
+        .remove(name)
+}
+
+pub fn get_ocr_provider(name: &str) -> Option<Arc<dyn OcrProvider>> {
+    OCR_PROVIDERS
+        .read()
+        .expect("ocr provider registry poisoned")
+        .get(name)
+        .cloned()
+}
+
+pub fn list_ocr_providers() -> Vec<String> {
+    OCR_PROVIDERS
+        .read()
+        .expect("ocr provider registry poisoned")
+        .keys()
+        .cloned()
+        .collect()
+}
+
+// Factory function
+pub fn create_ocr_provider(engine: &OcrEngine) -> Result<Arc<dyn OcrProvider>> {
+    match engine {
+        OcrEngine::Unstructured => Ok(Arc::new(UnstructuredOcrProvider)),
+        OcrEngine::Tesseract => Ok(Arc::new(TesseractOcrProvider)),
+        #[cfg(target_os = "windows")]
+        OcrEngine::WindowsNative => Ok(Arc::new(WindowsOcrProvider)),
+        #[cfg(target_os = "macos")]
+        OcrEngine::AppleNative => Ok(Arc::new(AppleOcrProvider)),
+        OcrEngine::Custom(config) => Ok(Arc::new(CustomOcrProvider::new(config.clone()))),
+        OcrEngine::Paddle => Ok(Arc::new(PaddleOcrProvider)),
+        OcrEngine::Embedded => Ok(Arc::new(EmbeddedOcrProvider)),
+        OcrEngine::Fallback(config) => Ok(Arc::new(FallbackOcrProvider::new(config)?)),
+        OcrEngine::Provider(name) => get_ocr_provider(name)
+            .ok_or_else(|| anyhow!("no ocr provider registered under '{}'", name)),
+        #[allow(unreachable_patterns)]
+        _ => Err(anyhow!(
+            "ocr engine {:?} is not supported on this platform",
+            engine
+        )),
+    }
+}
+
+pub struct TesseractOcrProvider;
+
+impl OcrProvider for TesseractOcrProvider {
+    fn name(&self) -> &str {
+        "tesseract"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { Ok(perform_ocr_tesseract(image, languages.to_vec())) })
+    }
+
+    fn confidence_scale(&self) -> ConfidenceScale {
+        ConfidenceScale::Percent
+    }
+}
+
+pub struct UnstructuredOcrProvider;
+
+impl OcrProvider for UnstructuredOcrProvider {
+    fn name(&self) -> &str {
+        "unstructured"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move {
+            let (text, json_output, confidence) =
+                perform_ocr_cloud(image, languages.to_vec()).await?;
+            Ok(OcrResult {
+                text,
+                lines: unstructured_lines(&json_output),
+                words: Vec::new(),
+                confidence,
+            })
+        })
+    }
+}
+
+pub struct CustomOcrProvider {
+    config: CustomOcrConfig,
+}
+
+impl CustomOcrProvider {
+    pub fn new(config: CustomOcrConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl OcrProvider for CustomOcrProvider {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { perform_ocr_custom(image, languages.to_vec(), &self.config).await })
+    }
+}
+
+pub struct PaddleOcrProvider;
+
+impl OcrProvider for PaddleOcrProvider {
+    fn name(&self) -> &str {
+        "paddle"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        _languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { perform_ocr_paddle(image).await })
+    }
+}
+
+pub struct EmbeddedOcrProvider;
+
+impl OcrProvider for EmbeddedOcrProvider {
+    fn name(&self) -> &str {
+        "embedded"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        _languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { perform_ocr_embedded(image).await })
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub struct WindowsOcrProvider;
+
+#[cfg(target_os = "windows")]
+impl OcrProvider for WindowsOcrProvider {
+    fn name(&self) -> &str {
+        "windows-native"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { perform_ocr_windows(image, languages).await })
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub struct AppleOcrProvider;
+
+#[cfg(target_os = "macos")]
+impl OcrProvider for AppleOcrProvider {
+    fn name(&self) -> &str {
+        "apple-native"
+    }
+
+    fn recognize<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move { Ok(perform_ocr_apple(image, languages)) })
+    }
+
+    fn confidence_scale(&self) -> ConfidenceScale {
+        ConfidenceScale::LineSum
+    }
+}
+
+/// Unstructured returns one element per detected text region, with the region polygon
+/// under `metadata.coordinates.points`.
+fn unstructured_lines(json_output: &str) -> Vec<OcrLine> {
+    let elements: Vec<serde_json::Value> = serde_json::from_str(json_output).unwrap_or_default();
+    elements
+        .iter()
+        .filter_map(|element| {
+            let text = element.get("text")?.as_str()?.to_string();
+            let points = element
+                .pointer("/metadata/coordinates/points")
+                .and_then(|p| p.as_array())
+                .map(|points| {
+                    points
+                        .iter()
+                        .filter_map(|point| {
+                            let point = point.as_array()?;
+                            Some((point.first()?.as_f64()?, point.get(1)?.as_f64()?))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let bbox = if points.is_empty() {
+                TextBounds::default()
+            } else {
+                let left = points.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+                let top = points.iter().map(|p| p.1).fold(f64::MAX, f64::min);
+                let right = points.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+                let bottom = points.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+                TextBounds {
+                    left: left as f32,
+                    top: top as f32,
+                    width: (right - left) as f32,
+                    height: (bottom - top) as f32,
+                }
+            };
+
+            Some(OcrLine {
+                text,
+                conf: element
+                    .get("confidence")
+                    .and_then(|c| c.as_f64())
+                    .unwrap_or(0.0) as f32,
+                bbox,
+            })
+        })
+        .collect()
+}
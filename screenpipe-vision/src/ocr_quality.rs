@@ -0,0 +1,97 @@
+use crate::ocr_provider::OcrResult;
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
+
+/// Confidence thresholds OCR output has to meet, between 0 and 1. Results are compared
+/// once brought to that scale, see `ConfidenceScale`. Both are off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OcrQualityConfig {
+    /// Words and lines below it are dropped from the result
+    pub min_word_confidence: Option<f64>,
+    /// Windows whose overall confidence is below it are stored as low quality, which search
+    /// can exclude
+    pub min_frame_confidence: Option<f64>,
+}
+
+impl OcrQualityConfig {
+    /// `result` without the words and lines below `min_word_confidence`. The text is rebuilt
+    /// from the remaining lines when anything was dropped, without the dropped words.
+    pub fn filter(&self, result: OcrResult) -> OcrResult {
+        let Some(min_confidence) = self.min_word_confidence else {
+            return result;
+        };
+        let passes = |conf: f32| conf as f64 >= min_confidence;
+
+        let line_count = result.lines.len();
+        let mut lines: Vec<_> = result
+            .lines
+            .into_iter()
+            .filter(|line| passes(line.conf))
+            .collect();
+        let (words, dropped): (Vec<_>, Vec<_>) =
+            result.words.into_iter().partition(|word| passes(word.conf));
+        let text = if lines.len() == line_count && dropped.is_empty() {
+            result.text
+        } else {
+            lines = lines
+                .into_iter()
+                .filter_map(|mut line| {
+                    line.text = without_words(&line, &dropped);
+                    (!line.text.is_empty()).then_some(line)
+                })
+                .collect();
+            lines
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        OcrResult {
+            text,
+            lines,
+            words,
+            confidence: result.confidence,
+        }
+    }
+
+    /// Whether a window with this overall confidence falls below `min_frame_confidence`.
+    /// Results without a confidence are never low quality.
+    pub fn is_low_quality(&self, confidence: Option<f64>) -> bool {
+        match (self.min_frame_confidence, confidence) {
+            (Some(min_confidence), Some(confidence)) => confidence < min_confidence,
+            _ => false,
+        }
+    }
+}
+
+/// Text of `line` without the `dropped` words lying in it, each removing one occurrence.
+fn without_words(line: &OcrLine, dropped: &[OcrWord]) -> String {
+    let mut dropped: Vec<&str> = dropped
+        .iter()
+        .filter(|word| contains_center(&line.bbox, &word.bbox))
+        .map(|word| word.text.as_str())
+        .collect();
+    if dropped.is_empty() {
+        return line.text.clone();
+    }
+    line.text
+        .split_whitespace()
+        .filter(|token| {
+            let Some(i) = dropped.iter().position(|word| word == token) else {
+                return true;
+            };
+            dropped.swap_remove(i);
+            false
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn contains_center(outer: &TextBounds, inner: &TextBounds) -> bool {
+    let x = inner.left + inner.width / 2.0;
+    let y = inner.top + inner.height / 2.0;
+    x >= outer.left
+        && x <= outer.left + outer.width
+        && y >= outer.top
+        && y <= outer.top + outer.height
+}
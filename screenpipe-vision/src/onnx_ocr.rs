@@ -0,0 +1,490 @@
+use crate::ocr_provider::OcrResult;
+use anyhow::{anyhow, Result};
+use image::{imageops::FilterType, DynamicImage, RgbImage};
+use ndarray::Array4;
+use ort::{ExecutionProviderDispatch, GraphOptimizationLevel, Session};
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+// Detection runs on a downscaled copy, both sides have to be multiples of 32 for DBNet
+const DEFAULT_DET_MAX_SIDE: u32 = 960;
+const DET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const DET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+const DET_THRESHOLD: f32 = 0.3;
+const DET_BOX_THRESHOLD: f32 = 0.5;
+const DET_UNCLIP_RATIO: f32 = 1.6;
+const DET_MIN_BOX_SIZE: u32 = 3;
+
+const REC_HEIGHT: u32 = 48;
+const REC_MAX_WIDTH: u32 = 2048;
+const REC_MEAN: [f32; 3] = [0.5, 0.5, 0.5];
+const REC_STD: [f32; 3] = [0.5, 0.5, 0.5];
+
+static MODELS_DIR: OnceLock<PathBuf> = OnceLock::new();
+static USE_GPU: AtomicBool = AtomicBool::new(false);
+
+/// Sets the directory OCR models are downloaded to, usually `<data dir>/models`.
+/// Only the first call has an effect.
+pub fn set_models_dir(dir: PathBuf) {
+    let _ = MODELS_DIR.set(dir);
+}
+
+pub fn models_dir() -> Result<PathBuf> {
+    if let Some(dir) = MODELS_DIR.get() {
+        return Ok(dir.clone());
+    }
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("failed to get home dir"))?;
+    Ok(home.join(".screenpipe").join("models"))
+}
+
+/// Runs the ONNX OCR models on the GPU when screenpipe was built with a GPU execution
+/// provider (the `cuda`, `directml` or `metal` feature), falling back to the CPU when the
+/// provider can't be initialized. Sessions are created on first use, so call this before.
+pub fn set_use_gpu(enabled: bool) {
+    USE_GPU.store(enabled, Ordering::Relaxed);
+}
+
+fn sha256_of_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the path of `filename` inside `dir`, downloading it from `url` first if it is
+/// not there yet. Files are checked against `sha256`, models are run as they are so a file
+/// whose content doesn't match is never used: an existing one is downloaded again and a
+/// download that doesn't match is refused. Downloads go to a temporary file that is renamed
+/// once verified, so an interrupted download is never mistaken for a model.
+pub async fn ensure_model_file(
+    dir: &Path,
+    filename: &str,
+    url: &str,
+    sha256: &str,
+) -> Result<PathBuf> {
+    if sha256.is_empty() {
+        return Err(anyhow!("no checksum is pinned for {}", filename));
+    }
+    let path = dir.join(filename);
+    // the checksum of a verified file, hashing the models on every start takes a while
+    let checksum_path = dir.join(format!("{}.sha256", filename));
+    if path.exists() {
+        let verified = tokio::fs::read_to_string(&checksum_path)
+            .await
+            .is_ok_and(|checksum| checksum.trim() == sha256);
+        if verified {
+            debug!("found verified ocr model at: {:?}", path);
+            return Ok(path);
+        }
+        let model = path.clone();
+        let actual = tokio::task::spawn_blocking(move || sha256_of_file(&model)).await??;
+        if actual == sha256 {
+            tokio::fs::write(&checksum_path, &actual).await?;
+            return Ok(path);
+        }
+        warn!(
+            "ocr model {:?} doesn't match its checksum, downloading it again",
+            path
+        );
+        tokio::fs::remove_file(&path).await?;
+    }
+
+    tokio::fs::create_dir_all(dir).await?;
+    info!("downloading {} from {}", filename, url);
+    let mut response = reqwest::get(url).await?.error_for_status()?;
+    let partial_path = dir.join(format!("{}.part", filename));
+    let mut partial = tokio::fs::File::create(&partial_path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        partial.write_all(&chunk).await?;
+        size += chunk.len();
+    }
+    partial.flush().await?;
+    drop(partial);
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != sha256 {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(anyhow!(
+            "downloaded {} has sha256 {}, expected {}",
+            filename,
+            actual,
+            sha256
+        ));
+    }
+    tokio::fs::rename(&partial_path, &path).await?;
+    tokio::fs::write(&checksum_path, &actual).await?;
+    info!("saved {} ({} bytes) to {:?}", filename, size, path);
+
+    Ok(path)
+}
+
+/// Files making up a DBNet detection + CRNN recognition model pair.
+#[derive(Debug, Clone)]
+pub struct OnnxOcrModels {
+    pub detection: PathBuf,
+    pub recognition: PathBuf,
+    /// One character per line, in the order of the recognition model's output classes
+    pub dictionary: PathBuf,
+}
+
+/// Two stage OCR: a DBNet model finds text regions, a CRNN model reads each region.
+///
+/// Regions are treated as axis aligned boxes, which holds for upright screen content.
+pub struct OnnxOcrPipeline {
+    detector: Session,
+    recognizer: Session,
+    charset: Vec<String>,
+    det_max_side: u32,
+}
+
+impl OnnxOcrPipeline {
+    pub fn new(models: &OnnxOcrModels) -> Result<Self> {
+        Self::with_det_max_side(models, DEFAULT_DET_MAX_SIDE)
+    }
+
+    /// Like [`OnnxOcrPipeline::new`] but caps the longest side of the detection input,
+    /// smaller values trade small text recall for speed.
+    pub fn with_det_max_side(models: &OnnxOcrModels, det_max_side: u32) -> Result<Self> {
+        let dictionary = std::fs::read_to_string(&models.dictionary)?;
+        let mut charset: Vec<String> = dictionary
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        // class 0 is the ctc blank, the space character is appended after the dictionary
+        charset.push(" ".to_string());
+
+        Ok(Self {
+            detector: create_session(&models.detection)?,
+            recognizer: create_session(&models.recognition)?,
+            charset,
+            det_max_side,
+        })
+    }
+
+    pub fn recognize(&self, image: &DynamicImage) -> Result<OcrResult> {
+        let image = image.to_rgb8();
+        if image.width() == 0 || image.height() == 0 {
+            return Ok(OcrResult::default());
+        }
+
+        let mut lines = Vec::new();
+        let mut words = Vec::new();
+        for bbox in self.detect(&image)? {
+            if let Some((line, line_words)) = self.recognize_region(&image, &bbox)? {
+                lines.push(line);
+                words.extend(line_words);
+            }
+        }
+
+        let text = lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let confidence = if lines.is_empty() {
+            0.0
+        } else {
+            lines.iter().map(|line| line.conf as f64).sum::<f64>() / lines.len() as f64
+        };
+
+        Ok(OcrResult {
+            text,
+            lines,
+            words,
+            confidence: Some(confidence),
+        })
+    }
+
+    fn detect(&self, image: &RgbImage) -> Result<Vec<TextBounds>> {
+        let (width, height) = image.dimensions();
+        let scale = (self.det_max_side as f32 / width.max(height) as f32).min(1.0);
+        let det_width = round_to_multiple_of_32(width as f32 * scale);
+        let det_height = round_to_multiple_of_32(height as f32 * scale);
+        let resized = image::imageops::resize(image, det_width, det_height, FilterType::Triangle);
+
+        let outputs = self
+            .detector
+            .run(ort::inputs![to_tensor(&resized, DET_MEAN, DET_STD)]?)?;
+        let probabilities = outputs[0].try_extract_tensor::<f32>()?;
+        let shape = probabilities.shape().to_vec();
+        if shape.len() != 4 {
+            return Err(anyhow!("unexpected detection output shape {:?}", shape));
+        }
+        let (map_height, map_width) = (shape[2], shape[3]);
+        let map: Vec<f32> = probabilities.iter().copied().collect();
+
+        let scale_x = width as f32 / map_width as f32;
+        let scale_y = height as f32 / map_height as f32;
+        let mut boxes: Vec<TextBounds> = text_regions(&map, map_width, map_height)
+            .into_iter()
+            .map(|region| TextBounds {
+                left: (region.left * scale_x).max(0.0),
+                top: (region.top * scale_y).max(0.0),
+                width: (region.width * scale_x).min(width as f32 - region.left * scale_x),
+                height: (region.height * scale_y).min(height as f32 - region.top * scale_y),
+            })
+            .filter(|bbox| bbox.width >= 1.0 && bbox.height >= 1.0)
+            .collect();
+
+        sort_reading_order(&mut boxes);
+        Ok(boxes)
+    }
+
+    fn recognize_region(
+        &self,
+        image: &RgbImage,
+        bbox: &TextBounds,
+    ) -> Result<Option<(OcrLine, Vec<OcrWord>)>> {
+        let (x, y) = (bbox.left as u32, bbox.top as u32);
+        let (crop_width, crop_height) = (
+            (bbox.width as u32).min(image.width() - x).max(1),
+            (bbox.height as u32).min(image.height() - y).max(1),
+        );
+        let crop = image::imageops::crop_imm(image, x, y, crop_width, crop_height).to_image();
+        let rec_width = ((crop_width as f32 * REC_HEIGHT as f32 / crop_height as f32).ceil()
+            as u32)
+            .clamp(REC_HEIGHT / 3, REC_MAX_WIDTH);
+        let resized = image::imageops::resize(&crop, rec_width, REC_HEIGHT, FilterType::Triangle);
+
+        let outputs = self
+            .recognizer
+            .run(ort::inputs![to_tensor(&resized, REC_MEAN, REC_STD)]?)?;
+        let probabilities = outputs[0].try_extract_tensor::<f32>()?;
+        let shape = probabilities.shape().to_vec();
+        if shape.len() != 3 {
+            return Err(anyhow!("unexpected recognition output shape {:?}", shape));
+        }
+        let (steps, classes) = (shape[1], shape[2]);
+        let scores: Vec<f32> = probabilities.iter().copied().collect();
+
+        let chars = ctc_greedy_decode(&scores, steps, classes, &self.charset);
+        let text = chars.iter().map(|c| c.text.as_str()).collect::<String>();
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+
+        let line = OcrLine {
+            text: text.to_string(),
+            conf: chars.iter().map(|c| c.conf).sum::<f32>() / chars.len() as f32,
+            bbox: bbox.clone(),
+        };
+        let words = split_words(&chars, bbox, steps);
+
+        Ok(Some((line, words)))
+    }
+}
+
+pub(crate) fn create_session(path: &Path) -> Result<Session> {
+    if USE_GPU.load(Ordering::Relaxed) {
+        let providers = gpu_execution_providers();
+        if providers.is_empty() {
+            warn!("gpu ocr requested but no gpu execution provider was compiled in, using cpu");
+        }
+        for provider in providers {
+            match build_session(path, vec![provider.error_on_failure()]) {
+                Ok(session) => return Ok(session),
+                Err(e) => warn!("failed to create gpu ocr session for {:?}: {}", path, e),
+            }
+        }
+    }
+
+    build_session(path, Vec::new())
+}
+
+fn gpu_execution_providers() -> Vec<ExecutionProviderDispatch> {
+    #[allow(unused_mut)]
+    let mut providers = Vec::new();
+    #[cfg(feature = "cuda")]
+    providers.push(ort::CUDAExecutionProvider::default().build());
+    #[cfg(feature = "directml")]
+    providers.push(ort::DirectMLExecutionProvider::default().build());
+    #[cfg(feature = "metal")]
+    providers.push(ort::CoreMLExecutionProvider::default().build());
+    providers
+}
+
+fn build_session(path: &Path, providers: Vec<ExecutionProviderDispatch>) -> Result<Session> {
+    let session = Session::builder()?
+        .with_execution_providers(providers)?
+        .with_optimization_level(GraphOptimizationLevel::Level3)?
+        .with_intra_threads(1)?
+        .with_inter_threads(1)?
+        .commit_from_file(path)?;
+    Ok(session)
+}
+
+fn round_to_multiple_of_32(value: f32) -> u32 {
+    (((value / 32.0).round() as u32) * 32).max(32)
+}
+
+fn to_tensor(image: &RgbImage, mean: [f32; 3], std: [f32; 3]) -> Array4<f32> {
+    let (width, height) = image.dimensions();
+    let mut tensor = Array4::<f32>::zeros((1, 3, height as usize, width as usize));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        for c in 0..3 {
+            // the paddle models are trained on BGR input
+            let value = pixel[2 - c] as f32 / 255.0;
+            tensor[[0, c, y as usize, x as usize]] = (value - mean[c]) / std[c];
+        }
+    }
+    tensor
+}
+
+/// Connected regions of the detection probability map, in map coordinates.
+fn text_regions(map: &[f32], width: usize, height: usize) -> Vec<TextBounds> {
+    let mut visited = vec![false; map.len()];
+    let mut regions = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for start in 0..map.len() {
+        if visited[start] || map[start] < DET_THRESHOLD {
+            continue;
+        }
+
+        visited[start] = true;
+        queue.push_back(start);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+        let (mut score_sum, mut count) = (0.0, 0usize);
+
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = (index % width, index / width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+            score_sum += map[index];
+            count += 1;
+
+            let mut neighbours = Vec::with_capacity(4);
+            if x > 0 {
+                neighbours.push(index - 1);
+            }
+            if x + 1 < width {
+                neighbours.push(index + 1);
+            }
+            if y > 0 {
+                neighbours.push(index - width);
+            }
+            if y + 1 < height {
+                neighbours.push(index + width);
+            }
+            for neighbour in neighbours {
+                if !visited[neighbour] && map[neighbour] >= DET_THRESHOLD {
+                    visited[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        let region_width = (max_x - min_x + 1) as f32;
+        let region_height = (max_y - min_y + 1) as f32;
+        if score_sum / (count as f32) < DET_BOX_THRESHOLD
+            || region_width.min(region_height) < DET_MIN_BOX_SIZE as f32
+        {
+            continue;
+        }
+
+        // DBNet predicts shrunk text kernels, grow them back by area * ratio / perimeter
+        let distance =
+            region_width * region_height * DET_UNCLIP_RATIO / (2.0 * (region_width + region_height));
+        let left = (min_x as f32 - distance).max(0.0);
+        let top = (min_y as f32 - distance).max(0.0);
+        regions.push(TextBounds {
+            left,
+            top,
+            width: (max_x as f32 + 1.0 + distance).min(width as f32) - left,
+            height: (max_y as f32 + 1.0 + distance).min(height as f32) - top,
+        });
+    }
+
+    regions
+}
+
+/// Top to bottom, then left to right for boxes sitting on the same line.
+fn sort_reading_order(boxes: &mut [TextBounds]) {
+    boxes.sort_by(|a, b| a.top.total_cmp(&b.top).then(a.left.total_cmp(&b.left)));
+    for i in 1..boxes.len() {
+        let mut j = i;
+        while j > 0 {
+            let (previous, current) = (&boxes[j - 1], &boxes[j]);
+            let same_line =
+                (current.top - previous.top).abs() < previous.height.min(current.height) / 2.0;
+            if same_line && current.left < previous.left {
+                boxes.swap(j - 1, j);
+                j -= 1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+struct DecodedChar {
+    text: String,
+    conf: f32,
+    step: usize,
+}
+
+fn ctc_greedy_decode(
+    scores: &[f32],
+    steps: usize,
+    classes: usize,
+    charset: &[String],
+) -> Vec<DecodedChar> {
+    let mut chars = Vec::new();
+    let mut previous = 0;
+    for step in 0..steps {
+        let row = &scores[step * classes..(step + 1) * classes];
+        let (class, conf) = row
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::MIN), |best, (i, p)| if p > best.1 { (i, p) } else { best });
+        if class != 0 && class != previous {
+            if let Some(text) = charset.get(class - 1) {
+                chars.push(DecodedChar {
+                    text: text.clone(),
+                    conf,
+                    step,
+                });
+            }
+        }
+        previous = class;
+    }
+    chars
+}
+
+/// Word boxes are placed from the ctc time steps of their first and last character,
+/// each step covering an equal slice of the line width.
+fn split_words(chars: &[DecodedChar], line_bbox: &TextBounds, steps: usize) -> Vec<OcrWord> {
+    let step_width = line_bbox.width / steps as f32;
+    chars
+        .split(|c| c.text.trim().is_empty())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let first = word.first().map(|c| c.step).unwrap_or(0);
+            let last = word.last().map(|c| c.step).unwrap_or(first);
+            OcrWord {
+                text: word.iter().map(|c| c.text.as_str()).collect(),
+                conf: word.iter().map(|c| c.conf).sum::<f32>() / word.len() as f32,
+                bbox: TextBounds {
+                    left: line_bbox.left + first as f32 * step_width,
+                    top: line_bbox.top,
+                    width: (last - first + 1) as f32 * step_width,
+                    height: line_bbox.height,
+                },
+            }
+        })
+        .collect()
+}
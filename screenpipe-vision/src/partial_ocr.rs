@@ -0,0 +1,228 @@
+use crate::ocr_provider::{OcrProvider, OcrResult};
+use anyhow::Result;
+use image::{DynamicImage, GrayImage};
+use screenpipe_core::Language;
+use screenpipe_db::TextBounds;
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+// Frames are compared tile by tile, a tile is dirty when any pixel moved past the threshold
+const TILE_SIZE: u32 = 32;
+const PIXEL_DIFF_THRESHOLD: u8 = 24;
+// Extra rows OCRed around a dirty band so text cut by the tile grid is read whole
+const BAND_PADDING: u32 = 8;
+// Past this share of the window changing, a single full pass is cheaper than many crops
+const MAX_DIRTY_RATIO: f32 = 0.5;
+// Full pass every so often so drift from merged results can't accumulate
+const FULL_OCR_INTERVAL: u32 = 30;
+
+struct CachedWindowOcr {
+    luma: GrayImage,
+    result: OcrResult,
+    partial_passes: u32,
+}
+
+/// OCR results of the previous frame per window, used to only re-read what changed.
+#[derive(Default)]
+pub struct PartialOcrCache {
+    windows: HashMap<(String, String), CachedWindowOcr>,
+}
+
+impl PartialOcrCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops windows that are no longer on screen.
+    pub fn retain_windows(&mut self, visible: &HashSet<(String, String)>) {
+        self.windows.retain(|key, _| visible.contains(key));
+    }
+
+    /// OCRs `image`, reusing cached text for regions unchanged since the last frame of the
+    /// same window. Falls back to a full pass when nothing is cached, the engine returned no
+    /// line layout to merge with, or most of the window changed. Confidences are brought
+    /// from the engine's scale to 0 - 1, so passes of any engine can be merged.
+    pub async fn recognize(
+        &mut self,
+        app_name: &str,
+        window_name: &str,
+        image: &DynamicImage,
+        ocr_provider: &dyn OcrProvider,
+        languages: &[Language],
+    ) -> Result<OcrResult> {
+        let key = (app_name.to_string(), window_name.to_string());
+        let luma = image.to_luma8();
+
+        if let Some(cached) = self.windows.get_mut(&key) {
+            if cached.partial_passes < FULL_OCR_INTERVAL && !cached.result.lines.is_empty() {
+                if let Some(bands) = changed_bands(&cached.luma, &luma) {
+                    if bands.is_empty() {
+                        cached.partial_passes += 1;
+                        return Ok(cached.result.clone());
+                    }
+
+                    let dirty_height: u32 = bands.iter().map(|band| band.height as u32).sum();
+                    if (dirty_height as f32) <= luma.height() as f32 * MAX_DIRTY_RATIO {
+                        debug!(
+                            "partial ocr for {} - {}: {} changed bands, {} of {} rows",
+                            app_name,
+                            window_name,
+                            bands.len(),
+                            dirty_height,
+                            luma.height()
+                        );
+                        let result = recognize_bands(
+                            &cached.result,
+                            &bands,
+                            image,
+                            ocr_provider,
+                            languages,
+                        )
+                        .await?;
+                        cached.luma = luma;
+                        cached.result = result.clone();
+                        cached.partial_passes += 1;
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+
+        let result = ocr_provider
+            .confidence_scale()
+            .to_unit(ocr_provider.recognize(image, languages).await?);
+        self.windows.insert(
+            key,
+            CachedWindowOcr {
+                luma,
+                result: result.clone(),
+                partial_passes: 0,
+            },
+        );
+        Ok(result)
+    }
+}
+
+/// Full width horizontal bands covering every changed tile, or `None` when the frames
+/// can't be compared. An empty list means nothing changed.
+pub fn changed_bands(previous: &GrayImage, current: &GrayImage) -> Option<Vec<TextBounds>> {
+    if previous.dimensions() != current.dimensions() {
+        return None;
+    }
+
+    let (width, height) = current.dimensions();
+    let tile_rows = height.div_ceil(TILE_SIZE);
+    let tile_columns = width.div_ceil(TILE_SIZE);
+
+    let mut dirty_rows = vec![false; tile_rows as usize];
+    for tile_y in 0..tile_rows {
+        'tiles: for tile_x in 0..tile_columns {
+            for y in tile_y * TILE_SIZE..((tile_y + 1) * TILE_SIZE).min(height) {
+                for x in tile_x * TILE_SIZE..((tile_x + 1) * TILE_SIZE).min(width) {
+                    let a = previous.get_pixel(x, y)[0];
+                    let b = current.get_pixel(x, y)[0];
+                    if a.abs_diff(b) > PIXEL_DIFF_THRESHOLD {
+                        dirty_rows[tile_y as usize] = true;
+                        break 'tiles;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut bands: Vec<TextBounds> = Vec::new();
+    for (row, _) in dirty_rows.iter().enumerate().filter(|(_, dirty)| **dirty) {
+        let top = (row as u32 * TILE_SIZE).saturating_sub(BAND_PADDING);
+        let bottom = ((row as u32 + 1) * TILE_SIZE + BAND_PADDING).min(height);
+        match bands.last_mut() {
+            Some(band) if band.top + band.height >= top as f32 => {
+                band.height = bottom as f32 - band.top;
+            }
+            _ => bands.push(TextBounds {
+                left: 0.0,
+                top: top as f32,
+                width: width as f32,
+                height: (bottom - top) as f32,
+            }),
+        }
+    }
+
+    Some(bands)
+}
+
+async fn recognize_bands(
+    cached: &OcrResult,
+    bands: &[TextBounds],
+    image: &DynamicImage,
+    ocr_provider: &dyn OcrProvider,
+    languages: &[Language],
+) -> Result<OcrResult> {
+    let overlaps_band = |bbox: &TextBounds| {
+        bands
+            .iter()
+            .any(|band| bbox.top < band.top + band.height && bbox.top + bbox.height > band.top)
+    };
+
+    let mut lines: Vec<_> = cached
+        .lines
+        .iter()
+        .filter(|line| !overlaps_band(&line.bbox))
+        .cloned()
+        .collect();
+    let mut words: Vec<_> = cached
+        .words
+        .iter()
+        .filter(|word| !overlaps_band(&word.bbox))
+        .cloned()
+        .collect();
+    let mut confidences: Vec<f64> = cached.confidence.into_iter().collect();
+
+    for band in bands {
+        let crop = image.crop_imm(0, band.top as u32, band.width as u32, band.height as u32);
+        let band_result = ocr_provider
+            .confidence_scale()
+            .to_unit(ocr_provider.recognize(&crop, languages).await?);
+        confidences.extend(band_result.confidence);
+
+        // band results are relative to the crop, move them back into window coordinates
+        lines.extend(band_result.lines.into_iter().map(|mut line| {
+            line.bbox.top += band.top;
+            line
+        }));
+        words.extend(band_result.words.into_iter().map(|mut word| {
+            word.bbox.top += band.top;
+            word
+        }));
+    }
+
+    lines.sort_by(|a, b| {
+        a.bbox
+            .top
+            .total_cmp(&b.bbox.top)
+            .then(a.bbox.left.total_cmp(&b.bbox.left))
+    });
+    words.sort_by(|a, b| {
+        a.bbox
+            .top
+            .total_cmp(&b.bbox.top)
+            .then(a.bbox.left.total_cmp(&b.bbox.left))
+    });
+
+    let text = lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let confidence = if confidences.is_empty() {
+        None
+    } else {
+        Some(confidences.iter().sum::<f64>() / confidences.len() as f64)
+    };
+
+    Ok(OcrResult {
+        text,
+        lines,
+        words,
+        confidence,
+    })
+}
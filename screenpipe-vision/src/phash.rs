@@ -0,0 +1,27 @@
+use image::{imageops::FilterType, DynamicImage};
+
+// 9x8 grid, comparing horizontal neighbours gives 8 bits per row, 64 bits total
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+
+/// Difference hash of an image: robust to small pixel changes (a ticking clock, a blinking
+/// cursor) that defeat a byte hash, while layout or content changes flip bits.
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(DHASH_WIDTH, DHASH_HEIGHT, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..DHASH_HEIGHT {
+        for x in 0..DHASH_WIDTH - 1 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
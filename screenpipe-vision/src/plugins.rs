@@ -0,0 +1,79 @@
+use crate::core::{CaptureResult, WindowOcrResult};
+use image::{DynamicImage, RgbaImage};
+use screenpipe_core::wasm_plugins::{Frame, Window};
+use screenpipe_core::{PluginCapability, WasmPlugins};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn plugin_window(window: &WindowOcrResult) -> Window {
+    Window {
+        app_name: window.app_name.clone(),
+        window_name: window.window_name.clone(),
+        browser_url: window.browser_url.clone(),
+        focused: window.focused,
+        text: window.text.clone(),
+    }
+}
+
+fn set_text(window: &mut WindowOcrResult, text: String) {
+    if text != window.text {
+        // the layout no longer matches, the text is stored without one
+        window.text = text;
+        window.lines.clear();
+        window.words.clear();
+    }
+}
+
+/// Passes a captured frame of `monitor_id` through the frame post-processors and OCR
+/// filters of `plugins`, after it is redacted and before it is stored. False when a plugin
+/// dropped the frame. Plugins may take their whole fuel, it is called in a blocking task.
+pub fn apply_plugins(result: &mut CaptureResult, monitor_id: u32, plugins: &WasmPlugins) -> bool {
+    if plugins.wants(PluginCapability::Frames) {
+        let frame = Frame {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_millis() as i64)
+                .unwrap_or_default(),
+            monitor_id,
+            width: result.image.width(),
+            height: result.image.height(),
+            windows: result
+                .window_ocr_results
+                .iter()
+                .map(plugin_window)
+                .collect(),
+        };
+        let (width, height) = (frame.width, frame.height);
+        let image = &result.image;
+        let Some(processed) = plugins.process_frame(frame, || image.to_rgba8().into_raw()) else {
+            return false;
+        };
+        if let Some(image) = processed
+            .pixels
+            .and_then(|pixels| RgbaImage::from_raw(width, height, pixels))
+        {
+            result.image = DynamicImage::ImageRgba8(image);
+        }
+        let windows = std::mem::take(&mut result.window_ocr_results);
+        result.window_ocr_results = windows
+            .into_iter()
+            .zip(processed.texts)
+            .filter_map(|(mut window, text)| {
+                set_text(&mut window, text?);
+                Some(window)
+            })
+            .collect();
+    }
+
+    if plugins.wants(PluginCapability::Ocr) {
+        let windows = std::mem::take(&mut result.window_ocr_results);
+        result.window_ocr_results = windows
+            .into_iter()
+            .filter_map(|mut window| {
+                let text = plugins.filter_ocr(plugin_window(&window))?;
+                set_text(&mut window, text);
+                Some(window)
+            })
+            .collect();
+    }
+    true
+}
@@ -0,0 +1,57 @@
+use accessibility_sys::{
+    kAXErrorSuccess, kAXFocusedUIElementAttribute, kAXSecureTextFieldSubrole,
+    kAXSubroleAttribute, AXUIElementCopyAttributeValue, AXUIElementCreateApplication,
+    AXUIElementRef,
+};
+use anyhow::Result;
+use core_foundation::{
+    base::{CFRelease, CFTypeRef, TCFType},
+    string::CFString,
+};
+
+use super::SecureInputDetector;
+
+pub struct MacOSSecureInputDetector;
+
+impl MacOSSecureInputDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SecureInputDetector for MacOSSecureInputDetector {
+    fn is_secure_input_focused(&self, process_id: i32) -> Result<bool> {
+        unsafe {
+            let app_element = AXUIElementCreateApplication(process_id);
+
+            let mut focused_element: CFTypeRef = std::ptr::null_mut();
+            let status = AXUIElementCopyAttributeValue(
+                app_element,
+                CFString::from_static_string(kAXFocusedUIElementAttribute).as_concrete_TypeRef(),
+                &mut focused_element,
+            );
+
+            if status != kAXErrorSuccess {
+                CFRelease(app_element as CFTypeRef);
+                return Ok(false);
+            }
+
+            // Password fields in native and web views report the secure text field subrole
+            let mut subrole: CFTypeRef = std::ptr::null_mut();
+            let status = AXUIElementCopyAttributeValue(
+                focused_element as AXUIElementRef,
+                CFString::from_static_string(kAXSubroleAttribute).as_concrete_TypeRef(),
+                &mut subrole,
+            );
+
+            let is_secure = status == kAXErrorSuccess
+                && CFString::wrap_under_create_rule(subrole as _).to_string()
+                    == kAXSecureTextFieldSubrole;
+
+            CFRelease(focused_element);
+            CFRelease(app_element as CFTypeRef);
+
+            Ok(is_secure)
+        }
+    }
+}
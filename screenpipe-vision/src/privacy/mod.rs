@@ -0,0 +1,150 @@
+use crate::capture_screenshot_by_window::{CapturedWindow, WindowBounds};
+use crate::core::BROWSER_NAMES;
+use anyhow::Result;
+use std::fmt;
+use tracing::error;
+
+// Title markers browsers add to private windows, matched lowercase
+const PRIVATE_BROWSING_MARKERS: [&str; 4] =
+    ["incognito", "private browsing", "inprivate", "private window"];
+
+// Trait definition
+pub trait SecureInputDetector {
+    /// Whether the focused element of the given process is a password / secure text field.
+    fn is_secure_input_focused(&self, process_id: i32) -> Result<bool>;
+}
+
+// Factory function
+pub fn create_secure_input_detector() -> Box<dyn SecureInputDetector> {
+    #[cfg(target_os = "macos")]
+    return Box::new(MacOSSecureInputDetector::new());
+
+    #[cfg(target_os = "windows")]
+    return Box::new(WindowsSecureInputDetector::new());
+
+    #[cfg(target_os = "linux")]
+    return Box::new(UnsupportedSecureInputDetector::new());
+}
+
+// Unsupported implementation
+pub struct UnsupportedSecureInputDetector;
+
+impl UnsupportedSecureInputDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SecureInputDetector for UnsupportedSecureInputDetector {
+    fn is_secure_input_focused(&self, _process_id: i32) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Best effort, browsers only expose private mode through their window title.
+pub fn is_private_browsing_window(app_name: &str, window_name: &str) -> bool {
+    let app_name = app_name.to_lowercase();
+    let window_name = window_name.to_lowercase();
+    BROWSER_NAMES
+        .iter()
+        .any(|browser| app_name.contains(browser))
+        && PRIVATE_BROWSING_MARKERS
+            .iter()
+            .any(|marker| window_name.contains(marker))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PauseReason {
+    SecureInput { app_name: String },
+    PrivateBrowsing { app_name: String },
+}
+
+impl fmt::Display for PauseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PauseReason::SecureInput { app_name } => {
+                write!(f, "password field focused in {}", app_name)
+            }
+            PauseReason::PrivateBrowsing { app_name } => {
+                write!(f, "private browsing window focused in {}", app_name)
+            }
+        }
+    }
+}
+
+/// When capture stops for privacy. Checked on every frame before anything is hashed,
+/// OCRed or stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrivacyPolicy {
+    pub pause_on_secure_input: bool,
+    pub pause_on_private_browsing: bool,
+}
+
+impl PrivacyPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.pause_on_secure_input || self.pause_on_private_browsing
+    }
+
+    /// Why the frame must be dropped, if the focused window is sensitive.
+    pub async fn check(&self, windows: &[CapturedWindow]) -> Option<PauseReason> {
+        let focused = windows.iter().find(|window| window.is_focused)?;
+
+        if self.pause_on_private_browsing
+            && is_private_browsing_window(&focused.app_name, &focused.window_name)
+        {
+            return Some(PauseReason::PrivateBrowsing {
+                app_name: focused.app_name.clone(),
+            });
+        }
+
+        if self.pause_on_secure_input {
+            let process_id = focused.process_id;
+            let secure = tokio::task::spawn_blocking(move || {
+                create_secure_input_detector().is_secure_input_focused(process_id)
+            })
+            .await;
+            match secure {
+                Ok(Ok(true)) => {
+                    return Some(PauseReason::SecureInput {
+                        app_name: focused.app_name.clone(),
+                    })
+                }
+                Ok(Ok(false)) | Ok(Err(_)) => {}
+                Err(e) => error!("Failed to spawn blocking task: {}", e),
+            }
+        }
+
+        None
+    }
+
+    /// Drops unfocused private browsing windows, they are not OCRed or stored.
+    pub fn filter_windows(&self, windows: &mut Vec<CapturedWindow>) {
+        if self.pause_on_private_browsing {
+            windows.retain(|window| {
+                !is_private_browsing_window(&window.app_name, &window.window_name)
+            });
+        }
+    }
+
+    /// Bounds of unfocused private browsing windows, blacked out of recorded video frames.
+    pub fn private_regions(&self, windows: &[CapturedWindow]) -> Vec<WindowBounds> {
+        if !self.pause_on_private_browsing {
+            return Vec::new();
+        }
+        windows
+            .iter()
+            .filter(|window| is_private_browsing_window(&window.app_name, &window.window_name))
+            .map(|window| window.bounds.clone())
+            .collect()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::MacOSSecureInputDetector;
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use windows::WindowsSecureInputDetector;
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use uiautomation::UIAutomation;
+
+use super::SecureInputDetector;
+
+pub struct WindowsSecureInputDetector;
+
+impl WindowsSecureInputDetector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SecureInputDetector for WindowsSecureInputDetector {
+    fn is_secure_input_focused(&self, process_id: i32) -> Result<bool> {
+        let automation = UIAutomation::new()?;
+        let focused = automation.get_focused_element()?;
+
+        // UI Automation reports the globally focused element, only trust it for this process
+        if focused.get_process_id()? as i32 != process_id {
+            return Ok(false);
+        }
+
+        Ok(focused.is_password()?)
+    }
+}
@@ -0,0 +1,168 @@
+use crate::text_direction::{page_direction, to_left_to_right};
+use screenpipe_db::{OcrLine, TextBounds};
+
+// Lines overlapping horizontally and at most this many line heights apart belong to the
+// same block
+const BLOCK_GAP: f32 = 1.5;
+// Vertical gap, in line heights, that starts a new paragraph inside a block. Line spacing
+// is well under half a height.
+const PARAGRAPH_GAP: f32 = 0.8;
+
+struct Block<'a> {
+    lines: Vec<&'a OcrLine>,
+    bbox: TextBounds,
+}
+
+/// Text of `lines` in visual reading order: lines are grouped into blocks, blocks are read
+/// column by column (recursive XY-cut) and each block top to bottom. Lines are separated by
+/// a line break, paragraphs and blocks by a blank line. Right to left and vertical pages
+/// are read in their own direction, see [`page_direction`].
+pub fn reading_order_text(lines: &[OcrLine]) -> String {
+    let direction = page_direction(lines);
+    let lines: Vec<OcrLine> = lines
+        .iter()
+        .filter(|line| !line.text.trim().is_empty())
+        .map(|line| OcrLine {
+            bbox: to_left_to_right(&line.bbox, direction),
+            ..line.clone()
+        })
+        .collect();
+    let lines: Vec<&OcrLine> = lines.iter().collect();
+    let mut ordered = Vec::new();
+    order_blocks(blocks(&lines), &mut ordered);
+    ordered
+        .iter()
+        .map(block_text)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn right(bbox: &TextBounds) -> f32 {
+    bbox.left + bbox.width
+}
+
+fn bottom(bbox: &TextBounds) -> f32 {
+    bbox.top + bbox.height
+}
+
+fn same_block(a: &TextBounds, b: &TextBounds) -> bool {
+    let overlaps = a.left < right(b) && b.left < right(a);
+    let gap = (a.top.max(b.top) - bottom(a).min(bottom(b))).max(0.0);
+    overlaps && gap <= a.height.max(b.height) * BLOCK_GAP
+}
+
+/// Lines merged into blocks, transitively: two lines share a block when a chain of close,
+/// horizontally overlapping lines connects them.
+fn blocks<'a>(lines: &[&'a OcrLine]) -> Vec<Block<'a>> {
+    let mut parent: Vec<usize> = (0..lines.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..lines.len() {
+        for j in i + 1..lines.len() {
+            if same_block(&lines[i].bbox, &lines[j].bbox) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut blocks: Vec<(usize, Block<'a>)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let id = root(&mut parent, i);
+        match blocks.iter_mut().find(|(block_id, _)| *block_id == id) {
+            Some((_, block)) => {
+                block.lines.push(line);
+                block.bbox = block.bbox.union(&line.bbox);
+            }
+            None => blocks.push((
+                id,
+                Block {
+                    lines: vec![line],
+                    bbox: line.bbox.clone(),
+                },
+            )),
+        }
+    }
+    blocks.into_iter().map(|(_, block)| block).collect()
+}
+
+/// Appends `blocks` to `ordered` in reading order. Columns are split off first so a page of
+/// side by side columns is read one column after the other, then rows, e.g. a full width
+/// heading above the columns.
+fn order_blocks<'a>(mut blocks: Vec<Block<'a>>, ordered: &mut Vec<Block<'a>>) {
+    if blocks.len() <= 1 {
+        ordered.extend(blocks);
+        return;
+    }
+
+    blocks.sort_by(|a, b| a.bbox.left.total_cmp(&b.bbox.left));
+    if let Some(split) = cut(&blocks, |bbox| bbox.left, right) {
+        let rest = blocks.split_off(split);
+        order_blocks(blocks, ordered);
+        order_blocks(rest, ordered);
+        return;
+    }
+
+    blocks.sort_by(|a, b| a.bbox.top.total_cmp(&b.bbox.top));
+    if let Some(split) = cut(&blocks, |bbox| bbox.top, bottom) {
+        let rest = blocks.split_off(split);
+        order_blocks(blocks, ordered);
+        order_blocks(rest, ordered);
+        return;
+    }
+
+    // overlapping in both directions, fall back to top to bottom
+    ordered.extend(blocks);
+}
+
+/// First index at which `blocks`, sorted by `start`, can be split with a gap no block
+/// crosses.
+fn cut(
+    blocks: &[Block],
+    start: impl Fn(&TextBounds) -> f32,
+    end: impl Fn(&TextBounds) -> f32,
+) -> Option<usize> {
+    let mut reach = end(&blocks[0].bbox);
+    for (i, block) in blocks.iter().enumerate().skip(1) {
+        if start(&block.bbox) >= reach {
+            return Some(i);
+        }
+        reach = reach.max(end(&block.bbox));
+    }
+    None
+}
+
+fn block_text(block: &Block) -> String {
+    let mut lines = block.lines.clone();
+    lines.sort_by(|a, b| {
+        a.bbox
+            .top
+            .total_cmp(&b.bbox.top)
+            .then(a.bbox.left.total_cmp(&b.bbox.left))
+    });
+
+    let mut text = String::new();
+    let mut previous: Option<&OcrLine> = None;
+    for line in lines {
+        if let Some(previous) = previous {
+            let height = previous.bbox.height.max(line.bbox.height);
+            let gap = line.bbox.top - bottom(&previous.bbox);
+            let same_row = line.bbox.top < previous.bbox.top + previous.bbox.height / 2.0;
+            text.push_str(if same_row {
+                " "
+            } else if gap > height * PARAGRAPH_GAP {
+                "\n\n"
+            } else {
+                "\n"
+            });
+        }
+        text.push_str(line.text.trim());
+        previous = Some(line);
+    }
+    text
+}
@@ -0,0 +1,121 @@
+use crate::core::{CaptureResult, WindowOcrResult};
+use image::{imageops, DynamicImage};
+use screenpipe_core::RedactionPolicy;
+use screenpipe_db::TextBounds;
+
+// Strong enough that digits and addresses are unreadable, not just softened
+const BLUR_SIGMA: f32 = 12.0;
+const REDACTED_WORD: &str = "[REDACTED]";
+
+/// Scrubs sensitive text from every window of a captured frame before it reaches the
+/// database or a video chunk. When the policy asks for it the matched text is also blurred
+/// in the window images and in the monitor frame.
+pub fn redact_capture_result(result: &mut CaptureResult, policy: &RedactionPolicy) {
+    if policy.is_empty() {
+        return;
+    }
+
+    for window in &mut result.window_ocr_results {
+        let regions = redact_window_text(window, policy);
+        if !policy.blur_images() || regions.is_empty() {
+            continue;
+        }
+
+        blur_regions(&mut window.image, &regions);
+        blur_regions(&mut result.image, &window_to_frame(window, &regions));
+    }
+}
+
+/// Moves regions of a window image into frame pixels.
+pub fn window_to_frame(window: &WindowOcrResult, regions: &[TextBounds]) -> Vec<TextBounds> {
+    let scale_x = window.bounds.width as f32 / window.image.width().max(1) as f32;
+    let scale_y = window.bounds.height as f32 / window.image.height().max(1) as f32;
+    regions
+        .iter()
+        .map(|region| TextBounds {
+            left: window.bounds.x as f32 + region.left * scale_x,
+            top: window.bounds.y as f32 + region.top * scale_y,
+            width: region.width * scale_x,
+            height: region.height * scale_y,
+        })
+        .collect()
+}
+
+/// Redacts the text, lines and words of a window and returns where the matched text sits
+/// in the window image. Engines that report no layout only get their text redacted.
+pub fn redact_window_text(
+    window: &mut WindowOcrResult,
+    policy: &RedactionPolicy,
+) -> Vec<TextBounds> {
+    window.text = policy.redact(&window.text);
+
+    let mut regions = Vec::new();
+    for line in &mut window.lines {
+        let matches = policy.find_matches(&line.text);
+        if matches.is_empty() {
+            continue;
+        }
+
+        // engines only give a box per line, so place each match by its share of characters
+        // and pad by one character to cover glyphs wider than average
+        let chars = line.text.chars().count().max(1) as f32;
+        let char_width = line.bbox.width / chars;
+        for found in &matches {
+            let start = line.text[..found.range.start].chars().count() as f32;
+            let end = line.text[..found.range.end].chars().count() as f32;
+            let left = (line.bbox.left + start * char_width - char_width).max(line.bbox.left);
+            let right = (line.bbox.left + end * char_width + char_width)
+                .min(line.bbox.left + line.bbox.width);
+            regions.push(TextBounds {
+                left,
+                top: line.bbox.top,
+                width: right - left,
+                height: line.bbox.height,
+            });
+        }
+        line.text = policy.redact(&line.text);
+    }
+
+    let mut word_regions = Vec::new();
+    for word in &mut window.words {
+        let redacted = policy.redact(&word.text);
+        if redacted != word.text {
+            word.text = redacted;
+            word_regions.push(word.bbox.clone());
+        } else if regions.iter().any(|region| contains_center(region, &word.bbox)) {
+            // part of a match spanning several words, e.g. a spaced card number
+            word.text = REDACTED_WORD.to_string();
+        }
+    }
+    regions.extend(word_regions);
+
+    regions
+}
+
+/// Blurs each region, in image pixels, in place.
+pub fn blur_regions(image: &mut DynamicImage, regions: &[TextBounds]) {
+    let (width, height) = (image.width(), image.height());
+    for region in regions {
+        let left = (region.left.max(0.0).floor() as u32).min(width);
+        let top = (region.top.max(0.0).floor() as u32).min(height);
+        let right = ((region.left + region.width).max(0.0).ceil() as u32).min(width);
+        let bottom = ((region.top + region.height).max(0.0).ceil() as u32).min(height);
+        if right <= left || bottom <= top {
+            continue;
+        }
+
+        let blurred = image
+            .crop_imm(left, top, right - left, bottom - top)
+            .blur(BLUR_SIGMA);
+        imageops::overlay(image, &blurred, left as i64, top as i64);
+    }
+}
+
+fn contains_center(region: &TextBounds, bbox: &TextBounds) -> bool {
+    let center_x = bbox.left + bbox.width / 2.0;
+    let center_y = bbox.top + bbox.height / 2.0;
+    center_x >= region.left
+        && center_x <= region.left + region.width
+        && center_y >= region.top
+        && center_y <= region.top + region.height
+}
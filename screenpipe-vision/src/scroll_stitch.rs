@@ -0,0 +1,405 @@
+use image::{DynamicImage, RgbaImage};
+
+// Each row is summarised by the mean brightness of this many column bins
+const ROW_BINS: usize = 32;
+// Mean bin difference at which two rows still count as the same content
+const ROW_TOLERANCE: u32 = 4;
+// Share of overlapping rows that must match for a scroll to be accepted
+const MIN_MATCH_FRACTION: f64 = 0.9;
+// Blank rows match anything, this many rows with content have to overlap
+const MIN_TEXTURED_ROWS: usize = 8;
+// Bin spread below which a row is considered blank
+const TEXTURE_SPREAD: u8 = 16;
+// Consecutive captures must overlap by at least this share of the window height
+const MIN_OVERLAP_FRACTION: f64 = 0.25;
+// Overlaps are compared on every n-th row
+const ROW_STEP: usize = 2;
+// Rows with content near the top of a capture, offsets are only compared in full where
+// all of them match, this many rows apart
+const ANCHOR_ROWS: usize = 3;
+const ANCHOR_SPACING: usize = 7;
+// Offsets compared in full at most, content repeating more often can't be told apart
+const MAX_CANDIDATE_OFFSETS: usize = 16;
+
+pub const DEFAULT_MAX_DOCUMENT_HEIGHT: u32 = 20_000;
+// Pixels held by the open documents of a stitcher, the least recently used ones are
+// completed past it
+pub const DEFAULT_MAX_OPEN_BYTES: usize = 256 * 1024 * 1024;
+const MAX_OPEN_DOCUMENTS: usize = 16;
+// RGBA
+const BYTES_PER_PIXEL: usize = 4;
+
+/// Per row brightness profile of a window capture, cheap to compare at every offset.
+#[derive(Debug, Clone)]
+pub struct RowSignature {
+    width: u32,
+    rows: Vec<[u8; ROW_BINS]>,
+    textured: Vec<bool>,
+}
+
+impl RowSignature {
+    pub fn new(image: &DynamicImage) -> Self {
+        let luma = image.to_luma8();
+        let (width, height) = luma.dimensions();
+        let mut rows = Vec::with_capacity(height as usize);
+        let mut textured = Vec::with_capacity(height as usize);
+        let bins: Vec<usize> = (0..width as usize)
+            .map(|x| (x * ROW_BINS) / width.max(1) as usize)
+            .collect();
+
+        for pixels in luma.as_raw().chunks_exact(width.max(1) as usize) {
+            let mut sums = [0u32; ROW_BINS];
+            let mut counts = [0u32; ROW_BINS];
+            for (&bin, &pixel) in bins.iter().zip(pixels) {
+                sums[bin] += pixel as u32;
+                counts[bin] += 1;
+            }
+            let mut row = [0u8; ROW_BINS];
+            for bin in 0..ROW_BINS {
+                row[bin] = (sums[bin] / counts[bin].max(1)) as u8;
+            }
+            let spread = row.iter().max().unwrap_or(&0) - row.iter().min().unwrap_or(&0);
+            rows.push(row);
+            textured.push(spread >= TEXTURE_SPREAD);
+        }
+
+        Self {
+            width,
+            rows,
+            textured,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+fn rows_match(a: &[u8; ROW_BINS], b: &[u8; ROW_BINS]) -> bool {
+    let difference: u32 = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a, b)| a.abs_diff(*b) as u32)
+        .sum();
+    difference <= ROW_TOLERANCE * ROW_BINS as u32
+}
+
+/// Share of rows matching when `current` shows `previous` scrolled down by `offset` rows,
+/// `None` when too little content overlaps to tell.
+fn match_fraction(previous: &RowSignature, current: &RowSignature, offset: usize) -> Option<f64> {
+    let overlap = previous.height() - offset;
+    let allowed_mismatches = ((overlap / ROW_STEP) as f64 * (1.0 - MIN_MATCH_FRACTION)) as usize;
+    let mut compared = 0;
+    let mut matched = 0;
+    let mut textured = 0;
+
+    for y in (0..overlap).step_by(ROW_STEP) {
+        compared += 1;
+        if previous.textured[y + offset] {
+            textured += 1;
+        }
+        if rows_match(&previous.rows[y + offset], &current.rows[y]) {
+            matched += 1;
+        } else if compared - matched > allowed_mismatches {
+            return None;
+        }
+    }
+
+    if compared == 0 || textured < MIN_TEXTURED_ROWS {
+        return None;
+    }
+    Some(matched as f64 / compared as f64)
+}
+
+/// How many rows `current` scrolled down past `previous`, `Some(0)` when the content didn't
+/// move and `None` when the two captures don't look like the same scrolled page.
+pub fn detect_scroll_offset(previous: &RowSignature, current: &RowSignature) -> Option<u32> {
+    if previous.width != current.width || previous.height() != current.height() {
+        return None;
+    }
+    let height = previous.height();
+    let min_overlap = ((height as f64 * MIN_OVERLAP_FRACTION).ceil() as usize).max(1);
+    if height < min_overlap {
+        return None;
+    }
+
+    // comparing every offset in full takes height² row comparisons, a few anchor rows are
+    // checked at each offset first
+    let mut anchors: Vec<usize> = Vec::with_capacity(ANCHOR_ROWS);
+    for y in 0..height {
+        let spaced = anchors
+            .last()
+            .is_none_or(|anchor| y >= anchor + ANCHOR_SPACING);
+        if current.textured[y] && spaced {
+            anchors.push(y);
+            if anchors.len() == ANCHOR_ROWS {
+                break;
+            }
+        }
+    }
+    let &first_anchor = anchors.first()?;
+    let candidates: Vec<usize> = (0..=(height - min_overlap))
+        .filter(|offset| first_anchor + offset < height)
+        .filter(|offset| {
+            anchors
+                .iter()
+                .filter(|anchor| *anchor + offset < height)
+                .all(|anchor| rows_match(&previous.rows[anchor + offset], &current.rows[*anchor]))
+        })
+        .take(MAX_CANDIDATE_OFFSETS + 1)
+        .collect();
+    if candidates.len() > MAX_CANDIDATE_OFFSETS {
+        return None;
+    }
+
+    let mut best: Option<(usize, f64)> = None;
+    for offset in candidates {
+        if let Some(fraction) = match_fraction(previous, current, offset) {
+            if fraction >= MIN_MATCH_FRACTION && best.map_or(true, |(_, best)| fraction > best) {
+                best = Some((offset, fraction));
+            }
+        }
+    }
+    best.map(|(offset, _)| offset as u32)
+}
+
+fn normalize_line(line: &str) -> String {
+    line.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Appends the lines of `addition` that `document` doesn't already end with. OCR of the
+/// line cut off at the top of a capture is unreliable, so the first line of `addition` may
+/// be ignored when looking for the overlap.
+pub fn merge_scrolled_text(document: &str, addition: &str) -> String {
+    let document_lines: Vec<&str> = document.lines().filter(|l| !l.trim().is_empty()).collect();
+    let addition_lines: Vec<&str> = addition.lines().filter(|l| !l.trim().is_empty()).collect();
+    let document_keys: Vec<String> = document_lines.iter().map(|l| normalize_line(l)).collect();
+    let addition_keys: Vec<String> = addition_lines.iter().map(|l| normalize_line(l)).collect();
+
+    let mut new_from = 0;
+    'search: for skip in 0..=1.min(addition_keys.len()) {
+        let candidates = &addition_keys[skip..];
+        for overlap in (1..=document_keys.len().min(candidates.len())).rev() {
+            if document_keys[document_keys.len() - overlap..] == candidates[..overlap] {
+                new_from = skip + overlap;
+                break 'search;
+            }
+        }
+    }
+
+    let mut merged: Vec<&str> = document_lines;
+    merged.extend(&addition_lines[new_from..]);
+    merged.join("\n")
+}
+
+/// A window scrolled across several captures, stitched into one tall image.
+#[derive(Debug, Clone)]
+pub struct ScrollDocument {
+    pub app_name: String,
+    pub window_name: String,
+    pub image: DynamicImage,
+    pub text: String,
+    pub frame_ids: Vec<i64>,
+}
+
+/// A document being stitched, its RGBA rows grow in place as the window scrolls.
+struct OpenDocument {
+    app_name: String,
+    window_name: String,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    text: String,
+    frame_ids: Vec<i64>,
+    last_signature: RowSignature,
+    last_used: u64,
+}
+
+impl OpenDocument {
+    fn new(
+        app_name: &str,
+        window_name: &str,
+        frame_id: i64,
+        image: &DynamicImage,
+        text: &str,
+        signature: RowSignature,
+        tick: u64,
+    ) -> Self {
+        let image = image.to_rgba8();
+        Self {
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw(),
+            text: text.to_string(),
+            frame_ids: vec![frame_id],
+            last_signature: signature,
+            last_used: tick,
+        }
+    }
+
+    /// Appends the bottom `rows` rows of `capture`.
+    fn append_rows(&mut self, capture: &DynamicImage, rows: u32) {
+        let new_rows = image::imageops::crop_imm(
+            capture,
+            0,
+            capture.height() - rows,
+            capture.width().min(self.width),
+            rows,
+        )
+        .to_image();
+        self.pixels.extend_from_slice(new_rows.as_raw());
+        self.height += rows;
+    }
+
+    fn bytes(&self) -> usize {
+        self.pixels.capacity()
+    }
+
+    fn into_document(self) -> Option<ScrollDocument> {
+        if self.frame_ids.len() < 2 {
+            return None;
+        }
+        let image = RgbaImage::from_raw(self.width, self.height, self.pixels)?;
+        Some(ScrollDocument {
+            app_name: self.app_name,
+            window_name: self.window_name,
+            image: DynamicImage::ImageRgba8(image),
+            text: self.text,
+            frame_ids: self.frame_ids,
+        })
+    }
+}
+
+/// Follows the captures of each window and stitches consecutive ones that only scrolled.
+/// Only downward scrolling is stitched, scrolling back up starts a new document.
+pub struct ScrollStitcher {
+    open: Vec<OpenDocument>,
+    max_height: u32,
+    max_open_bytes: usize,
+    tick: u64,
+}
+
+impl ScrollStitcher {
+    pub fn new(max_height: u32) -> Self {
+        Self {
+            open: Vec::new(),
+            max_height,
+            max_open_bytes: DEFAULT_MAX_OPEN_BYTES,
+            tick: 0,
+        }
+    }
+
+    pub fn with_max_open_bytes(mut self, max_open_bytes: usize) -> Self {
+        self.max_open_bytes = max_open_bytes;
+        self
+    }
+
+    /// Feeds a window capture and returns the documents it completed. A document completes
+    /// when its window shows something that isn't a continuation, and is only returned
+    /// when at least two captures were stitched.
+    pub fn push(
+        &mut self,
+        app_name: &str,
+        window_name: &str,
+        frame_id: i64,
+        image: &DynamicImage,
+        text: &str,
+    ) -> Vec<ScrollDocument> {
+        self.tick += 1;
+        let signature = RowSignature::new(image);
+        let mut finished = Vec::new();
+
+        let index = self
+            .open
+            .iter()
+            .position(|open| open.app_name == app_name && open.window_name == window_name);
+        if let Some(index) = index {
+            let open = &mut self.open[index];
+            let offset = detect_scroll_offset(&open.last_signature, &signature);
+            let fits = offset.is_some_and(|offset| {
+                let height = open.height + offset;
+                height <= self.max_height
+                    && height as usize * open.width as usize * BYTES_PER_PIXEL
+                        <= self.max_open_bytes
+            });
+            if let (Some(offset), true) = (offset, fits) {
+                if offset > 0 {
+                    open.append_rows(image, offset);
+                    open.text = merge_scrolled_text(&open.text, text);
+                }
+                open.frame_ids.push(frame_id);
+                open.last_signature = signature;
+                open.last_used = self.tick;
+                finished.extend(self.close_over_budget());
+                return finished;
+            }
+            finished.extend(self.close(index));
+        } else if self.open.len() >= MAX_OPEN_DOCUMENTS {
+            let oldest = self
+                .open
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, open)| open.last_used)
+                .map(|(index, _)| index);
+            if let Some(oldest) = oldest {
+                finished.extend(self.close(oldest));
+            }
+        }
+
+        self.open.push(OpenDocument::new(
+            app_name,
+            window_name,
+            frame_id,
+            image,
+            text,
+            signature,
+            self.tick,
+        ));
+        finished.extend(self.close_over_budget());
+        finished
+    }
+
+    /// Completes every open document, e.g. before shutting down.
+    pub fn finish_all(&mut self) -> Vec<ScrollDocument> {
+        self.open
+            .drain(..)
+            .filter_map(OpenDocument::into_document)
+            .collect()
+    }
+
+    fn close(&mut self, index: usize) -> Option<ScrollDocument> {
+        self.open.remove(index).into_document()
+    }
+
+    /// Completes the least recently used documents until the open ones fit the byte budget,
+    /// the document just pushed to is kept.
+    fn close_over_budget(&mut self) -> Vec<ScrollDocument> {
+        let mut finished = Vec::new();
+        while self.open.len() > 1
+            && self.open.iter().map(OpenDocument::bytes).sum::<usize>() > self.max_open_bytes
+        {
+            let oldest = self
+                .open
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, open)| open.last_used)
+                .map(|(index, _)| index);
+            match oldest {
+                Some(oldest) => finished.extend(self.close(oldest)),
+                None => break,
+            }
+        }
+        finished
+    }
+}
+
+impl Default for ScrollStitcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DOCUMENT_HEIGHT)
+    }
+}
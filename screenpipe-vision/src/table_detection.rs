@@ -0,0 +1,208 @@
+use screenpipe_db::{OcrWord, TextBounds};
+
+// Gap between two words, in word heights, that separates cells. Spaces inside a cell are
+// well under one height.
+const CELL_GAP: f32 = 1.5;
+// Vertical gap between rows, in row heights, past which the table ends
+const MAX_ROW_GAP: f32 = 1.5;
+// A header and two rows
+const MIN_ROWS: usize = 3;
+const MIN_COLUMNS: usize = 2;
+// Cells are short, so two columns of prose side by side aren't taken as a table
+const MAX_WORDS_PER_CELL: f32 = 4.0;
+
+/// A table found in the word layout of a window, see [`detect_tables`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedTable {
+    /// Text of every cell, row major. All rows have the same length, cells missing from a
+    /// row are empty.
+    pub cells: Vec<Vec<String>>,
+    /// In window image pixels
+    pub bbox: TextBounds,
+}
+
+impl DetectedTable {
+    pub fn column_count(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    /// The cells as CSV, quoting cells that contain a comma, quote or line break.
+    pub fn to_csv(&self) -> String {
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        if cell.contains([',', '"', '\n']) {
+                            format!("\"{}\"", cell.replace('"', "\"\""))
+                        } else {
+                            cell.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+struct Cell {
+    text: String,
+    words: usize,
+    bbox: TextBounds,
+}
+
+struct Row {
+    cells: Vec<Cell>,
+    bbox: TextBounds,
+}
+
+/// Finds runs of consecutive rows whose words fall into the same columns. Conservative:
+/// rows have to split into at least two short cells and at least three of them have to
+/// line up, anything else is left to the flat text.
+pub fn detect_tables(words: &[OcrWord]) -> Vec<DetectedTable> {
+    let mut tables = Vec::new();
+    let mut run: Vec<Row> = Vec::new();
+    for row in rows(words) {
+        let continues = run.last().is_some_and(|last| {
+            row.bbox.top - (last.bbox.top + last.bbox.height) <= last.bbox.height * MAX_ROW_GAP
+        });
+        if !continues {
+            tables.extend(table(&run));
+            run.clear();
+        }
+        if row.cells.len() >= MIN_COLUMNS {
+            run.push(row);
+        } else {
+            tables.extend(table(&run));
+            run.clear();
+        }
+    }
+    tables.extend(table(&run));
+    tables
+}
+
+/// Words grouped into lines from top to bottom, each split into cells at wide gaps.
+fn rows(words: &[OcrWord]) -> Vec<Row> {
+    let mut words: Vec<&OcrWord> = words
+        .iter()
+        .filter(|word| !word.text.trim().is_empty() && word.bbox.height > 0.0)
+        .collect();
+    words.sort_by(|a, b| center_y(&a.bbox).total_cmp(&center_y(&b.bbox)));
+
+    let mut lines: Vec<Vec<&OcrWord>> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            // the word's center falls within the line
+            Some(line)
+                if line.iter().any(|other| {
+                    (center_y(&word.bbox) - center_y(&other.bbox)).abs() <= other.bbox.height / 2.0
+                }) =>
+            {
+                line.push(word)
+            }
+            _ => lines.push(vec![word]),
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|mut line| {
+            line.sort_by(|a, b| a.bbox.left.total_cmp(&b.bbox.left));
+            let mut cells: Vec<Cell> = Vec::new();
+            let mut previous: Option<&OcrWord> = None;
+            for word in line {
+                let gap = previous.map(|previous| {
+                    let height = previous.bbox.height.max(word.bbox.height);
+                    (word.bbox.left - (previous.bbox.left + previous.bbox.width)) / height
+                });
+                match cells.last_mut() {
+                    Some(cell) if gap.is_some_and(|gap| gap < CELL_GAP) => {
+                        cell.text.push(' ');
+                        cell.text.push_str(word.text.trim());
+                        cell.words += 1;
+                        cell.bbox = cell.bbox.union(&word.bbox);
+                    }
+                    _ => cells.push(Cell {
+                        text: word.text.trim().to_string(),
+                        words: 1,
+                        bbox: word.bbox.clone(),
+                    }),
+                }
+                previous = Some(word);
+            }
+            let bbox = cells
+                .iter()
+                .skip(1)
+                .fold(cells[0].bbox.clone(), |bbox, cell| bbox.union(&cell.bbox));
+            Row { cells, bbox }
+        })
+        .collect()
+}
+
+fn center_y(bbox: &TextBounds) -> f32 {
+    bbox.top + bbox.height / 2.0
+}
+
+/// The rows as a table when their cells line up into columns.
+fn table(rows: &[Row]) -> Option<DetectedTable> {
+    if rows.len() < MIN_ROWS {
+        return None;
+    }
+    let cells: Vec<&Cell> = rows.iter().flat_map(|row| &row.cells).collect();
+    let words: usize = cells.iter().map(|cell| cell.words).sum();
+    if words as f32 > cells.len() as f32 * MAX_WORDS_PER_CELL {
+        return None;
+    }
+
+    // horizontal extents of the cells of all rows, merged where they overlap
+    let mut extents: Vec<(f32, f32)> = cells
+        .iter()
+        .map(|cell| (cell.bbox.left, cell.bbox.left + cell.bbox.width))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f32, f32)> = Vec::new();
+    for (left, right) in extents {
+        match columns.last_mut() {
+            Some(column) if left <= column.1 => column.1 = column.1.max(right),
+            _ => columns.push((left, right)),
+        }
+    }
+    if columns.len() < MIN_COLUMNS {
+        return None;
+    }
+
+    let grid: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            let mut values = vec![String::new(); columns.len()];
+            for cell in &row.cells {
+                let column = columns
+                    .iter()
+                    .position(|(_, right)| cell.bbox.left <= *right)
+                    .unwrap_or(columns.len() - 1);
+                if !values[column].is_empty() {
+                    values[column].push(' ');
+                }
+                values[column].push_str(&cell.text);
+            }
+            values
+        })
+        .collect();
+    // a cell spanning columns of other rows merges them, rows left with a single value
+    // didn't line up
+    let aligned = grid
+        .iter()
+        .filter(|row| row.iter().filter(|value| !value.is_empty()).count() >= MIN_COLUMNS)
+        .count();
+    if aligned < MIN_ROWS {
+        return None;
+    }
+
+    let bbox = rows
+        .iter()
+        .skip(1)
+        .fold(rows[0].bbox.clone(), |bbox, row| bbox.union(&row.bbox));
+    Some(DetectedTable { cells: grid, bbox })
+}
@@ -1,28 +1,24 @@
+use crate::ocr_provider::OcrResult;
+use crate::reading_order::reading_order_text;
+use crate::text_direction::{line_direction, order_words};
+use anyhow::{anyhow, Result};
 use image::DynamicImage;
 use rusty_tesseract::{Args, DataOutput, Image};
-use screenpipe_core::{Language, TESSERACT_LANGUAGES};
+use screenpipe_core::Language;
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
-pub fn perform_ocr_tesseract(
-    image: &DynamicImage,
-    languages: Vec<Language>,
-) -> (String, String, Option<f64>) {
-    let language_string = match languages.is_empty() {
-        true => "eng".to_string(),
-        _ => TESSERACT_LANGUAGES
-            .iter()
-            .filter_map(|(key, val)| {
-                if languages.iter().any(|l| l == val) {
-                    Some(key.to_string())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<String>>()
-            .join("+"),
-    };
+// Tesseract reports page, block, paragraph and line rows alongside words, level 5 is a word
+const TESSERACT_WORD_LEVEL: i32 = 5;
+
+pub fn perform_ocr_tesseract(image: &DynamicImage, languages: Vec<Language>) -> OcrResult {
     let args = Args {
-        lang: language_string,
+        lang: with_vertical_languages(
+            tesseract_language_string(&languages),
+            &languages,
+            installed_tesseract_languages(),
+        ),
         config_variables: HashMap::from([("tessedit_create_tsv".into(), "1".into())]),
         dpi: Some(600), // 150 is a balanced option, 600 seems faster surprisingly, the bigger the number the more granualar result
         psm: Some(1), // PSM 1: Automatic page segmentation with OSD. PSM 3: Automatic page segmentation with OSD
@@ -35,76 +31,146 @@ pub fn perform_ocr_tesseract(
     let data_output = rusty_tesseract::image_to_data(&ocr_image, &args).unwrap();
     // let tsv_output = data_output_to_tsv(&data_output);
 
-    // Extract text from data output
-    let text = data_output_to_text(&data_output);
-    let json_output = data_output_to_json(&data_output);
+    let (lines, words) = data_output_to_layout(&data_output);
+    // TSV order interleaves the lines of side by side columns
+    let text = reading_order_text(&lines);
 
     let overall_confidence = calculate_overall_confidence(&data_output);
 
-    (text, json_output, Some(overall_confidence))
+    OcrResult {
+        text,
+        lines,
+        words,
+        confidence: Some(overall_confidence),
+    }
 }
 
-fn data_output_to_text(data_output: &DataOutput) -> String {
-    let mut text = String::new();
-    for record in &data_output.data {
-        if !record.text.is_empty() {
-            if !text.is_empty() {
-                text.push(' ');
-            }
-            text.push_str(&record.text);
-        }
+/// Joins the requested languages into tesseract's `-l` form, e.g. `eng+deu+jpn`.
+/// Falls back to english when nothing tesseract knows about was requested.
+pub fn tesseract_language_string(languages: &[Language]) -> String {
+    let codes = languages
+        .iter()
+        .filter_map(|language| language.as_tesseract_code())
+        .collect::<Vec<&str>>();
+    if codes.is_empty() {
+        "eng".to_string()
+    } else {
+        codes.join("+")
     }
-    text
 }
 
-fn data_output_to_json(data_output: &DataOutput) -> String {
-    let mut lines: Vec<HashMap<String, String>> = Vec::new();
-    let mut current_line = String::new();
-    let mut current_conf = 0.0;
-    let mut word_count = 0;
-    let mut last_word_num = 0;
+/// Adds the vertical traineddata (`jpn_vert`, ...) of the requested CJK languages to `lang`
+/// when installed, so text set vertically is recognized as well. They are optional, a
+/// missing vertical pack doesn't fail validation.
+pub fn with_vertical_languages(
+    lang: String,
+    languages: &[Language],
+    installed: &[String],
+) -> String {
+    languages
+        .iter()
+        .filter_map(|language| language.as_vertical_tesseract_code())
+        .filter(|code| installed.iter().any(|lang| lang == code))
+        .fold(lang, |lang, code| format!("{}+{}", lang, code))
+}
+
+fn installed_tesseract_languages() -> &'static [String] {
+    static INSTALLED: OnceLock<Vec<String>> = OnceLock::new();
+    INSTALLED.get_or_init(|| rusty_tesseract::get_tesseract_langs().unwrap_or_default())
+}
+
+/// Checks that a traineddata pack is installed for every requested language, so a missing
+/// pack fails at startup instead of on every frame.
+pub fn validate_tesseract_languages(languages: &[Language]) -> Result<()> {
+    let installed = rusty_tesseract::get_tesseract_langs()
+        .map_err(|e| anyhow!("failed to list installed tesseract languages: {}", e))?;
+
+    let missing = tesseract_language_string(languages)
+        .split('+')
+        .filter(|code| !installed.iter().any(|lang| lang == code))
+        .map(str::to_string)
+        .collect::<Vec<String>>();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "missing tesseract traineddata for: {}. install the matching language packs \
+         (e.g. `apt install {}` or `brew install tesseract-lang`) or change --ocr-lang. installed: {}",
+        missing.join(", "),
+        missing
+            .iter()
+            .map(|code| format!("tesseract-ocr-{}", code.replace('_', "-").to_lowercase()))
+            .collect::<Vec<String>>()
+            .join(" "),
+        installed.join(", ")
+    ))
+}
+
+fn data_output_to_layout(data_output: &DataOutput) -> (Vec<OcrLine>, Vec<OcrWord>) {
+    let mut line_words: Vec<Vec<OcrWord>> = Vec::new();
+    let mut current_line: Option<(i32, i32, i32, i32)> = None;
 
     for record in &data_output.data {
-        if record.word_num == 0 && !current_line.is_empty() {
-            let avg_conf = current_conf / word_count as f32;
-            let mut line_data = HashMap::new();
-            line_data.insert("text".to_string(), current_line.clone());
-            line_data.insert("confidence".to_string(), format!("{:.2}", avg_conf));
-            line_data.insert(
-                "line_position".to_string(),
-                format!(
-                    "level{}page_num{}block_num{}par_num{}line_num{}",
-                    record.level,
-                    record.page_num,
-                    record.block_num,
-                    record.par_num,
-                    record.line_num
-                ),
-            );
-            lines.push(line_data);
-            current_line.clear();
-            current_conf = 0.0;
-            word_count = 0;
+        if record.level != TESSERACT_WORD_LEVEL || record.text.trim().is_empty() {
+            continue;
         }
-        if record.word_num > last_word_num {
-            if !current_line.is_empty() {
-                current_line.push(' ');
+
+        let word = OcrWord {
+            text: record.text.clone(),
+            conf: record.conf,
+            bbox: TextBounds {
+                left: record.left as f32,
+                top: record.top as f32,
+                width: record.width as f32,
+                height: record.height as f32,
+            },
+        };
+
+        let line_key = (
+            record.page_num,
+            record.block_num,
+            record.par_num,
+            record.line_num,
+        );
+        match line_words.last_mut() {
+            Some(words) if current_line == Some(line_key) => words.push(word),
+            _ => {
+                current_line = Some(line_key);
+                line_words.push(vec![word]);
             }
-            current_line.push_str(&record.text);
-            current_conf += record.conf;
-            word_count += 1;
         }
-        last_word_num = record.word_num;
     }
-    if !current_line.is_empty() {
-        let avg_conf = current_conf / word_count as f32;
-        let mut line_data = HashMap::new();
-        line_data.insert("text".to_string(), current_line);
-        line_data.insert("confidence".to_string(), format!("{:.2}", avg_conf));
-        lines.push(line_data);
+
+    let mut lines: Vec<OcrLine> = Vec::new();
+    let mut words: Vec<OcrWord> = Vec::new();
+    for mut line in line_words {
+        let text = line
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let bbox = line
+            .iter()
+            .skip(1)
+            .fold(line[0].bbox.clone(), |bbox, word| bbox.union(&word.bbox));
+        // arabic, hebrew and vertical CJK words aren't read left to right
+        order_words(&mut line, line_direction(&text, &bbox));
+
+        lines.push(OcrLine {
+            text: line
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            conf: line.iter().map(|word| word.conf).sum::<f32>() / line.len() as f32,
+            bbox,
+        });
+        words.extend(line);
     }
 
-    serde_json::to_string_pretty(&lines).unwrap()
+    (lines, words)
 }
 
 fn calculate_overall_confidence(data_output: &DataOutput) -> f64 {
@@ -0,0 +1,89 @@
+use screenpipe_db::{OcrLine, OcrWord, TextBounds};
+
+// A line of CJK text at least this many times taller than wide is set vertically
+const VERTICAL_ASPECT: f32 = 1.5;
+
+/// Direction text is read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    /// Arabic, Hebrew, ...
+    RightToLeft,
+    /// Top to bottom, columns right to left, e.g. vertical Japanese
+    Vertical,
+}
+
+fn is_right_to_left(c: char) -> bool {
+    // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic and their presentation forms
+    matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF)
+}
+
+fn is_cjk(c: char) -> bool {
+    // kana, CJK ideographs and extensions, hangul, compatibility ideographs
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// Direction of a line from its letters and shape: vertical for a tall, narrow line of CJK
+/// text, right to left when most letters are from a right to left script.
+pub fn line_direction(text: &str, bbox: &TextBounds) -> TextDirection {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let cjk = letters.iter().filter(|c| is_cjk(**c)).count();
+    if cjk >= 2 && cjk * 2 > letters.len() && bbox.height >= bbox.width * VERTICAL_ASPECT {
+        return TextDirection::Vertical;
+    }
+    let right_to_left = letters.iter().filter(|c| is_right_to_left(**c)).count();
+    if right_to_left * 2 > letters.len() {
+        TextDirection::RightToLeft
+    } else {
+        TextDirection::LeftToRight
+    }
+}
+
+/// Direction most of the text of a page is set in, weighing lines by their length.
+pub fn page_direction(lines: &[OcrLine]) -> TextDirection {
+    let mut weights = [0usize; 3];
+    for line in lines {
+        let index = match line_direction(&line.text, &line.bbox) {
+            TextDirection::LeftToRight => 0,
+            TextDirection::RightToLeft => 1,
+            TextDirection::Vertical => 2,
+        };
+        weights[index] += line.text.chars().count();
+    }
+    if weights[1] > weights[0] && weights[1] >= weights[2] {
+        TextDirection::RightToLeft
+    } else if weights[2] > weights[0] {
+        TextDirection::Vertical
+    } else {
+        TextDirection::LeftToRight
+    }
+}
+
+/// Sorts the words of one line into reading order.
+pub fn order_words(words: &mut [OcrWord], direction: TextDirection) {
+    match direction {
+        TextDirection::LeftToRight => words.sort_by(|a, b| a.bbox.left.total_cmp(&b.bbox.left)),
+        TextDirection::RightToLeft => words
+            .sort_by(|a, b| (b.bbox.left + b.bbox.width).total_cmp(&(a.bbox.left + a.bbox.width))),
+        TextDirection::Vertical => words.sort_by(|a, b| a.bbox.top.total_cmp(&b.bbox.top)),
+    }
+}
+
+/// `bbox` moved into a space where text of `direction` reads like left to right text: lines
+/// run left to right and follow each other top to bottom. Right to left text is mirrored,
+/// vertical text rotated so its rightmost column becomes the top line.
+pub fn to_left_to_right(bbox: &TextBounds, direction: TextDirection) -> TextBounds {
+    match direction {
+        TextDirection::LeftToRight => bbox.clone(),
+        TextDirection::RightToLeft => TextBounds {
+            left: -(bbox.left + bbox.width),
+            ..bbox.clone()
+        },
+        TextDirection::Vertical => TextBounds {
+            left: bbox.top,
+            top: -(bbox.left + bbox.width),
+            width: bbox.height,
+            height: bbox.width,
+        },
+    }
+}
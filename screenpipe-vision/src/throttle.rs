@@ -0,0 +1,41 @@
+use crate::ocr_provider::OcrProvider;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// How capture is slowed down to save power, e.g. while on battery.
+#[derive(Clone)]
+pub struct CaptureThrottle {
+    /// Every capture waits this many times longer, 1 for the configured rate
+    pub interval_factor: f64,
+    /// Frames are read by this instead of the configured OCR engine
+    pub ocr_provider: Option<Arc<dyn OcrProvider>>,
+}
+
+/// Most `interval_factor` applies, slower captures would hardly capture anything
+pub const MAX_INTERVAL_FACTOR: f64 = 60.0;
+
+static THROTTLE: RwLock<Option<CaptureThrottle>> = RwLock::new(None);
+
+/// Throttles capture in this process, `None` to go back to the configured rate and engine.
+pub fn set_capture_throttle(throttle: Option<CaptureThrottle>) {
+    *THROTTLE.write().unwrap_or_else(|e| e.into_inner()) = throttle;
+}
+
+/// `interval` between captures, slowed down while throttled.
+pub fn throttled_interval(interval: Duration) -> Duration {
+    match THROTTLE.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(throttle) if throttle.interval_factor > 1.0 => {
+            interval.mul_f64(throttle.interval_factor.min(MAX_INTERVAL_FACTOR))
+        }
+        _ => interval,
+    }
+}
+
+/// The engine reading frames while throttled, `None` for the configured one.
+pub fn throttled_ocr_provider() -> Option<Arc<dyn OcrProvider>> {
+    THROTTLE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|throttle| throttle.ocr_provider.clone())
+}
@@ -1,9 +1,15 @@
+use crate::capture_backend::screen_capturer;
+use crate::capture_region::crop_to_region;
 use crate::capture_screenshot_by_window::{
-    capture_all_visible_windows, CapturedWindow, WindowFilters,
+    capture_all_visible_windows, mask_regions, uncovered_regions, CapturedWindow, WindowBounds,
+    WindowFilters,
 };
 use crate::core::MaxAverageFrame;
+use crate::cursor::{cursor_options, overlay_cursor};
 use crate::custom_ocr::CustomOcrConfig;
+use crate::ocr_fallback::OcrFallbackConfig;
 use crate::monitor::SafeMonitor;
+use crate::phash::perceptual_hash;
 use image::DynamicImage;
 use image_compare::{Algorithm, Metric, Similarity};
 use tracing::{debug, warn};
@@ -19,6 +25,14 @@ pub enum OcrEngine {
     WindowsNative,
     AppleNative,
     Custom(CustomOcrConfig),
+    /// PaddleOCR models run locally through ONNX Runtime
+    Paddle,
+    /// Lightweight english ONNX models downloaded on first use, no system OCR install needed
+    Embedded,
+    /// Tries each engine in order until one clears the confidence threshold
+    Fallback(OcrFallbackConfig),
+    /// A provider registered at runtime through `ocr_provider::register_ocr_provider`
+    Provider(String),
 }
 
 impl From<OcrEngine> for screenpipe_db::OcrEngine {
@@ -31,6 +45,16 @@ impl From<OcrEngine> for screenpipe_db::OcrEngine {
             OcrEngine::Custom(config) => {
                 screenpipe_db::OcrEngine::Custom(DBCustomOcrConfig::from(config))
             }
+            OcrEngine::Paddle => screenpipe_db::OcrEngine::Paddle,
+            OcrEngine::Embedded => screenpipe_db::OcrEngine::Embedded,
+            // rows are attributed to the primary engine of the chain
+            OcrEngine::Fallback(config) => config
+                .engines
+                .into_iter()
+                .next()
+                .map(Into::into)
+                .unwrap_or_default(),
+            OcrEngine::Provider(name) => screenpipe_db::OcrEngine::Provider(name),
         }
     }
 }
@@ -43,6 +67,12 @@ impl From<screenpipe_db::OcrEngine> for OcrEngine {
             screenpipe_db::OcrEngine::WindowsNative => OcrEngine::WindowsNative,
             screenpipe_db::OcrEngine::AppleNative => OcrEngine::AppleNative,
             screenpipe_db::OcrEngine::Custom(config) => OcrEngine::Custom(config.into()),
+            screenpipe_db::OcrEngine::Paddle => OcrEngine::Paddle,
+            screenpipe_db::OcrEngine::Embedded => OcrEngine::Embedded,
+            screenpipe_db::OcrEngine::Provider(name) => OcrEngine::Provider(name),
+            // the accessibility tree isn't an engine, fall back to the platform's OCR
+            screenpipe_db::OcrEngine::AppleAccessibility => OcrEngine::AppleNative,
+            screenpipe_db::OcrEngine::WindowsUiAutomation => OcrEngine::WindowsNative,
         }
     }
 }
@@ -76,30 +106,148 @@ pub async fn capture_screenshot(
     monitor: &SafeMonitor,
     window_filters: &WindowFilters,
     capture_unfocused_windows: bool,
-) -> Result<(DynamicImage, Vec<CapturedWindow>, u64, Duration), anyhow::Error> {
+    focused_window_only: bool,
+    capture_region: Option<&WindowBounds>,
+) -> Result<
+    (DynamicImage, Vec<CapturedWindow>, u64, Duration, Option<f64>),
+    anyhow::Error,
+> {
     // info!("Starting screenshot capture for monitor: {:?}", monitor);
+    let (image, window_images, frame, capture_duration) = capture_masked_frame(
+        monitor,
+        window_filters,
+        capture_unfocused_windows,
+        focused_window_only,
+        capture_region,
+    )
+    .await?;
+    let image_hash = perceptual_hash(&image);
+    let changed_fraction = screen_capturer()
+        .take_dirty_regions(monitor)
+        .map(|regions| changed_fraction(&regions, &frame));
+
+    Ok((
+        image,
+        window_images,
+        image_hash,
+        capture_duration,
+        changed_fraction,
+    ))
+}
+
+/// Monitor frame with filtered windows masked out and cropped to `capture_region`, along
+/// with the visible windows and the frame bounds in monitor frame pixels. With
+/// `focused_window_only` everything but the focused window is masked out too, the frame
+/// keeps the monitor size so recordings don't change resolution with the focus. The cursor
+/// and click indicator are drawn in as configured by [`crate::cursor::set_cursor_options`],
+/// window images stay without them. Unlike
+/// [`capture_screenshot`] it leaves the capturer's dirty regions to the capture loop, so a
+/// recorder can take frames alongside it.
+pub async fn capture_masked_frame(
+    monitor: &SafeMonitor,
+    window_filters: &WindowFilters,
+    capture_unfocused_windows: bool,
+    focused_window_only: bool,
+    capture_region: Option<&WindowBounds>,
+) -> Result<(DynamicImage, Vec<CapturedWindow>, WindowBounds, Duration), anyhow::Error> {
+    let capturer = screen_capturer();
     let capture_start = Instant::now();
-    let image = monitor.capture_image().await.map_err(|e| {
+    let mut image = capturer.capture_monitor(monitor).await.map_err(|e| {
         debug!("failed to capture monitor image: {}", e);
         anyhow::anyhow!("monitor capture failed")
     })?;
-    let image_hash = calculate_hash(&image);
     let capture_duration = capture_start.elapsed();
 
-    let window_images =
+    let (mut window_images, filtered_regions) = if !capturer.capabilities().windows {
+        (vec![whole_monitor_window(monitor, &image)], Vec::new())
+    } else {
+        let capture_unfocused_windows = capture_unfocused_windows && !focused_window_only;
         match capture_all_visible_windows(monitor, window_filters, capture_unfocused_windows).await
         {
-            Ok(images) => images,
+            Ok(captures) => captures,
             Err(e) => {
                 warn!(
                     "Failed to capture window images: {}. Continuing with empty result.",
                     e
                 );
-                Vec::new()
+                (Vec::new(), Vec::new())
             }
+        }
+    };
+
+    // Filtered windows must not be readable in the full monitor frame either
+    let scale = image.width() as f32 / monitor.width().max(1) as f32;
+    mask_regions(&mut image, &filtered_regions, scale);
+    if focused_window_only && capturer.capabilities().windows {
+        let monitor_frame = WindowBounds {
+            x: 0,
+            y: 0,
+            width: monitor.width(),
+            height: monitor.height(),
         };
+        // Without a focused window on this monitor the whole frame goes black, which dedup
+        // then skips
+        mask_regions(
+            &mut image,
+            &uncovered_regions(&monitor_frame, &window_images),
+            scale,
+        );
+    }
+    overlay_cursor(
+        &mut image,
+        monitor,
+        cursor_options(),
+        capturer.capabilities().cursor,
+    );
+    for window in &mut window_images {
+        window.bounds = window.bounds.scaled(scale);
+    }
+    let mut frame = WindowBounds {
+        x: 0,
+        y: 0,
+        width: image.width(),
+        height: image.height(),
+    };
+    // Crop before hashing so dedup and diffing only see the region of interest
+    if let Some(region) = capture_region {
+        let region = region.scaled(scale);
+        crop_to_region(&mut image, &mut window_images, &region);
+        if let Some(cropped) = frame.intersect(&region) {
+            frame = cropped;
+        }
+    }
 
-    Ok((image, window_images, image_hash, capture_duration))
+    Ok((image, window_images, frame, capture_duration))
+}
+
+/// Share of `frame` covered by the changed `regions`, overlapping regions count twice so
+/// this is an upper bound.
+pub fn changed_fraction(regions: &[WindowBounds], frame: &WindowBounds) -> f64 {
+    let changed: u64 = regions
+        .iter()
+        .filter_map(|region| region.intersect(frame))
+        .map(|region| region.area() as u64)
+        .sum();
+    (changed as f64 / frame.area().max(1) as f64).min(1.0)
+}
+
+/// Stands in for the window list when the backend only captures whole monitors, so the frame
+/// still goes through OCR.
+fn whole_monitor_window(monitor: &SafeMonitor, image: &DynamicImage) -> CapturedWindow {
+    CapturedWindow {
+        image: image.clone(),
+        app_name: String::new(),
+        window_name: monitor.name().to_string(),
+        process_id: 0,
+        is_focused: true,
+        visible_percentage: 1.0,
+        bounds: WindowBounds {
+            x: 0,
+            y: 0,
+            width: monitor.width(),
+            height: monitor.height(),
+        },
+    }
 }
 
 pub async fn compare_with_previous_image(
@@ -0,0 +1,186 @@
+use crate::capture_screenshot_by_window::WindowBounds;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const TILE_SIZE: u32 = 32;
+// Mean luminance change of a tile between two frames for it to count as churning. Typing
+// or a blinking caret moves a few pixels, video repaints the whole tile.
+const CHURN_THRESHOLD: f32 = 12.0;
+// Consecutive churning frames before a tile is taken as video, scrolling stops long before
+const MIN_CHURN_FRAMES: u32 = 4;
+// Video smaller than this share of the window, e.g. an animated avatar, is OCRed as usual
+const MIN_VIDEO_AREA: f32 = 0.05;
+// Share of the tiles inside the video bounds that must churn, so scattered updates like a
+// ticking clock and a progress bar don't merge into one region
+const MIN_VIDEO_DENSITY: f32 = 0.6;
+// Past this share of the window the whole window is video and OCR is skipped
+const FULL_WINDOW_VIDEO: f32 = 0.9;
+
+/// Sent as `video_playing` when video starts playing in a window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoPlaybackEvent {
+    pub app_name: String,
+    pub window_name: String,
+    /// Video region in window image pixels
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Video playing in a window, see [`VideoPlaybackDetector`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoPlayback {
+    /// In window image pixels
+    pub region: WindowBounds,
+    /// The region covers (nearly) the whole window, e.g. a fullscreen player
+    pub covers_window: bool,
+    /// Playback wasn't detected in the previous frame of the window
+    pub started: bool,
+}
+
+struct WindowChurn {
+    luma: GrayImage,
+    // consecutive churning frames per tile, row major
+    churn: Vec<u32>,
+    playing: bool,
+}
+
+/// Finds regions of windows whose pixels keep changing from frame to frame, video players
+/// and games, so they can be left out of OCR instead of producing gibberish text.
+#[derive(Default)]
+pub struct VideoPlaybackDetector {
+    windows: HashMap<(String, String), WindowChurn>,
+}
+
+impl VideoPlaybackDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops windows that are no longer on screen.
+    pub fn retain_windows(&mut self, visible: &HashSet<(String, String)>) {
+        self.windows.retain(|key, _| visible.contains(key));
+    }
+
+    /// Compares `image` with the previous frame of the same window and returns the video
+    /// playing in it, if any.
+    pub fn observe(
+        &mut self,
+        app_name: &str,
+        window_name: &str,
+        image: &DynamicImage,
+    ) -> Option<VideoPlayback> {
+        let key = (app_name.to_string(), window_name.to_string());
+        let luma = image.to_luma8();
+        let (width, height) = luma.dimensions();
+
+        let Some(window) = self.windows.get_mut(&key) else {
+            self.windows.insert(
+                key,
+                WindowChurn {
+                    luma,
+                    churn: vec![0; tile_count(width, height)],
+                    playing: false,
+                },
+            );
+            return None;
+        };
+
+        match tile_changes(&window.luma, &luma) {
+            Some(changes) => {
+                for (churn, change) in window.churn.iter_mut().zip(changes) {
+                    *churn = if change >= CHURN_THRESHOLD {
+                        *churn + 1
+                    } else {
+                        0
+                    };
+                }
+            }
+            // resized, start over
+            None => window.churn = vec![0; tile_count(width, height)],
+        }
+        window.luma = luma;
+
+        let video: Vec<bool> = window
+            .churn
+            .iter()
+            .map(|churn| *churn >= MIN_CHURN_FRAMES)
+            .collect();
+        let region = video_region(&video, width, height);
+        let started = region.is_some() && !window.playing;
+        window.playing = region.is_some();
+
+        region.map(|region| VideoPlayback {
+            covers_window: region.area() as f32 >= (width * height) as f32 * FULL_WINDOW_VIDEO,
+            region,
+            started,
+        })
+    }
+}
+
+fn tile_count(width: u32, height: u32) -> usize {
+    (width.div_ceil(TILE_SIZE) * height.div_ceil(TILE_SIZE)) as usize
+}
+
+/// Mean luminance change of every tile between two frames, row major, or `None` when the
+/// frames can't be compared.
+pub fn tile_changes(previous: &GrayImage, current: &GrayImage) -> Option<Vec<f32>> {
+    if previous.dimensions() != current.dimensions() {
+        return None;
+    }
+
+    let (width, height) = current.dimensions();
+    let mut changes = Vec::new();
+    for tile_y in 0..height.div_ceil(TILE_SIZE) {
+        for tile_x in 0..width.div_ceil(TILE_SIZE) {
+            let mut total = 0u64;
+            let mut pixels = 0u64;
+            for y in tile_y * TILE_SIZE..((tile_y + 1) * TILE_SIZE).min(height) {
+                for x in tile_x * TILE_SIZE..((tile_x + 1) * TILE_SIZE).min(width) {
+                    let a = previous.get_pixel(x, y)[0];
+                    let b = current.get_pixel(x, y)[0];
+                    total += a.abs_diff(b) as u64;
+                    pixels += 1;
+                }
+            }
+            changes.push(total as f32 / pixels.max(1) as f32);
+        }
+    }
+    Some(changes)
+}
+
+/// Bounds of the video tiles of a `width` x `height` window, in pixels, when they form a
+/// dense enough block of at least [`MIN_VIDEO_AREA`] of the window.
+pub fn video_region(video: &[bool], width: u32, height: u32) -> Option<WindowBounds> {
+    let columns = width.div_ceil(TILE_SIZE);
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    let mut count = 0;
+    for (index, _) in video.iter().enumerate().filter(|(_, video)| **video) {
+        let (x, y) = (index as u32 % columns, index as u32 / columns);
+        bounds = Some(match bounds {
+            None => (x, y, x, y),
+            Some((left, top, right, bottom)) => {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }
+        });
+        count += 1;
+    }
+
+    let (left, top, right, bottom) = bounds?;
+    let tiles = (right - left + 1) * (bottom - top + 1);
+    if (count as f32) < tiles as f32 * MIN_VIDEO_DENSITY {
+        return None;
+    }
+
+    let x = left * TILE_SIZE;
+    let y = top * TILE_SIZE;
+    let region = WindowBounds {
+        x: x as i32,
+        y: y as i32,
+        width: ((right + 1) * TILE_SIZE).min(width) - x,
+        height: ((bottom + 1) * TILE_SIZE).min(height) - y,
+    };
+    (region.area() as f32 >= (width * height) as f32 * MIN_VIDEO_AREA).then_some(region)
+}
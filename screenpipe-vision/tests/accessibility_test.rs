@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::accessibility::{
+        merge_with_ocr, AccessibilityConfig, AccessibilityNode, AccessibilityText, ScreenRect,
+        TextSource,
+    };
+    use screenpipe_vision::{OcrLine, OcrResult, OcrWord, TextBounds};
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> ScreenRect {
+        ScreenRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn node(role: &str, text: &str, frame: Option<ScreenRect>) -> AccessibilityNode {
+        AccessibilityNode {
+            role: role.to_string(),
+            text: text.to_string(),
+            depth: 1,
+            frame,
+        }
+    }
+
+    #[test]
+    fn test_config_matches_app_names() {
+        let config = AccessibilityConfig::new(&["slack".to_string(), "Notes".to_string()]);
+        assert!(config.is_enabled_for("Slack"));
+        assert!(config.is_enabled_for("Apple Notes"));
+        assert!(!config.is_enabled_for("Safari"));
+        assert!(!AccessibilityConfig::default().is_enabled_for("Slack"));
+    }
+
+    #[test]
+    fn test_maps_element_frames_into_the_window_image() {
+        // window of 400x300 points captured at 2x
+        let text = AccessibilityText {
+            window_frame: Some(rect(100.0, 50.0, 400.0, 300.0)),
+            nodes: vec![
+                node(
+                    "AXHeading",
+                    "Release notes",
+                    Some(rect(110.0, 60.0, 130.0, 20.0)),
+                ),
+                node(
+                    "AXTextArea",
+                    "first line\n\nsecond line",
+                    Some(rect(110.0, 100.0, 200.0, 40.0)),
+                ),
+                node("AXButton", "Share", None),
+            ],
+        };
+        let result = text.to_ocr_result(800, 600);
+
+        assert_eq!(result.text, "Release notes\nfirst line\nsecond line\nShare");
+        assert_eq!(result.confidence, Some(1.0));
+        assert_eq!(result.lines.len(), 4);
+
+        let heading = &result.lines[0].bbox;
+        assert_eq!(
+            (heading.left, heading.top, heading.width, heading.height),
+            (20.0, 20.0, 260.0, 40.0)
+        );
+        // lines of a multi line element split its frame
+        let second = &result.lines[2].bbox;
+        assert_eq!((second.top, second.height), (140.0, 40.0));
+        // elements without a frame have no position
+        assert_eq!(result.lines[3].bbox.width, 0.0);
+
+        let words: Vec<&str> = result.words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(
+            words,
+            vec!["Release", "notes", "first", "line", "second", "line", "Share"]
+        );
+        let notes = &result.words[1].bbox;
+        assert_eq!((notes.left, notes.width), (20.0 + 20.0 * 8.0, 20.0 * 5.0));
+    }
+
+    #[test]
+    fn test_blank_trees_are_empty() {
+        let text = AccessibilityText {
+            window_frame: None,
+            nodes: vec![node("AXStaticText", "  ", None)],
+        };
+        assert!(text.is_empty());
+        assert!(AccessibilityText::default().is_empty());
+    }
+
+    fn line(text: &str, left: f32, top: f32) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            conf: 0.8,
+            bbox: TextBounds {
+                left,
+                top,
+                width: 100.0,
+                height: 20.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_ui_automation_text_is_merged_with_ocr() {
+        assert!(TextSource::Uia.merges_with_ocr());
+        assert!(!TextSource::Ax.merges_with_ocr());
+
+        let accessibility = AccessibilityText {
+            window_frame: Some(rect(0.0, 0.0, 400.0, 300.0)),
+            nodes: vec![node("Text", "Inbox", Some(rect(0.0, 0.0, 100.0, 20.0)))],
+        }
+        .to_ocr_result(400, 300);
+        let ocr = OcrResult {
+            text: "lnbox\nInbox\nchart label".to_string(),
+            lines: vec![
+                // misread copy of the element text, on top of it
+                line("lnbox", 0.0, 0.0),
+                // same text elsewhere, e.g. a title echoed in a header
+                line("Inbox", 200.0, 100.0),
+                // drawn on a canvas, no element reports it
+                line("chart label", 0.0, 200.0),
+            ],
+            words: vec![OcrWord {
+                text: "chart".to_string(),
+                conf: 0.8,
+                bbox: TextBounds {
+                    left: 0.0,
+                    top: 200.0,
+                    width: 50.0,
+                    height: 20.0,
+                },
+            }],
+            confidence: Some(0.8),
+        };
+
+        let merged = merge_with_ocr(accessibility, ocr);
+        assert_eq!(merged.text, "Inbox\nchart label");
+        assert_eq!(merged.lines.len(), 2);
+        assert_eq!(
+            merged
+                .words
+                .iter()
+                .map(|w| w.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Inbox", "chart"]
+        );
+        assert_eq!(merged.confidence, Some(1.0));
+    }
+}
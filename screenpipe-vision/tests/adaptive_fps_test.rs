@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::{AdaptiveFpsConfig, AdaptiveFpsScheduler};
+    use std::time::Duration;
+
+    fn scheduler(min_fps: f64, max_fps: f64, sensitivity: f64) -> AdaptiveFpsScheduler {
+        AdaptiveFpsScheduler::new(AdaptiveFpsConfig::new(min_fps, max_fps, sensitivity).unwrap())
+    }
+
+    #[test]
+    fn test_idle_frames_slow_down_to_min_fps() {
+        let mut scheduler = scheduler(0.5, 2.0, 1.0);
+        assert_eq!(scheduler.fps(), 2.0);
+
+        scheduler.observe(0.0);
+        assert!(scheduler.fps() < 2.0);
+
+        for _ in 0..50 {
+            scheduler.observe(0.0);
+        }
+        assert_eq!(scheduler.fps(), 0.5);
+        assert_eq!(scheduler.interval(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_activity_ramps_back_to_max_fps() {
+        let mut scheduler = scheduler(0.5, 2.0, 1.0);
+        for _ in 0..50 {
+            scheduler.observe(0.0);
+        }
+
+        scheduler.observe(0.2);
+        assert_eq!(scheduler.fps(), 2.0);
+        assert_eq!(scheduler.interval(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_sensitivity_scales_activity_threshold() {
+        let mut insensitive = scheduler(0.5, 2.0, 0.1);
+        let mut sensitive = scheduler(0.5, 2.0, 10.0);
+        for _ in 0..50 {
+            insensitive.observe(0.0);
+            sensitive.observe(0.0);
+        }
+
+        insensitive.observe(0.01);
+        sensitive.observe(0.01);
+        assert_eq!(insensitive.fps(), 0.5);
+        assert_eq!(sensitive.fps(), 2.0);
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(AdaptiveFpsConfig::new(0.0, 1.0, 1.0).is_err());
+        assert!(AdaptiveFpsConfig::new(2.0, 1.0, 1.0).is_err());
+        assert!(AdaptiveFpsConfig::new(0.5, 1.0, 0.0).is_err());
+        assert!(AdaptiveFpsConfig::new(0.5, 1.0, 1.0).is_ok());
+    }
+}
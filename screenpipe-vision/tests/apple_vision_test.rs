@@ -26,7 +26,8 @@ mod tests {
         let rgb_image = image.to_rgb8();
         println!("RGB image dimensions: {:?}", rgb_image.dimensions());
 
-        let (ocr_text, _, _) = perform_ocr_apple(&image, &[]);
+        let result = perform_ocr_apple(&image, &[]);
+        let ocr_text = result.text;
 
         println!("OCR text: {:?}", ocr_text);
         assert!(
@@ -46,7 +47,7 @@ mod tests {
         let image = image::open(&path).expect("Failed to open Chinese test image");
         println!("Image dimensions: {:?}", image.dimensions());
 
-        let (ocr_text, _, _) = perform_ocr_apple(&image, &[Language::Chinese]);
+        let ocr_text = perform_ocr_apple(&image, &[Language::Chinese]).text;
 
         println!("OCR text: {:?}", ocr_text);
         assert!(
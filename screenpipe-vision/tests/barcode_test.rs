@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GrayImage, Luma};
+    use rxing::{BarcodeFormat, MultiFormatWriter, Writer};
+    use screenpipe_vision::barcode::detect_codes;
+
+    /// A 640x400 light window with `payload` as a QR code at (100, 80).
+    fn window_with_qr(payload: &str) -> DynamicImage {
+        let matrix = MultiFormatWriter
+            .encode(payload, &BarcodeFormat::QR_CODE, 200, 200)
+            .unwrap();
+        let mut image = GrayImage::from_pixel(640, 400, Luma([255]));
+        for y in 0..matrix.getHeight() {
+            for x in 0..matrix.getWidth() {
+                if matrix.get(x, y) {
+                    image.put_pixel(100 + x, 80 + y, Luma([0]));
+                }
+            }
+        }
+        DynamicImage::ImageLuma8(image)
+    }
+
+    #[test]
+    fn test_reads_qr_code_payload() {
+        let payload = "otpauth://totp/example:me?secret=JBSWY3DPEHPK3PXP&issuer=example";
+        let codes = detect_codes(&window_with_qr(payload));
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].payload, payload);
+        assert_eq!(codes[0].format, BarcodeFormat::QR_CODE.to_string());
+    }
+
+    #[test]
+    fn test_no_codes_in_plain_window() {
+        let image = DynamicImage::ImageLuma8(GrayImage::from_pixel(640, 400, Luma([255])));
+        assert!(detect_codes(&image).is_empty());
+    }
+}
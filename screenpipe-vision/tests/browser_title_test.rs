@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::browser_utils::tab_title_from_window;
+
+    #[test]
+    fn test_strips_browser_name_from_window_title() {
+        assert_eq!(
+            tab_title_from_window("Google Chrome", "Pull request · repo - Google Chrome"),
+            Some("Pull request · repo".to_string())
+        );
+        // profile and browser name both trail the title
+        assert_eq!(
+            tab_title_from_window("Firefox", "Docs — Work — Mozilla Firefox"),
+            Some("Docs — Work".to_string())
+        );
+        assert_eq!(
+            tab_title_from_window(
+                "Microsoft Edge",
+                "Inbox and 2 more pages - Personal - Microsoft Edge"
+            ),
+            Some("Inbox".to_string())
+        );
+        // safari reports the bare tab title
+        assert_eq!(
+            tab_title_from_window("Safari", "Release notes - Blog"),
+            Some("Release notes - Blog".to_string())
+        );
+    }
+
+    #[test]
+    fn test_non_browsers_have_no_tab_title() {
+        assert_eq!(tab_title_from_window("Slack", "general - Acme"), None);
+        assert_eq!(
+            tab_title_from_window("Google Chrome", " - Google Chrome"),
+            None
+        );
+    }
+}
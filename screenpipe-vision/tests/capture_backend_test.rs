@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::capture_backend::{
+        create_screen_capturer, is_wayland_session, CaptureBackendKind, CaptureCapabilities,
+    };
+    use screenpipe_vision::capture_screenshot_by_window::WindowBounds;
+    use screenpipe_vision::utils::changed_fraction;
+
+    fn bounds(x: i32, y: i32, width: u32, height: u32) -> WindowBounds {
+        WindowBounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_xcap_capturer_captures_windows() {
+        let capturer = create_screen_capturer(CaptureBackendKind::Xcap).unwrap();
+        assert_eq!(capturer.name(), "xcap");
+        assert!(capturer.capabilities().windows);
+        assert!(!capturer.capabilities().dirty_regions);
+    }
+
+    #[test]
+    fn test_auto_candidates_end_with_xcap() {
+        let candidates = CaptureBackendKind::candidates();
+        assert_eq!(candidates.last(), Some(&CaptureBackendKind::Xcap));
+        assert!(!candidates.contains(&CaptureBackendKind::Auto));
+    }
+
+    #[test]
+    fn test_capabilities_display() {
+        assert_eq!(
+            CaptureCapabilities::default().to_string(),
+            "monitor frames only"
+        );
+        let capabilities = CaptureCapabilities {
+            windows: true,
+            change_notifications: false,
+            dirty_regions: true,
+            cursor: false,
+        };
+        assert_eq!(capabilities.to_string(), "windows, dirty regions");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_auto_skips_wayland_outside_wayland_sessions() {
+        std::env::set_var("XDG_SESSION_TYPE", "x11");
+        assert!(!is_wayland_session());
+
+        let capturer = create_screen_capturer(CaptureBackendKind::Auto).unwrap();
+        let expected = if cfg!(target_os = "windows") { "dxgi" } else { "xcap" };
+        assert_eq!(capturer.name(), expected);
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "wayland")))]
+    #[test]
+    fn test_wayland_backend_requires_feature() {
+        assert!(create_screen_capturer(CaptureBackendKind::Wayland).is_err());
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_screencapturekit_backend_is_macos_only() {
+        assert!(create_screen_capturer(CaptureBackendKind::ScreenCaptureKit).is_err());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_dxgi_backend_is_windows_only() {
+        assert!(create_screen_capturer(CaptureBackendKind::Dxgi).is_err());
+    }
+
+    #[test]
+    fn test_changed_fraction_of_dirty_regions() {
+        let frame = bounds(0, 0, 100, 100);
+
+        assert_eq!(changed_fraction(&[], &frame), 0.0);
+        assert_eq!(changed_fraction(&[bounds(0, 0, 50, 20)], &frame), 0.1);
+        // only the part inside the frame counts
+        assert_eq!(changed_fraction(&[bounds(90, 90, 20, 20)], &frame), 0.01);
+        assert_eq!(changed_fraction(&[frame.clone(), frame.clone()], &frame), 1.0);
+    }
+
+    #[test]
+    fn test_changed_fraction_inside_capture_region() {
+        let region = bounds(100, 100, 100, 100);
+
+        assert_eq!(changed_fraction(&[bounds(0, 0, 50, 50)], &region), 0.0);
+        assert_eq!(changed_fraction(&[bounds(150, 100, 50, 100)], &region), 0.5);
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use screenpipe_vision::capture_region::{crop_to_region, MonitorRegion};
+    use screenpipe_vision::capture_screenshot_by_window::{
+        uncovered_regions, CapturedWindow, WindowBounds,
+    };
+    use screenpipe_vision::monitor::MonitorSelector;
+
+    fn bounds(x: i32, y: i32, width: u32, height: u32) -> WindowBounds {
+        WindowBounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn window(name: &str, bounds: WindowBounds) -> CapturedWindow {
+        CapturedWindow {
+            image: DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+                bounds.width,
+                bounds.height,
+                Rgba([255, 255, 255, 255]),
+            )),
+            app_name: name.to_string(),
+            window_name: name.to_string(),
+            process_id: 0,
+            is_focused: true,
+            visible_percentage: 1.0,
+            bounds,
+        }
+    }
+
+    #[test]
+    fn test_parse_capture_region() {
+        let region: MonitorRegion = "primary=0,20,1280,800".parse().unwrap();
+        assert_eq!(region.selector, MonitorSelector::Primary);
+        assert_eq!(region.region, bounds(0, 20, 1280, 800));
+
+        let region: MonitorRegion = "name:DELL=10, 10, 640, 480".parse().unwrap();
+        assert_eq!(region.selector, MonitorSelector::Name("DELL".to_string()));
+        assert_eq!(region.region, bounds(10, 10, 640, 480));
+    }
+
+    #[test]
+    fn test_parse_invalid_capture_region() {
+        assert!("primary".parse::<MonitorRegion>().is_err());
+        assert!("primary=0,0,100".parse::<MonitorRegion>().is_err());
+        assert!("primary=0,0,0,100".parse::<MonitorRegion>().is_err());
+        assert!("primary=-5,0,100,100".parse::<MonitorRegion>().is_err());
+        assert!("primary=a,0,100,100".parse::<MonitorRegion>().is_err());
+    }
+
+    #[test]
+    fn test_crop_to_region_crops_frame_and_windows() {
+        let mut image = DynamicImage::ImageRgba8(RgbaImage::new(400, 300));
+        let mut windows = vec![
+            window("inside", bounds(120, 120, 50, 50)),
+            window("partial", bounds(50, 50, 100, 100)),
+            window("outside", bounds(350, 250, 40, 40)),
+        ];
+
+        crop_to_region(&mut image, &mut windows, &bounds(100, 100, 200, 100));
+
+        assert_eq!((image.width(), image.height()), (200, 100));
+        assert_eq!(windows.len(), 2);
+
+        assert_eq!(windows[0].bounds, bounds(20, 20, 50, 50));
+        assert_eq!(windows[0].visible_percentage, 1.0);
+
+        assert_eq!(windows[1].bounds, bounds(0, 0, 50, 50));
+        assert_eq!(
+            (windows[1].image.width(), windows[1].image.height()),
+            (50, 50)
+        );
+        assert_eq!(windows[1].visible_percentage, 0.25);
+    }
+
+    #[test]
+    fn test_crop_to_region_outside_frame_keeps_everything() {
+        let mut image = DynamicImage::ImageRgba8(RgbaImage::new(400, 300));
+        let mut windows = vec![window("app", bounds(0, 0, 100, 100))];
+
+        crop_to_region(&mut image, &mut windows, &bounds(500, 500, 100, 100));
+
+        assert_eq!((image.width(), image.height()), (400, 300));
+        assert_eq!(windows[0].bounds, bounds(0, 0, 100, 100));
+    }
+
+    #[test]
+    fn test_uncovered_regions_surround_the_focused_window() {
+        let frame = bounds(0, 0, 400, 300);
+        let regions = uncovered_regions(&frame, &[window("editor", bounds(100, 50, 200, 100))]);
+
+        let area: u32 = regions.iter().map(|r| r.width * r.height).sum();
+        assert_eq!(area, 400 * 300 - 200 * 100);
+        assert!(regions.iter().all(|r| r.x + r.width as i32 <= 100
+            || r.x >= 300
+            || r.y + r.height as i32 <= 50
+            || r.y >= 150));
+
+        // nothing focused on this monitor, the whole frame is masked
+        assert_eq!(uncovered_regions(&frame, &[]), vec![frame.clone()]);
+        // a window covering the monitor leaves nothing to mask
+        assert!(
+            uncovered_regions(&frame, &[window("editor", bounds(-10, -10, 500, 400))]).is_empty()
+        );
+    }
+}
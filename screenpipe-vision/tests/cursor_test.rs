@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+    use screenpipe_vision::cursor::{
+        cursor_options, draw_click_indicator, draw_cursor, CursorOptions,
+    };
+
+    const GRAY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+    #[test]
+    fn test_cursor_is_off_by_default() {
+        assert_eq!(cursor_options(), CursorOptions::default());
+        assert!(!cursor_options().show_cursor);
+    }
+
+    #[test]
+    fn test_draws_the_cursor_at_its_hotspot() {
+        let mut image = RgbaImage::from_pixel(100, 100, GRAY);
+        draw_cursor(&mut image, 40.0, 30.0, 2.0);
+
+        // black outline along the left edge, white inside
+        assert_eq!(*image.get_pixel(40, 40), Rgba([0, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(44, 45), Rgba([255, 255, 255, 255]));
+        // nothing up or left of the hotspot, nor past the tip of the arrow
+        assert_eq!(*image.get_pixel(38, 40), GRAY);
+        assert_eq!(*image.get_pixel(45, 28), GRAY);
+        assert_eq!(*image.get_pixel(80, 80), GRAY);
+    }
+
+    #[test]
+    fn test_draws_a_ring_around_clicks() {
+        let mut image = RgbaImage::from_pixel(100, 100, GRAY);
+        draw_click_indicator(&mut image, 50.0, 50.0, 1.0);
+
+        assert_ne!(*image.get_pixel(50, 37), GRAY);
+        assert_ne!(*image.get_pixel(62, 50), GRAY);
+        // the clicked spot itself stays visible
+        assert_eq!(*image.get_pixel(50, 50), GRAY);
+        assert_eq!(*image.get_pixel(80, 80), GRAY);
+    }
+
+    #[test]
+    fn test_drawing_is_clipped_to_the_frame() {
+        let mut image = RgbaImage::from_pixel(10, 10, GRAY);
+        draw_click_indicator(&mut image, 0.0, 0.0, 1.0);
+        draw_cursor(&mut image, 8.0, 8.0, 2.0);
+        assert_eq!(*image.get_pixel(8, 9), Rgba([0, 0, 0, 255]));
+    }
+}
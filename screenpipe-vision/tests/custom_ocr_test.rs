@@ -97,7 +97,7 @@ mod tests {
         let ocr_engine = OcrEngine::Custom(config);
 
         // Perform the custom OCR.
-        let (ocr_text, structured_data, confidence) = match ocr_engine {
+        let result = match ocr_engine {
             OcrEngine::Custom(ref config) => {
                 perform_ocr_custom(&image, vec![Language::English], config)
                     .await
@@ -106,13 +106,13 @@ mod tests {
             _ => panic!("Unexpected OCR engine"),
         };
 
-        println!("OCR text: {:?}", ocr_text);
-        println!("Structured data: {:?}", structured_data);
-        println!("Confidence: {:?}", confidence);
+        println!("OCR text: {:?}", result.text);
+        println!("Lines: {:?}", result.lines);
+        println!("Confidence: {:?}", result.confidence);
 
         // Check some basic assumption about the output
         assert!(
-            !ocr_text.is_empty(),
+            !result.text.is_empty(),
             "Custom OCR did not return any recognized text."
         );
     }
@@ -142,11 +142,12 @@ mod tests {
         };
         let ocr_engine = OcrEngine::Custom(config);
 
-        let (ocr_text, _, _) = match ocr_engine {
+        let ocr_text = match ocr_engine {
             OcrEngine::Custom(ref config) => {
                 perform_ocr_custom(&image, vec![Language::Chinese], config)
                     .await
                     .expect("Custom OCR failed")
+                    .text
             }
             _ => panic!("Unexpected OCR engine"),
         };
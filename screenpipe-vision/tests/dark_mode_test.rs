@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+    use screenpipe_vision::dark_mode::{is_dark, luminance_histogram, normalize_for_ocr};
+    use std::borrow::Cow;
+
+    /// A `background` window with a few lines of `text` colored pixels.
+    fn window(background: [u8; 3], text: [u8; 3]) -> DynamicImage {
+        let mut image = RgbImage::from_pixel(200, 100, Rgb(background));
+        for y in (10..90).step_by(20) {
+            for x in 10..190 {
+                for row in y..y + 4 {
+                    image.put_pixel(x, row, Rgb(text));
+                }
+            }
+        }
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn test_detects_dark_backgrounds() {
+        // terminal
+        assert!(is_dark(&window([30, 30, 30], [220, 220, 220])));
+        // dark mode editor tinted blue
+        assert!(is_dark(&window([40, 44, 52], [171, 178, 191])));
+        // browser
+        assert!(!is_dark(&window([255, 255, 255], [20, 20, 20])));
+        assert!(!is_dark(&window([240, 240, 240], [90, 90, 90])));
+        assert!(!is_dark(&DynamicImage::new_rgb8(0, 0)));
+    }
+
+    #[test]
+    fn test_histogram_samples_the_image() {
+        let histogram = luminance_histogram(&window([0, 0, 0], [255, 255, 255]));
+        let total: u32 = histogram.iter().sum();
+        assert_eq!(total, 50 * 25);
+        assert!(histogram[0] > histogram[255]);
+    }
+
+    #[test]
+    fn test_only_dark_windows_are_inverted() {
+        let light = window([255, 255, 255], [20, 20, 20]);
+        assert!(matches!(normalize_for_ocr(&light), Cow::Borrowed(_)));
+
+        let dark = window([30, 30, 30], [220, 220, 220]);
+        let inverted = normalize_for_ocr(&dark).into_owned().to_rgb8();
+        assert_eq!(inverted.dimensions(), (200, 100));
+        assert_eq!(*inverted.get_pixel(0, 0), Rgb([225, 225, 225]));
+        assert_eq!(*inverted.get_pixel(10, 10), Rgb([35, 35, 35]));
+    }
+}
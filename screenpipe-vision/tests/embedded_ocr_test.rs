@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::{create_ocr_provider, OcrEngine};
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    #[ignore] // downloads the embedded models
+    async fn test_embedded_ocr() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests");
+        path.push("testing_OCR.png");
+        let image = image::open(&path).expect("Failed to open image");
+
+        let provider = create_ocr_provider(&OcrEngine::Embedded).unwrap();
+        let result = provider.recognize(&image, &[]).await.unwrap();
+
+        println!("OCR text: {:?}", result.text);
+        assert_eq!(provider.name(), "embedded");
+        assert!(
+            result.text.contains("receiver_count"),
+            "OCR failed: {:?}",
+            result.text
+        );
+        assert!(result.confidence.unwrap_or(0.0) > 0.0);
+    }
+}
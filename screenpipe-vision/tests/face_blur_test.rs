@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::face_blur::{faces_from_outputs, FaceBlurConfig};
+
+    #[test]
+    fn test_only_video_call_windows_match() {
+        let config = FaceBlurConfig::default();
+        assert!(config.matches("zoom.us", "Zoom Meeting"));
+        assert!(config.matches("Google Chrome", "Meet - abc-defg-hij"));
+        assert!(config.matches("Microsoft Teams", ""));
+        assert!(!config.matches("Code", "main.rs"));
+
+        let config = FaceBlurConfig::new(vec![" Discord ".to_string(), String::new()]);
+        assert_eq!(config.apps(), ["discord"]);
+        assert!(config.matches("Discord", "voice"));
+        assert!(!config.matches("zoom.us", "Zoom Meeting"));
+    }
+
+    #[test]
+    fn test_overlapping_detections_keep_most_confident() {
+        let scores = [
+            0.1, 0.9, // face
+            0.2, 0.8, // same face, slightly shifted
+            0.5, 0.5, // below the threshold
+            0.05, 0.95, // second face
+        ];
+        let boxes = [
+            0.1, 0.1, 0.3, 0.3, //
+            0.11, 0.1, 0.31, 0.3, //
+            0.5, 0.5, 0.6, 0.6, //
+            0.6, 0.2, 0.8, 0.4,
+        ];
+
+        let faces = faces_from_outputs(&scores, &boxes, 1000.0, 500.0);
+        assert_eq!(faces.len(), 2);
+
+        // most confident first, padded by a fifth of the box on each side
+        assert!((faces[0].left - 560.0).abs() < 0.01);
+        assert!((faces[0].top - 80.0).abs() < 0.01);
+        assert!((faces[0].width - 280.0).abs() < 0.01);
+        assert!((faces[0].height - 140.0).abs() < 0.01);
+        assert!((faces[1].left - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_padding_stays_inside_the_image() {
+        let faces = faces_from_outputs(&[0.0, 1.0], &[0.0, 0.0, 1.0, 1.0], 320.0, 240.0);
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].left, 0.0);
+        assert_eq!(faces[0].top, 0.0);
+        assert_eq!(faces[0].width, 320.0);
+        assert_eq!(faces[0].height, 240.0);
+    }
+}
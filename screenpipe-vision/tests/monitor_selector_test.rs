@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::monitor::{MonitorData, MonitorFps, MonitorSelector};
+
+    fn monitor(name: &str, is_primary: bool) -> MonitorData {
+        MonitorData {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            name: name.to_string(),
+            is_primary,
+        }
+    }
+
+    #[test]
+    fn test_parse_monitor_selectors() {
+        assert_eq!("3".parse(), Ok(MonitorSelector::Id(3)));
+        assert_eq!("id:3".parse(), Ok(MonitorSelector::Id(3)));
+        assert_eq!("index:1".parse(), Ok(MonitorSelector::Index(1)));
+        assert_eq!(
+            "name:LG TV".parse(),
+            Ok(MonitorSelector::Name("LG TV".to_string()))
+        );
+        assert_eq!("Primary".parse(), Ok(MonitorSelector::Primary));
+        assert!("index:first".parse::<MonitorSelector>().is_err());
+        assert!("tv".parse::<MonitorSelector>().is_err());
+    }
+
+    #[test]
+    fn test_monitor_selector_matches() {
+        let tv = monitor("LG TV SSCR2", false);
+        let laptop = monitor("Built-in Retina Display", true);
+
+        assert!(MonitorSelector::Name("lg tv".to_string()).matches(2, 7, &tv));
+        assert!(!MonitorSelector::Name("lg tv".to_string()).matches(0, 1, &laptop));
+        assert!(MonitorSelector::Primary.matches(0, 1, &laptop));
+        assert!(!MonitorSelector::Primary.matches(2, 7, &tv));
+        assert!(MonitorSelector::Index(2).matches(2, 7, &tv));
+        assert!(MonitorSelector::Id(7).matches(2, 7, &tv));
+    }
+
+    #[test]
+    fn test_parse_monitor_fps() {
+        assert_eq!(
+            "name:LG TV=0.1".parse(),
+            Ok(MonitorFps {
+                selector: MonitorSelector::Name("LG TV".to_string()),
+                fps: 0.1,
+            })
+        );
+        assert!("primary".parse::<MonitorFps>().is_err());
+        assert!("primary=0".parse::<MonitorFps>().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use image::DynamicImage;
+    use screenpipe_core::Language;
+    use screenpipe_vision::ocr_provider::OcrFuture;
+    use screenpipe_vision::{
+        create_ocr_provider, register_ocr_provider, ConfidenceScale, OcrEngine, OcrFallbackConfig,
+        OcrLine, OcrProvider, OcrResult,
+    };
+    use std::sync::Arc;
+
+    struct FixedProvider {
+        name: &'static str,
+        confidence: Option<f64>,
+        fail: bool,
+        scale: ConfidenceScale,
+        lines: usize,
+    }
+
+    impl OcrProvider for FixedProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn recognize<'a>(
+            &'a self,
+            _image: &'a DynamicImage,
+            _languages: &'a [Language],
+        ) -> OcrFuture<'a> {
+            Box::pin(async move {
+                if self.fail {
+                    return Err(anyhow!("{} failed", self.name));
+                }
+                Ok(OcrResult {
+                    text: self.name.to_string(),
+                    lines: vec![OcrLine::default(); self.lines],
+                    confidence: self.confidence,
+                    ..Default::default()
+                })
+            })
+        }
+
+        fn confidence_scale(&self) -> ConfidenceScale {
+            self.scale
+        }
+    }
+
+    fn register(name: &'static str, confidence: Option<f64>, fail: bool) -> OcrEngine {
+        register_scaled(name, confidence, fail, ConfidenceScale::Unit, 0)
+    }
+
+    fn register_scaled(
+        name: &'static str,
+        confidence: Option<f64>,
+        fail: bool,
+        scale: ConfidenceScale,
+        lines: usize,
+    ) -> OcrEngine {
+        register_ocr_provider(Arc::new(FixedProvider {
+            name,
+            confidence,
+            fail,
+            scale,
+            lines,
+        }));
+        OcrEngine::Provider(name.to_string())
+    }
+
+    async fn run_chain(engines: Vec<OcrEngine>, min_confidence: f64) -> anyhow::Result<OcrResult> {
+        let provider = create_ocr_provider(&OcrEngine::Fallback(OcrFallbackConfig {
+            engines,
+            min_confidence,
+        }))?;
+        provider.recognize(&DynamicImage::new_rgb8(8, 8), &[]).await
+    }
+
+    #[tokio::test]
+    async fn test_fallback_skips_failing_engine() {
+        let failing = register("fallback-failing", None, true);
+        let working = register("fallback-working", Some(0.9), false);
+
+        let result = run_chain(vec![failing, working], 0.5).await.unwrap();
+        assert_eq!(result.text, "fallback-working");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_retries_low_confidence() {
+        let low = register("fallback-low", Some(0.2), false);
+        let high = register("fallback-high", Some(0.8), false);
+
+        let result = run_chain(vec![low, high], 0.5).await.unwrap();
+        assert_eq!(result.text, "fallback-high");
+    }
+
+    #[tokio::test]
+    async fn test_fallback_keeps_best_result_when_all_below_threshold() {
+        let lower = register("fallback-lower", Some(0.1), false);
+        let better = register_scaled(
+            "fallback-better",
+            Some(30.0),
+            false,
+            ConfidenceScale::Percent,
+            0,
+        );
+
+        let result = run_chain(vec![lower, better], 0.9).await.unwrap();
+        assert_eq!(result.text, "fallback-better");
+        assert_eq!(result.confidence, Some(0.3));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_scores_summed_line_confidences_per_line() {
+        // three lines at 0.9, not a percentage of 2.7
+        let summed = register_scaled(
+            "fallback-summed",
+            Some(2.7),
+            false,
+            ConfidenceScale::LineSum,
+            3,
+        );
+        let next = register("fallback-after-summed", Some(0.95), false);
+
+        let result = run_chain(vec![summed, next], 0.85).await.unwrap();
+        assert_eq!(result.text, "fallback-summed");
+        assert!((result.confidence.unwrap() - 0.9).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_returns_error_when_every_engine_fails() {
+        let failing = register("fallback-only-failing", None, true);
+
+        assert!(run_chain(vec![failing], 0.5).await.is_err());
+    }
+
+    #[test]
+    fn test_empty_fallback_chain_is_rejected() {
+        let result = create_ocr_provider(&OcrEngine::Fallback(OcrFallbackConfig {
+            engines: vec![],
+            min_confidence: 0.5,
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use image::DynamicImage;
+    use screenpipe_core::Language;
+    use screenpipe_vision::ocr_provider::{
+        get_ocr_provider, list_ocr_providers, unregister_ocr_provider, OcrFuture,
+    };
+    use screenpipe_vision::{create_ocr_provider, register_ocr_provider, OcrEngine, OcrProvider};
+    use screenpipe_vision::OcrResult;
+    use std::sync::Arc;
+
+    struct EchoProvider;
+
+    impl OcrProvider for EchoProvider {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn recognize<'a>(
+            &'a self,
+            image: &'a DynamicImage,
+            _languages: &'a [Language],
+        ) -> OcrFuture<'a> {
+            Box::pin(async move {
+                Ok(OcrResult {
+                    text: format!("{}x{}", image.width(), image.height()),
+                    confidence: Some(1.0),
+                    ..Default::default()
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_provider_is_dispatched() {
+        register_ocr_provider(Arc::new(EchoProvider));
+        assert!(list_ocr_providers().contains(&"echo".to_string()));
+
+        let provider = create_ocr_provider(&OcrEngine::Provider("echo".to_string())).unwrap();
+        let image = DynamicImage::new_rgb8(12, 34);
+        let result = provider.recognize(&image, &[]).await.unwrap();
+
+        assert_eq!(result.text, "12x34");
+        assert_eq!(result.confidence, Some(1.0));
+
+        unregister_ocr_provider("echo");
+        assert!(get_ocr_provider("echo").is_none());
+    }
+
+    #[test]
+    fn test_unknown_provider_errors() {
+        let result = create_ocr_provider(&OcrEngine::Provider("does-not-exist".to_string()));
+        assert!(result.is_err());
+    }
+}
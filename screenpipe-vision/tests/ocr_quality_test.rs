@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::ocr_quality::OcrQualityConfig;
+    use screenpipe_vision::{ConfidenceScale, OcrLine, OcrResult, OcrWord, TextBounds};
+
+    fn line(text: &str, conf: f32) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            conf,
+            bbox: TextBounds::default(),
+        }
+    }
+
+    fn word(text: &str, conf: f32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            conf,
+            bbox: TextBounds::default(),
+        }
+    }
+
+    fn result(conf_scale: f32) -> OcrResult {
+        OcrResult {
+            text: "Quarterly report\nx7#;l".to_string(),
+            lines: vec![
+                line("Quarterly report", 0.9 * conf_scale),
+                line("x7#;l", 0.2 * conf_scale),
+            ],
+            words: vec![
+                word("Quarterly", 0.95 * conf_scale),
+                word("report", 0.85 * conf_scale),
+                word("x7#;l", 0.2 * conf_scale),
+            ],
+            confidence: Some(0.65 * conf_scale as f64),
+        }
+    }
+
+    #[test]
+    fn test_drops_words_and_lines_below_the_threshold() {
+        let config = OcrQualityConfig {
+            min_word_confidence: Some(0.5),
+            ..Default::default()
+        };
+        // tesseract reports percentages, brought to 0 - 1 before filtering
+        for filtered in [
+            config.filter(result(1.0)),
+            config.filter(ConfidenceScale::Percent.to_unit(result(100.0))),
+        ] {
+            assert_eq!(filtered.text, "Quarterly report");
+            assert_eq!(filtered.lines.len(), 1);
+            let words: Vec<&str> = filtered.words.iter().map(|w| w.text.as_str()).collect();
+            assert_eq!(words, vec!["Quarterly", "report"]);
+        }
+    }
+
+    #[test]
+    fn test_dropped_words_leave_the_text() {
+        let config = OcrQualityConfig {
+            min_word_confidence: Some(0.5),
+            ..Default::default()
+        };
+        let filtered = config.filter(OcrResult {
+            text: "Total 4O0 due".to_string(),
+            lines: vec![line("Total 4O0 due", 0.7)],
+            words: vec![word("Total", 0.9), word("4O0", 0.2), word("due", 0.9)],
+            confidence: Some(0.7),
+        });
+        assert_eq!(filtered.text, "Total due");
+        assert_eq!(filtered.lines[0].text, "Total due");
+        assert_eq!(filtered.words.len(), 2);
+    }
+
+    #[test]
+    fn test_keeps_everything_without_thresholds() {
+        let config = OcrQualityConfig::default();
+        assert_eq!(config.filter(result(1.0)), result(1.0));
+        assert!(!config.is_low_quality(Some(0.0)));
+
+        // text is left as is when no line was dropped
+        let config = OcrQualityConfig {
+            min_word_confidence: Some(0.1),
+            ..Default::default()
+        };
+        assert_eq!(config.filter(result(1.0)).text, "Quarterly report\nx7#;l");
+    }
+
+    #[test]
+    fn test_flags_low_confidence_frames() {
+        let config = OcrQualityConfig {
+            min_frame_confidence: Some(0.7),
+            ..Default::default()
+        };
+        assert!(config.is_low_quality(Some(0.65)));
+        assert!(!config.is_low_quality(Some(0.8)));
+        assert!(!config.is_low_quality(None));
+    }
+
+    #[test]
+    fn test_confidences_are_read_per_engine() {
+        assert_eq!(ConfidenceScale::Percent.unit(92.0), 0.92);
+        assert_eq!(ConfidenceScale::Percent.unit(-1.0), 0.0);
+        assert_eq!(ConfidenceScale::Unit.unit(0.4), 0.4);
+        // Apple Vision sums its lines, a total above 1 is no percentage
+        let summed = OcrResult {
+            lines: vec![line("a", 0.9), line("b", 0.7)],
+            confidence: Some(1.6),
+            ..Default::default()
+        };
+        assert_eq!(ConfidenceScale::LineSum.overall(&summed), Some(0.8));
+        assert_eq!(ConfidenceScale::Unit.overall(&OcrResult::default()), None);
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::onnx_ocr::ensure_model_file;
+    use screenpipe_vision::perform_ocr_paddle;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn load_test_image(name: &str) -> image::DynamicImage {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests");
+        path.push(name);
+        image::open(&path).expect("Failed to open image")
+    }
+
+    #[tokio::test]
+    #[ignore] // downloads the paddle models
+    async fn test_paddle_ocr() {
+        let image = load_test_image("testing_OCR.png");
+
+        let result = perform_ocr_paddle(&image).await.unwrap();
+
+        println!("OCR text: {:?}", result.text);
+        assert!(
+            result.text.contains("receiver_count"),
+            "OCR failed: {:?}",
+            result.text
+        );
+        assert!(!result.words.is_empty());
+        assert!(result
+            .words
+            .iter()
+            .all(|word| word.bbox.width > 0.0 && word.bbox.height > 0.0));
+    }
+
+    #[tokio::test]
+    #[ignore] // downloads the paddle models
+    async fn test_paddle_ocr_chinese() {
+        let image = load_test_image("testing_OCR_chinese.png");
+
+        let result = perform_ocr_paddle(&image).await.unwrap();
+
+        println!("OCR text: {:?}", result.text);
+        assert!(
+            result.text.contains("管理分支"),
+            "OCR failed to recognize Chinese text: {:?}",
+            result.text
+        );
+    }
+
+    #[tokio::test]
+    async fn test_model_files_are_verified() {
+        let dir = TempDir::new().unwrap();
+        // nothing listens there, only files already on disk can be used
+        let url = "http://127.0.0.1:9/model.onnx";
+        // sha256 of "model"
+        let sha256 = "9372c470eeadd5ecd9c3c74c2b3cb633f8e2f2fad799250a0f70d652b6b825e4";
+        let model = dir.path().join("model.onnx");
+        std::fs::write(&model, b"model").unwrap();
+
+        let tampered = ensure_model_file(dir.path(), "model.onnx", url, &"0".repeat(64)).await;
+        assert!(tampered.is_err());
+        assert!(!model.exists(), "a file that doesn't match is removed");
+
+        std::fs::write(&model, b"model").unwrap();
+        assert_eq!(
+            ensure_model_file(dir.path(), "model.onnx", url, sha256)
+                .await
+                .unwrap(),
+            model
+        );
+        // verified once, the file isn't hashed again
+        assert!(dir.path().join("model.onnx.sha256").exists());
+        assert!(ensure_model_file(dir.path(), "model.onnx", url, "")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GrayImage, Luma};
+    use screenpipe_core::Language;
+    use screenpipe_db::{OcrLine, TextBounds};
+    use screenpipe_vision::ocr_provider::OcrFuture;
+    use screenpipe_vision::partial_ocr::{changed_bands, PartialOcrCache};
+    use screenpipe_vision::{OcrProvider, OcrResult};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Returns one line spanning the whole image and records the height of every call.
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+        heights: Mutex<Vec<u32>>,
+    }
+
+    impl OcrProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn recognize<'a>(
+            &'a self,
+            image: &'a DynamicImage,
+            _languages: &'a [Language],
+        ) -> OcrFuture<'a> {
+            Box::pin(async move {
+                let call = self.calls.fetch_add(1, Ordering::SeqCst);
+                self.heights.lock().unwrap().push(image.height());
+                let text = format!("call {}", call);
+                Ok(OcrResult {
+                    text: text.clone(),
+                    lines: vec![OcrLine {
+                        text,
+                        conf: 0.9,
+                        bbox: TextBounds {
+                            left: 0.0,
+                            top: 0.0,
+                            width: image.width() as f32,
+                            height: image.height() as f32,
+                        },
+                    }],
+                    words: vec![],
+                    confidence: Some(0.9),
+                })
+            })
+        }
+    }
+
+    fn blank(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_pixel(width, height, Luma([255]))
+    }
+
+    fn with_dark_block(mut image: GrayImage, top: u32, bottom: u32) -> GrayImage {
+        for y in top..bottom {
+            for x in 10..50 {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_changed_bands_identical_frames() {
+        let frame = blank(128, 256);
+        assert_eq!(changed_bands(&frame, &frame), Some(vec![]));
+    }
+
+    #[test]
+    fn test_changed_bands_single_region() {
+        let previous = blank(128, 256);
+        let current = with_dark_block(blank(128, 256), 70, 80);
+
+        let bands = changed_bands(&previous, &current).unwrap();
+        assert_eq!(bands.len(), 1);
+        // rows 70..80 fall in the third tile row (64..96), padded by 8 on both sides
+        assert_eq!(bands[0].top, 56.0);
+        assert_eq!(bands[0].height, 48.0);
+        assert_eq!(bands[0].width, 128.0);
+    }
+
+    #[test]
+    fn test_changed_bands_resized_window() {
+        assert_eq!(changed_bands(&blank(128, 256), &blank(128, 128)), None);
+    }
+
+    #[tokio::test]
+    async fn test_partial_ocr_only_rereads_changed_band() {
+        let provider = CountingProvider::default();
+        let mut cache = PartialOcrCache::new();
+
+        let first = DynamicImage::ImageLuma8(blank(128, 512));
+        let full = cache
+            .recognize("app", "window", &first, &provider, &[])
+            .await
+            .unwrap();
+        assert_eq!(full.text, "call 0");
+
+        // unchanged frame is served from the cache
+        let cached = cache
+            .recognize("app", "window", &first, &provider, &[])
+            .await
+            .unwrap();
+        assert_eq!(cached.text, "call 0");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+        // a small change only sends its band to the engine
+        let changed = DynamicImage::ImageLuma8(with_dark_block(blank(128, 512), 70, 80));
+        cache
+            .recognize("app", "window", &changed, &provider, &[])
+            .await
+            .unwrap();
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(*provider.heights.lock().unwrap(), vec![512, 48]);
+    }
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+    use screenpipe_vision::phash::{hamming_distance, perceptual_hash};
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, _| {
+            let value = (x * 255 / width) as u8;
+            Rgb([value, value, value])
+        })
+    }
+
+    #[test]
+    fn test_single_pixel_change_keeps_hash() {
+        let original = gradient(640, 480);
+        let mut changed = original.clone();
+        changed.put_pixel(600, 20, Rgb([255, 0, 0]));
+
+        let a = perceptual_hash(&DynamicImage::ImageRgb8(original));
+        let b = perceptual_hash(&DynamicImage::ImageRgb8(changed));
+        assert_eq!(hamming_distance(a, b), 0);
+    }
+
+    #[test]
+    fn test_different_content_changes_hash() {
+        let a = perceptual_hash(&DynamicImage::ImageRgb8(gradient(640, 480)));
+        let flipped = image::imageops::flip_horizontal(&gradient(640, 480));
+        let b = perceptual_hash(&DynamicImage::ImageRgb8(flipped));
+        assert_eq!(hamming_distance(a, b), 64);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(u64::MAX, 0), 64);
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use image::DynamicImage;
+    use screenpipe_vision::capture_screenshot_by_window::{CapturedWindow, WindowBounds};
+    use screenpipe_vision::privacy::{is_private_browsing_window, PauseReason, PrivacyPolicy};
+
+    fn window(app_name: &str, window_name: &str, is_focused: bool) -> CapturedWindow {
+        CapturedWindow {
+            image: DynamicImage::new_rgb8(4, 4),
+            app_name: app_name.to_string(),
+            window_name: window_name.to_string(),
+            process_id: 1,
+            is_focused,
+            visible_percentage: 1.0,
+            bounds: WindowBounds {
+                x: 0,
+                y: 0,
+                width: 4,
+                height: 4,
+            },
+        }
+    }
+
+    #[test]
+    fn test_private_browsing_titles() {
+        assert!(is_private_browsing_window(
+            "Google Chrome",
+            "New Tab - Google Chrome (Incognito)"
+        ));
+        assert!(is_private_browsing_window(
+            "Firefox",
+            "Mozilla Firefox Private Browsing"
+        ));
+        assert!(is_private_browsing_window("Microsoft Edge", "[InPrivate] - Microsoft Edge"));
+        assert!(!is_private_browsing_window("Google Chrome", "Inbox - Gmail"));
+        // only browsers are considered, a document can be titled anything
+        assert!(!is_private_browsing_window("TextEdit", "incognito notes.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_policy_pauses_on_focused_private_window() {
+        let policy = PrivacyPolicy {
+            pause_on_secure_input: false,
+            pause_on_private_browsing: true,
+        };
+        let windows = vec![
+            window("Slack", "general", false),
+            window("Google Chrome", "New Tab - Google Chrome (Incognito)", true),
+        ];
+
+        assert_eq!(
+            policy.check(&windows).await,
+            Some(PauseReason::PrivateBrowsing {
+                app_name: "Google Chrome".to_string()
+            })
+        );
+        assert_eq!(PrivacyPolicy::default().check(&windows).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_policy_drops_background_private_windows() {
+        let policy = PrivacyPolicy {
+            pause_on_secure_input: false,
+            pause_on_private_browsing: true,
+        };
+        let mut windows = vec![
+            window("Slack", "general", true),
+            window("Firefox", "Mozilla Firefox Private Browsing", false),
+        ];
+
+        assert_eq!(policy.check(&windows).await, None);
+        assert_eq!(policy.private_regions(&windows).len(), 1);
+        assert!(PrivacyPolicy::default().private_regions(&windows).is_empty());
+        policy.filter_windows(&mut windows);
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].app_name, "Slack");
+    }
+}
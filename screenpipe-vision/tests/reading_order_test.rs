@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::reading_order::reading_order_text;
+    use screenpipe_vision::{OcrLine, TextBounds};
+
+    fn line(text: &str, left: f32, top: f32, width: f32) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            conf: 0.9,
+            bbox: TextBounds {
+                left,
+                top,
+                width,
+                height: 20.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_reads_columns_one_after_the_other() {
+        // heading across the page, two columns below it, lines interleaved in TSV order
+        let lines = vec![
+            line("Weekly newsletter", 20.0, 0.0, 560.0),
+            line("left one", 20.0, 60.0, 260.0),
+            line("right one", 320.0, 60.0, 260.0),
+            line("left two", 20.0, 85.0, 260.0),
+            line("right two", 320.0, 85.0, 260.0),
+            line("left three", 20.0, 140.0, 260.0),
+            line("right three", 320.0, 110.0, 260.0),
+        ];
+        assert_eq!(
+            reading_order_text(&lines),
+            "Weekly newsletter\n\nleft one\nleft two\n\nleft three\n\nright one\nright two\nright three"
+        );
+    }
+
+    #[test]
+    fn test_reads_side_by_side_panes() {
+        let lines = vec![
+            line("chat b", 500.0, 0.0, 200.0),
+            line("editor a", 0.0, 0.0, 300.0),
+            line("chat c", 500.0, 25.0, 200.0),
+            line("editor b", 0.0, 25.0, 300.0),
+        ];
+        assert_eq!(
+            reading_order_text(&lines),
+            "editor a\neditor b\n\nchat b\nchat c"
+        );
+        assert_eq!(reading_order_text(&[]), "");
+    }
+}
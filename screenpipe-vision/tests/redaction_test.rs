@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use screenpipe_core::RedactionPolicy;
+    use screenpipe_vision::accessibility::TextSource;
+    use screenpipe_vision::capture_screenshot_by_window::WindowBounds;
+    use screenpipe_vision::core::WindowOcrResult;
+    use screenpipe_vision::redaction::{blur_regions, redact_capture_result, redact_window_text};
+    use screenpipe_vision::{CaptureResult, OcrLine, OcrWord, TextBounds};
+    use std::time::Instant;
+
+    fn bbox(left: f32, top: f32, width: f32, height: f32) -> TextBounds {
+        TextBounds {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    fn word(text: &str, left: f32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            conf: 0.9,
+            bbox: bbox(left, 10.0, 40.0, 10.0),
+        }
+    }
+
+    /// Striped image so a blur visibly changes pixels.
+    fn striped(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, _| {
+            if x % 2 == 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        }))
+    }
+
+    fn window(image: DynamicImage) -> WindowOcrResult {
+        let line = "card 4111 1111 1111 1111 ok";
+        WindowOcrResult {
+            bounds: WindowBounds {
+                x: 100,
+                y: 50,
+                width: image.width(),
+                height: image.height(),
+            },
+            image,
+            window_name: "checkout".to_string(),
+            app_name: "browser".to_string(),
+            app_id: None,
+            text: line.to_string(),
+            lines: vec![OcrLine {
+                text: line.to_string(),
+                conf: 0.9,
+                bbox: bbox(0.0, 10.0, 270.0, 10.0),
+            }],
+            words: vec![
+                word("card", 0.0),
+                word("4111", 50.0),
+                word("1111", 100.0),
+                word("1111", 150.0),
+                word("1111", 200.0),
+                word("ok", 250.0),
+            ],
+            focused: true,
+            confidence: 0.9,
+            low_quality: false,
+            browser_url: None,
+            browser_title: None,
+            visible_percentage: 1.0,
+            phash: 0,
+            source: TextSource::Ocr,
+        }
+    }
+
+    #[test]
+    fn test_redact_window_text_scrubs_text_lines_and_words() {
+        let mut window = window(striped(300, 40));
+        let regions = redact_window_text(&mut window, &RedactionPolicy::builtin());
+
+        assert_eq!(window.text, "card [CREDIT_CARD] ok");
+        assert_eq!(window.lines[0].text, "card [CREDIT_CARD] ok");
+        let words: Vec<_> = window.words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(
+            words,
+            vec!["card", "[REDACTED]", "[REDACTED]", "[REDACTED]", "[REDACTED]", "ok"]
+        );
+
+        assert_eq!(regions.len(), 1);
+        assert!(regions[0].left > 0.0 && regions[0].left < 50.0);
+        assert!(regions[0].left + regions[0].width > 240.0);
+    }
+
+    #[test]
+    fn test_blur_regions_only_touches_region() {
+        let mut image = striped(100, 20);
+        let original = image.clone();
+        blur_regions(&mut image, &[bbox(10.0, 0.0, 20.0, 20.0)]);
+
+        assert_ne!(
+            image.to_rgba8().get_pixel(20, 10),
+            original.to_rgba8().get_pixel(20, 10)
+        );
+        assert_eq!(
+            image.to_rgba8().get_pixel(60, 10),
+            original.to_rgba8().get_pixel(60, 10)
+        );
+    }
+
+    #[test]
+    fn test_redact_capture_result_blurs_monitor_frame() {
+        let frame = striped(600, 200);
+        let mut result = CaptureResult {
+            image: frame.clone(),
+            frame_number: 0,
+            timestamp: Instant::now(),
+            window_ocr_results: vec![window(striped(300, 40))],
+        };
+
+        let policy = RedactionPolicy::builtin().with_image_blur(true);
+        redact_capture_result(&mut result, &policy);
+
+        // inside the card number, offset by the window position
+        assert_ne!(
+            result.image.to_rgba8().get_pixel(100 + 120, 50 + 15),
+            frame.to_rgba8().get_pixel(100 + 120, 50 + 15)
+        );
+        // outside the window
+        assert_eq!(
+            result.image.to_rgba8().get_pixel(5, 5),
+            frame.to_rgba8().get_pixel(5, 5)
+        );
+    }
+}
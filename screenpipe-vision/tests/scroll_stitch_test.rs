@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+    use screenpipe_vision::scroll_stitch::{
+        detect_scroll_offset, merge_scrolled_text, RowSignature, ScrollStitcher,
+    };
+
+    const WIDTH: u32 = 320;
+    const HEIGHT: u32 = 200;
+
+    // Blocky pseudo random content, every two rows differ like lines of text
+    fn page(height: u32, seed: u32) -> RgbImage {
+        RgbImage::from_fn(WIDTH, height, |x, y| {
+            let mut value = (y / 2).wrapping_mul(2_654_435_761) ^ (x / 20).wrapping_mul(40_503);
+            value = value.wrapping_add(seed).wrapping_mul(2_246_822_519);
+            let value = (value >> 24) as u8;
+            Rgb([value, value, value])
+        })
+    }
+
+    fn capture(page: &RgbImage, top: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(page.view(0, top, WIDTH, HEIGHT).to_image())
+    }
+
+    #[test]
+    fn test_detects_scroll_distance() {
+        let page = page(1000, 1);
+        let first = RowSignature::new(&capture(&page, 0));
+
+        assert_eq!(detect_scroll_offset(&first, &first), Some(0));
+        assert_eq!(
+            detect_scroll_offset(&first, &RowSignature::new(&capture(&page, 40))),
+            Some(40)
+        );
+        // overlapping less than a quarter of the window isn't a scroll
+        assert_eq!(
+            detect_scroll_offset(&first, &RowSignature::new(&capture(&page, 180))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unrelated_and_blank_captures_are_not_scrolls() {
+        let first = RowSignature::new(&capture(&page(1000, 1), 0));
+        let other = RowSignature::new(&capture(&page(1000, 2), 0));
+        assert_eq!(detect_scroll_offset(&first, &other), None);
+
+        let blank = RowSignature::new(&DynamicImage::new_rgb8(WIDTH, HEIGHT));
+        assert_eq!(detect_scroll_offset(&blank, &blank), None);
+    }
+
+    #[test]
+    fn test_merges_overlapping_text() {
+        assert_eq!(
+            merge_scrolled_text("one\ntwo\nthree", "two\nthree\nfour"),
+            "one\ntwo\nthree\nfour"
+        );
+        // the half visible top line was misread
+        assert_eq!(
+            merge_scrolled_text("one\ntwo\nthree", "tw0 ~\nthree\nfour"),
+            "one\ntwo\nthree\nfour"
+        );
+        // case and punctuation noise doesn't break the overlap
+        assert_eq!(
+            merge_scrolled_text("Hello, world", "hello world\nnext"),
+            "Hello, world\nnext"
+        );
+        assert_eq!(merge_scrolled_text("a", "b"), "a\nb");
+    }
+
+    #[test]
+    fn test_stitches_consecutive_scrolled_captures() {
+        let page = page(1000, 1);
+        let mut stitcher = ScrollStitcher::default();
+
+        assert!(stitcher
+            .push("Safari", "Article", 1, &capture(&page, 0), "one\ntwo")
+            .is_empty());
+        assert!(stitcher
+            .push("Safari", "Article", 2, &capture(&page, 60), "two\nthree")
+            .is_empty());
+        // another window doesn't interrupt the scroll
+        assert!(stitcher
+            .push("Slack", "general", 3, &capture(&page, 500), "chat")
+            .is_empty());
+        assert!(stitcher
+            .push("Safari", "Article", 4, &capture(&page, 120), "three\nfour")
+            .is_empty());
+
+        // jumping elsewhere in the page finishes the document
+        let finished = stitcher.push("Safari", "Article", 5, &capture(&page, 700), "later");
+        assert_eq!(finished.len(), 1);
+        let document = &finished[0];
+        assert_eq!(document.frame_ids, vec![1, 2, 4]);
+        assert_eq!(document.text, "one\ntwo\nthree\nfour");
+        assert_eq!(document.image.dimensions(), (WIDTH, HEIGHT + 120));
+        assert_eq!(
+            document
+                .image
+                .to_rgb8()
+                .view(0, 0, WIDTH, HEIGHT + 120)
+                .to_image(),
+            page.view(0, 0, WIDTH, HEIGHT + 120).to_image()
+        );
+
+        // single captures never become documents
+        assert!(stitcher.finish_all().is_empty());
+    }
+
+    #[test]
+    fn test_documents_stop_at_the_height_limit() {
+        let page = page(1000, 1);
+        let mut stitcher = ScrollStitcher::new(HEIGHT + 50);
+
+        stitcher.push("Safari", "Article", 1, &capture(&page, 0), "");
+        stitcher.push("Safari", "Article", 2, &capture(&page, 40), "");
+        let finished = stitcher.push("Safari", "Article", 3, &capture(&page, 80), "");
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].frame_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_open_documents_stay_within_the_byte_budget() {
+        let page = page(1000, 1);
+        let capture_bytes = (WIDTH * HEIGHT * 4) as usize;
+        let mut stitcher = ScrollStitcher::default().with_max_open_bytes(2 * capture_bytes);
+
+        stitcher.push("Safari", "Article", 1, &capture(&page, 0), "");
+        stitcher.push("Safari", "Article", 2, &capture(&page, 40), "");
+        // the least recently used document completes to make room for the new window
+        let finished = stitcher.push("Slack", "general", 3, &capture(&page, 500), "");
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].frame_ids, vec![1, 2]);
+        assert_eq!(finished[0].image.dimensions(), (WIDTH, HEIGHT + 40));
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::table_detection::detect_tables;
+    use screenpipe_vision::{OcrWord, TextBounds};
+
+    fn word(text: &str, left: f32, top: f32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            conf: 0.9,
+            bbox: TextBounds {
+                left,
+                top,
+                width: text.len() as f32 * 8.0,
+                height: 16.0,
+            },
+        }
+    }
+
+    /// Words of `text` laid out left to right from `left` with regular spaces.
+    fn phrase(text: &str, left: f32, top: f32) -> Vec<OcrWord> {
+        let mut x = left;
+        text.split(' ')
+            .map(|t| {
+                let w = word(t, x, top);
+                x += w.bbox.width + 5.0;
+                w
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_aligned_columns() {
+        let mut words = phrase("Quarterly report", 10.0, 0.0);
+        for (i, (name, qty, price)) in [
+            ("Item", "Qty", "Price"),
+            ("Green apples", "3", "1,20"),
+            ("Pears", "12", "0.80"),
+            ("Plums", "", "2.00"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let top = 40.0 + i as f32 * 24.0;
+            words.extend(phrase(name, 10.0, top));
+            if !qty.is_empty() {
+                words.extend(phrase(qty, 200.0, top + 1.0));
+            }
+            words.extend(phrase(price, 300.0, top));
+        }
+        words.extend(phrase("Some closing paragraph text here", 10.0, 200.0));
+        let tables = detect_tables(&words);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(
+            tables[0].cells,
+            vec![
+                vec!["Item", "Qty", "Price"],
+                vec!["Green apples", "3", "1,20"],
+                vec!["Pears", "12", "0.80"],
+                vec!["Plums", "", "2.00"]
+            ]
+        );
+        assert_eq!(
+            tables[0].to_csv(),
+            "Item,Qty,Price\nGreen apples,3,\"1,20\"\nPears,12,0.80\nPlums,,2.00"
+        );
+        assert_eq!(tables[0].column_count(), 3);
+    }
+
+    #[test]
+    fn test_ignores_prose_columns() {
+        let mut words = Vec::new();
+        for i in 0..6 {
+            let top = i as f32 * 24.0;
+            words.extend(phrase("the quick brown fox jumps over", 10.0, top));
+            words.extend(phrase("lazy dogs sleeping in the sun", 400.0, top));
+        }
+        assert!(detect_tables(&words).is_empty());
+        assert!(detect_tables(&phrase("just a line", 0.0, 0.0)).is_empty());
+        assert!(detect_tables(&[]).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_core::Language;
+    use screenpipe_vision::reading_order::reading_order_text;
+    use screenpipe_vision::tesseract::with_vertical_languages;
+    use screenpipe_vision::text_direction::{
+        line_direction, order_words, page_direction, TextDirection,
+    };
+    use screenpipe_vision::{OcrLine, OcrWord, TextBounds};
+
+    fn bounds(left: f32, top: f32, width: f32, height: f32) -> TextBounds {
+        TextBounds {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    fn line(text: &str, bbox: TextBounds) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            conf: 0.9,
+            bbox,
+        }
+    }
+
+    #[test]
+    fn test_line_direction() {
+        assert_eq!(
+            line_direction("hello world", &bounds(0.0, 0.0, 100.0, 20.0)),
+            TextDirection::LeftToRight
+        );
+        assert_eq!(
+            line_direction("שלום עולם", &bounds(0.0, 0.0, 100.0, 20.0)),
+            TextDirection::RightToLeft
+        );
+        assert_eq!(
+            line_direction("مرحبا بالعالم", &bounds(0.0, 0.0, 100.0, 20.0)),
+            TextDirection::RightToLeft
+        );
+        assert_eq!(
+            line_direction("日本語の縦書き", &bounds(0.0, 0.0, 20.0, 140.0)),
+            TextDirection::Vertical
+        );
+        // horizontal japanese
+        assert_eq!(
+            line_direction("日本語の横書き", &bounds(0.0, 0.0, 140.0, 20.0)),
+            TextDirection::LeftToRight
+        );
+        // tall latin isn't vertical
+        assert_eq!(
+            line_direction("ll", &bounds(0.0, 0.0, 6.0, 20.0)),
+            TextDirection::LeftToRight
+        );
+    }
+
+    #[test]
+    fn test_order_words_right_to_left() {
+        let word = |text: &str, left: f32| OcrWord {
+            text: text.to_string(),
+            conf: 0.9,
+            bbox: bounds(left, 0.0, 40.0, 20.0),
+        };
+        let mut words = vec![word("עולם", 0.0), word("שלום", 50.0)];
+        order_words(&mut words, TextDirection::RightToLeft);
+        assert_eq!(words[0].text, "שלום");
+        let mut words = vec![
+            OcrWord {
+                text: "b".into(),
+                conf: 0.9,
+                bbox: bounds(0.0, 30.0, 20.0, 20.0),
+            },
+            OcrWord {
+                text: "a".into(),
+                conf: 0.9,
+                bbox: bounds(0.0, 0.0, 20.0, 20.0),
+            },
+        ];
+        order_words(&mut words, TextDirection::Vertical);
+        assert_eq!(words[0].text, "a");
+    }
+
+    #[test]
+    fn test_reads_right_to_left_columns() {
+        let lines = vec![
+            line("שמאל אחד", bounds(0.0, 0.0, 200.0, 20.0)),
+            line("ימין אחד", bounds(300.0, 0.0, 200.0, 20.0)),
+            line("שמאל שתיים", bounds(0.0, 25.0, 200.0, 20.0)),
+            line("ימין שתיים", bounds(300.0, 25.0, 200.0, 20.0)),
+        ];
+        assert_eq!(page_direction(&lines), TextDirection::RightToLeft);
+        assert_eq!(
+            reading_order_text(&lines),
+            "ימין אחד\nימין שתיים\n\nשמאל אחד\nשמאל שתיים"
+        );
+    }
+
+    #[test]
+    fn test_reads_vertical_columns_right_to_left() {
+        let lines = vec![
+            line("二行目です", bounds(0.0, 0.0, 20.0, 100.0)),
+            line("一行目です", bounds(25.0, 0.0, 20.0, 100.0)),
+        ];
+        assert_eq!(reading_order_text(&lines), "一行目です\n二行目です");
+    }
+
+    #[test]
+    fn test_adds_installed_vertical_languages() {
+        let installed = vec!["eng".to_string(), "jpn".to_string(), "jpn_vert".to_string()];
+        assert_eq!(
+            with_vertical_languages(
+                "eng+jpn+kor".to_string(),
+                &[Language::English, Language::Japanese, Language::Korean],
+                &installed,
+            ),
+            "eng+jpn+kor+jpn_vert"
+        );
+    }
+}
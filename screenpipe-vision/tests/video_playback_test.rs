@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GrayImage, Luma};
+    use screenpipe_vision::video_playback::{video_region, VideoPlaybackDetector};
+
+    /// A 320x256 light window with a 160x128 "video" at (64, 64) showing `shade`.
+    fn frame(shade: u8) -> DynamicImage {
+        let mut image = GrayImage::from_pixel(320, 256, Luma([240]));
+        for y in 64..192 {
+            for x in 64..224 {
+                image.put_pixel(x, y, Luma([shade]));
+            }
+        }
+        DynamicImage::ImageLuma8(image)
+    }
+
+    #[test]
+    fn test_detects_video_after_sustained_churn() {
+        let mut detector = VideoPlaybackDetector::new();
+        let mut playbacks = Vec::new();
+        for index in 0..8 {
+            let shade = if index % 2 == 0 { 20 } else { 200 };
+            playbacks.push(detector.observe("VLC", "movie.mkv", &frame(shade)));
+        }
+
+        // the first frame has nothing to compare with, then 4 churning frames are needed
+        assert!(playbacks[..4].iter().all(Option::is_none));
+        let playback = playbacks[4].as_ref().unwrap();
+        assert!(playback.started);
+        assert!(!playback.covers_window);
+        assert_eq!(
+            (
+                playback.region.x,
+                playback.region.y,
+                playback.region.width,
+                playback.region.height
+            ),
+            (64, 64, 160, 128)
+        );
+        assert!(!playbacks[7].as_ref().unwrap().started);
+
+        // paused video counts as still again
+        assert!(detector.observe("VLC", "movie.mkv", &frame(200)).is_none());
+    }
+
+    #[test]
+    fn test_windows_are_tracked_separately() {
+        let mut detector = VideoPlaybackDetector::new();
+        for index in 0..6 {
+            let shade = if index % 2 == 0 { 20 } else { 200 };
+            detector.observe("VLC", "movie.mkv", &frame(shade));
+            assert!(detector.observe("Notes", "todo", &frame(240)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_scattered_or_small_changes_are_not_video() {
+        // 10x8 tiles of 32px
+        let (width, height) = (320, 256);
+        let mut video = vec![false; 80];
+        // two distant tiles, e.g. a clock and a spinner
+        video[0] = true;
+        video[79] = true;
+        assert!(video_region(&video, width, height).is_none());
+
+        // a single tile is below the minimum area
+        let mut video = vec![false; 80];
+        video[35] = true;
+        assert!(video_region(&video, width, height).is_none());
+
+        let video = vec![true; 80];
+        let region = video_region(&video, width, height).unwrap();
+        assert_eq!((region.width, region.height), (320, 256));
+    }
+}
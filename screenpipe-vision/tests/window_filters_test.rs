@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use screenpipe_vision::capture_screenshot_by_window::{
+        mask_regions, WindowBounds, WindowFilters,
+    };
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_ignore_list_applies_without_include_list() {
+        let filters = WindowFilters::new(&strings(&["1password"]), &[]);
+
+        assert!(!filters.is_valid("1Password", "Vault"));
+        assert!(filters.is_valid("Google Chrome", "Inbox"));
+    }
+
+    #[test]
+    fn test_ignore_list_wins_over_include_list() {
+        let filters = WindowFilters::new(&strings(&["private"]), &strings(&["chrome"]));
+
+        assert!(filters.is_valid("Google Chrome", "Inbox"));
+        assert!(!filters.is_valid("Google Chrome", "Private Browsing"));
+        assert!(!filters.is_valid("Slack", "general"));
+    }
+
+    #[test]
+    fn test_title_patterns() {
+        let filters = WindowFilters::new(&[], &[])
+            .with_title_patterns(&strings(&[r"\.kdbx - keepass"]), &strings(&["^(inbox|draft)"]))
+            .unwrap();
+
+        assert!(!filters.is_valid("KeePassXC", "Passwords.kdbx - KeePassXC"));
+        assert!(filters.is_valid("Mail", "Inbox (3)"));
+        assert!(!filters.is_valid("Mail", "Sent"));
+    }
+
+    #[test]
+    fn test_invalid_title_pattern_is_rejected() {
+        assert!(WindowFilters::new(&[], &[])
+            .with_title_patterns(&strings(&["("]), &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_mask_regions_scales_to_image_pixels() {
+        let mut image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            200,
+            100,
+            Rgba([255, 255, 255, 255]),
+        ));
+        let region = WindowBounds {
+            x: 10,
+            y: 10,
+            width: 20,
+            height: 10,
+        };
+
+        // a 100x50 monitor captured at 2x
+        mask_regions(&mut image, &[region], 2.0);
+        let rgba = image.to_rgba8();
+
+        assert_eq!(rgba.get_pixel(20, 20), &Rgba([0, 0, 0, 255]));
+        assert_eq!(rgba.get_pixel(59, 39), &Rgba([0, 0, 0, 255]));
+        assert_eq!(rgba.get_pixel(60, 40), &Rgba([255, 255, 255, 255]));
+        assert_eq!(rgba.get_pixel(19, 19), &Rgba([255, 255, 255, 255]));
+    }
+}
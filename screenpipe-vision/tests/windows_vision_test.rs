@@ -1,10 +1,17 @@
 #[cfg(target_os = "windows")]
 #[cfg(test)]
 mod tests {
-    use screenpipe_vision::capture_screenshot_by_window::{CapturedWindow, WindowFilters};
+    use screenpipe_vision::accessibility::AccessibilityConfig;
+    use screenpipe_vision::capture_screenshot_by_window::{
+        CapturedWindow, WindowBounds, WindowFilters,
+    };
     use screenpipe_vision::core::OcrTaskData;
     use screenpipe_vision::monitor::get_default_monitor;
-    use screenpipe_vision::{process_ocr_task, OcrEngine};
+    use screenpipe_vision::ocr_quality::OcrQualityConfig;
+    use screenpipe_vision::partial_ocr::PartialOcrCache;
+    use screenpipe_vision::privacy::PrivacyPolicy;
+    use screenpipe_vision::video_playback::VideoPlaybackDetector;
+    use screenpipe_vision::{create_ocr_provider, process_ocr_task, OcrEngine};
     use std::sync::Arc;
     use std::{path::PathBuf, time::Instant};
     use tokio::sync::mpsc;
@@ -27,14 +34,22 @@ mod tests {
         let frame_number = 1;
         let timestamp = Instant::now();
         let (tx, _rx) = mpsc::channel(1);
-        let ocr_engine = OcrEngine::WindowsNative;
+        let ocr_provider = create_ocr_provider(&OcrEngine::WindowsNative).unwrap();
 
+        let bounds = WindowBounds {
+            x: 0,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+        };
         let window_images = vec![CapturedWindow {
             app_name: "test_app".to_string(),
             window_name: "test_window".to_string(),
             image,
             is_focused: true,
             process_id: 1234,
+            visible_percentage: 1.0,
+            bounds,
         }];
 
         let result = process_ocr_task(
@@ -45,8 +60,12 @@ mod tests {
                 timestamp,
                 result_tx: tx,
             },
-            &ocr_engine,
+            ocr_provider.as_ref(),
             vec![],
+            &mut PartialOcrCache::new(),
+            &mut VideoPlaybackDetector::new(),
+            &AccessibilityConfig::default(),
+            OcrQualityConfig::default(),
         )
         .await;
 
@@ -78,6 +97,13 @@ mod tests {
             window_filters, // window filters as empty vec
             vec![],         // languages as empty vec
             save_text_files_flag,
+            false,
+            None,
+            None,
+            PrivacyPolicy::default(),
+            None,
+            Arc::new(AccessibilityConfig::default()),
+            OcrQualityConfig::default(),
         ));
 
         // Wait for a short duration to allow some captures to occur